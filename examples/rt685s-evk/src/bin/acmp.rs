@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::acmp::{Acmp, Config, DacConfig, DacReference, Hysteresis, Input, InterruptHandler};
+use embassy_imxrt::{bind_interrupts, peripherals};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    ACMP => InterruptHandler<peripherals::ACMP>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    // Compare PIO0_5 against half of Vin1
+    let mut config = Config::default();
+    config.hysteresis = Hysteresis::Level1;
+    config.dac = Some(DacConfig {
+        reference: DacReference::Vin1,
+        level: 127,
+    });
+
+    let mut acmp = Acmp::new(p.ACMP, Irqs, Input::pin(p.PIO0_5), Input::dac(), config).unwrap();
+
+    loop {
+        info!("Comparator output = {}", acmp.output());
+
+        acmp.wait_for_rising().await;
+        info!("Input went above threshold");
+
+        acmp.wait_for_falling().await;
+        info!("Input went below threshold");
+    }
+}
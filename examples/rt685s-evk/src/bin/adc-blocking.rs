@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::adc::{Adc, Average, ChannelConfig, Config};
+use embassy_imxrt_examples as _;
+use embassy_time::Timer;
+use panic_probe as _;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+    let channel_config = [ChannelConfig::single_ended_with_average(p.PIO0_5, Average::_16)];
+    let mut adc = Adc::new_blocking(p.ADC0, Config::default(), channel_config);
+    let one_shot = ChannelConfig::single_ended(p.PIO0_6);
+
+    loop {
+        let mut data: [i16; 1] = [0; 1];
+        adc.blocking_sample(&mut data);
+        let value = adc.blocking_read(&one_shot);
+
+        info!("ADC sample = {:#x}, one-shot = {:#x}", data, value);
+
+        Timer::after_millis(1000).await;
+    }
+}
@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::adc::{Adc, Average, ChannelConfig, Config, InterruptHandler};
+use embassy_imxrt::{bind_interrupts, peripherals};
+use embassy_imxrt_examples as _;
+use embassy_time::Timer;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    ADC0 => InterruptHandler<peripherals::ADC0>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+    let channel = ChannelConfig::differential_with_average(p.PIO0_5, p.PIO0_6, Average::_16).unwrap();
+    let mut adc = Adc::new(p.ADC0, Irqs, Config::default(), [channel]);
+
+    loop {
+        let mut data: [i16; 1] = [0; 1];
+        adc.sample(&mut data).await;
+
+        // Drop the always zero bits to get the 13-bit signed difference
+        info!("ADC differential sample = {}", data[0] >> 3);
+
+        Timer::after_millis(1000).await;
+    }
+}
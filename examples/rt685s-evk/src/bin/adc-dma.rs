@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+use defmt::{error, info};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::adc::{Adc, Average, ChannelConfig, Config, InterruptHandler};
+use embassy_imxrt::{bind_interrupts, peripherals};
+use embassy_imxrt_examples as _;
+use embassy_time::Timer;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    ADC0 => InterruptHandler<peripherals::ADC0>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let mut p = embassy_imxrt::init(Default::default());
+    let channel_config = [
+        ChannelConfig::single_ended_with_average(p.PIO0_5, Average::_16),
+        ChannelConfig::single_ended(p.PIO0_6),
+    ];
+    let mut adc = Adc::new(p.ADC0, Irqs, Config::default(), channel_config);
+
+    loop {
+        let mut data: [i16; 16] = [0; 16];
+        match adc.sample_dma(p.DMA0_CH0.reborrow(), &mut data).await {
+            Ok(()) => info!("ADC samples = {:#x}", data),
+            Err(_) => error!("ADC DMA sampling failed"),
+        }
+
+        Timer::after_millis(1000).await;
+    }
+}
@@ -22,12 +22,14 @@ async fn main(_spawner: Spawner) {
         ChannelConfig::single_ended(p.PIO0_6),
     ];
     let mut adc = Adc::new(p.ADC0, Irqs, Config::default(), channel_config);
+    let one_shot = ChannelConfig::single_ended(p.PIO0_12);
 
     loop {
         let mut data: [i16; 2] = [0; 2];
         adc.sample(&mut data).await;
+        let value = adc.read(&one_shot).await;
 
-        info!("ADC sample = {:#x}", data);
+        info!("ADC sample = {:#x}, one-shot = {:#x}", data, value);
 
         Timer::after_millis(1000).await;
     }
@@ -0,0 +1,141 @@
+#![no_std]
+#![no_main]
+
+use defmt::{info, trace};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::hashcrypt::aes::AesKey;
+use embassy_imxrt::hashcrypt::{self, Hashcrypt, Masking};
+use embassy_imxrt::rng::Rng;
+use embassy_imxrt::{bind_interrupts, peripherals, rng};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    HASHCRYPT => hashcrypt::InterruptHandler<peripherals::HASHCRYPT>;
+    RNG => rng::InterruptHandler<peripherals::RNG>;
+});
+
+// Test vectors from NIST SP 800-38A
+const KEY: [u8; 16] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+const IV: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+const PLAINTEXT: [u8; 32] = [
+    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a, 0xae, 0x2d, 0x8a,
+    0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+];
+const ECB_CIPHERTEXT: [u8; 32] = [
+    0x3a, 0xd7, 0x7b, 0xb4, 0x0d, 0x7a, 0x36, 0x60, 0xa8, 0x9e, 0xca, 0xf3, 0x24, 0x66, 0xef, 0x97, 0xf5, 0xd3, 0xd5,
+    0x85, 0x03, 0xb9, 0x69, 0x9d, 0xe7, 0x85, 0x89, 0x5a, 0x96, 0xfd, 0xba, 0xaf,
+];
+const CBC_CIPHERTEXT: [u8; 32] = [
+    0x76, 0x49, 0xab, 0xac, 0x81, 0x19, 0xb2, 0x46, 0xce, 0xe9, 0x8e, 0x9b, 0x12, 0xe9, 0x19, 0x7d, 0x50, 0x86, 0xcb,
+    0x9b, 0x50, 0x72, 0x19, 0xee, 0x95, 0xdb, 0x11, 0x3a, 0x91, 0x76, 0x78, 0xb2,
+];
+const KEY_256: [u8; 32] = [
+    0x60, 0x3d, 0xeb, 0x10, 0x15, 0xca, 0x71, 0xbe, 0x2b, 0x73, 0xae, 0xf0, 0x85, 0x7d, 0x77, 0x81, 0x1f, 0x35, 0x2c,
+    0x07, 0x3b, 0x61, 0x08, 0xd7, 0x2d, 0x98, 0x10, 0xa3, 0x09, 0x14, 0xdf, 0xf4,
+];
+const ECB_256_CIPHERTEXT: [u8; 32] = [
+    0xf3, 0xee, 0xd1, 0xbd, 0xb5, 0xd2, 0xa0, 0x3c, 0x06, 0x4b, 0x5a, 0x7e, 0x3d, 0xb1, 0x81, 0xf8, 0x59, 0x1c, 0xcb,
+    0x10, 0xd4, 0x10, 0xed, 0x26, 0xdc, 0x5b, 0xa7, 0x4a, 0x31, 0x36, 0x28, 0x70,
+];
+const CTR_COUNTER: [u8; 16] = [
+    0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
+];
+const CTR_CIPHERTEXT: [u8; 32] = [
+    0x87, 0x4d, 0x61, 0x91, 0xb6, 0x20, 0xe3, 0x26, 0x1b, 0xef, 0x68, 0x64, 0x99, 0x0d, 0xb6, 0xce, 0x98, 0x06, 0xf6,
+    0x6b, 0x79, 0x70, 0xfd, 0xff, 0x86, 0x17, 0x18, 0x7b, 0xb9, 0xff, 0xfd, 0xff,
+];
+
+// Test case 2 from the original GCM specification
+const GCM_KEY: [u8; 16] = [0u8; 16];
+const GCM_NONCE: [u8; 12] = [0u8; 12];
+const GCM_PLAINTEXT: [u8; 16] = [0u8; 16];
+const GCM_CIPHERTEXT: [u8; 16] = [
+    0x03, 0x88, 0xda, 0xce, 0x60, 0xb6, 0xa3, 0x92, 0xf3, 0x28, 0xc2, 0xb9, 0x71, 0xb2, 0xfe, 0x78,
+];
+const GCM_TAG: [u8; 16] = [
+    0xab, 0x6e, 0x47, 0xd4, 0x2c, 0xec, 0x13, 0xbd, 0xf5, 0x3a, 0x67, 0xb2, 0x12, 0x57, 0xbd, 0xdf,
+];
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+    let mut output = [0u8; 32];
+
+    info!("Initializing Hashcrypt");
+    let mut rng = Rng::new(p.RNG, Irqs);
+    let config = hashcrypt::Config {
+        masking: Some(Masking::new(&mut rng).unwrap()),
+    };
+    let mut hashcrypt = Hashcrypt::new_async(p.HASHCRYPT, Irqs, p.DMA0_CH30, config);
+    let mut aes = hashcrypt.new_aes(AesKey::Aes128(KEY));
+
+    info!("ECB encrypt");
+    aes.encrypt_ecb(&PLAINTEXT, &mut output).await.unwrap();
+    defmt::assert_eq!(&output, &ECB_CIPHERTEXT);
+
+    info!("ECB decrypt");
+    aes.decrypt_ecb(&ECB_CIPHERTEXT, &mut output).await.unwrap();
+    defmt::assert_eq!(&output, &PLAINTEXT);
+
+    info!("CBC encrypt");
+    aes.encrypt_cbc(&IV, &PLAINTEXT, &mut output).await.unwrap();
+    defmt::assert_eq!(&output, &CBC_CIPHERTEXT);
+
+    info!("CBC decrypt");
+    aes.decrypt_cbc(&IV, &CBC_CIPHERTEXT, &mut output).await.unwrap();
+    defmt::assert_eq!(&output, &PLAINTEXT);
+
+    info!("CTR encrypt");
+    let mut counter = CTR_COUNTER;
+    aes.encrypt_ctr(&mut counter, &PLAINTEXT, &mut output).await.unwrap();
+    defmt::assert_eq!(&output, &CTR_CIPHERTEXT);
+
+    info!("CTR decrypt, split over two calls with a partial block");
+    let mut counter = CTR_COUNTER;
+    let (first, second) = output.split_at_mut(16);
+    aes.decrypt_ctr(&mut counter, &CTR_CIPHERTEXT[..16], first)
+        .await
+        .unwrap();
+    aes.decrypt_ctr(&mut counter, &CTR_CIPHERTEXT[16..27], &mut second[..11])
+        .await
+        .unwrap();
+    defmt::assert_eq!(&output[..27], &PLAINTEXT[..27]);
+
+    info!("ECB encrypt with 256-bit key");
+    let mut aes = hashcrypt.new_aes(AesKey::Aes256(KEY_256));
+    aes.encrypt_ecb(&PLAINTEXT, &mut output).await.unwrap();
+    defmt::assert_eq!(&output, &ECB_256_CIPHERTEXT);
+
+    info!("GCM encrypt");
+    let mut aes = hashcrypt.new_aes(AesKey::Aes128(GCM_KEY));
+    let mut gcm_output = [0u8; 16];
+    let mut tag = [0u8; 16];
+    aes.encrypt_gcm(&GCM_NONCE, &[], &GCM_PLAINTEXT, &mut gcm_output, &mut tag)
+        .await
+        .unwrap();
+    defmt::assert_eq!(&gcm_output, &GCM_CIPHERTEXT);
+    defmt::assert_eq!(&tag, &GCM_TAG);
+
+    info!("GCM decrypt");
+    aes.decrypt_gcm(&GCM_NONCE, &[], &GCM_CIPHERTEXT, &mut gcm_output, &GCM_TAG)
+        .await
+        .unwrap();
+    defmt::assert_eq!(&gcm_output, &GCM_PLAINTEXT);
+
+    info!("GCM decrypt with a corrupted tag");
+    let mut bad_tag = GCM_TAG;
+    bad_tag[0] ^= 1;
+    defmt::assert_eq!(
+        aes.decrypt_gcm(&GCM_NONCE, &[], &GCM_CIPHERTEXT, &mut gcm_output, &bad_tag)
+            .await,
+        Err(hashcrypt::Error::AuthenticationFailed)
+    );
+
+    trace!("AES complete");
+}
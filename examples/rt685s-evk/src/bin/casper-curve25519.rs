@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+use defmt::{info, trace};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::casper::curve25519::{KEY_LEN, SIGNATURE_LEN};
+use embassy_imxrt::casper::{self, Casper};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+// Test vectors from RFC 7748 6.1
+const ALICE_PRIVATE: [u8; KEY_LEN] = [
+    0x77, 0x07, 0x6d, 0x0a, 0x73, 0x18, 0xa5, 0x7d, 0x3c, 0x16, 0xc1, 0x72, 0x51, 0xb2, 0x66, 0x45, 0xdf, 0x4c, 0x2f,
+    0x87, 0xeb, 0xc0, 0x99, 0x2a, 0xb1, 0x77, 0xfb, 0xa5, 0x1d, 0xb9, 0x2c, 0x2a,
+];
+const ALICE_PUBLIC: [u8; KEY_LEN] = [
+    0x85, 0x20, 0xf0, 0x09, 0x89, 0x30, 0xa7, 0x54, 0x74, 0x8b, 0x7d, 0xdc, 0xb4, 0x3e, 0xf7, 0x5a, 0x0d, 0xbf, 0x3a,
+    0x0d, 0x26, 0x38, 0x1a, 0xf4, 0xeb, 0xa4, 0xa9, 0x8e, 0xaa, 0x9b, 0x4e, 0x6a,
+];
+const BOB_PRIVATE: [u8; KEY_LEN] = [
+    0x5d, 0xab, 0x08, 0x7e, 0x62, 0x4a, 0x8a, 0x4b, 0x79, 0xe1, 0x7f, 0x8b, 0x83, 0x80, 0x0e, 0xe6, 0x6f, 0x3b, 0xb1,
+    0x29, 0x26, 0x18, 0xb6, 0xfd, 0x1c, 0x2f, 0x8b, 0x27, 0xff, 0x88, 0xe0, 0xeb,
+];
+const BOB_PUBLIC: [u8; KEY_LEN] = [
+    0xde, 0x9e, 0xdb, 0x7d, 0x7b, 0x7d, 0xc1, 0xb4, 0xd3, 0x5b, 0x61, 0xc2, 0xec, 0xe4, 0x35, 0x37, 0x3f, 0x83, 0x43,
+    0xc8, 0x5b, 0x78, 0x67, 0x4d, 0xad, 0xfc, 0x7e, 0x14, 0x6f, 0x88, 0x2b, 0x4f,
+];
+const SHARED_SECRET: [u8; KEY_LEN] = [
+    0x4a, 0x5d, 0x9d, 0x5b, 0xa4, 0xce, 0x2d, 0xe1, 0x72, 0x8e, 0x3b, 0xf4, 0x80, 0x35, 0x0f, 0x25, 0xe0, 0x7e, 0x21,
+    0xc9, 0x47, 0xd1, 0x9e, 0x33, 0x76, 0xf0, 0x9b, 0x3c, 0x1e, 0x16, 0x17, 0x42,
+];
+
+// Test vectors from RFC 8032 7.1, test 2
+const ED25519_PUBLIC: [u8; KEY_LEN] = [
+    0x3d, 0x40, 0x17, 0xc3, 0xe8, 0x43, 0x89, 0x5a, 0x92, 0xb7, 0x0a, 0xa7, 0x4d, 0x1b, 0x7e, 0xbc, 0x9c, 0x98, 0x2c,
+    0xcf, 0x2e, 0xc4, 0x96, 0x8c, 0xc0, 0xcd, 0x55, 0xf1, 0x2a, 0xf4, 0x66, 0x0c,
+];
+const ED25519_MESSAGE: [u8; 1] = [0x72];
+const ED25519_SIGNATURE: [u8; SIGNATURE_LEN] = [
+    0x92, 0xa0, 0x09, 0xa9, 0xf0, 0xd4, 0xca, 0xb8, 0x72, 0x0e, 0x82, 0x0b, 0x5f, 0x64, 0x25, 0x40, 0xa2, 0xb2, 0x7b,
+    0x54, 0x16, 0x50, 0x3f, 0x8f, 0xb3, 0x76, 0x22, 0x23, 0xeb, 0xdb, 0x69, 0xda, 0x08, 0x5a, 0xc1, 0xe4, 0x3e, 0x15,
+    0x99, 0x6e, 0x45, 0x8f, 0x36, 0x13, 0xd0, 0xf1, 0x1d, 0x8c, 0x38, 0x7b, 0x2e, 0xae, 0xb4, 0x30, 0x2a, 0xee, 0xb0,
+    0x0d, 0x29, 0x16, 0x12, 0xbb, 0x0c, 0x00,
+];
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+    let mut result = [0u8; KEY_LEN];
+
+    info!("Initializing CASPER");
+    let mut casper = Casper::new_blocking(p.CASPER);
+
+    info!("X25519 public keys");
+    casper.x25519_public_key(&ALICE_PRIVATE, &mut result).unwrap();
+    defmt::assert_eq!(result, ALICE_PUBLIC);
+    casper.x25519_public_key(&BOB_PRIVATE, &mut result).unwrap();
+    defmt::assert_eq!(result, BOB_PUBLIC);
+
+    info!("X25519 shared secret");
+    casper.x25519(&ALICE_PRIVATE, &BOB_PUBLIC, &mut result).unwrap();
+    defmt::assert_eq!(result, SHARED_SECRET);
+    casper.x25519(&BOB_PRIVATE, &ALICE_PUBLIC, &mut result).unwrap();
+    defmt::assert_eq!(result, SHARED_SECRET);
+
+    info!("Ed25519 signature verification");
+    casper
+        .ed25519_verify(&ED25519_PUBLIC, &ED25519_MESSAGE, &ED25519_SIGNATURE)
+        .unwrap();
+    defmt::assert_eq!(
+        casper.ed25519_verify(&ED25519_PUBLIC, b"tampered", &ED25519_SIGNATURE),
+        Err(casper::Error::InvalidSignature)
+    );
+    trace!("Curve25519 complete");
+}
@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+use defmt::{info, trace};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::casper::ecdsa::{PUBLIC_KEY_LEN, SIGNATURE_LEN};
+use embassy_imxrt::casper::{self, Casper};
+use embassy_imxrt::rng::Rng;
+use embassy_imxrt::{bind_interrupts, peripherals, rng};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    CASPER => casper::InterruptHandler<peripherals::CASPER>;
+    RNG => rng::InterruptHandler<peripherals::RNG>;
+});
+
+// Test vectors from RFC 6979 A.2.5, message "sample" with SHA-256
+const PRIVATE_KEY: [u8; 32] = [
+    0xc9, 0xaf, 0xa9, 0xd8, 0x45, 0xba, 0x75, 0x16, 0x6b, 0x5c, 0x21, 0x57, 0x67, 0xb1, 0xd6, 0x93, 0x4e, 0x50, 0xc3,
+    0xdb, 0x36, 0xe8, 0x9b, 0x12, 0x7b, 0x8a, 0x62, 0x2b, 0x12, 0x0f, 0x67, 0x21,
+];
+const PUBLIC_KEY: [u8; PUBLIC_KEY_LEN] = [
+    0x60, 0xfe, 0xd4, 0xba, 0x25, 0x5a, 0x9d, 0x31, 0xc9, 0x61, 0xeb, 0x74, 0xc6, 0x35, 0x6d, 0x68, 0xc0, 0x49, 0xb8,
+    0x92, 0x3b, 0x61, 0xfa, 0x6c, 0xe6, 0x69, 0x62, 0x2e, 0x60, 0xf2, 0x9f, 0xb6, 0x79, 0x03, 0xfe, 0x10, 0x08, 0xb8,
+    0xbc, 0x99, 0xa4, 0x1a, 0xe9, 0xe9, 0x56, 0x28, 0xbc, 0x64, 0xf2, 0xf1, 0xb2, 0x0c, 0x2d, 0x7e, 0x9f, 0x51, 0x77,
+    0xa3, 0xc2, 0x94, 0xd4, 0x46, 0x22, 0x99,
+];
+const HASH: [u8; 32] = [
+    0xaf, 0x2b, 0xdb, 0xe1, 0xaa, 0x9b, 0x6e, 0xc1, 0xe2, 0xad, 0xe1, 0xd6, 0x94, 0xf4, 0x1f, 0xc7, 0x1a, 0x83, 0x1d,
+    0x02, 0x68, 0xe9, 0x89, 0x15, 0x62, 0x11, 0x3d, 0x8a, 0x62, 0xad, 0xd1, 0xbf,
+];
+const SIGNATURE: [u8; SIGNATURE_LEN] = [
+    0xef, 0xd4, 0x8b, 0x2a, 0xac, 0xb6, 0xa8, 0xfd, 0x11, 0x40, 0xdd, 0x9c, 0xd4, 0x5e, 0x81, 0xd6, 0x9d, 0x2c, 0x87,
+    0x7b, 0x56, 0xaa, 0xf9, 0x91, 0xc3, 0x4d, 0x0e, 0xa8, 0x4e, 0xaf, 0x37, 0x16, 0xf7, 0xcb, 0x1c, 0x94, 0x2d, 0x65,
+    0x7c, 0x41, 0xd4, 0x36, 0xc7, 0xa1, 0xb6, 0xe2, 0x9f, 0x65, 0xf3, 0xe9, 0x00, 0xdb, 0xb9, 0xaf, 0xf4, 0x06, 0x4d,
+    0xc4, 0xab, 0x2f, 0x84, 0x3a, 0xcd, 0xa8,
+];
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    info!("Initializing CASPER");
+    let mut rng = Rng::new(p.RNG, Irqs);
+    let mut casper = Casper::new_async(p.CASPER, Irqs);
+
+    info!("Public key derivation");
+    let mut public_key = [0u8; PUBLIC_KEY_LEN];
+    casper.p256_public_key(&PRIVATE_KEY, &mut public_key).await.unwrap();
+    defmt::assert_eq!(&public_key, &PUBLIC_KEY);
+
+    info!("Signature verification");
+    casper.p256_verify(&PUBLIC_KEY, &HASH, &SIGNATURE).await.unwrap();
+
+    let mut corrupted = SIGNATURE;
+    corrupted[0] ^= 1;
+    defmt::assert_eq!(
+        casper.p256_verify(&PUBLIC_KEY, &HASH, &corrupted).await,
+        Err(casper::Error::InvalidSignature)
+    );
+
+    info!("Signing");
+    let mut signature = [0u8; SIGNATURE_LEN];
+    casper
+        .p256_sign(&PRIVATE_KEY, &HASH, &mut rng, &mut signature)
+        .await
+        .unwrap();
+    casper.p256_verify(&PUBLIC_KEY, &HASH, &signature).await.unwrap();
+    trace!("ECDSA complete");
+}
@@ -0,0 +1,106 @@
+#![no_std]
+#![no_main]
+
+use defmt::{info, trace};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::casper::{self, Casper, Modulus};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+const MODULUS: [u8; 16] = [
+    0xc5, 0xa4, 0xe0, 0xb0, 0xd3, 0xf1, 0xa2, 0xb4, 0xc6, 0xd8, 0xe0, 0xf2, 0xa4, 0xb6, 0xc8, 0xd1,
+];
+const A: [u8; 16] = [
+    0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef,
+];
+const B: [u8; 16] = [
+    0x0f, 0xed, 0xcb, 0xa9, 0x87, 0x65, 0x43, 0x21, 0x0f, 0xed, 0xcb, 0xa9, 0x87, 0x65, 0x43, 0x21,
+];
+
+// RSA-2048 key and PKCS#1 v1.5 signature of SHA-256("firmware image")
+const RSA_MODULUS: [u8; 256] = [
+    0xb2, 0x6b, 0x9a, 0xc6, 0x3a, 0x93, 0xc5, 0xbb, 0xe1, 0xd0, 0xdf, 0x05, 0x6d, 0xcd, 0x9d, 0x5b, 0x2f, 0xe8, 0xfa,
+    0x53, 0x24, 0x19, 0xa8, 0x30, 0x18, 0x5f, 0x99, 0x45, 0xce, 0x01, 0x30, 0x2f, 0x72, 0xa2, 0x63, 0x7a, 0x84, 0x08,
+    0xf5, 0x51, 0x0b, 0xad, 0xe1, 0x4e, 0x56, 0x87, 0x85, 0x10, 0x01, 0x53, 0x21, 0xfc, 0x73, 0x4d, 0x62, 0xf6, 0x8a,
+    0x88, 0x3b, 0xca, 0x03, 0xeb, 0xb9, 0xe5, 0x9e, 0x71, 0x8d, 0x9d, 0x23, 0xaf, 0x09, 0x4c, 0x85, 0xa8, 0x27, 0x0f,
+    0xb6, 0x5a, 0x85, 0x96, 0x6b, 0x61, 0xb7, 0xfb, 0xee, 0x94, 0x17, 0x4e, 0xb8, 0x8d, 0x4d, 0xd0, 0x4f, 0x41, 0xfc,
+    0xb5, 0x7c, 0x69, 0x43, 0x4c, 0x0e, 0x1b, 0xf0, 0x7d, 0x6d, 0x26, 0x4a, 0x2f, 0x1b, 0xdc, 0xa5, 0x5b, 0x03, 0x2d,
+    0x9b, 0xaf, 0xc6, 0xa1, 0x95, 0x69, 0xc6, 0x1f, 0xcd, 0xc1, 0x99, 0xbd, 0x4d, 0xef, 0xe4, 0x56, 0xd9, 0x66, 0x54,
+    0xd0, 0xc2, 0x09, 0x7b, 0x4e, 0x43, 0x08, 0xdb, 0xc0, 0x0b, 0x41, 0xe3, 0xc7, 0x82, 0x5f, 0x35, 0x4c, 0xd5, 0xca,
+    0x33, 0x0a, 0xa2, 0xd0, 0x92, 0x6c, 0x80, 0x20, 0xb4, 0x12, 0xee, 0xec, 0x26, 0x34, 0x3a, 0xc7, 0xa3, 0xc4, 0x57,
+    0x4e, 0xc1, 0x27, 0xab, 0xc7, 0x03, 0xea, 0x0e, 0x38, 0x2d, 0xa3, 0x56, 0xa8, 0xc4, 0x55, 0x09, 0xf1, 0xc5, 0x42,
+    0x69, 0xec, 0x79, 0x9b, 0x22, 0x23, 0x98, 0x48, 0x16, 0x9b, 0x75, 0x95, 0x2e, 0xbe, 0xec, 0x11, 0x20, 0x22, 0xff,
+    0x22, 0x82, 0x7f, 0x12, 0xc9, 0xa9, 0x3c, 0x9a, 0xa6, 0x73, 0x20, 0xbf, 0x9f, 0xdb, 0x8e, 0xfa, 0xb3, 0xa3, 0x30,
+    0x1c, 0x12, 0x35, 0x81, 0x51, 0xf4, 0xa8, 0x91, 0xb5, 0xe8, 0xb1, 0xa7, 0xd7, 0x27, 0x21, 0x64, 0xe4, 0x28, 0x19,
+    0x5c, 0x95, 0xdf, 0x88, 0x94, 0x0b, 0x54, 0xa1, 0xbb,
+];
+const RSA_SIGNATURE: [u8; 256] = [
+    0x07, 0xe1, 0xef, 0xc9, 0xe7, 0x2a, 0x13, 0x06, 0x9b, 0x6d, 0xd2, 0x4d, 0x37, 0xd5, 0xae, 0x49, 0xba, 0xda, 0x02,
+    0xe0, 0xf7, 0x4a, 0x30, 0x1c, 0xc8, 0xd6, 0xd8, 0xc3, 0xac, 0xf5, 0xeb, 0x34, 0x6f, 0x59, 0x29, 0x04, 0x9b, 0xe1,
+    0x85, 0x2d, 0x47, 0x80, 0x46, 0xcd, 0xba, 0x2d, 0x12, 0x41, 0xc9, 0xa1, 0x7f, 0x60, 0xea, 0x6b, 0xc5, 0xe5, 0x97,
+    0x5c, 0x14, 0xa4, 0x07, 0xcd, 0xf3, 0x47, 0xef, 0xa0, 0x86, 0x8c, 0x78, 0xd3, 0x9c, 0x4b, 0x18, 0xb7, 0x1b, 0xcc,
+    0xde, 0xb2, 0xfa, 0x3d, 0xa1, 0xa3, 0xef, 0xc0, 0xc6, 0x1d, 0x20, 0xf4, 0xe8, 0xe5, 0x65, 0x23, 0xc6, 0xc0, 0xb7,
+    0x23, 0x55, 0x38, 0x01, 0x52, 0x5e, 0x78, 0x70, 0x8f, 0xe8, 0xe4, 0x1b, 0x9f, 0x0f, 0xa3, 0xb4, 0xaf, 0x46, 0xa5,
+    0xbc, 0x52, 0xb3, 0x4b, 0xe5, 0x3d, 0x9c, 0x15, 0xc4, 0xf3, 0xc7, 0xba, 0x48, 0x50, 0x51, 0x6e, 0x98, 0xc9, 0x5f,
+    0x74, 0xba, 0xa3, 0x70, 0x0b, 0x2b, 0x5a, 0x1d, 0x52, 0xd2, 0x2b, 0x9d, 0xee, 0x42, 0x2b, 0xa9, 0xb1, 0xc9, 0xe0,
+    0x6a, 0x50, 0x1d, 0x13, 0x30, 0x77, 0x3b, 0x28, 0x08, 0xbf, 0x91, 0xd4, 0x36, 0x5a, 0xfe, 0xe2, 0x05, 0xec, 0x98,
+    0x52, 0x58, 0x38, 0x7c, 0x61, 0x4a, 0x66, 0x90, 0x6a, 0xf4, 0x02, 0xdb, 0x0d, 0x39, 0x5e, 0xd3, 0x32, 0x40, 0xbb,
+    0xc4, 0x91, 0x3b, 0x63, 0x1d, 0xa1, 0x0a, 0xd4, 0x27, 0x99, 0x8d, 0x28, 0xb7, 0x7a, 0x89, 0x62, 0x20, 0x7f, 0xcb,
+    0xfd, 0xfc, 0x68, 0x4e, 0xc2, 0x07, 0x06, 0x5b, 0x78, 0xc6, 0xa4, 0x5e, 0xdd, 0x61, 0xf2, 0x95, 0x70, 0x43, 0x2e,
+    0x20, 0x62, 0x2a, 0x32, 0x52, 0xac, 0x72, 0x13, 0x2f, 0xa1, 0x1f, 0xe1, 0x56, 0x47, 0xb3, 0x1e, 0xa0, 0xa3, 0x08,
+    0xe6, 0xf5, 0xa1, 0xa9, 0x60, 0xe0, 0x5e, 0xd5, 0xef,
+];
+const RSA_HASH: [u8; 32] = [
+    0x1d, 0xf2, 0xf3, 0x85, 0x3d, 0x10, 0xa3, 0x05, 0xaa, 0x52, 0xd3, 0x6f, 0xd4, 0xa0, 0x3f, 0x57, 0x21, 0xd7, 0xce,
+    0x7d, 0xae, 0xf6, 0xf7, 0xe5, 0xe8, 0xd5, 0x10, 0x74, 0xd3, 0x13, 0x61, 0xf1,
+];
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+    let mut result = [0u8; 16];
+
+    info!("Initializing CASPER");
+    let mut casper = Casper::new_blocking(p.CASPER);
+
+    info!("Modular multiplication");
+    casper.mod_mul(&A, &B, &MODULUS, &mut result).unwrap();
+    defmt::assert_eq!(
+        result,
+        [
+            0x7a, 0xb9, 0x30, 0x13, 0xb2, 0x6f, 0x2b, 0x95, 0xd6, 0x00, 0xe3, 0x21, 0xb2, 0xa1, 0x09, 0x1c
+        ]
+    );
+
+    info!("Modular exponentiation");
+    casper.mod_exp(&A, &[0x01, 0x00, 0x01], &MODULUS, &mut result).unwrap();
+    defmt::assert_eq!(
+        result,
+        [
+            0xb0, 0x31, 0xb3, 0x18, 0xb6, 0x1f, 0x0c, 0x82, 0xe6, 0x1d, 0x33, 0x7c, 0x73, 0x88, 0xa6, 0xec
+        ]
+    );
+
+    info!("Montgomery arithmetic");
+    let modulus = Modulus::new(&MODULUS).unwrap();
+    // Little-endian words of A and B
+    let a = [0x89abcdef, 0x01234567, 0x89abcdef, 0x01234567];
+    let b = [0x87654321, 0x0fedcba9, 0x87654321, 0x0fedcba9];
+    let mut a_mont = [0u32; 4];
+    let mut product = [0u32; 4];
+    casper.montgomery_encode(&modulus, &a, &mut a_mont).unwrap();
+    casper.montgomery_multiply(&modulus, &a_mont, &b, &mut product).unwrap();
+    defmt::assert_eq!(product, [0xb2a1091c, 0xd600e321, 0xb26f2b95, 0x7ab93013]);
+
+    info!("RSA signature verification");
+    casper.rsa_verify(&RSA_MODULUS, &RSA_SIGNATURE, &RSA_HASH).unwrap();
+
+    let mut corrupted = RSA_SIGNATURE;
+    corrupted[0] ^= 1;
+    defmt::assert_eq!(
+        casper.rsa_verify(&RSA_MODULUS, &corrupted, &RSA_HASH),
+        Err(casper::Error::InvalidSignature)
+    );
+    trace!("CASPER complete");
+}
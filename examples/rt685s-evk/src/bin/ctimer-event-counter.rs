@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::timer::{CountEdge, EventCounter};
+use embassy_imxrt::{bind_interrupts, peripherals, timer};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    CTIMER2 => timer::InterruptHandler<peripherals::CTIMER2_COUNT_CHANNEL1>;
+});
+
+// Pulses per litre of the flow meter wired to PIO0_4
+const PULSES_PER_LITRE: u32 = 450;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    let mut counter = EventCounter::new_async(
+        p.CTIMER2_CAPTURE_CHANNEL0,
+        p.CTIMER2_COUNT_CHANNEL1,
+        p.PIO0_4,
+        CountEdge::Rising,
+        Irqs,
+    )
+    .unwrap();
+
+    counter.start_periodic(PULSES_PER_LITRE).unwrap();
+
+    loop {
+        counter.wait().await;
+        info!("{} litres", counter.count() / PULSES_PER_LITRE);
+    }
+}
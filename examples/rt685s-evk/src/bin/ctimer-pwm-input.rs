@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::clocks::ClockConfig;
+use embassy_imxrt::timer::PwmInput;
+use embassy_imxrt::{bind_interrupts, peripherals, timer};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    CTIMER3 => timer::InterruptHandler<peripherals::CTIMER3_CAPTURE_CHANNEL1>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    let sfro = ClockConfig::crystal().sfro;
+    let mut pwm_input = PwmInput::new_async(
+        p.CTIMER3_CAPTURE_CHANNEL0,
+        p.CTIMER3_CAPTURE_CHANNEL1,
+        p.PIO0_4,
+        sfro,
+        Irqs,
+    )
+    .unwrap();
+
+    loop {
+        let measurement = pwm_input.measure().await;
+        let duty = measurement.duty_cycle();
+        info!(
+            "period = {} us, high = {} us, frequency = {} Hz, duty = {}.{}%",
+            measurement.period().0,
+            measurement.high_time().0,
+            measurement.frequency().0,
+            duty.0,
+            duty.1
+        );
+    }
+}
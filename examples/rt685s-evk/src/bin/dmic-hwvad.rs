@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::dmic::{self, Dmic};
+use embassy_imxrt::{bind_interrupts, peripherals};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    HWVAD0 => dmic::HwvadInterruptHandler<peripherals::DMIC0>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    // the PDM microphone clock is on P2_16, its data on P2_19
+    info!("dmic hwvad example - say something");
+
+    // keep listening from the low-power oscillator while the chip sleeps
+    let config = dmic::Config {
+        clock: dmic::FunctionClock::Lposc,
+        wake_on_voice: true,
+        hwvad: dmic::HwvadConfig {
+            noise_gain: 3,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut dmic = Dmic::new(p.DMIC0, p.PIO2_16, p.PIO2_19, Irqs, config).unwrap();
+    info!("dmic hwvad example - sampling at {} Hz", dmic.sample_rate());
+
+    let mut count = 0u32;
+    loop {
+        dmic.wait_for_voice().await;
+        count += 1;
+        info!("dmic hwvad example - voice detected ({})", count);
+    }
+}
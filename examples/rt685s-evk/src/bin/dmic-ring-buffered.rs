@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+use defmt::{info, warn};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::dmic::{self, RingBufferedDmic};
+use embassy_imxrt::{bind_interrupts, peripherals};
+use embassy_imxrt_examples as _;
+use embassy_time::Timer;
+use panic_probe as _;
+
+// two blocks of 10 ms at 48 kHz
+const BLOCK_LEN: usize = 480;
+
+bind_interrupts!(struct Irqs {
+    HWVAD0 => dmic::HwvadInterruptHandler<peripherals::DMIC0>;
+});
+
+static mut SAMPLES: [i32; 2 * BLOCK_LEN] = [0; 2 * BLOCK_LEN];
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    // the PDM microphone clock is on P2_16, its data on P2_19
+    info!("dmic ring buffer example - start");
+
+    let mut dmic = RingBufferedDmic::new(
+        p.DMIC0,
+        p.PIO2_16,
+        p.PIO2_19,
+        Irqs,
+        p.DMA0_CH16,
+        unsafe { &mut *core::ptr::addr_of_mut!(SAMPLES) },
+        Default::default(),
+    )
+    .unwrap();
+    info!("dmic ring buffer example - sampling at {} Hz", dmic.sample_rate());
+
+    let mut block = [0; BLOCK_LEN];
+    loop {
+        match dmic.read(&mut block).await {
+            Ok(()) => {
+                let peak = block.iter().map(|sample| sample.unsigned_abs()).max().unwrap_or(0);
+                info!("dmic ring buffer example - peak {}", peak);
+            }
+            Err(e) => warn!("dmic ring buffer example - {}", e),
+        }
+
+        // busy executor: capture goes on in the meantime
+        Timer::after_millis(5).await;
+    }
+}
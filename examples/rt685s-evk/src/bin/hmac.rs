@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+use defmt::{info, trace};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::hashcrypt::{Hashcrypt, hmac};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+    let mut mac = [0u8; hmac::MAC_LEN];
+
+    info!("Initializing Hashcrypt");
+    let mut hashcrypt = Hashcrypt::new_blocking(p.HASHCRYPT, Default::default());
+
+    // Test vectors from RFC 4231
+    info!("Test case 1");
+    let mut hmac = hashcrypt.new_hmac_sha256(&[0x0b; 20]).unwrap();
+    hmac.update(b"Hi There").unwrap();
+    hmac.finalize(&mut mac).unwrap();
+    defmt::assert_eq!(
+        &mac,
+        &[
+            0xb0, 0x34, 0x4c, 0x61, 0xd8, 0xdb, 0x38, 0x53, 0x5c, 0xa8, 0xaf, 0xce, 0xaf, 0x0b, 0xf1, 0x2b, 0x88, 0x1d,
+            0xc2, 0x00, 0xc9, 0x83, 0x3d, 0xa7, 0x26, 0xe9, 0x37, 0x6c, 0x2e, 0x32, 0xcf, 0xf7
+        ]
+    );
+
+    info!("Test case 2, data submitted in pieces");
+    let mut hmac = hashcrypt.new_hmac_sha256(b"Jefe").unwrap();
+    hmac.update(b"what do ya want ").unwrap();
+    hmac.update(b"for nothing?").unwrap();
+    hmac.finalize(&mut mac).unwrap();
+    defmt::assert_eq!(
+        &mac,
+        &[
+            0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95, 0x75, 0xc7, 0x5a, 0x00,
+            0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9, 0x64, 0xec, 0x38, 0x43
+        ]
+    );
+    trace!("HMAC complete");
+}
@@ -77,6 +77,7 @@ async fn slave_service(mut slave: I2cSlave<'static, Async>) {
                     }
                 }
             }
+            _ => {}
         }
     }
 }
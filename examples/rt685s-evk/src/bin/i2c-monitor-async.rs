@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+use defmt::{error, info};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::i2c::Async;
+use embassy_imxrt::i2c::master::I2cMaster;
+use embassy_imxrt::i2c::monitor::{Event, I2cMonitor};
+use embassy_imxrt::{bind_interrupts, i2c, peripherals};
+use embassy_imxrt_examples as _;
+use embassy_time::Timer;
+use embedded_hal_async::i2c::I2c;
+use panic_probe as _;
+
+const ACC_ADDR: u8 = 0x1E;
+const ACC_ID_REG: u8 = 0x0D;
+
+bind_interrupts!(struct Irqs {
+    FLEXCOMM2 => i2c::InterruptHandler<peripherals::FLEXCOMM2>;
+    FLEXCOMM4 => i2c::InterruptHandler<peripherals::FLEXCOMM4>;
+});
+
+/// Log every event seen on the bus
+#[embassy_executor::task]
+async fn monitor_service(mut monitor: I2cMonitor<'static, Async>) {
+    loop {
+        match monitor.next_event().await {
+            Ok(Event::Address {
+                repeated,
+                address,
+                read,
+                ack,
+            }) => {
+                let start = if repeated { "Sr" } else { "S" };
+                let dir = if read { "R" } else { "W" };
+                info!("{} {:02X} {} ack={}", start, address, dir, ack);
+            }
+            Ok(Event::Data { byte, ack }) => info!("  {:02X} ack={}", byte, ack),
+            Ok(Event::Stop) => info!("P"),
+            Err(e) => error!("monitor error: {}", e),
+        }
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    // FC2 talks to the accelerometer on P0_18 (SCL) and P0_17 (SDA), FC4 watches the same bus:
+    // connect P0_29 to P0_18 and P0_30 to P0_17.
+    info!("i2c monitor example - connect P0_29 to P0_18 and P0_30 to P0_17");
+
+    let monitor = I2cMonitor::new_async(
+        p.FLEXCOMM4,
+        p.PIO0_29,
+        p.PIO0_30,
+        Irqs,
+        i2c::monitor::Config { clock_stretch: true },
+    );
+    spawner.spawn(monitor_service(monitor).unwrap());
+
+    let mut i2c =
+        I2cMaster::new_async(p.FLEXCOMM2, p.PIO0_18, p.PIO0_17, Irqs, Default::default(), p.DMA0_CH5).unwrap();
+
+    loop {
+        let mut id = [0u8; 1];
+        if let Err(e) = i2c.write_read(ACC_ADDR, &[ACC_ID_REG], &mut id).await {
+            error!("i2c monitor example - write_read failed: {}", e);
+        }
+        Timer::after_secs(1).await;
+    }
+}
@@ -42,7 +42,7 @@ async fn slave_service(mut i2c: I2cSlave<'static, Async>) {
                     }
                 }
             }
-            Command::Write => {
+            Command::Write | Command::GeneralCall => {
                 info!("Write");
                 loop {
                     match i2c.respond_to_write(&mut buf).await.unwrap() {
@@ -54,6 +54,7 @@ async fn slave_service(mut i2c: I2cSlave<'static, Async>) {
                     }
                 }
             }
+            _ => {}
         }
     }
 }
@@ -65,7 +66,8 @@ async fn main(spawner: Spawner) {
     // NOTE: Tested with a raspberry pi 5 as master controller connected FC2 to i2c on Pi5
     //       Test program here: https://github.com/jerrysxie/pi5-i2c-test
     info!("i2cs example - I2c::new");
-    let i2c = I2cSlave::new_async(p.FLEXCOMM2, p.PIO0_18, p.PIO0_17, Irqs, SLAVE_ADDR.unwrap(), p.DMA0_CH4).unwrap();
+    let mut i2c = I2cSlave::new_async(p.FLEXCOMM2, p.PIO0_18, p.PIO0_17, Irqs, SLAVE_ADDR.unwrap(), p.DMA0_CH4).unwrap();
+    i2c.set_general_call(true);
 
     spawner.spawn(slave_service(i2c).unwrap());
 }
@@ -49,6 +49,7 @@ async fn slave_service(i2c: I2cSlave<'static, Blocking>) {
                     }
                 }
             }
+            _ => {}
         }
     }
 }
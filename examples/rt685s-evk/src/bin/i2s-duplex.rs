@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+use defmt::{error, info};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::clocks::{AudioPllConfig, enable_audio_pll};
+use embassy_imxrt::i2s::{self, FunctionClock, I2sDuplex, Mclk, MclkSource};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+const FRAMES: usize = 256;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    // FC3 transmits and drives the clocks, FC1 receives on P0_9: connect P0_23 to P0_9.
+    info!("i2s duplex example - connect P0_23 to P0_9");
+
+    // 24.576 MHz audio PLL: exact 48 kHz frames, and a 256 x Fs MCLK on P1_10 for a codec
+    enable_audio_pll(&AudioPllConfig::audio_24_576mhz()).unwrap();
+    let mclk = Mclk::new(p.PIO1_10, MclkSource::AudioPll, 12_288_000).unwrap();
+    info!("i2s duplex example - MCLK at {} Hz", mclk.frequency());
+
+    let config = i2s::Config {
+        clock: FunctionClock::AudioPll,
+        ..Default::default()
+    };
+
+    let mut i2s = I2sDuplex::new(
+        p.FLEXCOMM3,
+        p.FLEXCOMM1,
+        p.PIO0_21,
+        p.PIO0_22,
+        p.PIO0_23,
+        p.PIO0_9,
+        p.DMA0_CH7,
+        p.DMA0_CH2,
+        config,
+    )
+    .unwrap();
+
+    let mut tx = [0u32; FRAMES];
+    let mut rx = [0u32; FRAMES];
+
+    // 16-bit stereo frames, left channel in the low half
+    for (i, frame) in tx.iter_mut().enumerate() {
+        let left = i as u32;
+        let right = !left & 0xffff;
+        *frame = right << 16 | left;
+    }
+
+    loop {
+        match i2s.transfer(&tx, &mut rx).await {
+            Ok(()) => info!("i2s duplex example - received {:08X}", rx[FRAMES - 1]),
+            Err(e) => error!("i2s duplex example - transfer failed: {}", e),
+        }
+    }
+}
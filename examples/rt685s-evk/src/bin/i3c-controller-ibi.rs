@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+use defmt::{error, info};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::i3c::controller::{I3cController, IbiKind};
+use embassy_imxrt::{bind_interrupts, i3c, peripherals};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+/// Static address of the sensor, and the dynamic address it gets
+const SENSOR_STATIC_ADDR: u8 = 0x30;
+const SENSOR_ADDR: u8 = 0x08;
+
+bind_interrupts!(struct Irqs {
+    I3C0 => i3c::InterruptHandler<peripherals::I3C0>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    // connect the sensor to P2_29 (SCL) and P2_30 (SDA)
+    info!("i3c controller ibi example");
+
+    let mut i3c = I3cController::new(p.I3C0, p.PIO2_29, p.PIO2_30, Irqs, Default::default()).unwrap();
+
+    // the sensor gets its address first, so it keeps the highest priority, any other target is
+    // found by ENTDAA
+    i3c.reset_dynamic_addresses().await.unwrap();
+    if let Err(e) = i3c
+        .set_dynamic_address_from_static(SENSOR_STATIC_ADDR, SENSOR_ADDR)
+        .await
+    {
+        error!("i3c controller ibi example - no sensor: {}", e);
+    }
+    i3c.assign_dynamic_addresses().await.unwrap();
+    for device in i3c.devices() {
+        info!(
+            "i3c controller ibi example - {:02X}: PID {:012X}, BCR {:02X}, DCR {:02X}",
+            device.address, device.pid, device.bcr, device.dcr
+        );
+    }
+    i3c.set_hot_join(true).await.unwrap();
+
+    // the sensor interrupts carry a mandatory data byte
+    i3c.set_ibi_rules(&[SENSOR_ADDR], true).unwrap();
+    i3c.enable_ibi(Some(SENSOR_ADDR)).await.unwrap();
+
+    let mut payload = [0u8; 8];
+    loop {
+        match i3c.wait_for_ibi(&mut payload).await {
+            Ok(ibi) if ibi.kind == IbiKind::Interrupt => {
+                info!(
+                    "i3c controller ibi example - IBI from {:02X}: {:02X}",
+                    ibi.address,
+                    payload[..ibi.len]
+                );
+            }
+            Ok(ibi) if ibi.kind == IbiKind::HotJoin => match i3c.assign_dynamic_addresses().await {
+                Ok(n) => info!("i3c controller ibi example - {} targets joined", n),
+                Err(e) => error!("i3c controller ibi example - hot-join failed: {}", e),
+            },
+            Ok(ibi) => info!("i3c controller ibi example - {} from {:02X}", ibi.kind, ibi.address),
+            Err(e) => error!("i3c controller ibi example - failed: {}", e),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+use defmt::{error, info};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::i3c::controller::{I3cController, MAX_DEVICES};
+use embassy_imxrt::{bind_interrupts, i3c, peripherals};
+use embassy_imxrt_examples as _;
+use embassy_time::Timer;
+use panic_probe as _;
+
+/// Fast-mode I2C EEPROM, with a spike filter: LVR index 0, Fast-mode only
+const EEPROM_ADDR: u8 = 0x50;
+const EEPROM_LVR: u8 = 1 << 4;
+
+bind_interrupts!(struct Irqs {
+    I3C0 => i3c::InterruptHandler<peripherals::I3C0>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    // connect the I3C targets and the I2C EEPROM to P2_29 (SCL) and P2_30 (SDA)
+    info!("i3c mixed bus example");
+
+    let mut i3c = I3cController::new(p.I3C0, p.PIO2_29, p.PIO2_30, Irqs, Default::default()).unwrap();
+
+    // register the I2C device first, ENTDAA won't hand out its address
+    i3c.add_i2c_device(EEPROM_ADDR, EEPROM_LVR).unwrap();
+
+    i3c.reset_dynamic_addresses().await.unwrap();
+    let n = i3c.assign_dynamic_addresses().await.unwrap();
+    info!("i3c mixed bus example - {} I3C targets", n);
+
+    loop {
+        let mut data = [0u8; 4];
+        match i3c.i2c_write_read(EEPROM_ADDR, &[0x00], &mut data).await {
+            Ok(()) => info!("i3c mixed bus example - EEPROM: {:02X}", data),
+            Err(e) => error!("i3c mixed bus example - EEPROM read failed: {}", e),
+        }
+
+        let mut addresses = [0u8; MAX_DEVICES];
+        let mut count = 0;
+        for (slot, device) in addresses.iter_mut().zip(i3c.devices()) {
+            *slot = device.address;
+            count += 1;
+        }
+
+        for &address in &addresses[..count] {
+            let mut byte = [0u8];
+            match i3c.read(address, &mut byte).await {
+                Ok(()) => info!("i3c mixed bus example - {:02X}: {:02X}", address, byte[0]),
+                Err(e) => error!("i3c mixed bus example - {:02X} read failed: {}", address, e),
+            }
+        }
+
+        Timer::after_secs(1).await;
+    }
+}
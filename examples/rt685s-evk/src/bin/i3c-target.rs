@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+use defmt::{error, info};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::i3c::target::{Command, I3cTarget, Response};
+use embassy_imxrt::{bind_interrupts, i3c, peripherals};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+const STATIC_ADDR: u8 = 0x30;
+const BUFLEN: usize = 16;
+
+bind_interrupts!(struct Irqs {
+    I3C0 => i3c::InterruptHandler<peripherals::I3C0>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    // connect the controller to P2_29 (SCL) and P2_30 (SDA)
+    info!("i3c target example - static address {:02X}", STATIC_ADDR);
+
+    let config = i3c::target::Config {
+        static_address: Some(STATIC_ADDR),
+        vendor_id: 0x0123,
+        part_number: 0x0000_0685,
+        ..Default::default()
+    };
+    let mut target = I3cTarget::new(p.I3C0, p.PIO2_29, p.PIO2_30, Irqs, config).unwrap();
+
+    let mut buf = [0u8; BUFLEN];
+    let mut len = 0;
+
+    loop {
+        let result = match target.listen().await {
+            Ok(Command::DynamicAddressChanged(addr)) => {
+                info!("i3c target example - dynamic address {:?}", addr);
+                continue;
+            }
+            Ok(Command::Write) => {
+                let result = target.respond_to_write(&mut buf).await;
+                if let Ok(Response::Complete(n) | Response::Pending(n)) = result {
+                    len = n;
+                }
+                result
+            }
+            Ok(Command::Ccc) => {
+                let mut ccc = [0u8; BUFLEN];
+                let result = target.respond_to_write(&mut ccc).await;
+                info!("i3c target example - CCC {:02X}", ccc[0]);
+                result
+            }
+            // echo back the last private write
+            Ok(Command::Read) => target.respond_to_read(&buf[..len]).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(Response::Complete(n)) | Ok(Response::Pending(n)) => {
+                info!("i3c target example - transferred {} bytes", n);
+            }
+            Err(e) => error!("i3c target example - failed: {}", e),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_futures::join::join;
+use embassy_imxrt::clocks::ClockConfig;
+use embassy_imxrt::mrt::{self, InterruptHandler, Mrt};
+use embassy_imxrt::{bind_interrupts, peripherals};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    MRT0 => InterruptHandler<peripherals::MRT0>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    let main_clk = ClockConfig::crystal().main_clk;
+    let Mrt {
+        mut channel0,
+        mut channel1,
+        channel3,
+        ..
+    } = Mrt::new(p.MRT0, Irqs, main_clk).unwrap();
+
+    // Stall-mode busy-wait, exact to a bus clock cycle
+    let _stall = channel3.into_stall();
+    mrt::stall_us(5).unwrap();
+    info!("5 us stall done");
+
+    // The 24-bit counters run at the bus clock, which limits the interval to tens of ms
+    channel0.start_repeat(10_000).unwrap();
+    channel1.start_repeat(25_000).unwrap();
+
+    let fast = async {
+        loop {
+            channel0.tick().await;
+            info!("10 ms tick");
+        }
+    };
+
+    let slow = async {
+        loop {
+            channel1.tick().await;
+            info!("25 ms tick");
+        }
+    };
+
+    join(fast, slow).await;
+}
@@ -0,0 +1,86 @@
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::casper::Casper;
+use embassy_imxrt::hashcrypt::Hashcrypt;
+use embassy_imxrt::hashcrypt::aes::{AesKey, KeySize};
+use embassy_imxrt::puf::{ACTIVATION_CODE_LEN, KeySlot, Puf, identity, key_code_len};
+use embassy_imxrt::rng::Rng;
+use embassy_imxrt::{bind_interrupts, peripherals, rng};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+use rand::TryRngCore;
+
+bind_interrupts!(struct Irqs {
+    RNG => rng::InterruptHandler<peripherals::RNG>;
+});
+
+const KEY_LEN: usize = 32;
+
+// AES-128 ECB test vector from NIST SP 800-38A
+const AES_KEY: [u8; 16] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+const PLAINTEXT: [u8; 16] = [
+    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+];
+const CIPHERTEXT: [u8; 16] = [
+    0x3a, 0xd7, 0x7b, 0xb4, 0x0d, 0x7a, 0x36, 0x60, 0xa8, 0x9e, 0xca, 0xf3, 0x24, 0x66, 0xef, 0x97,
+];
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    defmt::info!("Initializing PUF");
+    let mut puf = Puf::new(p.PUF);
+
+    // A real application enrolls once and keeps the activation code in flash, calling start()
+    // with it after every reset
+    let mut activation_code = [0u8; ACTIVATION_CODE_LEN];
+    puf.enroll(&mut activation_code).unwrap();
+
+    // Wrap a user key, then unwrap it again
+    let key = [0x5au8; KEY_LEN];
+    let mut key_code = [0u8; key_code_len(KEY_LEN)];
+    puf.set_key(1, &key, &mut key_code).unwrap();
+
+    let mut unwrapped = [0u8; KEY_LEN];
+    let index = puf.get_key(&key_code, &mut unwrapped).unwrap();
+    defmt::assert_eq!(index, 1);
+    defmt::assert_eq!(unwrapped, key);
+
+    // Generate an intrinsic key, only its key code ever leaves the PUF
+    let mut key_code = [0u8; key_code_len(KEY_LEN)];
+    puf.generate_key(2, KEY_LEN, &mut key_code).unwrap();
+    puf.get_key(&key_code, &mut unwrapped).unwrap();
+    defmt::info!("Intrinsic key: {:02x}", unwrapped);
+
+    // Wrap an AES key at index 0, then send it straight to Hashcrypt over the secret key bus
+    let mut key_code = [0u8; key_code_len(AES_KEY.len())];
+    puf.set_key(0, &AES_KEY, &mut key_code).unwrap();
+
+    let mut rng = Rng::new(p.RNG, Irqs);
+    let mask = rng.try_next_u32().unwrap();
+    puf.get_hw_key(&key_code, KeySlot::Slot0, mask).unwrap();
+
+    let mut hashcrypt = Hashcrypt::new_blocking(p.HASHCRYPT, Default::default());
+    let mut aes = hashcrypt.new_aes(AesKey::Secret(KeySize::Bits128));
+    let mut output = [0u8; 16];
+    aes.encrypt_ecb(&PLAINTEXT, &mut output).unwrap();
+    defmt::assert_eq!(output, CIPHERTEXT);
+
+    // Provision a device identity, then derive it twice: the keypair is stable
+    let mut casper = Casper::new_blocking(p.CASPER);
+    let mut key_code = [0u8; identity::KEY_CODE_LEN];
+    puf.generate_identity(&mut key_code).unwrap();
+
+    let first = puf.derive_identity(&key_code, &mut hashcrypt, &mut casper).unwrap();
+    let second = puf.derive_identity(&key_code, &mut hashcrypt, &mut casper).unwrap();
+    defmt::assert_eq!(first.public_key(), second.public_key());
+    defmt::info!("Device identity: {:02x}", first.public_key());
+
+    defmt::info!("PUF key storage done");
+}
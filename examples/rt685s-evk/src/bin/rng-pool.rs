@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::rng::{EntropyPool, Rng};
+use embassy_imxrt::{bind_interrupts, peripherals, rng};
+use embassy_imxrt_examples as _;
+use embassy_time::Timer;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    RNG => rng::InterruptHandler<peripherals::RNG>;
+});
+
+static POOL: EntropyPool<256> = EntropyPool::new();
+
+#[embassy_executor::task]
+async fn filler(rng: Rng<'static>) {
+    POOL.run(rng).await
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    info!("Initializing RNG");
+    let rng = Rng::new(p.RNG, Irqs);
+    spawner.spawn(filler(rng).unwrap());
+
+    // Give the filler some time to top up the pool
+    Timer::after_millis(100).await;
+    info!("{} bytes available", POOL.available());
+
+    let mut nonce = [0u8; 12];
+    if POOL.try_take(&mut nonce) {
+        info!("nonce {:02x}", nonce);
+    }
+
+    let mut key = [0u8; 32];
+    POOL.take(&mut key).await;
+    info!("key {:02x}", key);
+}
@@ -4,11 +4,11 @@
 use defmt::info;
 use defmt_rtt as _;
 use embassy_executor::Spawner;
-use embassy_imxrt::rng::Rng;
+use embassy_imxrt::rng::{ChaChaRng, Rng};
 use embassy_imxrt::{bind_interrupts, peripherals, rng};
 use embassy_imxrt_examples as _;
 use panic_probe as _;
-use rand::TryRngCore;
+use rand::RngCore;
 
 bind_interrupts!(struct Irqs {
     RNG => rng::InterruptHandler<peripherals::RNG>;
@@ -29,38 +29,24 @@ async fn main(_spawner: Spawner) {
         count += 1;
     }
     info!("random bytes: {:02x} (succeeded after {} retries)", buf, count);
+    info!("health: {}", rng.health_report());
 
-    // RngCore interface
+    // RngCore interface, failed samples are retried internally
+    info!("entropy ready: {}", rng.entropy_ready());
+    let random_u32 = rng.next_u32();
+    let random_u64 = rng.next_u64();
     let mut random_bytes = [0; 16];
+    rng.fill_bytes(&mut random_bytes);
 
-    count = 0;
-    let random_u32 = loop {
-        match rng.try_next_u32() {
-            Ok(r) => break r,
-            Err(_) => {
-                count += 1;
-            }
-        }
-    };
+    info!(
+        "random_u32 {}, random_u64 {}, random_bytes {:02x}",
+        random_u32, random_u64, random_bytes
+    );
 
-    info!("random_u32 {} (succeeded after {} retries)", random_u32, count);
+    // CSPRNG seeded from the TRNG, for high throughput
+    let mut csprng = ChaChaRng::from_trng(&mut rng);
+    let mut nonce = [0u8; 12];
+    csprng.fill_bytes(&mut nonce);
 
-    count = 0;
-    let random_u64 = loop {
-        match rng.try_next_u64() {
-            Ok(r) => break r,
-            Err(_) => {
-                count += 1;
-            }
-        }
-    };
-
-    info!("random_u64 {} (succeeded after {} retries)", random_u64, count);
-
-    count = 0;
-    while rng.try_fill_bytes(&mut random_bytes).is_err() {
-        count += 1;
-    }
-
-    info!("random_bytes {} (succeeded after {} retries)", random_u64, count);
+    info!("nonce {:02x}", nonce);
 }
@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::iopctl::IopctlPin;
+use embassy_imxrt::pwm::SCTClockSource;
+use embassy_imxrt::sct::{Condition, Event, InterruptHandler, Program, Sct};
+use embassy_imxrt::{bind_interrupts, gpio, peripherals};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    SCT0 => InterruptHandler<peripherals::SCT0>;
+});
+
+// SCT0_OUT6 drives the blue LED on PIO0_26
+const LED: u8 = 6;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    p.PIO0_26
+        .set_function(gpio::Function::F3)
+        .set_pull(gpio::Pull::None)
+        .disable_input_buffer()
+        .set_slew_rate(gpio::SlewRate::Standard)
+        .set_drive_strength(gpio::DriveStrength::Normal)
+        .disable_analog_multiplex()
+        .set_drive_mode(gpio::DriveMode::PushPull)
+        .set_input_inverter(gpio::Inverter::Disabled);
+
+    // 48 MHz / 256 = 187.5 kHz counter, alternating short and long pulses every second
+    let mut program = Program::new().with_prescaler(255);
+    let period = program.add_match(187_500).unwrap();
+    let short = program.add_match(18_750).unwrap();
+    let long = program.add_match(93_750).unwrap();
+
+    program
+        .add_event(Event::new(Condition::Match(short)).in_state(0).clear_output(LED))
+        .unwrap();
+    program
+        .add_event(Event::new(Condition::Match(long)).in_state(1).clear_output(LED))
+        .unwrap();
+    let to_long = program
+        .add_event(
+            Event::new(Condition::Match(period))
+                .in_state(0)
+                .limit()
+                .set_output(LED)
+                .goto_state(1),
+        )
+        .unwrap();
+    program
+        .add_event(
+            Event::new(Condition::Match(period))
+                .in_state(1)
+                .limit()
+                .set_output(LED)
+                .goto_state(0),
+        )
+        .unwrap();
+
+    let mut sct = Sct::new(p.SCT0, Irqs, SCTClockSource::FFRO, &program);
+    sct.start();
+
+    loop {
+        sct.wait_for_event(to_long).await.unwrap();
+        info!("Long pulse starting, state = {}", sct.state());
+    }
+}
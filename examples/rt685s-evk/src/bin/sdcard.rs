@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+use defmt::{error, info};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::usdhc::{self, DataBlock, Usdhc};
+use embassy_imxrt::{bind_interrupts, peripherals};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    USDHC0 => usdhc::InterruptHandler<peripherals::USDHC0>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    info!("sdcard example - insert a card in the SD slot");
+
+    let mut sdhc = Usdhc::new_4bit(
+        p.USDHC0,
+        Irqs,
+        p.PIO1_30,
+        p.PIO1_31,
+        p.PIO2_0,
+        p.PIO2_1,
+        p.PIO2_2,
+        p.PIO2_3,
+        usdhc::Config {
+            frequency: 50_000_000,
+            ..Default::default()
+        },
+    );
+
+    if let Err(e) = sdhc.init_sd_card().await {
+        error!("sdcard example - no card: {}", e);
+        return;
+    }
+
+    if let Ok(card) = sdhc.card() {
+        info!(
+            "sdcard example - {} card of {} blocks, at {} Hz",
+            card.card_type,
+            card.block_count(),
+            sdhc.clock_frequency()
+        );
+    }
+
+    let mut block = DataBlock::new();
+    match sdhc.read_block(0, &mut block).await {
+        Ok(()) => info!("sdcard example - block 0 ends with {:02x}", block[510..]),
+        Err(e) => error!("sdcard example - read failed: {}", e),
+    }
+}
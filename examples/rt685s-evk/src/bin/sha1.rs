@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+use defmt::{info, trace};
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::hashcrypt::{Hashcrypt, hasher};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+    let mut hash = [0u8; hasher::SHA1_HASH_LEN];
+
+    info!("Initializing Hashcrypt");
+    let mut hashcrypt = Hashcrypt::new_blocking(p.HASHCRYPT, Default::default());
+
+    info!("Starting hashes");
+    // Data that fits into a single block
+    info!("Single hash block");
+    hashcrypt.new_sha1().hash(b"abc", &mut hash).unwrap();
+    defmt::assert_eq!(
+        &hash,
+        &[
+            0xa9, 0x99, 0x3e, 0x36, 0x47, 0x06, 0x81, 0x6a, 0xba, 0x3e, 0x25, 0x71, 0x78, 0x50, 0xc2, 0x6c, 0x9c, 0xd0,
+            0xd8, 0x9d
+        ]
+    );
+
+    // Data that spans two blocks
+    info!("Two hash blocks");
+    hashcrypt
+        .new_sha1()
+        .hash(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq", &mut hash)
+        .unwrap();
+    defmt::assert_eq!(
+        &hash,
+        &[
+            0x84, 0x98, 0x3e, 0x44, 0x1c, 0x3b, 0xd2, 0x6e, 0xba, 0xae, 0x4a, 0xa1, 0xf9, 0x51, 0x29, 0xe5, 0xe5, 0x46,
+            0x70, 0xf1
+        ]
+    );
+    trace!("Hashes complete");
+}
@@ -13,13 +13,21 @@ bind_interrupts!(struct Irqs {
     HASHCRYPT => hashcrypt::InterruptHandler<peripherals::HASHCRYPT>;
 });
 
+/// Word aligned data, so it can be gathered by DMA without copying
+#[repr(C, align(4))]
+struct Aligned<const N: usize>([u8; N]);
+
+static MESSAGE: Aligned<120> = Aligned(
+    *b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890!@abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ12345678",
+);
+
 #[embassy_executor::main]
 async fn main(_spawner: Spawner) {
     let p = embassy_imxrt::init(Default::default());
     let mut hash = [0u8; hasher::HASH_LEN];
 
     info!("Initializing Hashcrypt");
-    let mut hashcrypt = Hashcrypt::new_async(p.HASHCRYPT, Irqs, p.DMA0_CH30);
+    let mut hashcrypt = Hashcrypt::new_async(p.HASHCRYPT, Irqs, p.DMA0_CH30, Default::default());
 
     info!("Starting hashes");
     // Data that fits into a single block
@@ -82,5 +90,20 @@ async fn main(_spawner: Spawner) {
             0x44, 0x95, 0x1d, 0xcd, 0xfc, 0xd0, 0x89, 0x90, 0xef, 0xe2, 0xb2, 0x4d, 0xac, 0x79
         ]
     );
+
+    // The same data, spread over a header, payload and footer
+    info!("Scatter-gather hash");
+    let (header, rest) = MESSAGE.0.split_at(16);
+    let (payload, footer) = rest.split_at(48);
+    let mut hasher = hashcrypt.new_sha256();
+    hasher.update_all(&[header, payload, footer]).await.unwrap();
+    hasher.finalize(&[], &mut hash).await.unwrap();
+    defmt::assert_eq!(
+        &hash,
+        &[
+            0x1a, 0xdc, 0x94, 0xa1, 0xa4, 0x10, 0x77, 0x4a, 0x59, 0xf8, 0x60, 0xe3, 0x09, 0xf1, 0x1d, 0x62, 0x1d, 0xae,
+            0x44, 0x95, 0x1d, 0xcd, 0xfc, 0xd0, 0x89, 0x90, 0xef, 0xe2, 0xb2, 0x4d, 0xac, 0x79
+        ]
+    );
     trace!("Hashes complete");
 }
@@ -14,7 +14,7 @@ async fn main(_spawner: Spawner) {
     let mut hash = [0u8; hasher::HASH_LEN];
 
     info!("Initializing Hashcrypt");
-    let mut hashcrypt = Hashcrypt::new_blocking(p.HASHCRYPT);
+    let mut hashcrypt = Hashcrypt::new_blocking(p.HASHCRYPT, Default::default());
 
     info!("Starting hashes");
     // Data that fits into a single block
@@ -75,5 +75,31 @@ async fn main(_spawner: Spawner) {
             0x44, 0x95, 0x1d, 0xcd, 0xfc, 0xd0, 0x89, 0x90, 0xef, 0xe2, 0xb2, 0x4d, 0xac, 0x79
         ]
     );
+
+    // Interleave two hashes by suspending one of them
+    info!("Interleaved hashes");
+    let mut hasher = hashcrypt.new_sha256();
+    hasher
+        .submit_blocks(b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890!@")
+        .unwrap();
+    let context = hasher.suspend();
+
+    hashcrypt.new_sha256().hash(b"abc", &mut hash).unwrap();
+    defmt::assert_eq!(
+        &hash,
+        &[
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23, 0xb0, 0x03,
+            0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad
+        ]
+    );
+
+    hashcrypt.resume_sha256(context).finalize(&[], &mut hash).unwrap();
+    defmt::assert_eq!(
+        &hash,
+        &[
+            0x85, 0x7c, 0xce, 0x23, 0xb6, 0xba, 0x40, 0xd9, 0xa8, 0x33, 0x0d, 0x93, 0x97, 0x98, 0x1d, 0xa5, 0x8f, 0x5a,
+            0x8f, 0x41, 0x34, 0x44, 0xc7, 0xa4, 0x1c, 0x42, 0x01, 0xa1, 0x47, 0x76, 0x51, 0xef
+        ]
+    );
     trace!("Hashes complete");
 }
@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::bind_interrupts;
+use embassy_imxrt::peripherals::FLEXCOMM5;
+use embassy_imxrt::spi::{InterruptHandler, Spi};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    FLEXCOMM5 => InterruptHandler<FLEXCOMM5>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    info!("Initializing SPI, connect MOSI to MISO");
+
+    let mut spi = Spi::new_async_with_dma(
+        p.FLEXCOMM5,
+        p.PIO1_3,
+        p.PIO1_5,
+        p.PIO1_4,
+        Irqs,
+        p.DMA0_CH11,
+        p.DMA0_CH10,
+        Default::default(),
+    );
+
+    let mut rxbuf = [0x55; 2048];
+    let mut txbuf = [0; 2048];
+    for (i, b) in txbuf.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+
+    loop {
+        spi.async_transfer(&mut rxbuf, &txbuf).await.unwrap();
+        defmt::assert_eq!(rxbuf, txbuf);
+        rxbuf.fill(0x55);
+
+        spi.async_transfer_in_place(&mut rxbuf).await.unwrap();
+        defmt::assert!(rxbuf.iter().all(|b| *b == 0x55));
+
+        info!("Transferred {} bytes", txbuf.len());
+    }
+}
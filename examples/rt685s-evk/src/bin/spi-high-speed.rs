@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::bind_interrupts;
+use embassy_imxrt::peripherals::FLEXCOMM14;
+use embassy_imxrt::spi::{Config, InterruptHandler, Spi};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    FLEXCOMM14 => InterruptHandler<FLEXCOMM14>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    info!("Initializing high-speed SPI, connect MOSI to MISO");
+
+    let mut config = Config::default();
+    config.frequency = 48_000_000;
+
+    let mut spi = Spi::new_async_with_dma(
+        p.FLEXCOMM14,
+        p.PIO1_11,
+        p.PIO1_13,
+        p.PIO1_12,
+        Irqs,
+        p.DMA0_CH17,
+        p.DMA0_CH16,
+        config,
+    );
+
+    let mut rxbuf = [0; 4096];
+    let mut txbuf = [0; 4096];
+    for (i, b) in txbuf.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+
+    loop {
+        spi.async_transfer(&mut rxbuf, &txbuf).await.unwrap();
+        defmt::assert_eq!(rxbuf, txbuf);
+        rxbuf.fill(0);
+
+        info!("Transferred {} bytes at 48 MHz", txbuf.len());
+    }
+}
@@ -0,0 +1,78 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_embedded_hal::shared_bus::asynch::spi::SpiDeviceWithConfig;
+use embassy_executor::Spawner;
+use embassy_futures::join::join;
+use embassy_imxrt::bind_interrupts;
+use embassy_imxrt::gpio::{DriveMode, DriveStrength, Level, Output, SlewRate};
+use embassy_imxrt::peripherals::FLEXCOMM5;
+use embassy_imxrt::spi::{Config, InterruptHandler, MODE_3, Spi};
+use embassy_imxrt_examples as _;
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::mutex::Mutex;
+use embassy_time::Timer;
+use embedded_hal_async::spi::SpiDevice;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    FLEXCOMM5 => InterruptHandler<FLEXCOMM5>;
+});
+
+async fn poll_device(mut device: impl SpiDevice, name: &str) -> ! {
+    let mut rx = [0u8; 4];
+
+    loop {
+        device.transfer(&mut rx, &[0x9f]).await.unwrap();
+        info!("{}: {:02x}", name, rx);
+        Timer::after_millis(100).await;
+    }
+}
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    info!("Initializing shared SPI bus");
+
+    let spi = Spi::new_async_with_dma(
+        p.FLEXCOMM5,
+        p.PIO1_3,
+        p.PIO1_5,
+        p.PIO1_4,
+        Irqs,
+        p.DMA0_CH11,
+        p.DMA0_CH10,
+        Default::default(),
+    );
+    let bus: Mutex<NoopRawMutex, _> = Mutex::new(spi);
+
+    let cs_a = Output::new(
+        p.PIO1_6,
+        Level::High,
+        DriveMode::PushPull,
+        DriveStrength::Normal,
+        SlewRate::Standard,
+    );
+    let cs_b = Output::new(
+        p.PIO1_7,
+        Level::High,
+        DriveMode::PushPull,
+        DriveStrength::Normal,
+        SlewRate::Standard,
+    );
+
+    // Each device gets its own configuration, applied before each of its transactions
+    let mut slow = Config::default();
+    slow.frequency = 1_000_000;
+    let mut fast = Config::default();
+    fast.frequency = 24_000_000;
+    fast.mode = MODE_3;
+
+    let device_a = SpiDeviceWithConfig::new(&bus, cs_a, slow);
+    let device_b = SpiDeviceWithConfig::new(&bus, cs_b, fast);
+
+    join(poll_device(device_a, "A"), poll_device(device_b, "B")).await;
+}
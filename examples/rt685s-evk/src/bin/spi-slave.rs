@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::bind_interrupts;
+use embassy_imxrt::peripherals::FLEXCOMM5;
+use embassy_imxrt::spi::{InterruptHandler, SpiSlave};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    FLEXCOMM5 => InterruptHandler<FLEXCOMM5>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    info!("Initializing SPI slave, waiting for the host");
+
+    let mut spi = SpiSlave::new_async(
+        p.FLEXCOMM5,
+        p.PIO1_3,
+        p.PIO1_5,
+        p.PIO1_4,
+        p.PIO1_6,
+        Irqs,
+        p.DMA0_CH11,
+        p.DMA0_CH10,
+        Default::default(),
+    )
+    .unwrap();
+
+    let mut command = [0; 64];
+    let mut response = [0; 64];
+
+    loop {
+        // The host sends a command, then reads it back inverted in the next transaction
+        let len = spi.read(&mut command).await.unwrap();
+        info!("Received {:02x}", command[..len]);
+
+        for (r, c) in response.iter_mut().zip(&command[..len]) {
+            *r = !c;
+        }
+
+        let len = spi.write(&response[..len]).await.unwrap();
+        info!("Sent {} bytes", len);
+    }
+}
@@ -58,9 +58,10 @@ async fn main(spawner: Spawner) {
         info!("Capture timer expired, time between two capture = {} us", event_time_us);
     }
 
+    // This code is showing how to use the timer in a periodic fashion
+    countdown_timer.start_periodic(5000000).expect("Invalid period");
     loop {
-        // This code is showing how to use the timer in a periodic fashion
-        countdown_timer.wait_us(5000000).await;
+        countdown_timer.wait().await;
         info!("Primary task running");
     }
 }
@@ -0,0 +1,78 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::uart::{Async, RingBufferedUartRx, Uart, UartTx};
+use embassy_imxrt::{bind_interrupts, peripherals, uart};
+use embassy_imxrt_examples as _;
+use embassy_time::Timer;
+use panic_probe as _;
+
+const BUFLEN: usize = 256;
+const CHUNK_LEN: usize = 64;
+
+bind_interrupts!(struct Irqs {
+    FLEXCOMM4 => uart::InterruptHandler<peripherals::FLEXCOMM4>;
+});
+
+static mut RX_BUF: [u8; BUFLEN] = [0; BUFLEN];
+
+#[embassy_executor::task]
+async fn reader(mut rx: RingBufferedUartRx<'static>) {
+    let mut expected: u8 = 0;
+    let mut buf = [0; BUFLEN];
+
+    loop {
+        // Busy executor: data keeps being received in the meantime
+        Timer::after_millis(5).await;
+
+        let len = rx.read(&mut buf).await.unwrap();
+        for b in &buf[..len] {
+            defmt::assert_eq!(*b, expected);
+            expected = expected.wrapping_add(1);
+        }
+        info!("Read {} bytes", len);
+    }
+}
+
+async fn writer(mut tx: UartTx<'static, Async>) -> ! {
+    let mut data = [0u8; CHUNK_LEN];
+    let mut next: u8 = 0;
+
+    loop {
+        for b in data.iter_mut() {
+            *b = next;
+            next = next.wrapping_add(1);
+        }
+        tx.write(&data).await.unwrap();
+        Timer::after_millis(2).await;
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    info!("UART ring buffer test start, connect TX to RX");
+
+    let uart = Uart::new_async(
+        p.FLEXCOMM4,
+        p.PIO0_29,
+        p.PIO0_30,
+        Irqs,
+        p.DMA0_CH9,
+        p.DMA0_CH8,
+        Default::default(),
+    )
+    .unwrap();
+    let (tx, rx) = uart.split();
+
+    let rx = rx
+        .into_ring_buffered(unsafe { &mut *core::ptr::addr_of_mut!(RX_BUF) })
+        .unwrap();
+    spawner.spawn(reader(rx).unwrap());
+
+    writer(tx).await
+}
@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_futures::join::join;
+use embassy_imxrt::usb::{self, Driver};
+use embassy_imxrt::{bind_interrupts, peripherals};
+use embassy_imxrt_examples as _;
+use embassy_usb::Builder;
+use embassy_usb::class::cdc_acm::{CdcAcmClass, State};
+use embassy_usb::driver::EndpointError;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    USB => usb::InterruptHandler<peripherals::USBHSD>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    info!("usb serial example - connect the high-speed USB port");
+
+    let driver = Driver::new(p.USBHSD, Irqs, Default::default());
+
+    let mut config = embassy_usb::Config::new(0xc0de, 0xcafe);
+    config.manufacturer = Some("Embassy");
+    config.product = Some("USB-serial example");
+    config.serial_number = Some("12345678");
+
+    let mut config_descriptor = [0; 256];
+    let mut bos_descriptor = [0; 256];
+    let mut control_buf = [0; 64];
+    let mut state = State::new();
+
+    let mut builder = Builder::new(
+        driver,
+        config,
+        &mut config_descriptor,
+        &mut bos_descriptor,
+        &mut [],
+        &mut control_buf,
+    );
+
+    let mut class = CdcAcmClass::new(&mut builder, &mut state, 512);
+    let mut usb = builder.build();
+
+    let echo = async {
+        loop {
+            class.wait_connection().await;
+            info!("usb serial example - connected");
+            let _ = echo(&mut class).await;
+            info!("usb serial example - disconnected");
+        }
+    };
+
+    join(usb.run(), echo).await;
+}
+
+async fn echo<'d>(class: &mut CdcAcmClass<'d, Driver<'d>>) -> Result<(), EndpointError> {
+    let mut buf = [0; 512];
+    loop {
+        let n = class.read_packet(&mut buf).await?;
+        class.write_packet(&buf[..n]).await?;
+    }
+}
@@ -0,0 +1,30 @@
+#![no_std]
+#![no_main]
+
+use defmt::info;
+use defmt_rtt as _;
+use embassy_executor::Spawner;
+use embassy_imxrt::utick::{InterruptHandler, Utick};
+use embassy_imxrt::{bind_interrupts, peripherals};
+use embassy_imxrt_examples as _;
+use panic_probe as _;
+
+bind_interrupts!(struct Irqs {
+    UTICK0 => InterruptHandler<peripherals::UTICK0>;
+});
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    let mut utick = Utick::new(p.UTICK0, Irqs);
+
+    // Short delays which are too fine for the RTC, long enough not to busy-wait
+    utick.blocking_delay_us(50).unwrap();
+    info!("50 us busy-wait done");
+
+    loop {
+        utick.delay_us(250_000).await.unwrap();
+        info!("250 ms tick");
+    }
+}
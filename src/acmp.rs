@@ -0,0 +1,409 @@
+//! Analog Comparator (ACMP)
+//!
+//! Compares two analog inputs, each one an input pin or the output of the internal 8-bit DAC,
+//! which gives a programmable threshold. [`Acmp::wait_for_rising`] and [`Acmp::wait_for_falling`]
+//! sleep until the output of the comparator changes, for zero-cross and threshold detection.
+//!
+//! With [`Config::wake_on_edge`], the comparator keeps running in deep sleep and a pending edge
+//! wait wakes the chip.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_hal_internal::{Peri, PeripheralType};
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::clocks::{SleepDomain, StartEnable, WakeupGuard, enable_and_reset, enable_wakeup};
+use crate::interrupt::typelevel::{Binding, Interrupt};
+use crate::iopctl::{DriveMode, DriveStrength, Function, Inverter, IopctlPin, Pull, SlewRate};
+use crate::peripherals::ACMP;
+use crate::{interrupt, peripherals};
+
+static WAKER: AtomicWaker = AtomicWaker::new();
+
+/// Input multiplexer selection of the DAC output.
+const DAC_INPUT: u8 = 7;
+
+/// Comparator hysteresis level
+///
+/// See the data sheet for the actual hysteresis voltage of each level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Hysteresis {
+    /// Level 0, the lowest hysteresis
+    #[default]
+    Level0,
+    /// Level 1
+    Level1,
+    /// Level 2
+    Level2,
+    /// Level 3, the highest hysteresis
+    Level3,
+}
+
+impl From<Hysteresis> for u8 {
+    fn from(value: Hysteresis) -> Self {
+        match value {
+            Hysteresis::Level0 => 0,
+            Hysteresis::Level1 => 1,
+            Hysteresis::Level2 => 2,
+            Hysteresis::Level3 => 3,
+        }
+    }
+}
+
+/// DAC supply voltage reference
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DacReference {
+    /// Vin1 supplies the resistor ladder
+    Vin1,
+    /// Vin2 supplies the resistor ladder
+    Vin2,
+}
+
+/// Internal DAC configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DacConfig {
+    /// Supply voltage of the resistor ladder
+    pub reference: DacReference,
+    /// Output level, the DAC outputs `reference * (level + 1) / 256`
+    pub level: u8,
+}
+
+/// ACMP config
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct Config {
+    /// Hysteresis of the comparator
+    pub hysteresis: Hysteresis,
+    /// High speed comparison, at a higher power consumption
+    pub high_speed: bool,
+    /// Invert the comparator output
+    pub invert: bool,
+    /// Internal DAC configuration, required when the DAC is one of the inputs
+    pub dac: Option<DacConfig>,
+    /// Wake the chip from deep sleep on the edge being waited for
+    ///
+    /// The comparator and its function clock are kept powered in deep sleep. Only the edges of a
+    /// pending [`Acmp::wait_for_rising`], [`Acmp::wait_for_falling`] or [`Acmp::wait_for_any_edge`]
+    /// wake the chip, as those enable the comparator interrupt.
+    pub wake_on_edge: bool,
+}
+
+/// Comparator output edge
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Edge {
+    /// Output went from low to high
+    Rising,
+    /// Output went from high to low
+    Falling,
+}
+
+/// ACMP error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Error {
+    /// Invalid ACMP configuration
+    InvalidConfig,
+}
+
+/// Comparator input, either an input pin or the internal DAC
+pub struct Input<'d> {
+    mux: u8,
+    _lifetime: PhantomData<&'d ()>,
+}
+
+impl<'d> Input<'d> {
+    /// Compare an input pin.
+    pub fn pin(pin: Peri<'d, impl InputPin>) -> Self {
+        Self {
+            mux: pin.input(),
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Compare the output of the internal DAC, as configured in [`Config::dac`].
+    pub fn dac() -> Self {
+        Self {
+            mux: DAC_INPUT,
+            _lifetime: PhantomData,
+        }
+    }
+}
+
+/// ACMP interrupt handler
+pub struct InterruptHandler<T: Instance> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandler<T> {
+    unsafe fn on_interrupt() {
+        let reg = T::info().regs;
+
+        // Disable the edge interrupts, keeping the edge flags for the task
+        reg.c0()
+            .modify(|_, w| w.cff().cff_0().cfr().cfr_0().ief().ief_0().ier().ier_0());
+        WAKER.wake();
+    }
+}
+
+/// Start enable bit of the comparator in STARTEN0
+const ACMP_START_ENABLE: StartEnable = StartEnable::Starten0(24);
+
+/// ACMP driver
+pub struct Acmp<'d> {
+    info: Info,
+    _wakeup: Option<WakeupGuard>,
+    _lifetime: PhantomData<&'d ()>,
+}
+
+struct Info {
+    regs: crate::pac::Cmp,
+}
+
+impl<'d> Acmp<'d> {
+    /// Create ACMP driver, comparing `plus` against `minus`.
+    pub fn new<T: Instance>(
+        _acmp: Peri<'d, T>,
+        _irq: impl Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        plus: Input<'d>,
+        minus: Input<'d>,
+        config: Config,
+    ) -> Result<Self, Error> {
+        let uses_dac = plus.mux == DAC_INPUT || minus.mux == DAC_INPUT;
+        if uses_dac && config.dac.is_none() {
+            return Err(Error::InvalidConfig);
+        }
+
+        let mut inst = Self {
+            info: T::info(),
+            _wakeup: None,
+            _lifetime: PhantomData,
+        };
+
+        Self::init();
+        inst.configure(&plus, &minus, config);
+
+        // Keep the comparator running in deep sleep, and let its interrupt wake the chip
+        if config.wake_on_edge {
+            inst._wakeup = Some(enable_wakeup(
+                &[SleepDomain::Acmp, SleepDomain::Sfro],
+                ACMP_START_ENABLE,
+            ));
+        }
+
+        // Enable interrupt
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        Ok(inst)
+    }
+
+    fn init() {
+        let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
+        let sysctl0 = unsafe { crate::pac::Sysctl0::steal() };
+
+        // Power up ACMP block
+        sysctl0.pdruncfg0_clr().write(|w| w.acmp_pd().set_bit());
+
+        // Configure ACMP clock, which times the DAC and the filters
+        clkctl1.acmp0fclksel().write(|w| w.sel().sfro_clk());
+
+        // Set ACMP clock divisor
+        clkctl1.acmp0fclkdiv().modify(|_, w| w.reset().set_bit());
+        clkctl1
+            .acmp0fclkdiv()
+            .write(|w| unsafe { w.div().bits(0x0).halt().clear_bit() });
+        while clkctl1.acmp0fclkdiv().read().reqflag().bit_is_set() {}
+
+        enable_and_reset::<ACMP>();
+    }
+
+    fn configure(&mut self, plus: &Input, minus: &Input, config: Config) {
+        let regs = &self.info.regs;
+
+        if let Some(dac) = config.dac {
+            regs.c1().write(|w| unsafe {
+                w.vosel()
+                    .bits(dac.level)
+                    .vrsel()
+                    .bit(dac.reference == DacReference::Vin2)
+                    .dacen()
+                    .dacen_1()
+            });
+        }
+
+        // Select inputs
+        regs.c1()
+            .modify(|_, w| unsafe { w.psel().bits(plus.mux).msel().bits(minus.mux) });
+
+        // Both channels in continuous mode, the comparator doesn't need a sampling clock
+        regs.c3().write(|w| w.pchcten().pchcten_1().nchcten().nchcten_1());
+
+        regs.c0().write(|w| unsafe {
+            w.hystctr()
+                .bits(config.hysteresis.into())
+                .filter_cnt()
+                .filter_cnt_0() /* No filter */
+                .pmode()
+                .bit(config.high_speed)
+                .invt()
+                .bit(config.invert)
+                .cos()
+                .cos_0()
+                .ope()
+                .ope_0() /* No output pin */
+                .we()
+                .we_0()
+                .se()
+                .se_0()
+                .cff()
+                .cff_1() /* Clear stale edge flags */
+                .cfr()
+                .cfr_1()
+                .en()
+                .en_1()
+        });
+    }
+
+    /// Current comparator output, `true` when `plus` is above `minus`, unless inverted.
+    pub fn output(&self) -> bool {
+        self.info.regs.c0().read().cout().bit_is_set()
+    }
+
+    /// Change the output level of the internal DAC, moving the threshold.
+    pub fn set_dac_level(&mut self, level: u8) {
+        self.info.regs.c1().modify(|_, w| unsafe { w.vosel().bits(level) });
+    }
+
+    /// Wait for the comparator output to go from low to high.
+    pub async fn wait_for_rising(&mut self) {
+        self.wait_for_edge(true, false).await;
+    }
+
+    /// Wait for the comparator output to go from high to low.
+    pub async fn wait_for_falling(&mut self) {
+        self.wait_for_edge(false, true).await;
+    }
+
+    /// Wait for any change of the comparator output, returning which edge happened.
+    pub async fn wait_for_any_edge(&mut self) -> Edge {
+        self.wait_for_edge(true, true).await
+    }
+
+    async fn wait_for_edge(&mut self, rising: bool, falling: bool) -> Edge {
+        let regs = &self.info.regs;
+
+        // Clear stale edge flags, then enable the edge interrupts
+        regs.c0().modify(|_, w| w.cff().bit(falling).cfr().bit(rising));
+        regs.c0()
+            .modify(|_, w| w.cff().cff_0().cfr().cfr_0().ief().bit(falling).ier().bit(rising));
+
+        let edge = poll_fn(|cx| {
+            WAKER.register(cx.waker());
+
+            let c0 = regs.c0().read();
+            if rising && c0.cfr().bit_is_set() {
+                return Poll::Ready(Edge::Rising);
+            }
+            if falling && c0.cff().bit_is_set() {
+                return Poll::Ready(Edge::Falling);
+            }
+
+            // The interrupt disables itself, re-enable it in case it fired for the other edge
+            regs.c0()
+                .modify(|_, w| w.cff().cff_0().cfr().cfr_0().ief().bit(falling).ier().bit(rising));
+
+            Poll::Pending
+        })
+        .await;
+
+        // Disable the edge interrupts and clear the edge flags
+        regs.c0()
+            .modify(|_, w| w.ief().ief_0().ier().ier_0().cff().bit(falling).cfr().bit(rising));
+
+        edge
+    }
+}
+
+impl Drop for Acmp<'_> {
+    fn drop(&mut self) {
+        // Disable the comparator, without clearing the edge flags
+        self.info
+            .regs
+            .c0()
+            .modify(|_, w| w.cff().cff_0().cfr().cfr_0().ief().ief_0().ier().ier_0().en().en_0());
+
+        <ACMP as Instance>::Interrupt::disable();
+        <ACMP as Instance>::Interrupt::unpend();
+
+        // Power down ACMP block
+        let sysctl0 = unsafe { crate::pac::Sysctl0::steal() };
+        sysctl0.pdruncfg0_set().write(|w| w.acmp_pd().set_bit());
+    }
+}
+
+trait SealedInstance {
+    fn info() -> Info;
+}
+
+/// ACMP instance trait.
+#[allow(private_bounds)]
+pub trait Instance: SealedInstance + PeripheralType + 'static + Send {
+    /// Interrupt for this ACMP instance.
+    type Interrupt: interrupt::typelevel::Interrupt;
+}
+
+impl Instance for peripherals::ACMP {
+    type Interrupt = crate::interrupt::typelevel::ACMP;
+}
+
+impl SealedInstance for peripherals::ACMP {
+    fn info() -> Info {
+        // SAFETY: safe from single executor
+        Info {
+            regs: unsafe { crate::pac::Cmp::steal() },
+        }
+    }
+}
+
+pub(crate) trait SealedInputPin {
+    fn input(&self) -> u8;
+}
+
+/// A dual purpose (digital/analog) pin that can be used as an input of the comparator.
+#[allow(private_bounds)]
+pub trait InputPin: SealedInputPin + PeripheralType + Sized + 'static {}
+
+/// Macro to implement required types for dual purpose pins
+macro_rules! impl_pin {
+    ($pin:ident, $input:expr) => {
+        impl crate::acmp::SealedInputPin for crate::peripherals::$pin {
+            fn input(&self) -> u8 {
+                self.set_function(Function::F0)
+                    .set_pull(Pull::None)
+                    .disable_input_buffer()
+                    .set_slew_rate(SlewRate::Standard)
+                    .set_drive_strength(DriveStrength::Normal)
+                    .enable_analog_multiplex()
+                    .set_drive_mode(DriveMode::PushPull)
+                    .set_input_inverter(Inverter::Disabled);
+
+                $input
+            }
+        }
+
+        impl crate::acmp::InputPin for crate::peripherals::$pin {}
+    };
+}
+
+impl_pin!(PIO0_5, 1);
+impl_pin!(PIO0_12, 2);
+impl_pin!(PIO0_19, 3);
+impl_pin!(PIO0_26, 4);
@@ -6,22 +6,46 @@ use core::future::poll_fn;
 use core::marker::PhantomData;
 use core::task::Poll;
 
+use embassy_hal_internal::drop::OnDrop;
 use embassy_hal_internal::interrupt::InterruptExt;
 use embassy_hal_internal::{Peri, PeripheralType, impl_peripheral};
 use embassy_sync::waitqueue::AtomicWaker;
 
 use crate::clocks::enable_and_reset;
+use crate::dma::MAX_TRANSFER_COUNT;
+use crate::dma::transfer::{Transfer, TransferOptions, Width};
 use crate::interrupt::typelevel::Binding;
 use crate::iopctl::{DriveMode, DriveStrength, Function, Inverter, IopctlPin, Pull, SlewRate};
 use crate::pac::adc0;
-use crate::pac::adc0::cmdh::Avgs;
+use crate::pac::adc0::cmdh::{Avgs, Sts};
+use crate::pac::inputmux::dmac0_itrig_sel::Dma0ItrigSel;
 use crate::peripherals::ADC0;
-use crate::{interrupt, peripherals};
+use crate::{dma, interrupt, peripherals};
 
 static WAKER: AtomicWaker = AtomicWaker::new();
 
+/// Command buffer used by one-shot conversions.
+///
+/// This is the last command buffer, so channel configurations of up to 14 channels never use it.
+const ONE_SHOT_CMD: usize = 15;
+
+/// Trigger used by one-shot conversions.
+///
+/// This is the last trigger, so it never collides with a [`TriggerSource`].
+const ONE_SHOT_TRIGGER: usize = 15;
+
+/// Command buffer used to monitor a threshold.
+///
+/// This is the last command buffer with a compare function, so channel configurations of up to
+/// 3 channels never use it.
+const COMPARE_CMD: usize = 4;
+
+/// Trigger used by software triggered sampling of the configured channels.
+const SAMPLE_TRIGGER: usize = 0;
+
 /// ADC error
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[non_exhaustive]
 pub enum Error {
     /// Invalid ADC configuration
@@ -79,6 +103,92 @@ impl From<Average> for Avgs {
     }
 }
 
+/// ADC channel sample time, in ADC clock cycles
+///
+/// Longer sample times allow the input to settle through a higher source impedance.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SampleTime {
+    /// Minimum sample time of 3 cycles.
+    _3,
+    /// 5 cycles.
+    _5,
+    /// 7 cycles.
+    _7,
+    /// 11 cycles.
+    _11,
+    /// 19 cycles.
+    _19,
+    /// 35 cycles.
+    _35,
+    /// 67 cycles.
+    _67,
+    /// 131 cycles.
+    _131,
+}
+
+impl From<SampleTime> for Sts {
+    fn from(value: SampleTime) -> Self {
+        match value {
+            SampleTime::_3 => Sts::Sts0,
+            SampleTime::_5 => Sts::Sts1,
+            SampleTime::_7 => Sts::Sts2,
+            SampleTime::_11 => Sts::Sts3,
+            SampleTime::_19 => Sts::Sts4,
+            SampleTime::_35 => Sts::Sts5,
+            SampleTime::_67 => Sts::Sts6,
+            SampleTime::_131 => Sts::Sts7,
+        }
+    }
+}
+
+/// Gain of a calibration which leaves results unchanged.
+const UNITY_GAIN: u16 = 1 << 15;
+
+/// ADC calibration
+///
+/// Results are corrected as `(raw - offset) * gain / 32768`, saturating to the result range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Calibration {
+    /// Raw result of a zero input
+    pub offset: i16,
+    /// Gain correction, where 32768 leaves results unchanged
+    pub gain: u16,
+}
+
+impl Default for Calibration {
+    /// Calibration which leaves results unchanged.
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            gain: UNITY_GAIN,
+        }
+    }
+}
+
+impl Calibration {
+    /// Compute a calibration from the raw results of a zero input and of an input that should read as `expected`.
+    pub fn from_measurements(measured_zero: i16, measured_full_scale: i16, expected: i16) -> Result<Self, Error> {
+        let span = i32::from(measured_full_scale) - i32::from(measured_zero);
+        if span <= 0 || expected <= 0 {
+            return Err(Error::InvalidConfig);
+        }
+
+        let gain = (i32::from(expected) * i32::from(UNITY_GAIN) + span / 2) / span;
+
+        Ok(Self {
+            offset: measured_zero,
+            gain: u16::try_from(gain).map_err(|_| Error::InvalidConfig)?,
+        })
+    }
+
+    /// Correct a raw result.
+    pub fn apply(&self, raw: i16) -> i16 {
+        let corrected = ((i64::from(raw) - i64::from(self.offset)) * i64::from(self.gain)) >> 15;
+        corrected.clamp(i64::from(i16::MIN), i64::from(i16::MAX)) as i16
+    }
+}
+
 /// ADC channel config
 pub struct ChannelConfig<'d> {
     /// Positive channel to sample
@@ -87,6 +197,8 @@ pub struct ChannelConfig<'d> {
     n_channel: Option<Peri<'d, AnyInput>>,
     /// Conversion average
     average: Average,
+    /// Sample time
+    sample_time: SampleTime,
 }
 
 impl<'d> ChannelConfig<'d> {
@@ -101,10 +213,14 @@ impl<'d> ChannelConfig<'d> {
             p_channel: input.into(),
             n_channel: None,
             average,
+            sample_time: SampleTime::_131,
         }
     }
 
     /// Default configuration for differential channel sampling.
+    ///
+    /// `p_input` must be the A side and `n_input` the B side of the same channel, the result is
+    /// the signed difference between them.
     pub fn differential(p_input: Peri<'d, impl Input>, n_input: Peri<'d, impl Input>) -> Result<Self, Error> {
         Self::differential_with_average(p_input, n_input, Average::_1)
     }
@@ -116,9 +232,11 @@ impl<'d> ChannelConfig<'d> {
         average: Average,
     ) -> Result<Self, Error> {
         // Check matching positive and negative pin are passed in
-        // Do not need to check for side as there are only 1 channel for each
-        //   polarity
-        if p_input.channel().ch != n_input.channel().ch {
+        // The A side is always the positive input of a differential
+        //   conversion, so swapped pins would invert the result
+        let p = p_input.channel();
+        let n = n_input.channel();
+        if p.ch != n.ch || p.side != Side::A || n.side != Side::B {
             return Err(Error::InvalidConfig);
         }
 
@@ -126,8 +244,21 @@ impl<'d> ChannelConfig<'d> {
             p_channel: p_input.into(),
             n_channel: Some(n_input.into()),
             average,
+            sample_time: SampleTime::_131,
         })
     }
+
+    /// Change the conversion average.
+    pub fn with_average(mut self, average: Average) -> Self {
+        self.average = average;
+        self
+    }
+
+    /// Change the sample time, which defaults to the longest one.
+    pub fn with_sample_time(mut self, sample_time: SampleTime) -> Self {
+        self.sample_time = sample_time;
+        self
+    }
 }
 
 /// ADC interrupt handler
@@ -145,17 +276,44 @@ impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandl
     }
 }
 
+mod sealed {
+    /// simply seal a trait
+    pub trait Sealed {}
+}
+
+/// Driver mode.
+#[allow(private_bounds)]
+pub trait Mode: sealed::Sealed {}
+
+/// Blocking mode.
+pub struct Blocking;
+impl sealed::Sealed for Blocking {}
+impl Mode for Blocking {}
+
+/// Async mode.
+pub struct Async;
+impl sealed::Sealed for Async {}
+impl Mode for Async {}
+
 /// ADC driver
-pub struct Adc<'p, const N: usize> {
+///
+/// Results are left aligned 16-bit values, with the 3 least significant bits always zero.
+/// The LPADC of this chip has no 16-bit conversion mode, averaging is the way to reduce noise.
+/// Single ended channels convert to unsigned 12-bit results, in the range `0..=0x7ff8`.
+/// Differential channels convert to signed 13-bit results, in the range `-0x8000..=0x7ff8`,
+/// which keeps the full resolution over the range of the difference between both inputs.
+pub struct Adc<'p, const N: usize, M: Mode> {
     info: Info,
     _lifetime: PhantomData<&'p ()>,
+    _mode: PhantomData<M>,
+    calibration: Calibration,
 }
 
 struct Info {
     regs: crate::pac::Adc0,
 }
 
-impl<const N: usize> Adc<'_, N> {
+impl<const N: usize, M: Mode> Adc<'_, N, M> {
     fn init() {
         let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
         let sysctl0 = unsafe { crate::pac::Sysctl0::steal() };
@@ -226,60 +384,188 @@ impl<const N: usize> Adc<'_, N> {
         // Set up a cmd chain, one cmd per channel
         //   one points to the next, last one points to 0
         for ch in channel_config {
-            // Mapping cmd [1-15] into reg array index [0-14]
-            // Reg array index is one less than cmd
-            let cmd_index = cmd - 1;
-            let p = ch.p_channel.channel();
-            let diff = match ch.n_channel {
-                None => adc0::cmdl::Diff::Diff0,
-                Some(_) => adc0::cmdl::Diff::Diff1,
-            };
-
-            self.info.regs.cmdl(cmd_index).write(|w| {
-                w.adch()
-                    .variant(p.ch) /* Analog channel number */
-                    .absel()
-                    .variant(p.side.into()) /* A/B side select */
-                    .diff()
-                    .variant(diff) /* Differential or single-ended */
-                    .cscale()
-                    .cscale_1() /* Full scale */
-            });
-
-            self.info.regs.cmdh(cmd_index).write(|w| unsafe {
-                w.cmpen()
-                    .cmpen_0() /* Disable analog comparator */
-                    .lwi()
-                    .clear_bit() /* Disable auto channel auto increment */
-                    .sts()
-                    .sts_7()
-                    .avgs()
-                    .variant(ch.average.into())
-                    .loop_()
-                    .loop_0()
-                    .next()
-                    .bits((cmd - 1) as u8)
-            });
+            self.configure_command(cmd, ch, cmd - 1);
 
             // Shift to next cmd-channel pair
             cmd -= 1;
         }
 
         /* Set trigger configuration. */
-        self.info.regs.tctrl(0).write(|w| unsafe {
+        self.configure_trigger(SAMPLE_TRIGGER, channel_config.len(), false);
+    }
+
+    fn configure_command(&mut self, cmd: usize, ch: &ChannelConfig, next: usize) {
+        let diff = match ch.n_channel {
+            None => adc0::cmdl::Diff::Diff0,
+            Some(_) => adc0::cmdl::Diff::Diff1,
+        };
+
+        self.configure_command_inner(cmd, ch.p_channel.channel(), diff, ch.average, ch.sample_time, next);
+    }
+
+    fn configure_command_inner(
+        &mut self,
+        cmd: usize,
+        p: AdcChannel,
+        diff: adc0::cmdl::Diff,
+        average: Average,
+        sample_time: SampleTime,
+        next: usize,
+    ) {
+        // Mapping cmd [1-15] into reg array index [0-14]
+        // Reg array index is one less than cmd
+        let cmd_index = cmd - 1;
+
+        self.info.regs.cmdl(cmd_index).write(|w| {
+            w.adch()
+                .variant(p.ch) /* Analog channel number */
+                .absel()
+                .variant(p.side.into()) /* A/B side select */
+                .diff()
+                .variant(diff) /* Differential or single-ended */
+                .cscale()
+                .cscale_1() /* Full scale */
+        });
+
+        self.info.regs.cmdh(cmd_index).write(|w| unsafe {
+            w.cmpen()
+                .cmpen_0() /* Disable analog comparator */
+                .lwi()
+                .clear_bit() /* Disable auto channel auto increment */
+                .sts()
+                .variant(sample_time.into())
+                .avgs()
+                .variant(average.into())
+                .loop_()
+                .loop_0()
+                .next()
+                .bits(next as u8)
+        });
+    }
+
+    fn configure_trigger(&mut self, trigger: usize, cmd: usize, hardware: bool) {
+        self.info.regs.tctrl(trigger).write(|w| unsafe {
             w.hten()
-                .clear_bit()
+                .bit(hardware)
                 .tpri()
                 .tpri_0()
                 .tdly()
                 .bits(0)
                 .tcmd()
-                .bits(channel_config.len() as u8)
+                .bits(cmd as u8)
         });
     }
+
+    fn prepare_one_shot(&mut self, channel: &ChannelConfig) {
+        self.configure_command(ONE_SHOT_CMD, channel, 0);
+        self.prepare_one_shot_trigger();
+    }
+
+    fn prepare_one_shot_trigger(&mut self) {
+        self.configure_trigger(ONE_SHOT_TRIGGER, ONE_SHOT_CMD, false);
+
+        // Reset ADC fifo
+        self.info.regs.ctrl().modify(|_, w| w.rstfifo().rstfifo_1());
+    }
+
+    /// Set the calibration applied to every result from now on.
+    ///
+    /// The LPADC of this chip has no offset or gain calibration registers, so results are
+    /// corrected in software. Calibration values are plain data, so they can be stored in
+    /// flash and restored on every boot.
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
+
+    /// Return the calibration currently applied to results.
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    /// Measure a calibration from two known input voltages.
+    ///
+    /// `zero` must be connected to the lowest voltage the result should read as 0 and `full_scale`
+    /// to a voltage that should read as `expected`, for instance the two ends of a reference divider.
+    /// Each input is sampled with the average configured on its channel. Both inputs are converted
+    /// with the last command buffer of the ADC, so the driver must not be configured with more than
+    /// 14 channels. The new calibration is applied to every result from now on.
+    pub fn blocking_calibrate(
+        &mut self,
+        zero: &ChannelConfig,
+        full_scale: &ChannelConfig,
+        expected: i16,
+    ) -> Result<Calibration, Error> {
+        let previous = self.calibration;
+
+        // Measure raw results
+        self.calibration = Calibration::default();
+        let measured_zero = self.blocking_read(zero);
+        let measured_full_scale = self.blocking_read(full_scale);
+
+        // Keep the previous calibration if the measurements are unusable
+        let result = Calibration::from_measurements(measured_zero, measured_full_scale, expected);
+        self.calibration = result.unwrap_or(previous);
+
+        result
+    }
+
+    /// Start a sampling of the configured channels on every rising edge of a hardware trigger source.
+    ///
+    /// The results are collected with [`Adc::wait_for_sample()`] or [`Adc::wait_for_samples_dma()`],
+    /// so conversions happen at exact sample instants, no matter how late the task gets to run.
+    pub fn enable_hardware_trigger(&mut self, source: TriggerSource) {
+        self.configure_trigger(source as usize, N, true);
+    }
+
+    /// Stop sampling on the edges of a hardware trigger source.
+    pub fn disable_hardware_trigger(&mut self, source: TriggerSource) {
+        self.configure_trigger(source as usize, N, false);
+    }
+
+    /// Blocking one shot sampling of the configured channels.
+    /// The buffer must be the same size as the number of channels configured.
+    pub fn blocking_sample(&mut self, buf: &mut [i16; N]) {
+        // Reset ADC fifo
+        self.info.regs.ctrl().modify(|_, w| w.rstfifo().rstfifo_1());
+
+        // Send software trigger
+        self.info.regs.swtrig().write(|w| w.swt0().swt0_1());
+
+        // Make sure there is at least one sample from each channel
+        //   in the fifo
+        while self.info.regs.fctrl().read().fcount().bits() < buf.len() as u8 {}
+
+        for e in buf {
+            *e = self
+                .calibration
+                .apply(self.info.regs.resfifo().read().d().bits() as i16);
+        }
+    }
+
+    /// Blocking single conversion of a channel, which does not have to be part of the configured channels.
+    ///
+    /// This uses the last command buffer of the ADC,
+    /// so the driver must not be configured with more than 14 channels.
+    pub fn blocking_read(&mut self, channel: &ChannelConfig) -> i16 {
+        self.prepare_one_shot(channel);
+        self.blocking_one_shot()
+    }
+
+    fn blocking_one_shot(&mut self) -> i16 {
+        // Send software trigger
+        self.info.regs.swtrig().write(|w| w.swt15().swt15_1());
+
+        // Wait for the conversion result
+        loop {
+            let result = self.info.regs.resfifo().read();
+            if result.valid().bit_is_set() {
+                return self.calibration.apply(result.d().bits() as i16);
+            }
+        }
+    }
 }
 
-impl<'p, const N: usize> Adc<'p, N> {
+impl<'p, const N: usize> Adc<'p, N, Async> {
     /// Create ADC driver.
     pub fn new<T: Instance>(
         _adc: Peri<'p, T>,
@@ -290,6 +576,8 @@ impl<'p, const N: usize> Adc<'p, N> {
         let mut inst = Self {
             info: T::info(),
             _lifetime: PhantomData,
+            _mode: PhantomData,
+            calibration: Calibration::default(),
         };
 
         Self::init();
@@ -308,6 +596,18 @@ impl<'p, const N: usize> Adc<'p, N> {
     /// consumption remains higher if sampling is not stopped explicitly). Cancellation will
     /// also cause the sampling to be stopped.
     pub async fn sample(&mut self, buf: &mut [i16; N]) {
+        self.sample_inner(buf, true).await
+    }
+
+    /// Wait for a sampling of the configured channels started by an enabled hardware trigger source.
+    /// The buffer must be the same size as the number of channels configured.
+    ///
+    /// Results of a sampling that is already in progress are discarded.
+    pub async fn wait_for_sample(&mut self, buf: &mut [i16; N]) {
+        self.sample_inner(buf, false).await
+    }
+
+    async fn sample_inner(&mut self, buf: &mut [i16; N], software_trigger: bool) {
         // Reset ADC fifo
         self.info.regs.ctrl().modify(|_, w| w.rstfifo().rstfifo_1());
 
@@ -320,8 +620,10 @@ impl<'p, const N: usize> Adc<'p, N> {
         // Enable the watermark interrupt
         self.info.regs.ie().write(|w| w.fwmie().fwmie_1());
 
-        // Send software trigger
-        self.info.regs.swtrig().write(|w| w.swt0().swt0_1());
+        if software_trigger {
+            // Send software trigger
+            self.info.regs.swtrig().write(|w| w.swt0().swt0_1());
+        }
 
         // Wait for fifo watermark interrupt.
         poll_fn(|cx| {
@@ -338,12 +640,264 @@ impl<'p, const N: usize> Adc<'p, N> {
         .await;
 
         for e in buf {
-            *e = self.info.regs.resfifo().read().d().bits() as i16;
+            *e = self
+                .calibration
+                .apply(self.info.regs.resfifo().read().d().bits() as i16);
         }
 
         // Disable the watermark interrupt
         self.info.regs.ie().write(|w| w.fwmie().fwmie_0());
     }
+
+    /// Single conversion of a channel, which does not have to be part of the configured channels.
+    ///
+    /// The task sleeps until the conversion complete interrupt fires.
+    /// This uses the last command buffer of the ADC,
+    /// so the driver must not be configured with more than 14 channels.
+    pub async fn read(&mut self, channel: &ChannelConfig<'_>) -> i16 {
+        self.prepare_one_shot(channel);
+
+        // Interrupt as soon as the result is in the fifo
+        self.info.regs.fctrl().write(|w| unsafe { w.fwmark().bits(0) });
+
+        // Enable the watermark interrupt
+        self.info.regs.ie().write(|w| w.fwmie().fwmie_1());
+
+        // Send software trigger
+        self.info.regs.swtrig().write(|w| w.swt15().swt15_1());
+
+        // Wait for fifo watermark interrupt.
+        poll_fn(|cx| {
+            WAKER.register(cx.waker());
+
+            if self.info.regs.fctrl().read().fcount().bits() >= 1 {
+                return Poll::Ready(());
+            }
+
+            Poll::Pending
+        })
+        .await;
+
+        // Disable the watermark interrupt
+        self.info.regs.ie().write(|w| w.fwmie().fwmie_0());
+
+        self.calibration
+            .apply(self.info.regs.resfifo().read().d().bits() as i16)
+    }
+
+    /// Wait for a channel, which does not have to be part of the configured channels, to reach a threshold.
+    ///
+    /// The ADC keeps converting the channel and comparing the results against the threshold in
+    /// hardware, and the task sleeps until a result satisfies it. The first matching result is returned.
+    /// This uses the last command buffer of the ADC with a compare function,
+    /// so the driver must not be configured with more than 3 channels.
+    /// Cancellation stops the monitoring.
+    pub async fn wait_for_threshold(
+        &mut self,
+        channel: &ChannelConfig<'_>,
+        threshold: Threshold,
+    ) -> Result<i16, Error> {
+        if N >= COMPARE_CMD {
+            return Err(Error::InvalidConfig);
+        }
+
+        let (low, high) = threshold.window();
+
+        self.configure_command(COMPARE_CMD, channel, 0);
+        self.configure_trigger(ONE_SHOT_TRIGGER, COMPARE_CMD, false);
+
+        let regs = &self.info.regs;
+
+        // Only store a result once it is inside the compare window
+        regs.cv(COMPARE_CMD - 1)
+            .write(|w| unsafe { w.cvl().bits(low).cvh().bits(high) });
+        regs.cmdh(COMPARE_CMD - 1).modify(|_, w| w.cmpen().cmpen_3());
+
+        // Reset ADC fifo
+        regs.ctrl().modify(|_, w| w.rstfifo().rstfifo_1());
+
+        // Interrupt as soon as the result is in the fifo
+        regs.fctrl().write(|w| unsafe { w.fwmark().bits(0) });
+
+        let _guard = OnDrop::new(|| {
+            // Stop repeating the conversion
+            regs.cmdh(COMPARE_CMD - 1).modify(|_, w| w.cmpen().cmpen_0());
+
+            // Disable the watermark interrupt
+            regs.ie().write(|w| w.fwmie().fwmie_0());
+
+            while regs.stat().read().cmdact().bits() != 0 {}
+
+            // Reset ADC fifo
+            regs.ctrl().modify(|_, w| w.rstfifo().rstfifo_1());
+        });
+
+        // Enable the watermark interrupt
+        regs.ie().write(|w| w.fwmie().fwmie_1());
+
+        // Send software trigger
+        regs.swtrig().write(|w| w.swt15().swt15_1());
+
+        // Wait for fifo watermark interrupt.
+        poll_fn(|cx| {
+            WAKER.register(cx.waker());
+
+            if regs.fctrl().read().fcount().bits() >= 1 {
+                return Poll::Ready(());
+            }
+
+            Poll::Pending
+        })
+        .await;
+
+        Ok(self.calibration.apply(regs.resfifo().read().d().bits() as i16))
+    }
+
+    /// Continuous sampling of the configured channels, with the result fifo drained into `buf` by DMA.
+    ///
+    /// The command chain is looped until `buf` is full, so it holds the samples of every channel
+    /// interleaved in the order they were configured. The length of `buf` must be a non-zero
+    /// multiple of the number of channels configured, of at most 1024 samples.
+    /// Any DMA channel can be used, the ADC is routed to it through the DMA trigger multiplexer.
+    /// Cancellation stops the sampling.
+    pub async fn sample_dma(&mut self, dma: Peri<'_, impl dma::Instance>, buf: &mut [i16]) -> Result<(), Error> {
+        self.sample_dma_inner(dma, buf, true).await
+    }
+
+    /// Sampling of the configured channels started by an enabled hardware trigger source, with the
+    /// result fifo drained into `buf` by DMA.
+    ///
+    /// Every edge of the trigger source samples all channels once, so `buf` holds the samples of
+    /// every channel interleaved in the order they were configured, once per trigger. The length of
+    /// `buf` must be a non-zero multiple of the number of channels configured, of at most 1024 samples.
+    /// Any DMA channel can be used, the ADC is routed to it through the DMA trigger multiplexer.
+    pub async fn wait_for_samples_dma(
+        &mut self,
+        dma: Peri<'_, impl dma::Instance>,
+        buf: &mut [i16],
+    ) -> Result<(), Error> {
+        self.sample_dma_inner(dma, buf, false).await
+    }
+
+    async fn sample_dma_inner(
+        &mut self,
+        dma: Peri<'_, impl dma::Instance>,
+        buf: &mut [i16],
+        continuous: bool,
+    ) -> Result<(), Error> {
+        if buf.is_empty() || !buf.len().is_multiple_of(N) || buf.len() > MAX_TRANSFER_COUNT {
+            return Err(Error::InvalidConfig);
+        }
+
+        let channel = dma::Dma::reserve_channel(dma).ok_or(Error::InvalidConfig)?;
+        let regs = &self.info.regs;
+
+        // Reset ADC fifo
+        regs.ctrl().modify(|_, w| w.rstfifo().rstfifo_1());
+
+        // Request a DMA transfer as soon as a result is in the fifo
+        regs.fctrl().write(|w| unsafe { w.fwmark().bits(0) });
+
+        let options = TransferOptions {
+            width: Width::Bit16,
+            ..Default::default()
+        };
+
+        // The data result is the lower half of the result fifo register, a 16-bit read of it still
+        // pops the whole entry
+        // SAFETY: `buf` outlives the transfer, which is aborted when dropped
+        let transfer = unsafe {
+            Transfer::new_read_triggered(
+                &channel,
+                Dma0ItrigSel::AdcDmac,
+                regs.resfifo().as_ptr() as *const u8,
+                buf.as_mut_ptr() as *mut u8,
+                core::mem::size_of_val(buf),
+                options,
+            )
+        }
+        .map_err(|_| Error::InvalidConfig)?;
+
+        let _guard = OnDrop::new(|| {
+            // Open the command chain again, the conversions stop after the last command
+            regs.cmdh(0).modify(|_, w| unsafe { w.next().bits(0) });
+
+            // Disable the watermark DMA request
+            regs.de().write(|w| w.fwmde().fwmde_0());
+
+            while regs.stat().read().cmdact().bits() != 0 {}
+
+            // Reset ADC fifo
+            regs.ctrl().modify(|_, w| w.rstfifo().rstfifo_1());
+        });
+
+        if continuous {
+            // Close the command chain into a loop, the last command is always the first buffer
+            regs.cmdh(0).modify(|_, w| unsafe { w.next().bits(N as u8) });
+        }
+
+        // Enable the watermark DMA request
+        regs.de().write(|w| w.fwmde().fwmde_1());
+
+        if continuous {
+            // Send software trigger
+            regs.swtrig().write(|w| w.swt0().swt0_1());
+        }
+
+        transfer.await;
+
+        for e in buf {
+            *e = self.calibration.apply(*e);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'p, const N: usize> Adc<'p, N, Blocking> {
+    /// Create ADC driver for blocking conversions, without interrupt.
+    ///
+    /// The ADC is powered up and configured before returning.
+    /// The LPADC of this chip has no offset calibration function,
+    /// so no calibration is needed before the first conversion.
+    pub fn new_blocking<T: Instance>(_adc: Peri<'p, T>, config: Config, channel_config: [ChannelConfig; N]) -> Self {
+        let mut inst = Self {
+            info: T::info(),
+            _lifetime: PhantomData,
+            _mode: PhantomData,
+            calibration: Calibration::default(),
+        };
+
+        Self::init();
+        inst.configure_adc(config);
+        inst.configure_channels(&channel_config);
+
+        inst
+    }
+}
+
+impl<const N: usize, M: Mode, P: Input + embedded_hal_02::adc::Channel<ADC0>>
+    embedded_hal_02::adc::OneShot<ADC0, u16, P> for Adc<'_, N, M>
+{
+    type Error = Error;
+
+    /// Single ended single conversion of an input pin, using the last command buffer of the ADC,
+    /// so the driver must not be configured with more than 14 channels.
+    ///
+    /// Negative results after calibration read as 0.
+    fn read(&mut self, pin: &mut P) -> nb::Result<u16, Self::Error> {
+        self.configure_command_inner(
+            ONE_SHOT_CMD,
+            pin.channel(),
+            adc0::cmdl::Diff::Diff0,
+            Average::_1,
+            SampleTime::_131,
+            0,
+        );
+        self.prepare_one_shot_trigger();
+
+        Ok(self.blocking_one_shot().max(0) as u16)
+    }
 }
 
 trait SealedInstance {
@@ -391,6 +945,62 @@ impl From<Reference> for adc0::cfg::Refsel {
     }
 }
 
+/// ADC compare threshold
+///
+/// Thresholds are raw results, before calibration, in the left aligned format of the results
+/// of single ended channels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Threshold {
+    /// Result at or above the value, e.g. for over-current detection
+    Above(u16),
+    /// Result at or below the value, e.g. for battery-low detection
+    Below(u16),
+    /// Result inside the inclusive range
+    Between(u16, u16),
+}
+
+impl Threshold {
+    /// Compare window, a result is stored when it is inside of it.
+    fn window(self) -> (u16, u16) {
+        match self {
+            Threshold::Above(value) => (value, u16::MAX),
+            Threshold::Below(value) => (0, value),
+            Threshold::Between(low, high) => (low.min(high), low.max(high)),
+        }
+    }
+}
+
+/// ADC hardware trigger source
+///
+/// Conversions start on the rising edge of the source. A CTIMER match output has to toggle, or
+/// an SCTimer output has to be driven, for instance as a PWM channel, at the sample rate.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum TriggerSource {
+    /// Pin interrupt 0
+    PinInt0 = 0,
+    /// Pin interrupt 1
+    PinInt1 = 1,
+    /// SCTimer output 4
+    Sct0Out4 = 2,
+    /// SCTimer output 5
+    Sct0Out5 = 3,
+    /// SCTimer output 9
+    Sct0Out9 = 4,
+    /// CTIMER0 match 3
+    Ctimer0Mat3 = 5,
+    /// CTIMER1 match 3
+    Ctimer1Mat3 = 6,
+    /// CTIMER2 match 3
+    Ctimer2Mat3 = 7,
+    /// CTIMER3 match 3
+    Ctimer3Mat3 = 8,
+    /// CTIMER4 match 3
+    Ctimer4Mat3 = 9,
+}
+
 /// ADC channel side
 #[derive(Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -479,6 +1089,17 @@ macro_rules! impl_pin {
 
         impl crate::adc::Input for $pin {}
 
+        impl embedded_hal_02::adc::Channel<crate::peripherals::ADC0> for $pin {
+            type ID = crate::adc::AdcChannel;
+
+            fn channel() -> Self::ID {
+                crate::adc::AdcChannel {
+                    ch: crate::pac::adc0::cmdl::Adch::$ch,
+                    side: crate::adc::Side::$side
+                }
+            }
+        }
+
         impl From<$pin> for crate::adc::AnyInput {
             fn from(val: $pin) -> Self {
                 crate::adc::Input::degrade_adc(val)
@@ -499,3 +1120,47 @@ impl_pin!(PIO1_8, Adch4, A);
 impl_pin!(PIO1_9, Adch4, B);
 impl_pin!(PIO3_23, Adch5, A);
 impl_pin!(PIO3_24, Adch5, B);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold_window() {
+        assert!(Threshold::Above(0x4000).window() == (0x4000, u16::MAX));
+        assert!(Threshold::Below(0x1000).window() == (0, 0x1000));
+        assert!(Threshold::Between(0x1000, 0x2000).window() == (0x1000, 0x2000));
+        assert!(Threshold::Between(0x2000, 0x1000).window() == (0x1000, 0x2000));
+    }
+
+    #[test]
+    fn test_default_calibration_is_identity() {
+        let calibration = Calibration::default();
+        assert!(calibration.apply(0) == 0);
+        assert!(calibration.apply(0x1234) == 0x1234);
+        assert!(calibration.apply(i16::MAX) == i16::MAX);
+        assert!(calibration.apply(i16::MIN) == i16::MIN);
+    }
+
+    #[test]
+    fn test_calibration_from_measurements() {
+        let calibration = Calibration::from_measurements(0x40, 0x7000, 0x7ff0);
+        assert!(matches!(calibration, Ok(Calibration { offset: 0x40, .. })));
+
+        if let Ok(calibration) = calibration {
+            assert!(calibration.apply(0x40) == 0);
+            assert!(calibration.apply(0x7000) == 0x7ff0);
+            assert!(calibration.apply(0) < 0);
+            assert!(calibration.apply(i16::MAX) == i16::MAX);
+        }
+    }
+
+    #[test]
+    fn test_calibration_rejects_bad_measurements() {
+        assert!(Calibration::from_measurements(0x100, 0x100, 0x7ff0).is_err());
+        assert!(Calibration::from_measurements(0x100, 0x80, 0x7ff0).is_err());
+        assert!(Calibration::from_measurements(0, 0x1000, 0).is_err());
+        // A gain above 2 does not fit
+        assert!(Calibration::from_measurements(0, 0x1000, 0x3000).is_err());
+    }
+}
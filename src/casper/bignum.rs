@@ -0,0 +1,247 @@
+//! Software helpers for big numbers stored as little-endian 32-bit words
+//!
+//! Everything that depends on secret data runs in constant time, branching on lengths only.
+
+use core::cmp::Ordering;
+
+use super::Error;
+
+/// Load a big-endian byte string into little-endian words, zero extending it
+pub(super) fn from_be_bytes(bytes: &[u8], words: &mut [u32]) -> Result<(), Error> {
+    if bytes.len() > words.len() * 4 {
+        return Err(Error::UnsupportedConfiguration);
+    }
+
+    words.fill(0);
+    for (word, chunk) in words.iter_mut().zip(bytes.rchunks(4)) {
+        *word = chunk.iter().fold(0, |acc, b| (acc << 8) | u32::from(*b));
+    }
+    Ok(())
+}
+
+/// Store little-endian words into a big-endian byte string, truncating any higher words
+pub(super) fn to_be_bytes(words: &[u32], bytes: &mut [u8]) -> Result<(), Error> {
+    if bytes.len() > words.len() * 4 {
+        return Err(Error::UnsupportedConfiguration);
+    }
+
+    for (i, byte) in bytes.iter_mut().rev().enumerate() {
+        let word = words.get(i / 4).ok_or(Error::UnsupportedConfiguration)?;
+        *byte = (word >> (8 * (i % 4))) as u8;
+    }
+    Ok(())
+}
+
+/// Load a little-endian byte string into little-endian words, zero extending it
+pub(super) fn from_le_bytes(bytes: &[u8], words: &mut [u32]) -> Result<(), Error> {
+    if bytes.len() > words.len() * 4 {
+        return Err(Error::UnsupportedConfiguration);
+    }
+
+    words.fill(0);
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks(4)) {
+        *word = chunk.iter().rev().fold(0, |acc, b| (acc << 8) | u32::from(*b));
+    }
+    Ok(())
+}
+
+/// Store little-endian words into a little-endian byte string, truncating any higher words
+pub(super) fn to_le_bytes(words: &[u32], bytes: &mut [u8]) -> Result<(), Error> {
+    if bytes.len() > words.len() * 4 {
+        return Err(Error::UnsupportedConfiguration);
+    }
+
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        let word = words.get(i / 4).ok_or(Error::UnsupportedConfiguration)?;
+        *byte = (word >> (8 * (i % 4))) as u8;
+    }
+    Ok(())
+}
+
+/// Compare two numbers of the same length
+pub(super) fn compare(a: &[u32], b: &[u32]) -> Ordering {
+    // Subtracting gives the borrow (a < b), or-ing the difference tells equality apart
+    let mut borrow = 0u64;
+    let mut diff = 0u32;
+    for (x, y) in a.iter().zip(b) {
+        let d = u64::from(*x).wrapping_sub(u64::from(*y)).wrapping_sub(borrow);
+        diff |= d as u32;
+        borrow = d >> 63;
+    }
+
+    match (borrow, diff) {
+        (1, _) => Ordering::Less,
+        (_, 0) => Ordering::Equal,
+        _ => Ordering::Greater,
+    }
+}
+
+/// `a -= b` if `condition` is 1, leaving `a` untouched if it is 0
+pub(super) fn conditional_sub(a: &mut [u32], b: &[u32], condition: u32) {
+    let mask = 0u32.wrapping_sub(condition);
+    let mut borrow = 0u64;
+    for (x, y) in a.iter_mut().zip(b) {
+        let d = u64::from(*x).wrapping_sub(u64::from(*y & mask)).wrapping_sub(borrow);
+        *x = d as u32;
+        borrow = d >> 63;
+    }
+}
+
+/// `a += b` if `condition` is 1, leaving `a` untouched if it is 0, returning the carry
+pub(super) fn conditional_add(a: &mut [u32], b: &[u32], condition: u32) -> u32 {
+    let mask = 0u32.wrapping_sub(condition);
+    let mut carry = 0u64;
+    for (x, y) in a.iter_mut().zip(b) {
+        let s = u64::from(*x) + u64::from(*y & mask) + carry;
+        *x = s as u32;
+        carry = s >> 32;
+    }
+    carry as u32
+}
+
+/// `a = a + b mod n`, for `a` and `b` smaller than `n`
+pub(super) fn mod_add(a: &mut [u32], b: &[u32], n: &[u32]) {
+    let carry = conditional_add(a, b, 1);
+    let reduce = carry | u32::from(compare(a, n) != Ordering::Less);
+    conditional_sub(a, n, reduce);
+}
+
+/// `a = a - b mod n`, for `a` and `b` smaller than `n`
+pub(super) fn mod_sub(a: &mut [u32], b: &[u32], n: &[u32]) {
+    let borrow = u32::from(compare(a, b) == Ordering::Less);
+    conditional_sub(a, b, 1);
+    conditional_add(a, n, borrow);
+}
+
+/// Copy `b` into `a` if `condition` is 1, leaving `a` untouched if it is 0
+pub(super) fn conditional_copy(a: &mut [u32], b: &[u32], condition: u32) {
+    let mask = 0u32.wrapping_sub(condition);
+    for (x, y) in a.iter_mut().zip(b) {
+        *x ^= (*x ^ *y) & mask;
+    }
+}
+
+/// 1 if the number is zero, 0 otherwise
+pub(super) fn is_zero(a: &[u32]) -> u32 {
+    let acc = a.iter().fold(0, |acc, x| acc | x);
+    // The top bit of `acc - 1` is only set by the borrow when `acc` is zero
+    (u64::from(acc).wrapping_sub(1) >> 63) as u32
+}
+
+/// Swap `a` and `b` if `condition` is 1, leaving them untouched if it is 0
+pub(super) fn conditional_swap(a: &mut [u32], b: &mut [u32], condition: u32) {
+    let mask = 0u32.wrapping_sub(condition);
+    for (x, y) in a.iter_mut().zip(b.iter_mut()) {
+        let t = (*x ^ *y) & mask;
+        *x ^= t;
+        *y ^= t;
+    }
+}
+
+/// Return bit `i` of a number, counting from the least significant bit
+pub(super) fn bit(a: &[u32], i: usize) -> u32 {
+    a.get(i / 32).map_or(0, |word| (word >> (i % 32)) & 1)
+}
+
+/// `-n^-1 mod 2^64`, for the Montgomery reduction of an odd modulus
+pub(super) fn montgomery_inverse(n0: u64) -> u64 {
+    // Newton's iteration doubles the number of correct bits on each step, starting from 1 bit
+    let mut inverse: u64 = 1;
+    for _ in 0..6 {
+        inverse = inverse.wrapping_mul(2u64.wrapping_sub(n0.wrapping_mul(inverse)));
+    }
+    inverse.wrapping_neg()
+}
+
+/// `r = a mod n` for `a` of any length, `n` must leave the top bit of its last word clear
+pub(super) fn modulo(a: &[u32], n: &[u32], r: &mut [u32]) {
+    r.fill(0);
+
+    // Shift `a` in one bit at a time, reducing after every step
+    for i in (0..32 * a.len()).rev() {
+        let mut carry = bit(a, i);
+        for word in r.iter_mut() {
+            let next = *word >> 31;
+            *word = (*word << 1) | carry;
+            carry = next;
+        }
+
+        let reduce = u32::from(compare(r, n) != Ordering::Less);
+        conditional_sub(r, n, reduce);
+    }
+}
+
+/// `R^2 mod n` where `R = 2^(32 * n.len())`, for converting numbers into the Montgomery domain
+pub(super) fn montgomery_r_squared(n: &[u32], rr: &mut [u32]) {
+    rr.fill(0);
+    if let Some(first) = rr.first_mut() {
+        *first = 1;
+    }
+
+    // Doubling `2 * bits` times starting from 1 gives R^2, reducing after every step
+    for _ in 0..2 * 32 * n.len() {
+        let mut carry = 0;
+        for word in rr.iter_mut() {
+            let next = *word >> 31;
+            *word = (*word << 1) | carry;
+            carry = next;
+        }
+
+        let reduce = carry | u32::from(compare(rr, n) != Ordering::Less);
+        conditional_sub(rr, n, reduce);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_conversion_round_trip() {
+        let bytes = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06];
+        let mut words = [0u32; 2];
+        assert_eq!(from_be_bytes(&bytes, &mut words), Ok(()));
+        assert_eq!(words, [0x0304_0506, 0x0102]);
+
+        let mut out = [0u8; 6];
+        assert_eq!(to_be_bytes(&words, &mut out), Ok(()));
+        assert_eq!(out, bytes);
+    }
+
+    #[test]
+    fn montgomery_constants() {
+        // 2^64 - 59 is prime
+        let n0: u64 = 0xffff_ffff_ffff_ffc5;
+        assert_eq!(n0.wrapping_mul(montgomery_inverse(n0)), u64::MAX);
+
+        let n = [n0 as u32, (n0 >> 32) as u32];
+        let mut rr = [0u32; 2];
+        montgomery_r_squared(&n, &mut rr);
+
+        let r = (1u128 << 64) % u128::from(n0);
+        let expected = (r * r) % u128::from(n0);
+        assert_eq!(u64::from(rr[0]) | (u64::from(rr[1]) << 32), expected as u64);
+    }
+
+    #[test]
+    fn modular_add_sub() {
+        let n = [0xffff_fffb, 0xffff_ffff];
+        let mut a = [0xffff_fff0, 0xffff_ffff];
+        mod_add(&mut a, &[0x10, 0], &n);
+        assert_eq!(a, [5, 0]);
+
+        mod_sub(&mut a, &[0x10, 0], &n);
+        assert_eq!(a, [0xffff_fff0, 0xffff_ffff]);
+
+        let mut r = [0u32; 2];
+        modulo(
+            &[0x1234_5678, 0x9abc_def0, 0x0fed_cba9],
+            &[0xffff_fffb, 0x7fff_ffff],
+            &mut r,
+        );
+        assert_eq!(r, [0xb17e_4b17, 0x1abc_def0]);
+
+        assert_eq!(is_zero(&[0, 0]), 1);
+        assert_eq!(is_zero(&[0, 0x8000_0000]), 0);
+    }
+}
@@ -0,0 +1,394 @@
+//! X25519 key exchange and Ed25519 signature verification
+//!
+//! Keys, coordinates and scalars use the little-endian encodings of RFC 7748 and RFC 8032. Field
+//! multiplications run on CASPER, the SHA-512 needed by Ed25519 is computed in software.
+use core::cmp::Ordering;
+
+use embassy_futures::block_on;
+
+use super::field::{BITS, Element, Field, LEN, ONE, WORDS, decode};
+use super::sha512::{self, Sha512};
+use super::{Async, Blocking, Casper, Error, Multiplier, bignum};
+
+/// Length of a key, a scalar or a coordinate
+pub const KEY_LEN: usize = LEN;
+
+/// Length of an Ed25519 signature, R followed by S
+pub const SIGNATURE_LEN: usize = 2 * KEY_LEN;
+
+/// u-coordinate of the X25519 base point
+pub const BASE_POINT: [u8; KEY_LEN] = [
+    9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// `2^255 - 19`
+const P: [u8; LEN] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xed,
+];
+/// Order of the base point, `2^252 + 27742317777372353535851937790883648493`
+const L: [u8; LEN] = [
+    0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x14, 0xde, 0xf9,
+    0xde, 0xa2, 0xf7, 0x9c, 0xd6, 0x58, 0x12, 0x63, 0x1a, 0x5c, 0xf5, 0xd3, 0xed,
+];
+/// Edwards curve constant, `-121665 / 121666`
+const D: [u8; LEN] = [
+    0x52, 0x03, 0x6c, 0xee, 0x2b, 0x6f, 0xfe, 0x73, 0x8c, 0xc7, 0x40, 0x79, 0x77, 0x79, 0xe8, 0x98, 0x00, 0x70, 0x0a,
+    0x4d, 0x41, 0x41, 0xd8, 0xab, 0x75, 0xeb, 0x4d, 0xca, 0x13, 0x59, 0x78, 0xa3,
+];
+/// A square root of -1
+const SQRT_M1: [u8; LEN] = [
+    0x2b, 0x83, 0x24, 0x80, 0x4f, 0xc1, 0xdf, 0x0b, 0x2b, 0x4d, 0x00, 0x99, 0x3d, 0xfb, 0xd7, 0xa7, 0x2f, 0x43, 0x18,
+    0x06, 0xad, 0x2f, 0xe4, 0x78, 0xc4, 0xee, 0x1b, 0x27, 0x4a, 0x0e, 0xa0, 0xb0,
+];
+/// `(p - 5) / 8`, for square roots
+const SQRT_EXPONENT: [u8; LEN] = [
+    0x0f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfd,
+];
+/// Coordinates of the Ed25519 base point
+const BX: [u8; LEN] = [
+    0x21, 0x69, 0x36, 0xd3, 0xcd, 0x6e, 0x53, 0xfe, 0xc0, 0xa4, 0xe2, 0x31, 0xfd, 0xd6, 0xdc, 0x5c, 0x69, 0x2c, 0xc7,
+    0x60, 0x95, 0x25, 0xa7, 0xb2, 0xc9, 0x56, 0x2d, 0x60, 0x8f, 0x25, 0xd5, 0x1a,
+];
+const BY: [u8; LEN] = [
+    0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+    0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x58,
+];
+
+/// `(A - 2) / 4` for the Montgomery curve
+const A24: Element = [121665, 0, 0, 0, 0, 0, 0, 0];
+
+fn decode_le(bytes: &[u8]) -> Result<Element, Error> {
+    let mut element = [0u32; WORDS];
+    bignum::from_le_bytes(bytes, &mut element)?;
+    Ok(element)
+}
+
+/// X25519 function from RFC 7748 5, with a Montgomery ladder which runs in constant time
+async fn x25519<C: Multiplier>(
+    casper: &mut C,
+    scalar: &[u8; KEY_LEN],
+    u: &[u8; KEY_LEN],
+    result: &mut [u8; KEY_LEN],
+) -> Result<(), Error> {
+    let f = Field::new(&P)?;
+
+    let mut k = *scalar;
+    k[0] &= 0xf8;
+    k[31] &= 0x7f;
+    k[31] |= 0x40;
+    let k = decode_le(&k)?;
+
+    // The top bit is ignored, and non-canonical values are accepted and reduced
+    let mut u = *u;
+    u[31] &= 0x7f;
+    let mut u = decode_le(&u)?;
+    f.reduce(&mut u)?;
+
+    let x1 = f.to_montgomery(casper, &u).await?;
+    let a24 = f.to_montgomery(casper, &A24).await?;
+    let mut x2 = f.to_montgomery(casper, &ONE).await?;
+    let mut z2 = [0u32; WORDS];
+    let mut x3 = x1;
+    let mut z3 = x2;
+    let mut swap = 0;
+
+    for t in (0..BITS - 1).rev() {
+        let bit = bignum::bit(&k, t);
+        swap ^= bit;
+        bignum::conditional_swap(&mut x2, &mut x3, swap);
+        bignum::conditional_swap(&mut z2, &mut z3, swap);
+        swap = bit;
+
+        let a = f.add(&x2, &z2)?;
+        let aa = f.mul(casper, &a, &a).await?;
+        let b = f.sub(&x2, &z2)?;
+        let bb = f.mul(casper, &b, &b).await?;
+        let e = f.sub(&aa, &bb)?;
+        let c = f.add(&x3, &z3)?;
+        let d = f.sub(&x3, &z3)?;
+        let da = f.mul(casper, &d, &a).await?;
+        let cb = f.mul(casper, &c, &b).await?;
+
+        let sum = f.add(&da, &cb)?;
+        x3 = f.mul(casper, &sum, &sum).await?;
+        let diff = f.sub(&da, &cb)?;
+        let diff2 = f.mul(casper, &diff, &diff).await?;
+        z3 = f.mul(casper, &x1, &diff2).await?;
+        x2 = f.mul(casper, &aa, &bb).await?;
+        let a24e = f.mul(casper, &a24, &e).await?;
+        z2 = f.mul(casper, &e, &f.add(&aa, &a24e)?).await?;
+    }
+    bignum::conditional_swap(&mut x2, &mut x3, swap);
+    bignum::conditional_swap(&mut z2, &mut z3, swap);
+
+    let z_inv = f.invert(casper, &z2).await?;
+    let x = f.mul(casper, &x2, &z_inv).await?;
+    let x = f.to_normal(casper, &x).await?;
+
+    // Low order points give an all-zero shared secret
+    if bignum::is_zero(&x) == 1 {
+        return Err(Error::InvalidKey);
+    }
+    bignum::to_le_bytes(&x, result)
+}
+
+/// A point of the twisted Edwards curve in extended coordinates, `x = X / Z`, `y = Y / Z` and
+/// `x * y = T / Z`
+#[derive(Copy, Clone)]
+struct Point {
+    x: Element,
+    y: Element,
+    z: Element,
+    t: Element,
+}
+
+/// The Ed25519 curve, with its constants in the Montgomery domain
+struct Edwards {
+    p: Field,
+    one: Element,
+    d: Element,
+    d2: Element,
+    sqrt_m1: Element,
+}
+
+impl Edwards {
+    async fn new<C: Multiplier>(casper: &mut C) -> Result<Self, Error> {
+        let p = Field::new(&P)?;
+        let one = p.to_montgomery(casper, &ONE).await?;
+        let d = p.to_montgomery(casper, &decode(&D)?).await?;
+        let d2 = p.add(&d, &d)?;
+        let sqrt_m1 = p.to_montgomery(casper, &decode(&SQRT_M1)?).await?;
+
+        Ok(Self { p, one, d, d2, sqrt_m1 })
+    }
+
+    fn identity(&self) -> Point {
+        Point {
+            x: [0; WORDS],
+            y: self.one,
+            z: self.one,
+            t: [0; WORDS],
+        }
+    }
+
+    async fn base_point<C: Multiplier>(&self, casper: &mut C) -> Result<Point, Error> {
+        let f = &self.p;
+        let x = f.to_montgomery(casper, &decode(&BX)?).await?;
+        let y = f.to_montgomery(casper, &decode(&BY)?).await?;
+        let t = f.mul(casper, &x, &y).await?;
+        Ok(Point { x, y, z: self.one, t })
+    }
+
+    /// Decode a point following RFC 8032 5.1.3, `Error::InvalidKey` if it is not on the curve
+    async fn decompress<C: Multiplier>(&self, casper: &mut C, bytes: &[u8]) -> Result<Point, Error> {
+        let f = &self.p;
+        let mut encoded = <[u8; KEY_LEN]>::try_from(bytes).map_err(|_| Error::UnsupportedConfiguration)?;
+        let sign = u32::from(encoded[31] >> 7);
+        encoded[31] &= 0x7f;
+
+        let y = decode_le(&encoded)?;
+        if bignum::compare(&y, f.modulus()?) != Ordering::Less {
+            return Err(Error::InvalidKey);
+        }
+        let y = f.to_montgomery(casper, &y).await?;
+
+        // x^2 = u / v, with u = y^2 - 1 and v = d * y^2 + 1
+        let y2 = f.mul(casper, &y, &y).await?;
+        let u = f.sub(&y2, &self.one)?;
+        let dy2 = f.mul(casper, &self.d, &y2).await?;
+        let v = f.add(&dy2, &self.one)?;
+
+        // Candidate root x = u * v^3 * (u * v^7)^((p - 5) / 8)
+        let v2 = f.mul(casper, &v, &v).await?;
+        let v3 = f.mul(casper, &v2, &v).await?;
+        let v6 = f.mul(casper, &v3, &v3).await?;
+        let v7 = f.mul(casper, &v6, &v).await?;
+        let uv7 = f.mul(casper, &u, &v7).await?;
+        let root = f.pow(casper, &uv7, &decode(&SQRT_EXPONENT)?).await?;
+        let uv3 = f.mul(casper, &u, &v3).await?;
+        let mut x = f.mul(casper, &uv3, &root).await?;
+
+        let x2 = f.mul(casper, &x, &x).await?;
+        let vx2 = f.mul(casper, &v, &x2).await?;
+        if vx2 != u {
+            if vx2 != f.sub(&[0; WORDS], &u)? {
+                return Err(Error::InvalidKey);
+            }
+            x = f.mul(casper, &x, &self.sqrt_m1).await?;
+        }
+
+        let x_normal = f.to_normal(casper, &x).await?;
+        if bignum::is_zero(&x_normal) == 1 && sign == 1 {
+            return Err(Error::InvalidKey);
+        }
+        if bignum::bit(&x_normal, 0) != sign {
+            x = f.sub(&[0; WORDS], &x)?;
+        }
+
+        let t = f.mul(casper, &x, &y).await?;
+        Ok(Point { x, y, z: self.one, t })
+    }
+
+    /// `a + b`, using the complete formulas of RFC 8032 5.1.4, which also double
+    async fn add<C: Multiplier>(&self, casper: &mut C, a: &Point, b: &Point) -> Result<Point, Error> {
+        let f = &self.p;
+        let y1_x1 = f.sub(&a.y, &a.x)?;
+        let y2_x2 = f.sub(&b.y, &b.x)?;
+        let pa = f.mul(casper, &y1_x1, &y2_x2).await?;
+        let y1x1 = f.add(&a.y, &a.x)?;
+        let y2x2 = f.add(&b.y, &b.x)?;
+        let pb = f.mul(casper, &y1x1, &y2x2).await?;
+        let t1d2 = f.mul(casper, &a.t, &self.d2).await?;
+        let pc = f.mul(casper, &t1d2, &b.t).await?;
+        let z1z2 = f.mul(casper, &a.z, &b.z).await?;
+        let pd = f.add(&z1z2, &z1z2)?;
+
+        let e = f.sub(&pb, &pa)?;
+        let ff = f.sub(&pd, &pc)?;
+        let g = f.add(&pd, &pc)?;
+        let h = f.add(&pb, &pa)?;
+
+        Ok(Point {
+            x: f.mul(casper, &e, &ff).await?,
+            y: f.mul(casper, &g, &h).await?,
+            z: f.mul(casper, &ff, &g).await?,
+            t: f.mul(casper, &e, &h).await?,
+        })
+    }
+
+    /// `k * a` by double-and-add, only for public scalars
+    async fn multiply<C: Multiplier>(&self, casper: &mut C, k: &Element, a: &Point) -> Result<Point, Error> {
+        let mut r = self.identity();
+        for i in (0..BITS).rev() {
+            r = self.add(casper, &r, &r).await?;
+            if bignum::bit(k, i) == 1 {
+                r = self.add(casper, &r, a).await?;
+            }
+        }
+        Ok(r)
+    }
+
+    /// Compare two points, without leaving projective coordinates
+    async fn equal<C: Multiplier>(&self, casper: &mut C, a: &Point, b: &Point) -> Result<bool, Error> {
+        let f = &self.p;
+        let x1z2 = f.mul(casper, &a.x, &b.z).await?;
+        let x2z1 = f.mul(casper, &b.x, &a.z).await?;
+        let y1z2 = f.mul(casper, &a.y, &b.z).await?;
+        let y2z1 = f.mul(casper, &b.y, &a.z).await?;
+        Ok(x1z2 == x2z1 && y1z2 == y2z1)
+    }
+}
+
+/// Ed25519 verification from RFC 8032 5.1.7, checking `[S]B = R + [k]A`
+async fn ed25519_verify<C: Multiplier>(
+    casper: &mut C,
+    public_key: &[u8; KEY_LEN],
+    message: &[u8],
+    signature: &[u8; SIGNATURE_LEN],
+) -> Result<(), Error> {
+    let curve = Edwards::new(casper).await?;
+    let a = curve.decompress(casper, public_key).await?;
+
+    let (r_bytes, s_bytes) = signature.split_at(KEY_LEN);
+    let r = curve.decompress(casper, r_bytes).await.map_err(|error| match error {
+        Error::InvalidKey => Error::InvalidSignature,
+        error => error,
+    })?;
+    let s = decode_le(s_bytes)?;
+    let l = decode(&L)?;
+    if bignum::compare(&s, &l) != Ordering::Less {
+        return Err(Error::InvalidSignature);
+    }
+
+    // k = SHA-512(R || A || M) mod L
+    let mut hasher = Sha512::new();
+    hasher.update(r_bytes);
+    hasher.update(public_key);
+    hasher.update(message);
+    let mut digest = [0u32; sha512::HASH_LEN / 4];
+    bignum::from_le_bytes(&hasher.finalize(), &mut digest)?;
+    let mut k = [0u32; WORDS];
+    bignum::modulo(&digest, &l, &mut k);
+
+    let b = curve.base_point(casper).await?;
+    let sb = curve.multiply(casper, &s, &b).await?;
+    let ka = curve.multiply(casper, &k, &a).await?;
+    let rka = curve.add(casper, &r, &ka).await?;
+    if !curve.equal(casper, &sb, &rka).await? {
+        return Err(Error::InvalidSignature);
+    }
+    Ok(())
+}
+
+impl<'d> Casper<'d, Blocking> {
+    /// Compute the X25519 function of a secret scalar and a u-coordinate
+    ///
+    /// Returns [`Error::InvalidKey`] if the result is all zeros, which happens when the peer
+    /// sent a point of low order.
+    pub fn x25519(
+        &mut self,
+        scalar: &[u8; KEY_LEN],
+        u: &[u8; KEY_LEN],
+        result: &mut [u8; KEY_LEN],
+    ) -> Result<(), Error> {
+        // The blocking multiplier never yields, this future completes on its first poll
+        block_on(x25519(self, scalar, u, result))
+    }
+
+    /// Derive the X25519 public key of a secret scalar
+    pub fn x25519_public_key(&mut self, scalar: &[u8; KEY_LEN], public_key: &mut [u8; KEY_LEN]) -> Result<(), Error> {
+        self.x25519(scalar, &BASE_POINT, public_key)
+    }
+
+    /// Verify an Ed25519 signature of a message
+    ///
+    /// Returns [`Error::InvalidSignature`] if the signature does not match, or
+    /// [`Error::InvalidKey`] if the public key is not a point of the curve.
+    pub fn ed25519_verify(
+        &mut self,
+        public_key: &[u8; KEY_LEN],
+        message: &[u8],
+        signature: &[u8; SIGNATURE_LEN],
+    ) -> Result<(), Error> {
+        block_on(ed25519_verify(self, public_key, message, signature))
+    }
+}
+
+impl<'d> Casper<'d, Async> {
+    /// Compute the X25519 function of a secret scalar and a u-coordinate
+    ///
+    /// Returns [`Error::InvalidKey`] if the result is all zeros, which happens when the peer
+    /// sent a point of low order.
+    pub async fn x25519(
+        &mut self,
+        scalar: &[u8; KEY_LEN],
+        u: &[u8; KEY_LEN],
+        result: &mut [u8; KEY_LEN],
+    ) -> Result<(), Error> {
+        x25519(self, scalar, u, result).await
+    }
+
+    /// Derive the X25519 public key of a secret scalar
+    pub async fn x25519_public_key(
+        &mut self,
+        scalar: &[u8; KEY_LEN],
+        public_key: &mut [u8; KEY_LEN],
+    ) -> Result<(), Error> {
+        self.x25519(scalar, &BASE_POINT, public_key).await
+    }
+
+    /// Verify an Ed25519 signature of a message
+    ///
+    /// Returns [`Error::InvalidSignature`] if the signature does not match, or
+    /// [`Error::InvalidKey`] if the public key is not a point of the curve.
+    pub async fn ed25519_verify(
+        &mut self,
+        public_key: &[u8; KEY_LEN],
+        message: &[u8],
+        signature: &[u8; SIGNATURE_LEN],
+    ) -> Result<(), Error> {
+        ed25519_verify(self, public_key, message, signature).await
+    }
+}
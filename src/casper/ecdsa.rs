@@ -0,0 +1,419 @@
+//! ECDSA over the NIST P-256 curve
+//!
+//! Field and scalar multiplications run on CASPER, additions and subtractions are done in
+//! software. Points are kept in Jacobian coordinates, with every coordinate in the Montgomery
+//! domain.
+use core::cmp::Ordering;
+
+use embassy_futures::block_on;
+use rand_core::TryCryptoRng;
+
+use super::field::{BITS, Element, Field, ONE, WORDS, decode};
+use super::{Async, Blocking, Casper, Error, Multiplier, bignum};
+
+/// Length of a private key, a coordinate or a signature component
+pub const SCALAR_LEN: usize = 32;
+
+/// Length of a public key, the X coordinate followed by the Y coordinate
+pub const PUBLIC_KEY_LEN: usize = 2 * SCALAR_LEN;
+
+/// Length of a signature, r followed by s
+pub const SIGNATURE_LEN: usize = 2 * SCALAR_LEN;
+
+// Curve parameters, from FIPS 186-4 D.1.2.3
+const P: [u8; SCALAR_LEN] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+const N: [u8; SCALAR_LEN] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xbc, 0xe6, 0xfa,
+    0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63, 0x25, 0x51,
+];
+const B: [u8; SCALAR_LEN] = [
+    0x5a, 0xc6, 0x35, 0xd8, 0xaa, 0x3a, 0x93, 0xe7, 0xb3, 0xeb, 0xbd, 0x55, 0x76, 0x98, 0x86, 0xbc, 0x65, 0x1d, 0x06,
+    0xb0, 0xcc, 0x53, 0xb0, 0xf6, 0x3b, 0xce, 0x3c, 0x3e, 0x27, 0xd2, 0x60, 0x4b,
+];
+const GX: [u8; SCALAR_LEN] = [
+    0x6b, 0x17, 0xd1, 0xf2, 0xe1, 0x2c, 0x42, 0x47, 0xf8, 0xbc, 0xe6, 0xe5, 0x63, 0xa4, 0x40, 0xf2, 0x77, 0x03, 0x7d,
+    0x81, 0x2d, 0xeb, 0x33, 0xa0, 0xf4, 0xa1, 0x39, 0x45, 0xd8, 0x98, 0xc2, 0x96,
+];
+const GY: [u8; SCALAR_LEN] = [
+    0x4f, 0xe3, 0x42, 0xe2, 0xfe, 0x1a, 0x7f, 0x9b, 0x8e, 0xe7, 0xeb, 0x4a, 0x7c, 0x0f, 0x9e, 0x16, 0x2b, 0xce, 0x33,
+    0x57, 0x6b, 0x31, 0x5e, 0xce, 0xcb, 0xb6, 0x40, 0x68, 0x37, 0xbf, 0x51, 0xf5,
+];
+
+/// A point in Jacobian coordinates, the point at infinity has `z == 0`
+#[derive(Copy, Clone)]
+struct Point {
+    x: Element,
+    y: Element,
+    z: Element,
+}
+
+impl Point {
+    const INFINITY: Self = Self {
+        x: [0; WORDS],
+        y: [0; WORDS],
+        z: [0; WORDS],
+    };
+
+    fn is_infinity(&self) -> u32 {
+        bignum::is_zero(&self.z)
+    }
+
+    /// Replace this point with `other` if `condition` is 1
+    fn select(&mut self, other: &Self, condition: u32) {
+        bignum::conditional_copy(&mut self.x, &other.x, condition);
+        bignum::conditional_copy(&mut self.y, &other.y, condition);
+        bignum::conditional_copy(&mut self.z, &other.z, condition);
+    }
+
+    /// Swap two points if `condition` is 1
+    fn swap(a: &mut Self, b: &mut Self, condition: u32) {
+        bignum::conditional_swap(&mut a.x, &mut b.x, condition);
+        bignum::conditional_swap(&mut a.y, &mut b.y, condition);
+        bignum::conditional_swap(&mut a.z, &mut b.z, condition);
+    }
+}
+
+/// The P-256 curve, with its coordinate field and its scalar field
+struct Curve {
+    p: Field,
+    n: Field,
+}
+
+impl Curve {
+    fn new() -> Result<Self, Error> {
+        Ok(Self {
+            p: Field::new(&P)?,
+            n: Field::new(&N)?,
+        })
+    }
+
+    /// Convert affine coordinates, checking that they lie on the curve
+    async fn point<C: Multiplier>(&self, casper: &mut C, x: &[u8], y: &[u8]) -> Result<Point, Error> {
+        let f = &self.p;
+        let (x, y) = (decode(x)?, decode(y)?);
+        let p = f.modulus()?;
+        if bignum::compare(&x, p) != Ordering::Less || bignum::compare(&y, p) != Ordering::Less {
+            return Err(Error::InvalidKey);
+        }
+
+        let x = f.to_montgomery(casper, &x).await?;
+        let y = f.to_montgomery(casper, &y).await?;
+        let b = f.to_montgomery(casper, &decode(&B)?).await?;
+
+        // y^2 = x^3 - 3x + b
+        let x2 = f.mul(casper, &x, &x).await?;
+        let x3 = f.mul(casper, &x2, &x).await?;
+        let x3 = f.sub(&f.sub(&f.sub(&x3, &x)?, &x)?, &x)?;
+        let rhs = f.add(&x3, &b)?;
+        let lhs = f.mul(casper, &y, &y).await?;
+        if lhs != rhs {
+            return Err(Error::InvalidKey);
+        }
+
+        Ok(Point {
+            x,
+            y,
+            z: f.to_montgomery(casper, &ONE).await?,
+        })
+    }
+
+    async fn generator<C: Multiplier>(&self, casper: &mut C) -> Result<Point, Error> {
+        self.point(casper, &GX, &GY).await
+    }
+
+    /// Affine coordinates of a point other than infinity, out of the Montgomery domain
+    async fn to_affine<C: Multiplier>(&self, casper: &mut C, point: &Point) -> Result<(Element, Element), Error> {
+        let f = &self.p;
+        let z_inv = f.invert(casper, &point.z).await?;
+        let z_inv2 = f.mul(casper, &z_inv, &z_inv).await?;
+        let z_inv3 = f.mul(casper, &z_inv2, &z_inv).await?;
+
+        let x = f.mul(casper, &point.x, &z_inv2).await?;
+        let y = f.mul(casper, &point.y, &z_inv3).await?;
+        Ok((f.to_normal(casper, &x).await?, f.to_normal(casper, &y).await?))
+    }
+
+    /// `2 * a`, using the dbl-2001-b formulas for curves with `a = -3`
+    async fn double<C: Multiplier>(&self, casper: &mut C, a: &Point) -> Result<Point, Error> {
+        let f = &self.p;
+        let delta = f.mul(casper, &a.z, &a.z).await?;
+        let gamma = f.mul(casper, &a.y, &a.y).await?;
+        let beta = f.mul(casper, &a.x, &gamma).await?;
+
+        let t = f.mul(casper, &f.sub(&a.x, &delta)?, &f.add(&a.x, &delta)?).await?;
+        let alpha = f.add(&f.add(&t, &t)?, &t)?;
+
+        let beta2 = f.add(&beta, &beta)?;
+        let beta4 = f.add(&beta2, &beta2)?;
+        let beta8 = f.add(&beta4, &beta4)?;
+        let x = f.sub(&f.mul(casper, &alpha, &alpha).await?, &beta8)?;
+
+        let yz = f.add(&a.y, &a.z)?;
+        let z = f.sub(&f.sub(&f.mul(casper, &yz, &yz).await?, &gamma)?, &delta)?;
+
+        let gamma2 = f.mul(casper, &gamma, &gamma).await?;
+        let gamma4 = f.add(&gamma2, &gamma2)?;
+        let gamma8 = f.add(&gamma4, &gamma4)?;
+        let gamma16 = f.add(&gamma8, &gamma8)?;
+        let y = f.sub(&f.mul(casper, &alpha, &f.sub(&beta4, &x)?).await?, &gamma16)?;
+
+        Ok(Point { x, y, z })
+    }
+
+    /// `a + b`, using the add-2007-bl formulas
+    ///
+    /// Doubling and the point at infinity are handled by selecting the right result, the same
+    /// operations are performed whatever the inputs.
+    async fn add<C: Multiplier>(&self, casper: &mut C, a: &Point, b: &Point) -> Result<Point, Error> {
+        let f = &self.p;
+        let z1z1 = f.mul(casper, &a.z, &a.z).await?;
+        let z2z2 = f.mul(casper, &b.z, &b.z).await?;
+        let u1 = f.mul(casper, &a.x, &z2z2).await?;
+        let u2 = f.mul(casper, &b.x, &z1z1).await?;
+        let y1z2 = f.mul(casper, &a.y, &b.z).await?;
+        let s1 = f.mul(casper, &y1z2, &z2z2).await?;
+        let y2z1 = f.mul(casper, &b.y, &a.z).await?;
+        let s2 = f.mul(casper, &y2z1, &z1z1).await?;
+
+        let h = f.sub(&u2, &u1)?;
+        let h2 = f.add(&h, &h)?;
+        let i = f.mul(casper, &h2, &h2).await?;
+        let j = f.mul(casper, &h, &i).await?;
+        let s = f.sub(&s2, &s1)?;
+        let r = f.add(&s, &s)?;
+        let v = f.mul(casper, &u1, &i).await?;
+
+        let x = f.sub(&f.sub(&f.mul(casper, &r, &r).await?, &j)?, &f.add(&v, &v)?)?;
+        let s1j = f.mul(casper, &s1, &j).await?;
+        let y = f.sub(&f.mul(casper, &r, &f.sub(&v, &x)?).await?, &f.add(&s1j, &s1j)?)?;
+        let zz = f.add(&a.z, &b.z)?;
+        let z = f.sub(&f.sub(&f.mul(casper, &zz, &zz).await?, &z1z1)?, &z2z2)?;
+        let z = f.mul(casper, &z, &h).await?;
+
+        let mut sum = Point { x, y, z };
+        let doubled = self.double(casper, a).await?;
+        sum.select(&doubled, bignum::is_zero(&h) & bignum::is_zero(&s));
+        sum.select(b, a.is_infinity());
+        sum.select(a, b.is_infinity());
+        Ok(sum)
+    }
+
+    /// `k * a` with a Montgomery ladder, which runs in constant time
+    async fn multiply<C: Multiplier>(&self, casper: &mut C, k: &Element, a: &Point) -> Result<Point, Error> {
+        let mut r0 = Point::INFINITY;
+        let mut r1 = *a;
+
+        for i in (0..BITS).rev() {
+            let bit = bignum::bit(k, i);
+            Point::swap(&mut r0, &mut r1, bit);
+            r1 = self.add(casper, &r0, &r1).await?;
+            r0 = self.double(casper, &r0).await?;
+            Point::swap(&mut r0, &mut r1, bit);
+        }
+
+        Ok(r0)
+    }
+
+    /// Decode a private key, which must lie in `[1, n - 1]`
+    fn private_key(&self, private_key: &[u8; SCALAR_LEN]) -> Result<Element, Error> {
+        let d = decode(private_key)?;
+        if self.n.is_valid(&d)? == 0 {
+            return Err(Error::InvalidKey);
+        }
+        Ok(d)
+    }
+
+    /// Decode a message hash, reduced modulo `n`
+    fn hash(&self, hash: &[u8; SCALAR_LEN]) -> Result<Element, Error> {
+        let mut e = decode(hash)?;
+        self.n.reduce(&mut e)?;
+        Ok(e)
+    }
+}
+
+async fn public_key<C: Multiplier>(
+    casper: &mut C,
+    private_key: &[u8; SCALAR_LEN],
+    public_key: &mut [u8; PUBLIC_KEY_LEN],
+) -> Result<(), Error> {
+    let curve = Curve::new()?;
+    let d = curve.private_key(private_key)?;
+
+    let g = curve.generator(casper).await?;
+    let q = curve.multiply(casper, &d, &g).await?;
+    let (x, y) = curve.to_affine(casper, &q).await?;
+
+    let (qx, qy) = public_key.split_at_mut(SCALAR_LEN);
+    bignum::to_be_bytes(&x, qx)?;
+    bignum::to_be_bytes(&y, qy)
+}
+
+async fn sign<C: Multiplier, R: TryCryptoRng>(
+    casper: &mut C,
+    private_key: &[u8; SCALAR_LEN],
+    hash: &[u8; SCALAR_LEN],
+    rng: &mut R,
+    signature: &mut [u8; SIGNATURE_LEN],
+) -> Result<(), Error> {
+    let curve = Curve::new()?;
+    let n = &curve.n;
+    let d = curve.private_key(private_key)?;
+    let e = curve.hash(hash)?;
+    let g = curve.generator(casper).await?;
+
+    loop {
+        // Rejection sampling keeps the nonce uniform in [1, n - 1]
+        let mut nonce = [0u8; SCALAR_LEN];
+        rng.try_fill_bytes(&mut nonce).map_err(|_| Error::RngFailure)?;
+        let k = decode(&nonce)?;
+        if n.is_valid(&k)? == 0 {
+            continue;
+        }
+
+        let kg = curve.multiply(casper, &k, &g).await?;
+        let (mut r, _) = curve.to_affine(casper, &kg).await?;
+        n.reduce(&mut r)?;
+        if bignum::is_zero(&r) == 1 {
+            continue;
+        }
+
+        // s = k^-1 * (e + r * d), a Montgomery product with one operand in the Montgomery
+        // domain leaves it
+        let k = n.to_montgomery(casper, &k).await?;
+        let k_inv = n.invert(casper, &k).await?;
+        let r_mont = n.to_montgomery(casper, &r).await?;
+        let rd = n.mul(casper, &r_mont, &d).await?;
+        let s = n.mul(casper, &k_inv, &n.add(&e, &rd)?).await?;
+        if bignum::is_zero(&s) == 1 {
+            continue;
+        }
+
+        let (sig_r, sig_s) = signature.split_at_mut(SCALAR_LEN);
+        bignum::to_be_bytes(&r, sig_r)?;
+        return bignum::to_be_bytes(&s, sig_s);
+    }
+}
+
+async fn verify<C: Multiplier>(
+    casper: &mut C,
+    public_key: &[u8; PUBLIC_KEY_LEN],
+    hash: &[u8; SCALAR_LEN],
+    signature: &[u8; SIGNATURE_LEN],
+) -> Result<(), Error> {
+    let curve = Curve::new()?;
+    let n = &curve.n;
+    let (qx, qy) = public_key.split_at(SCALAR_LEN);
+    let q = curve.point(casper, qx, qy).await?;
+
+    let (sig_r, sig_s) = signature.split_at(SCALAR_LEN);
+    let (r, s) = (decode(sig_r)?, decode(sig_s)?);
+    if n.is_valid(&r)? == 0 || n.is_valid(&s)? == 0 {
+        return Err(Error::InvalidSignature);
+    }
+    let e = curve.hash(hash)?;
+
+    // u1 = e / s and u2 = r / s
+    let s = n.to_montgomery(casper, &s).await?;
+    let w = n.invert(casper, &s).await?;
+    let u1 = n.mul(casper, &w, &e).await?;
+    let u2 = n.mul(casper, &w, &r).await?;
+
+    let g = curve.generator(casper).await?;
+    let a = curve.multiply(casper, &u1, &g).await?;
+    let b = curve.multiply(casper, &u2, &q).await?;
+    let sum = curve.add(casper, &a, &b).await?;
+    if sum.is_infinity() == 1 {
+        return Err(Error::InvalidSignature);
+    }
+
+    let (mut x, _) = curve.to_affine(casper, &sum).await?;
+    n.reduce(&mut x)?;
+    if x != r {
+        return Err(Error::InvalidSignature);
+    }
+    Ok(())
+}
+
+impl<'d> Casper<'d, Blocking> {
+    /// Derive the P-256 public key of a private key
+    ///
+    /// Keys are big-endian, the public key is the X coordinate followed by the Y coordinate.
+    pub fn p256_public_key(
+        &mut self,
+        private_key: &[u8; SCALAR_LEN],
+        public_key: &mut [u8; PUBLIC_KEY_LEN],
+    ) -> Result<(), Error> {
+        // The blocking multiplier never yields, this future completes on its first poll
+        block_on(self::public_key(self, private_key, public_key))
+    }
+
+    /// Sign a message hash with ECDSA P-256
+    ///
+    /// The nonce is drawn from `rng`, which must be a cryptographically secure source such as
+    /// [`crate::rng::Rng`]. The signature is r followed by s, both big-endian.
+    pub fn p256_sign<R: TryCryptoRng>(
+        &mut self,
+        private_key: &[u8; SCALAR_LEN],
+        hash: &[u8; SCALAR_LEN],
+        rng: &mut R,
+        signature: &mut [u8; SIGNATURE_LEN],
+    ) -> Result<(), Error> {
+        block_on(sign(self, private_key, hash, rng, signature))
+    }
+
+    /// Verify an ECDSA P-256 signature of a message hash
+    ///
+    /// Returns [`Error::InvalidSignature`] if the signature does not match, or
+    /// [`Error::InvalidKey`] if the public key is not a point of the curve.
+    pub fn p256_verify(
+        &mut self,
+        public_key: &[u8; PUBLIC_KEY_LEN],
+        hash: &[u8; SCALAR_LEN],
+        signature: &[u8; SIGNATURE_LEN],
+    ) -> Result<(), Error> {
+        block_on(verify(self, public_key, hash, signature))
+    }
+}
+
+impl<'d> Casper<'d, Async> {
+    /// Derive the P-256 public key of a private key
+    ///
+    /// Keys are big-endian, the public key is the X coordinate followed by the Y coordinate.
+    pub async fn p256_public_key(
+        &mut self,
+        private_key: &[u8; SCALAR_LEN],
+        public_key: &mut [u8; PUBLIC_KEY_LEN],
+    ) -> Result<(), Error> {
+        self::public_key(self, private_key, public_key).await
+    }
+
+    /// Sign a message hash with ECDSA P-256
+    ///
+    /// The nonce is drawn from `rng`, which must be a cryptographically secure source such as
+    /// [`crate::rng::Rng`]. The signature is r followed by s, both big-endian.
+    pub async fn p256_sign<R: TryCryptoRng>(
+        &mut self,
+        private_key: &[u8; SCALAR_LEN],
+        hash: &[u8; SCALAR_LEN],
+        rng: &mut R,
+        signature: &mut [u8; SIGNATURE_LEN],
+    ) -> Result<(), Error> {
+        sign(self, private_key, hash, rng, signature).await
+    }
+
+    /// Verify an ECDSA P-256 signature of a message hash
+    ///
+    /// Returns [`Error::InvalidSignature`] if the signature does not match, or
+    /// [`Error::InvalidKey`] if the public key is not a point of the curve.
+    pub async fn p256_verify(
+        &mut self,
+        public_key: &[u8; PUBLIC_KEY_LEN],
+        hash: &[u8; SCALAR_LEN],
+        signature: &[u8; SIGNATURE_LEN],
+    ) -> Result<(), Error> {
+        verify(self, public_key, hash, signature).await
+    }
+}
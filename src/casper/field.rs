@@ -0,0 +1,107 @@
+//! Arithmetic over 256-bit prime fields, shared by the elliptic curves
+use core::cmp::Ordering;
+
+use super::{Error, Modulus, Multiplier, bignum, operand};
+
+/// Length of an element in bytes
+pub(super) const LEN: usize = 32;
+pub(super) const WORDS: usize = LEN / 4;
+pub(super) const BITS: usize = 8 * LEN;
+
+pub(super) type Element = [u32; WORDS];
+
+pub(super) const ONE: Element = [1, 0, 0, 0, 0, 0, 0, 0];
+
+/// Decode a big-endian number
+pub(super) fn decode(bytes: &[u8]) -> Result<Element, Error> {
+    let mut element = [0u32; WORDS];
+    bignum::from_be_bytes(bytes, &mut element)?;
+    Ok(element)
+}
+
+/// Arithmetic modulo a 256-bit prime, with elements in the Montgomery domain
+pub(super) struct Field {
+    modulus: Modulus,
+}
+
+impl Field {
+    pub(super) fn new(modulus: &[u8; LEN]) -> Result<Self, Error> {
+        Ok(Self {
+            modulus: Modulus::new(modulus)?,
+        })
+    }
+
+    pub(super) fn modulus(&self) -> Result<&[u32], Error> {
+        operand(&self.modulus.n, WORDS)
+    }
+
+    /// 1 if `0 < a < modulus`, 0 otherwise
+    pub(super) fn is_valid(&self, a: &Element) -> Result<u32, Error> {
+        let below = u32::from(bignum::compare(a, self.modulus()?) == Ordering::Less);
+        Ok(below & (bignum::is_zero(a) ^ 1))
+    }
+
+    /// Reduce a number smaller than twice the modulus
+    pub(super) fn reduce(&self, a: &mut Element) -> Result<(), Error> {
+        let n = self.modulus()?;
+        let reduce = u32::from(bignum::compare(a, n) != Ordering::Less);
+        bignum::conditional_sub(a, n, reduce);
+        Ok(())
+    }
+
+    pub(super) fn add(&self, a: &Element, b: &Element) -> Result<Element, Error> {
+        let mut r = *a;
+        bignum::mod_add(&mut r, b, self.modulus()?);
+        Ok(r)
+    }
+
+    pub(super) fn sub(&self, a: &Element, b: &Element) -> Result<Element, Error> {
+        let mut r = *a;
+        bignum::mod_sub(&mut r, b, self.modulus()?);
+        Ok(r)
+    }
+
+    /// `a * b / R`, which stays in the Montgomery domain when both are in it
+    pub(super) async fn mul<C: Multiplier>(&self, casper: &mut C, a: &[u32], b: &[u32]) -> Result<Element, Error> {
+        let mut r = [0u32; WORDS];
+        casper.multiply(&self.modulus, a, b, &mut r).await?;
+        Ok(r)
+    }
+
+    pub(super) async fn to_montgomery<C: Multiplier>(&self, casper: &mut C, a: &Element) -> Result<Element, Error> {
+        self.mul(casper, a, operand(&self.modulus.rr, WORDS)?).await
+    }
+
+    pub(super) async fn to_normal<C: Multiplier>(&self, casper: &mut C, a: &Element) -> Result<Element, Error> {
+        self.mul(casper, a, &ONE).await
+    }
+
+    /// `a^exponent` for a public exponent, branching on its bits leaks nothing
+    pub(super) async fn pow<C: Multiplier>(
+        &self,
+        casper: &mut C,
+        a: &Element,
+        exponent: &Element,
+    ) -> Result<Element, Error> {
+        let mut r = self.to_montgomery(casper, &ONE).await?;
+        for i in (0..BITS).rev() {
+            r = self.mul(casper, &r, &r).await?;
+            if bignum::bit(exponent, i) == 1 {
+                r = self.mul(casper, &r, a).await?;
+            }
+        }
+        Ok(r)
+    }
+
+    /// `modulus - k`, for deriving the exponents of inversions and square roots
+    pub(super) fn modulus_minus(&self, k: u32) -> Result<Element, Error> {
+        let mut r = Element::try_from(self.modulus()?).map_err(|_| Error::UnsupportedConfiguration)?;
+        bignum::conditional_sub(&mut r, &[k, 0, 0, 0, 0, 0, 0, 0], 1);
+        Ok(r)
+    }
+
+    /// `a^-1` by Fermat's little theorem, the modulus being prime
+    pub(super) async fn invert<C: Multiplier>(&self, casper: &mut C, a: &Element) -> Result<Element, Error> {
+        self.pow(casper, a, &self.modulus_minus(2)?).await
+    }
+}
@@ -0,0 +1,583 @@
+//! CASPER cryptographic accelerator
+//!
+//! CASPER runs 64-bit multiply-accumulate operations over operands held in its own RAM. This
+//! driver builds Montgomery modular multiplication and exponentiation on top of it, the basis
+//! for RSA and elliptic curve cryptography.
+use core::cmp::Ordering;
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::ptr;
+use core::task::Poll;
+
+use embassy_hal_internal::PeripheralType;
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::clocks::enable_and_reset;
+use crate::interrupt::typelevel::Interrupt;
+use crate::peripherals::CASPER;
+use crate::{Peri, interrupt, pac};
+
+mod bignum;
+/// Curve25519 module
+pub mod curve25519;
+/// ECDSA module
+pub mod ecdsa;
+mod field;
+/// Montgomery arithmetic module
+pub mod montgomery;
+/// RSA module
+pub mod rsa;
+mod sha512;
+
+/// Largest supported modulus, in bytes (4096 bits)
+pub const MAX_MODULUS_LEN: usize = 512;
+
+const MAX_WORDS: usize = MAX_MODULUS_LEN / 4;
+const MAX_DWORDS: usize = MAX_MODULUS_LEN / 8;
+
+/// Base address of the CASPER RAM, as seen by the CPU
+const RAM_BASE: usize = 0x2400_0000;
+
+/// The CASPER RAM is interleaved: the engine sees 64-bit words, with the low half stored in the
+/// first bank and the high half in the second one, at this offset from the first.
+const RAM_BANK_OFFSET: usize = 0x4000;
+
+// Operand locations in CASPER RAM, in 64-bit words
+const RAM_A: usize = 0;
+const RAM_B: usize = RAM_A + MAX_DWORDS;
+const RAM_N: usize = RAM_B + MAX_DWORDS;
+// The accumulator has two extra words for the carries
+const RAM_W: usize = RAM_N + MAX_DWORDS;
+const RAM_M: usize = RAM_W + MAX_DWORDS + 2;
+
+/// Error information type
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// configuration requested is not supported
+    UnsupportedConfiguration,
+    /// modulus is even or smaller than 3
+    InvalidModulus,
+    /// operand is not smaller than the modulus
+    InvalidOperand,
+    /// key is out of range or not on the curve
+    InvalidKey,
+    /// signature did not verify
+    InvalidSignature,
+    /// random number generator failed
+    RngFailure,
+    /// hashing failed
+    Hashcrypt(crate::hashcrypt::Error),
+}
+
+impl From<crate::hashcrypt::Error> for Error {
+    fn from(error: crate::hashcrypt::Error) -> Self {
+        Self::Hashcrypt(error)
+    }
+}
+
+/// CASPER operations
+#[derive(Debug, Copy, Clone)]
+enum Opcode {
+    /// `RES += AB * CD`, with AB a single word and CD a vector, carrying into two extra words
+    Mul6464FullSum = 0x03,
+    /// `RES = AB`
+    Copy = 0x14,
+}
+
+trait Sealed {}
+
+/// Asynchronous or blocking mode
+#[allow(private_bounds)]
+pub trait Mode: Sealed {}
+
+/// Blocking mode
+pub struct Blocking {}
+impl Sealed for Blocking {}
+impl Mode for Blocking {}
+
+/// Asynchronous mode
+pub struct Async {}
+impl Sealed for Async {}
+impl Mode for Async {}
+
+/// CASPER driver
+pub struct Casper<'d, M: Mode> {
+    casper: pac::Casper,
+    _mode: PhantomData<M>,
+    _lifetime: PhantomData<&'d ()>,
+}
+
+static WAKER: AtomicWaker = AtomicWaker::new();
+
+/// CASPER interrupt handler.
+pub struct InterruptHandler<T: Instance> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandler<T> {
+    unsafe fn on_interrupt() {
+        let reg = unsafe { pac::Casper::steal() };
+
+        if reg.intstat().read().done().is_caused() {
+            reg.intenclr().write(|w| w.done().clear_bit_by_one());
+            WAKER.wake();
+        }
+    }
+}
+
+/// An odd modulus, with the values precomputed for Montgomery arithmetic
+///
+/// With `k` the number of [words](Modulus::words), the Montgomery radix is `R = 2^(32 * k)`.
+pub struct Modulus {
+    n: [u32; MAX_WORDS],
+    /// `R^2 mod n`
+    rr: [u32; MAX_WORDS],
+    /// `-n^-1 mod 2^64`
+    inverse: u64,
+    /// Length in 64-bit words
+    dwords: usize,
+}
+
+impl Modulus {
+    /// Prepare a big-endian modulus, which must be odd and at most [`MAX_MODULUS_LEN`] bytes long
+    pub fn new(modulus: &[u8]) -> Result<Self, Error> {
+        if modulus.is_empty() || modulus.len() > MAX_MODULUS_LEN {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let dwords = modulus.len().div_ceil(8);
+        let mut n = [0u32; MAX_WORDS];
+        let words = n.get_mut(..2 * dwords).ok_or(Error::UnsupportedConfiguration)?;
+        bignum::from_be_bytes(modulus, words)?;
+
+        let mut three = [0u32; MAX_WORDS];
+        three[0] = 3;
+        if bignum::bit(words, 0) == 0 || bignum::compare(words, operand(&three, 2 * dwords)?) == Ordering::Less {
+            return Err(Error::InvalidModulus);
+        }
+
+        let mut rr = [0u32; MAX_WORDS];
+        bignum::montgomery_r_squared(words, rr.get_mut(..2 * dwords).ok_or(Error::UnsupportedConfiguration)?);
+
+        let inverse = bignum::montgomery_inverse(u64::from(n[0]) | (u64::from(n[1]) << 32));
+
+        Ok(Self { n, rr, inverse, dwords })
+    }
+
+    /// Length of the operands, in 32-bit words
+    ///
+    /// The engine works on 64-bit words, so this is always even.
+    pub fn words(&self) -> usize {
+        2 * self.dwords
+    }
+
+    /// Load an operand, which must be smaller than the modulus
+    fn load(&self, bytes: &[u8], operand: &mut [u32; MAX_WORDS]) -> Result<(), Error> {
+        let words = self.words();
+        let value = operand.get_mut(..words).ok_or(Error::UnsupportedConfiguration)?;
+        bignum::from_be_bytes(bytes, value)?;
+
+        if bignum::compare(value, self.n.get(..words).ok_or(Error::UnsupportedConfiguration)?) != Ordering::Less {
+            return Err(Error::InvalidOperand);
+        }
+        Ok(())
+    }
+}
+
+impl<'d, M: Mode> Casper<'d, M> {
+    fn new_inner<T: Instance>(_peripheral: Peri<'d, T>) -> Self {
+        enable_and_reset::<CASPER>();
+
+        Self {
+            casper: unsafe { pac::Casper::steal() },
+            _mode: PhantomData,
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Write words to CASPER RAM, starting at a 64-bit word offset
+    fn write_ram(&self, offset: usize, data: &[u32]) {
+        for (i, word) in data.iter().enumerate() {
+            // SAFETY: the offsets used by this driver all lie within the CASPER RAM
+            unsafe { ptr::write_volatile(ram_address(2 * offset + i), *word) };
+        }
+    }
+
+    /// Read words from CASPER RAM, starting at a 64-bit word offset
+    fn read_ram(&self, offset: usize, data: &mut [u32]) {
+        for (i, word) in data.iter_mut().enumerate() {
+            // SAFETY: the offsets used by this driver all lie within the CASPER RAM
+            *word = unsafe { ptr::read_volatile(ram_address(2 * offset + i)) };
+        }
+    }
+
+    /// Start an operation over `iterations` 64-bit words, all offsets are in 64-bit words
+    fn start(&self, opcode: Opcode, ab: usize, cd: usize, res: usize, iterations: usize) {
+        // The offset fields hold byte offsets, use the raw register layout
+        // SAFETY: unsafe due to .bits usage
+        self.casper
+            .ctrl0()
+            .write(|w| unsafe { w.bits((8 * ab as u32) | ((8 * cd as u32) << 16)) });
+        // Writing CTRL1 starts the operation and clears any previous DONE flag
+        self.casper
+            .ctrl1()
+            .write(|w| unsafe { w.bits((iterations as u32 - 1) | ((opcode as u32) << 8) | ((8 * res as u32) << 16)) });
+    }
+
+    /// Start the `a * b[j]` step of a Montgomery multiplication
+    fn start_multiply(&self, modulus: &Modulus, j: usize) {
+        self.start(Opcode::Mul6464FullSum, RAM_B + j, RAM_A, RAM_W, modulus.dwords);
+    }
+
+    /// Start the `m * n` step of a Montgomery multiplication, which clears the lowest word
+    fn start_reduce(&self, modulus: &Modulus) {
+        let mut w0 = [0u32; 2];
+        self.read_ram(RAM_W, &mut w0);
+
+        let m = (u64::from(w0[0]) | (u64::from(w0[1]) << 32)).wrapping_mul(modulus.inverse);
+        self.write_ram(RAM_M, &[m as u32, (m >> 32) as u32]);
+
+        self.start(Opcode::Mul6464FullSum, RAM_M, RAM_N, RAM_W, modulus.dwords);
+    }
+
+    /// Start shifting the accumulator down by one word
+    fn start_shift(&self, modulus: &Modulus) {
+        self.start(Opcode::Copy, RAM_W + 1, 0, RAM_W, modulus.dwords + 1);
+    }
+
+    /// Prepare the accumulator and operands of a Montgomery multiplication
+    fn setup_multiply(&self, modulus: &Modulus, a: &[u32], b: &[u32]) -> Result<(), Error> {
+        self.write_ram(RAM_A, a);
+        self.write_ram(RAM_B, b);
+        self.write_ram(RAM_N, operand(&modulus.n, modulus.words())?);
+        self.clear_ram(RAM_W, modulus.dwords + 2);
+        Ok(())
+    }
+
+    fn clear_ram(&self, offset: usize, dwords: usize) {
+        for i in 0..dwords {
+            self.write_ram(offset + i, &[0, 0]);
+        }
+    }
+
+    /// Read the result of a Montgomery multiplication, which is below `2n`, and reduce it
+    fn finish_multiply(&self, modulus: &Modulus, result: &mut [u32]) -> Result<(), Error> {
+        let words = modulus.words();
+        let mut w = [0u32; MAX_WORDS + 2];
+        let w = w.get_mut(..words + 2).ok_or(Error::UnsupportedConfiguration)?;
+        self.read_ram(RAM_W, w);
+
+        let (value, high) = w.split_at_mut(words);
+        let n = modulus.n.get(..words).ok_or(Error::UnsupportedConfiguration)?;
+        let reduce = u32::from(high.iter().any(|x| *x != 0)) | u32::from(bignum::compare(value, n) != Ordering::Less);
+        bignum::conditional_sub(value, n, reduce);
+
+        result
+            .get_mut(..words)
+            .ok_or(Error::UnsupportedConfiguration)?
+            .copy_from_slice(value);
+        Ok(())
+    }
+}
+
+/// CPU address of a 32-bit word of CASPER RAM
+fn ram_address(word: usize) -> *mut u32 {
+    (RAM_BASE + (word & 1) * RAM_BANK_OFFSET + (word >> 1) * 4) as *mut u32
+}
+
+/// The number one, as a Montgomery operand
+const fn one() -> [u32; MAX_WORDS] {
+    let mut one = [0u32; MAX_WORDS];
+    one[0] = 1;
+    one
+}
+
+impl<'d> Casper<'d, Blocking> {
+    /// Create a new instance
+    pub fn new_blocking<T: Instance>(peripheral: Peri<'d, T>) -> Self {
+        Self::new_inner(peripheral)
+    }
+
+    fn wait(&self) {
+        while self.casper.status().read().done().is_busy() {}
+    }
+
+    /// Montgomery multiplication, `result = a * b / R mod n`
+    fn multiply_words(&mut self, modulus: &Modulus, a: &[u32], b: &[u32], result: &mut [u32]) -> Result<(), Error> {
+        self.setup_multiply(modulus, a, b)?;
+
+        for j in 0..modulus.dwords {
+            self.start_multiply(modulus, j);
+            self.wait();
+            self.start_reduce(modulus);
+            self.wait();
+            self.start_shift(modulus);
+            self.wait();
+            self.write_ram(RAM_W + modulus.dwords + 1, &[0, 0]);
+        }
+
+        self.finish_multiply(modulus, result)
+    }
+
+    /// Compute `result = a * b mod modulus`
+    ///
+    /// All numbers are big-endian, `a` and `b` must be smaller than the odd `modulus`, and
+    /// `result` must be as long as `modulus`.
+    pub fn mod_mul(&mut self, a: &[u8], b: &[u8], modulus: &[u8], result: &mut [u8]) -> Result<(), Error> {
+        if result.len() != modulus.len() {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let modulus_ctx = Modulus::new(modulus)?;
+        let words = modulus_ctx.words();
+        let mut x = [0u32; MAX_WORDS];
+        let mut y = [0u32; MAX_WORDS];
+        modulus_ctx.load(a, &mut x)?;
+        modulus_ctx.load(b, &mut y)?;
+
+        // a * b / R, then multiplying by R^2 / R cancels out the remaining 1 / R
+        let mut t = [0u32; MAX_WORDS];
+        self.multiply_words(&modulus_ctx, operand(&x, words)?, operand(&y, words)?, &mut t)?;
+        self.multiply_words(
+            &modulus_ctx,
+            operand(&t, words)?,
+            operand(&modulus_ctx.rr, words)?,
+            &mut x,
+        )?;
+
+        bignum::to_be_bytes(operand(&x, words)?, result)
+    }
+
+    /// Compute `result = base ^ exponent mod modulus`
+    ///
+    /// All numbers are big-endian, `base` must be smaller than the odd `modulus`, and `result`
+    /// must be as long as `modulus`. The exponent may be secret, the same sequence of operations
+    /// is performed for every exponent of a given length.
+    pub fn mod_exp(&mut self, base: &[u8], exponent: &[u8], modulus: &[u8], result: &mut [u8]) -> Result<(), Error> {
+        if result.len() != modulus.len() || exponent.len() > MAX_MODULUS_LEN {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let modulus_ctx = Modulus::new(modulus)?;
+        let words = modulus_ctx.words();
+        let mut x = [0u32; MAX_WORDS];
+        modulus_ctx.load(base, &mut x)?;
+        let mut e = [0u32; MAX_WORDS];
+        bignum::from_be_bytes(exponent, &mut e)?;
+
+        // Montgomery ladder, r0 = 1 and r1 = base, both in the Montgomery domain
+        let one = one();
+        let mut r0 = [0u32; MAX_WORDS];
+        let mut r1 = [0u32; MAX_WORDS];
+        let mut t = [0u32; MAX_WORDS];
+        self.multiply_words(
+            &modulus_ctx,
+            operand(&modulus_ctx.rr, words)?,
+            operand(&one, words)?,
+            &mut r0,
+        )?;
+        self.multiply_words(
+            &modulus_ctx,
+            operand(&x, words)?,
+            operand(&modulus_ctx.rr, words)?,
+            &mut r1,
+        )?;
+
+        for i in (0..8 * exponent.len()).rev() {
+            let bit = bignum::bit(&e, i);
+            bignum::conditional_swap(&mut r0, &mut r1, bit);
+            self.multiply_words(&modulus_ctx, operand(&r0, words)?, operand(&r1, words)?, &mut t)?;
+            r1 = t;
+            self.multiply_words(&modulus_ctx, operand(&r0, words)?, operand(&r0, words)?, &mut t)?;
+            r0 = t;
+            bignum::conditional_swap(&mut r0, &mut r1, bit);
+        }
+
+        // Multiplying by one leaves the Montgomery domain
+        self.multiply_words(&modulus_ctx, operand(&r0, words)?, operand(&one, words)?, &mut t)?;
+        bignum::to_be_bytes(operand(&t, words)?, result)
+    }
+}
+
+impl<'d> Casper<'d, Async> {
+    /// Create a new instance
+    pub fn new_async<T: Instance>(
+        peripheral: Peri<'d, T>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+    ) -> Self {
+        let casper = Self::new_inner(peripheral);
+
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        casper
+    }
+
+    async fn wait(&self) {
+        poll_fn(|cx| {
+            if self.casper.status().read().done().is_completed() {
+                return Poll::Ready(());
+            }
+
+            WAKER.register(cx.waker());
+            self.casper.intenset().write(|w| w.done().interrupt());
+            Poll::Pending
+        })
+        .await;
+    }
+
+    /// Montgomery multiplication, `result = a * b / R mod n`
+    async fn multiply_words(
+        &mut self,
+        modulus: &Modulus,
+        a: &[u32],
+        b: &[u32],
+        result: &mut [u32],
+    ) -> Result<(), Error> {
+        self.setup_multiply(modulus, a, b)?;
+
+        for j in 0..modulus.dwords {
+            self.start_multiply(modulus, j);
+            self.wait().await;
+            self.start_reduce(modulus);
+            self.wait().await;
+            self.start_shift(modulus);
+            self.wait().await;
+            self.write_ram(RAM_W + modulus.dwords + 1, &[0, 0]);
+        }
+
+        self.finish_multiply(modulus, result)
+    }
+
+    /// Compute `result = a * b mod modulus`
+    ///
+    /// All numbers are big-endian, `a` and `b` must be smaller than the odd `modulus`, and
+    /// `result` must be as long as `modulus`.
+    pub async fn mod_mul(&mut self, a: &[u8], b: &[u8], modulus: &[u8], result: &mut [u8]) -> Result<(), Error> {
+        if result.len() != modulus.len() {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let modulus_ctx = Modulus::new(modulus)?;
+        let words = modulus_ctx.words();
+        let mut x = [0u32; MAX_WORDS];
+        let mut y = [0u32; MAX_WORDS];
+        modulus_ctx.load(a, &mut x)?;
+        modulus_ctx.load(b, &mut y)?;
+
+        // a * b / R, then multiplying by R^2 / R cancels out the remaining 1 / R
+        let mut t = [0u32; MAX_WORDS];
+        self.multiply_words(&modulus_ctx, operand(&x, words)?, operand(&y, words)?, &mut t)
+            .await?;
+        self.multiply_words(
+            &modulus_ctx,
+            operand(&t, words)?,
+            operand(&modulus_ctx.rr, words)?,
+            &mut x,
+        )
+        .await?;
+
+        bignum::to_be_bytes(operand(&x, words)?, result)
+    }
+
+    /// Compute `result = base ^ exponent mod modulus`
+    ///
+    /// All numbers are big-endian, `base` must be smaller than the odd `modulus`, and `result`
+    /// must be as long as `modulus`. The exponent may be secret, the same sequence of operations
+    /// is performed for every exponent of a given length.
+    pub async fn mod_exp(
+        &mut self,
+        base: &[u8],
+        exponent: &[u8],
+        modulus: &[u8],
+        result: &mut [u8],
+    ) -> Result<(), Error> {
+        if result.len() != modulus.len() || exponent.len() > MAX_MODULUS_LEN {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let modulus_ctx = Modulus::new(modulus)?;
+        let words = modulus_ctx.words();
+        let mut x = [0u32; MAX_WORDS];
+        modulus_ctx.load(base, &mut x)?;
+        let mut e = [0u32; MAX_WORDS];
+        bignum::from_be_bytes(exponent, &mut e)?;
+
+        // Montgomery ladder, r0 = 1 and r1 = base, both in the Montgomery domain
+        let one = one();
+        let mut r0 = [0u32; MAX_WORDS];
+        let mut r1 = [0u32; MAX_WORDS];
+        let mut t = [0u32; MAX_WORDS];
+        self.multiply_words(
+            &modulus_ctx,
+            operand(&modulus_ctx.rr, words)?,
+            operand(&one, words)?,
+            &mut r0,
+        )
+        .await?;
+        self.multiply_words(
+            &modulus_ctx,
+            operand(&x, words)?,
+            operand(&modulus_ctx.rr, words)?,
+            &mut r1,
+        )
+        .await?;
+
+        for i in (0..8 * exponent.len()).rev() {
+            let bit = bignum::bit(&e, i);
+            bignum::conditional_swap(&mut r0, &mut r1, bit);
+            self.multiply_words(&modulus_ctx, operand(&r0, words)?, operand(&r1, words)?, &mut t)
+                .await?;
+            r1 = t;
+            self.multiply_words(&modulus_ctx, operand(&r0, words)?, operand(&r0, words)?, &mut t)
+                .await?;
+            r0 = t;
+            bignum::conditional_swap(&mut r0, &mut r1, bit);
+        }
+
+        // Multiplying by one leaves the Montgomery domain
+        self.multiply_words(&modulus_ctx, operand(&r0, words)?, operand(&one, words)?, &mut t)
+            .await?;
+        bignum::to_be_bytes(operand(&t, words)?, result)
+    }
+}
+
+/// Montgomery multiplication, shared by the blocking and async drivers
+///
+/// The blocking implementation completes without ever returning `Poll::Pending`.
+trait Multiplier {
+    async fn multiply(&mut self, modulus: &Modulus, a: &[u32], b: &[u32], result: &mut [u32]) -> Result<(), Error>;
+}
+
+impl Multiplier for Casper<'_, Blocking> {
+    async fn multiply(&mut self, modulus: &Modulus, a: &[u32], b: &[u32], result: &mut [u32]) -> Result<(), Error> {
+        self.multiply_words(modulus, a, b, result)
+    }
+}
+
+impl Multiplier for Casper<'_, Async> {
+    async fn multiply(&mut self, modulus: &Modulus, a: &[u32], b: &[u32], result: &mut [u32]) -> Result<(), Error> {
+        self.multiply_words(modulus, a, b, result).await
+    }
+}
+
+/// The significant words of an operand
+fn operand(value: &[u32; MAX_WORDS], words: usize) -> Result<&[u32], Error> {
+    value.get(..words).ok_or(Error::UnsupportedConfiguration)
+}
+
+trait SealedInstance {}
+
+/// CASPER instance trait
+#[allow(private_bounds)]
+pub trait Instance: SealedInstance + PeripheralType {
+    /// Interrupt for this CASPER.
+    type Interrupt: interrupt::typelevel::Interrupt;
+}
+
+impl SealedInstance for crate::peripherals::CASPER {}
+
+impl Instance for crate::peripherals::CASPER {
+    type Interrupt = crate::interrupt::typelevel::CASPER;
+}
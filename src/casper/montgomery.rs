@@ -0,0 +1,82 @@
+//! Low-level Montgomery arithmetic, for building custom big-number algorithms
+//!
+//! Operands are slices of little-endian 32-bit words, exactly [`Modulus::words`] long and smaller
+//! than the modulus. Products stay in the Montgomery domain when both operands are in it.
+use core::cmp::Ordering;
+
+use super::{Async, Blocking, Casper, Error, MAX_WORDS, Modulus, bignum, one, operand};
+
+/// Check that every operand is shorter than the modulus and that the result has the right length
+fn check_operands(modulus: &Modulus, operands: &[&[u32]], result: &[u32]) -> Result<(), Error> {
+    let words = modulus.words();
+    if result.len() != words {
+        return Err(Error::UnsupportedConfiguration);
+    }
+
+    let n = operand(&modulus.n, words)?;
+    for a in operands {
+        if a.len() != words {
+            return Err(Error::UnsupportedConfiguration);
+        }
+        if bignum::compare(a, n) != Ordering::Less {
+            return Err(Error::InvalidOperand);
+        }
+    }
+    Ok(())
+}
+
+impl<'d> Casper<'d, Blocking> {
+    /// Montgomery product, `result = a * b / R mod n`
+    pub fn montgomery_multiply(
+        &mut self,
+        modulus: &Modulus,
+        a: &[u32],
+        b: &[u32],
+        result: &mut [u32],
+    ) -> Result<(), Error> {
+        check_operands(modulus, &[a, b], result)?;
+        self.multiply_words(modulus, a, b, result)
+    }
+
+    /// Convert into the Montgomery domain, `result = a * R mod n`
+    pub fn montgomery_encode(&mut self, modulus: &Modulus, a: &[u32], result: &mut [u32]) -> Result<(), Error> {
+        check_operands(modulus, &[a], result)?;
+        self.multiply_words(modulus, a, operand(&modulus.rr, modulus.words())?, result)
+    }
+
+    /// Montgomery reduction, `result = a / R mod n`, which converts out of the Montgomery domain
+    pub fn montgomery_reduce(&mut self, modulus: &Modulus, a: &[u32], result: &mut [u32]) -> Result<(), Error> {
+        check_operands(modulus, &[a], result)?;
+        let one: [u32; MAX_WORDS] = one();
+        self.multiply_words(modulus, a, operand(&one, modulus.words())?, result)
+    }
+}
+
+impl<'d> Casper<'d, Async> {
+    /// Montgomery product, `result = a * b / R mod n`
+    pub async fn montgomery_multiply(
+        &mut self,
+        modulus: &Modulus,
+        a: &[u32],
+        b: &[u32],
+        result: &mut [u32],
+    ) -> Result<(), Error> {
+        check_operands(modulus, &[a, b], result)?;
+        self.multiply_words(modulus, a, b, result).await
+    }
+
+    /// Convert into the Montgomery domain, `result = a * R mod n`
+    pub async fn montgomery_encode(&mut self, modulus: &Modulus, a: &[u32], result: &mut [u32]) -> Result<(), Error> {
+        check_operands(modulus, &[a], result)?;
+        self.multiply_words(modulus, a, operand(&modulus.rr, modulus.words())?, result)
+            .await
+    }
+
+    /// Montgomery reduction, `result = a / R mod n`, which converts out of the Montgomery domain
+    pub async fn montgomery_reduce(&mut self, modulus: &Modulus, a: &[u32], result: &mut [u32]) -> Result<(), Error> {
+        check_operands(modulus, &[a], result)?;
+        let one: [u32; MAX_WORDS] = one();
+        self.multiply_words(modulus, a, operand(&one, modulus.words())?, result)
+            .await
+    }
+}
@@ -0,0 +1,265 @@
+//! RSA signature verification with SHA-256, using PKCS#1 v1.5 or PSS padding
+//!
+//! Only the public exponent 65537 is supported, as used by practically every RSA key.
+use embassy_futures::block_on;
+
+use super::{Async, Blocking, Casper, Error, MAX_MODULUS_LEN};
+use crate::hashcrypt::Hashcrypt;
+use crate::hashcrypt::hasher::HASH_LEN;
+
+/// Public exponent, 65537
+pub const PUBLIC_EXPONENT: [u8; 3] = [0x01, 0x00, 0x01];
+
+/// DER encoding of the SHA-256 `DigestInfo` header, from RFC 8017 9.2
+const SHA256_DIGEST_INFO: [u8; 19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00, 0x04, 0x20,
+];
+
+/// Minimum number of 0xff bytes in a PKCS#1 v1.5 padding string
+const PKCS1_MIN_PADDING: usize = 8;
+
+/// Trailer byte of a PSS encoded message
+const PSS_TRAILER: u8 = 0xbc;
+
+/// Check a PKCS#1 v1.5 encoded message, `00 01 ff .. ff 00 DigestInfo hash`
+fn check_pkcs1v15(em: &[u8], hash: &[u8; HASH_LEN]) -> Result<(), Error> {
+    let (header, rest) = em.split_at_checked(2).ok_or(Error::InvalidSignature)?;
+    let padding_len = rest
+        .len()
+        .checked_sub(1 + SHA256_DIGEST_INFO.len() + HASH_LEN)
+        .filter(|len| *len >= PKCS1_MIN_PADDING)
+        .ok_or(Error::InvalidSignature)?;
+    let (padding, rest) = rest.split_at(padding_len);
+    let (separator, rest) = rest.split_at(1);
+    let (digest_info, digest) = rest.split_at(SHA256_DIGEST_INFO.len());
+
+    if header != [0x00, 0x01]
+        || padding.iter().any(|b| *b != 0xff)
+        || separator != [0x00]
+        || digest_info != SHA256_DIGEST_INFO
+        || digest != hash
+    {
+        return Err(Error::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// SHA-256 of data given in pieces, shared by the blocking and async drivers
+///
+/// The blocking implementation completes without ever returning `Poll::Pending`.
+trait Sha256 {
+    async fn sha256(&mut self, data: &[&[u8]], hash: &mut [u8; HASH_LEN]) -> Result<(), Error>;
+}
+
+impl Sha256 for Hashcrypt<'_, crate::hashcrypt::Blocking> {
+    async fn sha256(&mut self, data: &[&[u8]], hash: &mut [u8; HASH_LEN]) -> Result<(), Error> {
+        let mut hasher = self.new_sha256();
+        for part in data {
+            hasher.update(part)?;
+        }
+        Ok(hasher.finalize(&[], hash)?)
+    }
+}
+
+impl Sha256 for Hashcrypt<'_, crate::hashcrypt::Async> {
+    async fn sha256(&mut self, data: &[&[u8]], hash: &mut [u8; HASH_LEN]) -> Result<(), Error> {
+        let mut hasher = self.new_sha256();
+        for part in data {
+            hasher.update(part).await?;
+        }
+        Ok(hasher.finalize(&[], hash).await?)
+    }
+}
+
+/// Check a PSS encoded message with MGF1-SHA256, following EMSA-PSS-VERIFY from RFC 8017 9.1.2
+///
+/// `em` is the whole result of the public key operation, `modulus_bits` the exact size of the
+/// modulus.
+async fn check_pss<H: Sha256>(
+    hasher: &mut H,
+    em: &[u8],
+    modulus_bits: usize,
+    hash: &[u8; HASH_LEN],
+    salt_len: usize,
+) -> Result<(), Error> {
+    // The encoded message is one bit shorter than the modulus, dropping a leading zero byte
+    let em_bits = modulus_bits.saturating_sub(1);
+    let em_len = em_bits.div_ceil(8);
+    let (leading, em) = em
+        .split_at_checked(em.len().saturating_sub(em_len))
+        .ok_or(Error::InvalidSignature)?;
+    if leading.iter().any(|b| *b != 0) {
+        return Err(Error::InvalidSignature);
+    }
+
+    let db_len = em_len
+        .checked_sub(HASH_LEN + 1)
+        .filter(|len| *len > salt_len)
+        .ok_or(Error::InvalidSignature)?;
+    let (masked_db, rest) = em.split_at(db_len);
+    let (h, trailer) = rest.split_at(HASH_LEN);
+    // Bits above em_bits in the first byte must be clear
+    let top_mask = 0xffu8 >> (8 * em_len - em_bits);
+    if trailer != [PSS_TRAILER] || masked_db.first().is_some_and(|b| b & !top_mask != 0) {
+        return Err(Error::InvalidSignature);
+    }
+
+    // Unmask the data block with MGF1(h)
+    let mut db = [0u8; MAX_MODULUS_LEN];
+    let db = db.get_mut(..db_len).ok_or(Error::UnsupportedConfiguration)?;
+    db.copy_from_slice(masked_db);
+    for (counter, chunk) in db.chunks_mut(HASH_LEN).enumerate() {
+        let mut mask = [0u8; HASH_LEN];
+        hasher.sha256(&[h, &(counter as u32).to_be_bytes()], &mut mask).await?;
+        for (b, m) in chunk.iter_mut().zip(mask) {
+            *b ^= m;
+        }
+    }
+    if let Some(first) = db.first_mut() {
+        *first &= top_mask;
+    }
+
+    // DB is zero padding, a 0x01 separator and the salt
+    let (padding, rest) = db.split_at(db_len - salt_len - 1);
+    let (separator, salt) = rest.split_at(1);
+    if padding.iter().any(|b| *b != 0) || separator != [0x01] {
+        return Err(Error::InvalidSignature);
+    }
+
+    let mut expected = [0u8; HASH_LEN];
+    hasher.sha256(&[&[0u8; 8], hash, salt], &mut expected).await?;
+    if expected != h {
+        return Err(Error::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// Number of significant bits of a big-endian number
+fn bit_len(value: &[u8]) -> usize {
+    value
+        .iter()
+        .position(|b| *b != 0)
+        .and_then(|i| value.get(i).map(|b| 8 * (value.len() - i) - b.leading_zeros() as usize))
+        .unwrap_or(0)
+}
+
+/// Map the errors of the public key operation, a signature out of range is simply invalid
+fn invalid_operand(error: Error) -> Error {
+    match error {
+        Error::InvalidOperand => Error::InvalidSignature,
+        error => error,
+    }
+}
+
+impl<'d> Casper<'d, Blocking> {
+    /// Verify an RSA PKCS#1 v1.5 signature of a SHA-256 hash
+    ///
+    /// `modulus` and `signature` are big-endian and must have the same length, a 2048-bit key
+    /// has a 256 byte modulus.
+    pub fn rsa_verify(&mut self, modulus: &[u8], signature: &[u8], hash: &[u8; HASH_LEN]) -> Result<(), Error> {
+        if signature.len() != modulus.len() {
+            return Err(Error::InvalidSignature);
+        }
+        let mut em = [0u8; MAX_MODULUS_LEN];
+        let em = em.get_mut(..modulus.len()).ok_or(Error::UnsupportedConfiguration)?;
+        self.mod_exp(signature, &PUBLIC_EXPONENT, modulus, em)
+            .map_err(invalid_operand)?;
+
+        check_pkcs1v15(em, hash)
+    }
+
+    /// Verify an RSA PSS signature of a SHA-256 hash, with MGF1-SHA256 and a `salt_len` byte salt
+    ///
+    /// `hashcrypt` computes the mask and the salted hash. `modulus` and `signature` are
+    /// big-endian and must have the same length.
+    pub fn rsa_verify_pss(
+        &mut self,
+        hashcrypt: &mut Hashcrypt<'_, crate::hashcrypt::Blocking>,
+        modulus: &[u8],
+        signature: &[u8],
+        hash: &[u8; HASH_LEN],
+        salt_len: usize,
+    ) -> Result<(), Error> {
+        if signature.len() != modulus.len() {
+            return Err(Error::InvalidSignature);
+        }
+        let mut em = [0u8; MAX_MODULUS_LEN];
+        let em = em.get_mut(..modulus.len()).ok_or(Error::UnsupportedConfiguration)?;
+        self.mod_exp(signature, &PUBLIC_EXPONENT, modulus, em)
+            .map_err(invalid_operand)?;
+
+        // The blocking hasher never yields, this future completes on its first poll
+        block_on(check_pss(hashcrypt, em, bit_len(modulus), hash, salt_len))
+    }
+}
+
+impl<'d> Casper<'d, Async> {
+    /// Verify an RSA PKCS#1 v1.5 signature of a SHA-256 hash
+    ///
+    /// `modulus` and `signature` are big-endian and must have the same length, a 2048-bit key
+    /// has a 256 byte modulus.
+    pub async fn rsa_verify(&mut self, modulus: &[u8], signature: &[u8], hash: &[u8; HASH_LEN]) -> Result<(), Error> {
+        if signature.len() != modulus.len() {
+            return Err(Error::InvalidSignature);
+        }
+        let mut em = [0u8; MAX_MODULUS_LEN];
+        let em = em.get_mut(..modulus.len()).ok_or(Error::UnsupportedConfiguration)?;
+        self.mod_exp(signature, &PUBLIC_EXPONENT, modulus, em)
+            .await
+            .map_err(invalid_operand)?;
+
+        check_pkcs1v15(em, hash)
+    }
+
+    /// Verify an RSA PSS signature of a SHA-256 hash, with MGF1-SHA256 and a `salt_len` byte salt
+    ///
+    /// `hashcrypt` computes the mask and the salted hash. `modulus` and `signature` are
+    /// big-endian and must have the same length.
+    pub async fn rsa_verify_pss(
+        &mut self,
+        hashcrypt: &mut Hashcrypt<'_, crate::hashcrypt::Async>,
+        modulus: &[u8],
+        signature: &[u8],
+        hash: &[u8; HASH_LEN],
+        salt_len: usize,
+    ) -> Result<(), Error> {
+        if signature.len() != modulus.len() {
+            return Err(Error::InvalidSignature);
+        }
+        let mut em = [0u8; MAX_MODULUS_LEN];
+        let em = em.get_mut(..modulus.len()).ok_or(Error::UnsupportedConfiguration)?;
+        self.mod_exp(signature, &PUBLIC_EXPONENT, modulus, em)
+            .await
+            .map_err(invalid_operand)?;
+
+        check_pss(hashcrypt, em, bit_len(modulus), hash, salt_len).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pkcs1v15_encoding() {
+        let hash = [0x5a; HASH_LEN];
+        // 00 01, 74 bytes of padding, 00, then the DigestInfo and the hash
+        let mut em = [0xffu8; 128];
+        em[0] = 0x00;
+        em[1] = 0x01;
+        em[76] = 0x00;
+        em[77..96].copy_from_slice(&SHA256_DIGEST_INFO);
+        em[96..].copy_from_slice(&hash);
+        assert_eq!(check_pkcs1v15(&em, &hash), Ok(()));
+
+        em[5] = 0xfe;
+        assert_eq!(check_pkcs1v15(&em, &hash), Err(Error::InvalidSignature));
+    }
+
+    #[test]
+    fn modulus_bit_length() {
+        assert_eq!(bit_len(&[0x00, 0x00]), 0);
+        assert_eq!(bit_len(&[0x00, 0x01, 0x00]), 9);
+        assert_eq!(bit_len(&[0x80, 0x00]), 16);
+    }
+}
@@ -207,6 +207,7 @@ embassy_hal_internal::peripherals!(
     MRT0,
     MU_A,
     OS_EVENT,
+    OTP,
     PIN_INT0,
     PIN_INT1,
     PIN_INT2,
@@ -377,6 +378,7 @@ embassy_hal_internal::peripherals!(
     SGPIO_INTA,
     SGPIO_INTB,
     USBHSD,
+    USBHSDCD,
     USBHSH,
     USBPHY,
     USB_WAKEUP,
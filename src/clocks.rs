@@ -1,8 +1,11 @@
 //! Clock configuration for the `RT6xx`
+use core::cell::Cell;
 use core::sync::atomic::{AtomicU8, AtomicU32, Ordering};
 
 #[cfg(feature = "defmt")]
 use defmt;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use paste::paste;
 
 use crate::pac;
@@ -1504,6 +1507,245 @@ impl ClockOutConfig {
     }
 }
 
+/// Audio PLL sources
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AudioPllClkSrc {
+    /// SFRO clock, 16 MHz
+    Sfro,
+    /// External crystal oscillator
+    SysOsc,
+    /// FFRO clock divided by 2
+    FfroDiv2,
+}
+
+/// Audio PLL configuration
+///
+/// The PLL runs at `src × (mult + num / denom)`, PFD0 divides it by `pfd0 / 18` and the audio PLL
+/// clock divider by `div + 1`: the result feeds the flexcomm function clocks and MCLK.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AudioPllConfig {
+    /// PLL input
+    pub src: AudioPllClkSrc,
+    /// Integer multiplier, 16, 17, 20, 22, 27 or 33
+    pub mult: u8,
+    /// Numerator of the fractional multiplier
+    pub num: u32,
+    /// Denominator of the fractional multiplier, non-zero
+    pub denom: u32,
+    /// PFD0 fractional divider, 12 to 35
+    pub pfd0: u8,
+    /// Audio PLL clock divider, minus 1 encoded
+    pub div: u8,
+}
+
+impl AudioPllConfig {
+    /// 24.576 MHz from FFRO / 2, 512 × 48 kHz
+    #[must_use]
+    pub fn audio_24_576mhz() -> Self {
+        Self {
+            src: AudioPllClkSrc::FfroDiv2,
+            mult: 22,
+            num: 5040,
+            denom: 27000,
+            pfd0: 26,
+            div: 14,
+        }
+    }
+
+    /// 22.5792 MHz from FFRO / 2, 512 × 44.1 kHz
+    #[must_use]
+    pub fn audio_22_5792mhz() -> Self {
+        Self {
+            src: AudioPllClkSrc::FfroDiv2,
+            mult: 20,
+            num: 24480,
+            denom: 27000,
+            pfd0: 25,
+            div: 15,
+        }
+    }
+
+    fn src_freq(&self) -> u32 {
+        match self.src {
+            AudioPllClkSrc::Sfro => 16_000_000,
+            AudioPllClkSrc::SysOsc => SYS_OSC_DEFAULT_FREQ,
+            AudioPllClkSrc::FfroDiv2 => 24_000_000,
+        }
+    }
+
+    /// Audio PLL clock frequency (Hz) produced by this configuration
+    pub fn frequency(&self) -> Result<u32, ClockError> {
+        const VALIDMULTS: [u8; 6] = [16, 17, 20, 22, 27, 33];
+
+        if !VALIDMULTS.contains(&self.mult) {
+            return Err(ClockError::InvalidMult);
+        }
+
+        if self.denom == 0 || self.denom >= 1 << 30 || self.num >= self.denom || !(12..=35).contains(&self.pfd0) {
+            return Err(ClockError::InvalidDiv);
+        }
+
+        let vco = u64::from(self.src_freq()) * (u64::from(self.mult) * u64::from(self.denom) + u64::from(self.num))
+            / u64::from(self.denom);
+        let freq = vco * 18 / u64::from(self.pfd0) / (u64::from(self.div) + 1);
+
+        u32::try_from(freq).map_err(|_| ClockError::InvalidFrequency)
+    }
+}
+
+/// Frequency of the audio PLL clock, 0 while disabled
+static AUDIO_PLL_FREQ: AtomicU32 = AtomicU32::new(0);
+
+/// Frequency (Hz) of the audio PLL clock, if enabled
+#[must_use]
+pub fn audio_pll_frequency() -> Option<u32> {
+    match AUDIO_PLL_FREQ.load(Ordering::Relaxed) {
+        0 => None,
+        freq => Some(freq),
+    }
+}
+
+/// Power up the audio PLL and program it with `config`, returning its frequency (Hz)
+pub fn enable_audio_pll(config: &AudioPllConfig) -> Result<u32, ClockError> {
+    let freq = config.frequency()?;
+
+    // SAFETY: unsafe needed to take pointers to Sysctl0 and Clkctl1
+    let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
+    let sysctl0 = unsafe { crate::pac::Sysctl0::steal() };
+
+    // Power down the audio PLL before changing its fractional settings
+    sysctl0
+        .pdruncfg0_set()
+        .write(|w| w.audpllldo_pd().set_pdruncfg0().audpllana_pd().set_pdruncfg0());
+
+    clkctl1.audiopll0clksel().write(|w| match config.src {
+        AudioPllClkSrc::Sfro => w.sel().sfro_clk(),
+        AudioPllClkSrc::SysOsc => w.sel().xtal_clk(),
+        AudioPllClkSrc::FfroDiv2 => w.sel().ffro_div_2(),
+    });
+
+    // SAFETY: unsafe needed to write the bits for both num and denom, checked by frequency()
+    clkctl1.audiopll0num().write(|w| unsafe { w.num().bits(config.num) });
+    clkctl1
+        .audiopll0denom()
+        .write(|w| unsafe { w.denom().bits(config.denom) });
+
+    clkctl1.audiopll0ctl0().modify(|_, w| match config.mult {
+        16 => w.mult().div_16(),
+        17 => w.mult().div_17(),
+        20 => w.mult().div_20(),
+        22 => w.mult().div_22(),
+        27 => w.mult().div_27(),
+        _ => w.mult().div_33(),
+    });
+
+    // Clear audio PLL reset, and leave bypass
+    clkctl1
+        .audiopll0ctl0()
+        .modify(|_, w| w.reset().normal().bypass().programmed_clk());
+
+    // Power up the audio PLL
+    sysctl0
+        .pdruncfg0_clr()
+        .write(|w| w.audpllldo_pd().clr_pdruncfg0().audpllana_pd().clr_pdruncfg0());
+    delay_loop_clocks((150 & 0xFFFF) / 2, 12_000_000);
+
+    // Set audio PLL HOLDRINGOFF_ENA
+    clkctl1.audiopll0ctl0().modify(|_, w| w.holdringoff_ena().enable());
+    delay_loop_clocks((150 & 0xFFFF) / 2, 12_000_000);
+
+    // Clear audio PLL HOLDRINGOFF_ENA
+    clkctl1.audiopll0ctl0().modify(|_, w| w.holdringoff_ena().dsiable());
+    delay_loop_clocks((15 & 0xFFFF) / 2, 12_000_000);
+
+    // Disable the PFD0 output first
+    // SAFETY: unsafe needed to write the bits for pfd0
+    clkctl1
+        .audiopll0pfd()
+        .modify(|_, w| unsafe { w.pfd0().bits(0) }.pfd0_clkgate().gated());
+
+    // Set the new value and enable output, checked by frequency()
+    clkctl1
+        .audiopll0pfd()
+        .modify(|_, w| unsafe { w.pfd0().bits(config.pfd0) }.pfd0_clkgate().not_gated());
+
+    // Wait for output becomes stable
+    while clkctl1.audiopll0pfd().read().pfd0_clkrdy().bit_is_clear() {}
+
+    // Clear ready status flag
+    clkctl1.audiopll0pfd().modify(|_, w| w.pfd0_clkrdy().clear_bit());
+
+    // SAFETY: unsafe needed to write the bits for the divider
+    clkctl1
+        .audiopllclkdiv()
+        .modify(|_, w| unsafe { w.div().bits(config.div) }.halt().clear_bit());
+    while clkctl1.audiopllclkdiv().read().reqflag().bit_is_set() {}
+
+    AUDIO_PLL_FREQ.store(freq, Ordering::Relaxed);
+
+    Ok(freq)
+}
+
+/// Power down the audio PLL
+pub fn disable_audio_pll() {
+    // SAFETY: unsafe needed to take pointers to Sysctl0 and Clkctl1
+    let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
+    let sysctl0 = unsafe { crate::pac::Sysctl0::steal() };
+
+    clkctl1.audiopll0pfd().modify(|_, w| w.pfd0_clkgate().gated());
+    sysctl0
+        .pdruncfg0_set()
+        .write(|w| w.audpllldo_pd().set_pdruncfg0().audpllana_pd().set_pdruncfg0());
+
+    AUDIO_PLL_FREQ.store(0, Ordering::Relaxed);
+}
+
+/// Frequency (Hz) of the main PLL clock, read back from the system PLL and its PFD0, if running
+#[must_use]
+pub fn main_pll_clk_frequency() -> Option<u32> {
+    // SAFETY: unsafe needed to take pointers to Sysctl0 and Clkctl0, only to read the PLL setup
+    let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
+    let sysctl0 = unsafe { crate::pac::Sysctl0::steal() };
+
+    if sysctl0.pdruncfg0().read().syspllana_pd().is_power_down() {
+        return None;
+    }
+
+    let sel = clkctl0.syspll0clksel().read().sel();
+    let src_freq = if sel.is_sfro_clk() {
+        SFRO_FREQ
+    } else if sel.is_sysxtal_clk() {
+        SYS_OSC_DEFAULT_FREQ
+    } else if sel.is_ffro_div_2() {
+        if clkctl0.ffroctl0().read().trim_range().is_ffro_48mhz() {
+            FfroFreq::Ffro48m as u32 / 2
+        } else {
+            FfroFreq::Ffro60m as u32 / 2
+        }
+    } else {
+        return None;
+    };
+
+    let pfd = clkctl0.syspll0pfd().read();
+    if pfd.pfd0_clkgate().is_gated() || pfd.pfd0().bits() == 0 {
+        return None;
+    }
+
+    let ctl0 = clkctl0.syspll0ctl0().read();
+    let vco = if ctl0.bypass().is_bypass() {
+        u64::from(src_freq)
+    } else {
+        let mult = u64::from(ctl0.mult().bits());
+        let num = u64::from(clkctl0.syspll0num().read().num().bits());
+        let denom = u64::from(clkctl0.syspll0denom().read().denom().bits()).max(1);
+        u64::from(src_freq) * (mult * denom + num) / denom
+    };
+
+    u32::try_from(vco * 18 / u64::from(pfd.pfd0().bits())).ok()
+}
+
 /// Using the config, enables all desired clocks to desired clock rates
 fn init_clock_hw(config: ClockConfig) -> Result<(), ClockError> {
     config.rtc.enable_and_reset()?;
@@ -1593,6 +1835,225 @@ pub fn reset<T: SysconPeripheral>() {
 pub fn disable<T: SysconPeripheral>() {
     T::disable_perph_clock();
 }
+
+/// Power domains a peripheral can keep powered in deep sleep, to wake the chip from it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SleepDomain {
+    /// Low power oscillator
+    Lposc,
+    /// 16 MHz free running oscillator
+    Sfro,
+    /// Fast free running oscillator
+    Ffro,
+    /// Analog comparator
+    Acmp,
+}
+
+/// Start enable bit of a wake-up source, by its bit in STARTEN0 or STARTEN1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StartEnable {
+    Starten0(u8),
+    Starten1(u8),
+}
+
+/// Wake-up sources keeping a domain powered, and whether it was already kept powered before the
+/// first of them
+#[derive(Clone, Copy)]
+struct SleepDomainUsers {
+    count: u8,
+    was_powered: bool,
+}
+
+struct SleepDomains {
+    lposc: Cell<SleepDomainUsers>,
+    sfro: Cell<SleepDomainUsers>,
+    ffro: Cell<SleepDomainUsers>,
+    acmp: Cell<SleepDomainUsers>,
+}
+
+impl SleepDomains {
+    fn users(&self, domain: SleepDomain) -> &Cell<SleepDomainUsers> {
+        match domain {
+            SleepDomain::Lposc => &self.lposc,
+            SleepDomain::Sfro => &self.sfro,
+            SleepDomain::Ffro => &self.ffro,
+            SleepDomain::Acmp => &self.acmp,
+        }
+    }
+}
+
+const NO_USERS: SleepDomainUsers = SleepDomainUsers {
+    count: 0,
+    was_powered: false,
+};
+
+static SLEEP_DOMAINS: Mutex<CriticalSectionRawMutex, SleepDomains> = Mutex::const_new(
+    CriticalSectionRawMutex::new(),
+    SleepDomains {
+        lposc: Cell::new(NO_USERS),
+        sfro: Cell::new(NO_USERS),
+        ffro: Cell::new(NO_USERS),
+        acmp: Cell::new(NO_USERS),
+    },
+);
+
+fn sleep_powered(sysctl0: &pac::Sysctl0, domain: SleepDomain) -> bool {
+    let cfg = sysctl0.pdsleepcfg0().read();
+    match domain {
+        SleepDomain::Lposc => cfg.lposc_pd().is_enabled(),
+        SleepDomain::Sfro => cfg.sfro_pd().is_enabled(),
+        SleepDomain::Ffro => cfg.ffro_pd().is_enabled(),
+        SleepDomain::Acmp => cfg.acmp_pd().is_enabled(),
+    }
+}
+
+fn set_sleep_powered(sysctl0: &pac::Sysctl0, domain: SleepDomain, powered: bool) {
+    sysctl0.pdsleepcfg0().modify(|_, w| match (domain, powered) {
+        (SleepDomain::Lposc, true) => w.lposc_pd().enabled(),
+        (SleepDomain::Lposc, false) => w.lposc_pd().power_down(),
+        (SleepDomain::Sfro, true) => w.sfro_pd().enabled(),
+        (SleepDomain::Sfro, false) => w.sfro_pd().power_down(),
+        (SleepDomain::Ffro, true) => w.ffro_pd().enabled(),
+        (SleepDomain::Ffro, false) => w.ffro_pd().power_down(),
+        (SleepDomain::Acmp, true) => w.acmp_pd().enabled(),
+        (SleepDomain::Acmp, false) => w.acmp_pd().power_down(),
+    });
+}
+
+/// Keeps power domains powered in deep sleep and a start enable bit set, so that a peripheral can
+/// wake the chip. Both are undone when dropped.
+#[must_use]
+pub(crate) struct WakeupGuard {
+    domains: &'static [SleepDomain],
+    start: StartEnable,
+}
+
+/// Let a peripheral wake the chip from deep sleep: set its start enable bit `start`, and keep
+/// `domains` powered in deep sleep. Domains are shared, the last guard dropped powers one down
+/// again, unless it was kept powered before the first guard.
+pub(crate) fn enable_wakeup(domains: &'static [SleepDomain], start: StartEnable) -> WakeupGuard {
+    // SAFETY: unsafe needed to take pointer to Sysctl0, PDSLEEPCFG0 is only modified in a critical
+    // section, the start enable bits have their own set and clear registers
+    let sysctl0 = unsafe { pac::Sysctl0::steal() };
+
+    critical_section::with(|cs| {
+        for &domain in domains {
+            let users = SLEEP_DOMAINS.borrow(cs).users(domain);
+            let mut state = users.get();
+
+            if state.count == 0 {
+                state.was_powered = sleep_powered(&sysctl0, domain);
+                set_sleep_powered(&sysctl0, domain, true);
+            }
+
+            state.count += 1;
+            users.set(state);
+        }
+
+        // SAFETY: unsafe due to the use of bits()
+        match start {
+            StartEnable::Starten0(bit) => sysctl0.starten0_set().write(|w| unsafe { w.bits(1 << bit) }),
+            StartEnable::Starten1(bit) => sysctl0.starten1_set().write(|w| unsafe { w.bits(1 << bit) }),
+        };
+    });
+
+    WakeupGuard { domains, start }
+}
+
+impl Drop for WakeupGuard {
+    fn drop(&mut self) {
+        // SAFETY: unsafe needed to take pointer to Sysctl0, as in enable_wakeup()
+        let sysctl0 = unsafe { pac::Sysctl0::steal() };
+
+        critical_section::with(|cs| {
+            // SAFETY: unsafe due to the use of bits()
+            match self.start {
+                StartEnable::Starten0(bit) => sysctl0.starten0_clr().write(|w| unsafe { w.bits(1 << bit) }),
+                StartEnable::Starten1(bit) => sysctl0.starten1_clr().write(|w| unsafe { w.bits(1 << bit) }),
+            };
+
+            for &domain in self.domains {
+                let users = SLEEP_DOMAINS.borrow(cs).users(domain);
+                let mut state = users.get();
+
+                state.count -= 1;
+                if state.count == 0 && !state.was_powered {
+                    set_sleep_powered(&sysctl0, domain, false);
+                }
+
+                users.set(state);
+            }
+        });
+    }
+}
+
+/// Source of the HWWAKE register, which keeps the peripheral clocks running in deep sleep while it is
+/// busy, without waking the core
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HwWake {
+    /// The DMIC FIFO reaching its trigger level
+    Dmic,
+    /// DMA controller 0 being busy
+    Dmac0,
+}
+
+/// Wake-up guards using each [`HwWake`] source
+static HW_WAKE_USERS: Mutex<CriticalSectionRawMutex, [Cell<u8>; 2]> =
+    Mutex::const_new(CriticalSectionRawMutex::new(), [const { Cell::new(0) }; 2]);
+
+fn set_hw_wake(sysctl0: &pac::Sysctl0, source: HwWake, enabled: bool) {
+    sysctl0.hwwake().modify(|_, w| match source {
+        HwWake::Dmic => w.dmicwake().bit(enabled),
+        HwWake::Dmac0 => w.dmac0wake().bit(enabled),
+    });
+}
+
+/// Keeps [`HwWake`] sources enabled, undone when dropped.
+#[must_use]
+pub(crate) struct HwWakeGuard {
+    sources: &'static [HwWake],
+}
+
+/// Keep the peripheral clocks running in deep sleep while one of `sources` is busy. Sources are
+/// shared, the last guard dropped disables one again.
+pub(crate) fn enable_hw_wake(sources: &'static [HwWake]) -> HwWakeGuard {
+    // SAFETY: unsafe needed to take pointer to Sysctl0, HWWAKE is only modified in a critical section
+    let sysctl0 = unsafe { pac::Sysctl0::steal() };
+
+    critical_section::with(|cs| {
+        let users = HW_WAKE_USERS.borrow(cs);
+        for &source in sources {
+            if let Some(count) = users.get(source as usize) {
+                if count.get() == 0 {
+                    set_hw_wake(&sysctl0, source, true);
+                }
+                count.set(count.get() + 1);
+            }
+        }
+    });
+
+    HwWakeGuard { sources }
+}
+
+impl Drop for HwWakeGuard {
+    fn drop(&mut self) {
+        // SAFETY: unsafe needed to take pointer to Sysctl0, as in enable_hw_wake()
+        let sysctl0 = unsafe { pac::Sysctl0::steal() };
+
+        critical_section::with(|cs| {
+            let users = HW_WAKE_USERS.borrow(cs);
+            for &source in self.sources {
+                if let Some(count) = users.get(source as usize) {
+                    count.set(count.get() - 1);
+                    if count.get() == 0 {
+                        set_hw_wake(&sysctl0, source, false);
+                    }
+                }
+            }
+        });
+    }
+}
+
 macro_rules! impl_perph_clk {
     ($peripheral:ident, $clkctl:ident, $clkreg:ident, $rstctl:ident, $rstreg:ident, $bit:expr) => {
         impl SealedSysconPeripheral for crate::peripherals::$peripheral {
@@ -4,9 +4,28 @@ use core::marker::PhantomData;
 
 use embassy_sync::waitqueue::AtomicWaker;
 
-use super::{BufferStatus, DESCRIPTORS, PING_DESCRIPTORS, PING_PONG_STATUS, PONG_DESCRIPTORS, PingPongSelector};
+use super::{
+    BufferStatus, DESCRIPTORS, Error, LinkedDescriptor, MAX_TRANSFER_COUNT, PING_DESCRIPTORS, PING_PONG_STATUS,
+    PONG_DESCRIPTORS, PingPongSelector,
+};
 use crate::dma::DmaInfo;
 use crate::dma::transfer::{Direction, Transfer, TransferOptions};
+use crate::pac::inputmux::dmac0_itrig_sel::Dma0ItrigSel;
+
+/// Part of a chained transfer, moved by a single descriptor
+#[derive(Clone, Copy)]
+pub(crate) struct Segment {
+    /// Start of the source
+    pub(crate) src: *const u32,
+    /// Whether the source address advances after each transfer
+    pub(crate) src_inc: bool,
+    /// Start of the destination
+    pub(crate) dst: *mut u32,
+    /// Whether the destination address advances after each transfer
+    pub(crate) dst_inc: bool,
+    /// Length in bytes, a multiple of the transfer width of at most [`MAX_TRANSFER_COUNT`] transfers
+    pub(crate) len: usize,
+}
 
 /// DMA channel
 pub struct Channel<'d> {
@@ -32,6 +51,17 @@ impl<'d> Channel<'d> {
         Transfer::new_write(self, buf, peri_addr, options)
     }
 
+    /// Writes several memory buffers, one after the other, to a peripheral
+    pub fn write_gather_to_peripheral(
+        &'d self,
+        bufs: &[&'d [u8]],
+        descriptors: &'d mut [LinkedDescriptor],
+        peri_addr: *mut u8,
+        options: TransferOptions,
+    ) -> Result<Transfer<'d>, Error> {
+        Transfer::new_write_gather(self, bufs, descriptors, peri_addr, options)
+    }
+
     /// Writes from a memory buffer to another memory buffer
     pub fn write_to_memory(
         &'d self,
@@ -160,6 +190,294 @@ impl<'d> Channel<'d> {
         });
     }
 
+    /// Prepare the DMA channel to read a peripheral FIFO that is mapped as a window of registers
+    ///
+    /// The source address walks through the `window_len` bytes at `window` and wraps back to its
+    /// start after every burst, so each peripheral request reads the next word of the window.
+    ///
+    /// # Note
+    ///
+    /// `window_len` must be a power of two number of transfers with the window aligned to it, and
+    /// `mem_len` must be a multiple of `window_len` of at most [`MAX_TRANSFER_COUNT`] transfers.
+    pub fn configure_channel_fifo_window(
+        &self,
+        window: *const u32,
+        window_len: usize,
+        dstbase: *mut u32,
+        mem_len: usize,
+        options: TransferOptions,
+    ) -> Result<(), Error> {
+        let xferwidth: usize = options.width.byte_width();
+        let burst = window_len / xferwidth;
+        if !burst.is_power_of_two()
+            || burst > MAX_TRANSFER_COUNT
+            || !(window as usize).is_multiple_of(window_len)
+            || mem_len == 0
+            || !mem_len.is_multiple_of(window_len)
+            || mem_len / xferwidth > MAX_TRANSFER_COUNT
+        {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        // Both addresses increment like in a memory-to-memory transfer, the request and the wrap
+        // are set up below
+        self.configure_channel(Direction::MemoryToMemory, window, dstbase, mem_len, options);
+
+        let channel = self.info.ch_num;
+
+        // Panic safety: `info()` would have returned None if our channel number was out of bounds and thus would never get here
+        // SAFETY: unsafe due to use of a mutable static (DESCRIPTORS.list)
+        #[allow(clippy::indexing_slicing)]
+        let descriptor = unsafe { &mut DESCRIPTORS.list[channel] };
+
+        // NOTE: with a wrapped source, the DMA controller expects the end address of the first burst
+        descriptor.src_data_end_addr = window as u32 + (window_len - xferwidth) as u32;
+
+        // Paced by the peripheral requests, with the source wrapping around the window
+        // SAFETY: unsafe due to .bits usage
+        self.info.regs.channel(channel).cfg().modify(|_, w| unsafe {
+            w.periphreqen().set_bit();
+            w.srcburstwrap().set_bit();
+            w.burstpower().bits(burst.trailing_zeros() as u8)
+        });
+
+        Ok(())
+    }
+
+    /// Prepare the DMA channel to read a peripheral that paces the transfer through a hardware trigger
+    ///
+    /// `source` is routed to this channel through the input trigger multiplexer, and transfers
+    /// continue for as long as it is asserted.
+    ///
+    /// # Note
+    ///
+    /// `mem_len` must be a non-zero multiple of the transfer width of at most
+    /// [`MAX_TRANSFER_COUNT`] transfers.
+    pub(crate) fn configure_channel_triggered(
+        &self,
+        source: Dma0ItrigSel,
+        peri_addr: *const u32,
+        dstbase: *mut u32,
+        mem_len: usize,
+        options: TransferOptions,
+    ) -> Result<(), Error> {
+        let xferwidth: usize = options.width.byte_width();
+        let channel = self.info.ch_num;
+        // The trigger enable register only covers the first 32 channels
+        if channel >= 32
+            || mem_len == 0
+            || !mem_len.is_multiple_of(xferwidth)
+            || mem_len / xferwidth > MAX_TRANSFER_COUNT
+        {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        self.configure_channel(Direction::PeripheralToMemory, peri_addr, dstbase, mem_len, options);
+
+        // SAFETY: the trigger multiplexer of a channel is only used by the owner of the channel
+        let inputmux = unsafe { crate::pac::Inputmux::steal() };
+        inputmux
+            .dmac0_itrig_sel(channel)
+            .write(|w| w.dma0_itrig_sel().variant(source));
+        inputmux
+            .dmac0_itrig_ena0_set()
+            .write(|w| unsafe { w.bits(1 << channel) });
+
+        // Paced by the active high trigger level instead of a peripheral request, one transfer at
+        // a time
+        // SAFETY: unsafe due to .bits usage
+        self.info.regs.channel(channel).cfg().modify(|_, w| unsafe {
+            w.periphreqen().clear_bit();
+            w.hwtrigen().set_bit();
+            w.trigpol().active_high_rising();
+            w.trigtype().level();
+            w.trigburst().burst();
+            w.burstpower().bits(0)
+        });
+
+        Ok(())
+    }
+
+    /// Prepare the DMA channel for a memory-to-peripheral transfer gathered from several buffers
+    ///
+    /// The first [`MAX_TRANSFER_COUNT`] transfers use the channel's own descriptor, everything
+    /// else is chained through `descriptors`. Nothing is touched if `descriptors` is too short.
+    ///
+    /// # Note
+    ///
+    /// Buffer lengths should be a multiple of the transfer width, otherwise transfer count will be rounded down
+    pub fn configure_channel_gather(
+        &self,
+        bufs: &[&[u8]],
+        dstbase: *mut u32,
+        descriptors: &mut [LinkedDescriptor],
+        options: TransferOptions,
+    ) -> Result<(), Error> {
+        let xferwidth: usize = options.width.byte_width();
+        let segments = bufs
+            .iter()
+            .flat_map(move |buf| buf.chunks(MAX_TRANSFER_COUNT * xferwidth))
+            .filter(move |chunk| chunk.len() >= xferwidth)
+            .map(move |chunk| Segment {
+                src: chunk.as_ptr() as *const u32,
+                src_inc: true,
+                dst: dstbase,
+                dst_inc: false,
+                len: chunk.len() / xferwidth * xferwidth,
+            });
+
+        self.configure_channel_chain(true, segments, descriptors, options)
+    }
+
+    /// Prepare the DMA channel for a transfer of `mem_len` bytes, more than a single descriptor can move
+    ///
+    /// The first [`MAX_TRANSFER_COUNT`] transfers use the channel's own descriptor, the rest of the
+    /// memory buffer is chained through `descriptors`. Nothing is touched if `descriptors` is too short.
+    ///
+    /// # Note
+    ///
+    /// `mem_len` should be a multiple of the transfer width, otherwise transfer count will be rounded down
+    pub fn configure_channel_linked(
+        &self,
+        dir: Direction,
+        srcbase: *const u32,
+        dstbase: *mut u32,
+        mem_len: usize,
+        descriptors: &mut [LinkedDescriptor],
+        options: TransferOptions,
+    ) -> Result<(), Error> {
+        let xferwidth: usize = options.width.byte_width();
+        let chunk_len = MAX_TRANSFER_COUNT * xferwidth;
+        let count = (mem_len / xferwidth).div_ceil(MAX_TRANSFER_COUNT);
+        let src_inc = dir != Direction::PeripheralToMemory;
+        let dst_inc = dir != Direction::MemoryToPeripheral;
+
+        let segments = (0..count).map(move |i| {
+            let offset = i * chunk_len;
+            Segment {
+                src: if src_inc {
+                    srcbase.wrapping_byte_add(offset)
+                } else {
+                    srcbase
+                },
+                src_inc,
+                dst: if dst_inc {
+                    dstbase.wrapping_byte_add(offset)
+                } else {
+                    dstbase
+                },
+                dst_inc,
+                len: (mem_len - offset).min(chunk_len) / xferwidth * xferwidth,
+            }
+        });
+
+        self.configure_channel_chain(dir != Direction::MemoryToMemory, segments, descriptors, options)
+    }
+
+    /// Prepare the DMA channel to move `segments` one after the other, paced by the peripheral
+    /// requests if `periphreq` is set
+    ///
+    /// The first segment uses the channel's own descriptor, the others are chained through
+    /// `descriptors`. Nothing is touched if `descriptors` is too short or a segment doesn't fit
+    /// into a descriptor.
+    pub(crate) fn configure_channel_chain(
+        &self,
+        periphreq: bool,
+        mut segments: impl Iterator<Item = Segment> + Clone,
+        descriptors: &mut [LinkedDescriptor],
+        options: TransferOptions,
+    ) -> Result<(), Error> {
+        let xferwidth: usize = options.width.byte_width();
+        let count = segments.clone().count();
+        if count == 0
+            || count - 1 > descriptors.len()
+            || segments
+                .clone()
+                .any(|segment| segment.len < xferwidth || segment.len / xferwidth > MAX_TRANSFER_COUNT)
+        {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let channel = self.info.ch_num;
+
+        // Configure for transfer type, no hardware triggering (we'll trigger via software), high priority
+        // SAFETY: unsafe due to .bits usage
+        self.info.regs.channel(channel).cfg().write(|w| unsafe {
+            w.periphreqen().bit(periphreq);
+            w.hwtrigen().clear_bit();
+            w.chpriority().bits(0)
+        });
+
+        // Enable the interrupt on this channel
+        self.info
+            .regs
+            .intenset0()
+            .write(|w| unsafe { w.inten().bits(1 << channel) });
+
+        // Every descriptor but the last reloads the next one and keeps the trigger set, only the
+        // last one raises the interrupt
+        let xfercfg = |segment: &Segment, last: bool| {
+            // SAFETY: unsafe due to .bits usage
+            self.info.regs.channel(channel).xfercfg().write(|w| unsafe {
+                w.cfgvalid().set_bit();
+                w.clrtrig().bit(last);
+                w.reload().bit(!last);
+                w.setinta().bit(last);
+                w.width().bits(options.width.into());
+                w.srcinc().bits(segment.src_inc.into());
+                w.dstinc().bits(segment.dst_inc.into());
+                w.xfercount().bits((segment.len / xferwidth - 1) as u16)
+            });
+            self.info.regs.channel(channel).xfercfg().read().bits()
+        };
+
+        // Descriptor `i` holds segment `i + 1` and links to descriptor `i + 1`
+        let linked_base = descriptors.as_ptr() as u32;
+        let link_addr = |i: usize| {
+            if i < count - 1 {
+                linked_base + (i * size_of::<LinkedDescriptor>()) as u32
+            } else {
+                0
+            }
+        };
+
+        // NOTE: the DMA controller expects the memory buffer end address but peripheral address is actual
+        let end = |base: u32, inc: bool, len: usize| {
+            if inc {
+                base + (len / xferwidth - 1) as u32 * xferwidth as u32
+            } else {
+                base
+            }
+        };
+
+        // The linked descriptors are set up first, since computing their settings goes through the
+        // XFERCFG register, which must be left with the settings of the first segment.
+        for ((i, segment), descriptor) in segments.clone().enumerate().skip(1).zip(descriptors.iter_mut()) {
+            let descriptor = &mut descriptor.inner;
+            descriptor.reserved = xfercfg(&segment, i == count - 1);
+            descriptor.src_data_end_addr = end(segment.src as u32, segment.src_inc, segment.len);
+            descriptor.dst_data_end_addr = end(segment.dst as u32, segment.dst_inc, segment.len);
+            descriptor.nxt_desc_link_addr = link_addr(i);
+        }
+
+        let Some(first) = segments.next() else {
+            return Err(Error::UnsupportedConfiguration);
+        };
+
+        // Panic safety: `info()` would have returned None if our channel number was out of bounds and thus would never get here
+        // SAFETY: unsafe due to use of a mutable static (DESCRIPTORS.list)
+        #[allow(clippy::indexing_slicing)]
+        let descriptor = unsafe { &mut DESCRIPTORS.list[channel] };
+
+        descriptor.reserved = 0;
+        descriptor.src_data_end_addr = end(first.src as u32, first.src_inc, first.len);
+        descriptor.dst_data_end_addr = end(first.dst as u32, first.dst_inc, first.len);
+        descriptor.nxt_desc_link_addr = link_addr(0);
+        xfercfg(&first, count == 1);
+
+        Ok(())
+    }
+
     /// Configure the DMA channel for ping-pong (double buffer) transfer
     ///
     /// # Note
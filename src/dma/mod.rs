@@ -40,6 +40,39 @@ struct DescriptorBlock {
     list: [ChannelDescriptor; DMA_CHANNEL_COUNT],
 }
 
+/// Maximum number of transfers a single descriptor can perform
+pub const MAX_TRANSFER_COUNT: usize = 1024;
+
+/// Descriptor used to chain additional buffers onto a transfer
+///
+/// Linked descriptors are read by the DMA controller while the transfer is running, so they
+/// must stay in place until it completes.
+#[derive(Copy, Clone, Debug)]
+#[repr(C, align(16))]
+pub struct LinkedDescriptor {
+    inner: ChannelDescriptor,
+}
+
+impl LinkedDescriptor {
+    /// Create an empty descriptor
+    pub const fn new() -> Self {
+        Self {
+            inner: ChannelDescriptor {
+                reserved: 0,
+                src_data_end_addr: 0,
+                dst_data_end_addr: 0,
+                nxt_desc_link_addr: 0,
+            },
+        }
+    }
+}
+
+impl Default for LinkedDescriptor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// DMA channel descriptor list
 static mut DESCRIPTORS: DescriptorBlock = DescriptorBlock {
     list: [ChannelDescriptor {
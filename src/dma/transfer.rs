@@ -4,7 +4,9 @@ use core::future::Future;
 use core::pin::Pin;
 use core::task::{Context, Poll};
 
-use crate::dma::channel::Channel;
+use crate::dma::channel::{Channel, Segment};
+use crate::dma::{Error, LinkedDescriptor};
+use crate::pac::inputmux::dmac0_itrig_sel::Dma0ItrigSel;
 
 /// DMA transfer options
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -130,6 +132,200 @@ impl<'d> Transfer<'d> {
         )
     }
 
+    /// Reads from a peripheral register into `len` bytes at `buf` using DMA
+    ///
+    /// # Safety
+    ///
+    /// `buf` must stay valid for writes of `len` bytes until the transfer completes or is dropped.
+    pub(crate) unsafe fn new_read_raw(
+        channel: &'d Channel<'d>,
+        peri_addr: *const u8,
+        buf: *mut u8,
+        len: usize,
+        options: TransferOptions,
+    ) -> Self {
+        Self::new_inner_transfer(
+            channel,
+            Direction::PeripheralToMemory,
+            peri_addr as *const u32,
+            buf as *mut u32,
+            len,
+            options,
+        )
+    }
+
+    /// Writes `len` bytes at `buf` into a peripheral register using DMA
+    ///
+    /// # Safety
+    ///
+    /// `buf` must stay valid for reads of `len` bytes until the transfer completes or is dropped.
+    pub(crate) unsafe fn new_write_raw(
+        channel: &'d Channel<'d>,
+        buf: *const u8,
+        len: usize,
+        peri_addr: *mut u8,
+        options: TransferOptions,
+    ) -> Self {
+        Self::new_inner_transfer(
+            channel,
+            Direction::MemoryToPeripheral,
+            buf as *const u32,
+            peri_addr as *mut u32,
+            len,
+            options,
+        )
+    }
+
+    /// Reads from a peripheral register into `len` bytes at `buf` using DMA, chaining
+    /// `descriptors` when `len` is more than [`MAX_TRANSFER_COUNT`](super::MAX_TRANSFER_COUNT)
+    /// transfers
+    ///
+    /// # Safety
+    ///
+    /// `buf` must stay valid for writes of `len` bytes until the transfer completes or is dropped.
+    pub(crate) unsafe fn new_read_linked(
+        channel: &'d Channel<'d>,
+        peri_addr: *const u8,
+        buf: *mut u8,
+        len: usize,
+        descriptors: &'d mut [LinkedDescriptor],
+        options: TransferOptions,
+    ) -> Result<Self, Error> {
+        channel.configure_channel_linked(
+            Direction::PeripheralToMemory,
+            peri_addr as *const u32,
+            buf as *mut u32,
+            len,
+            descriptors,
+            options,
+        )?;
+
+        channel.enable_channel();
+        channel.trigger_channel();
+
+        Ok(Self { _inner: channel })
+    }
+
+    /// Reads from a peripheral FIFO mapped as a window of `window_len` bytes at `window` into
+    /// `len` bytes at `buf` using DMA
+    ///
+    /// See [`Channel::configure_channel_fifo_window()`] for the supported lengths.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must stay valid for writes of `len` bytes until the transfer completes or is dropped.
+    pub(crate) unsafe fn new_read_fifo_window(
+        channel: &'d Channel<'d>,
+        window: *const u8,
+        window_len: usize,
+        buf: *mut u8,
+        len: usize,
+        options: TransferOptions,
+    ) -> Result<Self, Error> {
+        channel.configure_channel_fifo_window(window as *const u32, window_len, buf as *mut u32, len, options)?;
+
+        channel.enable_channel();
+        channel.trigger_channel();
+
+        Ok(Self { _inner: channel })
+    }
+
+    /// Reads from a peripheral register into `len` bytes at `buf` using DMA, with every transfer
+    /// paced by the hardware trigger `source`
+    ///
+    /// See [`Channel::configure_channel_triggered()`] for the supported lengths.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must stay valid for writes of `len` bytes until the transfer completes or is dropped.
+    pub(crate) unsafe fn new_read_triggered(
+        channel: &'d Channel<'d>,
+        source: Dma0ItrigSel,
+        peri_addr: *const u8,
+        buf: *mut u8,
+        len: usize,
+        options: TransferOptions,
+    ) -> Result<Self, Error> {
+        channel.configure_channel_triggered(source, peri_addr as *const u32, buf as *mut u32, len, options)?;
+
+        // No software trigger, the first hardware trigger starts the transfer
+        channel.enable_channel();
+
+        Ok(Self { _inner: channel })
+    }
+
+    /// Writes `len` bytes at `buf` into a peripheral register using DMA, chaining `descriptors`
+    /// when `len` is more than [`MAX_TRANSFER_COUNT`](super::MAX_TRANSFER_COUNT) transfers
+    ///
+    /// # Safety
+    ///
+    /// `buf` must stay valid for reads of `len` bytes until the transfer completes or is dropped.
+    pub(crate) unsafe fn new_write_linked(
+        channel: &'d Channel<'d>,
+        buf: *const u8,
+        len: usize,
+        peri_addr: *mut u8,
+        descriptors: &'d mut [LinkedDescriptor],
+        options: TransferOptions,
+    ) -> Result<Self, Error> {
+        channel.configure_channel_linked(
+            Direction::MemoryToPeripheral,
+            buf as *const u32,
+            peri_addr as *mut u32,
+            len,
+            descriptors,
+            options,
+        )?;
+
+        channel.enable_channel();
+        channel.trigger_channel();
+
+        Ok(Self { _inner: channel })
+    }
+
+    /// Moves `segments` one after the other using DMA, paced by the peripheral requests
+    ///
+    /// `descriptors` needs one entry for each segment after the first, nothing is started if it
+    /// is too short.
+    ///
+    /// # Safety
+    ///
+    /// Every segment must stay valid for its reads and writes until the transfer completes or is
+    /// dropped.
+    pub(crate) unsafe fn new_chain(
+        channel: &'d Channel<'d>,
+        segments: impl Iterator<Item = Segment> + Clone,
+        descriptors: &'d mut [LinkedDescriptor],
+        options: TransferOptions,
+    ) -> Result<Self, Error> {
+        channel.configure_channel_chain(true, segments, descriptors, options)?;
+
+        channel.enable_channel();
+        channel.trigger_channel();
+
+        Ok(Self { _inner: channel })
+    }
+
+    /// Writes several memory buffers, one after the other, into a peripheral register using DMA
+    ///
+    /// Buffers are split into chunks of at most [`MAX_TRANSFER_COUNT`](super::MAX_TRANSFER_COUNT)
+    /// transfers, and `descriptors` needs one entry for each chunk after the first. Nothing is
+    /// started if it is too short.
+    pub fn new_write_gather(
+        channel: &'d Channel<'d>,
+        bufs: &[&'d [u8]],
+        descriptors: &'d mut [LinkedDescriptor],
+        peri_addr: *mut u8,
+        options: TransferOptions,
+    ) -> Result<Self, Error> {
+        channel.configure_channel_gather(bufs, peri_addr as *mut u32, descriptors, options)?;
+
+        channel.enable_channel();
+        channel.trigger_channel();
+
+        Ok(Self { _inner: channel })
+    }
+
     /// Writes a memory buffer into another memory buffer using DMA
     pub fn new_write_mem(
         channel: &'d Channel<'d>,
@@ -180,7 +376,13 @@ impl Future for Transfer<'_> {
         // wake will deregister the waker.
         self._inner.get_waker().register(cx.waker());
 
-        if self._inner.info.regs.active0().read().act().bits() & (1 << channel) == 0 {
+        // A hardware triggered channel is not active until it sees its first trigger, so it is only
+        // done once its descriptor has been used up as well
+        let regs = self._inner.info.regs.channel(channel);
+        let waiting_for_trigger =
+            regs.cfg().read().hwtrigen().bit_is_set() && regs.xfercfg().read().cfgvalid().bit_is_set();
+
+        if self._inner.info.regs.active0().read().act().bits() & (1 << channel) == 0 && !waiting_for_trigger {
             Poll::Ready(())
         } else {
             Poll::Pending
@@ -0,0 +1,824 @@
+//! Digital Microphone (DMIC)
+//!
+//! Captures one or two PDM microphones sharing a data line: the left one on channel 0, which also
+//! feeds the hardware voice activity detector (HWVAD), and the right one on channel 1.
+//! [`Dmic::wait_for_voice`] sleeps until someone speaks, [`RingBufferedDmic`] streams the left
+//! microphone into memory.
+//!
+//! Each channel decimates the PDM stream by the OSR in a CIC filter, then by 2 in a half-band
+//! filter: the output rate is the PDM clock / (2 x OSR), twice that with [`OutputRate::Fs2`].
+//!
+//! With [`Config::wake_on_voice`], the DMIC keeps running in deep sleep and
+//! [`Dmic::wait_for_voice`] wakes the chip: [`FunctionClock::Lposc`] is the clock to pick then,
+//! its 1 MHz PDM clock is plenty for voice detection.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Poll;
+
+use embassy_hal_internal::Peri;
+use embassy_hal_internal::interrupt::InterruptExt;
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::clocks::{
+    HwWake, HwWakeGuard, SleepDomain, StartEnable, SysconPeripheral, WakeupGuard, enable_and_reset, enable_hw_wake,
+};
+use crate::dma;
+use crate::dma::channel::Channel;
+use crate::interrupt::typelevel::Interrupt;
+use crate::iopctl::{DriveMode, DriveStrength, Function, Inverter, IopctlPin as Pin, Pull, SlewRate};
+use crate::{PeripheralType, interrupt, peripherals};
+
+/// shorthand for -> `Result<T>`
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Error information type
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// configuration requested is not supported
+    UnsupportedConfiguration,
+    /// samples were lost, the application did not read blocks fast enough
+    Overrun,
+}
+
+/// DMIC clock the PDM clock derives from, 64 times 48 kHz
+const DMIC_CLOCK_HZ: u32 = 3_072_000;
+
+/// Channel of the left microphone, fed to the HWVAD
+const LEFT: usize = 0;
+
+/// Channel of the right microphone
+const RIGHT: usize = 1;
+
+/// Source of the DMIC function clock
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FunctionClock {
+    /// FFRO, 48 MHz
+    #[default]
+    Ffro,
+    /// Audio PLL clock, which must be enabled first
+    AudioPll,
+    /// Low-power oscillator, 1 MHz, the cheapest clock to keep running in deep sleep
+    Lposc,
+}
+
+impl FunctionClock {
+    fn frequency(self) -> Result<u32> {
+        match self {
+            FunctionClock::Ffro => Ok(48_000_000),
+            FunctionClock::AudioPll => crate::clocks::audio_pll_frequency().ok_or(Error::UnsupportedConfiguration),
+            FunctionClock::Lposc => Ok(1_000_000),
+        }
+    }
+}
+
+/// Divider from the DMIC clock to the PDM clock
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum PdmDivider {
+    /// PDM clock is the DMIC clock
+    #[default]
+    Div1,
+    /// Divide by 2
+    Div2,
+    /// Divide by 3
+    Div3,
+    /// Divide by 4
+    Div4,
+    /// Divide by 6
+    Div6,
+    /// Divide by 8
+    Div8,
+    /// Divide by 12
+    Div12,
+    /// Divide by 16
+    Div16,
+    /// Divide by 24
+    Div24,
+    /// Divide by 32
+    Div32,
+    /// Divide by 48
+    Div48,
+    /// Divide by 64
+    Div64,
+    /// Divide by 96
+    Div96,
+    /// Divide by 128
+    Div128,
+}
+
+impl PdmDivider {
+    /// Divisor of the DMIC clock
+    pub fn divisor(self) -> u32 {
+        match self {
+            PdmDivider::Div1 => 1,
+            PdmDivider::Div2 => 2,
+            PdmDivider::Div3 => 3,
+            PdmDivider::Div4 => 4,
+            PdmDivider::Div6 => 6,
+            PdmDivider::Div8 => 8,
+            PdmDivider::Div12 => 12,
+            PdmDivider::Div16 => 16,
+            PdmDivider::Div24 => 24,
+            PdmDivider::Div32 => 32,
+            PdmDivider::Div48 => 48,
+            PdmDivider::Div64 => 64,
+            PdmDivider::Div96 => 96,
+            PdmDivider::Div128 => 128,
+        }
+    }
+}
+
+/// Rate of the samples out of the decimators
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OutputRate {
+    /// PDM clock / (2 x OSR)
+    #[default]
+    Fs1,
+    /// Twice the rate, skipping the half-band filter
+    Fs2,
+}
+
+/// Coefficient of the filter compensating the droop of the CIC filter
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Compensation {
+    /// No compensation
+    #[default]
+    Zero,
+    /// -0.16
+    Minus0_16,
+    /// -0.15
+    Minus0_15,
+    /// -0.13
+    Minus0_13,
+}
+
+/// DC blocking filter
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DcBlock {
+    /// No filtering
+    Flat,
+    /// Cut below about 155 Hz
+    #[default]
+    Hz155,
+    /// Cut below about 78 Hz
+    Hz78,
+    /// Cut below about 39 Hz
+    Hz39,
+}
+
+/// Configuration of one channel
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ChannelConfig {
+    /// Gain of the decimator output, as a shift: -32 to 31, positive values amplify
+    pub gain: i8,
+    /// DC blocking filter
+    pub dc_block: DcBlock,
+    /// Attenuation after the DC blocking filter, as a right shift: 0 to 15
+    pub dc_gain: u8,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            gain: 3,
+            dc_block: DcBlock::Hz155,
+            dc_gain: 1,
+        }
+    }
+}
+
+impl ChannelConfig {
+    fn is_valid(&self) -> bool {
+        (-32..=31).contains(&self.gain) && self.dc_gain <= 15
+    }
+}
+
+/// Output sample rate of the decimators
+fn sample_rate(dmic_clock_hz: u32, divider: PdmDivider, osr: u8, rate: OutputRate) -> u32 {
+    let rate_factor = match rate {
+        OutputRate::Fs1 => 1,
+        OutputRate::Fs2 => 2,
+    };
+
+    dmic_clock_hz * rate_factor / (divider.divisor() * 2 * u32::from(osr.max(1)))
+}
+
+/// High-pass filter in front of the HWVAD
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HighPassFilter {
+    /// No filtering
+    Bypass,
+    /// Cut below 1750 Hz, keeping the upper voice band
+    #[default]
+    Hz1750,
+    /// Cut below 215 Hz
+    Hz215,
+}
+
+/// Configuration of the hardware voice activity detector
+///
+/// The HWVAD compares a fast estimate of the signal with a slow estimate of the noise floor, and
+/// flags voice when the signal stands out.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HwvadConfig {
+    /// Gain of the signal into the HWVAD, 0 to 15
+    pub input_gain: u8,
+    /// Filter of the signal into the HWVAD
+    pub high_pass: HighPassFilter,
+    /// Gain of the noise floor estimate, 0 to 15: higher is less sensitive
+    pub noise_gain: u8,
+    /// Gain of the signal estimate, 0 to 15: higher is more sensitive
+    pub signal_gain: u8,
+}
+
+impl Default for HwvadConfig {
+    fn default() -> Self {
+        Self {
+            input_gain: 5,
+            high_pass: HighPassFilter::Hz1750,
+            noise_gain: 2,
+            signal_gain: 1,
+        }
+    }
+}
+
+/// Configuration for DMIC
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Function clock, divided down to the 3.072 MHz DMIC clock or just below it
+    pub clock: FunctionClock,
+    /// Divider from the DMIC clock to the PDM clock of the microphones
+    pub pdm_divider: PdmDivider,
+    /// Oversampling of the CIC decimators, 16 to 32 is typical
+    pub osr: u8,
+    /// Rate of the samples out of the decimators
+    pub output_rate: OutputRate,
+    /// Droop compensation of the CIC filter, in the stage running at twice the output rate
+    pub compensation_2fs: Compensation,
+    /// Droop compensation of the CIC filter, in the stage running at four times the output rate
+    pub compensation_4fs: Compensation,
+    /// Saturate samples to 16 bits, instead of keeping 24 bits
+    pub saturate_16bit: bool,
+    /// Left microphone, sampled on the rising edge of the PDM clock
+    pub left: ChannelConfig,
+    /// Right microphone, sampled on the falling edge, if any
+    pub right: Option<ChannelConfig>,
+    /// Voice activity detector
+    pub hwvad: HwvadConfig,
+    /// Wake the chip from deep sleep when voice is detected
+    ///
+    /// The function clock is kept powered in deep sleep, which the audio PLL can't be. The ring
+    /// buffered capture goes on in deep sleep too, waking the DMA for each sample, but blocks
+    /// fill up unread until the core wakes.
+    pub wake_on_voice: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            clock: FunctionClock::Ffro,
+            pdm_divider: PdmDivider::Div1,
+            osr: 32,
+            output_rate: OutputRate::Fs1,
+            compensation_2fs: Compensation::Zero,
+            compensation_4fs: Compensation::Zero,
+            saturate_16bit: true,
+            left: ChannelConfig::default(),
+            right: None,
+            hwvad: HwvadConfig::default(),
+            wake_on_voice: false,
+        }
+    }
+}
+
+struct Info {
+    regs: &'static crate::pac::dmic0::RegisterBlock,
+    waker: &'static AtomicWaker,
+    voice: &'static AtomicBool,
+}
+
+// SAFETY: safety for Send here is the same as the other accessors to unsafe blocks: it must be done from a single executor context.
+//         This is a temporary workaround -- a better solution might be to refactor Info to no longer maintain a reference to regs,
+//         but instead look up the correct register set and then perform operations within an unsafe block as we do for other peripherals
+unsafe impl Send for Info {}
+
+trait SealedInstance {
+    fn info() -> Info;
+}
+
+/// DMIC instance trait.
+#[allow(private_bounds)]
+pub trait Instance: SealedInstance + PeripheralType + SysconPeripheral + 'static + Send {
+    /// Interrupt of the voice activity detector.
+    type HwvadInterrupt: interrupt::typelevel::Interrupt;
+}
+
+impl SealedInstance for peripherals::DMIC0 {
+    fn info() -> Info {
+        static WAKER: AtomicWaker = AtomicWaker::new();
+        static VOICE: AtomicBool = AtomicBool::new(false);
+
+        Info {
+            regs: unsafe { &*crate::pac::Dmic0::ptr() },
+            waker: &WAKER,
+            voice: &VOICE,
+        }
+    }
+}
+
+impl Instance for peripherals::DMIC0 {
+    type HwvadInterrupt = crate::interrupt::typelevel::HWVAD0;
+}
+
+/// HWVAD interrupt handler.
+pub struct HwvadInterruptHandler<T: Instance> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Instance> interrupt::typelevel::Handler<T::HwvadInterrupt> for HwvadInterruptHandler<T> {
+    unsafe fn on_interrupt() {
+        // the detection holds the interrupt until cleared, which the next wait does
+        T::HwvadInterrupt::disable();
+
+        let info = T::info();
+        info.voice.store(true, Ordering::Release);
+        info.waker.wake();
+    }
+}
+
+/// Capture PDM microphones
+pub struct Dmic<'d> {
+    info: Info,
+    hwvad_irq: interrupt::Interrupt,
+    sample_rate: u32,
+    _wakeup: Option<WakeupGuard>,
+    _phantom: PhantomData<&'d ()>,
+}
+
+impl<'d> Dmic<'d> {
+    /// Capture the microphones on pins `clk` and `data`
+    pub fn new<T: Instance>(
+        _dmic: Peri<'d, T>,
+        clk: Peri<'d, impl ClkPin<T>>,
+        data: Peri<'d, impl DataPin<T>>,
+        _irq: impl interrupt::typelevel::Binding<T::HwvadInterrupt, HwvadInterruptHandler<T>> + 'd,
+        config: Config,
+    ) -> Result<Self> {
+        let hwvad = config.hwvad;
+        if hwvad.input_gain > 15
+            || hwvad.noise_gain > 15
+            || hwvad.signal_gain > 15
+            || config.osr == 0
+            || !config.left.is_valid()
+            || !config.right.is_none_or(|right| right.is_valid())
+        {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let wakeup = if config.wake_on_voice {
+            Some(enable_wakeup(config.clock)?)
+        } else {
+            None
+        };
+
+        let dmic_clock_hz = init::<T>(config.clock)?;
+
+        clk.as_clk();
+        data.as_data();
+
+        let info = T::info();
+        let regs = info.regs;
+
+        Self::apply_channel_config(&info, LEFT, &config, &config.left);
+        if let Some(right) = &config.right {
+            Self::apply_channel_config(&info, RIGHT, &config, right);
+        }
+
+        regs.use2fs().write(|w| match config.output_rate {
+            OutputRate::Fs1 => w.use2fs().use_1fs(),
+            OutputRate::Fs2 => w.use2fs().use_2fs(),
+        });
+
+        regs.chanen()
+            .write(|w| w.en_ch0().set_bit().en_ch1().bit(config.right.is_some()));
+
+        // SAFETY: only unsafe due to .bits usage, values checked above
+        regs.hwvadgain()
+            .write(|w| unsafe { w.inputgain().bits(hwvad.input_gain) });
+        regs.hwvadhpfs().write(|w| match hwvad.high_pass {
+            HighPassFilter::Bypass => w.hpfs().bypass(),
+            HighPassFilter::Hz1750 => w.hpfs().high_pass_1750hz(),
+            HighPassFilter::Hz215 => w.hpfs().high_pass_215hz(),
+        });
+        regs.hwvadthgn().write(|w| unsafe { w.thgn().bits(hwvad.noise_gain) });
+        regs.hwvadthgs().write(|w| unsafe { w.thgs().bits(hwvad.signal_gain) });
+
+        // restart the noise floor estimate with the new settings
+        regs.hwvadrstt().write(|w| w.rstt().set_bit());
+        regs.hwvadrstt().write(|w| w.rstt().clear_bit());
+
+        T::HwvadInterrupt::disable();
+        T::HwvadInterrupt::unpend();
+
+        Ok(Self {
+            info,
+            hwvad_irq: T::HwvadInterrupt::IRQ,
+            sample_rate: sample_rate(dmic_clock_hz, config.pdm_divider, config.osr, config.output_rate),
+            _wakeup: wakeup,
+            _phantom: PhantomData,
+        })
+    }
+
+    fn apply_channel_config(info: &Info, index: usize, config: &Config, channel_config: &ChannelConfig) {
+        let channel = info.regs.channel(index);
+
+        // SAFETY: only unsafe due to .bits usage, values checked by the constructor
+        channel.osr().write(|w| unsafe { w.osr().bits(config.osr) });
+        channel
+            .divhfclk()
+            .write(|w| unsafe { w.pdmdiv().bits(config.pdm_divider as u8) });
+        channel
+            .preac2fscoef()
+            .write(|w| unsafe { w.comp().bits(config.compensation_2fs as u8) });
+        channel
+            .preac4fscoef()
+            .write(|w| unsafe { w.comp().bits(config.compensation_4fs as u8) });
+        // 6-bit two's complement
+        channel
+            .gainshift()
+            .write(|w| unsafe { w.gain().bits(channel_config.gain as u8 & 0x3F) });
+
+        channel.dc_ctrl().write(|w| {
+            let w = unsafe { w.dcgain().bits(channel_config.dc_gain) }
+                .saturateat16bit()
+                .bit(config.saturate_16bit)
+                .signextend()
+                .signextend();
+
+            match channel_config.dc_block {
+                DcBlock::Flat => w.dcpole().flat_response(),
+                DcBlock::Hz155 => w.dcpole().hz_155(),
+                DcBlock::Hz78 => w.dcpole().hz_78(),
+                DcBlock::Hz39 => w.dcpole().hz_39(),
+            }
+        });
+
+        // both microphones share the data line, one answers on each edge of the clock
+        channel.phy_ctrl().write(|w| {
+            let w = w.phy_half().standard();
+            if index == RIGHT {
+                w.phy_fall().falling_edge()
+            } else {
+                w.phy_fall().rising_edge()
+            }
+        });
+    }
+
+    /// Output sample rate of the channels
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Wait for the HWVAD to detect voice
+    ///
+    /// The noise floor estimate settles for a few tens of milliseconds after [`Dmic::new`], and
+    /// over the same time after loud sounds: detections start again once it did.
+    pub async fn wait_for_voice(&mut self) {
+        let regs = self.info.regs;
+
+        // the detection latches until cleared
+        self.info.voice.store(false, Ordering::Release);
+        regs.hwvadst10().write(|w| w.st10().reset());
+        regs.hwvadst10().write(|w| w.st10().normal());
+
+        self.hwvad_irq.unpend();
+        // SAFETY: the handler is bound by the constructor
+        unsafe { self.hwvad_irq.enable() };
+
+        poll_fn(|cx| {
+            self.info.waker.register(cx.waker());
+
+            if self.info.voice.swap(false, Ordering::AcqRel) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+    }
+}
+
+impl Drop for Dmic<'_> {
+    fn drop(&mut self) {
+        self.hwvad_irq.disable();
+        self.info
+            .regs
+            .chanen()
+            .write(|w| w.en_ch0().clear_bit().en_ch1().clear_bit());
+    }
+}
+
+/// Capture of the left microphone into a circular DMA buffer
+///
+/// The buffer is split in two blocks: the DMA fills one while the application reads the other, so
+/// capture never stops and the application only has to keep up on average, a block at a time.
+/// Samples are sign extended to 32 bits.
+pub struct RingBufferedDmic<'d> {
+    dmic: Dmic<'d>,
+    rx_dma: Channel<'d>,
+    buffer_a: &'static mut [i32],
+    buffer_b: &'static mut [i32],
+    consumer_buf: dma::PingPongSelector,
+    _hw_wake: Option<HwWakeGuard>,
+}
+
+impl<'d> RingBufferedDmic<'d> {
+    /// Capture the microphones on pins `clk` and `data`, streaming the left one into `buffer`
+    ///
+    /// `buffer` is used as two blocks, its length must be even and at most twice
+    /// [`MAX_TRANSFER_COUNT`](dma::MAX_TRANSFER_COUNT).
+    pub fn new<T: Instance>(
+        dmic: Peri<'d, T>,
+        clk: Peri<'d, impl ClkPin<T>>,
+        data: Peri<'d, impl DataPin<T>>,
+        irq: impl interrupt::typelevel::Binding<T::HwvadInterrupt, HwvadInterruptHandler<T>> + 'd,
+        rx_dma: Peri<'d, impl RxDma<T>>,
+        buffer: &'static mut [i32],
+        config: Config,
+    ) -> Result<Self> {
+        if buffer.is_empty() || !buffer.len().is_multiple_of(2) || buffer.len() > 2 * dma::MAX_TRANSFER_COUNT {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let rx_dma = dma::Dma::reserve_channel(rx_dma).ok_or(Error::UnsupportedConfiguration)?;
+        let dmic = Dmic::new(dmic, clk, data, irq, config)?;
+
+        // the FIFO trigger wakes the peripheral clocks for the DMA, without waking the core
+        let hw_wake = config
+            .wake_on_voice
+            .then(|| enable_hw_wake(&[HwWake::Dmic, HwWake::Dmac0]));
+
+        let regs = dmic.info.regs;
+        let channel = regs.channel(LEFT);
+
+        // start from an empty FIFO
+        channel.fifo_ctrl().write(|w| w.resetn().reset());
+        channel
+            .fifo_status()
+            .write(|w| w.overrun().set_bit().underrun().set_bit());
+
+        let (buffer_a, buffer_b) = buffer.split_at_mut(buffer.len() / 2);
+        rx_dma.configure_channel_ping_pong(
+            dma::transfer::Direction::PeripheralToMemory,
+            channel.fifo_data().as_ptr() as *const u32,
+            buffer_a.as_mut_ptr() as *mut u32,
+            buffer_b.as_mut_ptr() as *mut u32,
+            size_of_val(buffer_a),
+            dma::transfer::TransferOptions {
+                width: dma::transfer::Width::Bit32,
+                priority: dma::transfer::Priority::Priority0,
+            },
+        );
+        rx_dma.enable_channel();
+        rx_dma.trigger_channel();
+
+        // request the DMA for every sample
+        // SAFETY: only unsafe due to .bits usage
+        channel.fifo_ctrl().write(|w| {
+            unsafe { w.triglvl().bits(0) }
+                .resetn()
+                .normal()
+                .dmaen()
+                .enabled()
+                .enable()
+                .enabled()
+        });
+
+        Ok(Self {
+            dmic,
+            rx_dma,
+            buffer_a,
+            buffer_b,
+            consumer_buf: dma::PingPongSelector::BufferA,
+            _hw_wake: hw_wake,
+        })
+    }
+
+    /// Number of samples in a block
+    pub fn block_len(&self) -> usize {
+        self.buffer_a.len()
+    }
+
+    /// Output sample rate of the channels
+    pub fn sample_rate(&self) -> u32 {
+        self.dmic.sample_rate()
+    }
+
+    /// Read the oldest captured block into `block`, waiting until one is complete
+    ///
+    /// `block` must hold at least [`RingBufferedDmic::block_len`] samples. Returns
+    /// [`Error::Overrun`] once if samples were lost since the last read, capture goes on and the
+    /// next read returns the most recent block.
+    pub async fn read(&mut self, block: &mut [i32]) -> Result<()> {
+        let block = block
+            .get_mut(..self.buffer_a.len())
+            .ok_or(Error::UnsupportedConfiguration)?;
+
+        if self.check_overrun() {
+            // skip the block being overwritten, the other one is the most recent
+            let current = self.rx_dma.current_buffer();
+            self.consumer_buf = Self::other(current);
+            // SAFETY: the DMA is already writing to the current block
+            unsafe { self.rx_dma.commit_buffer(current) };
+            return Err(Error::Overrun);
+        }
+
+        poll_fn(|cx| {
+            self.rx_dma.get_waker().register(cx.waker());
+
+            if self.rx_dma.buffer_status(self.consumer_buf) == dma::BufferStatus::Granted {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+
+        let filled = match self.consumer_buf {
+            dma::PingPongSelector::BufferA => &self.buffer_a,
+            dma::PingPongSelector::BufferB => &self.buffer_b,
+        };
+        block.copy_from_slice(filled);
+
+        // SAFETY: the DMA is writing to the other block, it only comes back to this one once the
+        //         other block is full
+        unsafe { self.rx_dma.commit_buffer(self.consumer_buf) };
+        self.consumer_buf = Self::other(self.consumer_buf);
+
+        Ok(())
+    }
+
+    /// Wait for the HWVAD to detect voice, capture goes on meanwhile
+    ///
+    /// See [`Dmic::wait_for_voice`].
+    pub async fn wait_for_voice(&mut self) {
+        self.dmic.wait_for_voice().await
+    }
+
+    /// Check and clear overruns, of the ping-pong buffer or of the FIFO if the DMA fell behind
+    fn check_overrun(&self) -> bool {
+        let channel = self.dmic.info.regs.channel(LEFT);
+        let fifo_overrun = channel.fifo_status().read().overrun().bit_is_set();
+        if fifo_overrun {
+            channel.fifo_status().write(|w| w.overrun().set_bit());
+        }
+
+        self.rx_dma.check_and_clear_overrun_error() | fifo_overrun
+    }
+
+    fn other(selector: dma::PingPongSelector) -> dma::PingPongSelector {
+        match selector {
+            dma::PingPongSelector::BufferA => dma::PingPongSelector::BufferB,
+            dma::PingPongSelector::BufferB => dma::PingPongSelector::BufferA,
+        }
+    }
+}
+
+impl Drop for RingBufferedDmic<'_> {
+    fn drop(&mut self) {
+        self.rx_dma.abort();
+        self.dmic
+            .info
+            .regs
+            .channel(LEFT)
+            .fifo_ctrl()
+            .write(|w| w.dmaen().disabled().enable().disabled());
+    }
+}
+
+/// Clock and reset the DMIC, returning the DMIC clock frequency
+fn init<T: Instance>(clock: FunctionClock) -> Result<u32> {
+    let clock_hz = clock.frequency()?;
+    let divisor = clock_hz.div_ceil(DMIC_CLOCK_HZ).max(1);
+    let div = u8::try_from(divisor - 1).map_err(|_| Error::UnsupportedConfiguration)?;
+
+    if clock == FunctionClock::Lposc {
+        init_lposc();
+    }
+
+    // SAFETY: safe from single executor
+    let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
+
+    clkctl1.dmic0fclksel().write(|w| match clock {
+        FunctionClock::Ffro => w.sel().ffro_clk(),
+        FunctionClock::AudioPll => w.sel().audio_pll_clk(),
+        FunctionClock::Lposc => w.sel().lposc(),
+    });
+    // SAFETY: unsafe needed to write the bits for the divider
+    clkctl1
+        .dmic0fclkdiv()
+        .modify(|_, w| unsafe { w.div().bits(div) }.halt().clear_bit());
+    while clkctl1.dmic0fclkdiv().read().reqflag().bit_is_set() {}
+
+    enable_and_reset::<T>();
+
+    Ok(clock_hz / divisor)
+}
+
+/// Power up the low-power oscillator, a no-op if it already runs
+fn init_lposc() {
+    // SAFETY: unsafe needed to take pointer to Sysctl0, only to power the LPOSC
+    let sysctl0 = unsafe { crate::pac::Sysctl0::steal() };
+    sysctl0.pdruncfg0_clr().write(|w| w.lposc_pd().clr_pdruncfg0());
+
+    // Wait for low-power oscillator to be ready (typically 64 us)
+    // SAFETY: unsafe needed to take pointer to Clkctl0, only to check the LPOSC is ready
+    let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
+    while clkctl0.lposcctl0().read().clkrdy().bit_is_clear() {}
+}
+
+/// Start enable bit of the HWVAD in STARTEN0
+const HWVAD0_START_ENABLE: StartEnable = StartEnable::Starten0(29);
+
+/// Keep the DMIC running in deep sleep, and let the HWVAD wake the chip
+fn enable_wakeup(clock: FunctionClock) -> Result<WakeupGuard> {
+    let domains: &[SleepDomain] = match clock {
+        FunctionClock::Ffro => &[SleepDomain::Ffro],
+        FunctionClock::Lposc => &[SleepDomain::Lposc],
+        FunctionClock::AudioPll => return Err(Error::UnsupportedConfiguration),
+    };
+
+    Ok(crate::clocks::enable_wakeup(domains, HWVAD0_START_ENABLE))
+}
+
+mod sealed {
+    /// simply seal a trait
+    pub trait Sealed {}
+}
+
+impl<T: Pin> sealed::Sealed for T {}
+
+/// io configuration trait for easier configuration
+pub trait ClkPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for PDM clock usage
+    fn as_clk(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait DataPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for PDM data usage
+    fn as_data(&self);
+}
+
+macro_rules! impl_pin {
+    ($piom_n:ident, $fn:ident, $mode:ident) => {
+        paste::paste! {
+            impl [<$mode:camel Pin>]<peripherals::DMIC0> for peripherals::$piom_n {
+                fn [<as_ $mode>](&self) {
+                    self.set_function(Function::$fn)
+                        .set_pull(Pull::None)
+                        .enable_input_buffer()
+                        .set_slew_rate(SlewRate::Standard)
+                        .set_drive_strength(DriveStrength::Normal)
+                        .disable_analog_multiplex()
+                        .set_drive_mode(DriveMode::PushPull)
+                        .set_input_inverter(Inverter::Disabled);
+                }
+            }
+        }
+    };
+}
+
+impl_pin!(PIO2_16, F1, clk);
+impl_pin!(PIO2_19, F1, data);
+
+/// DMA channel serving the FIFO of the left microphone
+pub trait RxDma<T: Instance>: dma::Instance {}
+
+impl RxDma<peripherals::DMIC0> for peripherals::DMA0_CH16 {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_rate_follows_the_decimation() {
+        // 3.072 MHz PDM clock
+        assert_eq!(sample_rate(3_072_000, PdmDivider::Div1, 32, OutputRate::Fs1), 48_000);
+        assert_eq!(sample_rate(3_072_000, PdmDivider::Div1, 32, OutputRate::Fs2), 96_000);
+        // 1.536 MHz PDM clock
+        assert_eq!(sample_rate(3_072_000, PdmDivider::Div2, 48, OutputRate::Fs1), 16_000);
+        // FFRO / 16
+        assert_eq!(sample_rate(3_000_000, PdmDivider::Div1, 32, OutputRate::Fs1), 46_875);
+    }
+}
@@ -66,6 +66,7 @@ impl State {
 #[must_use]
 pub(crate) struct FlexcommRef {
     disable_fn: fn(),
+    set_clock_fn: fn(Clock),
     state: &'static State,
 }
 
@@ -75,9 +76,15 @@ impl FlexcommRef {
         assert_eq!(state.refcount.fetch_add(1, Ordering::AcqRel), 0);
         Self {
             disable_fn: T::disable,
+            set_clock_fn: T::set_clock,
             state,
         }
     }
+
+    /// Switch the functional clock, the peripheral function should be disabled meanwhile
+    pub(crate) fn set_clock(&self, clk: Clock) {
+        (self.set_clock_fn)(clk);
+    }
 }
 
 impl Clone for FlexcommRef {
@@ -85,6 +92,7 @@ impl Clone for FlexcommRef {
         self.state.refcount.fetch_add(1, Ordering::AcqRel);
         Self {
             disable_fn: self.disable_fn,
+            set_clock_fn: self.set_clock_fn,
             state: self.state,
         }
     }
@@ -106,6 +114,9 @@ pub(crate) trait FlexcommLowLevel: sealed::Sealed + PeripheralType + SysconPerip
     // set the clock select for this flexcomm instance and remove from reset
     fn enable(clk: Clock) -> FlexcommRef;
 
+    // set the clock select for this flexcomm instance
+    fn set_clock(clk: Clock);
+
     // deconfigure the clock select
     fn disable();
 
@@ -129,7 +140,7 @@ macro_rules! impl_flexcomm {
                         }
                     }
 
-                    fn enable(clk: Clock) -> FlexcommRef {
+                    fn set_clock(clk: Clock) {
                         // SAFETY: safe from single executor
                         let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
 
@@ -160,6 +171,10 @@ macro_rules! impl_flexcomm {
                             .write(|w|
                             // SAFETY: unsafe only used for .bits() call
                             unsafe { w.mult().bits(0) });
+                    }
+
+                    fn enable(clk: Clock) -> FlexcommRef {
+                        Self::set_clock(clk);
 
                         enable_and_reset::<[<FLEXCOMM $idx>]>();
 
@@ -187,8 +202,7 @@ macro_rules! impl_flexcomm {
 
 impl_flexcomm!(0, 1, 2, 3, 4, 5, 6, 7);
 
-// TODO: FLEXCOMM 14 is untested. Enable SPI support on FLEXCOMM14
-// Add special case FLEXCOMM14
+// Add special case FLEXCOMM14, the high-speed SPI, with its own clock selection registers
 impl sealed::Sealed for crate::peripherals::FLEXCOMM14 {}
 
 impl FlexcommLowLevel for crate::peripherals::FLEXCOMM14 {
@@ -198,7 +212,7 @@ impl FlexcommLowLevel for crate::peripherals::FLEXCOMM14 {
         unsafe { &*crate::pac::Flexcomm14::ptr() }
     }
 
-    fn enable(clk: Clock) -> FlexcommRef {
+    fn set_clock(clk: Clock) {
         // SAFETY: safe from single executor
         let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
 
@@ -226,6 +240,10 @@ impl FlexcommLowLevel for crate::peripherals::FLEXCOMM14 {
         clkctl1.frg14ctl().write(|w|
                 // SAFETY: unsafe only used for .bits() call
                 unsafe { w.mult().bits(0) });
+    }
+
+    fn enable(clk: Clock) -> FlexcommRef {
+        Self::set_clock(clk);
 
         enable_and_reset::<FLEXCOMM14>();
 
@@ -257,7 +275,7 @@ impl FlexcommLowLevel for crate::peripherals::FLEXCOMM15 {
         unsafe { &*crate::pac::Flexcomm15::ptr() }
     }
 
-    fn enable(clk: Clock) -> FlexcommRef {
+    fn set_clock(clk: Clock) {
         // SAFETY: safe from single executor
         let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
 
@@ -283,6 +301,10 @@ impl FlexcommLowLevel for crate::peripherals::FLEXCOMM15 {
         clkctl1.frg15ctl().write(|w|
                 // SAFETY: unsafe only used for .bits() call
                 unsafe { w.mult().bits(0) });
+    }
+
+    fn enable(clk: Clock) -> FlexcommRef {
+        Self::set_clock(clk);
 
         enable_and_reset::<FLEXCOMM15>();
 
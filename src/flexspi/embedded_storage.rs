@@ -1,4 +1,4 @@
-//! Implementations of the `embedded_storage` traits using the FlexSPI peripheral.
+//! Implementations of the `embedded_storage` and `embedded_storage_async` traits using the FlexSPI peripheral.
 
 use super::nor_flash::FlexSpiNorFlash;
 
@@ -18,6 +18,9 @@ impl<'a, const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32>
     /// This also applies to memory mapped executable code if you are executing your program directly from flash.
     ///
     /// The functions of the `embedded_storage` traits are not marked unsafe, so it is up to the caller of the constructor to ensure that the flash is used correctly.
+    ///
+    /// The `embedded_storage_async` traits leave the flash memory busy while their futures are pending.
+    /// They may not be used if anything else reads the flash memory in that time, which rules them out when executing your program directly from flash.
     pub unsafe fn new(flash: FlexSpiNorFlash<'a>) -> Result<Self, Error> {
         let alignment = flash.alignment();
 
@@ -43,6 +46,57 @@ impl<'a, const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32>
             Ok(Self { flash })
         }
     }
+
+    /// Get a shared reference to the wrapped [`FlexSpiNorFlash`] driver.
+    pub fn flash(&self) -> &FlexSpiNorFlash<'a> {
+        &self.flash
+    }
+
+    /// Release the wrapped [`FlexSpiNorFlash`] driver.
+    pub fn into_inner(self) -> FlexSpiNorFlash<'a> {
+        self.flash
+    }
+
+    /// Check that `from..to` is a valid erase range.
+    fn check_erase(&self, from: u32, to: u32) -> Result<(), Error> {
+        // Driver already checks alignment on start addresses, so we only check the size of the range.
+        let len = to.checked_sub(from).ok_or(Error::InvalidEraseRange { from, to })?;
+        if len % ERASE_SIZE != 0 {
+            return Err(Error::InvalidEraseSize {
+                actual: len,
+                alignment: ERASE_SIZE,
+            });
+        }
+        self.check_bounds(from, len)
+    }
+
+    /// Check if the erase of `from..to` can continue with a whole block at `from`.
+    fn can_erase_block(&self, from: u32, to: u32) -> bool {
+        let sector_size = self.flash.alignment().sector_size;
+        let block_size = self.flash.alignment().block_size;
+
+        // Erase a whole block if we can:
+        //  * blocks must be sector aligned (TODO: verify this in FlexSpiNorFlash constructor and rely on it unconditionally?).
+        //  * remaining erase size must be at-least a block
+        //  * current offset must be block aligned
+        block_size.is_multiple_of(sector_size) && to - from >= block_size && from.is_multiple_of(block_size)
+    }
+
+    /// Get the number of bytes of `remaining` that can be written at `offset` without crossing a page boundary.
+    fn page_write_size(&self, offset: u32, remaining: usize) -> u32 {
+        let page_size = self.flash.alignment().page_size;
+        let page_end = round_down_nearest_multiple(offset, page_size) + page_size;
+        (remaining as u32).min(page_end - offset)
+    }
+
+    /// Check that `length` bytes from `offset` fit in the flash memory.
+    fn check_bounds(&self, offset: u32, length: u32) -> Result<(), Error> {
+        if is_in_bounds(offset, length, self.flash.size_bytes()) {
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds { offset, length })
+        }
+    }
 }
 
 impl<const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32> embedded_storage::nor_flash::ErrorType
@@ -57,6 +111,7 @@ impl<const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32> embedde
     const READ_SIZE: usize = READ_SIZE as usize;
 
     fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.check_bounds(offset, bytes.len() as u32)?;
         self.flash.read(offset, bytes).map_err(Error::ReadFailed)
     }
 
@@ -72,38 +127,23 @@ impl<const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32> embedde
     const ERASE_SIZE: usize = ERASE_SIZE as usize;
 
     fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
-        // Driver already checks alignment on start addresses, so we only check the size of the range.
-        let len = to.checked_sub(from).ok_or(Error::InvalidEraseRange { from, to })?;
-        if len % ERASE_SIZE != 0 {
-            return Err(Error::InvalidEraseSize {
-                actual: len,
-                alignment: ERASE_SIZE,
-            });
-        }
-
-        let sector_size = self.flash.alignment().sector_size;
-        let block_size = self.flash.alignment().block_size;
-        let blocks_are_sector_aligned = block_size.is_multiple_of(sector_size);
+        self.check_erase(from, to)?;
 
         let mut from = from;
         while from < to {
-            // Erase a whole block if we can:
-            //  * blocks must be sector aligned (TODO: verify this in FlexSpiNorFlash constructor and rely on it unconditionally?).
-            //  * remaining erase size must be at-least a block
-            //  * current offset must be block aligned
-            if blocks_are_sector_aligned && to - from >= block_size && from.is_multiple_of(block_size) {
+            if self.can_erase_block(from, to) {
                 // SAFETY: The safety requirements have been shifted to the caller of the constructor.
                 unsafe {
                     self.flash.erase_block(from).map_err(Error::EraseBlockFailed)?;
                 }
-                from += block_size;
+                from += self.flash.alignment().block_size;
             // Otherwise erase a sector.
             } else {
                 // SAFETY: The safety requirements have been shifted to the caller of the constructor.
                 unsafe {
                     self.flash.erase_sector(from).map_err(Error::EraseSectorFailed)?;
                 }
-                from += sector_size;
+                from += self.flash.alignment().sector_size;
             }
         }
 
@@ -111,15 +151,15 @@ impl<const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32> embedde
     }
 
     fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
-        let page_size = self.flash.alignment().page_size;
+        self.check_bounds(offset, bytes.len() as u32)?;
+
         let mut offset = offset;
         let mut bytes = bytes;
 
         // Write the data page by page.
         // The driver already checks alignment, so we only split on page boundaries.
         while !bytes.is_empty() {
-            let page_end = round_down_nearest_multiple(offset, page_size) + page_size;
-            let write_size = (bytes.len() as u32).min(page_end - offset);
+            let write_size = self.page_write_size(offset, bytes.len());
             let (page_bytes, rest) = bytes.split_at(write_size as usize);
 
             // SAFETY: The safety requirements have been shifted to the caller of the constructor.
@@ -136,6 +176,92 @@ impl<const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32> embedde
     }
 }
 
+impl<const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32> embedded_storage_async::nor_flash::ReadNorFlash
+    for FlexSpiNorStorage<'_, READ_SIZE, WRITE_SIZE, ERASE_SIZE>
+{
+    const READ_SIZE: usize = READ_SIZE as usize;
+
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        // Reads are short IP commands, there is nothing to wait for.
+        embedded_storage::nor_flash::ReadNorFlash::read(self, offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        self.flash.size_bytes() as usize
+    }
+}
+
+impl<const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32> embedded_storage_async::nor_flash::NorFlash
+    for FlexSpiNorStorage<'_, READ_SIZE, WRITE_SIZE, ERASE_SIZE>
+{
+    const WRITE_SIZE: usize = WRITE_SIZE as usize;
+    const ERASE_SIZE: usize = ERASE_SIZE as usize;
+
+    async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        self.check_erase(from, to)?;
+
+        let mut from = from;
+        while from < to {
+            if self.can_erase_block(from, to) {
+                // SAFETY: The safety requirements have been shifted to the caller of the constructor.
+                unsafe {
+                    self.flash
+                        .erase_block_async(from)
+                        .await
+                        .map_err(Error::EraseBlockFailed)?;
+                }
+                from += self.flash.alignment().block_size;
+            } else {
+                // SAFETY: The safety requirements have been shifted to the caller of the constructor.
+                unsafe {
+                    self.flash
+                        .erase_sector_async(from)
+                        .await
+                        .map_err(Error::EraseSectorFailed)?;
+                }
+                from += self.flash.alignment().sector_size;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.check_bounds(offset, bytes.len() as u32)?;
+
+        let mut offset = offset;
+        let mut bytes = bytes;
+
+        while !bytes.is_empty() {
+            let write_size = self.page_write_size(offset, bytes.len());
+            let (page_bytes, rest) = bytes.split_at(write_size as usize);
+
+            // SAFETY: The safety requirements have been shifted to the caller of the constructor.
+            unsafe {
+                self.flash
+                    .page_program_async(offset, page_bytes)
+                    .await
+                    .map_err(Error::WriteFailed)?;
+            }
+            bytes = rest;
+            offset += write_size;
+        }
+
+        Ok(())
+    }
+}
+
+/// NOR flash programming only clears bits, so a word can be written again without an erase in between.
+impl<const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32> embedded_storage::nor_flash::MultiwriteNorFlash
+    for FlexSpiNorStorage<'_, READ_SIZE, WRITE_SIZE, ERASE_SIZE>
+{
+}
+
+impl<const READ_SIZE: u32, const WRITE_SIZE: u32, const ERASE_SIZE: u32>
+    embedded_storage_async::nor_flash::MultiwriteNorFlash for FlexSpiNorStorage<'_, READ_SIZE, WRITE_SIZE, ERASE_SIZE>
+{
+}
+
 /// Error type for the [`FlexSpiNorStorage`] struct.
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -172,6 +298,15 @@ pub enum Error {
 
     /// Failed to write (program) flash memory.
     WriteFailed(super::nor_flash::PageProgramError),
+
+    /// The requested range does not fit in the flash memory.
+    OutOfBounds {
+        /// The start offset of the requested operation.
+        offset: u32,
+
+        /// The size of the requested operation.
+        length: u32,
+    },
 }
 
 /// One of the const generic parameters does not match the runtime information of the driver.
@@ -214,6 +349,10 @@ impl From<IncorrectConstGeneric> for Error {
     }
 }
 
+fn is_in_bounds(offset: u32, length: u32, capacity: u32) -> bool {
+    offset.checked_add(length).is_some_and(|end| end <= capacity)
+}
+
 fn check_const_size_parameter(given: u32, driver: u32) -> bool {
     // The given const param must be a multiple of the driver value (but it can not be zero if the driver value is non-zero).
     given >= driver && given.is_multiple_of(driver)
@@ -227,6 +366,7 @@ impl embedded_storage::nor_flash::NorFlashError for Error {
             Self::IncorrectConstGeneric(_) => NorFlashErrorKind::Other,
             Self::InvalidEraseRange { .. } => NorFlashErrorKind::NotAligned,
             Self::InvalidEraseSize { .. } => NorFlashErrorKind::NotAligned,
+            Self::OutOfBounds { .. } => NorFlashErrorKind::OutOfBounds,
             Self::ReadFailed(e) => read_error_kind(e),
             Self::EraseSectorFailed(e) => write_error_kind(e),
             Self::EraseBlockFailed(e) => write_error_kind(e),
@@ -241,6 +381,7 @@ fn read_error_kind(error: &super::nor_flash::ReadError) -> embedded_storage::nor
         super::nor_flash::ReadError::Misaligned(_) => NorFlashErrorKind::NotAligned,
         super::nor_flash::ReadError::Command(_) => NorFlashErrorKind::Other,
         super::nor_flash::ReadError::NotEnoughData(_) => NorFlashErrorKind::Other,
+        super::nor_flash::ReadError::Dma(_) => NorFlashErrorKind::Other,
     }
 }
 
@@ -270,3 +411,16 @@ fn page_program_error_kind(
 fn round_down_nearest_multiple(value: u32, multiple: u32) -> u32 {
     value / multiple * multiple
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds() {
+        assert!(is_in_bounds(0, 0x1000, 0x1000));
+        assert!(is_in_bounds(0x1000, 0, 0x1000));
+        assert!(!is_in_bounds(0x0FFF, 2, 0x1000));
+        assert!(!is_in_bounds(u32::MAX, 2, u32::MAX));
+    }
+}
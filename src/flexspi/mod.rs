@@ -2,6 +2,9 @@
 
 #![deny(unsafe_op_in_unsafe_fn)]
 
+use crate::dma;
+use crate::peripherals::DMA0_CH28;
+
 pub mod embedded_storage;
 
 pub mod peripheral;
@@ -9,3 +12,13 @@ pub mod peripheral;
 pub mod nor_flash;
 
 pub mod nor_storage_bus;
+
+pub mod psram;
+
+trait Sealed {}
+
+/// Trait for DMA channels that can read the FlexSPI IP RX FIFO
+#[allow(private_bounds)]
+pub trait RxDma: Sealed + dma::Instance {}
+impl Sealed for DMA0_CH28 {}
+impl RxDma for DMA0_CH28 {}
@@ -1,7 +1,15 @@
 //! FlexSPI FLASH driver.
 
+use core::pin::pin;
+
+use embassy_futures::select::{Either, select};
+use embassy_hal_internal::drop::OnDrop;
+
+use super::RxDma;
 use super::peripheral::{CommandSequence, FlexSpi, InvalidCommandSequence};
 use crate::Peri;
+use crate::dma::transfer::{Transfer, TransferOptions, Width};
+use crate::dma::{self, MAX_TRANSFER_COUNT};
 use crate::peripherals::FLEXSPI;
 
 /// FlexSPI NOR FLASH driver.
@@ -20,6 +28,9 @@ pub struct FlexSpiNorFlash<'a> {
 
     /// The alignment requirements of the flash memory.
     alignment: FlashAlignment,
+
+    /// The configuration of the security registers and block protection, if any.
+    security: Option<SecurityConfig>,
 }
 
 /// Configuration of the [`FlexSpiNorFlash`] driver.
@@ -86,6 +97,67 @@ pub struct FlashSequences {
     pub page_program: [u32; 4],
 }
 
+/// FlexSPI command sequences for the security registers and block protection of a NOR flash.
+///
+/// These commands differ a lot between flash memory vendors,
+/// refer to the datasheet of your flash memory for the details.
+/// You can use the [`mimxrt600_fcb`] crate to create command sequences.
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SecuritySequences {
+    /// The sequence for reading data from the security registers.
+    pub read: [u32; 4],
+
+    /// The sequence for programming data into the security registers.
+    pub program: [u32; 4],
+
+    /// The sequence that permanently locks the security registers.
+    ///
+    /// It is sent without data, after setting the write-enable latch.
+    /// For flash memories that lock through bits of a status register,
+    /// the new register value can be sent as an extra command instruction in the sequence.
+    pub lock: [u32; 4],
+
+    /// The sequence for reading the one byte register holding the block protection bits.
+    pub read_protection: [u32; 4],
+
+    /// The sequence for writing the one byte register holding the block protection bits.
+    pub write_protection: [u32; 4],
+}
+
+/// Configuration of the security registers and block protection of a NOR flash.
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SecurityConfig {
+    /// Command sequences for the FlexSPI peripheral.
+    pub sequences: SecuritySequences,
+
+    /// The size of a security register page.
+    ///
+    /// Programming the security registers may not cross a page boundary.
+    pub page_size: u32,
+
+    /// The bits of the protection register that select the protected blocks.
+    ///
+    /// [`FlexSpiNorFlash::set_block_protection()`] leaves the other bits of the register unchanged.
+    pub block_protection_mask: u8,
+}
+
+/// Interval between polls of the flash status during an async erase, which takes tens of milliseconds.
+const ERASE_POLL_INTERVAL_US: u64 = 1_000;
+
+/// Interval between polls of the flash status during an async page program, which takes about a millisecond.
+const PROGRAM_POLL_INTERVAL_US: u64 = 50;
+
+/// Interval between polls of the command status during a DMA read, to catch a failed command.
+const DMA_READ_POLL_INTERVAL_US: u64 = 100;
+
+/// IP RX FIFO watermark for DMA reads, the full 32 word window of RX FIFO data registers.
+const DMA_WATERMARK_BYTES: usize = 128;
+
+/// Maximum length of a single DMA read command, bound by the transfer count of a DMA descriptor.
+const DMA_CHUNK_BYTES: usize = MAX_TRANSFER_COUNT * 4;
+
 /// Sequence indexes in the LUT for specific commands.
 ///
 /// These are chosen specifically for the driver.
@@ -100,6 +172,9 @@ pub(super) mod sequence {
     pub const ERASE_BLOCK: u8 = 12;
     pub const ERASE_CHIP: u8 = 13;
     pub const PAGE_PROGRAM: u8 = 14;
+
+    /// Shared by the [`SecuritySequences`](super::SecuritySequences), which are loaded before each use.
+    pub const SECURITY: u8 = 15;
 }
 
 impl<'a> FlexSpiNorFlash<'a> {
@@ -125,6 +200,7 @@ impl<'a> FlexSpiNorFlash<'a> {
         let mut me = Self {
             flex_spi,
             alignment: config.alignment,
+            security: None,
         };
 
         // Copy the sequences into the LUT.
@@ -210,6 +286,107 @@ impl<'a> FlexSpiNorFlash<'a> {
         read(&mut self.flex_spi, sequence::READ, 1, address, buffer)
     }
 
+    /// Read data from the given flash address, streaming it out of the IP RX FIFO with DMA.
+    ///
+    /// The CPU is free while the data is transferred, which makes this suitable for large regions.
+    /// Only the few bytes before the first word-aligned byte of `buffer`,
+    /// and the last bytes that do not fill a FIFO watermark, are read by the CPU.
+    /// Everything is read by the CPU if that would leave the DMA part misaligned for the flash memory.
+    ///
+    /// The command status is polled between awaits to catch failed commands.
+    /// Without the `time` feature, it is polled every time the executor comes back to the task.
+    ///
+    /// NOTE: The address argument is a physical flash address, not a CPU memory address.
+    pub async fn read_dma(
+        &mut self,
+        rx_dma: Peri<'_, impl RxDma>,
+        address: u32,
+        buffer: &mut [u8],
+    ) -> Result<(), ReadError> {
+        MisalignedAccessError::check(address, self.alignment.read_alignment)?;
+        let Some(channel) = dma::Dma::reserve_channel(rx_dma) else {
+            return self.read(address, buffer);
+        };
+
+        // The DMA only writes whole words, and FIFO data below the watermark raises no DMA request.
+        let head_len = buffer.as_ptr().align_offset(4).min(buffer.len());
+        let body_len = (buffer.len() - head_len) / DMA_WATERMARK_BYTES * DMA_WATERMARK_BYTES;
+        let body_address = address + head_len as u32;
+        let tail_address = body_address + body_len as u32;
+        if body_len == 0 || MisalignedAccessError::check(body_address, self.alignment.read_alignment).is_err() {
+            return self.read(address, buffer);
+        }
+
+        let (head, rest) = buffer.split_at_mut(head_len);
+        let (body, tail) = rest.split_at_mut(body_len);
+        self.read(address, head)?;
+        for (i, chunk) in body.chunks_mut(DMA_CHUNK_BYTES).enumerate() {
+            self.read_dma_chunk(&channel, body_address + (i * DMA_CHUNK_BYTES) as u32, chunk)
+                .await?;
+        }
+        self.read(tail_address, tail)
+    }
+
+    /// Read a chunk of at most [`DMA_CHUNK_BYTES`] with a single command, using DMA.
+    ///
+    /// The length of the chunk must be a multiple of [`DMA_WATERMARK_BYTES`].
+    async fn read_dma_chunk(
+        &mut self,
+        channel: &dma::channel::Channel<'_>,
+        address: u32,
+        buffer: &mut [u8],
+    ) -> Result<(), ReadError> {
+        // Make sure no old data remains in the RX fifo.
+        self.flex_spi
+            .set_rx_fifo_watermark_u64_words((DMA_WATERMARK_BYTES / 8) as u8);
+        self.flex_spi.clear_rx_fifo();
+
+        // Disable DMA on completion/cancellation, and let a cancelled command run to completion.
+        let _dma_guard = OnDrop::new(FlexSpi::stop_rx_fifo_dma);
+        self.flex_spi.set_rx_fifo_dma_enabled(true);
+
+        let mut options = TransferOptions::default();
+        options.width = Width::Bit32;
+
+        // SAFETY: `buffer` outlives the transfer, which is aborted if this future is dropped.
+        let mut transfer = unsafe {
+            Transfer::new_read_fifo_window(
+                channel,
+                self.flex_spi.rx_fifo_window().cast(),
+                DMA_WATERMARK_BYTES,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                options,
+            )
+        }
+        .map_err(ReadError::Dma)?;
+
+        // SAFETY: A read command does not keep the flash memory from serving AHB reads.
+        unsafe {
+            self.flex_spi
+                .configure_command_sequence(CommandSequence {
+                    start: sequence::READ,
+                    count: 1,
+                    address,
+                    data_size: buffer.len() as u16,
+                    parallel: false,
+                })
+                .map_err(|e| ReadError::Command(e.into()))?;
+            self.flex_spi.trigger_command();
+        }
+
+        let mut command = pin!(wait_command_finished(&mut self.flex_spi, DMA_READ_POLL_INTERVAL_US));
+        match select(&mut transfer, command.as_mut()).await {
+            Either::First(()) => command.await.map_err(ReadError::Command),
+            Either::Second(result) => {
+                // On success, the DMA still has to empty the RX FIFO.
+                result.map_err(ReadError::Command)?;
+                transfer.await;
+                Ok(())
+            }
+        }
+    }
+
     /// Erase a sector of flash memory.
     ///
     /// NOTE: The address argument is a physical flash address, not a CPU memory address.
@@ -218,7 +395,8 @@ impl<'a> FlexSpiNorFlash<'a> {
     /// You may not erase flash memory holding code of the current program.
     ///
     /// If your program also performs memory mapped access to the erased region,
-    /// you must invalidate the FlexSPI cache and the AHB RX buffer.
+    /// you must invalidate the FlexSPI cache and the AHB RX buffer,
+    /// unless the `ram-functions` feature is enabled, which does so after every erase and program.
     pub unsafe fn erase_sector(&mut self, address: u32) -> Result<(), WriteError> {
         MisalignedAccessError::check(address, self.alignment.sector_size)?;
         self.set_and_verify_write_enable()?;
@@ -233,7 +411,7 @@ impl<'a> FlexSpiNorFlash<'a> {
                     parallel: false,
                 })
                 .map_err(|e| WriteError::Command(e.into()))?;
-            self.flex_spi.trigger_command_and_wait_write()?;
+            self.trigger_write_and_wait()?;
         }
         Ok(())
     }
@@ -246,7 +424,8 @@ impl<'a> FlexSpiNorFlash<'a> {
     /// You may not erase flash memory holding code of the current program.
     ///
     /// If your program also performs memory mapped access to the erased region,
-    /// you must invalidate the FlexSPI cache and the AHB RX buffer.
+    /// you must invalidate the FlexSPI cache and the AHB RX buffer,
+    /// unless the `ram-functions` feature is enabled, which does so after every erase and program.
     pub unsafe fn erase_block(&mut self, address: u32) -> Result<(), WriteError> {
         MisalignedAccessError::check(address, self.alignment.block_size)?;
         self.set_and_verify_write_enable()?;
@@ -261,7 +440,7 @@ impl<'a> FlexSpiNorFlash<'a> {
                     parallel: false,
                 })
                 .map_err(|e| WriteError::Command(e.into()))?;
-            self.flex_spi.trigger_command_and_wait_write()?;
+            self.trigger_write_and_wait()?;
         }
         Ok(())
     }
@@ -274,7 +453,8 @@ impl<'a> FlexSpiNorFlash<'a> {
     /// directly from flash.
     ///
     /// If your program also performs memory mapped access to the erased region,
-    /// you must invalidate the FlexSPI cache and the AHB RX buffer.
+    /// you must invalidate the FlexSPI cache and the AHB RX buffer,
+    /// unless the `ram-functions` feature is enabled, which does so after every erase and program.
     pub unsafe fn erase_chip(&mut self) -> Result<(), WriteError> {
         self.set_and_verify_write_enable()?;
 
@@ -288,7 +468,7 @@ impl<'a> FlexSpiNorFlash<'a> {
                     parallel: false,
                 })
                 .map_err(|e| WriteError::Command(e.into()))?;
-            self.flex_spi.trigger_command_and_wait_write()?;
+            self.trigger_write_and_wait()?;
         }
         Ok(())
     }
@@ -305,7 +485,8 @@ impl<'a> FlexSpiNorFlash<'a> {
     /// You may not modify flash memory holding code of the current program.
     ///
     /// If your program also performs memory mapped access to the erased region,
-    /// you must invalidate the FlexSPI cache and the AHB RX buffer.
+    /// you must invalidate the FlexSPI cache and the AHB RX buffer,
+    /// unless the `ram-functions` feature is enabled, which does so after every erase and program.
     pub unsafe fn page_program(&mut self, address: u32, data: &[u8]) -> Result<(), PageProgramError> {
         // Check that address is aligned to self.write_alignment.
         MisalignedAccessError::check(address, self.alignment.write_alignment)?;
@@ -313,6 +494,15 @@ impl<'a> FlexSpiNorFlash<'a> {
         // Check if the write fully falls into one page.
         WriteCrossesPageBoundary::check(address, data.len() as u32, self.alignment.page_size)?;
 
+        unsafe { self.program(sequence::PAGE_PROGRAM, address, data)? };
+        Ok(())
+    }
+
+    /// Program data with the given sequence, after setting the write-enable latch.
+    ///
+    /// # Safety
+    /// The command could potentially change the code of the currently running program.
+    unsafe fn program(&mut self, start: u8, address: u32, data: &[u8]) -> Result<(), WriteError> {
         // Set write enable latch and verify that it worked.
         self.set_and_verify_write_enable()?;
 
@@ -327,20 +517,270 @@ impl<'a> FlexSpiNorFlash<'a> {
             unsafe {
                 self.flex_spi
                     .configure_command_sequence(CommandSequence {
-                        start: sequence::PAGE_PROGRAM,
+                        start,
                         count: 1,
                         address: address + i as u32 * 128,
                         data_size: chunk.len() as u16,
                         parallel: false,
                     })
                     .map_err(|e| WriteError::Command(e.into()))?;
-                self.flex_spi.trigger_command_and_wait_write()?;
+                self.trigger_write_and_wait()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Erase a sector of flash memory, awaiting the end of the erase instead of blocking the core.
+    ///
+    /// The flash memory has no busy interrupt, so its status is polled between awaits.
+    ///
+    /// NOTE: The address argument is a physical flash address, not a CPU memory address.
+    ///
+    /// # Safety
+    /// The same requirements as for [`Self::erase_sector()`] apply.
+    ///
+    /// Additionally, the flash memory can not be read while the erase is in progress,
+    /// which lasts until the future completes.
+    /// Nothing may be fetched from the flash memory in the meantime, neither code nor data,
+    /// so this is never sound to call when executing your program directly from the same flash memory.
+    pub async unsafe fn erase_sector_async(&mut self, address: u32) -> Result<(), WriteError> {
+        MisalignedAccessError::check(address, self.alignment.sector_size)?;
+        unsafe { self.start_write(sequence::ERASE_SECTOR, address, 0)? };
+        self.wait_write_done(ERASE_POLL_INTERVAL_US).await
+    }
+
+    /// Erase a block of flash memory, awaiting the end of the erase instead of blocking the core.
+    ///
+    /// The flash memory has no busy interrupt, so its status is polled between awaits.
+    ///
+    /// NOTE: The address argument is a physical flash address, not a CPU memory address.
+    ///
+    /// # Safety
+    /// The same requirements as for [`Self::erase_sector_async()`] apply.
+    pub async unsafe fn erase_block_async(&mut self, address: u32) -> Result<(), WriteError> {
+        MisalignedAccessError::check(address, self.alignment.block_size)?;
+        unsafe { self.start_write(sequence::ERASE_BLOCK, address, 0)? };
+        self.wait_write_done(ERASE_POLL_INTERVAL_US).await
+    }
+
+    /// Erase the whole flash chip, awaiting the end of the erase instead of blocking the core.
+    ///
+    /// # Safety
+    /// The same requirements as for [`Self::erase_chip()`] and [`Self::erase_sector_async()`] apply.
+    pub async unsafe fn erase_chip_async(&mut self) -> Result<(), WriteError> {
+        unsafe { self.start_write(sequence::ERASE_CHIP, 0, 0)? };
+        self.wait_write_done(ERASE_POLL_INTERVAL_US).await
+    }
+
+    /// Perform a page program, awaiting the end of each program operation instead of blocking the core.
+    ///
+    /// The same restrictions as for [`Self::page_program()`] apply to the data.
+    ///
+    /// NOTE: The address argument is a physical flash address, not a CPU memory address.
+    ///
+    /// # Safety
+    /// The same requirements as for [`Self::page_program()`] and [`Self::erase_sector_async()`] apply.
+    pub async unsafe fn page_program_async(&mut self, address: u32, data: &[u8]) -> Result<(), PageProgramError> {
+        MisalignedAccessError::check(address, self.alignment.write_alignment)?;
+        WriteCrossesPageBoundary::check(address, data.len() as u32, self.alignment.page_size)?;
+
+        // Program chunks of at most 128 bytes, each one clears the write-enable latch when done.
+        for (i, chunk) in data.chunks(128).enumerate() {
+            self.flex_spi.set_tx_fifo_watermark_u64_words(16);
+            self.flex_spi.clear_tx_fifo();
+            self.flex_spi.fill_tx_fifo(chunk);
+
+            unsafe {
+                self.start_write(sequence::PAGE_PROGRAM, address + i as u32 * 128, chunk.len() as u16)?;
+            }
+            self.wait_write_done(PROGRAM_POLL_INTERVAL_US).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Trigger the configured write command and wait for the flash memory to finish it.
+    ///
+    /// With the `ram-functions` feature, this also invalidates the FlexSPI cache and the AHB RX buffer,
+    /// so memory mapped reads see the new contents of the flash memory.
+    ///
+    /// # Safety
+    /// The command could potentially change the code of the currently running program.
+    unsafe fn trigger_write_and_wait(&mut self) -> Result<(), WriteError> {
+        let result = unsafe { self.flex_spi.trigger_command_and_wait_write() };
+
+        // Also invalidate after a failure, the flash memory may have been modified partially.
+        #[cfg(feature = "ram-functions")]
+        unsafe {
+            self.flex_spi.invalidate_ahb_read_caches();
+        }
+
+        result.map(drop)
+    }
+
+    /// Set the write-enable latch and start a write command, without waiting for the flash memory to finish it.
+    ///
+    /// # Safety
+    /// The command could potentially change the code of the currently running program.
+    unsafe fn start_write(&mut self, start: u8, address: u32, data_size: u16) -> Result<(), WriteError> {
+        self.set_and_verify_write_enable()?;
+
+        unsafe {
+            self.flex_spi
+                .configure_command_sequence(CommandSequence {
+                    start,
+                    count: 1,
+                    address,
+                    data_size,
+                    parallel: false,
+                })
+                .map_err(|e| WriteError::Command(e.into()))?;
+            self.flex_spi
+                .trigger_command_and_wait()
+                .map_err(|e| WriteError::Command(e.into()))?;
+        }
+        Ok(())
+    }
+
+    /// Poll the status of the flash memory every `interval_us` until the write in progress is done.
+    ///
+    /// Without the `time` feature, the status is polled every time the executor comes back to the task.
+    async fn wait_write_done(&mut self, interval_us: u64) -> Result<(), WriteError> {
+        #[cfg(not(feature = "time"))]
+        let _ = interval_us;
+
+        loop {
+            let status = self.read_status().map_err(WriteError::ReadStatus)?;
+            if !status.is_write_in_progress() {
+                // SAFETY: The write is done, so no IP command is running.
+                #[cfg(feature = "ram-functions")]
+                unsafe {
+                    self.flex_spi.invalidate_ahb_read_caches();
+                }
+                return Ok(());
             }
+
+            #[cfg(feature = "time")]
+            embassy_time::Timer::after_micros(interval_us).await;
+            #[cfg(not(feature = "time"))]
+            embassy_futures::yield_now().await;
+        }
+    }
+
+    /// Set the configuration used to access the security registers and block protection of the flash memory.
+    ///
+    /// # Safety
+    /// The driver does not check if the config is correct for the flash chip connected to the FlexSPI peripheral.
+    /// A wrong sequence could modify flash memory holding code of the current program.
+    ///
+    /// The sequences are loaded into the FlexSPI LUT before each use,
+    /// which might interfere with memory mapped flash access in non-default system configurations.
+    pub unsafe fn set_security_config(&mut self, config: SecurityConfig) {
+        self.security = Some(config);
+    }
+
+    /// Read data from the security registers of the flash memory.
+    ///
+    /// NOTE: The address argument is the address in the security register space of the flash memory.
+    pub fn read_security_registers(&mut self, address: u32, buffer: &mut [u8]) -> Result<(), SecurityError> {
+        MisalignedAccessError::check(address, self.alignment.read_alignment).map_err(ReadError::from)?;
+        self.load_security_sequence(|sequences| sequences.read)?;
+        read(&mut self.flex_spi, sequence::SECURITY, 1, address, buffer)?;
+        Ok(())
+    }
+
+    /// Program data into the security registers of the flash memory.
+    ///
+    /// The data to be written may not cross a security register page boundary.
+    /// Security registers are typically one-time programmable,
+    /// so bits can not be set again once they are programmed to zero.
+    ///
+    /// NOTE: The address argument is the address in the security register space of the flash memory.
+    pub fn program_security_registers(&mut self, address: u32, data: &[u8]) -> Result<(), SecurityError> {
+        MisalignedAccessError::check(address, self.alignment.write_alignment).map_err(PageProgramError::from)?;
+        let config = self.load_security_sequence(|sequences| sequences.program)?;
+        WriteCrossesPageBoundary::check(address, data.len() as u32, config.page_size)
+            .map_err(PageProgramError::from)?;
+
+        // SAFETY: The security registers are outside of the main flash memory array.
+        unsafe { self.program(sequence::SECURITY, address, data)? };
+        Ok(())
+    }
+
+    /// Permanently lock the security registers of the flash memory.
+    ///
+    /// This is irreversible: the security registers can not be programmed any more afterwards.
+    ///
+    /// # Safety
+    /// The lock bits are one-time programmable and can not be cleared again, not even by erasing the flash.
+    /// The caller must be sure the part is meant to be locked for good,
+    /// and that the security registers already hold their final contents.
+    pub unsafe fn lock_security_registers(&mut self) -> Result<(), SecurityError> {
+        self.load_security_sequence(|sequences| sequences.lock)?;
+        self.set_and_verify_write_enable()?;
+
+        // SAFETY: The lock command does not modify the main flash memory array.
+        unsafe {
+            self.flex_spi
+                .configure_command_sequence(CommandSequence {
+                    start: sequence::SECURITY,
+                    count: 1,
+                    address: 0,
+                    data_size: 0,
+                    parallel: false,
+                })
+                .map_err(|e| WriteError::Command(e.into()))?;
+            self.trigger_write_and_wait()?;
         }
+        Ok(())
+    }
 
+    /// Read the block protection bits of the flash memory.
+    ///
+    /// Only the bits of [`SecurityConfig::block_protection_mask`] are returned, in their place in the register.
+    pub fn block_protection(&mut self) -> Result<u8, SecurityError> {
+        let config = self.load_security_sequence(|sequences| sequences.read_protection)?;
+        let mut buffer = [0; 1];
+        read(&mut self.flex_spi, sequence::SECURITY, 1, 0, &mut buffer)?;
+        let [value] = buffer;
+        Ok(value & config.block_protection_mask)
+    }
+
+    /// Set the block protection bits of the flash memory.
+    ///
+    /// Only the bits of [`SecurityConfig::block_protection_mask`] are changed,
+    /// the other bits of the protection register are written back unchanged.
+    /// Protected blocks can not be erased or programmed until they are unprotected again.
+    pub fn set_block_protection(&mut self, bits: u8) -> Result<(), SecurityError> {
+        let config = self.load_security_sequence(|sequences| sequences.read_protection)?;
+        let mut buffer = [0; 1];
+        read(&mut self.flex_spi, sequence::SECURITY, 1, 0, &mut buffer)?;
+        let [value] = buffer;
+        let value = (value & !config.block_protection_mask) | (bits & config.block_protection_mask);
+
+        self.load_security_sequence(|sequences| sequences.write_protection)?;
+        // SAFETY: The protection register is outside of the main flash memory array.
+        unsafe { self.program(sequence::SECURITY, 0, &[value])? };
         Ok(())
     }
 
+    /// Load one of the security sequences into its LUT entry.
+    ///
+    /// Returns the security configuration, or an error if there is none.
+    fn load_security_sequence(
+        &mut self,
+        sequence: impl FnOnce(&SecuritySequences) -> [u32; 4],
+    ) -> Result<SecurityConfig, SecurityError> {
+        let config = self.security.ok_or(SecurityError::NotConfigured)?;
+        // SAFETY: The LUT entry is reserved for the security sequences, which do not serve memory mapped access.
+        unsafe {
+            self.flex_spi
+                .write_lut_sequence(sequence::SECURITY, sequence(&config.sequences));
+        }
+        Ok(config)
+    }
+
     /// Read the status of the flash memory.
     ///
     /// Note that you normally do not need to call this yourself.
@@ -490,6 +930,25 @@ fn read(
     Ok(())
 }
 
+/// Poll the FlexSPI peripheral every `interval_us` until the triggered command finishes.
+///
+/// Without the `time` feature, the command is polled every time the executor comes back to the task.
+async fn wait_command_finished(flex_spi: &mut FlexSpi<'_>, interval_us: u64) -> Result<(), CommandError> {
+    #[cfg(not(feature = "time"))]
+    let _ = interval_us;
+
+    loop {
+        if let Some(result) = flex_spi.poll_command_finished() {
+            return result.map(|_| ()).map_err(CommandError::from);
+        }
+
+        #[cfg(feature = "time")]
+        embassy_time::Timer::after_micros(interval_us).await;
+        #[cfg(not(feature = "time"))]
+        embassy_futures::yield_now().await;
+    }
+}
+
 impl FlashConfig {
     /// Read the configuration from the FlexSPI Configuration Block (FCB) on the flash memory.
     ///
@@ -689,6 +1148,9 @@ pub enum ReadError {
 
     /// The command finished, but we did not get the amount of data we expected.
     NotEnoughData(NotEnoughData),
+
+    /// The DMA transfer could not be set up.
+    Dma(dma::Error),
 }
 
 /// We did not receive the amount of data we expected.
@@ -784,6 +1246,38 @@ impl From<MisalignedAccessError> for PageProgramError {
     }
 }
 
+/// Error that can occur when accessing the security registers or block protection of the flash memory.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SecurityError {
+    /// No [`SecurityConfig`] was set with [`FlexSpiNorFlash::set_security_config()`].
+    NotConfigured,
+
+    /// Reading failed.
+    Read(ReadError),
+
+    /// Programming failed.
+    Program(PageProgramError),
+}
+
+impl From<ReadError> for SecurityError {
+    fn from(value: ReadError) -> Self {
+        Self::Read(value)
+    }
+}
+
+impl From<PageProgramError> for SecurityError {
+    fn from(value: PageProgramError) -> Self {
+        Self::Program(value)
+    }
+}
+
+impl From<WriteError> for SecurityError {
+    fn from(value: WriteError) -> Self {
+        Self::Program(value.into())
+    }
+}
+
 /// A write operation would have crossed a page boundary.
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
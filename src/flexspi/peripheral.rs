@@ -9,6 +9,9 @@ mod arm {
     /// The base address of the FlexSPI peripheral,
     pub const FLEXSPI_BASE: u32 = 0x40134000;
 
+    /// The byte offset of the FlexSPI MCR0 register.
+    pub const FLEXSPI_MCR0: u16 = 0x00;
+
     /// The byte offset of the FlexSPI INTR register.
     pub const FLEXSPI_INTR: u16 = 0x14;
 
@@ -41,11 +44,101 @@ mod arm {
 
     /// The bitmask of the SEQTIMEOUT (sequence timeout) interrupt in the INTR register.
     pub const SEQTIMEOUT: u32 = 1 << 11;
+
+    /// The byte offset of the FlexSPI DLLCR[0] register.
+    pub const FLEXSPI_DLLCR0: u16 = 0xC0;
+
+    /// The byte offset of the FlexSPI DLLCR[1] register.
+    pub const FLEXSPI_DLLCR1: u16 = 0xC4;
+
+    /// The byte offset of the FlexSPI STS0 register.
+    pub const FLEXSPI_STS0: u16 = 0xE0;
+
+    /// The byte offset of the FlexSPI STS2 register.
+    pub const FLEXSPI_STS2: u16 = 0xE8;
+
+    /// The bitmask of the SWRESET (software reset) bit in the MCR0 register.
+    pub const MCR0_SWRESET: u32 = 1 << 0;
+
+    /// The bitmask of the MDIS (module disable) bit in the MCR0 register.
+    pub const MCR0_MDIS: u32 = 1 << 1;
+
+    /// The bitmask of the SEQIDLE and ARBIDLE (sequencer and arbiter idle) bits in the STS0 register.
+    pub const STS0_IDLE: u32 = 0b11;
+
+    /// The base address of the CLKCTL0 clock controller.
+    pub const CLKCTL0_BASE: u32 = 0x40001000;
+
+    /// The byte offset of the CLKCTL0 PSCCTL0_SET register.
+    pub const CLKCTL0_PSCCTL0_SET: u16 = 0x40;
+
+    /// The byte offset of the CLKCTL0 PSCCTL0_CLR register.
+    pub const CLKCTL0_PSCCTL0_CLR: u16 = 0x70;
+
+    /// The byte offset of the CLKCTL0 FLEXSPIFCLKSEL register.
+    pub const CLKCTL0_FLEXSPIFCLKSEL: u16 = 0x620;
+
+    /// The byte offset of the CLKCTL0 FLEXSPIFCLKDIV register.
+    pub const CLKCTL0_FLEXSPIFCLKDIV: u16 = 0x624;
+
+    /// The bitmask of the FLEXSPI_OTFAD_CLK bit in the PSCCTL0 registers.
+    pub const PSCCTL0_FLEXSPI: u32 = 1 << 16;
+
+    /// The bitmask of the RESET (reset divider counter) bit in the FLEXSPIFCLKDIV register.
+    pub const FCLKDIV_RESET: u32 = 1 << 29;
+
+    /// The bitmask of the REQFLAG (divider change in progress) bit in the FLEXSPIFCLKDIV register.
+    pub const FCLKDIV_REQFLAG: u32 = 1 << 31;
+
+    /// The base address of the CACHE64 controller in front of the FlexSPI peripheral.
+    pub const CACHE64_BASE: u32 = 0x40033000;
+
+    /// The byte offset of the CACHE64 CCR register.
+    pub const CACHE64_CCR: u16 = 0x800;
+
+    /// The bitmask of the INVW0 (invalidate way 0) bit in the CCR register.
+    pub const CCR_INVW0: u32 = 1 << 24;
+
+    /// The bitmask of the INVW1 (invalidate way 1) bit in the CCR register.
+    pub const CCR_INVW1: u32 = 1 << 26;
+
+    /// The bitmask of the GO (initiate cache command) bit in the CCR register.
+    pub const CCR_GO: u32 = 1 << 31;
 }
 
 #[cfg(target_arch = "arm")]
 use self::arm::*;
 
+/// Serial root clock from which the DLL can lock on the sample clock.
+pub(super) const DLL_MIN_CLOCK_HZ: u32 = 100_000_000;
+
+/// Delay target of the DLL slave delay line used before calibration, in 1/32 of a clock cycle (minus one).
+pub(super) const DEFAULT_DELAY_TARGET: u8 = 0xF;
+
+/// Delay cells of the sample clock when the clock is too slow for the DLL, for a 1 ns data valid time in 75 ps cells.
+pub(super) const OVERRIDE_DELAY_CELLS: u8 = 14;
+
+/// Polls of the DLL lock bits after a clock change before giving up, about 10 ms at the highest core clock.
+const DLL_LOCK_SPINS: u32 = 1_000_000;
+
+/// The bitmasks of the lock bits of the DLLs of port A and port B in the STS2 register.
+const STS2_DLL_LOCKED: [u32; 2] = [0b11, 0b11 << 16];
+
+/// The bitmask of the DLLEN (DLL calibration enable) bit in the DLLCR registers.
+const DLLCR_DLLEN: u32 = 1 << 0;
+
+/// The bit offset of the SLVDLYTARGET (slave delay line target) field in the DLLCR registers.
+const DLLCR_SLVDLYTARGET_SHIFT: u32 = 3;
+
+/// The bitmask of the OVRDEN (delay cell override enable) bit in the DLLCR registers.
+const DLLCR_OVRDEN: u32 = 1 << 8;
+
+/// The bit offset of the OVRDVAL (delay cell override value) field in the DLLCR registers.
+const DLLCR_OVRDVAL_SHIFT: u32 = 9;
+
+/// The LUT opcodes of the dummy instructions: DUMMY_SDR, DUMMY_DDR, DUMMY_RWDS_SDR and DUMMY_RWDS_DDR.
+const LUT_DUMMY_OPCODES: [u16; 4] = [0x0C, 0x2C, 0x0D, 0x2D];
+
 /// Low level FlexSPI interface.
 pub struct FlexSpi<'a> {
     /// The FlexSPI peripheral.
@@ -395,6 +488,309 @@ impl<'a> FlexSpi<'a> {
         }
     }
 
+    /// Invalidate the CACHE64 and the AHB RX buffers in front of the flash memory.
+    ///
+    /// Memory mapped reads may return stale data after an erase or program until this is done.
+    /// The AHB RX buffers are flushed with a software reset of the FlexSPI peripheral,
+    /// which keeps the configuration registers and the LUT.
+    ///
+    /// Part of this function is in the .data section so that it is located in RAM,
+    /// since the FlexSPI peripheral can not serve instruction fetches during the reset.
+    ///
+    /// Interrupts are disabled until both invalidations are complete,
+    /// to prevent interrupt handlers located in FLASH memory from executing.
+    ///
+    /// # Safety
+    /// No IP command may currently be running on the FlexSPI peripheral.
+    ///
+    /// You must also ensure that the .data section is executable before calling this function.
+    pub unsafe fn invalidate_ahb_read_caches(&mut self) {
+        critical_section::with(|_| unsafe { self._invalidate_ahb_read_caches() });
+    }
+
+    /// Implementation details for [`Self::invalidate_ahb_read_caches()`].
+    ///
+    /// This part is located in RAM (the .data section) and implemented in inline assembly,
+    /// to ensure that no instructions need to be fetched from FLASH while the FlexSPI peripheral is in reset.
+    ///
+    /// TODO: Loading the instructions over the data bus works,
+    /// but the end-user may need more control over the address of the function
+    /// if they are using TrustZone.
+    #[unsafe(link_section = ".data")]
+    #[inline(never)]
+    unsafe fn _invalidate_ahb_read_caches(&mut self) {
+        #[cfg(target_arch = "arm")]
+        unsafe {
+            core::arch::asm! {
+                // Reset the FlexSPI peripheral to flush the AHB RX buffers, and wait for the reset to complete.
+                "ldr {value}, [{flexspi_base}, #{FLEXSPI_MCR0}]",
+                "orr {value}, #{MCR0_SWRESET}",
+                "str {value}, [{flexspi_base}, #{FLEXSPI_MCR0}]",
+                "2:",
+                    "ldr {value}, [{flexspi_base}, #{FLEXSPI_MCR0}]",
+                    "tst {value}, #{MCR0_SWRESET}",
+                    "bne 2b",
+
+                // Invalidate both ways of the cache, and wait for the command to complete.
+                "ldr {value}, [{cache64_base}, #{CACHE64_CCR}]",
+                "orr {value}, {ccr_invalidate}",
+                "str {value}, [{cache64_base}, #{CACHE64_CCR}]",
+                "3:",
+                    "ldr {value}, [{cache64_base}, #{CACHE64_CCR}]",
+                    "tst {value}, {ccr_go}",
+                    "bne 3b",
+
+                // Discard anything fetched before the invalidation.
+                "dsb",
+                "isb",
+
+                flexspi_base = in(reg) FLEXSPI_BASE,
+                FLEXSPI_MCR0 = const FLEXSPI_MCR0,
+                MCR0_SWRESET = const MCR0_SWRESET,
+                cache64_base = in(reg) CACHE64_BASE,
+                CACHE64_CCR = const CACHE64_CCR,
+                ccr_invalidate = in(reg) CCR_GO | CCR_INVW1 | CCR_INVW0,
+                ccr_go = in(reg) CCR_GO,
+                value = out(reg) _,
+                options(nostack),
+            }
+        }
+    }
+
+    /// Switch the FlexSPI functional clock to a new source and divider.
+    ///
+    /// The DLLs of the ports with a flash memory attached are reconfigured for the new frequency:
+    /// they calibrate the sample clock at 100 MHz and above,
+    /// and use a fixed delay below that, since the DLL can not lock on slower clocks.
+    /// The delay target of a calibrating DLL is kept.
+    ///
+    /// This allows starting with a safe low frequency,
+    /// and switching to the highest frequency supported by the flash memory once it is known.
+    ///
+    /// Part of this function is in the .data section so that it is located in RAM,
+    /// since the FlexSPI peripheral is disabled while the clock changes.
+    ///
+    /// Interrupts are disabled until the clock change is complete,
+    /// to prevent interrupt handlers located in FLASH memory from executing.
+    ///
+    /// If the DLLs do not lock on the new clock, the FlexSPI peripheral is reset anyway and
+    /// [`SetClockError::DllLockTimeout`] is returned, so the caller can fall back to a slower clock.
+    ///
+    /// # Safety
+    /// The new frequency `root_clock_hz` must be correct, and supported by every connected memory
+    /// with its current command sequences and dummy cycles (see [`Self::set_dummy_cycles()`]).
+    ///
+    /// No IP command may currently be running on the FlexSPI peripheral.
+    ///
+    /// You must also ensure that the .data section is executable before calling this function.
+    pub unsafe fn set_clock(
+        &mut self,
+        source: ClockSource,
+        divider: u16,
+        root_clock_hz: u32,
+    ) -> Result<(), SetClockError> {
+        if !(1..=256).contains(&divider) {
+            return Err(InvalidClockDivider { divider }.into());
+        }
+
+        let flex_spi = unsafe { pac::Flexspi::steal() };
+        let ports = [[FlashPort::A1, FlashPort::A2], [FlashPort::B1, FlashPort::B2]];
+        let mut dllcr = [0; 2];
+        let mut lock_mask = 0;
+        for (((dllcr, ports), index), locked) in dllcr.iter_mut().zip(ports).zip(0..).zip(STS2_DLL_LOCKED) {
+            let current = flex_spi.dllcr(index).read().bits();
+            *dllcr = if ports.iter().any(|&port| self.flash_size_kb(port) != 0) {
+                dll_config_for_clock(current, root_clock_hz)
+            } else {
+                current
+            };
+            if *dllcr & DLLCR_DLLEN != 0 {
+                lock_mask |= locked;
+            }
+        }
+
+        let [dllcr0, dllcr1] = dllcr;
+        let spins = critical_section::with(|_| unsafe {
+            self._set_clock(source as u32, u32::from(divider - 1), dllcr0, dllcr1, lock_mask)
+        });
+        if spins == 0 {
+            return Err(SetClockError::DllLockTimeout);
+        }
+        Ok(())
+    }
+
+    /// Implementation details for [`Self::set_clock()`].
+    ///
+    /// This part is located in RAM (the .data section) and implemented in inline assembly,
+    /// to ensure that no instructions need to be fetched from FLASH while the FlexSPI peripheral is disabled.
+    ///
+    /// Returns the polls of the DLL lock bits that were left, zero if the DLLs did not lock.
+    ///
+    /// TODO: Loading the instructions over the data bus works,
+    /// but the end-user may need more control over the address of the function
+    /// if they are using TrustZone.
+    #[unsafe(link_section = ".data")]
+    #[inline(never)]
+    unsafe fn _set_clock(&mut self, source: u32, divider: u32, dllcr0: u32, dllcr1: u32, lock_mask: u32) -> u32 {
+        #[allow(unused_mut)]
+        let mut spins = DLL_LOCK_SPINS;
+
+        #[cfg(not(target_arch = "arm"))]
+        let _ = (source, divider, dllcr0, dllcr1, lock_mask);
+
+        #[cfg(target_arch = "arm")]
+        unsafe {
+            core::arch::asm! {
+                // Wait for the FlexSPI peripheral to be idle, and disable it.
+                "2:",
+                    "ldr {value}, [{flexspi_base}, #{FLEXSPI_STS0}]",
+                    "and {value}, #{STS0_IDLE}",
+                    "cmp {value}, #{STS0_IDLE}",
+                    "bne 2b",
+                "ldr {value}, [{flexspi_base}, #{FLEXSPI_MCR0}]",
+                "orr {value}, #{MCR0_MDIS}",
+                "str {value}, [{flexspi_base}, #{FLEXSPI_MCR0}]",
+
+                // Gate the clock while the source and divider are changed, and wait for the divider to update.
+                "mov {value}, #{PSCCTL0_FLEXSPI}",
+                "str {value}, [{clkctl0_base}, #{CLKCTL0_PSCCTL0_CLR}]",
+                "str {source}, [{clkctl0_base}, #{CLKCTL0_FLEXSPIFCLKSEL}]",
+                "ldr {value}, [{clkctl0_base}, #{CLKCTL0_FLEXSPIFCLKDIV}]",
+                "orr {value}, #{FCLKDIV_RESET}",
+                "str {value}, [{clkctl0_base}, #{CLKCTL0_FLEXSPIFCLKDIV}]",
+                "str {divider}, [{clkctl0_base}, #{CLKCTL0_FLEXSPIFCLKDIV}]",
+                "3:",
+                    "ldr {value}, [{clkctl0_base}, #{CLKCTL0_FLEXSPIFCLKDIV}]",
+                    "tst {value}, #{FCLKDIV_REQFLAG}",
+                    "bne 3b",
+                "mov {value}, #{PSCCTL0_FLEXSPI}",
+                "str {value}, [{clkctl0_base}, #{CLKCTL0_PSCCTL0_SET}]",
+
+                // Reconfigure the DLLs, enable the FlexSPI peripheral again and wait for the DLLs to lock.
+                "str {dllcr0}, [{flexspi_base}, #{FLEXSPI_DLLCR0}]",
+                "str {dllcr1}, [{flexspi_base}, #{FLEXSPI_DLLCR1}]",
+                "ldr {value}, [{flexspi_base}, #{FLEXSPI_MCR0}]",
+                "bic {value}, #{MCR0_MDIS}",
+                "str {value}, [{flexspi_base}, #{FLEXSPI_MCR0}]",
+                "4:",
+                    "subs {spins}, #1",
+                    "beq 6f",
+                    "ldr {value}, [{flexspi_base}, #{FLEXSPI_STS2}]",
+                    "and {value}, {lock_mask}",
+                    "cmp {value}, {lock_mask}",
+                    "bne 4b",
+                "6:",
+
+                // Reset the FlexSPI peripheral for the new clock, and wait for the reset to complete.
+                "ldr {value}, [{flexspi_base}, #{FLEXSPI_MCR0}]",
+                "orr {value}, #{MCR0_SWRESET}",
+                "str {value}, [{flexspi_base}, #{FLEXSPI_MCR0}]",
+                "5:",
+                    "ldr {value}, [{flexspi_base}, #{FLEXSPI_MCR0}]",
+                    "tst {value}, #{MCR0_SWRESET}",
+                    "bne 5b",
+
+                // Discard anything fetched before the clock change.
+                "dsb",
+                "isb",
+
+                flexspi_base = in(reg) FLEXSPI_BASE,
+                FLEXSPI_MCR0 = const FLEXSPI_MCR0,
+                FLEXSPI_STS0 = const FLEXSPI_STS0,
+                FLEXSPI_STS2 = const FLEXSPI_STS2,
+                FLEXSPI_DLLCR0 = const FLEXSPI_DLLCR0,
+                FLEXSPI_DLLCR1 = const FLEXSPI_DLLCR1,
+                MCR0_SWRESET = const MCR0_SWRESET,
+                MCR0_MDIS = const MCR0_MDIS,
+                STS0_IDLE = const STS0_IDLE,
+                clkctl0_base = in(reg) CLKCTL0_BASE,
+                CLKCTL0_PSCCTL0_SET = const CLKCTL0_PSCCTL0_SET,
+                CLKCTL0_PSCCTL0_CLR = const CLKCTL0_PSCCTL0_CLR,
+                CLKCTL0_FLEXSPIFCLKSEL = const CLKCTL0_FLEXSPIFCLKSEL,
+                CLKCTL0_FLEXSPIFCLKDIV = const CLKCTL0_FLEXSPIFCLKDIV,
+                PSCCTL0_FLEXSPI = const PSCCTL0_FLEXSPI,
+                FCLKDIV_RESET = const FCLKDIV_RESET,
+                FCLKDIV_REQFLAG = const FCLKDIV_REQFLAG,
+                source = in(reg) source,
+                divider = in(reg) divider,
+                dllcr0 = in(reg) dllcr0,
+                dllcr1 = in(reg) dllcr1,
+                lock_mask = in(reg) lock_mask,
+                spins = inout(reg) spins,
+                value = out(reg) _,
+                options(nostack),
+            }
+        }
+
+        spins
+    }
+
+    /// Set the number of dummy cycles of the memory mapped read sequence of a flash port.
+    ///
+    /// This changes the operand of every dummy instruction in the sequences given by [`Self::ahb_read_sequence()`],
+    /// and returns the number of instructions that were changed.
+    /// Use [`Self::write_lut_sequence()`] to change the dummy cycles of other sequences, like those of IP commands.
+    ///
+    /// Each LUT word is changed with a single write, so memory mapped reads see either the old or the new value.
+    ///
+    /// # Safety
+    /// The flash memory must expect the new number of dummy cycles before the next memory mapped read.
+    /// When executing from the same flash memory, its configuration and the LUT must be changed together from RAM.
+    pub unsafe fn set_dummy_cycles(&mut self, port: FlashPort, cycles: u8) -> usize {
+        let flexspi = unsafe { pac::Flexspi::steal() };
+        let mut changed = 0;
+
+        // Unlock the LUT.
+        unsafe { flexspi.lutkey().modify(|_, w| w.key().bits(0x5AF05AF0)) };
+        flexspi.lutcr().write(|w| w.unlock().set_bit());
+
+        for index in self.ahb_read_sequence(port) {
+            for entry in usize::from(index) * 4..usize::from(index) * 4 + 4 {
+                let word = flexspi.lut(entry).read().bits();
+                let (new_word, count) = set_dummy_operands(word, cycles);
+                if count != 0 {
+                    unsafe { flexspi.lut(entry).write(|w| w.bits(new_word)) };
+                    changed += count;
+                }
+            }
+        }
+
+        // Lock the LUT.
+        unsafe { flexspi.lutkey().modify(|_, w| w.key().bits(0x5AF05AF0)) };
+        flexspi.lutcr().write(|w| w.lock().set_bit());
+
+        changed
+    }
+
+    /// Trigger a pre-configured command on the FlexSPI peripheral without waiting for it to complete.
+    ///
+    /// Use [`Self::poll_command_finished()`] to check for completion.
+    ///
+    /// # Safety
+    /// The command may not make the flash memory unreadable while it runs,
+    /// since instructions and interrupt handlers can still be fetched from FLASH.
+    /// In practice, only read commands can be used.
+    ///
+    /// Additionally, no IP command may currently be running on the FlexSPI peripheral.
+    pub unsafe fn trigger_command(&mut self) {
+        let flex_spi = unsafe { pac::Flexspi::steal() };
+        flex_spi.ipcmd().write(|w| w.trg().set_bit());
+    }
+
+    /// Check if the command started with [`Self::trigger_command()`] has finished.
+    ///
+    /// Returns `None` while the command is still running.
+    pub fn poll_command_finished(&mut self) -> Option<Result<pac::flexspi::intr::R, WaitCommandError>> {
+        let flex_spi = unsafe { pac::Flexspi::steal() };
+        let interrupts = flex_spi.intr().read();
+        let finished = interrupts.ipcmddone().bit()
+            || interrupts.ipcmderr().bit()
+            || interrupts.ipcmdge().bit()
+            || interrupts.datalearnfail().bit()
+            || interrupts.seqtimeout().bit();
+        finished.then(|| self.check_and_clear_command_interrupts(interrupts))
+    }
+
     /// Set the IP TX FIFO watermark to the given number of u64 entries.
     ///
     /// Note: Attempts to set the watermark level to zero will set the level to one 64 bit word instead.
@@ -452,6 +848,45 @@ impl<'a> FlexSpi<'a> {
         flex_spi.iprxfcr().modify(|_, w| w.clriprxf().set_bit());
     }
 
+    /// Enable or disable DMA requests for the IP RX FIFO.
+    ///
+    /// While enabled, a DMA request is raised every time the FIFO is filled to the watermark level,
+    /// and the watermark data is popped once the DMA has read it from [`Self::rx_fifo_window()`].
+    pub fn set_rx_fifo_dma_enabled(&mut self, enabled: bool) {
+        let flex_spi = unsafe { pac::Flexspi::steal() };
+        flex_spi.iprxfcr().modify(|_, w| w.rxdmaen().bit(enabled));
+    }
+
+    /// Disable DMA requests for the IP RX FIFO, and discard data until the running command is done.
+    ///
+    /// This lets a read command run to completion after its DMA transfer was aborted,
+    /// instead of leaving the FlexSPI peripheral stalled on a full RX FIFO.
+    /// It does not borrow the driver, so it can be used from a drop guard.
+    pub(super) fn stop_rx_fifo_dma() {
+        let flex_spi = unsafe { pac::Flexspi::steal() };
+        flex_spi.iprxfcr().modify(|_, w| w.rxdmaen().clear_bit());
+
+        loop {
+            let status0 = flex_spi.sts0().read();
+            if status0.seqidle().bit() && status0.arbidle().bit() {
+                break;
+            }
+            if flex_spi.intr().read().iprxwa().bit() {
+                flex_spi.intr().write(|w| w.iprxwa().clear_bit_by_one());
+            }
+        }
+
+        flex_spi.iprxfcr().modify(|_, w| w.clriprxf().set_bit());
+    }
+
+    /// Get the address of the RX FIFO data registers.
+    ///
+    /// The watermark data can be read from the registers starting at this address.
+    pub fn rx_fifo_window(&self) -> *const u32 {
+        let flex_spi = unsafe { pac::Flexspi::steal() };
+        flex_spi.rfdr(0).as_ptr().cast_const()
+    }
+
     /// Clear the entire TX fifo.
     pub fn clear_tx_fifo(&mut self) {
         let flex_spi = unsafe { pac::Flexspi::steal() };
@@ -642,6 +1077,47 @@ pub struct CommandSequence {
     pub parallel: bool,
 }
 
+/// Source of the FlexSPI functional clock.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClockSource {
+    /// Main clock
+    MainClk = 0,
+    /// Main PLL clock
+    MainPllClk = 1,
+    /// AUX0 PLL clock
+    Aux0PllClk = 2,
+    /// FFRO clock
+    FfroClk = 3,
+    /// AUX1 PLL clock
+    Aux1PllClk = 4,
+}
+
+/// The clock divider given to [`FlexSpi::set_clock()`] is out of range (1..=256).
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidClockDivider {
+    /// The requested divider.
+    pub divider: u16,
+}
+
+/// Error that can occur when changing the clock with [`FlexSpi::set_clock()`].
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SetClockError {
+    /// The clock divider is out of range.
+    InvalidDivider(InvalidClockDivider),
+
+    /// The DLLs did not lock on the new clock.
+    DllLockTimeout,
+}
+
+impl From<InvalidClockDivider> for SetClockError {
+    fn from(value: InvalidClockDivider) -> Self {
+        Self::InvalidDivider(value)
+    }
+}
+
 /// Error that can occur while waiting for a command to complete or make progress.
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -786,6 +1262,38 @@ pub struct InvalidCommandSequence {
     pub count: u8,
 }
 
+/// Compute the DLLCR value for a new serial root clock.
+///
+/// The delay target of a calibrating DLL is kept, other DLLs get the default delay target when they start calibrating.
+fn dll_config_for_clock(current: u32, root_clock_hz: u32) -> u32 {
+    if root_clock_hz < DLL_MIN_CLOCK_HZ {
+        DLLCR_OVRDEN | (u32::from(OVERRIDE_DELAY_CELLS) << DLLCR_OVRDVAL_SHIFT)
+    } else if current & DLLCR_DLLEN != 0 {
+        (current & (0xF << DLLCR_SLVDLYTARGET_SHIFT)) | DLLCR_DLLEN
+    } else {
+        (u32::from(DEFAULT_DELAY_TARGET) << DLLCR_SLVDLYTARGET_SHIFT) | DLLCR_DLLEN
+    }
+}
+
+/// Set the operand of the dummy instructions in a LUT word to `cycles`.
+///
+/// Each LUT word holds two instructions, with the opcode in the upper 6 bits and the operand in the lower 8 bits.
+/// Returns the new word and the number of dummy instructions in it.
+fn set_dummy_operands(word: u32, cycles: u8) -> (u32, usize) {
+    let mut count = 0;
+    let mut set = |instruction: u16| {
+        if LUT_DUMMY_OPCODES.contains(&(instruction >> 10)) {
+            count += 1;
+            (instruction & 0xFF00) | u16::from(cycles)
+        } else {
+            instruction
+        }
+    };
+    let low = set(word as u16);
+    let high = set((word >> 16) as u16);
+    ((u32::from(high) << 16) | u32::from(low), count)
+}
+
 /// Copy bytes from `source` to `dest`.
 ///
 /// The data is read from the source as bytes,
@@ -975,4 +1483,24 @@ mod tests {
             assert!(dest == [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF]);
         }
     }
+
+    #[test]
+    fn test_dll_config_for_clock() {
+        // Too slow for the DLL: fixed delay.
+        assert!(dll_config_for_clock(0x0000_0079, 48_000_000) == ((1 << 8) | (14 << 9)));
+        // Calibrating DLL keeps its delay target.
+        assert!(dll_config_for_clock(0x0000_0029, 200_000_000) == 0x0000_0029);
+        // Fixed delay switches to calibration with the default delay target.
+        assert!(dll_config_for_clock(0x0000_1D00, 200_000_000) == 0x0000_0079);
+    }
+
+    #[test]
+    fn test_set_dummy_operands() {
+        // CMD_DDR 0xEE on 8 pads, CMD_DDR 0x11 on 8 pads: no dummy.
+        assert!(set_dummy_operands(0x8711_87EE, 20) == (0x8711_87EE, 0));
+        // RADDR_DDR 0x20 on 8 pads, DUMMY_DDR 0x12 on 8 pads.
+        assert!(set_dummy_operands(0xB312_8B20, 20) == (0xB314_8B20, 1));
+        // DUMMY_SDR 0x08 on 4 pads, READ_SDR 0x04 on 4 pads.
+        assert!(set_dummy_operands(0x2604_3208, 6) == (0x2604_3206, 1));
+    }
 }
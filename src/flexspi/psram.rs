@@ -0,0 +1,782 @@
+//! FlexSPI PSRAM driver.
+//!
+//! The PSRAM is connected to port B1 of the FlexSPI peripheral and mapped into memory after the flash memory on port A,
+//! so it can hold buffers that do not fit in the on-chip SRAM.
+
+use mimxrt600_fcb::FlexSpiLutOpcode::{
+    CMD_DDR, CMD_SDR, DUMMY_RWDS_DDR, DUMMY_SDR, RADDR_DDR, RADDR_SDR, READ_DDR, READ_SDR, STOP, WRITE_DDR, WRITE_SDR,
+};
+use mimxrt600_fcb::FlexSpiNumPads::{Octal, Quad, Single};
+use mimxrt600_fcb::flexspi_lut_seq;
+
+use super::nor_flash::{CommandError, NotEnoughData};
+use super::nor_storage_bus::FlexSpiPin;
+use super::peripheral::{
+    ClockSource, CommandSequence, DEFAULT_DELAY_TARGET, DLL_MIN_CLOCK_HZ, FlashPort, FlexSpi, OVERRIDE_DELAY_CELLS,
+    SetClockError,
+};
+use crate::peripherals::FLEXSPI;
+use crate::{Peri, pac};
+
+/// The start of the memory mapped FlexSPI address space.
+const FLEXSPI_AHB_BASE: u32 = 0x0800_0000;
+
+/// The size of the memory mapped FlexSPI address space.
+const FLEXSPI_AHB_SIZE: u32 = 0x0800_0000;
+
+/// Index of the DLL of port B in the DLLCR registers.
+const DLL_PORT_B: usize = 1;
+
+/// Index of port B1 in the FLSHCR1 and FLSHCR2 registers.
+const FLSHCR_PORT_B1: usize = 2;
+
+/// Number of delay targets of the DLL slave delay line.
+const DELAY_TARGETS: u8 = 16;
+
+/// Polls of the DLL status before giving up on a lock.
+const DLL_LOCK_RETRIES: u32 = 100_000;
+
+/// Time the PSRAM needs after a reset, in microseconds.
+const RESET_DELAY_US: u64 = 2;
+
+/// Highest core clock, so the reset delay is long enough at any core clock.
+const CORE_MAX_HZ: u64 = 500_000_000;
+
+/// CS setup and hold time, in serial clock cycles.
+const CS_SETUP_HOLD_CYCLES: u8 = 3;
+
+/// Minimum time between two chip select assertions, in serial clock cycles.
+const CS_INTERVAL_CYCLES: u16 = 5;
+
+/// Octal PSRAM mode register 0, holding the read latency code.
+const MR0: u8 = 0;
+/// Octal PSRAM mode register 4, holding the write latency code.
+const MR4: u8 = 4;
+/// Octal PSRAM mode register 8, holding the burst configuration.
+const MR8: u8 = 8;
+/// MR0: read latency code.
+const MR0_LATENCY: u8 = 0b111 << 2;
+/// MR4: write latency code.
+const MR4_LATENCY: u8 = 0b111 << 5;
+/// MR8: 1 KiB wrapped bursts in hybrid mode, allowed to cross row boundaries.
+const MR8_BURST: u8 = 0x0F;
+
+/// Number of 32 bit words in the calibration pattern.
+const PATTERN_WORDS: usize = 16;
+
+/// Highest serial clock of quad PSRAM, for the quad read command with 6 wait cycles.
+const QUAD_MAX_HZ: u32 = 133_000_000;
+
+/// Sequence indexes in the LUT for the PSRAM.
+///
+/// The upper half of the LUT (16..32) ignores writes, see the NOR flash driver.
+/// The memory mapped sequences take the two entries the NOR flash configuration block leaves unused,
+/// the commands share the entry of the NOR flash security sequences and are loaded before each use.
+pub(super) mod sequence {
+    pub const READ: u8 = 6;
+    pub const WRITE: u8 = 7;
+    pub const COMMAND: u8 = super::super::nor_flash::sequence::SECURITY;
+}
+
+/// Type of PSRAM connected to port B of the FlexSPI peripheral.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PsramKind {
+    /// Octal PSRAM transferring on both clock edges with a read strobe on DQS, like the APS6408L.
+    ///
+    /// The latency is programmed in the mode registers of the PSRAM, for the frequency of the serial clock.
+    OctalDdr,
+
+    /// Quad PSRAM using the quad SPI read (`0xEB`) and write (`0x38`) commands, like the APS1604M.
+    QuadSdr,
+}
+
+/// Configuration of the [`Psram`] driver.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PsramConfig {
+    /// Type of the PSRAM.
+    pub kind: PsramKind,
+
+    /// Size of the PSRAM in KiB.
+    pub size_kb: u32,
+
+    /// Frequency of the FlexSPI serial root clock in Hz, as set up for the flash memory on port A.
+    ///
+    /// Octal PSRAM transfers on both clock edges at half this frequency.
+    pub root_clock_hz: u32,
+}
+
+/// Latency of octal PSRAM for a serial clock frequency.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct OctalLatency {
+    /// Latency in clock cycles, for both reads and writes.
+    cycles: u8,
+
+    /// Code of the read latency in MR0.
+    read_code: u8,
+
+    /// Code of the write latency in MR4.
+    write_code: u8,
+}
+
+/// Get the lowest latency of octal PSRAM that supports a serial clock of `clock_hz`.
+fn octal_latency(clock_hz: u32) -> Option<OctalLatency> {
+    // Maximum frequency, latency, MR0 code and MR4 code, from the APS6408L datasheet.
+    const LATENCIES: [(u32, u8, u8, u8); 5] = [
+        (66_000_000, 3, 0b000, 0b000),
+        (109_000_000, 4, 0b001, 0b100),
+        (133_000_000, 5, 0b010, 0b010),
+        (166_000_000, 6, 0b011, 0b110),
+        (200_000_000, 7, 0b100, 0b001),
+    ];
+
+    LATENCIES
+        .iter()
+        .find(|(max_hz, ..)| clock_hz <= *max_hz)
+        .map(|&(_, cycles, read_code, write_code)| OctalLatency {
+            cycles,
+            read_code,
+            write_code,
+        })
+}
+
+/// Get the latency of octal PSRAM for a serial root clock of `root_clock_hz`, checking that the PSRAM supports it.
+fn latency_for_clock(kind: PsramKind, root_clock_hz: u32) -> Result<Option<OctalLatency>, PsramError> {
+    match kind {
+        PsramKind::OctalDdr => octal_latency(root_clock_hz / 2)
+            .map(Some)
+            .ok_or(PsramError::UnsupportedClock(root_clock_hz)),
+        PsramKind::QuadSdr if root_clock_hz > QUAD_MAX_HZ => Err(PsramError::UnsupportedClock(root_clock_hz)),
+        PsramKind::QuadSdr => Ok(None),
+    }
+}
+
+/// Get the delay target in the middle of the longest run of passing delay targets.
+///
+/// Bit `n` of `passes` is set when delay target `n` read the calibration pattern back correctly.
+fn calibration_window(passes: u16) -> Option<u8> {
+    let mut best: Option<(u8, u8)> = None;
+    let mut start = 0;
+
+    for target in 0..=DELAY_TARGETS {
+        let pass = target < DELAY_TARGETS && passes & (1 << target) != 0;
+        if pass {
+            continue;
+        }
+
+        let len = target - start;
+        if len > 0 && best.is_none_or(|(_, best_len)| len > best_len) {
+            best = Some((start, len));
+        }
+        start = target + 1;
+    }
+
+    best.map(|(start, len)| start + (len - 1) / 2)
+}
+
+/// Get the calibration pattern word at `index`.
+///
+/// The words toggle all data lines and differ from each other, so sampling them at the wrong edge does not read them back.
+fn pattern_word(index: usize) -> u32 {
+    let index = index as u32;
+    let word = if index % 2 == 0 { 0x55AA_33CC } else { 0xAA55_CC33 };
+    word ^ index.wrapping_mul(0x0101_0101)
+}
+
+/// FlexSPI PSRAM driver.
+///
+/// The driver sets up port B1 of the FlexSPI peripheral next to the flash memory on port A.
+/// It keeps the global configuration of the peripheral,
+/// so the FlexSPI clock and the read sample clock source must already suit the PSRAM.
+/// Octal PSRAM needs the read strobe from the DQS pad as sample clock.
+///
+/// Once created, the PSRAM is accessed through memory mapped reads and writes, see [`Self::as_mut_slice()`].
+pub struct Psram<'a> {
+    /// The FlexSPI peripheral.
+    flex_spi: FlexSpi<'a>,
+
+    /// The configuration of the PSRAM.
+    config: PsramConfig,
+
+    /// Cycles before data of octal PSRAM, as programmed in its mode registers.
+    latency: u8,
+
+    /// Configuration of port B before the driver took it over, restored on drop.
+    saved: SavedPort,
+}
+
+/// Configuration of port B and the LUT entries the driver overwrites.
+struct SavedPort {
+    flshb1cr0: u32,
+    flshcr1: u32,
+    flshcr2: u32,
+    wmenb: bool,
+    dllcr: u32,
+    read: [u32; 4],
+    write: [u32; 4],
+}
+
+impl<'a> Psram<'a> {
+    /// Create a new PSRAM driver for quad PSRAM on port B1.
+    ///
+    /// # Safety
+    /// The driver overwrites entries in the FlexSPI LUT and the configuration of port B,
+    /// which might interfer with memory mapped flash access in non-default system configurations.
+    pub unsafe fn new_quad(
+        flex_spi: Peri<'a, FLEXSPI>,
+        data0: Peri<'a, impl FlexSpiPin>,
+        data1: Peri<'a, impl FlexSpiPin>,
+        data2: Peri<'a, impl FlexSpiPin>,
+        data3: Peri<'a, impl FlexSpiPin>,
+        clk: Peri<'a, impl FlexSpiPin>,
+        cs: Peri<'a, impl FlexSpiPin>,
+        config: PsramConfig,
+    ) -> Result<Self, PsramError> {
+        data0.config_pin();
+        data1.config_pin();
+        data2.config_pin();
+        data3.config_pin();
+        clk.config_pin();
+        cs.config_pin();
+
+        unsafe { Self::new_no_pin_config(flex_spi, config) }
+    }
+
+    /// Create a new PSRAM driver for octal PSRAM on port B1.
+    ///
+    /// The DQS pad of port B is board specific and must be configured by the caller.
+    ///
+    /// # Safety
+    /// The same requirements as for [`Self::new_quad()`] apply.
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn new_octal(
+        flex_spi: Peri<'a, FLEXSPI>,
+        data0: Peri<'a, impl FlexSpiPin>,
+        data1: Peri<'a, impl FlexSpiPin>,
+        data2: Peri<'a, impl FlexSpiPin>,
+        data3: Peri<'a, impl FlexSpiPin>,
+        data4: Peri<'a, impl FlexSpiPin>,
+        data5: Peri<'a, impl FlexSpiPin>,
+        data6: Peri<'a, impl FlexSpiPin>,
+        data7: Peri<'a, impl FlexSpiPin>,
+        clk: Peri<'a, impl FlexSpiPin>,
+        cs: Peri<'a, impl FlexSpiPin>,
+        config: PsramConfig,
+    ) -> Result<Self, PsramError> {
+        data4.config_pin();
+        data5.config_pin();
+        data6.config_pin();
+        data7.config_pin();
+
+        unsafe { Self::new_quad(flex_spi, data0, data1, data2, data3, clk, cs, config) }
+    }
+
+    /// Create a new PSRAM driver without configuring any pins.
+    ///
+    /// The PSRAM is reset, its latency is set for the serial clock and the read sample clock is calibrated.
+    /// Calibration overwrites the first 64 bytes of the PSRAM.
+    ///
+    /// # Safety
+    /// The same requirements as for [`Self::new_quad()`] apply.
+    pub unsafe fn new_no_pin_config(flex_spi: Peri<'a, FLEXSPI>, config: PsramConfig) -> Result<Self, PsramError> {
+        let mut flex_spi = FlexSpi::new(flex_spi);
+        let saved = {
+            let regs = unsafe { pac::Flexspi::steal() };
+            SavedPort {
+                flshb1cr0: regs.flshb1cr0().read().bits(),
+                flshcr1: regs.flshcr1(FLSHCR_PORT_B1).read().bits(),
+                flshcr2: regs.flshcr2(FLSHCR_PORT_B1).read().bits(),
+                wmenb: regs.flshcr4().read().wmenb().bit_is_set(),
+                dllcr: regs.dllcr(DLL_PORT_B).read().bits(),
+                read: flex_spi.read_lut_sequence(sequence::READ),
+                write: flex_spi.read_lut_sequence(sequence::WRITE),
+            }
+        };
+        let mut me = Self {
+            flex_spi,
+            config,
+            latency: 0,
+            saved,
+        };
+
+        let offset = me.offset();
+        if config.size_kb == 0 || u64::from(offset) + u64::from(config.size_kb) * 1024 > u64::from(FLEXSPI_AHB_SIZE) {
+            return Err(PsramError::InvalidSize(config.size_kb));
+        }
+
+        let latency = latency_for_clock(config.kind, config.root_clock_hz)?;
+
+        me.latency = latency.map_or(0, |latency| latency.cycles);
+        unsafe {
+            me.write_sequences();
+        }
+        me.configure_port();
+        me.set_delay_target(DEFAULT_DELAY_TARGET)?;
+        me.reset()?;
+
+        if let Some(latency) = latency {
+            let mr0 = me.read_mode_register(MR0)?;
+            let mr4 = me.read_mode_register(MR4)?;
+            let mr8 = me.read_mode_register(MR8)?;
+
+            unsafe {
+                me.write_mode_register(MR8, mr8 | MR8_BURST)?;
+                me.write_mode_register(MR0, (mr0 & !MR0_LATENCY) | (latency.read_code << 2))?;
+                me.write_mode_register(MR4, (mr4 & !MR4_LATENCY) | (latency.write_code << 5))?;
+            }
+        }
+
+        me.calibrate()?;
+        Ok(me)
+    }
+
+    /// Get the configuration of the PSRAM.
+    pub fn config(&self) -> &PsramConfig {
+        &self.config
+    }
+
+    /// Get the size of the PSRAM in bytes.
+    pub fn size_bytes(&self) -> u32 {
+        self.config.size_kb * 1024
+    }
+
+    /// Get the CPU address of the memory mapped PSRAM.
+    pub fn address(&self) -> *mut u8 {
+        (FLEXSPI_AHB_BASE + self.offset()) as *mut u8
+    }
+
+    /// Get the PSRAM as a slice of bytes.
+    ///
+    /// The contents of the PSRAM are unspecified after power-up.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: The PSRAM stays mapped at this address for as long as the driver lives,
+        // and borrowing the driver mutably gives unique access to it.
+        unsafe { core::slice::from_raw_parts_mut(self.address(), self.size_bytes() as usize) }
+    }
+
+    /// Read a mode register of octal PSRAM.
+    pub fn read_mode_register(&mut self, register: u8) -> Result<u8, PsramError> {
+        self.check_octal()?;
+
+        // Registers are read in pairs, since two bytes are transferred per clock.
+        let mut buffer = [0u8; 2];
+        self.flex_spi.set_rx_fifo_watermark_u64_words(1);
+        self.flex_spi.clear_rx_fifo();
+        let read_register = [
+            flexspi_lut_seq(CMD_DDR, Octal, 0x40, RADDR_DDR, Octal, 0x20),
+            flexspi_lut_seq(DUMMY_RWDS_DDR, Octal, self.latency, READ_DDR, Octal, 0x04),
+            0,
+            0,
+        ];
+        self.command(read_register, u32::from(register), buffer.len() as u16)?;
+
+        let read = self.flex_spi.drain_rx_fifo(&mut buffer);
+        if read != buffer.len() {
+            return Err(NotEnoughData {
+                expected: buffer.len(),
+                actual: read,
+            }
+            .into());
+        }
+
+        let [value, _] = buffer;
+        Ok(value)
+    }
+
+    /// Write a mode register of octal PSRAM.
+    ///
+    /// # Safety
+    /// Changing the latency or burst configuration of the PSRAM changes the behaviour of memory mapped access.
+    /// You must ensure that memory mapped access still behaves properly.
+    pub unsafe fn write_mode_register(&mut self, register: u8, value: u8) -> Result<(), PsramError> {
+        self.check_octal()?;
+
+        self.flex_spi.set_tx_fifo_watermark_u64_words(1);
+        self.flex_spi.clear_tx_fifo();
+        self.flex_spi.fill_tx_fifo(&[value]);
+        let write_register = [
+            flexspi_lut_seq(CMD_DDR, Octal, 0xC0, RADDR_DDR, Octal, 0x20),
+            flexspi_lut_seq(WRITE_DDR, Octal, 0x08, STOP, Single, 0x00),
+            0,
+            0,
+        ];
+        self.command(write_register, u32::from(register), 1)
+    }
+
+    /// Change the FlexSPI serial root clock, see [`FlexSpi::set_clock()`].
+    ///
+    /// The latency of octal PSRAM follows the new clock: it is raised before a faster clock is set,
+    /// and lowered after a slower one.
+    /// The delay target of the sample clock is kept, call [`Self::calibrate()`] to find a new one.
+    ///
+    /// # Safety
+    /// The same requirements as for [`FlexSpi::set_clock()`] apply, for the flash memory on port A.
+    pub unsafe fn set_clock(
+        &mut self,
+        source: ClockSource,
+        divider: u16,
+        root_clock_hz: u32,
+    ) -> Result<(), PsramError> {
+        let latency = latency_for_clock(self.config.kind, root_clock_hz)?;
+
+        if let Some(latency) = latency.filter(|latency| latency.cycles > self.latency) {
+            unsafe { self.set_latency(latency)? };
+        }
+
+        let result = unsafe { self.flex_spi.set_clock(source, divider, root_clock_hz) };
+        if !matches!(result, Err(SetClockError::InvalidDivider(_))) {
+            // The clock changed even if the DLLs did not lock.
+            self.config.root_clock_hz = root_clock_hz;
+        }
+        result.map_err(PsramError::Clock)?;
+
+        if let Some(latency) = latency.filter(|latency| latency.cycles < self.latency) {
+            unsafe { self.set_latency(latency)? };
+        }
+        Ok(())
+    }
+
+    /// Program `latency` into the mode registers of octal PSRAM and into the memory mapped sequences.
+    ///
+    /// # Safety
+    /// The latency must suit the current serial clock, or a clock about to be set.
+    unsafe fn set_latency(&mut self, latency: OctalLatency) -> Result<(), PsramError> {
+        let mr0 = self.read_mode_register(MR0)?;
+        let mr4 = self.read_mode_register(MR4)?;
+
+        unsafe {
+            self.write_mode_register(MR0, (mr0 & !MR0_LATENCY) | (latency.read_code << 2))?;
+            self.write_mode_register(MR4, (mr4 & !MR4_LATENCY) | (latency.write_code << 5))?;
+        }
+
+        self.latency = latency.cycles;
+        // SAFETY: The sequences serve the PSRAM only.
+        unsafe {
+            self.write_sequences();
+            self.flex_spi.invalidate_ahb_read_caches();
+        }
+        Ok(())
+    }
+
+    /// Calibrate the read sample clock of port B.
+    ///
+    /// This writes a test pattern to the first 64 bytes of the PSRAM, and reads it back with each delay of the sample clock.
+    /// The delay in the middle of the longest passing range is kept.
+    ///
+    /// Below 100 MHz the DLL can not lock, so a fixed delay is used and only the pattern is checked.
+    pub fn calibrate(&mut self) -> Result<(), PsramError> {
+        let base = self.address().cast::<u32>();
+        for index in 0..PATTERN_WORDS {
+            // SAFETY: The pattern fits in the PSRAM, which is at least 1 KiB large.
+            unsafe { base.add(index).write_volatile(pattern_word(index)) };
+        }
+
+        if self.config.root_clock_hz < DLL_MIN_CLOCK_HZ {
+            if !self.check_pattern() {
+                return Err(PsramError::CalibrationFailed);
+            }
+            return Ok(());
+        }
+
+        let mut passes = 0u16;
+        for target in 0..DELAY_TARGETS {
+            self.set_delay_target(target)?;
+            if self.check_pattern() {
+                passes |= 1 << target;
+            }
+        }
+
+        let target = calibration_window(passes).ok_or(PsramError::CalibrationFailed)?;
+        self.set_delay_target(target)
+    }
+
+    /// Read the calibration pattern back, with fresh data from the PSRAM.
+    fn check_pattern(&mut self) -> bool {
+        // SAFETY: No IP command is running, all of them are waited for.
+        unsafe { self.flex_spi.invalidate_ahb_read_caches() };
+
+        let base = self.address().cast::<u32>();
+        // SAFETY: The pattern fits in the PSRAM, which is at least 1 KiB large.
+        (0..PATTERN_WORDS).all(|index| unsafe { base.add(index).read_volatile() } == pattern_word(index))
+    }
+
+    /// Get the offset of port B1 in the FlexSPI address space, behind the flash memory on port A.
+    fn offset(&self) -> u32 {
+        let a1 = self.flex_spi.flash_size_kb(FlashPort::A1);
+        let a2 = self.flex_spi.flash_size_kb(FlashPort::A2);
+        a1.saturating_add(a2).saturating_mul(1024)
+    }
+
+    /// Check that the PSRAM has mode registers.
+    fn check_octal(&self) -> Result<(), PsramError> {
+        match self.config.kind {
+            PsramKind::OctalDdr => Ok(()),
+            PsramKind::QuadSdr => Err(PsramError::Unsupported),
+        }
+    }
+
+    /// Copy the memory mapped sequences for the PSRAM into the LUT, with `latency` cycles before data for octal PSRAM.
+    ///
+    /// # Safety
+    /// The sequences may not be in use for memory mapped access to another device.
+    unsafe fn write_sequences(&mut self) {
+        let latency = self.latency;
+        let (read, write) = match self.config.kind {
+            // The read and write latency doubles when DQS signals a refresh collision.
+            PsramKind::OctalDdr => (
+                [
+                    flexspi_lut_seq(CMD_DDR, Octal, 0x20, RADDR_DDR, Octal, 0x20),
+                    flexspi_lut_seq(DUMMY_RWDS_DDR, Octal, latency, READ_DDR, Octal, 0x04),
+                    0,
+                    0,
+                ],
+                [
+                    flexspi_lut_seq(CMD_DDR, Octal, 0xA0, RADDR_DDR, Octal, 0x20),
+                    flexspi_lut_seq(DUMMY_RWDS_DDR, Octal, latency, WRITE_DDR, Octal, 0x04),
+                    0,
+                    0,
+                ],
+            ),
+            PsramKind::QuadSdr => (
+                [
+                    flexspi_lut_seq(CMD_SDR, Single, 0xEB, RADDR_SDR, Quad, 0x18),
+                    flexspi_lut_seq(DUMMY_SDR, Quad, 0x06, READ_SDR, Quad, 0x04),
+                    0,
+                    0,
+                ],
+                [
+                    flexspi_lut_seq(CMD_SDR, Single, 0x38, RADDR_SDR, Quad, 0x18),
+                    flexspi_lut_seq(WRITE_SDR, Quad, 0x04, STOP, Single, 0x00),
+                    0,
+                    0,
+                ],
+            ),
+        };
+
+        unsafe {
+            self.flex_spi.write_lut_sequence(sequence::READ, read);
+            self.flex_spi.write_lut_sequence(sequence::WRITE, write);
+        }
+    }
+
+    /// Configure port B1 for the PSRAM: its size, CS timing, memory mapped sequences and write mask.
+    fn configure_port(&mut self) {
+        let flex_spi = unsafe { pac::Flexspi::steal() };
+
+        // Wait for the bus to be idle before changing the flash configuration.
+        while !(flex_spi.sts0().read().arbidle().bit_is_set() && flex_spi.sts0().read().seqidle().bit_is_set()) {}
+
+        flex_spi
+            .flshb1cr0()
+            .write(|w| unsafe { w.flshsz().bits(self.config.size_kb) });
+        flex_spi.flshcr1(FLSHCR_PORT_B1).write(|w| unsafe {
+            w.tcss()
+                .bits(CS_SETUP_HOLD_CYCLES)
+                .tcsh()
+                .bits(CS_SETUP_HOLD_CYCLES)
+                .csinterval()
+                .bits(CS_INTERVAL_CYCLES)
+        });
+        flex_spi.flshcr2(FLSHCR_PORT_B1).write(|w| unsafe {
+            w.ardseqid()
+                .bits(sequence::READ)
+                .ardseqnum()
+                .bits(0)
+                .awrseqid()
+                .bits(sequence::WRITE)
+                .awrseqnum()
+                .bits(0)
+        });
+
+        // Octal PSRAM takes DQS as data mask, for writes smaller than the two bytes of a clock cycle.
+        flex_spi
+            .flshcr4()
+            .modify(|_, w| w.wmenb().bit(self.config.kind == PsramKind::OctalDdr));
+    }
+
+    /// Set the delay target of the DLL of port B, and wait for the DLL to lock.
+    ///
+    /// Below 100 MHz the DLL is bypassed with a fixed delay instead.
+    fn set_delay_target(&mut self, target: u8) -> Result<(), PsramError> {
+        let flex_spi = unsafe { pac::Flexspi::steal() };
+
+        if self.config.root_clock_hz < DLL_MIN_CLOCK_HZ {
+            flex_spi.dllcr(DLL_PORT_B).write(|w| unsafe {
+                w.dllen()
+                    .clear_bit()
+                    .ovrden()
+                    .set_bit()
+                    .ovrdval()
+                    .bits(OVERRIDE_DELAY_CELLS)
+            });
+            return Ok(());
+        }
+
+        flex_spi
+            .dllcr(DLL_PORT_B)
+            .write(|w| unsafe { w.dllen().set_bit().slvdlytarget().bits(target).ovrden().clear_bit() });
+
+        for _ in 0..DLL_LOCK_RETRIES {
+            let sts2 = flex_spi.sts2().read();
+            if sts2.bslvlock().bit_is_set() && sts2.breflock().bit_is_set() {
+                // The DLL needs some more time after it reports a lock (ERR011377).
+                cortex_m::asm::delay(100);
+                return Ok(());
+            }
+        }
+
+        Err(PsramError::DllLockTimeout)
+    }
+
+    /// Reset the PSRAM to its default configuration.
+    fn reset(&mut self) -> Result<(), PsramError> {
+        match self.config.kind {
+            PsramKind::OctalDdr => {
+                let reset = [
+                    flexspi_lut_seq(CMD_DDR, Octal, 0xFF, DUMMY_RWDS_DDR, Octal, 0x03),
+                    0,
+                    0,
+                    0,
+                ];
+                self.command(reset, 0, 0)?;
+            }
+            PsramKind::QuadSdr => {
+                let reset_enable = [flexspi_lut_seq(CMD_SDR, Single, 0x66, STOP, Single, 0x00), 0, 0, 0];
+                let reset = [flexspi_lut_seq(CMD_SDR, Single, 0x99, STOP, Single, 0x00), 0, 0, 0];
+                self.command(reset_enable, 0, 0)?;
+                self.command(reset, 0, 0)?;
+            }
+        }
+
+        crate::clocks::delay_loop_clocks(RESET_DELAY_US, CORE_MAX_HZ);
+        Ok(())
+    }
+
+    /// Run a command sequence on the PSRAM at `address`, transferring `data_size` bytes through the IP FIFOs.
+    fn command(&mut self, sequence: [u32; 4], address: u32, data_size: u16) -> Result<(), PsramError> {
+        let address = self.offset() + address;
+
+        // SAFETY: Commands are waited for, so no IP command is running.
+        // The PSRAM sequences only access port B, so they do not change the running program.
+        // The LUT entry is loaded before each use, it does not serve memory mapped access.
+        unsafe {
+            self.flex_spi.write_lut_sequence(sequence::COMMAND, sequence);
+            self.flex_spi
+                .configure_command_sequence(CommandSequence {
+                    start: sequence::COMMAND,
+                    count: 1,
+                    address,
+                    data_size,
+                    parallel: false,
+                })
+                .map_err(|e| PsramError::Command(e.into()))?;
+            self.flex_spi
+                .trigger_command_and_wait()
+                .map_err(|e| PsramError::Command(e.into()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Psram<'_> {
+    fn drop(&mut self) {
+        let flex_spi = unsafe { pac::Flexspi::steal() };
+        let saved = &self.saved;
+
+        // Wait for the bus to be idle before changing the flash configuration.
+        while !(flex_spi.sts0().read().arbidle().bit_is_set() && flex_spi.sts0().read().seqidle().bit_is_set()) {}
+
+        // SAFETY: The values were read from the same registers when the driver was created.
+        unsafe {
+            flex_spi.flshb1cr0().write(|w| w.bits(saved.flshb1cr0));
+            flex_spi.flshcr1(FLSHCR_PORT_B1).write(|w| w.bits(saved.flshcr1));
+            flex_spi.flshcr2(FLSHCR_PORT_B1).write(|w| w.bits(saved.flshcr2));
+            flex_spi.dllcr(DLL_PORT_B).write(|w| w.bits(saved.dllcr));
+        }
+        flex_spi.flshcr4().modify(|_, w| w.wmenb().bit(saved.wmenb));
+
+        // SAFETY: Port B no longer maps the PSRAM, so the sequences are not in use.
+        unsafe {
+            self.flex_spi.write_lut_sequence(sequence::READ, saved.read);
+            self.flex_spi.write_lut_sequence(sequence::WRITE, saved.write);
+            self.flex_spi.invalidate_ahb_read_caches();
+        }
+    }
+}
+
+/// Error that can occur when using the PSRAM.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PsramError {
+    /// The size of the PSRAM is zero, or it does not fit in the FlexSPI address space behind the flash memory.
+    InvalidSize(u32),
+
+    /// The serial clock is too fast for the PSRAM.
+    UnsupportedClock(u32),
+
+    /// The operation is not supported by this type of PSRAM.
+    Unsupported,
+
+    /// A command to the PSRAM failed.
+    Command(CommandError),
+
+    /// The command finished, but we did not get the amount of data we expected.
+    NotEnoughData(NotEnoughData),
+
+    /// The DLL of port B did not lock on the sample clock.
+    DllLockTimeout,
+
+    /// Changing the FlexSPI clock failed.
+    Clock(SetClockError),
+
+    /// The test pattern did not read back correctly with any delay of the sample clock.
+    CalibrationFailed,
+}
+
+impl From<NotEnoughData> for PsramError {
+    fn from(value: NotEnoughData) -> Self {
+        Self::NotEnoughData(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_for_clock() {
+        assert_eq!(
+            octal_latency(200_000_000),
+            Some(OctalLatency {
+                cycles: 7,
+                read_code: 0b100,
+                write_code: 0b001
+            })
+        );
+        assert!(matches!(
+            octal_latency(100_000_000),
+            Some(OctalLatency { cycles: 4, .. })
+        ));
+        assert!(matches!(
+            octal_latency(66_000_000),
+            Some(OctalLatency { cycles: 3, .. })
+        ));
+        assert_eq!(octal_latency(250_000_000), None);
+    }
+
+    #[test]
+    fn calibration_window_middle() {
+        assert_eq!(calibration_window(0), None);
+        assert_eq!(calibration_window(0xFFFF), Some(7));
+        assert_eq!(calibration_window(0b0000_0000_0111_1100), Some(4));
+        // The longest run wins over an earlier shorter one.
+        assert_eq!(calibration_window(0b0011_1100_0000_0011), Some(11));
+        assert_eq!(calibration_window(1 << 15), Some(15));
+    }
+
+    #[test]
+    fn pattern_words_differ() {
+        assert!((1..PATTERN_WORDS).all(|index| pattern_word(index) != pattern_word(index - 1)));
+    }
+}
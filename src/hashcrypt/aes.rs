@@ -0,0 +1,752 @@
+#[cfg(feature = "cipher")]
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use super::{Algorithm, Async, Blocking, Error, Hashcrypt, Mode};
+use crate::dma::LinkedDescriptor;
+
+/// Block length
+pub const BLOCK_LEN: usize = 16;
+
+/// AES key, the variant selects the key size programmed into the engine
+#[derive(Clone)]
+pub enum AesKey {
+    /// 128-bit key
+    Aes128([u8; 16]),
+    /// 192-bit key
+    Aes192([u8; 24]),
+    /// 256-bit key
+    Aes256([u8; 32]),
+    /// Hidden secret key of the given size, delivered by the PUF over the secret key bus
+    ///
+    /// The key must have been loaded with [`crate::puf::Puf::get_hw_key`] into
+    /// [`crate::puf::KeySlot::Slot0`], it never transits CPU-visible memory.
+    Secret(KeySize),
+}
+
+/// Size of a hidden secret key
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KeySize {
+    /// 128-bit key
+    Bits128,
+    /// 192-bit key
+    Bits192,
+    /// 256-bit key
+    Bits256,
+}
+
+impl AesKey {
+    /// Key words to load into the engine, none for the hidden secret key
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            AesKey::Aes128(key) => key,
+            AesKey::Aes192(key) => key,
+            AesKey::Aes256(key) => key,
+            AesKey::Secret(_) => &[],
+        }
+    }
+}
+
+impl From<[u8; 16]> for AesKey {
+    fn from(key: [u8; 16]) -> Self {
+        AesKey::Aes128(key)
+    }
+}
+
+impl From<[u8; 24]> for AesKey {
+    fn from(key: [u8; 24]) -> Self {
+        AesKey::Aes192(key)
+    }
+}
+
+impl From<[u8; 32]> for AesKey {
+    fn from(key: [u8; 32]) -> Self {
+        AesKey::Aes256(key)
+    }
+}
+
+/// AES cipher mode
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum CipherMode {
+    /// Electronic codebook
+    Ecb,
+    /// Cipher block chaining
+    Cbc,
+    /// Counter
+    Ctr,
+    /// Integer counter block
+    Icb(IcbSize),
+}
+
+/// Size of the counter held in the low order bits of an ICB-AES counter block
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IcbSize {
+    /// 32-bit counter, as used by AES-GCM
+    Bits32,
+    /// 64-bit counter
+    Bits64,
+    /// 96-bit counter
+    Bits96,
+    /// 128-bit counter
+    Bits128,
+}
+
+impl IcbSize {
+    /// Mask of the counter bits within the counter block
+    fn mask(self) -> u128 {
+        match self {
+            IcbSize::Bits32 => u128::from(u32::MAX),
+            IcbSize::Bits64 => u128::from(u64::MAX),
+            IcbSize::Bits96 => (1 << 96) - 1,
+            IcbSize::Bits128 => u128::MAX,
+        }
+    }
+}
+
+/// Number of blocks the engine is configured to accept before ICB-AES needs a new counter
+const ICB_STREAM_BLOCKS: usize = 64;
+
+/// Number of blocks moved by each DMA run of the async operations
+const DMA_RUN_BLOCKS: usize = 16;
+
+/// Word aligned blocks of a DMA run, processed in place
+#[repr(C, align(4))]
+struct DmaRun([u8; DMA_RUN_BLOCKS * BLOCK_LEN]);
+
+/// AES operation direction
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+/// An AES cipher
+pub struct Aes<'d, 'a, M: Mode> {
+    hashcrypt: &'a mut Hashcrypt<'d, M>,
+    _mode: PhantomData<M>,
+    key: AesKey,
+}
+
+impl<'d, 'a, M: Mode> Aes<'d, 'a, M> {
+    pub(super) fn new_inner(hashcrypt: &'a mut Hashcrypt<'d, M>, key: AesKey) -> Self {
+        Self {
+            hashcrypt,
+            _mode: PhantomData,
+            key,
+        }
+    }
+
+    fn check_buffers(input: &[u8], output: &[u8]) -> Result<(), Error> {
+        if input.is_empty() || !input.len().is_multiple_of(BLOCK_LEN) || input.len() != output.len() {
+            return Err(Error::UnsupportedConfiguration);
+        }
+        Ok(())
+    }
+
+    /// Configure the engine for a new operation, then load the key and, if required, the IV
+    fn start(&mut self, mode: CipherMode, direction: Direction, iv: Option<&[u8; BLOCK_LEN]>) {
+        // Key, data and IV are all loaded as byte streams, most significant word first
+        self.hashcrypt.hashcrypt.cryptcfg().write(|w| {
+            match mode {
+                CipherMode::Ecb => w.aesmode().ecb(),
+                CipherMode::Cbc => w.aesmode().cbc(),
+                // The 16-bit hardware counter sits in the last two bytes of the IV
+                CipherMode::Ctr => unsafe { w.aesmode().ctr().aesctrpos().bits(0) },
+                CipherMode::Icb(size) => {
+                    match size {
+                        IcbSize::Bits32 => w.icbsz().bits_32(),
+                        IcbSize::Bits64 => w.icbsz().bits_64(),
+                        IcbSize::Bits96 => w.icbsz().bits_96(),
+                        IcbSize::Bits128 => w.icbsz().bit_128(),
+                    };
+                    w.icbstrm().blocks_64()
+                }
+            };
+            match direction {
+                Direction::Encrypt => w.aesdecrypt().encrypt(),
+                Direction::Decrypt => w.aesdecrypt().decrypt(),
+            };
+            match self.key {
+                AesKey::Aes128(_) | AesKey::Secret(KeySize::Bits128) => w.aeskeysz().bits_128(),
+                AesKey::Aes192(_) | AesKey::Secret(KeySize::Bits192) => w.aeskeysz().bits_192(),
+                AesKey::Aes256(_) | AesKey::Secret(KeySize::Bits256) => w.aeskeysz().bits_256(),
+            };
+            match self.key {
+                AesKey::Secret(_) => w.aessecret().hidden_way(),
+                _ => w.aessecret().normal_way(),
+            };
+            w.msw1st_out()
+                .set_bit()
+                .swapkey()
+                .set_bit()
+                .swapdat()
+                .set_bit()
+                .msw1st()
+                .set_bit()
+        });
+
+        let algorithm = match mode {
+            CipherMode::Icb(_) => Algorithm::IcbAes,
+            _ => Algorithm::AES,
+        };
+        self.hashcrypt.start_algorithm(algorithm, false);
+        self.hashcrypt.write_words(self.key.as_bytes());
+
+        if let Some(iv) = iv {
+            self.hashcrypt.write_words(iv);
+        }
+    }
+
+    fn read_output(&self, output: &mut [u8]) {
+        for (i, chunk) in output.chunks_exact_mut(4).enumerate() {
+            // Output words are little-endian, swap to BE to convert to a stream of bytes
+            chunk.copy_from_slice(&self.hashcrypt.hashcrypt.digest0(i).read().bits().to_be_bytes());
+        }
+    }
+
+    /// Read the output of a block which may have been zero padded
+    fn read_partial_output(&self, output: &mut [u8]) -> Result<(), Error> {
+        if output.len() == BLOCK_LEN {
+            self.read_output(output);
+        } else {
+            let mut buffer = [0u8; BLOCK_LEN];
+            self.read_output(&mut buffer);
+            output.copy_from_slice(buffer.get(..output.len()).ok_or(Error::UnsupportedConfiguration)?);
+        }
+        Ok(())
+    }
+}
+
+fn check_ctr_buffers(input: &[u8], output: &[u8]) -> Result<(), Error> {
+    if input.is_empty() || input.len() != output.len() {
+        return Err(Error::UnsupportedConfiguration);
+    }
+    Ok(())
+}
+
+/// Number of bytes, starting at `offset`, which can be processed before the hardware's
+/// 16-bit counter wraps around. Carries into the rest of the counter are handled in
+/// software by restarting the engine with an updated counter.
+fn ctr_segment_len(counter: &[u8; BLOCK_LEN], len: usize, offset: usize) -> usize {
+    let low = u16::from_be_bytes([counter[BLOCK_LEN - 2], counter[BLOCK_LEN - 1]]);
+    let blocks_until_wrap = 0x1_0000 - usize::from(low);
+    (len - offset).min(blocks_until_wrap * BLOCK_LEN)
+}
+
+/// Advance the counter by the number of blocks needed to process `len` bytes
+fn increment_counter(counter: &mut [u8; BLOCK_LEN], len: usize) {
+    let blocks = len.div_ceil(BLOCK_LEN) as u128;
+    *counter = u128::from_be_bytes(*counter).wrapping_add(blocks).to_be_bytes();
+}
+
+/// Number of bytes, starting at `offset`, which fit into a single ICB-AES stream
+fn icb_segment_len(len: usize, offset: usize) -> usize {
+    (len - offset).min(ICB_STREAM_BLOCKS * BLOCK_LEN)
+}
+
+/// Advance the counter bits of an ICB-AES counter block by the number of blocks needed to
+/// process `len` bytes, wrapping around without touching the remaining bits
+fn increment_icb_counter(counter: &mut [u8; BLOCK_LEN], size: IcbSize, len: usize) {
+    let blocks = len.div_ceil(BLOCK_LEN) as u128;
+    let value = u128::from_be_bytes(*counter);
+    let mask = size.mask();
+    *counter = ((value & !mask) | (value.wrapping_add(blocks) & mask)).to_be_bytes();
+}
+
+/// Zero pad the last block of a CTR operation, if necessary
+fn pad_block<'b>(block: &'b [u8], buffer: &'b mut [u8; BLOCK_LEN]) -> Result<&'b [u8], Error> {
+    if block.len() == BLOCK_LEN {
+        return Ok(block);
+    }
+    buffer.fill(0);
+    buffer
+        .get_mut(..block.len())
+        .ok_or(Error::UnsupportedConfiguration)?
+        .copy_from_slice(block);
+    Ok(buffer.as_slice())
+}
+
+impl<'d, 'a> Aes<'d, 'a, Blocking> {
+    /// Create a new AES instance
+    pub fn new_blocking(hashcrypt: &'a mut Hashcrypt<'d, Blocking>, key: AesKey) -> Self {
+        Self::new_inner(hashcrypt, key)
+    }
+
+    fn process(
+        &mut self,
+        mode: CipherMode,
+        direction: Direction,
+        iv: Option<&[u8; BLOCK_LEN]>,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        Self::check_buffers(input, output)?;
+        self.start(mode, direction, iv);
+
+        for (inblock, outblock) in input.chunks_exact(BLOCK_LEN).zip(output.chunks_exact_mut(BLOCK_LEN)) {
+            self.hashcrypt.write_words(inblock);
+            self.hashcrypt.wait_for_digest();
+            self.read_output(outblock);
+        }
+
+        Ok(())
+    }
+
+    /// Encrypt data in ECB mode, data must be a multiple of the block length
+    pub fn encrypt_ecb(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        self.process(CipherMode::Ecb, Direction::Encrypt, None, input, output)
+    }
+
+    /// Decrypt data in ECB mode, data must be a multiple of the block length
+    pub fn decrypt_ecb(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        self.process(CipherMode::Ecb, Direction::Decrypt, None, input, output)
+    }
+
+    /// Encrypt data in CBC mode, data must be a multiple of the block length
+    pub fn encrypt_cbc(&mut self, iv: &[u8; BLOCK_LEN], input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        self.process(CipherMode::Cbc, Direction::Encrypt, Some(iv), input, output)
+    }
+
+    /// Decrypt data in CBC mode, data must be a multiple of the block length
+    pub fn decrypt_cbc(&mut self, iv: &[u8; BLOCK_LEN], input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        self.process(CipherMode::Cbc, Direction::Decrypt, Some(iv), input, output)
+    }
+
+    /// Encrypt data in CTR mode
+    ///
+    /// `counter` is advanced past the blocks that were used, so a stream may be processed
+    /// over several calls. Only the last call of a stream may use a length which is not a
+    /// multiple of the block length.
+    pub fn encrypt_ctr(&mut self, counter: &mut [u8; BLOCK_LEN], input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        check_ctr_buffers(input, output)?;
+
+        let mut offset = 0;
+        while offset < input.len() {
+            let len = ctr_segment_len(counter, input.len(), offset);
+            let segment = input.get(offset..offset + len).ok_or(Error::UnsupportedConfiguration)?;
+            let out_segment = output
+                .get_mut(offset..offset + len)
+                .ok_or(Error::UnsupportedConfiguration)?;
+
+            self.start(CipherMode::Ctr, Direction::Encrypt, Some(counter));
+
+            let mut buffer = [0u8; BLOCK_LEN];
+            for (inblock, outblock) in segment.chunks(BLOCK_LEN).zip(out_segment.chunks_mut(BLOCK_LEN)) {
+                self.hashcrypt.write_words(pad_block(inblock, &mut buffer)?);
+                self.hashcrypt.wait_for_digest();
+                self.read_partial_output(outblock)?;
+            }
+
+            increment_counter(counter, len);
+            offset += len;
+        }
+
+        Ok(())
+    }
+
+    /// Decrypt data in CTR mode
+    ///
+    /// See [`Self::encrypt_ctr`], as both directions are the same operation.
+    pub fn decrypt_ctr(&mut self, counter: &mut [u8; BLOCK_LEN], input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        self.encrypt_ctr(counter, input, output)
+    }
+
+    /// Encrypt data in ICB-AES mode
+    ///
+    /// Only the low order bits of `counter` selected by `size` are incremented. `counter` is
+    /// advanced past the blocks that were used, so a stream may be processed over several
+    /// calls. Only the last call of a stream may use a length which is not a multiple of the
+    /// block length.
+    pub fn encrypt_icb(
+        &mut self,
+        size: IcbSize,
+        counter: &mut [u8; BLOCK_LEN],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        check_ctr_buffers(input, output)?;
+
+        let mut offset = 0;
+        while offset < input.len() {
+            let len = icb_segment_len(input.len(), offset);
+            let segment = input.get(offset..offset + len).ok_or(Error::UnsupportedConfiguration)?;
+            let out_segment = output
+                .get_mut(offset..offset + len)
+                .ok_or(Error::UnsupportedConfiguration)?;
+
+            self.start(CipherMode::Icb(size), Direction::Encrypt, Some(counter));
+
+            let mut buffer = [0u8; BLOCK_LEN];
+            for (inblock, outblock) in segment.chunks(BLOCK_LEN).zip(out_segment.chunks_mut(BLOCK_LEN)) {
+                self.hashcrypt.write_words(pad_block(inblock, &mut buffer)?);
+                self.hashcrypt.wait_for_digest();
+                self.read_partial_output(outblock)?;
+            }
+
+            increment_icb_counter(counter, size, len);
+            offset += len;
+        }
+
+        Ok(())
+    }
+
+    /// Decrypt data in ICB-AES mode
+    ///
+    /// See [`Self::encrypt_icb`], as both directions are the same operation.
+    pub fn decrypt_icb(
+        &mut self,
+        size: IcbSize,
+        counter: &mut [u8; BLOCK_LEN],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        self.encrypt_icb(size, counter, input, output)
+    }
+}
+
+impl<'d, 'a> Aes<'d, 'a, Async> {
+    /// Create a new AES instance
+    pub fn new_async(hashcrypt: &'a mut Hashcrypt<'d, Async>, key: AesKey) -> Self {
+        Self::new_inner(hashcrypt, key)
+    }
+
+    async fn process(
+        &mut self,
+        mode: CipherMode,
+        direction: Direction,
+        iv: Option<&[u8; BLOCK_LEN]>,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        Self::check_buffers(input, output)?;
+        self.start(mode, direction, iv);
+        self.run(input, output).await
+    }
+
+    /// Feed `input` to the engine once [`Self::start`] loaded the key and IV, zero padding the
+    /// last block, and store as much of the output as `output` holds
+    async fn run(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        if self.hashcrypt.dma_ch.is_none() {
+            let mut buffer = [0u8; BLOCK_LEN];
+            for (inblock, outblock) in input.chunks(BLOCK_LEN).zip(output.chunks_mut(BLOCK_LEN)) {
+                self.hashcrypt.transfer(pad_block(inblock, &mut buffer)?).await?;
+                self.read_partial_output(outblock)?;
+            }
+            return Ok(());
+        }
+
+        // Key and IV have been written by the CPU, hand the data over to DMA. Each run goes
+        // through an aligned buffer, which also pads the last block.
+        self.hashcrypt.enable_dma();
+        let mut run = DmaRun([0; DMA_RUN_BLOCKS * BLOCK_LEN]);
+        let mut descriptors = [LinkedDescriptor::new(); 2 * DMA_RUN_BLOCKS - 1];
+
+        for (inrun, outrun) in input.chunks(run.0.len()).zip(output.chunks_mut(run.0.len())) {
+            let blocks = run
+                .0
+                .get_mut(..inrun.len().next_multiple_of(BLOCK_LEN))
+                .ok_or(Error::UnsupportedConfiguration)?;
+            blocks.fill(0);
+            blocks
+                .get_mut(..inrun.len())
+                .ok_or(Error::UnsupportedConfiguration)?
+                .copy_from_slice(inrun);
+
+            self.hashcrypt.transfer_blocks(blocks, &mut descriptors).await?;
+
+            // Output words are little-endian, swap to BE to convert to a stream of bytes
+            for word in blocks.chunks_exact_mut(4) {
+                word.reverse();
+            }
+            outrun.copy_from_slice(blocks.get(..outrun.len()).ok_or(Error::UnsupportedConfiguration)?);
+        }
+
+        Ok(())
+    }
+
+    /// Encrypt data in ECB mode, data must be a multiple of the block length
+    pub async fn encrypt_ecb(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        self.process(CipherMode::Ecb, Direction::Encrypt, None, input, output)
+            .await
+    }
+
+    /// Decrypt data in ECB mode, data must be a multiple of the block length
+    pub async fn decrypt_ecb(&mut self, input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        self.process(CipherMode::Ecb, Direction::Decrypt, None, input, output)
+            .await
+    }
+
+    /// Encrypt data in CBC mode, data must be a multiple of the block length
+    pub async fn encrypt_cbc(&mut self, iv: &[u8; BLOCK_LEN], input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        self.process(CipherMode::Cbc, Direction::Encrypt, Some(iv), input, output)
+            .await
+    }
+
+    /// Decrypt data in CBC mode, data must be a multiple of the block length
+    pub async fn decrypt_cbc(&mut self, iv: &[u8; BLOCK_LEN], input: &[u8], output: &mut [u8]) -> Result<(), Error> {
+        self.process(CipherMode::Cbc, Direction::Decrypt, Some(iv), input, output)
+            .await
+    }
+
+    /// Encrypt data in CTR mode, feeding the engine with DMA
+    ///
+    /// `counter` is advanced past the blocks that were used, so a stream may be processed
+    /// over several calls. Only the last call of a stream may use a length which is not a
+    /// multiple of the block length.
+    pub async fn encrypt_ctr(
+        &mut self,
+        counter: &mut [u8; BLOCK_LEN],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        check_ctr_buffers(input, output)?;
+
+        let mut offset = 0;
+        while offset < input.len() {
+            let len = ctr_segment_len(counter, input.len(), offset);
+            let segment = input.get(offset..offset + len).ok_or(Error::UnsupportedConfiguration)?;
+            let out_segment = output
+                .get_mut(offset..offset + len)
+                .ok_or(Error::UnsupportedConfiguration)?;
+
+            self.start(CipherMode::Ctr, Direction::Encrypt, Some(counter));
+            self.run(segment, out_segment).await?;
+
+            increment_counter(counter, len);
+            offset += len;
+        }
+
+        Ok(())
+    }
+
+    /// Decrypt data in CTR mode, feeding the engine with DMA
+    ///
+    /// See [`Self::encrypt_ctr`], as both directions are the same operation.
+    pub async fn decrypt_ctr(
+        &mut self,
+        counter: &mut [u8; BLOCK_LEN],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        self.encrypt_ctr(counter, input, output).await
+    }
+
+    /// Encrypt data in ICB-AES mode
+    ///
+    /// Only the low order bits of `counter` selected by `size` are incremented. `counter` is
+    /// advanced past the blocks that were used, so a stream may be processed over several
+    /// calls. Only the last call of a stream may use a length which is not a multiple of the
+    /// block length.
+    pub async fn encrypt_icb(
+        &mut self,
+        size: IcbSize,
+        counter: &mut [u8; BLOCK_LEN],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        check_ctr_buffers(input, output)?;
+
+        let mut offset = 0;
+        while offset < input.len() {
+            let len = icb_segment_len(input.len(), offset);
+            let segment = input.get(offset..offset + len).ok_or(Error::UnsupportedConfiguration)?;
+            let out_segment = output
+                .get_mut(offset..offset + len)
+                .ok_or(Error::UnsupportedConfiguration)?;
+
+            self.start(CipherMode::Icb(size), Direction::Encrypt, Some(counter));
+            self.run(segment, out_segment).await?;
+
+            increment_icb_counter(counter, size, len);
+            offset += len;
+        }
+
+        Ok(())
+    }
+
+    /// Decrypt data in ICB-AES mode
+    ///
+    /// See [`Self::encrypt_icb`], as both directions are the same operation.
+    pub async fn decrypt_icb(
+        &mut self,
+        size: IcbSize,
+        counter: &mut [u8; BLOCK_LEN],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        self.encrypt_icb(size, counter, input, output).await
+    }
+}
+
+/// AES-CTR keystream generator, implementing the RustCrypto `StreamCipher` trait
+///
+/// Unlike [`Aes::encrypt_ctr`], data of any length may be processed on each call.
+#[cfg(feature = "cipher")]
+pub struct AesCtr<'d, 'a> {
+    aes: Aes<'d, 'a, Blocking>,
+    counter: [u8; BLOCK_LEN],
+    keystream: [u8; BLOCK_LEN],
+    used: usize,
+}
+
+#[cfg(feature = "cipher")]
+impl<'d, 'a> AesCtr<'d, 'a> {
+    /// Create a new CTR stream starting at `counter`
+    pub fn new(aes: Aes<'d, 'a, Blocking>, counter: [u8; BLOCK_LEN]) -> Self {
+        Self {
+            aes,
+            counter,
+            keystream: [0; BLOCK_LEN],
+            used: BLOCK_LEN,
+        }
+    }
+
+    /// Generate the next block of keystream
+    fn refill(&mut self) -> Result<(), Error> {
+        self.aes
+            .encrypt_ctr(&mut self.counter, &[0; BLOCK_LEN], &mut self.keystream)?;
+        self.used = 0;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cipher")]
+impl cipher::StreamCipher for AesCtr<'_, '_> {
+    fn try_apply_keystream_inout(
+        &mut self,
+        mut buf: cipher::inout::InOutBuf<'_, '_, u8>,
+    ) -> Result<(), cipher::StreamCipherError> {
+        while !buf.is_empty() {
+            if self.used == BLOCK_LEN {
+                self.refill().map_err(|_| cipher::StreamCipherError)?;
+            }
+
+            let count = (BLOCK_LEN - self.used).min(buf.len());
+            let (mut head, tail) = buf.split_at(count);
+            head.xor_in2out(
+                self.keystream
+                    .get(self.used..self.used + count)
+                    .ok_or(cipher::StreamCipherError)?,
+            );
+            self.used += count;
+            buf = tail;
+        }
+
+        Ok(())
+    }
+}
+
+/// Single block backend for the RustCrypto block cipher traits
+///
+/// Each block restarts the engine, so the inherent ECB methods are faster for bulk data.
+#[cfg(feature = "cipher")]
+struct Backend<'b, 'd, 'a> {
+    aes: &'b mut Aes<'d, 'a, Blocking>,
+    direction: Direction,
+}
+
+#[cfg(feature = "cipher")]
+impl cipher::BlockSizeUser for Backend<'_, '_, '_> {
+    type BlockSize = cipher::consts::U16;
+}
+
+#[cfg(feature = "cipher")]
+impl cipher::ParBlocksSizeUser for Backend<'_, '_, '_> {
+    type ParBlocksSize = cipher::consts::U1;
+}
+
+#[cfg(feature = "cipher")]
+impl cipher::BlockBackend for Backend<'_, '_, '_> {
+    fn proc_block(&mut self, mut block: cipher::inout::InOut<'_, '_, cipher::Block<Self>>) {
+        let mut output = [0u8; BLOCK_LEN];
+        let result = match self.direction {
+            Direction::Encrypt => self.aes.encrypt_ecb(block.get_in(), &mut output),
+            Direction::Decrypt => self.aes.decrypt_ecb(block.get_in(), &mut output),
+        };
+
+        if result.is_err() {
+            error!("Failed to process AES block");
+        }
+        block.get_out().copy_from_slice(&output);
+    }
+}
+
+#[cfg(feature = "cipher")]
+impl cipher::BlockSizeUser for Aes<'_, '_, Blocking> {
+    type BlockSize = cipher::consts::U16;
+}
+
+#[cfg(feature = "cipher")]
+impl cipher::BlockEncryptMut for Aes<'_, '_, Blocking> {
+    fn encrypt_with_backend_mut(&mut self, f: impl cipher::BlockClosure<BlockSize = Self::BlockSize>) {
+        f.call(&mut Backend {
+            aes: self,
+            direction: Direction::Encrypt,
+        });
+    }
+}
+
+#[cfg(feature = "cipher")]
+impl cipher::BlockDecryptMut for Aes<'_, '_, Blocking> {
+    fn decrypt_with_backend_mut(&mut self, f: impl cipher::BlockClosure<BlockSize = Self::BlockSize>) {
+        f.call(&mut Backend {
+            aes: self,
+            direction: Direction::Decrypt,
+        });
+    }
+}
+
+/// AES block cipher implementing the RustCrypto `BlockEncrypt` and `BlockDecrypt` traits, for
+/// stacks which only hold a shared reference to their cipher
+///
+/// The engine needs exclusive access for every block, which the cell hands out one call at a
+/// time. The cipher isn't `Sync`, so calls can't overlap.
+#[cfg(feature = "cipher")]
+pub struct AesCipher<'d, 'a> {
+    aes: RefCell<Aes<'d, 'a, Blocking>>,
+}
+
+#[cfg(feature = "cipher")]
+impl<'d, 'a> AesCipher<'d, 'a> {
+    /// Create a new block cipher
+    pub fn new(aes: Aes<'d, 'a, Blocking>) -> Self {
+        Self { aes: RefCell::new(aes) }
+    }
+
+    /// Give back the AES instance
+    pub fn into_inner(self) -> Aes<'d, 'a, Blocking> {
+        self.aes.into_inner()
+    }
+
+    fn with_backend(&self, direction: Direction, f: impl cipher::BlockClosure<BlockSize = cipher::consts::U16>) {
+        // Only fails if a closure reenters the cipher
+        match self.aes.try_borrow_mut() {
+            Ok(mut aes) => f.call(&mut Backend {
+                aes: &mut aes,
+                direction,
+            }),
+            Err(_) => error!("AES engine already in use"),
+        }
+    }
+}
+
+#[cfg(feature = "cipher")]
+impl cipher::BlockSizeUser for AesCipher<'_, '_> {
+    type BlockSize = cipher::consts::U16;
+}
+
+#[cfg(feature = "cipher")]
+impl cipher::BlockEncrypt for AesCipher<'_, '_> {
+    fn encrypt_with_backend(&self, f: impl cipher::BlockClosure<BlockSize = Self::BlockSize>) {
+        self.with_backend(Direction::Encrypt, f);
+    }
+}
+
+#[cfg(feature = "cipher")]
+impl cipher::BlockDecrypt for AesCipher<'_, '_> {
+    fn decrypt_with_backend(&self, f: impl cipher::BlockClosure<BlockSize = Self::BlockSize>) {
+        self.with_backend(Direction::Decrypt, f);
+    }
+}
@@ -0,0 +1,202 @@
+//! AES-GCM, built from the ICB-AES mode of the engine and a software GHASH
+use super::aes::{Aes, BLOCK_LEN, IcbSize};
+use super::{Async, Blocking, Error};
+
+/// Nonce length, only 96-bit nonces are supported
+pub const NONCE_LEN: usize = 12;
+
+/// Authentication tag length
+pub const TAG_LEN: usize = 16;
+
+/// Multiply two elements of GF(2^128), using the bit order defined for GHASH
+///
+/// Runs without data dependent branches since `y` is the secret hash subkey.
+fn gf_mul(x: u128, y: u128) -> u128 {
+    const R: u128 = 0xe1 << 120;
+
+    let mut z = 0;
+    let mut v = y;
+    for i in (0..128).rev() {
+        z ^= v & 0u128.wrapping_sub((x >> i) & 1);
+        v = (v >> 1) ^ (R & 0u128.wrapping_sub(v & 1));
+    }
+    z
+}
+
+/// GHASH of the additional data and the ciphertext, followed by their lengths in bits
+fn ghash(h: u128, aad: &[u8], ciphertext: &[u8]) -> u128 {
+    let mut y = 0;
+
+    for data in [aad, ciphertext] {
+        for chunk in data.chunks(BLOCK_LEN) {
+            // The last block is zero padded
+            let mut block = [0u8; BLOCK_LEN];
+            for (b, c) in block.iter_mut().zip(chunk) {
+                *b = *c;
+            }
+            y = gf_mul(y ^ u128::from_be_bytes(block), h);
+        }
+    }
+
+    let lengths = ((aad.len() as u128 * 8) << 64) | (ciphertext.len() as u128 * 8);
+    gf_mul(y ^ lengths, h)
+}
+
+/// Pre-counter block for a 96-bit nonce
+fn initial_counter(nonce: &[u8; NONCE_LEN]) -> [u8; BLOCK_LEN] {
+    let mut counter = [0u8; BLOCK_LEN];
+    for (c, n) in counter.iter_mut().zip(nonce) {
+        *c = *n;
+    }
+    counter[BLOCK_LEN - 1] = 1;
+    counter
+}
+
+/// Compare tags without exiting early on the first mismatch
+fn tags_match(a: &[u8; TAG_LEN], b: &[u8; TAG_LEN]) -> bool {
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn check_gcm_buffers(input: &[u8], output: &[u8]) -> Result<(), Error> {
+    if input.len() != output.len() {
+        return Err(Error::UnsupportedConfiguration);
+    }
+    Ok(())
+}
+
+impl<'d, 'a> Aes<'d, 'a, Blocking> {
+    /// Derive the hash subkey, the encrypted pre-counter block and the first payload counter
+    fn gcm_start(&mut self, nonce: &[u8; NONCE_LEN]) -> Result<(u128, u128, [u8; BLOCK_LEN]), Error> {
+        let mut h = [0u8; BLOCK_LEN];
+        self.encrypt_ecb(&[0u8; BLOCK_LEN], &mut h)?;
+
+        let mut counter = initial_counter(nonce);
+        let mut tag_mask = [0u8; BLOCK_LEN];
+        self.encrypt_icb(IcbSize::Bits32, &mut counter, &[0u8; BLOCK_LEN], &mut tag_mask)?;
+
+        Ok((u128::from_be_bytes(h), u128::from_be_bytes(tag_mask), counter))
+    }
+
+    /// Encrypt and authenticate data in GCM mode
+    ///
+    /// `aad` is authenticated but not encrypted. A nonce must never be reused with the same key.
+    pub fn encrypt_gcm(
+        &mut self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+        tag: &mut [u8; TAG_LEN],
+    ) -> Result<(), Error> {
+        check_gcm_buffers(input, output)?;
+        let (h, tag_mask, mut counter) = self.gcm_start(nonce)?;
+
+        if !input.is_empty() {
+            self.encrypt_icb(IcbSize::Bits32, &mut counter, input, output)?;
+        }
+
+        *tag = (ghash(h, aad, output) ^ tag_mask).to_be_bytes();
+        Ok(())
+    }
+
+    /// Authenticate and decrypt data in GCM mode
+    ///
+    /// The tag is checked before anything is decrypted, `output` is left untouched on failure.
+    pub fn decrypt_gcm(
+        &mut self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+        tag: &[u8; TAG_LEN],
+    ) -> Result<(), Error> {
+        check_gcm_buffers(input, output)?;
+        let (h, tag_mask, mut counter) = self.gcm_start(nonce)?;
+
+        if !tags_match(&(ghash(h, aad, input) ^ tag_mask).to_be_bytes(), tag) {
+            return Err(Error::AuthenticationFailed);
+        }
+
+        if !input.is_empty() {
+            self.decrypt_icb(IcbSize::Bits32, &mut counter, input, output)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'d, 'a> Aes<'d, 'a, Async> {
+    /// Derive the hash subkey, the encrypted pre-counter block and the first payload counter
+    async fn gcm_start(&mut self, nonce: &[u8; NONCE_LEN]) -> Result<(u128, u128, [u8; BLOCK_LEN]), Error> {
+        let mut h = [0u8; BLOCK_LEN];
+        self.encrypt_ecb(&[0u8; BLOCK_LEN], &mut h).await?;
+
+        let mut counter = initial_counter(nonce);
+        let mut tag_mask = [0u8; BLOCK_LEN];
+        self.encrypt_icb(IcbSize::Bits32, &mut counter, &[0u8; BLOCK_LEN], &mut tag_mask)
+            .await?;
+
+        Ok((u128::from_be_bytes(h), u128::from_be_bytes(tag_mask), counter))
+    }
+
+    /// Encrypt and authenticate data in GCM mode
+    ///
+    /// `aad` is authenticated but not encrypted. A nonce must never be reused with the same key.
+    pub async fn encrypt_gcm(
+        &mut self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+        tag: &mut [u8; TAG_LEN],
+    ) -> Result<(), Error> {
+        check_gcm_buffers(input, output)?;
+        let (h, tag_mask, mut counter) = self.gcm_start(nonce).await?;
+
+        if !input.is_empty() {
+            self.encrypt_icb(IcbSize::Bits32, &mut counter, input, output).await?;
+        }
+
+        *tag = (ghash(h, aad, output) ^ tag_mask).to_be_bytes();
+        Ok(())
+    }
+
+    /// Authenticate and decrypt data in GCM mode
+    ///
+    /// The tag is checked before anything is decrypted, `output` is left untouched on failure.
+    pub async fn decrypt_gcm(
+        &mut self,
+        nonce: &[u8; NONCE_LEN],
+        aad: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+        tag: &[u8; TAG_LEN],
+    ) -> Result<(), Error> {
+        check_gcm_buffers(input, output)?;
+        let (h, tag_mask, mut counter) = self.gcm_start(nonce).await?;
+
+        if !tags_match(&(ghash(h, aad, input) ^ tag_mask).to_be_bytes(), tag) {
+            return Err(Error::AuthenticationFailed);
+        }
+
+        if !input.is_empty() {
+            self.decrypt_icb(IcbSize::Bits32, &mut counter, input, output).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test case 2 from the original GCM specification
+    #[test]
+    fn ghash_test_case_2() {
+        let h = 0x66e94bd4ef8a2c3b884cfa59ca342b2e;
+        let ciphertext = 0x0388dace60b6a392f328c2b971b2fe78u128.to_be_bytes();
+
+        assert_eq!(ghash(h, &[], &ciphertext), 0xf38cbb1ad69223dcc3457ae5b6b0f885);
+    }
+}
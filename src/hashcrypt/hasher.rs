@@ -1,37 +1,84 @@
-use core::future::poll_fn;
 use core::iter::zip;
 use core::marker::PhantomData;
-use core::task::Poll;
-
-use embassy_futures::select::select;
 
 use super::{Async, Blocking, Error, Hashcrypt, Mode};
-use crate::dma;
-use crate::dma::transfer::{Transfer, Width};
+use crate::dma::{LinkedDescriptor, MAX_TRANSFER_COUNT};
 
 /// Block length
 pub const BLOCK_LEN: usize = 64;
-/// Hash length
+/// Hash length (SHA256)
 pub const HASH_LEN: usize = 32;
+/// SHA1 hash length
+pub const SHA1_HASH_LEN: usize = 20;
 const END_BYTE: u8 = 0x80;
 
 // 9 from the end byte and the 64-bit length
 const LAST_BLOCK_MAX_DATA: usize = BLOCK_LEN - 9;
 
-/// A hasher
-pub struct Hasher<'d, 'a, M: Mode> {
+/// Number of linked DMA descriptors available to [`Hasher::update_all`]
+const MAX_GATHER_DESCRIPTORS: usize = 8;
+// Each buffer takes at least one descriptor, the first one being the channel's own
+const MAX_GATHER_BUFFERS: usize = MAX_GATHER_DESCRIPTORS + 1;
+
+/// Saved state of an in-progress SHA256 hash
+///
+/// Created by [`Hasher::suspend`] and resumed with `Hashcrypt::resume_sha256`, which allows
+/// the peripheral to be used for other operations in the meantime.
+#[derive(Clone)]
+pub struct HashContext {
+    digest: [u32; HASH_LEN / 4],
+    written: usize,
+    buffer: [u8; BLOCK_LEN],
+    buffered: usize,
+}
+
+/// A hasher, producing an `N` byte digest
+pub struct Hasher<'d, 'a, M: Mode, const N: usize = HASH_LEN> {
     hashcrypt: &'a mut Hashcrypt<'d, M>,
     _mode: PhantomData<M>,
     written: usize,
+    buffer: [u8; BLOCK_LEN],
+    buffered: usize,
 }
 
-impl<'d, 'a, M: Mode> Hasher<'d, 'a, M> {
+impl<'d, 'a, M: Mode, const N: usize> Hasher<'d, 'a, M, N> {
     pub(super) fn new_inner(hashcrypt: &'a mut Hashcrypt<'d, M>) -> Self {
         Self {
             hashcrypt,
             _mode: PhantomData,
             written: 0,
+            buffer: [0; BLOCK_LEN],
+            buffered: 0,
+        }
+    }
+
+    /// Copy as much data as fits into the partial block buffer, returning the data that didn't fit
+    fn fill_buffer<'b>(&mut self, data: &'b [u8]) -> Result<&'b [u8], Error> {
+        let count = (BLOCK_LEN - self.buffered).min(data.len());
+        let (head, tail) = data.split_at(count);
+
+        self.buffer
+            .get_mut(self.buffered..self.buffered + count)
+            .ok_or(Error::UnsupportedConfiguration)?
+            .copy_from_slice(head);
+        self.buffered += count;
+
+        Ok(tail)
+    }
+
+    /// Split data into whole blocks and a remainder for the partial block buffer
+    fn split_blocks(data: &[u8]) -> (&[u8], &[u8]) {
+        data.split_at(data.len() - data.len() % BLOCK_LEN)
+    }
+
+    /// Take the contents of the partial block buffer
+    fn take_buffer(&mut self) -> Result<([u8; BLOCK_LEN], usize), Error> {
+        if self.buffered > BLOCK_LEN {
+            return Err(Error::UnsupportedConfiguration);
         }
+        let buffered = self.buffered;
+        self.buffered = 0;
+        Ok((self.buffer, buffered))
     }
 
     fn init_final_data(&self, data: &[u8], buffer: &mut [u8; BLOCK_LEN]) -> Result<(), Error> {
@@ -56,19 +103,57 @@ impl<'d, 'a, M: Mode> Hasher<'d, 'a, M> {
         Ok(())
     }
 
-    fn wait_for_digest(&self) {
-        while self.hashcrypt.hashcrypt.status().read().digest().is_not_ready() {}
-    }
-
-    fn read_hash(&mut self, hash: &mut [u8; HASH_LEN]) {
+    fn read_hash(&mut self, hash: &mut [u8; N]) {
         for (reg, chunk) in zip(self.hashcrypt.hashcrypt.digest0_iter(), hash.chunks_mut(4)) {
             // Values in digest registers are little-endian, swap to BE to convert to a stream of bytes
             chunk.copy_from_slice(&reg.read().bits().to_be_bytes());
         }
     }
+
+    /// Release the Hashcrypt peripheral, so a new operation can be started on it
+    pub(super) fn into_hashcrypt(self) -> &'a mut Hashcrypt<'d, M> {
+        self.hashcrypt
+    }
 }
 
-impl<'d, 'a> Hasher<'d, 'a, Blocking> {
+impl<'d, 'a, M: Mode> Hasher<'d, 'a, M> {
+    /// Continue a hash from a saved context, the engine must already be started in SHA256 mode
+    pub(super) fn resume(hashcrypt: &'a mut Hashcrypt<'d, M>, context: HashContext) -> Self {
+        // Nothing has been processed by the engine yet, so there is no digest to reload
+        if context.written > 0 {
+            hashcrypt.hashcrypt.ctrl().modify(|_, w| w.reload().set_bit());
+            for (reg, word) in zip(hashcrypt.hashcrypt.reload_iter(), context.digest) {
+                reg.write(|w| unsafe { w.digest().bits(word) });
+            }
+            hashcrypt.hashcrypt.ctrl().modify(|_, w| w.reload().clear_bit());
+        }
+
+        Self {
+            hashcrypt,
+            _mode: PhantomData,
+            written: context.written,
+            buffer: context.buffer,
+            buffered: context.buffered,
+        }
+    }
+
+    /// Save the state of this hash and release the Hashcrypt peripheral
+    pub fn suspend(self) -> HashContext {
+        let mut digest = [0u32; HASH_LEN / 4];
+        for (reg, word) in zip(self.hashcrypt.hashcrypt.digest0_iter(), digest.iter_mut()) {
+            *word = reg.read().bits();
+        }
+
+        HashContext {
+            digest,
+            written: self.written,
+            buffer: self.buffer,
+            buffered: self.buffered,
+        }
+    }
+}
+
+impl<'d, 'a, const N: usize> Hasher<'d, 'a, Blocking, N> {
     /// Create a new hasher instance
     pub fn new_blocking(hashcrypt: &'a mut Hashcrypt<'d, Blocking>) -> Self {
         Self::new_inner(hashcrypt)
@@ -77,18 +162,11 @@ impl<'d, 'a> Hasher<'d, 'a, Blocking> {
     fn transfer_block(&mut self, data: &[u8; BLOCK_LEN]) {
         const _: () = core::assert!(BLOCK_LEN.is_multiple_of(4), "BLOCK_LEN must be divisible by 4");
 
-        for word in data.chunks(4) {
-            self.hashcrypt.hashcrypt.indata().write(|w| unsafe {
-                #[allow(clippy::unwrap_used)]
-                // panic safety: word is always 4 bytes and BLOCK_LEN is multiple of 4
-                w.data().bits(u32::from_le_bytes(word.try_into().unwrap()))
-            });
-        }
-        self.wait_for_digest();
+        self.hashcrypt.write_words(data);
+        self.hashcrypt.wait_for_digest();
     }
 
-    /// Submit one or more blocks of data to the hasher, data must be a multiple of the block length
-    pub fn submit_blocks(&mut self, data: &[u8]) -> Result<(), Error> {
+    fn transfer_blocks(&mut self, data: &[u8]) -> Result<(), Error> {
         if data.is_empty() || !data.len().is_multiple_of(BLOCK_LEN) {
             return Err(Error::UnsupportedConfiguration);
         }
@@ -101,8 +179,52 @@ impl<'d, 'a> Hasher<'d, 'a, Blocking> {
         Ok(())
     }
 
+    /// Submit one or more blocks of data to the hasher, data must be a multiple of the block length
+    pub fn submit_blocks(&mut self, data: &[u8]) -> Result<(), Error> {
+        if data.is_empty() || !data.len().is_multiple_of(BLOCK_LEN) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        self.update(data)
+    }
+
+    /// Submit data of any length to the hasher
+    ///
+    /// Data which doesn't fill a whole block is buffered until more data is submitted or the hash is
+    /// finalized.
+    pub fn update(&mut self, data: &[u8]) -> Result<(), Error> {
+        let mut data = data;
+
+        if self.buffered > 0 {
+            data = self.fill_buffer(data)?;
+            if self.buffered < BLOCK_LEN {
+                return Ok(());
+            }
+            let (block, _) = self.take_buffer()?;
+            self.transfer_blocks(&block)?;
+        }
+
+        let (blocks, remainder) = Self::split_blocks(data);
+        if !blocks.is_empty() {
+            self.transfer_blocks(blocks)?;
+        }
+        self.fill_buffer(remainder)?;
+
+        Ok(())
+    }
+
     /// Submits the final data for hashing
-    pub fn finalize(mut self, data: &[u8], hash: &mut [u8; HASH_LEN]) -> Result<(), Error> {
+    pub fn finalize(mut self, data: &[u8], hash: &mut [u8; N]) -> Result<(), Error> {
+        self.finish(data, hash)
+    }
+
+    pub(super) fn finish(&mut self, data: &[u8], hash: &mut [u8; N]) -> Result<(), Error> {
+        self.update(data)?;
+        let (buffer, buffered) = self.take_buffer()?;
+        self.finish_block(buffer.get(..buffered).ok_or(Error::UnsupportedConfiguration)?, hash)
+    }
+
+    fn finish_block(&mut self, data: &[u8], hash: &mut [u8; N]) -> Result<(), Error> {
         let mut buffer = [0u8; BLOCK_LEN];
 
         self.written += data.len();
@@ -126,7 +248,7 @@ impl<'d, 'a> Hasher<'d, 'a, Blocking> {
     }
 
     /// Computes the hash of the given data
-    pub fn hash(mut self, data: &[u8], hash: &mut [u8; HASH_LEN]) -> Result<(), Error> {
+    pub fn hash(mut self, data: &[u8], hash: &mut [u8; N]) -> Result<(), Error> {
         let mut iter = data.chunks_exact(BLOCK_LEN);
 
         for block in &mut iter {
@@ -139,7 +261,7 @@ impl<'d, 'a> Hasher<'d, 'a, Blocking> {
     }
 }
 
-impl<'d, 'a> Hasher<'d, 'a, Async> {
+impl<'d, 'a, const N: usize> Hasher<'d, 'a, Async, N> {
     /// Create a new hasher instance
     pub fn new_async(hashcrypt: &'a mut Hashcrypt<'d, Async>) -> Self {
         Self::new_inner(hashcrypt)
@@ -150,57 +272,139 @@ impl<'d, 'a> Hasher<'d, 'a, Async> {
             return Err(Error::UnsupportedConfiguration);
         }
 
-        let options = dma::transfer::TransferOptions {
-            width: Width::Bit32,
-            ..Default::default()
-        };
+        self.hashcrypt.transfer(data).await
+    }
 
-        let transfer = Transfer::new_write(
-            self.hashcrypt.dma_ch.as_ref().ok_or(Error::UnsupportedConfiguration)?,
-            data,
-            self.hashcrypt.hashcrypt.indata().as_ptr() as *mut u8,
-            options,
-        );
-
-        select(
-            transfer,
-            poll_fn(|cx| {
-                // Check if transfer ended with an error
-                if self.hashcrypt.hashcrypt.status().read().error().is_error() {
-                    return Poll::Ready(());
-                }
+    async fn transfer_blocks(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.transfer(data).await?;
+        self.written += data.len();
+        Ok(())
+    }
+
+    /// Submit one or more blocks of data to the hasher, data must be a multiple of the block length
+    pub async fn submit_blocks(&mut self, data: &[u8]) -> Result<(), Error> {
+        if data.is_empty() || !data.len().is_multiple_of(BLOCK_LEN) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        self.update(data).await
+    }
 
-                super::WAKER.register(cx.waker());
-                self.hashcrypt.hashcrypt.intenset().write(|w| w.error().interrupt());
-                Poll::Pending
-            }),
-        )
-        .await;
-
-        poll_fn(|cx| {
-            // Check if digest is ready
-            if self.hashcrypt.hashcrypt.status().read().digest().is_ready() {
-                return Poll::Ready(());
+    /// Submit data of any length to the hasher
+    ///
+    /// Data which doesn't fill a whole block is buffered until more data is submitted or the hash is
+    /// finalized.
+    pub async fn update(&mut self, data: &[u8]) -> Result<(), Error> {
+        let mut data = data;
+
+        if self.buffered > 0 {
+            data = self.fill_buffer(data)?;
+            if self.buffered < BLOCK_LEN {
+                return Ok(());
             }
+            let (block, _) = self.take_buffer()?;
+            self.transfer_blocks(&block).await?;
+        }
 
-            super::WAKER.register(cx.waker());
-            self.hashcrypt.hashcrypt.intenset().write(|w| w.digest().interrupt());
-            Poll::Pending
-        })
-        .await;
+        let (blocks, remainder) = Self::split_blocks(data);
+        if !blocks.is_empty() {
+            self.transfer_blocks(blocks).await?;
+        }
+        self.fill_buffer(remainder)?;
 
         Ok(())
     }
 
-    /// Submit one or more blocks of data to the hasher, data must be a multiple of the block length
-    pub async fn submit_blocks(&mut self, data: &[u8]) -> Result<(), Error> {
-        self.transfer(data).await?;
-        self.written += data.len();
+    /// Submit several buffers of any length to the hasher, as if they were concatenated
+    ///
+    /// If the hasher uses DMA, has no data buffered and every buffer but the last is word aligned
+    /// and a multiple of 4 bytes long, the whole blocks are pushed in a single DMA run without
+    /// copying them. Otherwise the buffers are submitted one at a time.
+    pub async fn update_all(&mut self, data: &[&[u8]]) -> Result<(), Error> {
+        if !self.can_gather(data) {
+            for buf in data {
+                self.update(buf).await?;
+            }
+            return Ok(());
+        }
+
+        let total: usize = data.iter().map(|buf| buf.len()).sum();
+        let dma_len = total - total % BLOCK_LEN;
+
+        // Cut the buffers down to the whole blocks, the rest goes through the partial block buffer
+        let mut pieces: [&[u8]; MAX_GATHER_BUFFERS] = [&[]; MAX_GATHER_BUFFERS];
+        let mut count = 0;
+        let mut remaining = dma_len;
+        let mut tail: &[&[u8]] = &[];
+        let mut tail_start: &[u8] = &[];
+        for (i, buf) in data.iter().enumerate() {
+            if remaining == 0 {
+                tail = data.get(i..).ok_or(Error::UnsupportedConfiguration)?;
+                break;
+            }
+            if buf.is_empty() {
+                continue;
+            }
+
+            let (head, rest) = buf.split_at(remaining.min(buf.len()));
+            *pieces.get_mut(count).ok_or(Error::UnsupportedConfiguration)? = head;
+            count += 1;
+            remaining -= head.len();
+
+            if remaining == 0 {
+                tail_start = rest;
+                tail = data.get(i + 1..).ok_or(Error::UnsupportedConfiguration)?;
+                break;
+            }
+        }
+
+        if dma_len > 0 {
+            let mut descriptors = [LinkedDescriptor::new(); MAX_GATHER_DESCRIPTORS];
+            self.hashcrypt
+                .transfer_gather(
+                    pieces.get(..count).ok_or(Error::UnsupportedConfiguration)?,
+                    &mut descriptors,
+                )
+                .await?;
+            self.written += dma_len;
+        }
+
+        self.fill_buffer(tail_start)?;
+        for buf in tail {
+            self.fill_buffer(buf)?;
+        }
+
         Ok(())
     }
 
+    /// Check whether [`Self::update_all`] can push the buffers with a single DMA run
+    fn can_gather(&self, data: &[&[u8]]) -> bool {
+        let Some((_, leading)) = data.split_last() else {
+            return false;
+        };
+
+        let descriptors: usize = data.iter().map(|buf| buf.len().div_ceil(MAX_TRANSFER_COUNT * 4)).sum();
+
+        self.hashcrypt.dma_ch.is_some()
+            && self.buffered == 0
+            && descriptors <= MAX_GATHER_BUFFERS
+            && data.iter().all(|buf| buf.as_ptr().cast::<u32>().is_aligned())
+            && leading.iter().all(|buf| buf.len().is_multiple_of(4))
+    }
+
     /// Submits the final data for hashing
-    pub async fn finalize(mut self, data: &[u8], hash: &mut [u8; HASH_LEN]) -> Result<(), Error> {
+    pub async fn finalize(mut self, data: &[u8], hash: &mut [u8; N]) -> Result<(), Error> {
+        self.finish(data, hash).await
+    }
+
+    pub(super) async fn finish(&mut self, data: &[u8], hash: &mut [u8; N]) -> Result<(), Error> {
+        self.update(data).await?;
+        let (buffer, buffered) = self.take_buffer()?;
+        self.finish_block(buffer.get(..buffered).ok_or(Error::UnsupportedConfiguration)?, hash)
+            .await
+    }
+
+    async fn finish_block(&mut self, data: &[u8], hash: &mut [u8; N]) -> Result<(), Error> {
         let mut buffer = [0u8; BLOCK_LEN];
 
         self.written += data.len();
@@ -222,7 +426,7 @@ impl<'d, 'a> Hasher<'d, 'a, Async> {
     }
 
     /// Computes the hash of the given data
-    pub async fn hash(mut self, data: &[u8], hash: &mut [u8; HASH_LEN]) -> Result<(), Error> {
+    pub async fn hash(mut self, data: &[u8], hash: &mut [u8; N]) -> Result<(), Error> {
         let mut iter = data.chunks_exact(BLOCK_LEN);
 
         for block in &mut iter {
@@ -232,3 +436,39 @@ impl<'d, 'a> Hasher<'d, 'a, Async> {
         self.finalize(iter.remainder(), hash).await
     }
 }
+
+#[cfg(feature = "digest")]
+macro_rules! impl_digest {
+    ($len:ident, $size:ty) => {
+        impl digest::HashMarker for Hasher<'_, '_, Blocking, $len> {}
+
+        impl digest::OutputSizeUser for Hasher<'_, '_, Blocking, $len> {
+            type OutputSize = $size;
+        }
+
+        impl digest::Update for Hasher<'_, '_, Blocking, $len> {
+            fn update(&mut self, data: &[u8]) {
+                if Hasher::update(self, data).is_err() {
+                    error!("Failed to submit data to hasher");
+                }
+            }
+        }
+
+        impl digest::FixedOutput for Hasher<'_, '_, Blocking, $len> {
+            fn finalize_into(mut self, out: &mut digest::Output<Self>) {
+                let result = <&mut [u8; $len]>::try_from(out.as_mut_slice())
+                    .map_err(|_| Error::UnsupportedConfiguration)
+                    .and_then(|hash| self.finish(&[], hash));
+
+                if result.is_err() {
+                    error!("Failed to finalize hash");
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "digest")]
+impl_digest!(HASH_LEN, digest::consts::U32);
+#[cfg(feature = "digest")]
+impl_digest!(SHA1_HASH_LEN, digest::consts::U20);
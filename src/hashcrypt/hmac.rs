@@ -0,0 +1,115 @@
+use super::hasher::{BLOCK_LEN, HASH_LEN, Hasher};
+use super::{Async, Blocking, Error, Hashcrypt, Mode};
+
+/// MAC length
+pub const MAC_LEN: usize = HASH_LEN;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+/// An HMAC-SHA256 calculation
+pub struct Hmac<'d, 'a, M: Mode> {
+    hasher: Hasher<'d, 'a, M>,
+    opad_key: [u8; BLOCK_LEN],
+}
+
+/// Derive the inner and outer padded keys from a key that fits into a block
+fn padded_keys(key: &[u8; BLOCK_LEN]) -> ([u8; BLOCK_LEN], [u8; BLOCK_LEN]) {
+    let mut ipad_key = [IPAD; BLOCK_LEN];
+    let mut opad_key = [OPAD; BLOCK_LEN];
+
+    for ((i, o), k) in ipad_key.iter_mut().zip(opad_key.iter_mut()).zip(key) {
+        *i ^= k;
+        *o ^= k;
+    }
+
+    (ipad_key, opad_key)
+}
+
+impl<'d, 'a> Hmac<'d, 'a, Blocking> {
+    /// Create a new HMAC instance
+    pub fn new_blocking(hashcrypt: &'a mut Hashcrypt<'d, Blocking>, key: &[u8]) -> Result<Self, Error> {
+        let mut block_key = [0u8; BLOCK_LEN];
+
+        if key.len() > BLOCK_LEN {
+            // Keys longer than a block are hashed first
+            let mut hashed = [0u8; HASH_LEN];
+            hashcrypt.new_sha256().hash(key, &mut hashed)?;
+            block_key
+                .get_mut(..HASH_LEN)
+                .ok_or(Error::UnsupportedConfiguration)?
+                .copy_from_slice(&hashed);
+        } else {
+            block_key
+                .get_mut(..key.len())
+                .ok_or(Error::UnsupportedConfiguration)?
+                .copy_from_slice(key);
+        }
+
+        let (ipad_key, opad_key) = padded_keys(&block_key);
+
+        let mut hasher = hashcrypt.new_sha256();
+        hasher.submit_blocks(&ipad_key)?;
+
+        Ok(Self { hasher, opad_key })
+    }
+
+    /// Add data to the MAC calculation
+    pub fn update(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.hasher.update(data)
+    }
+
+    /// Complete the MAC calculation
+    pub fn finalize(mut self, mac: &mut [u8; MAC_LEN]) -> Result<(), Error> {
+        let mut inner = [0u8; HASH_LEN];
+        self.hasher.finish(&[], &mut inner)?;
+
+        let mut outer = self.hasher.into_hashcrypt().new_sha256();
+        outer.submit_blocks(&self.opad_key)?;
+        outer.finalize(&inner, mac)
+    }
+}
+
+impl<'d, 'a> Hmac<'d, 'a, Async> {
+    /// Create a new HMAC instance
+    pub async fn new_async(hashcrypt: &'a mut Hashcrypt<'d, Async>, key: &[u8]) -> Result<Self, Error> {
+        let mut block_key = [0u8; BLOCK_LEN];
+
+        if key.len() > BLOCK_LEN {
+            // Keys longer than a block are hashed first
+            let mut hashed = [0u8; HASH_LEN];
+            hashcrypt.new_sha256().hash(key, &mut hashed).await?;
+            block_key
+                .get_mut(..HASH_LEN)
+                .ok_or(Error::UnsupportedConfiguration)?
+                .copy_from_slice(&hashed);
+        } else {
+            block_key
+                .get_mut(..key.len())
+                .ok_or(Error::UnsupportedConfiguration)?
+                .copy_from_slice(key);
+        }
+
+        let (ipad_key, opad_key) = padded_keys(&block_key);
+
+        let mut hasher = hashcrypt.new_sha256();
+        hasher.submit_blocks(&ipad_key).await?;
+
+        Ok(Self { hasher, opad_key })
+    }
+
+    /// Add data to the MAC calculation
+    pub async fn update(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.hasher.update(data).await
+    }
+
+    /// Complete the MAC calculation
+    pub async fn finalize(mut self, mac: &mut [u8; MAC_LEN]) -> Result<(), Error> {
+        let mut inner = [0u8; HASH_LEN];
+        self.hasher.finish(&[], &mut inner).await?;
+
+        let mut outer = self.hasher.into_hashcrypt().new_sha256();
+        outer.submit_blocks(&self.opad_key).await?;
+        outer.finalize(&inner, mac).await
+    }
+}
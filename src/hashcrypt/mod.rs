@@ -1,16 +1,31 @@
 //! Hashcrypt
+use core::future::poll_fn;
 use core::marker::PhantomData;
+use core::task::Poll;
 
+use aes::{Aes, AesKey};
+use embassy_futures::select::select;
 use embassy_hal_internal::PeripheralType;
 use embassy_sync::waitqueue::AtomicWaker;
-use hasher::Hasher;
+use hasher::{HashContext, Hasher, SHA1_HASH_LEN};
+use hmac::Hmac;
+use rand_core::TryCryptoRng;
 
 use crate::clocks::enable_and_reset;
+use crate::dma::LinkedDescriptor;
+use crate::dma::channel::Segment;
+use crate::dma::transfer::{Transfer, TransferOptions, Width};
 use crate::peripherals::{DMA0_CH30, HASHCRYPT};
 use crate::{Peri, dma, interrupt, pac};
 
+/// AES module
+pub mod aes;
+/// AES-GCM module
+pub mod gcm;
 /// Hasher module
 pub mod hasher;
+/// HMAC module
+pub mod hmac;
 
 /// Error information type
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -18,6 +33,48 @@ pub mod hasher;
 pub enum Error {
     /// configuration requested is not supported
     UnsupportedConfiguration,
+    /// authentication tag did not match
+    AuthenticationFailed,
+}
+
+/// Hashcrypt configuration
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Config {
+    /// Enable the memory masking side-channel countermeasure, the engine runs unmasked if `None`
+    pub masking: Option<Masking>,
+}
+
+/// Random words used to mask keys and data inside the engine and to seed its PRNG
+///
+/// These must come from a true random source, such as [`crate::rng::Rng`], and should be
+/// regenerated each time the peripheral is created. They are left out of the `Debug` output.
+#[derive(Copy, Clone)]
+pub struct Masking {
+    /// Mask words
+    pub mask: [u32; 4],
+    /// PRNG seed
+    pub prng_seed: u32,
+}
+
+impl Masking {
+    /// Draw the mask and seed from a cryptographically secure RNG
+    pub fn new<R: TryCryptoRng>(rng: &mut R) -> Result<Self, R::Error> {
+        let mut mask = [0u32; 4];
+        for word in mask.iter_mut() {
+            *word = rng.try_next_u32()?;
+        }
+
+        Ok(Self {
+            mask,
+            prng_seed: rng.try_next_u32()?,
+        })
+    }
+}
+
+impl core::fmt::Debug for Masking {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Masking").finish_non_exhaustive()
+    }
 }
 
 trait Sealed {}
@@ -52,6 +109,9 @@ pub struct Hashcrypt<'d, M: Mode> {
 
 static WAKER: AtomicWaker = AtomicWaker::new();
 
+/// Size of the INDATA input buffer
+const INPUT_BUFFER_LEN: usize = 64;
+
 /// Hashcrypt interrupt handler.
 pub struct InterruptHandler<T: Instance> {
     _phantom: PhantomData<T>,
@@ -70,6 +130,11 @@ impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandl
             reg.intenclr().write(|w| w.digest().clear_bit_by_one());
             WAKER.wake();
         }
+
+        if reg.status().read().waiting().is_waiting() {
+            reg.intenclr().write(|w| w.waiting().clear_bit_by_one());
+            WAKER.wake();
+        }
     }
 }
 
@@ -92,29 +157,48 @@ impl Instance for crate::peripherals::HASHCRYPT {
 #[derive(Debug, Copy, Clone)]
 #[non_exhaustive]
 enum Algorithm {
+    /// SHA1
+    SHA1,
     /// SHA256
     SHA256,
+    /// AES
+    AES,
+    /// ICB-AES
+    IcbAes,
 }
 
 impl From<Algorithm> for u8 {
     fn from(value: Algorithm) -> Self {
         match value {
+            Algorithm::SHA1 => 0x1,
             Algorithm::SHA256 => 0x2,
+            Algorithm::AES => 0x4,
+            Algorithm::IcbAes => 0x5,
         }
     }
 }
 
 impl<'d, M: Mode> Hashcrypt<'d, M> {
     /// Instantiate new Hashcrypt peripheral
-    fn new_inner<T: Instance>(_peripheral: Peri<'d, T>, dma_ch: Option<dma::channel::Channel<'d>>) -> Self {
+    fn new_inner<T: Instance>(
+        _peripheral: Peri<'d, T>,
+        dma_ch: Option<dma::channel::Channel<'d>>,
+        config: Config,
+    ) -> Self {
         enable_and_reset::<HASHCRYPT>();
 
-        Self {
+        let hashcrypt = Self {
             _ownership: PhantomData,
             _mode: PhantomData,
             dma_ch,
             hashcrypt: unsafe { pac::Hashcrypt::steal() },
+        };
+
+        if let Some(masking) = config.masking {
+            hashcrypt.enable_masking(&masking);
         }
+
+        hashcrypt
     }
 
     // Safety: unsafe for writing algorithm type to register
@@ -128,12 +212,43 @@ impl<'d, M: Mode> Hashcrypt<'d, M> {
             w
         });
     }
+
+    /// Load the mask words and PRNG seed for the side-channel countermeasures
+    fn enable_masking(&self, masking: &Masking) {
+        for (i, word) in masking.mask.iter().enumerate() {
+            self.hashcrypt.mask(i).write(|w| unsafe { w.mask().bits(*word) });
+        }
+        self.hashcrypt
+            .prng_seed()
+            .write(|w| unsafe { w.prng_seed().bits(masking.prng_seed) });
+    }
+
+    /// Write data to INDATA one word at a time
+    fn write_words(&self, data: &[u8]) {
+        for word in data.chunks_exact(4) {
+            self.hashcrypt.indata().write(|w| unsafe {
+                #[allow(clippy::unwrap_used)]
+                // panic safety: chunks_exact always yields 4 bytes
+                w.data().bits(u32::from_le_bytes(word.try_into().unwrap()))
+            });
+        }
+    }
+
+    fn wait_for_digest(&self) {
+        while self.hashcrypt.status().read().digest().is_not_ready() {}
+    }
 }
 
 impl<'d> Hashcrypt<'d, Blocking> {
     /// Create a new instance
-    pub fn new_blocking<T: Instance>(peripheral: Peri<'d, T>) -> Self {
-        Self::new_inner(peripheral, None)
+    pub fn new_blocking<T: Instance>(peripheral: Peri<'d, T>, config: Config) -> Self {
+        Self::new_inner(peripheral, None, config)
+    }
+
+    /// Start a new SHA1 hash
+    pub fn new_sha1<'a>(&'a mut self) -> Hasher<'d, 'a, Blocking, SHA1_HASH_LEN> {
+        self.start_algorithm(Algorithm::SHA1, false);
+        Hasher::new_blocking(self)
     }
 
     /// Start a new SHA256 hash
@@ -141,6 +256,22 @@ impl<'d> Hashcrypt<'d, Blocking> {
         self.start_algorithm(Algorithm::SHA256, false);
         Hasher::new_blocking(self)
     }
+
+    /// Resume a SHA256 hash that was previously suspended
+    pub fn resume_sha256<'a>(&'a mut self, context: HashContext) -> Hasher<'d, 'a, Blocking> {
+        self.start_algorithm(Algorithm::SHA256, false);
+        Hasher::resume(self, context)
+    }
+
+    /// Start a new HMAC-SHA256 calculation with the given key
+    pub fn new_hmac_sha256<'a>(&'a mut self, key: &[u8]) -> Result<Hmac<'d, 'a, Blocking>, Error> {
+        Hmac::new_blocking(self, key)
+    }
+
+    /// Start a new AES session with the given key
+    pub fn new_aes<'a>(&'a mut self, key: AesKey) -> Aes<'d, 'a, Blocking> {
+        Aes::new_blocking(self, key)
+    }
 }
 
 impl<'d> Hashcrypt<'d, Async> {
@@ -149,13 +280,199 @@ impl<'d> Hashcrypt<'d, Async> {
         peripheral: Peri<'d, T>,
         _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
         dma_ch: Peri<'d, impl HashcryptDma>,
+        config: Config,
+    ) -> Self {
+        Self::new_inner(peripheral, dma::Dma::reserve_channel(dma_ch), config)
+    }
+
+    /// Create a new instance which feeds the engine from interrupts instead of DMA
+    ///
+    /// This leaves DMA0_CH30 free for other uses, at the cost of higher CPU load.
+    pub fn new_async_without_dma<T: Instance>(
+        peripheral: Peri<'d, T>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        config: Config,
     ) -> Self {
-        Self::new_inner(peripheral, dma::Dma::reserve_channel(dma_ch))
+        Self::new_inner(peripheral, None, config)
+    }
+
+    /// Start a new SHA1 hash
+    pub fn new_sha1<'a>(&'a mut self) -> Hasher<'d, 'a, Async, SHA1_HASH_LEN> {
+        self.start_algorithm(Algorithm::SHA1, self.dma_ch.is_some());
+        Hasher::new_async(self)
     }
 
     /// Start a new SHA256 hash
     pub fn new_sha256<'a>(&'a mut self) -> Hasher<'d, 'a, Async> {
-        self.start_algorithm(Algorithm::SHA256, true);
+        self.start_algorithm(Algorithm::SHA256, self.dma_ch.is_some());
         Hasher::new_async(self)
     }
+
+    /// Resume a SHA256 hash that was previously suspended
+    pub fn resume_sha256<'a>(&'a mut self, context: HashContext) -> Hasher<'d, 'a, Async> {
+        self.start_algorithm(Algorithm::SHA256, self.dma_ch.is_some());
+        Hasher::resume(self, context)
+    }
+
+    /// Start a new HMAC-SHA256 calculation with the given key
+    pub async fn new_hmac_sha256<'a>(&'a mut self, key: &[u8]) -> Result<Hmac<'d, 'a, Async>, Error> {
+        Hmac::new_async(self, key).await
+    }
+
+    /// Start a new AES session with the given key
+    pub fn new_aes<'a>(&'a mut self, key: AesKey) -> Aes<'d, 'a, Async> {
+        Aes::new_async(self, key)
+    }
+
+    /// Hand the remaining input and the output of an AES operation over to DMA, if this instance
+    /// uses it
+    fn enable_dma(&self) {
+        if self.dma_ch.is_some() {
+            self.hashcrypt.ctrl().modify(|_, w| w.dma_i().push().dma_o().set_bit());
+        }
+    }
+
+    /// Wait for the engine to accept more input data
+    async fn wait_for_input(&self) {
+        poll_fn(|cx| {
+            if self.hashcrypt.status().read().waiting().is_waiting() {
+                return Poll::Ready(());
+            }
+
+            WAKER.register(cx.waker());
+            self.hashcrypt.intenset().write(|w| w.waiting().interrupt());
+            Poll::Pending
+        })
+        .await;
+    }
+
+    /// Wait for a DMA transfer into INDATA to complete, or for the engine to flag an error
+    async fn wait_for_dma(&self, transfer: Transfer<'_>) {
+        select(
+            transfer,
+            poll_fn(|cx| {
+                // Check if transfer ended with an error
+                if self.hashcrypt.status().read().error().is_error() {
+                    return Poll::Ready(());
+                }
+
+                WAKER.register(cx.waker());
+                self.hashcrypt.intenset().write(|w| w.error().interrupt());
+                Poll::Pending
+            }),
+        )
+        .await;
+    }
+
+    /// Push data into INDATA, using DMA if available, and wait for the digest (or output) to become ready
+    async fn transfer(&mut self, data: &[u8]) -> Result<(), Error> {
+        if let Some(dma_ch) = self.dma_ch.as_ref() {
+            let options = TransferOptions {
+                width: Width::Bit32,
+                ..Default::default()
+            };
+
+            let transfer = Transfer::new_write(dma_ch, data, self.hashcrypt.indata().as_ptr() as *mut u8, options);
+            self.wait_for_dma(transfer).await;
+        } else {
+            // The input buffer holds up to 16 words, refill it whenever the engine asks for more
+            for chunk in data.chunks(INPUT_BUFFER_LEN) {
+                self.wait_for_input().await;
+                self.write_words(chunk);
+            }
+        }
+
+        self.wait_for_digest_async().await;
+        Ok(())
+    }
+
+    /// Push several buffers into INDATA in a single DMA run and wait for the digest to become ready
+    async fn transfer_gather(&mut self, data: &[&[u8]], descriptors: &mut [LinkedDescriptor]) -> Result<(), Error> {
+        let options = TransferOptions {
+            width: Width::Bit32,
+            ..Default::default()
+        };
+
+        let transfer = Transfer::new_write_gather(
+            self.dma_ch.as_ref().ok_or(Error::UnsupportedConfiguration)?,
+            data,
+            descriptors,
+            self.hashcrypt.indata().as_ptr() as *mut u8,
+            options,
+        )
+        .map_err(|_| Error::UnsupportedConfiguration)?;
+        self.wait_for_dma(transfer).await;
+
+        self.wait_for_digest_async().await;
+        Ok(())
+    }
+
+    /// Run whole AES blocks through the engine in place, in a single DMA run
+    ///
+    /// The DMA alternates between pushing a block into INDATA and draining its output from DIGEST
+    /// back over it, so `descriptors` needs two entries per block, less one. The output words are
+    /// stored as the engine presents them. `data` must be word aligned.
+    async fn transfer_blocks(&mut self, data: &mut [u8], descriptors: &mut [LinkedDescriptor]) -> Result<(), Error> {
+        if data.is_empty() || !data.len().is_multiple_of(aes::BLOCK_LEN) || !data.as_ptr().cast::<u32>().is_aligned() {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let options = TransferOptions {
+            width: Width::Bit32,
+            ..Default::default()
+        };
+        let indata = self.hashcrypt.indata().as_ptr() as *mut u32;
+        let digest = self.hashcrypt.digest0(0).as_ptr() as *const u32;
+        let base = data.as_mut_ptr() as *mut u32;
+
+        let segments = (0..data.len() / aes::BLOCK_LEN).flat_map(move |i| {
+            let block = base.wrapping_byte_add(i * aes::BLOCK_LEN);
+            [
+                Segment {
+                    src: block,
+                    src_inc: true,
+                    dst: indata,
+                    dst_inc: false,
+                    len: aes::BLOCK_LEN,
+                },
+                Segment {
+                    src: digest,
+                    src_inc: true,
+                    dst: block,
+                    dst_inc: true,
+                    len: aes::BLOCK_LEN,
+                },
+            ]
+        });
+
+        // SAFETY: `data` stays borrowed until the transfer completes or is dropped, and each block
+        // is read before its output is written back
+        let transfer = unsafe {
+            Transfer::new_chain(
+                self.dma_ch.as_ref().ok_or(Error::UnsupportedConfiguration)?,
+                segments,
+                descriptors,
+                options,
+            )
+        }
+        .map_err(|_| Error::UnsupportedConfiguration)?;
+        self.wait_for_dma(transfer).await;
+
+        Ok(())
+    }
+
+    /// Wait for the digest (or output) to become ready
+    async fn wait_for_digest_async(&self) {
+        poll_fn(|cx| {
+            // Check if digest is ready
+            if self.hashcrypt.status().read().digest().is_ready() {
+                return Poll::Ready(());
+            }
+
+            WAKER.register(cx.waker());
+            self.hashcrypt.intenset().write(|w| w.digest().interrupt());
+            Poll::Pending
+        })
+        .await;
+    }
 }
@@ -9,10 +9,12 @@ use embassy_hal_internal::drop::OnDrop;
 use itertools::Itertools;
 
 use super::{
-    Async, Blocking, Error, Info, Instance, InterruptHandler, MasterDma, Mode, REMEDIATON_MASTER_STOP, Result, SclPin,
-    SdaPin, TEN_BIT_PREFIX, TransferError, force_clear_remediation, wait_remediation_complete,
+    Async, Blocking, BusPin, Error, Info, Instance, InterruptHandler, MasterDma, Mode, REMEDIATON_MASTER_STOP, Result,
+    SclPin, SdaPin, TEN_BIT_PREFIX, TransferError, force_clear_remediation, wait_remediation_complete,
 };
+use crate::dma::LinkedDescriptor;
 use crate::flexcomm::FlexcommRef;
+use crate::gpio::{DriveMode, DriveStrength, Flex, SenseEnabled, SlewRate};
 use crate::interrupt::typelevel::Interrupt;
 use crate::pac::i2c0::msttime::{Mstsclhigh, Mstscllow};
 use crate::{Peri, dma, interrupt};
@@ -29,7 +31,7 @@ pub enum Speed {
     /// 1 Mbit/s
     FastPlus,
 
-    /// 3.4Mbit/s only available for slave devices
+    /// 3.4Mbit/s only available for slave devices, the master rejects it
     High,
 }
 
@@ -96,13 +98,33 @@ struct SpeedRegisterSettings {
     scl_low_clocks: Mstscllow,
     clock_div_multiplier: u16,
 
+    /// Frequency of the I2C master function clock, before CLKDIV
+    clock_hz: u32,
+
     _actual_freq_hz: u32,
 }
 
+/// Frequency of the flexcomm function clock feeding the I2C master
+fn clock_frequency(clock: crate::flexcomm::Clock) -> Result<u32> {
+    match clock {
+        crate::flexcomm::Clock::Sfro => Ok(16_000_000),
+        crate::flexcomm::Clock::Ffro => Ok(48_000_000),
+        _ => Err(Error::UnsupportedConfiguration),
+    }
+}
+
 impl SpeedRegisterSettings {
-    fn new(duty_cycle: DutyCycle, speed: Speed, strict_mode: bool) -> Result<Self> {
-        const CLOCK_SPEED_HZ: u32 = 48_000_000;
+    /// TIMEOUT.TO value for a time-out of `timeout_us`, the time-out lasts (TO + 1) * 16 divided
+    /// function clocks
+    fn timeout_to(&self, timeout_us: u32) -> u16 {
+        const TO_MAX: u64 = 0xfff;
+
+        let clock_hz = u64::from(self.clock_hz / (u32::from(self.clock_div_multiplier) + 1));
+        let ticks = u64::from(timeout_us) * clock_hz / 1_000_000 / 16;
+        ticks.saturating_sub(1).min(TO_MAX) as u16
+    }
 
+    fn new(clock_hz: u32, duty_cycle: DutyCycle, speed: Speed, strict_mode: bool) -> Result<Self> {
         let mut target_freq_hz: u32 = match speed {
             Speed::Standard => 100_000,   // 100 KHz
             Speed::Fast => 400_000,       // 400 KHz
@@ -126,16 +148,15 @@ impl SpeedRegisterSettings {
                 // As speeds increase, clock_div_multiplier will approach 1, and this can cause nontrivial overshoot of the target frequency in
                 // cases where the clock_div_multiplier is low. To mitigate this, we round up rather than down when calculating clock_div_multiplier
                 // because undershoot is preferable to overshoot in these cases.
-                let clock_div_multiplier =
-                    CLOCK_SPEED_HZ.div_ceil(target_freq_hz * u32::from(hi_clocks + lo_clocks)) as u16;
+                let clock_div_multiplier = clock_hz.div_ceil(target_freq_hz * u32::from(hi_clocks + lo_clocks)) as u16;
                 (hi_clocks, lo_clocks, clock_div_multiplier)
             })
             .filter(|(hi_clocks, lo_clocks, clock_div_multiplier)| {
-                get_freq_hz(*hi_clocks, *lo_clocks, *clock_div_multiplier, CLOCK_SPEED_HZ) <= target_freq_hz
+                get_freq_hz(*hi_clocks, *lo_clocks, *clock_div_multiplier, clock_hz) <= target_freq_hz
             })
             .min_by(|(hi_a, lo_a, div_a), (hi_b, lo_b, div_b)| {
-                let freq_a = get_freq_hz(*hi_a, *lo_a, *div_a, CLOCK_SPEED_HZ);
-                let freq_b = get_freq_hz(*hi_b, *lo_b, *div_b, CLOCK_SPEED_HZ);
+                let freq_a = get_freq_hz(*hi_a, *lo_a, *div_a, clock_hz);
+                let freq_b = get_freq_hz(*hi_b, *lo_b, *div_b, clock_hz);
 
                 target_freq_hz.abs_diff(freq_a).cmp(&target_freq_hz.abs_diff(freq_b))
             })
@@ -148,18 +169,34 @@ impl SpeedRegisterSettings {
             scl_high_clocks: result_clocks_hi.to_clocks_enum()?,
             scl_low_clocks: result_clocks_lo.to_clocks_enum()?,
             clock_div_multiplier: result_div_multiplier - CLOCK_DIV_MULTIPLIER_OFFSET,
-            _actual_freq_hz: CLOCK_SPEED_HZ
+            clock_hz,
+            _actual_freq_hz: clock_hz
                 / (u32::from(result_clocks_hi + result_clocks_lo) * u32::from(result_div_multiplier)),
         })
     }
 }
 
+/// Number of linked descriptors chained behind the DMA channel descriptor, payloads are limited
+/// to this many plus one times [`dma::MAX_TRANSFER_COUNT`] bytes.
+const DMA_LINKED_DESCRIPTORS: usize = 7;
+
+/// Half an SCL period while recovering the bus: 5 us at the 300 MHz maximum core clock, longer
+/// at lower clocks, which I2C devices tolerate.
+const RECOVERY_HALF_PERIOD_CYCLES: u32 = 1500;
+
+/// Clock pulses that release any slave in the middle of a byte, 8 data bits and the ACK.
+const RECOVERY_PULSES: usize = 9;
+
 /// use `FCn` as I2C Master controller
 pub struct I2cMaster<'a, M: Mode> {
     info: Info,
     _flexcomm: FlexcommRef,
     _phantom: PhantomData<M>,
     dma_ch: Option<dma::channel::Channel<'a>>,
+    dma_descriptors: [LinkedDescriptor; DMA_LINKED_DESCRIPTORS],
+    bus_pins: Option<(BusPin<'a>, BusPin<'a>)>,
+    clock_hz: u32,
+    config: Config,
 }
 
 /// Represents a duty cycle (percentage of time to hold the SCL line high per bit).  Fitting is best-effort / not exact.
@@ -224,6 +261,19 @@ pub struct Config {
     ///
     /// If enabled, this flag will reduce the target frequency by 3% when calculating the clock settings to provide some margin, which should prevent jitter from causing the clock speed to exceed the target speed.
     pub strict_mode: bool,
+
+    /// Longest time, in microseconds, a slave may stretch SCL low before the transfer fails with
+    /// [`TransferError::Timeout`]
+    ///
+    /// The time is rounded to a multiple of 16 I2C function clocks and capped by the hardware,
+    /// to about 85 ms at 100 kbit/s. `None` lets slaves stretch SCL forever.
+    pub scl_timeout_us: Option<u32>,
+
+    /// Number of times a transaction is started over after losing arbitration to another master
+    ///
+    /// Only `transaction()` and the `embedded-hal` methods built on it retry. With the default of
+    /// 0, losing arbitration fails the transaction with [`TransferError::ArbitrationLoss`].
+    pub arbitration_retries: u8,
 }
 
 impl Default for Config {
@@ -232,6 +282,8 @@ impl Default for Config {
             speed: Speed::Standard,
             duty_cycle: Default::default(),
             strict_mode: false,
+            scl_timeout_us: None,
+            arbitration_retries: 0,
         }
     }
 }
@@ -252,11 +304,34 @@ impl<'a, M: Mode> I2cMaster<'a, M> {
 
         sda.as_sda();
         scl.as_scl();
+        let bus_pins = BusPin::new::<T, _>(scl).zip(BusPin::new::<T, _>(sda));
 
         let info = T::info();
         let regs = info.regs;
 
-        let speed_settings = SpeedRegisterSettings::new(config.duty_cycle, config.speed, config.strict_mode)?;
+        let clock_hz = clock_frequency(clock)?;
+        let speed_settings = SpeedRegisterSettings::new(clock_hz, config.duty_cycle, config.speed, config.strict_mode)?;
+        Self::apply_speed(info, &speed_settings, &config);
+
+        regs.intenset().reset();
+
+        regs.cfg()
+            .write(|w| w.msten().set_bit().timeouten().bit(config.scl_timeout_us.is_some()));
+
+        Ok(Self {
+            info,
+            _flexcomm: flexcomm,
+            _phantom: PhantomData,
+            dma_ch,
+            dma_descriptors: [LinkedDescriptor::new(); DMA_LINKED_DESCRIPTORS],
+            bus_pins,
+            clock_hz,
+            config,
+        })
+    }
+
+    fn apply_speed(info: Info, speed_settings: &SpeedRegisterSettings, config: &Config) {
+        let regs = info.regs;
 
         regs.msttime().write(|w| {
             w.mstsclhigh()
@@ -270,16 +345,103 @@ impl<'a, M: Mode> I2cMaster<'a, M> {
             unsafe { w.divval().bits(speed_settings.clock_div_multiplier) }
         });
 
-        regs.intenset().reset();
+        // the time-out counts divided clocks, follow the new divider
+        if let Some(timeout_us) = config.scl_timeout_us {
+            regs.timeout().write(|w|
+                // SAFETY: only unsafe due to .bits usage
+                unsafe { w.to().bits(speed_settings.timeout_to(timeout_us)) });
+        }
+    }
 
-        regs.cfg().write(|w| w.msten().set_bit());
+    /// Change the bus speed between transactions, keeping the duty cycle and strict mode of the
+    /// configuration
+    ///
+    /// SCL timings are recomputed for the function clock of the flexcomm. [`Speed::High`] is not
+    /// supported by the master and fails with [`Error::UnsupportedConfiguration`], as does a
+    /// speed the function clock cannot reach, both leaving the current speed in place.
+    pub fn set_speed(&mut self, speed: Speed) -> Result<()> {
+        let regs = self.info.regs;
+
+        let speed_settings =
+            SpeedRegisterSettings::new(self.clock_hz, self.config.duty_cycle, speed, self.config.strict_mode)?;
+
+        // the timings may only change while the master is idle
+        let stat = regs.stat().read();
+        if stat.mstpending().is_in_progress() || !stat.mststate().is_idle() {
+            return Err(TransferError::OtherBusError.into());
+        }
 
-        Ok(Self {
-            info,
-            _flexcomm: flexcomm,
-            _phantom: PhantomData,
-            dma_ch,
-        })
+        regs.cfg().modify(|_, w| w.msten().disabled());
+        Self::apply_speed(self.info, &speed_settings, &self.config);
+        regs.cfg().modify(|_, w| w.msten().enabled());
+
+        self.config.speed = speed;
+
+        Ok(())
+    }
+
+    /// Recover a bus held by a slave stuck in the middle of a transfer, without a power cycle.
+    ///
+    /// If SDA is held low, SCL is driven as a GPIO for up to 9 clock pulses until the slave
+    /// releases it, then a STOP is sent. The pins are given back to the flexcomm afterwards.
+    /// FLEXCOMM15 pins have no GPIO function, so its bus cannot be recovered this way.
+    pub fn recover_bus(&mut self) -> Result<()> {
+        let Some((scl_pin, sda_pin)) = self.bus_pins.as_mut() else {
+            return Err(Error::UnsupportedConfiguration);
+        };
+
+        let regs = self.info.regs;
+        regs.cfg().modify(|_, w| w.msten().disabled());
+
+        let released = {
+            let mut scl = Flex::<SenseEnabled>::new(scl_pin.pin.reborrow());
+            let mut sda = Flex::<SenseEnabled>::new(sda_pin.pin.reborrow());
+            for pin in [&mut scl, &mut sda] {
+                pin.set_high();
+                pin.set_as_output(DriveMode::OpenDrain, DriveStrength::Normal, SlewRate::Slow);
+            }
+            cortex_m::asm::delay(RECOVERY_HALF_PERIOD_CYCLES);
+
+            if sda.is_low() {
+                for _ in 0..RECOVERY_PULSES {
+                    scl.set_low();
+                    cortex_m::asm::delay(RECOVERY_HALF_PERIOD_CYCLES);
+                    scl.set_high();
+                    cortex_m::asm::delay(RECOVERY_HALF_PERIOD_CYCLES);
+
+                    if sda.is_high() {
+                        break;
+                    }
+                }
+
+                // STOP: SDA rising while SCL is high
+                scl.set_low();
+                cortex_m::asm::delay(RECOVERY_HALF_PERIOD_CYCLES);
+                sda.set_low();
+                cortex_m::asm::delay(RECOVERY_HALF_PERIOD_CYCLES);
+                scl.set_high();
+                cortex_m::asm::delay(RECOVERY_HALF_PERIOD_CYCLES);
+                sda.set_high();
+                cortex_m::asm::delay(RECOVERY_HALF_PERIOD_CYCLES);
+            }
+
+            sda.is_high() && scl.is_high()
+        };
+
+        scl_pin.restore();
+        sda_pin.restore();
+        regs.cfg().modify(|_, w| w.msten().enabled());
+
+        if released {
+            Ok(())
+        } else {
+            Err(TransferError::OtherBusError.into())
+        }
+    }
+
+    /// ACK the byte just read, so the next read gets the following byte
+    pub(super) fn continue_read(&mut self) {
+        self.info.regs.mstctl().write(|w| w.mstcontinue().set_bit());
     }
 
     fn check_for_bus_errors(&self) -> Result<()> {
@@ -287,6 +449,8 @@ impl<'a, M: Mode> I2cMaster<'a, M> {
 
         if stat.mststate().is_nack_data() {
             Err(TransferError::WriteFail.into())
+        } else if stat.scltimeout().is_timeout() {
+            Err(TransferError::Timeout.into())
         } else if stat.mstarbloss().is_arbitration_loss() {
             Err(TransferError::ArbitrationLoss.into())
         } else if stat.mstststperr().is_error() {
@@ -310,7 +474,13 @@ impl<'a> I2cMaster<'a, Blocking> {
         Self::new_inner::<T>(fc, scl, sda, config, None)
     }
 
-    fn start(&mut self, address: u16, is_read: bool) -> Result<()> {
+    pub(super) fn start(&mut self, address: u16, is_read: bool) -> Result<()> {
+        // clear an arbitration loss or time-out left over from a previous transfer
+        self.info
+            .regs
+            .stat()
+            .write(|w| w.mstarbloss().set_bit().scltimeout().set_bit());
+
         // check if the address is 10-bit
         let is_10bit = address > 0x7F;
 
@@ -415,7 +585,7 @@ impl<'a> I2cMaster<'a, Blocking> {
         Ok(())
     }
 
-    fn read_no_start_no_stop(&mut self, read: &mut [u8]) -> Result<()> {
+    pub(super) fn read_no_start_no_stop(&mut self, read: &mut [u8]) -> Result<()> {
         let i2cregs = self.info.regs;
 
         // read of 0 size is not allowed according to i2c spec
@@ -446,7 +616,7 @@ impl<'a> I2cMaster<'a, Blocking> {
         Ok(())
     }
 
-    fn write_no_start_no_stop(&mut self, write: &[u8]) -> Result<()> {
+    pub(super) fn write_no_start_no_stop(&mut self, write: &[u8]) -> Result<()> {
         // Procedure from 24.3.1.1 pg 545
         let i2cregs = self.info.regs;
 
@@ -464,7 +634,7 @@ impl<'a> I2cMaster<'a, Blocking> {
         Ok(())
     }
 
-    fn stop(&mut self) -> Result<()> {
+    pub(super) fn stop(&mut self) -> Result<()> {
         // Procedure from 24.3.1.1 pg 545
         let i2cregs = self.info.regs;
 
@@ -480,8 +650,80 @@ impl<'a> I2cMaster<'a, Blocking> {
         }
     }
 
+    /// Run `operations` as one transaction: a START, a repeated START whenever the direction
+    /// changes and a STOP at the end
+    fn transaction_once(&mut self, address: u16, operations: &mut [embedded_hal_1::i2c::Operation<'_>]) -> Result<()> {
+        let Some(first_operation) = operations.first() else {
+            return Ok(());
+        };
+        check_operations(operations)?;
+
+        let is_read = matches!(first_operation, embedded_hal_1::i2c::Operation::Read(_));
+        let result = self.run_operations(address, is_read, operations);
+
+        if result.is_err() {
+            self.release_bus();
+        }
+
+        result
+    }
+
+    /// Don't leave the bus claimed by a START without its STOP, after a failed transfer
+    pub(super) fn release_bus(&mut self) {
+        let stat = self.info.regs.stat().read();
+        if stat.mstpending().is_pending() && !stat.mststate().is_idle() {
+            self.info.regs.mstctl().write(|w| w.mststop().set_bit());
+        }
+    }
+
+    fn run_operations(
+        &mut self,
+        address: u16,
+        is_read: bool,
+        operations: &mut [embedded_hal_1::i2c::Operation<'_>],
+    ) -> Result<()> {
+        // Send beginning start
+        self.start(address, is_read)?;
+
+        let mut last_seen_op: Option<&mut embedded_hal_1::i2c::Operation<'_>> = None;
+        for op in operations {
+            match op {
+                embedded_hal_1::i2c::Operation::Read(read) if read.is_empty() => {
+                    // Merged into the neighbouring read, checked by `check_operations`
+                    continue;
+                }
+                embedded_hal_1::i2c::Operation::Read(read) => {
+                    if matches!(last_seen_op.as_ref(), Some(embedded_hal_1::i2c::Operation::Write(_))) {
+                        // We just sent a Write and now we have a Read, send restart.
+                        self.start(address, true)?;
+                    } else if last_seen_op.is_some() {
+                        // Reads are contiguous, ACK the last byte of the previous one.
+                        self.continue_read();
+                    }
+                    self.read_no_start_no_stop(read)?;
+                }
+                embedded_hal_1::i2c::Operation::Write(write) => {
+                    if matches!(last_seen_op.as_ref(), Some(embedded_hal_1::i2c::Operation::Read(_))) {
+                        // We just sent a Read and now we have a Write, send restart.
+                        self.start(address, false)?;
+                    }
+                    self.write_no_start_no_stop(write)?;
+                }
+            }
+            last_seen_op = Some(op);
+        }
+
+        self.stop()?;
+
+        Ok(())
+    }
+
     fn poll_ready(&mut self) -> Result<()> {
-        while self.info.regs.stat().read().mstpending().is_in_progress() {}
+        while self.info.regs.stat().read().mstpending().is_in_progress() {
+            if self.info.regs.stat().read().scltimeout().is_timeout() {
+                return Err(TransferError::Timeout.into());
+            }
+        }
 
         Ok(())
     }
@@ -489,6 +731,9 @@ impl<'a> I2cMaster<'a, Blocking> {
 
 impl<'a> I2cMaster<'a, Async> {
     /// use flexcomm fc with Pins scl, sda as an I2C Master bus, configuring to speed and pull
+    ///
+    /// Payloads are moved with DMA, chained over several descriptors when longer than
+    /// [`dma::MAX_TRANSFER_COUNT`] bytes; the protocol state is followed from interrupts.
     pub fn new_async<T: Instance>(
         fc: Peri<'a, T>,
         scl: Peri<'a, impl SclPin<T>>,
@@ -498,9 +743,34 @@ impl<'a> I2cMaster<'a, Async> {
         config: Config,
         dma_ch: Peri<'a, impl MasterDma<T>>,
     ) -> Result<Self> {
-        force_clear_remediation(&T::info());
         let ch = dma::Dma::reserve_channel(dma_ch);
-        let this = Self::new_inner::<T>(fc, scl, sda, config, ch)?;
+        Self::new_async_inner::<T>(fc, scl, sda, config, ch)
+    }
+
+    /// use flexcomm fc with Pins scl, sda as an I2C Master bus, configuring to speed and pull
+    ///
+    /// Every byte is moved from the interrupt handler, without a DMA channel. Short register
+    /// accesses complete sooner than with [`I2cMaster::new_async`], which pays for the DMA setup
+    /// on each transfer, at the cost of one interrupt per byte.
+    pub fn new_async_without_dma<T: Instance>(
+        fc: Peri<'a, T>,
+        scl: Peri<'a, impl SclPin<T>>,
+        sda: Peri<'a, impl SdaPin<T>>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'a,
+        config: Config,
+    ) -> Result<Self> {
+        Self::new_async_inner::<T>(fc, scl, sda, config, None)
+    }
+
+    fn new_async_inner<T: Instance>(
+        fc: Peri<'a, T>,
+        scl: Peri<'a, impl SclPin<T>>,
+        sda: Peri<'a, impl SdaPin<T>>,
+        config: Config,
+        dma_ch: Option<dma::channel::Channel<'a>>,
+    ) -> Result<Self> {
+        force_clear_remediation(&T::info());
+        let this = Self::new_inner::<T>(fc, scl, sda, config, dma_ch)?;
 
         T::Interrupt::unpend();
         unsafe { T::Interrupt::enable() };
@@ -508,7 +778,79 @@ impl<'a> I2cMaster<'a, Async> {
         Ok(this)
     }
 
-    async fn start(&mut self, address: u16, is_read: bool, guard: Option<StartStopGuard>) -> Result<StartStopGuard> {
+    /// Run `operations` as one transaction: a START, a repeated START whenever the direction
+    /// changes and a STOP at the end
+    async fn transaction_once(
+        &mut self,
+        address: u16,
+        operations: &mut [embedded_hal_1::i2c::Operation<'_>],
+    ) -> Result<()> {
+        let Some(first_operation) = operations.first() else {
+            return Ok(());
+        };
+        check_operations(operations)?;
+
+        // Send beginning start
+        let mut guard = Some(
+            self.start(
+                address,
+                match first_operation {
+                    embedded_hal_1::i2c::Operation::Read(_) => true,
+                    embedded_hal_1::i2c::Operation::Write(_) => false,
+                },
+                None,
+            )
+            .await?,
+        );
+
+        let mut last_seen_op: Option<&mut embedded_hal_1::i2c::Operation<'_>> = None;
+        for op in operations {
+            match op {
+                embedded_hal_1::i2c::Operation::Read(read) if read.is_empty() => {
+                    // Merged into the neighbouring read, checked by `check_operations`
+                    continue;
+                }
+                embedded_hal_1::i2c::Operation::Read(read) => {
+                    if matches!(last_seen_op.as_ref(), Some(embedded_hal_1::i2c::Operation::Write(_))) {
+                        // We just sent a Write and now we have a Read, send restart.
+                        guard = Some(self.start(address, true, guard).await?);
+                    } else if last_seen_op.is_some() {
+                        // Reads are contiguous, ACK the last byte of the previous one.
+                        self.continue_read();
+                    }
+                    self.read_no_start_no_stop(read).await?;
+                }
+                embedded_hal_1::i2c::Operation::Write(write) => {
+                    if matches!(last_seen_op.as_ref(), Some(embedded_hal_1::i2c::Operation::Read(_))) {
+                        // We just sent a Read and now we have a Write, send restart.
+                        guard = Some(self.start(address, false, guard).await?);
+                    }
+                    self.write_no_start_no_stop(write).await?;
+                }
+            }
+            last_seen_op = Some(op);
+        }
+
+        if let Some(guard) = guard {
+            self.stop()?.await?;
+            guard.defuse();
+        }
+
+        Ok(())
+    }
+
+    pub(super) async fn start(
+        &mut self,
+        address: u16,
+        is_read: bool,
+        guard: Option<StartStopGuard>,
+    ) -> Result<StartStopGuard> {
+        // clear an arbitration loss or time-out left over from a previous transfer
+        self.info
+            .regs
+            .stat()
+            .write(|w| w.mstarbloss().set_bit().scltimeout().set_bit());
+
         // check if the address is 10-bit
         let is_10bit = address > 0x7F;
 
@@ -534,8 +876,8 @@ impl<'a> I2cMaster<'a, Async> {
             // if we failed to complete sending of the address
             // In practice, this seems to be only way to recover. Engaging with
             // NXP to see if there is better way to handle this.
-            i2cregs.cfg().write(|w| w.msten().disabled());
-            i2cregs.cfg().write(|w| w.msten().enabled());
+            i2cregs.cfg().modify(|_, w| w.msten().disabled());
+            i2cregs.cfg().modify(|_, w| w.msten().enabled());
         });
 
         // If there was a previous cancellation, wait for the remediation step by the
@@ -548,6 +890,8 @@ impl<'a> I2cMaster<'a, Async> {
 
                 if stat.mstpending().is_pending() {
                     Poll::Ready(Ok::<(), Error>(()))
+                } else if stat.scltimeout().is_timeout() {
+                    Poll::Ready(Err(TransferError::Timeout.into()))
                 } else if stat.mstarbloss().is_arbitration_loss() {
                     Poll::Ready(Err(TransferError::ArbitrationLoss.into()))
                 } else if stat.mstststperr().is_error() {
@@ -564,6 +908,8 @@ impl<'a> I2cMaster<'a, Async> {
                         .set_bit()
                         .mstststperren()
                         .set_bit()
+                        .scltimeouten()
+                        .set_bit()
                 });
             },
         )
@@ -607,8 +953,8 @@ impl<'a> I2cMaster<'a, Async> {
             // if we failed to complete sending of the address
             // In practice, this seems to be only way to recover. Engaging with
             // NXP to see if there is better way to handle this.
-            i2cregs.cfg().write(|w| w.msten().disabled());
-            i2cregs.cfg().write(|w| w.msten().enabled());
+            i2cregs.cfg().modify(|_, w| w.msten().disabled());
+            i2cregs.cfg().modify(|_, w| w.msten().enabled());
         });
 
         // If there was a previous cancellation, wait for the remediation step by the
@@ -621,6 +967,8 @@ impl<'a> I2cMaster<'a, Async> {
 
                 if stat.mstpending().is_pending() {
                     Poll::Ready(Ok::<(), Error>(()))
+                } else if stat.scltimeout().is_timeout() {
+                    Poll::Ready(Err(TransferError::Timeout.into()))
                 } else if stat.mstarbloss().is_arbitration_loss() {
                     Poll::Ready(Err(TransferError::ArbitrationLoss.into()))
                 } else if stat.mstststperr().is_error() {
@@ -637,6 +985,8 @@ impl<'a> I2cMaster<'a, Async> {
                         .set_bit()
                         .mstststperren()
                         .set_bit()
+                        .scltimeouten()
+                        .set_bit()
                 });
             },
         )
@@ -674,7 +1024,7 @@ impl<'a> I2cMaster<'a, Async> {
         Ok(guard)
     }
 
-    async fn read_no_start_no_stop(&mut self, read: &mut [u8]) -> Result<()> {
+    pub(super) async fn read_no_start_no_stop(&mut self, read: &mut [u8]) -> Result<()> {
         let i2cregs = self.info.regs;
 
         // read of 0 size is not allowed according to i2c spec
@@ -687,12 +1037,19 @@ impl<'a> I2cMaster<'a, Async> {
 
         if let Some(dma_ch) = &self.dma_ch {
             if !dma_read.is_empty() {
-                let transfer = dma::transfer::Transfer::new_read(
-                    dma_ch,
-                    i2cregs.mstdat().as_ptr() as *mut u8,
-                    dma_read,
-                    Default::default(),
-                );
+                // SAFETY: `dma_read` is exclusively borrowed until the transfer completes, which
+                // is aborted if this future is dropped.
+                let transfer = unsafe {
+                    dma::transfer::Transfer::new_read_linked(
+                        dma_ch,
+                        i2cregs.mstdat().as_ptr() as *const u8,
+                        dma_read.as_mut_ptr(),
+                        dma_read.len(),
+                        &mut self.dma_descriptors,
+                        Default::default(),
+                    )
+                }
+                .map_err(|_| Error::UnsupportedConfiguration)?;
 
                 // According to sections 24.7.7.1 and 24.7.7.2, we should
                 // first program the DMA channel for carrying out a transfer
@@ -720,11 +1077,15 @@ impl<'a> I2cMaster<'a, Async> {
                                 .set_bit()
                                 .mstststperren()
                                 .set_bit()
+                                .scltimeouten()
+                                .set_bit()
                         });
 
                         let stat = i2cregs.stat().read();
 
-                        if stat.mstarbloss().is_arbitration_loss() {
+                        if stat.scltimeout().is_timeout() {
+                            Poll::Ready(Err::<(), Error>(TransferError::Timeout.into()))
+                        } else if stat.mstarbloss().is_arbitration_loss() {
                             Poll::Ready(Err::<(), Error>(TransferError::ArbitrationLoss.into()))
                         } else if stat.mstststperr().is_error() {
                             Poll::Ready(Err::<(), Error>(TransferError::StartStopError.into()))
@@ -746,6 +1107,8 @@ impl<'a> I2cMaster<'a, Async> {
 
                     if stat.mstpending().is_pending() {
                         Poll::Ready(Ok::<(), Error>(()))
+                    } else if stat.scltimeout().is_timeout() {
+                        Poll::Ready(Err(TransferError::Timeout.into()))
                     } else if stat.mstarbloss().is_arbitration_loss() {
                         Poll::Ready(Err(TransferError::ArbitrationLoss.into()))
                     } else if stat.mstststperr().is_error() {
@@ -762,6 +1125,8 @@ impl<'a> I2cMaster<'a, Async> {
                             .set_bit()
                             .mstststperren()
                             .set_bit()
+                            .scltimeouten()
+                            .set_bit()
                     });
                 },
             )
@@ -780,6 +1145,8 @@ impl<'a> I2cMaster<'a, Async> {
 
                         if stat.mstpending().is_pending() {
                             Poll::Ready(Ok::<(), Error>(()))
+                        } else if stat.scltimeout().is_timeout() {
+                            Poll::Ready(Err(TransferError::Timeout.into()))
                         } else if stat.mstarbloss().is_arbitration_loss() {
                             Poll::Ready(Err(TransferError::ArbitrationLoss.into()))
                         } else if stat.mstststperr().is_error() {
@@ -796,6 +1163,8 @@ impl<'a> I2cMaster<'a, Async> {
                                 .set_bit()
                                 .mstststperren()
                                 .set_bit()
+                                .scltimeouten()
+                                .set_bit()
                         });
                     },
                 )
@@ -819,7 +1188,7 @@ impl<'a> I2cMaster<'a, Async> {
         Ok(())
     }
 
-    async fn write_no_start_no_stop(&mut self, write: &[u8]) -> Result<()> {
+    pub(super) async fn write_no_start_no_stop(&mut self, write: &[u8]) -> Result<()> {
         // Procedure from 24.3.1.1 pg 545
         let i2cregs = self.info.regs;
 
@@ -828,12 +1197,19 @@ impl<'a> I2cMaster<'a, Async> {
         }
 
         if let Some(dma_ch) = &self.dma_ch {
-            let transfer = dma::transfer::Transfer::new_write(
-                dma_ch,
-                write,
-                i2cregs.mstdat().as_ptr() as *mut u8,
-                Default::default(),
-            );
+            // SAFETY: `write` is borrowed until the transfer completes, which is aborted if this
+            // future is dropped.
+            let transfer = unsafe {
+                dma::transfer::Transfer::new_write_linked(
+                    dma_ch,
+                    write.as_ptr(),
+                    write.len(),
+                    i2cregs.mstdat().as_ptr() as *mut u8,
+                    &mut self.dma_descriptors,
+                    Default::default(),
+                )
+            }
+            .map_err(|_| Error::UnsupportedConfiguration)?;
 
             // According to sections 24.7.7.1 and 24.7.7.2, we should
             // first program the DMA channel for carrying out a transfer
@@ -861,12 +1237,16 @@ impl<'a> I2cMaster<'a, Async> {
                             .set_bit()
                             .mstststperren()
                             .set_bit()
+                            .scltimeouten()
+                            .set_bit()
                     });
 
                     let stat = i2cregs.stat().read();
 
                     if stat.mststate().is_nack_data() {
                         Poll::Ready(Err::<(), Error>(TransferError::WriteFail.into()))
+                    } else if stat.scltimeout().is_timeout() {
+                        Poll::Ready(Err::<(), Error>(TransferError::Timeout.into()))
                     } else if stat.mstarbloss().is_arbitration_loss() {
                         Poll::Ready(Err::<(), Error>(TransferError::ArbitrationLoss.into()))
                     } else if stat.mstststperr().is_error() {
@@ -895,6 +1275,8 @@ impl<'a> I2cMaster<'a, Async> {
                         } else {
                             Poll::Ready(Ok::<(), Error>(()))
                         }
+                    } else if stat.scltimeout().is_timeout() {
+                        Poll::Ready(Err(TransferError::Timeout.into()))
                     } else if stat.mstarbloss().is_arbitration_loss() {
                         Poll::Ready(Err(TransferError::ArbitrationLoss.into()))
                     } else if stat.mstststperr().is_error() {
@@ -911,6 +1293,8 @@ impl<'a> I2cMaster<'a, Async> {
                             .set_bit()
                             .mstststperren()
                             .set_bit()
+                            .scltimeouten()
+                            .set_bit()
                     });
                 },
             )
@@ -934,6 +1318,8 @@ impl<'a> I2cMaster<'a, Async> {
                             } else {
                                 Poll::Ready(Ok::<(), Error>(()))
                             }
+                        } else if stat.scltimeout().is_timeout() {
+                            Poll::Ready(Err(TransferError::Timeout.into()))
                         } else if stat.mstarbloss().is_arbitration_loss() {
                             Poll::Ready(Err(TransferError::ArbitrationLoss.into()))
                         } else if stat.mstststperr().is_error() {
@@ -950,6 +1336,8 @@ impl<'a> I2cMaster<'a, Async> {
                                 .set_bit()
                                 .mstststperren()
                                 .set_bit()
+                                .scltimeouten()
+                                .set_bit()
                         });
                     },
                 )
@@ -961,7 +1349,7 @@ impl<'a> I2cMaster<'a, Async> {
         }
     }
 
-    fn stop(&mut self) -> Result<impl Future<Output = Result<()>> + use<'a, '_>> {
+    pub(super) fn stop(&mut self) -> Result<impl Future<Output = Result<()>> + use<'a, '_>> {
         // Procedure from 24.3.1.1 pg 545
         let i2cregs = self.info.regs;
 
@@ -977,6 +1365,8 @@ impl<'a> I2cMaster<'a, Async> {
 
                 if stat.mstpending().is_pending() && stat.mststate().is_idle() {
                     Poll::Ready(Ok(()))
+                } else if stat.scltimeout().is_timeout() {
+                    Poll::Ready(Err(TransferError::Timeout.into()))
                 } else if stat.mstarbloss().is_arbitration_loss() {
                     Poll::Ready(Err(TransferError::ArbitrationLoss.into()))
                 } else if stat.mstststperr().is_error() {
@@ -993,6 +1383,8 @@ impl<'a> I2cMaster<'a, Async> {
                         .set_bit()
                         .mstststperren()
                         .set_bit()
+                        .scltimeouten()
+                        .set_bit()
                 });
             },
         ))
@@ -1038,6 +1430,8 @@ impl<'a> I2cMaster<'a, Async> {
                     } else {
                         Poll::<Result<()>>::Pending
                     }
+                } else if stat.scltimeout().is_timeout() {
+                    Poll::Ready(Err(TransferError::Timeout.into()))
                 } else if stat.mstarbloss().is_arbitration_loss() {
                     Poll::Ready(Err(TransferError::ArbitrationLoss.into()))
                 } else if stat.mstststperr().is_error() {
@@ -1054,17 +1448,44 @@ impl<'a> I2cMaster<'a, Async> {
                         .set_bit()
                         .mstststperren()
                         .set_bit()
+                        .scltimeouten()
+                        .set_bit()
                 });
             },
         )
     }
 }
 
+/// Reject sequences the bus cannot carry before anything is sent: an I2C read always clocks in at
+/// least one byte, so an empty read is only accepted next to another read it can merge with.
+fn check_operations(operations: &[embedded_hal_1::i2c::Operation<'_>]) -> Result<()> {
+    // Bytes read so far by the current run of contiguous reads
+    let mut run: Option<usize> = None;
+
+    for op in operations {
+        match op {
+            embedded_hal_1::i2c::Operation::Read(read) => run = Some(run.unwrap_or(0) + read.len()),
+            embedded_hal_1::i2c::Operation::Write(_) => {
+                if run.take() == Some(0) {
+                    return Err(Error::InvalidArgument);
+                }
+            }
+        }
+    }
+
+    if run == Some(0) {
+        return Err(Error::InvalidArgument);
+    }
+
+    Ok(())
+}
+
 /// Error Types for I2C communication
 impl embedded_hal_1::i2c::Error for Error {
     fn kind(&self) -> embedded_hal_1::i2c::ErrorKind {
         match *self {
             Self::UnsupportedConfiguration => embedded_hal_1::i2c::ErrorKind::Other,
+            Self::InvalidArgument => embedded_hal_1::i2c::ErrorKind::Other,
             Self::Transfer(e) => match e {
                 TransferError::Timeout => embedded_hal_1::i2c::ErrorKind::Other,
                 TransferError::ReadFail | TransferError::WriteFail => {
@@ -1076,6 +1497,8 @@ impl embedded_hal_1::i2c::Error for Error {
                 TransferError::ArbitrationLoss => embedded_hal_1::i2c::ErrorKind::ArbitrationLoss,
                 TransferError::StartStopError => embedded_hal_1::i2c::ErrorKind::Bus,
                 TransferError::OtherBusError => embedded_hal_1::i2c::ErrorKind::Bus,
+                TransferError::PecMismatch => embedded_hal_1::i2c::ErrorKind::Other,
+                TransferError::Overrun => embedded_hal_1::i2c::ErrorKind::Overrun,
             },
         }
     }
@@ -1088,94 +1511,35 @@ impl<M: Mode> embedded_hal_1::i2c::ErrorType for I2cMaster<'_, M> {
 // implement generic i2c interface for peripheral master type
 impl<A: embedded_hal_1::i2c::AddressMode + Into<u16>> embedded_hal_1::i2c::I2c<A> for I2cMaster<'_, Blocking> {
     fn transaction(&mut self, address: A, operations: &mut [embedded_hal_1::i2c::Operation<'_>]) -> Result<()> {
-        let Some(first_operation) = operations.first() else {
-            return Ok(());
-        };
-
-        // Send beginning start
         let address = address.into();
-        self.start(
-            address,
-            match first_operation {
-                embedded_hal_1::i2c::Operation::Read(_) => true,
-                embedded_hal_1::i2c::Operation::Write(_) => false,
-            },
-        )?;
+        let mut retries = self.config.arbitration_retries;
 
-        let mut last_seen_op: Option<&mut embedded_hal_1::i2c::Operation<'_>> = None;
-        for op in operations {
-            match op {
-                embedded_hal_1::i2c::Operation::Read(read) => {
-                    if matches!(last_seen_op.as_ref(), Some(embedded_hal_1::i2c::Operation::Write(_))) {
-                        // We just sent a Write and now we have a Read, send restart.
-                        self.start(address, true)?;
-                    }
-                    self.read_no_start_no_stop(read)?;
-                }
-                embedded_hal_1::i2c::Operation::Write(write) => {
-                    if matches!(last_seen_op.as_ref(), Some(embedded_hal_1::i2c::Operation::Read(_))) {
-                        // We just sent a Read and now we have a Write, send restart.
-                        self.start(address, false)?;
-                    }
-                    self.write_no_start_no_stop(write)?;
+        loop {
+            match self.transaction_once(address, operations) {
+                Err(Error::Transfer(TransferError::ArbitrationLoss)) if retries > 0 => {
+                    warn!("I2C arbitration lost, retrying transaction");
+                    retries -= 1;
                 }
+                result => return result,
             }
-            last_seen_op = Some(op);
         }
-
-        self.stop()?;
-
-        Ok(())
     }
 }
 
 impl<A: embedded_hal_1::i2c::AddressMode + Into<u16>> embedded_hal_async::i2c::I2c<A> for I2cMaster<'_, Async> {
     async fn transaction(&mut self, address: A, operations: &mut [embedded_hal_1::i2c::Operation<'_>]) -> Result<()> {
-        let Some(first_operation) = operations.first() else {
-            return Ok(());
-        };
-
-        // Send beginning start
         let address = address.into();
-        let mut guard = Some(
-            self.start(
-                address,
-                match first_operation {
-                    embedded_hal_1::i2c::Operation::Read(_) => true,
-                    embedded_hal_1::i2c::Operation::Write(_) => false,
-                },
-                None,
-            )
-            .await?,
-        );
+        let mut retries = self.config.arbitration_retries;
 
-        let mut last_seen_op: Option<&mut embedded_hal_1::i2c::Operation<'_>> = None;
-        for op in operations {
-            match op {
-                embedded_hal_1::i2c::Operation::Read(read) => {
-                    if matches!(last_seen_op.as_ref(), Some(embedded_hal_1::i2c::Operation::Write(_))) {
-                        // We just sent a Write and now we have a Read, send restart.
-                        guard = Some(self.start(address, true, guard).await?);
-                    }
-                    self.read_no_start_no_stop(read).await?;
-                }
-                embedded_hal_1::i2c::Operation::Write(write) => {
-                    if matches!(last_seen_op.as_ref(), Some(embedded_hal_1::i2c::Operation::Read(_))) {
-                        // We just sent a Read and now we have a Write, send restart.
-                        guard = Some(self.start(address, false, guard).await?);
-                    }
-                    self.write_no_start_no_stop(write).await?;
+        loop {
+            match self.transaction_once(address, operations).await {
+                Err(Error::Transfer(TransferError::ArbitrationLoss)) if retries > 0 => {
+                    warn!("I2C arbitration lost, retrying transaction");
+                    retries -= 1;
                 }
+                result => return result,
             }
-            last_seen_op = Some(op);
         }
-
-        if let Some(guard) = guard {
-            self.stop()?.await?;
-            guard.defuse();
-        }
-
-        Ok(())
     }
 }
 
@@ -1190,12 +1554,12 @@ impl<A: embedded_hal_1::i2c::AddressMode + Into<u16>> embedded_hal_async::i2c::I
 /// or leaving the bus in the un-stopped state, we ask the interrupt handler to do
 /// it for us.
 #[must_use]
-struct StartStopGuard {
+pub(super) struct StartStopGuard {
     info: Info,
 }
 
 impl StartStopGuard {
-    fn defuse(self) {
+    pub(super) fn defuse(self) {
         core::mem::forget(self);
     }
 }
@@ -1223,3 +1587,47 @@ impl Drop for StartStopGuard {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_never_exceeds_target() {
+        for clock_hz in [16_000_000, 48_000_000] {
+            for (speed, target_hz) in [
+                (Speed::Standard, 100_000),
+                (Speed::Fast, 400_000),
+                (Speed::FastPlus, 1_000_000),
+            ] {
+                let settings = SpeedRegisterSettings::new(clock_hz, DutyCycle::default(), speed, false);
+                assert!(settings.is_ok_and(|settings| settings._actual_freq_hz <= target_hz));
+            }
+        }
+    }
+
+    #[test]
+    fn high_speed_is_rejected() {
+        assert!(SpeedRegisterSettings::new(48_000_000, DutyCycle::default(), Speed::High, false).is_err());
+    }
+
+    #[test]
+    fn empty_read_needs_a_neighbour() {
+        use embedded_hal_1::i2c::Operation;
+
+        let (mut a, mut b) = ([0u8; 2], [0u8; 0]);
+        assert!(check_operations(&[Operation::Read(&mut a), Operation::Read(&mut b)]).is_ok());
+
+        let mut b = [0u8; 0];
+        assert_eq!(
+            check_operations(&[Operation::Write(&[1]), Operation::Read(&mut b)]),
+            Err(Error::InvalidArgument)
+        );
+
+        let mut b = [0u8; 0];
+        assert_eq!(
+            check_operations(&[Operation::Read(&mut b), Operation::Write(&[1])]),
+            Err(Error::InvalidArgument)
+        );
+    }
+}
@@ -9,8 +9,9 @@ use embassy_sync::waitqueue::AtomicWaker;
 use paste::paste;
 use sealed::Sealed;
 
-use crate::iopctl::IopctlPin as Pin;
-use crate::{PeripheralType, dma, interrupt};
+use crate::gpio::AnyPin;
+use crate::iopctl::{Function, IopctlPin as Pin};
+use crate::{Peri, PeripheralType, dma, interrupt};
 
 /// I2C Master Driver
 pub mod master;
@@ -18,12 +19,19 @@ pub mod master;
 /// I2C Slave Driver
 pub mod slave;
 
+/// I2C Bus Monitor Driver
+pub mod monitor;
+
+/// SMBus over the I2C Master Driver
+pub mod smbus;
+
 /// shorthand for -> `Result<T>`
 pub type Result<T> = core::result::Result<T, Error>;
 
 /// specific information regarding transfer errors
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
 pub enum TransferError {
     /// Timeout error
     Timeout,
@@ -39,15 +47,23 @@ pub enum TransferError {
     StartStopError,
     /// state mismatch or other internal register unexpected state
     OtherBusError,
+    /// SMBus packet error code does not match the data received
+    PecMismatch,
+    /// monitor received a byte before the previous one was read
+    Overrun,
 }
 
 /// Error information type
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
 pub enum Error {
     /// configuration requested is not supported
     UnsupportedConfiguration,
 
+    /// operations cannot be carried on the bus, e.g. an empty read with no read to merge with
+    InvalidArgument,
+
     /// transaction failure types
     Transfer(TransferError),
 }
@@ -187,6 +203,10 @@ impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandl
             i2c.intenclr().write(|w| w.mstststperrclr().set_bit());
         }
 
+        if i2c.intstat().read().scltimeout().bit_is_set() {
+            i2c.intenclr().write(|w| w.scltimeoutclr().set_bit());
+        }
+
         if i2c.intstat().read().slvpending().bit_is_set() {
             // Retrieve and mask off the remediation flags
             let rem = T::remediation().fetch_and(!REMEDIATON_SLAVE_NAK, Ordering::AcqRel);
@@ -201,22 +221,73 @@ impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandl
             i2c.intenclr().write(|w| w.slvdeselclr().set_bit());
         }
 
+        let intstat = i2c.intstat().read();
+        if intstat.monrdy().bit_is_set() || intstat.monov().bit_is_set() || intstat.monidle().bit_is_set() {
+            i2c.intenclr()
+                .write(|w| w.monrdyclr().set_bit().monovclr().set_bit().monidleclr().set_bit());
+        }
+
         T::waker().wake();
     }
 }
 
 /// io configuration trait for easier configuration
-pub trait SclPin<Instance>: Pin + sealed::Sealed + PeripheralType {
+#[allow(private_bounds)]
+pub trait SclPin<Instance>: Pin + sealed::Sealed + SealedBusPin + SealedBusFunction<Instance> + PeripheralType {
     /// convert the pin to appropriate function for SCL usage
     fn as_scl(&self);
 }
 
 /// io configuration trait for easier configuration
-pub trait SdaPin<Instance>: Pin + sealed::Sealed + PeripheralType {
+#[allow(private_bounds)]
+pub trait SdaPin<Instance>: Pin + sealed::Sealed + SealedBusPin + SealedBusFunction<Instance> + PeripheralType {
     /// convert the pin to appropriate function for SDA usage
     fn as_sda(&self);
 }
 
+/// Bus pin kept as a GPIO, to recover a stuck bus
+struct BusPin<'a> {
+    pin: Peri<'a, AnyPin>,
+    function: Function,
+}
+
+impl<'a> BusPin<'a> {
+    /// `None` for pins without a GPIO function
+    fn new<T, P: SealedBusPin + SealedBusFunction<T>>(pin: Peri<'a, P>) -> Option<Self> {
+        Some(Self {
+            pin: P::into_gpio(pin)?,
+            function: P::FUNCTION,
+        })
+    }
+
+    /// give the pin back to the flexcomm
+    fn restore(&self) {
+        configure_bus_pin(&*self.pin, self.function);
+    }
+}
+
+trait SealedBusPin: PeripheralType {
+    /// type-erase the pin, `None` for pins without a GPIO function
+    fn into_gpio(pin: Peri<'_, Self>) -> Option<Peri<'_, AnyPin>>;
+}
+
+trait SealedBusFunction<Instance> {
+    /// pin function connecting the pin to the flexcomm
+    const FUNCTION: Function;
+}
+
+fn configure_bus_pin(pin: &impl Pin, function: Function) {
+    // UM11147 table 556 pg 550
+    pin.set_function(function)
+        .set_pull(crate::iopctl::Pull::None)
+        .enable_input_buffer()
+        .set_slew_rate(crate::gpio::SlewRate::Slow)
+        .set_drive_strength(crate::gpio::DriveStrength::Normal)
+        .disable_analog_multiplex()
+        .set_drive_mode(crate::gpio::DriveMode::OpenDrain)
+        .set_input_inverter(crate::gpio::Inverter::Disabled);
+}
+
 /// Driver mode.
 #[allow(private_bounds)]
 pub trait Mode: Sealed {}
@@ -234,39 +305,43 @@ impl Mode for Async {}
 // flexcomm <-> Pin function map
 macro_rules! impl_scl {
     ($piom_n:ident, $fn:ident, $fcn:ident) => {
+        impl SealedBusFunction<crate::peripherals::$fcn> for crate::peripherals::$piom_n {
+            const FUNCTION: Function = Function::$fn;
+        }
+
         impl SclPin<crate::peripherals::$fcn> for crate::peripherals::$piom_n {
             fn as_scl(&self) {
-                // UM11147 table 556 pg 550
-                self.set_function(crate::iopctl::Function::$fn)
-                    .set_pull(crate::iopctl::Pull::None)
-                    .enable_input_buffer()
-                    .set_slew_rate(crate::gpio::SlewRate::Slow)
-                    .set_drive_strength(crate::gpio::DriveStrength::Normal)
-                    .disable_analog_multiplex()
-                    .set_drive_mode(crate::gpio::DriveMode::OpenDrain)
-                    .set_input_inverter(crate::gpio::Inverter::Disabled);
+                configure_bus_pin(self, Self::FUNCTION);
             }
         }
     };
 }
 macro_rules! impl_sda {
     ($piom_n:ident, $fn:ident, $fcn:ident) => {
+        impl SealedBusFunction<crate::peripherals::$fcn> for crate::peripherals::$piom_n {
+            const FUNCTION: Function = Function::$fn;
+        }
+
         impl SdaPin<crate::peripherals::$fcn> for crate::peripherals::$piom_n {
             fn as_sda(&self) {
-                // UM11147 table 556 pg 550
-                self.set_function(crate::iopctl::Function::$fn)
-                    .set_pull(crate::iopctl::Pull::None)
-                    .enable_input_buffer()
-                    .set_slew_rate(crate::gpio::SlewRate::Slow)
-                    .set_drive_strength(crate::gpio::DriveStrength::Normal)
-                    .disable_analog_multiplex()
-                    .set_drive_mode(crate::gpio::DriveMode::OpenDrain)
-                    .set_input_inverter(crate::gpio::Inverter::Disabled);
+                configure_bus_pin(self, Self::FUNCTION);
             }
         }
     };
 }
 
+macro_rules! impl_bus_pin {
+    ($($piom_n:ident),*) => {
+        $(
+            impl SealedBusPin for crate::peripherals::$piom_n {
+                fn into_gpio(pin: Peri<'_, Self>) -> Option<Peri<'_, AnyPin>> {
+                    Some(pin.into())
+                }
+            }
+        )*
+    };
+}
+
 // Flexcomm0 GPIOs -
 impl_scl!(PIO0_1, F1, FLEXCOMM0);
 impl_sda!(PIO0_2, F1, FLEXCOMM0);
@@ -333,9 +408,28 @@ impl_sda!(PIO4_2, F1, FLEXCOMM7);
 impl_sda!(PIO4_3, F1, FLEXCOMM7);
 impl_scl!(PIO4_4, F1, FLEXCOMM7);
 
+// GPIOs usable for bus recovery
+impl_bus_pin!(
+    PIO0_1, PIO0_2, PIO0_8, PIO0_9, PIO0_10, PIO0_11, PIO0_15, PIO0_16, PIO0_17, PIO0_18, PIO0_22, PIO0_23, PIO0_24,
+    PIO0_25, PIO0_29, PIO0_30, PIO0_31, PIO1_0, PIO1_4, PIO1_5, PIO1_6, PIO1_7, PIO3_1, PIO3_2, PIO3_3, PIO3_4,
+    PIO3_16, PIO3_17, PIO3_18, PIO3_22, PIO3_26, PIO3_27, PIO3_28, PIO3_29, PIO4_1, PIO4_2, PIO4_3, PIO4_4, PIO4_8,
+    PIO7_26, PIO7_27, PIO7_28, PIO7_29, PIO7_30, PIO7_31
+);
+
 // Flexcomm15 GPIOs
 // Function configuration is not needed for FC15
 // Implementing SCL/SDA traits to use the I2C APIs
+// These pads have no GPIO function, so the bus cannot be recovered by hand
+impl SealedBusPin for crate::peripherals::PIOFC15_SCL {
+    fn into_gpio(_pin: Peri<'_, Self>) -> Option<Peri<'_, AnyPin>> {
+        None
+    }
+}
+impl SealedBusPin for crate::peripherals::PIOFC15_SDA {
+    fn into_gpio(_pin: Peri<'_, Self>) -> Option<Peri<'_, AnyPin>> {
+        None
+    }
+}
 impl_scl!(PIOFC15_SCL, F1, FLEXCOMM15);
 impl_sda!(PIOFC15_SDA, F1, FLEXCOMM15);
 
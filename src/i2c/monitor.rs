@@ -0,0 +1,197 @@
+//! Implements the I2C bus monitor, which records the traffic of other devices without driving the bus
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_hal_internal::Peri;
+
+use super::{Async, Blocking, Info, Instance, InterruptHandler, Mode, Result, SclPin, SdaPin, TransferError};
+use crate::flexcomm::FlexcommRef;
+use crate::interrupt;
+use crate::interrupt::typelevel::Interrupt;
+
+/// Configuration for I2C Monitor
+#[derive(Clone, Copy, Default)]
+pub struct Config {
+    /// Stretch SCL until each byte has been read from the monitor
+    ///
+    /// No byte is ever lost, at the cost of slowing down the bus being watched. When disabled,
+    /// a byte not read before the next one arrives fails with [`TransferError::Overrun`].
+    pub clock_stretch: bool,
+}
+
+/// Activity seen on the bus
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Event {
+    /// START, or repeated START, followed by the address byte
+    ///
+    /// For 10-bit addresses `address` holds the `0b11110XX` prefix, the low address byte follows
+    /// as [`Event::Data`].
+    Address {
+        /// a repeated START, without a STOP since the previous transfer
+        repeated: bool,
+        /// 7-bit address
+        address: u8,
+        /// the master reads from the addressed device
+        read: bool,
+        /// a device acknowledged the address
+        ack: bool,
+    },
+
+    /// Data byte
+    Data {
+        /// byte on the bus
+        byte: u8,
+        /// receiver acknowledged the byte
+        ack: bool,
+    },
+
+    /// STOP, the bus is idle again
+    Stop,
+}
+
+/// use `FCn` as I2C Monitor
+pub struct I2cMonitor<'a, M: Mode> {
+    info: Info,
+    _flexcomm: FlexcommRef,
+    _phantom: PhantomData<&'a M>,
+}
+
+impl<'a, M: Mode> I2cMonitor<'a, M> {
+    fn new_inner<T: Instance>(
+        _bus: Peri<'a, T>,
+        scl: Peri<'a, impl SclPin<T>>,
+        sda: Peri<'a, impl SdaPin<T>>,
+        config: Config,
+    ) -> Self {
+        let clock = crate::flexcomm::Clock::Ffro;
+        let flexcomm = T::enable(clock);
+        T::into_i2c();
+
+        sda.as_sda();
+        scl.as_scl();
+
+        let info = T::info();
+        let i2c = info.regs;
+
+        // sample the bus as fast as the function clock allows
+        i2c.clkdiv().write(|w|
+            // SAFETY: only unsafe due to .bits usage
+            unsafe { w.divval().bits(0) });
+
+        i2c.intenclr()
+            .write(|w| w.monrdyclr().set_bit().monovclr().set_bit().monidleclr().set_bit());
+
+        i2c.cfg()
+            .write(|w| w.monen().enabled().monclkstr().bit(config.clock_stretch));
+
+        // start from a clean state, the bus being idle is not a STOP
+        i2c.stat().write(|w| w.monov().set_bit().monidle().set_bit());
+
+        Self {
+            info,
+            _flexcomm: flexcomm,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Event waiting to be read, if any
+    fn poll_event(&self) -> Option<Result<Event>> {
+        let i2c = self.info.regs;
+        let stat = i2c.stat().read();
+
+        if stat.monov().is_overrun() {
+            i2c.stat().write(|w| w.monov().set_bit());
+            return Some(Err(TransferError::Overrun.into()));
+        }
+
+        if stat.monrdy().is_data_waiting() {
+            let data = i2c.monrxdat().read();
+            let byte = data.monrxdat().bits();
+            let ack = data.monnack().is_acknowledged();
+
+            let event = if data.monstart().is_start_detected() || data.monrestart().is_detected() {
+                Event::Address {
+                    repeated: data.monrestart().is_detected(),
+                    address: byte >> 1,
+                    read: byte & 1 != 0,
+                    ack,
+                }
+            } else {
+                Event::Data { byte, ack }
+            };
+
+            return Some(Ok(event));
+        }
+
+        if stat.monidle().is_idle() {
+            i2c.stat().write(|w| w.monidle().set_bit());
+            return Some(Ok(Event::Stop));
+        }
+
+        None
+    }
+}
+
+impl<'a> I2cMonitor<'a, Blocking> {
+    /// use flexcomm fc with Pins scl, sda to watch an I2C bus
+    pub fn new_blocking<T: Instance>(
+        _bus: Peri<'a, T>,
+        scl: Peri<'a, impl SclPin<T>>,
+        sda: Peri<'a, impl SdaPin<T>>,
+        config: Config,
+    ) -> Self {
+        Self::new_inner::<T>(_bus, scl, sda, config)
+    }
+
+    /// Wait for the next event on the bus
+    pub fn next_event(&mut self) -> Result<Event> {
+        loop {
+            if let Some(event) = self.poll_event() {
+                return event;
+            }
+        }
+    }
+}
+
+impl<'a> I2cMonitor<'a, Async> {
+    /// use flexcomm fc with Pins scl, sda to watch an I2C bus
+    pub fn new_async<T: Instance>(
+        _bus: Peri<'a, T>,
+        scl: Peri<'a, impl SclPin<T>>,
+        sda: Peri<'a, impl SdaPin<T>>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'a,
+        config: Config,
+    ) -> Self {
+        let this = Self::new_inner::<T>(_bus, scl, sda, config);
+
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        this
+    }
+
+    /// Wait for the next event on the bus
+    ///
+    /// Call it in a loop to follow the bus: without clock stretching, events are lost unless the
+    /// next call comes before the following byte completes.
+    pub async fn next_event(&mut self) -> Result<Event> {
+        poll_fn(|cx| {
+            self.info.waker.register(cx.waker());
+
+            if let Some(event) = self.poll_event() {
+                return Poll::Ready(event);
+            }
+
+            self.info
+                .regs
+                .intenset()
+                .write(|w| w.monrdyen().enabled().monoven().enabled().monidleen().enabled());
+
+            Poll::Pending
+        })
+        .await
+    }
+}
@@ -1,5 +1,6 @@
 //! Implements I2C function support over flexcomm + gpios
 
+use core::cell::Cell;
 use core::future::{Future, poll_fn};
 use core::marker::PhantomData;
 use core::sync::atomic::Ordering;
@@ -93,6 +94,25 @@ impl TryFrom<Address> for u16 {
     }
 }
 
+/// General call address, matched through slave address [`GENERAL_CALL_SLOT`]
+const GENERAL_CALL_ADDRESS: u8 = 0x00;
+
+/// Hardware slave address kept for the general call, the last one
+const GENERAL_CALL_SLOT: usize = 3;
+
+/// Qualification widening the match of the address given at construction
+#[derive(Copy, Clone, Debug)]
+pub enum Qualifier {
+    /// Only the address itself matches
+    None,
+
+    /// Addresses differing from it only in bits set in the mask match too
+    Mask(u8),
+
+    /// Every address from it up to this one, included, matches
+    Range(u8),
+}
+
 #[derive(Copy, Clone, Debug)]
 struct TenBitAddressInfo {
     first_byte: u8,
@@ -109,6 +129,7 @@ impl TenBitAddressInfo {
 }
 
 /// Command from master
+#[non_exhaustive]
 pub enum Command {
     /// I2C probe with no data
     Probe,
@@ -118,6 +139,10 @@ pub enum Command {
 
     /// I2C Write
     Write,
+
+    /// I2C write to the general call address, only reported once enabled with
+    /// [`I2cSlave::set_general_call`]
+    GeneralCall,
 }
 
 /// Result of response functions
@@ -136,6 +161,9 @@ pub struct I2cSlave<'a, M: Mode> {
     _phantom: PhantomData<M>,
     dma_ch: Option<dma::channel::Channel<'a>>,
     ten_bit_info: Option<TenBitAddressInfo>,
+    matched_address: Cell<Address>,
+    /// General calls are answered, and reported as such
+    general_call: bool,
 }
 
 impl<'a, M: Mode> I2cSlave<'a, M> {
@@ -207,8 +235,103 @@ impl<'a, M: Mode> I2cSlave<'a, M> {
             _phantom: PhantomData,
             dma_ch,
             ten_bit_info,
+            matched_address: Cell::new(address),
+            general_call: false,
         })
     }
+
+    /// Also answer the 7-bit `address` on hardware slave address `index`, 1 or 2, or stop
+    /// answering it with `None`
+    ///
+    /// Hardware slave address 3 is kept for [`I2cSlave::set_general_call`].
+    pub fn set_secondary_address(&mut self, index: usize, address: Option<Address>) -> Result<()> {
+        if !(1..GENERAL_CALL_SLOT).contains(&index) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        match address {
+            Some(Address::SevenBit(addr)) => {
+                self.info.regs.slvadr(index).write(|w|
+                    // SAFETY: unsafe only required due to use of unnamed "bits" field
+                    unsafe { w.slvadr().bits(addr) }.sadisable().enabled());
+            }
+            Some(Address::TenBit(_)) => return Err(Error::UnsupportedConfiguration),
+            None => self.info.regs.slvadr(index).write(|w| w.sadisable().disabled()),
+        }
+
+        Ok(())
+    }
+
+    /// Widen the match of the address given at construction, see [`Qualifier`]
+    ///
+    /// Only 7-bit slaves can be qualified, as a 10-bit address is matched on its first byte.
+    pub fn set_qualifier(&mut self, qualifier: Qualifier) -> Result<()> {
+        if self.ten_bit_info.is_some() {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let i2c = self.info.regs;
+        let address = i2c.slvadr(0).read().slvadr().bits();
+
+        match qualifier {
+            Qualifier::None => i2c.slvqual0().write(|w|
+                // SAFETY: unsafe only required due to use of unnamed "bits" field
+                unsafe { w.slvqual0().bits(0) }),
+            Qualifier::Mask(mask) if mask <= 0x7F => i2c.slvqual0().write(|w|
+                // SAFETY: unsafe only required due to use of unnamed "bits" field
+                unsafe { w.slvqual0().bits(mask) }.qualmode0().mask()),
+            Qualifier::Range(last) if (address..=0x7F).contains(&last) => i2c.slvqual0().write(|w|
+                // SAFETY: unsafe only required due to use of unnamed "bits" field
+                unsafe { w.slvqual0().bits(last) }.qualmode0().extend()),
+            _ => return Err(Error::UnsupportedConfiguration),
+        }
+
+        Ok(())
+    }
+
+    /// Address targeted by the transaction reported by the last `listen()`
+    ///
+    /// Useful to tell apart the devices emulated through secondary addresses or a [`Qualifier`].
+    pub fn matched_address(&self) -> Address {
+        self.matched_address.get()
+    }
+
+    /// Record which address matched, must be called before the address is acknowledged
+    fn latch_address(&self) {
+        let i2c = self.info.regs;
+
+        let address = match (i2c.stat().read().slvidx().is_address0(), self.ten_bit_info) {
+            (true, Some(ten_bit_address)) => Address::TenBit(
+                (u16::from(ten_bit_address.first_byte & 0b110) << 7) | u16::from(ten_bit_address.second_byte),
+            ),
+            // the received address byte, to report qualified addresses as well
+            _ => Address::SevenBit(i2c.slvdat().read().data().bits() >> 1),
+        };
+
+        self.matched_address.set(address);
+    }
+
+    /// Also answer writes to the general call address (0x00), reported as
+    /// [`Command::GeneralCall`]
+    pub fn set_general_call(&mut self, enabled: bool) {
+        self.general_call = enabled;
+
+        // the last address match = general call, the others keep their configured addresses
+        let slvadr = self.info.regs.slvadr(GENERAL_CALL_SLOT);
+        if enabled {
+            slvadr.modify(|_, w|
+                // SAFETY: unsafe only required due to use of unnamed "bits" field
+                unsafe { w.slvadr().bits(GENERAL_CALL_ADDRESS) }.sadisable().enabled());
+        } else {
+            slvadr.modify(|_, w| w.sadisable().disabled());
+        }
+    }
+
+    fn is_general_call(&self) -> bool {
+        self.general_call
+            && self.info.regs.stat().read().slvidx().is_address3()
+            && matches!(self.matched_address.get(), Address::SevenBit(GENERAL_CALL_ADDRESS))
+    }
 }
 
 impl<'a> I2cSlave<'a, Blocking> {
@@ -239,6 +362,7 @@ impl<'a> I2cSlave<'a, Blocking> {
             return Err(TransferError::AddressNack.into());
         }
 
+        self.latch_address();
         i2c.slvctl().write(|w| w.slvcontinue().continue_());
         Ok(())
     }
@@ -280,7 +404,10 @@ impl I2cSlave<'_, Blocking> {
         // Block until we know it is read or write
         self.poll()?;
 
-        if let Some(ten_bit_address) = self.ten_bit_info {
+        // The general call address is a 7-bit address, even on a 10-bit slave
+        let general_call = self.is_general_call();
+
+        if let Some(ten_bit_address) = self.ten_bit_info.filter(|_| !general_call) {
             // For 10 bit address, the first byte received is the second byte of the address
             if i2c.slvdat().read().data().bits() == ten_bit_address.second_byte {
                 i2c.slvctl().write(|w| w.slvcontinue().continue_());
@@ -326,6 +453,7 @@ impl I2cSlave<'_, Blocking> {
 
         let state = i2c.stat().read().slvstate().variant();
         match state {
+            Some(Slvstate::SlaveReceive) if general_call => Ok(Command::GeneralCall),
             Some(Slvstate::SlaveReceive) => Ok(Command::Write),
             Some(Slvstate::SlaveTransmit) => Ok(Command::Read),
             _ => Err(TransferError::OtherBusError.into()),
@@ -442,6 +570,7 @@ impl I2cSlave<'_, Async> {
         }
 
         if i2c.stat().read().slvstate().is_slave_address() {
+            self.latch_address();
             i2c.slvctl().write(|w| w.slvcontinue().continue_());
         } else {
             // If we are already past the addressed phase and in transmit or receive, that means we are already in the
@@ -452,7 +581,10 @@ impl I2cSlave<'_, Async> {
         // Poll for HW to transitioning from addressed to receive/transmit
         self.poll_sw_action().await;
 
-        if let Some(ten_bit_address) = self.ten_bit_info {
+        // The general call address is a 7-bit address, even on a 10-bit slave
+        let general_call = self.is_general_call();
+
+        if let Some(ten_bit_address) = self.ten_bit_info.filter(|_| !general_call) {
             // For 10 bit address, the first byte received is the second byte of the address
             if i2c.slvdat().read().data().bits() == ten_bit_address.second_byte {
                 i2c.slvctl().write(|w| w.slvcontinue().continue_());
@@ -498,6 +630,7 @@ impl I2cSlave<'_, Async> {
 
         let state = i2c.stat().read().slvstate().variant();
         match state {
+            Some(Slvstate::SlaveReceive) if general_call => Ok(Command::GeneralCall),
             Some(Slvstate::SlaveReceive) => Ok(Command::Write),
             Some(Slvstate::SlaveTransmit) => Ok(Command::Read),
             _ => Err(TransferError::OtherBusError.into()),
@@ -0,0 +1,379 @@
+//! Implements SMBus transactions over the I2C master
+//!
+//! Every transaction can carry a packet error code (PEC), a CRC-8 of all the bytes on the bus,
+//! addresses included, sent by the master after a write and checked by the master after a read.
+
+use embedded_hal_1::i2c::I2c as _;
+use embedded_hal_async::i2c::I2c as _;
+
+use super::master::I2cMaster;
+use super::{Async, Blocking, Error, Mode, Result, TransferError};
+
+/// Largest payload of a block transfer
+pub const BLOCK_MAX: usize = 32;
+
+/// Longest frame sent by the master: command, byte count, payload and PEC
+const FRAME_MAX: usize = BLOCK_MAX + 3;
+
+/// CRC-8 with polynomial x^8 + x^2 + x + 1, the SMBus packet error code
+fn crc8(mut crc: u8, data: &[u8]) -> u8 {
+    for byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Configuration for SMBus
+#[derive(Clone, Copy, Default)]
+pub struct Config {
+    /// Send and check a packet error code with every transaction
+    pub pec: bool,
+}
+
+/// SMBus host over an I2C Master bus
+pub struct Smbus<'a, M: Mode> {
+    i2c: I2cMaster<'a, M>,
+    pec: bool,
+}
+
+impl<'a, M: Mode> Smbus<'a, M> {
+    /// use the I2C master `i2c` as an SMBus host
+    pub fn new(i2c: I2cMaster<'a, M>, config: Config) -> Self {
+        Self { i2c, pec: config.pec }
+    }
+
+    /// give back the I2C master
+    pub fn into_inner(self) -> I2cMaster<'a, M> {
+        self.i2c
+    }
+
+    /// copy `bytes` to `frame`, followed by the PEC if enabled
+    fn frame<'f>(&self, address: u8, bytes: &[&[u8]], frame: &'f mut [u8; FRAME_MAX]) -> Result<&'f [u8]> {
+        let mut len = 0;
+        for part in bytes {
+            frame
+                .get_mut(len..len + part.len())
+                .ok_or(Error::UnsupportedConfiguration)?
+                .copy_from_slice(part);
+            len += part.len();
+        }
+
+        if self.pec {
+            let (data, rest) = frame.split_at_mut(len);
+            let pec = rest.first_mut().ok_or(Error::UnsupportedConfiguration)?;
+            *pec = crc8(crc8(0, &[address << 1]), data);
+            len += 1;
+        }
+
+        frame.get(..len).ok_or(Error::UnsupportedConfiguration)
+    }
+
+    /// check the PEC ending `read`, received after writing `write`
+    fn check(&self, address: u8, write: &[u8], read: &[u8]) -> Result<()> {
+        if !self.pec {
+            return Ok(());
+        }
+
+        let Some((pec, data)) = read.split_last() else {
+            return Err(TransferError::PecMismatch.into());
+        };
+
+        let mut crc = crc8(0, &[address << 1]);
+        crc = crc8(crc, write);
+        crc = crc8(crc, &[(address << 1) | 1]);
+        crc = crc8(crc, data);
+
+        if crc == *pec {
+            Ok(())
+        } else {
+            Err(TransferError::PecMismatch.into())
+        }
+    }
+
+    /// number of bytes to read for `len` data bytes
+    fn read_len(&self, len: usize) -> usize {
+        len + usize::from(self.pec)
+    }
+}
+
+impl Smbus<'_, Blocking> {
+    /// Send a single byte, without command code
+    pub fn send_byte(&mut self, address: u8, byte: u8) -> Result<()> {
+        self.write(address, &[&[byte]])
+    }
+
+    /// Receive a single byte, without command code
+    pub fn receive_byte(&mut self, address: u8) -> Result<u8> {
+        let mut buf = [0; 2];
+        let len = self.read_len(1);
+        let read = buf.get_mut(..len).ok_or(Error::UnsupportedConfiguration)?;
+        self.i2c.read(address, read)?;
+        self.check(address, &[], read)?;
+        let [byte, _] = buf;
+        Ok(byte)
+    }
+
+    /// Write a byte to register `command`
+    pub fn write_byte(&mut self, address: u8, command: u8, byte: u8) -> Result<()> {
+        self.write(address, &[&[command, byte]])
+    }
+
+    /// Read a byte from register `command`
+    pub fn read_byte(&mut self, address: u8, command: u8) -> Result<u8> {
+        let mut data = [0; 1];
+        self.write_read(address, &[command], &mut data)?;
+        let [byte] = data;
+        Ok(byte)
+    }
+
+    /// Write a little-endian word to register `command`
+    pub fn write_word(&mut self, address: u8, command: u8, word: u16) -> Result<()> {
+        self.write(address, &[&[command], &word.to_le_bytes()])
+    }
+
+    /// Read a little-endian word from register `command`
+    pub fn read_word(&mut self, address: u8, command: u8) -> Result<u16> {
+        let mut data = [0; 2];
+        self.write_read(address, &[command], &mut data)?;
+        Ok(u16::from_le_bytes(data))
+    }
+
+    /// Write `word` to register `command` and read back the word of the reply
+    pub fn process_call(&mut self, address: u8, command: u8, word: u16) -> Result<u16> {
+        let [lo, hi] = word.to_le_bytes();
+        let mut data = [0; 2];
+        self.write_read(address, &[command, lo, hi], &mut data)?;
+        Ok(u16::from_le_bytes(data))
+    }
+
+    /// Write a block of at most [`BLOCK_MAX`] bytes to register `command`
+    pub fn block_write(&mut self, address: u8, command: u8, data: &[u8]) -> Result<()> {
+        if data.len() > BLOCK_MAX {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        self.write(address, &[&[command, data.len() as u8], data])
+    }
+
+    /// Read a block from register `command` into `buf`, returns the number of bytes read
+    ///
+    /// The transaction fails if the device sends more than [`BLOCK_MAX`] bytes, or more than
+    /// fit in `buf`.
+    pub fn block_read(&mut self, address: u8, command: u8, buf: &mut [u8]) -> Result<usize> {
+        let mut count = [0];
+        let mut pec = [0; 1];
+        let pec = pec.get_mut(..usize::from(self.pec)).unwrap_or_default();
+
+        let result = Self::block_read_bus(&mut self.i2c, address, command, &mut count, buf, pec);
+        if result.is_err() {
+            self.i2c.release_bus();
+        }
+        let len = result?;
+        let data = buf.get(..len).unwrap_or_default();
+
+        if self.pec {
+            let mut crc = crc8(0, &[address << 1, command, (address << 1) | 1]);
+            crc = crc8(crc, &count);
+            crc = crc8(crc, data);
+            if pec != [crc] {
+                return Err(TransferError::PecMismatch.into());
+            }
+        }
+
+        Ok(len)
+    }
+
+    /// Bus part of [`Self::block_read`], which sends the STOP if it fails
+    fn block_read_bus(
+        i2c: &mut I2cMaster<'_, Blocking>,
+        address: u8,
+        command: u8,
+        count: &mut [u8; 1],
+        buf: &mut [u8],
+        pec: &mut [u8],
+    ) -> Result<usize> {
+        i2c.start(address.into(), false)?;
+        i2c.write_no_start_no_stop(&[command])?;
+        i2c.start(address.into(), true)?;
+
+        i2c.read_no_start_no_stop(count)?;
+        let [len] = count.map(usize::from);
+
+        let Some(data) = buf.get_mut(..len).filter(|_| len <= BLOCK_MAX) else {
+            i2c.stop()?;
+            return Err(Error::UnsupportedConfiguration);
+        };
+
+        for part in [data, &mut *pec] {
+            if !part.is_empty() {
+                i2c.continue_read();
+                i2c.read_no_start_no_stop(part)?;
+            }
+        }
+        i2c.stop()?;
+
+        Ok(len)
+    }
+
+    fn write(&mut self, address: u8, bytes: &[&[u8]]) -> Result<()> {
+        let mut frame = [0; FRAME_MAX];
+        let frame = self.frame(address, bytes, &mut frame)?;
+        self.i2c.write(address, frame)
+    }
+
+    fn write_read(&mut self, address: u8, write: &[u8], data: &mut [u8]) -> Result<()> {
+        let mut buf = [0; 3];
+        let len = self.read_len(data.len());
+        let read = buf.get_mut(..len).ok_or(Error::UnsupportedConfiguration)?;
+        self.i2c.write_read(address, write, read)?;
+        self.check(address, write, read)?;
+
+        for (d, r) in data.iter_mut().zip(read.iter()) {
+            *d = *r;
+        }
+        Ok(())
+    }
+}
+
+impl Smbus<'_, Async> {
+    /// Send a single byte, without command code
+    pub async fn send_byte(&mut self, address: u8, byte: u8) -> Result<()> {
+        self.write(address, &[&[byte]]).await
+    }
+
+    /// Receive a single byte, without command code
+    pub async fn receive_byte(&mut self, address: u8) -> Result<u8> {
+        let mut buf = [0; 2];
+        let len = self.read_len(1);
+        let read = buf.get_mut(..len).ok_or(Error::UnsupportedConfiguration)?;
+        self.i2c.read(address, read).await?;
+        self.check(address, &[], read)?;
+        let [byte, _] = buf;
+        Ok(byte)
+    }
+
+    /// Write a byte to register `command`
+    pub async fn write_byte(&mut self, address: u8, command: u8, byte: u8) -> Result<()> {
+        self.write(address, &[&[command, byte]]).await
+    }
+
+    /// Read a byte from register `command`
+    pub async fn read_byte(&mut self, address: u8, command: u8) -> Result<u8> {
+        let mut data = [0; 1];
+        self.write_read(address, &[command], &mut data).await?;
+        let [byte] = data;
+        Ok(byte)
+    }
+
+    /// Write a little-endian word to register `command`
+    pub async fn write_word(&mut self, address: u8, command: u8, word: u16) -> Result<()> {
+        self.write(address, &[&[command], &word.to_le_bytes()]).await
+    }
+
+    /// Read a little-endian word from register `command`
+    pub async fn read_word(&mut self, address: u8, command: u8) -> Result<u16> {
+        let mut data = [0; 2];
+        self.write_read(address, &[command], &mut data).await?;
+        Ok(u16::from_le_bytes(data))
+    }
+
+    /// Write `word` to register `command` and read back the word of the reply
+    pub async fn process_call(&mut self, address: u8, command: u8, word: u16) -> Result<u16> {
+        let [lo, hi] = word.to_le_bytes();
+        let mut data = [0; 2];
+        self.write_read(address, &[command, lo, hi], &mut data).await?;
+        Ok(u16::from_le_bytes(data))
+    }
+
+    /// Write a block of at most [`BLOCK_MAX`] bytes to register `command`
+    pub async fn block_write(&mut self, address: u8, command: u8, data: &[u8]) -> Result<()> {
+        if data.len() > BLOCK_MAX {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        self.write(address, &[&[command, data.len() as u8], data]).await
+    }
+
+    /// Read a block from register `command` into `buf`, returns the number of bytes read
+    ///
+    /// The transaction fails if the device sends more than [`BLOCK_MAX`] bytes, or more than
+    /// fit in `buf`.
+    pub async fn block_read(&mut self, address: u8, command: u8, buf: &mut [u8]) -> Result<usize> {
+        let i2c = &mut self.i2c;
+
+        // If any step fails, dropping the guard has the interrupt handler send the STOP
+        let guard = i2c.start(address.into(), false, None).await?;
+        i2c.write_no_start_no_stop(&[command]).await?;
+        let guard = i2c.start(address.into(), true, Some(guard)).await?;
+
+        let mut count = [0];
+        i2c.read_no_start_no_stop(&mut count).await?;
+        let [len] = count.map(usize::from);
+
+        let Some(data) = buf.get_mut(..len).filter(|_| len <= BLOCK_MAX) else {
+            i2c.stop()?.await?;
+            guard.defuse();
+            return Err(Error::UnsupportedConfiguration);
+        };
+
+        let mut pec = [0; 1];
+        let pec = pec.get_mut(..usize::from(self.pec)).unwrap_or_default();
+        for part in [&mut *data, &mut *pec] {
+            if !part.is_empty() {
+                i2c.continue_read();
+                i2c.read_no_start_no_stop(part).await?;
+            }
+        }
+        i2c.stop()?.await?;
+        guard.defuse();
+
+        if self.pec {
+            let mut crc = crc8(0, &[address << 1, command, (address << 1) | 1]);
+            crc = crc8(crc, &count);
+            crc = crc8(crc, data);
+            if pec != [crc] {
+                return Err(TransferError::PecMismatch.into());
+            }
+        }
+
+        Ok(len)
+    }
+
+    async fn write(&mut self, address: u8, bytes: &[&[u8]]) -> Result<()> {
+        let mut frame = [0; FRAME_MAX];
+        let frame = self.frame(address, bytes, &mut frame)?;
+        self.i2c.write(address, frame).await
+    }
+
+    async fn write_read(&mut self, address: u8, write: &[u8], data: &mut [u8]) -> Result<()> {
+        let mut buf = [0; 3];
+        let len = self.read_len(data.len());
+        let read = buf.get_mut(..len).ok_or(Error::UnsupportedConfiguration)?;
+        self.i2c.write_read(address, write, read).await?;
+        self.check(address, write, read)?;
+
+        for (d, r) in data.iter_mut().zip(read.iter()) {
+            *d = *r;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc8_check_value() {
+        // CRC-8/SMBUS check value
+        assert_eq!(crc8(0, b"123456789"), 0xf4);
+    }
+
+    #[test]
+    fn crc8_is_incremental() {
+        assert_eq!(crc8(crc8(0, b"1234"), b"56789"), crc8(0, b"123456789"));
+    }
+}
@@ -0,0 +1,962 @@
+//! Inter-IC Sound (I2S) driver.
+//!
+//! A flexcomm moves I2S data in one direction, see [`I2sTx`] and [`I2sRx`]. Full-duplex streams
+//! take a pair of flexcomms, see [`I2sDuplex`]: the receiver runs from the SCK and WS of the
+//! transmitter, routed through the shared signal sets of SYSCTL1, so both streams stay
+//! sample-synchronous.
+//!
+//! Either side of a link can drive SCK and WS. The `new_slave` constructors run from the clocks of
+//! another device, e.g. a host SoC: the transmitter only starts once its FIFO is full, and
+//! starts on a frame boundary.
+//!
+//! Every stream starts on the first transfer and runs on between transfers. After an underrun, an
+//! overrun or a frame error, it is stopped and its FIFO emptied, so the next transfer primes it
+//! again and restarts in step with the frames.
+//!
+//! Exact audio sample rates need the audio PLL, see [`crate::clocks::enable_audio_pll`], as
+//! function clock, see [`Config::clock`]. Codecs asking for a master clock get it from [`Mclk`].
+//!
+//! Samples are moved by DMA as 32-bit FIFO words. Up to 16 data bits, a word holds a whole stereo
+//! frame, left channel in the low half. Above, each channel takes its own word, left first.
+
+use core::marker::PhantomData;
+
+use embassy_futures::join::join;
+use embassy_hal_internal::{Peri, PeripheralType};
+use paste::paste;
+
+use crate::dma;
+use crate::dma::LinkedDescriptor;
+use crate::dma::channel::Channel;
+use crate::dma::transfer::{Transfer, TransferOptions, Width};
+use crate::flexcomm::{Clock, FlexcommRef};
+use crate::gpio::GpioPin as Pin;
+use crate::iopctl::{DriveMode, DriveStrength, Inverter, IopctlPin, Pull, SlewRate};
+
+/// I2S errors
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The requested configuration is not supported, e.g. a sample rate out of reach of the clock
+    UnsupportedConfiguration,
+    /// TX FIFO ran empty, zeros were sent in place of the missing samples
+    Underrun,
+    /// RX FIFO overflowed, received samples were lost
+    Overrun,
+    /// Clock slave saw WS change at a point not matching the configured frame
+    FrameError,
+}
+
+/// shorthand for -> `Result<T>`
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Number of linked descriptors chained behind each DMA channel descriptor.
+const DMA_LINKED_DESCRIPTORS: usize = 7;
+
+/// STAT.SLVFRMERR, write-only in the PAC although it can be read.
+const STAT_SLVFRMERR: u32 = 1 << 1;
+
+/// Words moved by a single run of chained DMA descriptors, longer transfers take several runs.
+const DMA_RUN_LEN: usize = dma::MAX_TRANSFER_COUNT * (DMA_LINKED_DESCRIPTORS + 1);
+
+/// Function clock of the flexcomm, SCK is divided from it
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FunctionClock {
+    /// FFRO, 48 MHz
+    #[default]
+    Ffro,
+    /// Audio PLL clock, which must be enabled first
+    AudioPll,
+}
+
+impl FunctionClock {
+    fn frequency(self) -> Result<u32> {
+        match self {
+            FunctionClock::Ffro => Ok(48_000_000),
+            FunctionClock::AudioPll => crate::clocks::audio_pll_frequency().ok_or(Error::UnsupportedConfiguration),
+        }
+    }
+}
+
+impl From<FunctionClock> for Clock {
+    fn from(clock: FunctionClock) -> Self {
+        match clock {
+            FunctionClock::Ffro => Clock::Ffro,
+            FunctionClock::AudioPll => Clock::AudioPll,
+        }
+    }
+}
+
+/// Frame format
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Format {
+    /// Philips I2S: WS low for the left channel, data one SCK after each WS edge
+    #[default]
+    Classic,
+    /// DSP: WS high for the first half of the frame, data starting with the WS edge
+    DspWs50,
+    /// DSP: WS high for one SCK at the start of the frame
+    DspWs1Clock,
+    /// DSP: WS high for the first sample of the frame
+    DspWs1Data,
+}
+
+/// Channels in a frame
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Channels {
+    /// Single channel, sent in the left slot
+    Mono,
+    /// Left and right channels
+    #[default]
+    Stereo,
+}
+
+/// I2S configuration
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    /// Frame format
+    pub format: Format,
+    /// Bits in a sample, 4 to 32
+    pub data_bits: u8,
+    /// Channels in a frame
+    pub channels: Channels,
+    /// Frames per second, rounded to the closest rate the function clock divides to
+    ///
+    /// Unused by clock slaves, which follow the frames of the clock master.
+    pub sample_rate: u32,
+    /// Function clock, which also runs the FIFOs of clock slaves
+    pub clock: FunctionClock,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            format: Format::Classic,
+            data_bits: 16,
+            channels: Channels::Stereo,
+            sample_rate: 48_000,
+            clock: FunctionClock::Ffro,
+        }
+    }
+}
+
+impl Config {
+    /// SCK periods in a frame
+    fn frame_len(&self) -> u32 {
+        let slots = match (self.format, self.channels) {
+            // WS toggles halfway through the frame, the right slot is there even when unused
+            (Format::Classic, _) | (_, Channels::Stereo) => 2,
+            (_, Channels::Mono) => 1,
+        };
+
+        u32::from(self.data_bits) * slots
+    }
+}
+
+/// DIV value producing SCK for `sample_rate` frames of `frame_len` bits from `clock_hz`
+fn divider(clock_hz: u32, sample_rate: u32, frame_len: u32) -> Option<u16> {
+    let sck = sample_rate
+        .checked_mul(frame_len)
+        .filter(|&sck| sck != 0 && sck <= clock_hz)?;
+    let div = (clock_hz + sck / 2) / sck;
+
+    // DIV is minus 1 encoded
+    u16::try_from(div - 1).ok().filter(|&div| div < 1 << 12)
+}
+
+fn apply_config(regs: &'static crate::pac::i2s0::RegisterBlock, config: &Config, master: bool) -> Result<()> {
+    if !(4..=32).contains(&config.data_bits) {
+        return Err(Error::UnsupportedConfiguration);
+    }
+
+    let frame_len = config.frame_len();
+
+    if master {
+        let div =
+            divider(config.clock.frequency()?, config.sample_rate, frame_len).ok_or(Error::UnsupportedConfiguration)?;
+        // SAFETY: only unsafe due to .bits usage, value checked against the field width
+        regs.div().write(|w| unsafe { w.div().bits(div) });
+    }
+
+    // SAFETY: only unsafe due to .bits usage, frame_len is at most 64
+    regs.cfg2()
+        .write(|w| unsafe { w.framelen().bits(frame_len as u16 - 1).position().bits(0) });
+
+    regs.cfg1().write(|w| {
+        let w = match config.format {
+            Format::Classic => w.mode().classic_mode(),
+            Format::DspWs50 => w.mode().dsp_mode_ws_50_dutycycle(),
+            Format::DspWs1Clock => w.mode().dsp_mode_ws_1_clock(),
+            Format::DspWs1Data => w.mode().dsp_mode_ws_1_data(),
+        };
+
+        let w = match config.channels {
+            Channels::Mono => w.onechannel().single_channel(),
+            Channels::Stereo => w.onechannel().dual_channel(),
+        };
+
+        let w = if master {
+            w.mstslvcfg().normal_master()
+        } else {
+            w.mstslvcfg().normal_slave_mode()
+        };
+
+        // SAFETY: only unsafe due to .bits usage, data_bits checked above
+        unsafe { w.datalen().bits(config.data_bits - 1) }
+    });
+
+    Ok(())
+}
+
+fn transfer_options() -> TransferOptions {
+    let mut options = TransferOptions::default();
+    options.width = Width::Bit32;
+    options
+}
+
+struct Info {
+    regs: &'static crate::pac::i2s0::RegisterBlock,
+    index: u8,
+}
+
+impl Info {
+    fn is_running(&self) -> bool {
+        self.regs.cfg1().read().mainenable().is_enabled()
+    }
+
+    /// Start the serializer, from a clean error state.
+    fn start(&self) {
+        self.regs
+            .fifostat()
+            .modify(|_, w| w.txerr().set_bit().rxerr().set_bit());
+        self.regs.stat().write(|w| w.slvfrmerr().set_bit());
+        self.regs.cfg1().modify(|_, w| w.mainenable().enabled());
+    }
+
+    /// Wait for `dma` to fill the TX FIFO, so the stream doesn't start with an underrun.
+    fn wait_primed(&self, dma: &Channel<'_>) {
+        while dma.is_active() && self.regs.fifostat().read().txnotfull().bit_is_set() {}
+    }
+
+    /// Stop the serializer and empty the FIFO, the next transfer primes it again.
+    fn stop(&self) {
+        self.regs.cfg1().modify(|_, w| w.mainenable().disabled());
+        self.regs
+            .fifocfg()
+            .modify(|_, w| w.emptytx().set_bit().emptyrx().set_bit());
+    }
+
+    fn check_errors(&self) -> Result<()> {
+        let stat = self.regs.fifostat().read();
+
+        if stat.txerr().bit_is_set() {
+            self.regs.fifostat().modify(|_, w| w.txerr().set_bit());
+            return Err(Error::Underrun);
+        }
+
+        if stat.rxerr().bit_is_set() {
+            self.regs.fifostat().modify(|_, w| w.rxerr().set_bit());
+            return Err(Error::Overrun);
+        }
+
+        if self.regs.stat().read().bits() & STAT_SLVFRMERR != 0 {
+            self.regs.stat().write(|w| w.slvfrmerr().set_bit());
+            return Err(Error::FrameError);
+        }
+
+        Ok(())
+    }
+
+    /// Check for errors, stopping the stream on the first one.
+    fn check_running(&self) -> Result<()> {
+        self.check_errors().inspect_err(|_| self.stop())
+    }
+
+    /// Start moving `data` to the TX FIFO.
+    ///
+    /// # Safety
+    ///
+    /// `data` must stay borrowed until the transfer completes or is dropped.
+    unsafe fn write_transfer<'d>(
+        &self,
+        dma: &'d Channel<'d>,
+        descriptors: &'d mut [LinkedDescriptor],
+        data: &[u32],
+    ) -> Result<Transfer<'d>> {
+        let fifowr = self.regs.fifowr().as_ptr() as *mut u8;
+
+        // SAFETY: guaranteed by the caller
+        unsafe {
+            Transfer::new_write_linked(
+                dma,
+                data.as_ptr() as *const u8,
+                size_of_val(data),
+                fifowr,
+                descriptors,
+                transfer_options(),
+            )
+        }
+        .map_err(|_| Error::UnsupportedConfiguration)
+    }
+
+    /// Start moving the RX FIFO to `data`.
+    ///
+    /// # Safety
+    ///
+    /// `data` must stay borrowed until the transfer completes or is dropped.
+    unsafe fn read_transfer<'d>(
+        &self,
+        dma: &'d Channel<'d>,
+        descriptors: &'d mut [LinkedDescriptor],
+        data: &mut [u32],
+    ) -> Result<Transfer<'d>> {
+        let fiford = self.regs.fiford().as_ptr() as *const u8;
+
+        // SAFETY: guaranteed by the caller
+        unsafe {
+            Transfer::new_read_linked(
+                dma,
+                fiford,
+                data.as_mut_ptr() as *mut u8,
+                size_of_val(data),
+                descriptors,
+                transfer_options(),
+            )
+        }
+        .map_err(|_| Error::UnsupportedConfiguration)
+    }
+}
+
+// SAFETY: safety for Send here is the same as the other accessors to
+// unsafe blocks: it must be done from a single executor context.
+//
+// This is a temporary workaround -- a better solution might be to
+// refactor Info to no longer maintain a reference to regs, but
+// instead look up the correct register set and then perform
+// operations within an unsafe block as we do for other peripherals
+unsafe impl Send for Info {}
+
+/// One direction of I2S on a flexcomm, with its DMA channel.
+struct Stream<'a> {
+    info: Info,
+    dma: Channel<'a>,
+    descriptors: [LinkedDescriptor; DMA_LINKED_DESCRIPTORS],
+    _flexcomm: FlexcommRef,
+}
+
+impl<'a> Stream<'a> {
+    fn new<T: Instance>(dma: Channel<'a>, transmit: bool, master: bool, config: &Config) -> Result<Self> {
+        let flexcomm = T::enable(config.clock.into());
+        if transmit {
+            T::into_i2s_transmit();
+        } else {
+            T::into_i2s_receive();
+        }
+
+        let info = T::info();
+        let regs = info.regs;
+
+        apply_config(regs, config, master)?;
+
+        if transmit {
+            // send zeros rather than repeating the last sample when running dry
+            regs.fifocfg().write(|w| {
+                w.enabletx()
+                    .enabled()
+                    .txi2se0()
+                    .zero()
+                    .dmatx()
+                    .enabled()
+                    .emptytx()
+                    .set_bit()
+            });
+        } else {
+            regs.fifocfg()
+                .write(|w| w.enablerx().enabled().dmarx().enabled().emptyrx().set_bit());
+        }
+
+        Ok(Self {
+            info,
+            dma,
+            descriptors: [LinkedDescriptor::new(); DMA_LINKED_DESCRIPTORS],
+            _flexcomm: flexcomm,
+        })
+    }
+}
+
+impl Drop for Stream<'_> {
+    fn drop(&mut self) {
+        let regs = self.info.regs;
+
+        regs.cfg1().modify(|_, w| w.mainenable().disabled());
+        regs.fifocfg().modify(|_, w| w.dmatx().disabled().dmarx().disabled());
+    }
+}
+
+/// I2S transmitter.
+pub struct I2sTx<'a> {
+    stream: Stream<'a>,
+}
+
+impl<'a> I2sTx<'a> {
+    fn new_inner<T: Instance>(
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        ws: Peri<'a, impl WsPin<T> + 'a>,
+        data: Peri<'a, impl DataPin<T> + 'a>,
+        dma: Peri<'a, impl TxDma<T>>,
+        config: Config,
+        master: bool,
+    ) -> Result<Self> {
+        sck.as_sck();
+        ws.as_ws();
+        data.as_data();
+
+        let dma = dma::Dma::reserve_channel(dma).ok_or(Error::UnsupportedConfiguration)?;
+
+        Ok(Self {
+            stream: Stream::new::<T>(dma, true, master, &config)?,
+        })
+    }
+
+    /// Create an I2S transmitter driving SCK and WS.
+    pub fn new<T: Instance>(
+        _inner: Peri<'a, T>,
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        ws: Peri<'a, impl WsPin<T> + 'a>,
+        data: Peri<'a, impl DataPin<T> + 'a>,
+        dma: Peri<'a, impl TxDma<T>>,
+        config: Config,
+    ) -> Result<Self> {
+        Self::new_inner(sck, ws, data, dma, config, true)
+    }
+
+    /// Create an I2S transmitter clocked by SCK and WS from another device.
+    pub fn new_slave<T: Instance>(
+        _inner: Peri<'a, T>,
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        ws: Peri<'a, impl WsPin<T> + 'a>,
+        data: Peri<'a, impl DataPin<T> + 'a>,
+        dma: Peri<'a, impl TxDma<T>>,
+        config: Config,
+    ) -> Result<Self> {
+        Self::new_inner(sck, ws, data, dma, config, false)
+    }
+
+    /// Send `data`, starting the stream on the first call.
+    ///
+    /// The stream runs on between calls: call again before the FIFO drains, or the next call
+    /// fails with [`Error::Underrun`] and the one after restarts the stream.
+    pub async fn write(&mut self, data: &[u32]) -> Result<()> {
+        let Stream {
+            info, dma, descriptors, ..
+        } = &mut self.stream;
+
+        for chunk in data.chunks(DMA_RUN_LEN) {
+            // SAFETY: `chunk` is borrowed until the transfer completes, which is aborted if this
+            // future is dropped.
+            let transfer = unsafe { info.write_transfer(dma, descriptors, chunk) }?;
+
+            if !info.is_running() {
+                info.wait_primed(dma);
+                info.start();
+            }
+
+            transfer.await;
+            info.check_running()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// I2S receiver.
+pub struct I2sRx<'a> {
+    stream: Stream<'a>,
+}
+
+impl<'a> I2sRx<'a> {
+    fn new_inner<T: Instance>(
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        ws: Peri<'a, impl WsPin<T> + 'a>,
+        data: Peri<'a, impl DataPin<T> + 'a>,
+        dma: Peri<'a, impl RxDma<T>>,
+        config: Config,
+        master: bool,
+    ) -> Result<Self> {
+        sck.as_sck();
+        ws.as_ws();
+        data.as_data();
+
+        let dma = dma::Dma::reserve_channel(dma).ok_or(Error::UnsupportedConfiguration)?;
+
+        Ok(Self {
+            stream: Stream::new::<T>(dma, false, master, &config)?,
+        })
+    }
+
+    /// Create an I2S receiver driving SCK and WS.
+    pub fn new<T: Instance>(
+        _inner: Peri<'a, T>,
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        ws: Peri<'a, impl WsPin<T> + 'a>,
+        data: Peri<'a, impl DataPin<T> + 'a>,
+        dma: Peri<'a, impl RxDma<T>>,
+        config: Config,
+    ) -> Result<Self> {
+        Self::new_inner(sck, ws, data, dma, config, true)
+    }
+
+    /// Create an I2S receiver clocked by SCK and WS from another device.
+    pub fn new_slave<T: Instance>(
+        _inner: Peri<'a, T>,
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        ws: Peri<'a, impl WsPin<T> + 'a>,
+        data: Peri<'a, impl DataPin<T> + 'a>,
+        dma: Peri<'a, impl RxDma<T>>,
+        config: Config,
+    ) -> Result<Self> {
+        Self::new_inner(sck, ws, data, dma, config, false)
+    }
+
+    /// Receive into `data`, starting the stream on the first call.
+    ///
+    /// The stream runs on between calls: call again before the FIFO fills up, or the next call
+    /// fails with [`Error::Overrun`] and the one after restarts the stream.
+    pub async fn read(&mut self, data: &mut [u32]) -> Result<()> {
+        let Stream {
+            info, dma, descriptors, ..
+        } = &mut self.stream;
+
+        for chunk in data.chunks_mut(DMA_RUN_LEN) {
+            // SAFETY: `chunk` is borrowed until the transfer completes, which is aborted if this
+            // future is dropped.
+            let transfer = unsafe { info.read_transfer(dma, descriptors, chunk) }?;
+
+            if !info.is_running() {
+                info.start();
+            }
+
+            transfer.await;
+            info.check_running()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Full-duplex I2S on a pair of flexcomms sharing one set of clocks.
+///
+/// The transmitting flexcomm drives SCK and WS, or takes them from another device for
+/// [`I2sDuplex::new_slave`], and the receiving one takes them from the shared signal set 0, which
+/// must not be used for anything else. Each received frame is the one clocked
+/// in while the frame at the same position of the transmitted buffer goes out.
+pub struct I2sDuplex<'a> {
+    tx: Stream<'a>,
+    rx: Stream<'a>,
+}
+
+impl<'a> I2sDuplex<'a> {
+    fn new_inner<T: Instance, R: Instance>(
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        ws: Peri<'a, impl WsPin<T> + 'a>,
+        tx_data: Peri<'a, impl DataPin<T> + 'a>,
+        rx_data: Peri<'a, impl DataPin<R> + 'a>,
+        tx_dma: Peri<'a, impl TxDma<T>>,
+        rx_dma: Peri<'a, impl RxDma<R>>,
+        config: Config,
+        master: bool,
+    ) -> Result<Self> {
+        sck.as_sck();
+        ws.as_ws();
+        tx_data.as_data();
+        rx_data.as_data();
+
+        let tx_dma = dma::Dma::reserve_channel(tx_dma).ok_or(Error::UnsupportedConfiguration)?;
+        let rx_dma = dma::Dma::reserve_channel(rx_dma).ok_or(Error::UnsupportedConfiguration)?;
+
+        let tx = Stream::new::<T>(tx_dma, true, master, &config)?;
+        let rx = Stream::new::<R>(rx_dma, false, false, &config)?;
+
+        // SAFETY: safe from single executor, shared set 0 is owned by this driver
+        let sysctl1 = unsafe { crate::pac::Sysctl1::steal() };
+        let tx_index = tx.info.index;
+        sysctl1
+            .sharedctrlset(0)
+            .write(|w| w.sharedscksel().bits(tx_index).sharedwssel().bits(tx_index));
+        sysctl1.fcctrlsel(usize::from(rx.info.index)).write(|w| {
+            w.sckinsel()
+                .shared_set0_i2s_signals()
+                .wsinsel()
+                .shared_set0_i2s_signals()
+        });
+
+        Ok(Self { tx, rx })
+    }
+
+    /// Create a full-duplex I2S pair, `T` transmitting and driving the clocks, `R` receiving.
+    pub fn new<T: Instance, R: Instance>(
+        _tx_inner: Peri<'a, T>,
+        _rx_inner: Peri<'a, R>,
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        ws: Peri<'a, impl WsPin<T> + 'a>,
+        tx_data: Peri<'a, impl DataPin<T> + 'a>,
+        rx_data: Peri<'a, impl DataPin<R> + 'a>,
+        tx_dma: Peri<'a, impl TxDma<T>>,
+        rx_dma: Peri<'a, impl RxDma<R>>,
+        config: Config,
+    ) -> Result<Self> {
+        Self::new_inner(sck, ws, tx_data, rx_data, tx_dma, rx_dma, config, true)
+    }
+
+    /// Create a full-duplex I2S pair clocked by SCK and WS from another device, on the pins of
+    /// `T` transmitting, `R` receiving.
+    pub fn new_slave<T: Instance, R: Instance>(
+        _tx_inner: Peri<'a, T>,
+        _rx_inner: Peri<'a, R>,
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        ws: Peri<'a, impl WsPin<T> + 'a>,
+        tx_data: Peri<'a, impl DataPin<T> + 'a>,
+        rx_data: Peri<'a, impl DataPin<R> + 'a>,
+        tx_dma: Peri<'a, impl TxDma<T>>,
+        rx_dma: Peri<'a, impl RxDma<R>>,
+        config: Config,
+    ) -> Result<Self> {
+        Self::new_inner(sck, ws, tx_data, rx_data, tx_dma, rx_dma, config, false)
+    }
+
+    /// Send `tx` while receiving into `rx`, starting both streams on the first call.
+    ///
+    /// Both buffers must have the same length. The streams run on between calls: call again
+    /// before the FIFOs drain or fill up, or the next call fails with [`Error::Underrun`] or
+    /// [`Error::Overrun`] and the one after restarts both streams.
+    pub async fn transfer(&mut self, tx: &[u32], rx: &mut [u32]) -> Result<()> {
+        if tx.len() != rx.len() {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let Stream {
+            info: tx_info,
+            dma: tx_dma,
+            descriptors: tx_descriptors,
+            ..
+        } = &mut self.tx;
+        let Stream {
+            info: rx_info,
+            dma: rx_dma,
+            descriptors: rx_descriptors,
+            ..
+        } = &mut self.rx;
+
+        for (tx, rx) in tx.chunks(DMA_RUN_LEN).zip(rx.chunks_mut(DMA_RUN_LEN)) {
+            // SAFETY: `tx` and `rx` are borrowed until the transfers complete, which are aborted
+            // if this future is dropped.
+            let rx_transfer = unsafe { rx_info.read_transfer(rx_dma, rx_descriptors, rx) }?;
+            let tx_transfer = unsafe { tx_info.write_transfer(tx_dma, tx_descriptors, tx) }?;
+
+            if !tx_info.is_running() {
+                tx_info.wait_primed(tx_dma);
+                // the receiver waits for the first WS edge, start it first to catch the first frame
+                rx_info.start();
+                tx_info.start();
+            }
+
+            join(rx_transfer, tx_transfer).await;
+
+            // restart both streams together, so they stay in step
+            if let Err(e) = tx_info.check_errors().and(rx_info.check_errors()) {
+                tx_info.stop();
+                rx_info.stop();
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for I2sDuplex<'_> {
+    fn drop(&mut self) {
+        // SAFETY: safe from single executor
+        let sysctl1 = unsafe { crate::pac::Sysctl1::steal() };
+        sysctl1.fcctrlsel(usize::from(self.rx.info.index)).reset();
+    }
+}
+
+/// MCLK sources
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MclkSource {
+    /// FFRO, 48 MHz
+    Ffro,
+    /// Audio PLL clock, which must be enabled first
+    AudioPll,
+}
+
+/// Master clock output, for codecs that need one
+///
+/// MCLK is shared by all flexcomms. For instance, the divided by 2 24.576 MHz audio PLL clock of
+/// [`AudioPllConfig::audio_24_576mhz`](crate::clocks::AudioPllConfig::audio_24_576mhz) is the
+/// 256 × Fs master clock of 48 kHz streams.
+pub struct Mclk<'a> {
+    frequency: u32,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> Mclk<'a> {
+    /// Drive MCLK on `pin` at `frequency` (Hz), which must divide `source` by 1 to 256.
+    pub fn new(_pin: Peri<'a, impl MclkPin + 'a>, source: MclkSource, frequency: u32) -> Result<Self> {
+        let source_hz = match source {
+            MclkSource::Ffro => 48_000_000,
+            MclkSource::AudioPll => crate::clocks::audio_pll_frequency().ok_or(Error::UnsupportedConfiguration)?,
+        };
+
+        // MCLK is only as exact as the clock it comes from, don't round
+        let div = Some(frequency)
+            .filter(|&f| f != 0 && source_hz.is_multiple_of(f))
+            .and_then(|f| u8::try_from(source_hz / f - 1).ok())
+            .ok_or(Error::UnsupportedConfiguration)?;
+
+        _pin.as_mclk();
+
+        // SAFETY: safe from single executor, MCLK is owned by this driver
+        let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
+        let sysctl1 = unsafe { crate::pac::Sysctl1::steal() };
+
+        clkctl1.audiomclksel().write(|w| match source {
+            MclkSource::Ffro => w.sel().ffro_clk(),
+            MclkSource::AudioPll => w.sel().audio_pll_clk(),
+        });
+
+        // SAFETY: only unsafe due to .bits usage
+        clkctl1
+            .audiomclkdiv()
+            .modify(|_, w| unsafe { w.div().bits(div) }.halt().clear_bit());
+        while clkctl1.audiomclkdiv().read().reqflag().bit_is_set() {}
+
+        sysctl1.mclkpindir().write(|w| w.mclkpindir().output_direction());
+
+        Ok(Self {
+            frequency,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// MCLK frequency (Hz)
+    pub fn frequency(&self) -> u32 {
+        self.frequency
+    }
+}
+
+impl Drop for Mclk<'_> {
+    fn drop(&mut self) {
+        // SAFETY: safe from single executor
+        let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
+        let sysctl1 = unsafe { crate::pac::Sysctl1::steal() };
+
+        sysctl1.mclkpindir().write(|w| w.mclkpindir().input_direction());
+        clkctl1.audiomclksel().write(|w| w.sel().none());
+    }
+}
+
+trait SealedInstance {
+    fn info() -> Info;
+}
+
+/// I2S instance trait.
+#[allow(private_bounds)]
+pub trait Instance:
+    crate::flexcomm::IntoI2sTransmit + crate::flexcomm::IntoI2sReceive + SealedInstance + PeripheralType + 'static + Send
+{
+}
+
+macro_rules! impl_instance {
+    ($($n:expr),*) => {
+        $(
+            paste!{
+                impl SealedInstance for crate::peripherals::[<FLEXCOMM $n>] {
+                    #[inline]
+                    fn info() -> Info {
+                        Info {
+                            regs: unsafe { &*crate::pac::[<I2s $n>]::ptr() },
+                            index: $n,
+                        }
+                    }
+                }
+
+                impl Instance for crate::peripherals::[<FLEXCOMM $n>] {}
+            }
+        )*
+    }
+}
+
+impl_instance!(0, 1, 2, 3, 4, 5, 6, 7);
+
+mod sealed {
+    /// Seal a trait
+    pub trait Sealed {}
+}
+
+impl<T: Pin> sealed::Sealed for T {}
+
+/// IO configuration trait for I2S bit clock
+pub trait SckPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for I2S bit clock usage.
+    fn as_sck(&self);
+}
+
+/// IO configuration trait for I2S word select
+pub trait WsPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for I2S word select usage.
+    fn as_ws(&self);
+}
+
+/// IO configuration trait for I2S data
+pub trait DataPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for I2S data usage.
+    fn as_data(&self);
+}
+
+/// IO configuration trait for MCLK
+pub trait MclkPin: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for MCLK usage.
+    fn as_mclk(&self);
+}
+
+impl MclkPin for crate::peripherals::PIO1_10 {
+    fn as_mclk(&self) {
+        self.set_function(crate::iopctl::Function::F5)
+            .set_pull(Pull::None)
+            .enable_input_buffer()
+            .set_slew_rate(SlewRate::Standard)
+            .set_drive_strength(DriveStrength::Normal)
+            .disable_analog_multiplex()
+            .set_drive_mode(DriveMode::PushPull)
+            .set_input_inverter(Inverter::Disabled);
+    }
+}
+
+macro_rules! impl_pin_trait {
+    ($fcn:ident, $mode:ident, $($pin:ident, $fn:ident),*) => {
+        paste! {
+            $(
+                impl [<$mode:camel Pin>]<crate::peripherals::$fcn> for crate::peripherals::$pin {
+                    fn [<as_ $mode>](&self) {
+                        // UM11147 table 530 pg 518
+                        self.set_function(crate::iopctl::Function::$fn)
+                            .set_pull(Pull::None)
+                            .enable_input_buffer()
+                            .set_slew_rate(SlewRate::Standard)
+                            .set_drive_strength(DriveStrength::Normal)
+                            .disable_analog_multiplex()
+                            .set_drive_mode(DriveMode::PushPull)
+                            .set_input_inverter(Inverter::Disabled);
+                    }
+                }
+            )*
+        }
+    }
+}
+
+// FLEXCOMM0
+impl_pin_trait!(FLEXCOMM0, sck, PIO0_0, F1, PIO3_0, F5);
+impl_pin_trait!(FLEXCOMM0, ws, PIO0_1, F1, PIO3_1, F5);
+impl_pin_trait!(FLEXCOMM0, data, PIO0_2, F1, PIO3_2, F5);
+
+// FLEXCOMM1
+impl_pin_trait!(FLEXCOMM1, sck, PIO0_7, F1, PIO7_25, F1);
+impl_pin_trait!(FLEXCOMM1, ws, PIO0_8, F1, PIO7_26, F1);
+impl_pin_trait!(FLEXCOMM1, data, PIO0_9, F1, PIO7_28, F1);
+
+// FLEXCOMM2
+impl_pin_trait!(FLEXCOMM2, sck, PIO0_14, F1, PIO7_29, F5);
+impl_pin_trait!(FLEXCOMM2, ws, PIO0_15, F1, PIO7_30, F5);
+impl_pin_trait!(FLEXCOMM2, data, PIO0_16, F1, PIO7_31, F5);
+
+// FLEXCOMM3
+impl_pin_trait!(FLEXCOMM3, sck, PIO0_21, F1);
+impl_pin_trait!(FLEXCOMM3, ws, PIO0_22, F1);
+impl_pin_trait!(FLEXCOMM3, data, PIO0_23, F1);
+
+// FLEXCOMM4
+impl_pin_trait!(FLEXCOMM4, sck, PIO0_28, F1);
+impl_pin_trait!(FLEXCOMM4, ws, PIO0_29, F1);
+impl_pin_trait!(FLEXCOMM4, data, PIO0_30, F1);
+
+// FLEXCOMM5
+impl_pin_trait!(FLEXCOMM5, sck, PIO1_3, F1, PIO3_15, F5);
+impl_pin_trait!(FLEXCOMM5, ws, PIO1_4, F1, PIO3_16, F5);
+impl_pin_trait!(FLEXCOMM5, data, PIO1_5, F1, PIO3_17, F5);
+
+// FLEXCOMM6
+impl_pin_trait!(FLEXCOMM6, sck, PIO3_25, F1);
+impl_pin_trait!(FLEXCOMM6, ws, PIO3_26, F1);
+impl_pin_trait!(FLEXCOMM6, data, PIO3_27, F1);
+
+// FLEXCOMM7
+impl_pin_trait!(FLEXCOMM7, sck, PIO4_0, F1);
+impl_pin_trait!(FLEXCOMM7, ws, PIO4_1, F1);
+impl_pin_trait!(FLEXCOMM7, data, PIO4_2, F1);
+
+/// I2S Tx DMA trait.
+#[allow(private_bounds)]
+pub trait TxDma<T: Instance>: dma::Instance {}
+
+/// I2S Rx DMA trait.
+#[allow(private_bounds)]
+pub trait RxDma<T: Instance>: dma::Instance {}
+
+macro_rules! impl_dma {
+    ($fcn:ident, $mode:ident, $dma:ident) => {
+        paste! {
+            impl [<$mode Dma>]<crate::peripherals::$fcn> for crate::peripherals::$dma {}
+        }
+    };
+}
+
+impl_dma!(FLEXCOMM0, Rx, DMA0_CH0);
+impl_dma!(FLEXCOMM0, Tx, DMA0_CH1);
+
+impl_dma!(FLEXCOMM1, Rx, DMA0_CH2);
+impl_dma!(FLEXCOMM1, Tx, DMA0_CH3);
+
+impl_dma!(FLEXCOMM2, Rx, DMA0_CH4);
+impl_dma!(FLEXCOMM2, Tx, DMA0_CH5);
+
+impl_dma!(FLEXCOMM3, Rx, DMA0_CH6);
+impl_dma!(FLEXCOMM3, Tx, DMA0_CH7);
+
+impl_dma!(FLEXCOMM4, Rx, DMA0_CH8);
+impl_dma!(FLEXCOMM4, Tx, DMA0_CH9);
+
+impl_dma!(FLEXCOMM5, Rx, DMA0_CH10);
+impl_dma!(FLEXCOMM5, Tx, DMA0_CH11);
+
+impl_dma!(FLEXCOMM6, Rx, DMA0_CH12);
+impl_dma!(FLEXCOMM6, Tx, DMA0_CH13);
+
+impl_dma!(FLEXCOMM7, Rx, DMA0_CH14);
+impl_dma!(FLEXCOMM7, Tx, DMA0_CH15);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn divider_rounds_to_closest_rate() {
+        // 48 MHz / (46875 Hz * 32) is exactly 32
+        assert_eq!(divider(48_000_000, 46_875, 32), Some(31));
+        // 48 MHz / (48 kHz * 32) is 31.25
+        assert_eq!(divider(48_000_000, 48_000, 32), Some(30));
+    }
+
+    #[test]
+    fn divider_rejects_unreachable_rates() {
+        assert_eq!(divider(48_000_000, 0, 32), None);
+        assert_eq!(divider(48_000_000, 1_000_000, 64), None);
+        assert_eq!(divider(48_000_000, 100, 32), None);
+    }
+}
@@ -0,0 +1,960 @@
+//! Implements the I3C controller, driving the bus
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_hal_internal::Peri;
+
+use super::{Error, FCLK_HZ, Info, Instance, InterruptHandler, Result, SclPin, SdaPin};
+use crate::interrupt;
+use crate::interrupt::typelevel::Interrupt;
+
+/// Address every target answers to
+const BROADCAST_ADDRESS: u8 = 0x7E;
+
+/// Broadcast Enable Events Command
+const CCC_ENEC: u8 = 0x00;
+/// Broadcast Disable Events Command
+const CCC_DISEC: u8 = 0x01;
+/// Broadcast Reset Dynamic Address Assignment
+const CCC_RSTDAA: u8 = 0x06;
+/// Direct Set Dynamic Address from Static Address
+const CCC_SETDASA: u8 = 0x87;
+/// Direct Get Provisioned ID
+const CCC_GETPID: u8 = 0x8D;
+/// Direct Get Bus Characteristics Register
+const CCC_GETBCR: u8 = 0x8E;
+/// Direct Get Device Characteristics Register
+const CCC_GETDCR: u8 = 0x8F;
+/// Direct commands are the broadcast ones with the top bit set
+const CCC_DIRECT: u8 = 0x80;
+/// ENEC/DISEC event: in-band interrupts
+const EVENT_INT: u8 = 1 << 0;
+/// ENEC/DISEC event: hot-join
+const EVENT_HJ: u8 = 1 << 3;
+
+/// Capacity of the device table
+pub const MAX_DEVICES: usize = 8;
+
+/// Lowest address usable as a dynamic address
+const FIRST_DYNAMIC_ADDRESS: u8 = 0x08;
+
+/// Bytes sent by each target during ENTDAA: provisioned ID, BCR and DCR
+const DAA_ID_LEN: usize = 8;
+
+/// Longest read the controller ends by itself, RDTERM is 8 bits wide
+const MAX_READ_LEN: usize = 255;
+
+// MSTATUS flags, with the same layout in MINTSET
+const SLVSTART: u32 = 1 << 8;
+const MCTRLDONE: u32 = 1 << 9;
+const COMPLETE: u32 = 1 << 10;
+const RXPEND: u32 = 1 << 11;
+const TXNOTFULL: u32 = 1 << 12;
+const IBIWON: u32 = 1 << 13;
+const ERRWARN: u32 = 1 << 15;
+
+/// Configuration for I3C Controller
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// SCL frequency of push-pull data, up to 12.5 MHz
+    pub push_pull_frequency: u32,
+    /// SCL frequency of open-drain phases: addresses arbitration, in-band interrupts and ENTDAA
+    pub open_drain_frequency: u32,
+    /// SCL frequency of messages to legacy I2C devices
+    pub i2c_frequency: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            push_pull_frequency: 12_000_000,
+            open_drain_frequency: 2_000_000,
+            i2c_frequency: 400_000,
+        }
+    }
+}
+
+/// Divisors of the function clock for SCL, as (PPBAUD, ODBAUD)
+///
+/// Push-pull SCL is high and low for PPBAUD + 1 clocks. Open-drain SCL is high as in push-pull, and
+/// low for ODBAUD + 1 times as long. Both round down the frequency.
+fn baud(clock_hz: u32, push_pull_hz: u32, open_drain_hz: u32) -> Option<(u8, u8)> {
+    if push_pull_hz == 0 || open_drain_hz == 0 || open_drain_hz > push_pull_hz {
+        return None;
+    }
+
+    let half_periods = clock_hz.div_ceil(2 * push_pull_hz).max(1);
+    let ppbaud = u8::try_from(half_periods - 1).ok().filter(|&b| b < 16)?;
+
+    let od_periods = clock_hz.div_ceil(half_periods * open_drain_hz).max(2);
+    let odbaud = u8::try_from(od_periods - 2).ok()?;
+
+    Some((ppbaud, odbaud))
+}
+
+/// MCONFIG timings
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Timings {
+    ppbaud: u8,
+    odbaud: u8,
+    odhpp: bool,
+    i2cbaud: u8,
+}
+
+/// Timings for `config`, on a bus with legacy I2C devices up to `i2c_max_hz`, if any
+///
+/// I2C devices filter the short push-pull high of open-drain phases, too short for them to decode
+/// the address header and stay quiet, so they get a symmetric open-drain SCL at their speed. I2C
+/// SCL is high and low for I2CBAUD + 1 times the open-drain low.
+fn timings(clock_hz: u32, config: &Config, i2c_max_hz: Option<u32>) -> Option<Timings> {
+    let (ppbaud, odbaud, odhpp) = match i2c_max_hz {
+        None => {
+            let (ppbaud, odbaud) = baud(clock_hz, config.push_pull_frequency, config.open_drain_frequency)?;
+            (ppbaud, odbaud, true)
+        }
+        Some(i2c_max_hz) => {
+            let (ppbaud, _) = baud(clock_hz, config.push_pull_frequency, config.open_drain_frequency)?;
+            let open_drain_hz = config.open_drain_frequency.min(i2c_max_hz);
+            if open_drain_hz == 0 {
+                return None;
+            }
+
+            let od_periods = clock_hz.div_ceil(2 * (u32::from(ppbaud) + 1) * open_drain_hz).max(1);
+            (ppbaud, u8::try_from(od_periods - 1).ok()?, false)
+        }
+    };
+
+    let i2c_hz = i2c_max_hz.map_or(config.i2c_frequency, |hz| hz.min(config.i2c_frequency));
+    if i2c_hz == 0 {
+        return None;
+    }
+
+    let od_low = (u64::from(ppbaud) + 1) * (u64::from(odbaud) + 1);
+    let i2c_periods = u64::from(clock_hz).div_ceil(2 * od_low * u64::from(i2c_hz)).max(1);
+    let i2cbaud = u8::try_from(i2c_periods - 1).ok().filter(|&b| b < 16)?;
+
+    Some(Timings {
+        ppbaud,
+        odbaud,
+        odhpp,
+        i2cbaud,
+    })
+}
+
+/// Whether `address` can't be assigned as a dynamic address
+///
+/// The low addresses and the I2C extended ones are reserved, as are the addresses a single bit
+/// error away from the broadcast address.
+fn is_reserved(address: u8) -> bool {
+    address < FIRST_DYNAMIC_ADDRESS || address >= 0x78 || (address ^ BROADCAST_ADDRESS).count_ones() == 1
+}
+
+/// Target known to the controller
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Device {
+    /// dynamic address assigned by the controller
+    pub address: u8,
+    /// static address the dynamic address was assigned from, `None` for targets found by ENTDAA
+    pub static_address: Option<u8>,
+    /// 48-bit provisioned ID
+    pub pid: u64,
+    /// Bus Characteristics Register
+    pub bcr: u8,
+    /// Device Characteristics Register
+    pub dcr: u8,
+}
+
+/// Legacy I2C device sharing the bus
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct I2cDevice {
+    /// static address of the device
+    pub address: u8,
+    /// Legacy Virtual Register, describing the I2C device to the I3C bus
+    pub lvr: u8,
+}
+
+impl I2cDevice {
+    /// Whether the device has the 50 ns spike filter ignoring I3C traffic, LVR index 0
+    pub fn has_spike_filter(&self) -> bool {
+        self.lvr >> 5 == 0
+    }
+
+    /// Highest SCL frequency of the device, Fast-mode Plus unless the LVR says Fast-mode only
+    pub fn max_frequency(&self) -> u32 {
+        if self.lvr & (1 << 4) != 0 { 400_000 } else { 1_000_000 }
+    }
+}
+
+/// Kind of in-band request raised by a target
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IbiKind {
+    /// target interrupt, with its payload if any
+    Interrupt,
+    /// target without a dynamic address asks to join the bus
+    HotJoin,
+    /// secondary controller asks for the bus, the request is acknowledged but not handed over
+    ControllerRequest,
+}
+
+/// In-band request received by the controller
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ibi {
+    /// kind of request
+    pub kind: IbiKind,
+    /// dynamic address of the target raising it
+    pub address: u8,
+    /// bytes of payload stored at the start of the buffer
+    pub len: usize,
+}
+
+/// use the I3C block as the bus controller
+pub struct I3cController<'a> {
+    info: Info,
+    config: Config,
+    devices: [Option<Device>; MAX_DEVICES],
+    i2c_devices: [Option<I2cDevice>; MAX_DEVICES],
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> I3cController<'a> {
+    /// use I3C with Pins scl, sda as the bus controller
+    pub fn new<T: Instance>(
+        _bus: Peri<'a, T>,
+        scl: Peri<'a, impl SclPin<T>>,
+        sda: Peri<'a, impl SdaPin<T>>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'a,
+        config: Config,
+    ) -> Result<Self> {
+        let timings = timings(FCLK_HZ, &config, None).ok_or(Error::UnsupportedConfiguration)?;
+
+        super::init::<T>();
+
+        scl.as_scl();
+        sda.as_sda();
+
+        let info = T::info();
+        let regs = info.regs;
+
+        let this = Self {
+            info,
+            config,
+            devices: [None; MAX_DEVICES],
+            i2c_devices: [None; MAX_DEVICES],
+            _phantom: PhantomData,
+        };
+        this.apply_timings(timings);
+
+        regs.mdatactrl().write(|w| {
+            // TX flags room for one more byte, RX flags any byte
+            unsafe { w.txtrig().bits(3).rxtrig().bits(0) }
+                .flushtb()
+                .set_bit()
+                .flushfb()
+                .set_bit()
+        });
+
+        regs.mintclr().write(|w| unsafe { w.bits(u32::MAX) });
+        regs.merrwarn().write(|w| unsafe { w.bits(u32::MAX) });
+
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        Ok(this)
+    }
+
+    fn apply_timings(&self, timings: Timings) {
+        // SAFETY: only unsafe due to .bits usage, values range checked by timings()
+        self.info.regs.mconfig().write(|w| {
+            unsafe {
+                w.ppbaud()
+                    .bits(timings.ppbaud)
+                    .odbaud()
+                    .bits(timings.odbaud)
+                    .i2cbaud()
+                    .bits(timings.i2cbaud)
+            }
+            .odhpp()
+            .bit(timings.odhpp)
+            .mstena()
+            .master_on()
+        });
+    }
+
+    /// Take the first error flagged by the hardware, clearing all of them
+    fn check_errors(&self) -> Result<()> {
+        let regs = self.info.regs;
+
+        if regs.mstatus().read().errwarn().bit_is_clear() {
+            return Ok(());
+        }
+
+        let errors = regs.merrwarn().read();
+        // SAFETY: writing ones only clears the flags just read
+        regs.merrwarn().write(|w| unsafe { w.bits(errors.bits()) });
+
+        if errors.nack().bit_is_set() || errors.wrabt().bit_is_set() {
+            Err(Error::Nack)
+        } else if errors.timeout().bit_is_set() {
+            Err(Error::Timeout)
+        } else if errors.term().bit_is_set() {
+            Err(Error::Terminated)
+        } else if errors.hpar().bit_is_set() || errors.hcrc().bit_is_set() {
+            Err(Error::Parity)
+        } else if errors.owrite().bit_is_set() {
+            Err(Error::Overrun)
+        } else if errors.oread().bit_is_set() {
+            Err(Error::Underrun)
+        } else {
+            Err(Error::Protocol)
+        }
+    }
+
+    /// Wait for any of the MSTATUS `flags`, returning the status
+    async fn wait_for(&self, flags: u32) -> Result<u32> {
+        let regs = self.info.regs;
+
+        poll_fn(|cx| {
+            self.info.waker.register(cx.waker());
+
+            if let Err(e) = self.check_errors() {
+                return Poll::Ready(Err(e));
+            }
+
+            let status = regs.mstatus().read().bits();
+            if status & flags != 0 {
+                return Poll::Ready(Ok(status));
+            }
+
+            // SAFETY: MINTSET mirrors the layout of MSTATUS
+            regs.mintset().write(|w| unsafe { w.bits(flags | ERRWARN) });
+
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Clear the sticky MSTATUS `flags`
+    fn clear(&self, flags: u32) {
+        // SAFETY: the sticky flags of MSTATUS are cleared by writing ones
+        self.info.regs.mstatus().write(|w| unsafe { w.bits(flags) });
+    }
+
+    /// Emit a START, or a repeated START, then the address header
+    ///
+    /// In-band interrupts winning the arbitration of the header are NACKed, the targets raise them
+    /// again once the bus is free.
+    async fn start(&self, address: u8, read_len: Option<u8>, i2c: bool) -> Result<()> {
+        let regs = self.info.regs;
+
+        loop {
+            // SAFETY: only unsafe due to .bits usage, addresses are 7 bits
+            regs.mctrl().write(|w| {
+                let w = unsafe { w.addr().bits(address & 0x7F).rdterm().bits(read_len.unwrap_or(0)) }
+                    .request()
+                    .emitstartaddr();
+                let w = if i2c { w.type_().i2c() } else { w.type_().i3c() };
+
+                w.ibiresp().nack().dir().bit(read_len.is_some())
+            });
+
+            let status = self.wait_for(MCTRLDONE).await?;
+            self.clear(MCTRLDONE);
+
+            if status & IBIWON != 0 {
+                self.clear(IBIWON);
+                continue;
+            }
+
+            if regs.mstatus().read().nacked().bit_is_set() {
+                return Err(Error::Nack);
+            }
+
+            return Ok(());
+        }
+    }
+
+    /// Emit a STOP, returning the bus to idle, dropping the FIFO contents of a `failed` message
+    fn finish(&self, failed: bool) {
+        let regs = self.info.regs;
+
+        if failed {
+            regs.mdatactrl()
+                .modify(|_, w| w.flushtb().set_bit().flushfb().set_bit());
+        }
+
+        if regs.mstatus().read().state().is_idle() {
+            return;
+        }
+
+        regs.mctrl().write(|w| w.request().emitstop());
+        while !regs.mstatus().read().state().is_idle() {}
+        self.clear(MCTRLDONE | COMPLETE);
+    }
+
+    /// Send `data` after the address header, ending the message
+    async fn write_bytes(&self, data: &[u8]) -> Result<()> {
+        self.write_message(data.iter().copied()).await
+    }
+
+    /// Send `bytes` as one message, END is only set with the last byte
+    async fn write_message(&self, bytes: impl Iterator<Item = u8>) -> Result<()> {
+        let regs = self.info.regs;
+        let mut bytes = bytes.peekable();
+
+        if bytes.peek().is_none() {
+            return Ok(());
+        }
+
+        while let Some(byte) = bytes.next() {
+            self.wait_for(TXNOTFULL).await?;
+
+            // SAFETY: only unsafe due to .bits usage
+            if bytes.peek().is_none() {
+                regs.mwdatabe().write(|w| unsafe { w.data().bits(byte) });
+            } else {
+                regs.mwdatab().write(|w| unsafe { w.data().bits(byte) });
+            }
+        }
+
+        self.wait_for(COMPLETE).await?;
+        self.clear(COMPLETE);
+
+        Ok(())
+    }
+
+    /// Receive `buf` after the address header, the message length was set by the START
+    async fn read_bytes(&self, buf: &mut [u8]) -> Result<()> {
+        let regs = self.info.regs;
+
+        for byte in buf.iter_mut() {
+            self.wait_for(RXPEND).await?;
+            *byte = regs.mrdatab().read().value().bits();
+        }
+
+        self.wait_for(COMPLETE).await?;
+        self.clear(COMPLETE);
+
+        Ok(())
+    }
+
+    async fn write_inner(&self, address: u8, data: &[u8], i2c: bool) -> Result<()> {
+        self.start(address, None, i2c).await?;
+        self.write_bytes(data).await
+    }
+
+    async fn read_inner(&self, address: u8, buf: &mut [u8], i2c: bool) -> Result<()> {
+        let len = u8::try_from(buf.len())
+            .ok()
+            .filter(|&len| len != 0)
+            .ok_or(Error::UnsupportedConfiguration)?;
+
+        self.start(address, Some(len), i2c).await?;
+        self.read_bytes(buf).await
+    }
+
+    /// Write `data` to the target at `address`
+    pub async fn write(&mut self, address: u8, data: &[u8]) -> Result<()> {
+        let result = self.write_inner(address, data, false).await;
+        self.finish(result.is_err());
+        result
+    }
+
+    /// Read `buf` from the target at `address`, 1 to 255 bytes
+    pub async fn read(&mut self, address: u8, buf: &mut [u8]) -> Result<()> {
+        let result = self.read_inner(address, buf, false).await;
+        self.finish(result.is_err());
+        result
+    }
+
+    /// Write `data` then read `buf` from the target at `address`, with a repeated START between
+    pub async fn write_read(&mut self, address: u8, data: &[u8], buf: &mut [u8]) -> Result<()> {
+        let result = match self.write_inner(address, data, false).await {
+            Ok(()) => self.read_inner(address, buf, false).await,
+            Err(e) => Err(e),
+        };
+        self.finish(result.is_err());
+        result
+    }
+
+    /// Register the legacy I2C device at `address`, described by its `lvr`
+    ///
+    /// The open-drain phases slow down to the speed of the slowest I2C device. Devices without a
+    /// spike filter would need all of the bus at I2C speed, out of reach of the push-pull timings,
+    /// and aren't supported.
+    pub fn add_i2c_device(&mut self, address: u8, lvr: u8) -> Result<()> {
+        let device = I2cDevice { address, lvr };
+        if address > 0x7F || is_reserved(address) || self.is_used(address) || !device.has_spike_filter() {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let i2c_max_hz = self
+            .i2c_devices()
+            .map(I2cDevice::max_frequency)
+            .chain([device.max_frequency()])
+            .min();
+        let timings = timings(FCLK_HZ, &self.config, i2c_max_hz).ok_or(Error::UnsupportedConfiguration)?;
+
+        let slot = self
+            .i2c_devices
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(Error::TableFull)?;
+        *slot = Some(device);
+
+        self.apply_timings(timings);
+
+        Ok(())
+    }
+
+    /// Legacy I2C devices registered on the bus
+    pub fn i2c_devices(&self) -> impl Iterator<Item = &I2cDevice> {
+        self.i2c_devices.iter().flatten()
+    }
+
+    /// Check `address` is a registered I2C device, the bus timings depend on it
+    fn check_i2c_device(&self, address: u8) -> Result<()> {
+        if self.i2c_devices().any(|device| device.address == address) {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedConfiguration)
+        }
+    }
+
+    /// Write `data` to the legacy I2C device at `address`
+    pub async fn i2c_write(&mut self, address: u8, data: &[u8]) -> Result<()> {
+        self.check_i2c_device(address)?;
+
+        let result = self.write_inner(address, data, true).await;
+        self.finish(result.is_err());
+        result
+    }
+
+    /// Read `buf` from the legacy I2C device at `address`, 1 to 255 bytes
+    pub async fn i2c_read(&mut self, address: u8, buf: &mut [u8]) -> Result<()> {
+        self.check_i2c_device(address)?;
+
+        let result = self.read_inner(address, buf, true).await;
+        self.finish(result.is_err());
+        result
+    }
+
+    /// Write `data` then read `buf` from the legacy I2C device at `address`, with a repeated START
+    /// between
+    pub async fn i2c_write_read(&mut self, address: u8, data: &[u8], buf: &mut [u8]) -> Result<()> {
+        self.check_i2c_device(address)?;
+
+        let result = match self.write_inner(address, data, true).await {
+            Ok(()) => self.read_inner(address, buf, true).await,
+            Err(e) => Err(e),
+        };
+        self.finish(result.is_err());
+        result
+    }
+
+    /// Send the broadcast common command code `ccc`, followed by `data`
+    pub async fn broadcast_ccc(&mut self, ccc: u8, data: &[u8]) -> Result<()> {
+        if ccc & CCC_DIRECT != 0 {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let result = async {
+            self.start(BROADCAST_ADDRESS, None, false).await?;
+            // the defining data belongs to the same message as the CCC
+            self.write_message(core::iter::once(ccc).chain(data.iter().copied()))
+                .await
+        }
+        .await;
+        self.finish(result.is_err());
+        result
+    }
+
+    /// Send the direct common command code `ccc`, writing `data` to the target at `address`
+    pub async fn direct_ccc_write(&mut self, ccc: u8, address: u8, data: &[u8]) -> Result<()> {
+        if ccc & CCC_DIRECT == 0 {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let result = async {
+            self.start(BROADCAST_ADDRESS, None, false).await?;
+            self.write_bytes(&[ccc]).await?;
+            self.write_inner(address, data, false).await
+        }
+        .await;
+        self.finish(result.is_err());
+        result
+    }
+
+    /// Send the direct common command code `ccc`, reading `buf` from the target at `address`
+    pub async fn direct_ccc_read(&mut self, ccc: u8, address: u8, buf: &mut [u8]) -> Result<()> {
+        if ccc & CCC_DIRECT == 0 {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let result = async {
+            self.start(BROADCAST_ADDRESS, None, false).await?;
+            self.write_bytes(&[ccc]).await?;
+            self.read_inner(address, buf, false).await
+        }
+        .await;
+        self.finish(result.is_err());
+        result
+    }
+
+    /// Allow in-band interrupts from the target at `address`, or from all targets for `None`
+    pub async fn enable_ibi(&mut self, address: Option<u8>) -> Result<()> {
+        match address {
+            Some(address) => {
+                self.direct_ccc_write(CCC_ENEC | CCC_DIRECT, address, &[EVENT_INT])
+                    .await
+            }
+            None => self.broadcast_ccc(CCC_ENEC, &[EVENT_INT]).await,
+        }
+    }
+
+    /// Forbid in-band interrupts from the target at `address`, or from all targets for `None`
+    pub async fn disable_ibi(&mut self, address: Option<u8>) -> Result<()> {
+        match address {
+            Some(address) => {
+                self.direct_ccc_write(CCC_DISEC | CCC_DIRECT, address, &[EVENT_INT])
+                    .await
+            }
+            None => self.broadcast_ccc(CCC_DISEC, &[EVENT_INT]).await,
+        }
+    }
+
+    /// Allow targets without a dynamic address to request one, or forbid it
+    pub async fn set_hot_join(&mut self, enabled: bool) -> Result<()> {
+        let ccc = if enabled { CCC_ENEC } else { CCC_DISEC };
+        self.broadcast_ccc(ccc, &[EVENT_HJ]).await
+    }
+
+    /// Targets assigned a dynamic address by this controller
+    pub fn devices(&self) -> impl Iterator<Item = &Device> {
+        self.devices.iter().flatten()
+    }
+
+    /// Whether `address` is used by a target of the device table, or by an I2C device
+    fn is_used(&self, address: u8) -> bool {
+        self.devices()
+            .any(|device| device.address == address || device.static_address == Some(address))
+            || self.i2c_devices().any(|device| device.address == address)
+    }
+
+    /// Lowest address free for a new target
+    fn free_address(&self) -> Option<u8> {
+        (FIRST_DYNAMIC_ADDRESS..0x78).find(|&address| !is_reserved(address) && !self.is_used(address))
+    }
+
+    fn has_room(&self) -> bool {
+        self.devices.iter().any(Option::is_none)
+    }
+
+    fn add_device(&mut self, device: Device) -> Result<()> {
+        let slot = self
+            .devices
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(Error::TableFull)?;
+        *slot = Some(device);
+
+        Ok(())
+    }
+
+    /// Make all targets drop their dynamic address, and empty the device table
+    ///
+    /// The I2C devices stay registered.
+    pub async fn reset_dynamic_addresses(&mut self) -> Result<()> {
+        self.broadcast_ccc(CCC_RSTDAA, &[]).await?;
+        self.devices = [None; MAX_DEVICES];
+
+        Ok(())
+    }
+
+    /// Assign a dynamic address to every target without one with ENTDAA, adding them to the device
+    /// table
+    ///
+    /// This is also the answer to a hot-join request. Returns the number of targets added.
+    pub async fn assign_dynamic_addresses(&mut self) -> Result<usize> {
+        let result = self.entdaa().await;
+        self.finish(result.is_err());
+        result
+    }
+
+    fn process_daa(&self) {
+        self.info.regs.mctrl().write(|w| w.request().processdaa().type_().i3c());
+    }
+
+    async fn entdaa(&mut self) -> Result<usize> {
+        let regs = self.info.regs;
+        let mut added = 0;
+        let mut id = [0u8; DAA_ID_LEN];
+        let mut len = 0;
+
+        self.process_daa();
+
+        loop {
+            let status = self.wait_for(RXPEND | MCTRLDONE | COMPLETE).await?;
+
+            while regs.mdatactrl().read().rxempty().bit_is_clear() {
+                let byte = regs.mrdatab().read().value().bits();
+                if let Some(slot) = id.get_mut(len) {
+                    *slot = byte;
+                    len += 1;
+                }
+            }
+
+            if status & COMPLETE != 0 {
+                self.clear(COMPLETE | MCTRLDONE);
+                return Ok(added);
+            }
+
+            if status & MCTRLDONE == 0 {
+                continue;
+            }
+            self.clear(MCTRLDONE);
+
+            // the controller waits between targets for the address to assign to the last one
+            let state = regs.mstatus().read();
+            if !state.state().is_daa() || state.between().bit_is_clear() {
+                return Ok(added);
+            }
+
+            let [pid @ .., bcr, dcr] = id;
+            if len != DAA_ID_LEN {
+                return Err(Error::Protocol);
+            }
+
+            if !self.has_room() {
+                return Err(Error::TableFull);
+            }
+            let address = self.free_address().ok_or(Error::TableFull)?;
+
+            // SAFETY: only unsafe due to .bits usage
+            regs.mwdatab().write(|w| unsafe { w.data().bits(address) });
+            self.add_device(Device {
+                address,
+                static_address: None,
+                pid: pid.iter().fold(0, |pid, &byte| (pid << 8) | u64::from(byte)),
+                bcr,
+                dcr,
+            })?;
+            added += 1;
+            len = 0;
+
+            self.process_daa();
+        }
+    }
+
+    /// Assign `address` to the target at `static_address` with SETDASA, adding it to the device
+    /// table
+    ///
+    /// If reading its PID, BCR or DCR fails afterwards, the target is still added with them
+    /// zeroed, since it already answers `address`.
+    pub async fn set_dynamic_address_from_static(&mut self, static_address: u8, address: u8) -> Result<()> {
+        if static_address > 0x7F || is_reserved(address) || self.is_used(address) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        if !self.has_room() {
+            return Err(Error::TableFull);
+        }
+
+        self.direct_ccc_write(CCC_SETDASA, static_address, &[address << 1])
+            .await?;
+
+        let mut pid = [0u8; 6];
+        let mut bcr = [0u8];
+        let mut dcr = [0u8];
+        let result = async {
+            self.direct_ccc_read(CCC_GETPID, address, &mut pid).await?;
+            self.direct_ccc_read(CCC_GETBCR, address, &mut bcr).await?;
+            self.direct_ccc_read(CCC_GETDCR, address, &mut dcr).await
+        }
+        .await;
+
+        // the target owns the address now, record it even if its characteristics are unknown so
+        // the address isn't handed out again
+        let [bcr] = bcr;
+        let [dcr] = dcr;
+        self.add_device(Device {
+            address,
+            static_address: Some(static_address),
+            pid: pid.iter().fold(0, |pid, &byte| (pid << 8) | u64::from(byte)),
+            bcr,
+            dcr,
+        })?;
+
+        result
+    }
+
+    /// Register up to 5 targets whose in-band interrupts all carry a payload, or all don't
+    ///
+    /// The controller acknowledges interrupts from unregistered targets too, and reads their
+    /// payload until the target ends it.
+    pub fn set_ibi_rules(&mut self, addresses: &[u8], payload: bool) -> Result<()> {
+        // the rules only keep the low 6 bits, the top one is shared by all addresses
+        let msb = addresses.first().map_or(0, |address| address & 0x40);
+        if addresses.len() > 5 || addresses.iter().any(|&address| address > 0x7F || address & 0x40 != msb) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let rules = addresses.iter().enumerate().fold(0u32, |rules, (i, &address)| {
+            rules | (u32::from(address & 0x3F) << (6 * i))
+        });
+
+        // SAFETY: only unsafe due to .bits usage, layout built above
+        self.info.regs.mibirules().write(|w| {
+            unsafe { w.bits(rules) }
+                .msb0()
+                .bit(msb == 0 && !addresses.is_empty())
+                .nobyte()
+                .bit(!payload)
+        });
+
+        Ok(())
+    }
+
+    /// Wait for a target to raise an in-band request, storing its payload in `payload`
+    ///
+    /// Payload bytes beyond the buffer are read and dropped. Hot-join requests are reported for the
+    /// application to assign an address to the new target, with
+    /// [`assign_dynamic_addresses`](Self::assign_dynamic_addresses).
+    pub async fn wait_for_ibi(&mut self, payload: &mut [u8]) -> Result<Ibi> {
+        let result = self.wait_for_ibi_inner(payload).await;
+        self.finish(result.is_err());
+        result
+    }
+
+    async fn wait_for_ibi_inner(&self, payload: &mut [u8]) -> Result<Ibi> {
+        let regs = self.info.regs;
+
+        self.wait_for(SLVSTART).await?;
+        self.clear(SLVSTART);
+
+        let rdterm = payload.len().min(MAX_READ_LEN) as u8;
+        // SAFETY: only unsafe due to .bits usage
+        regs.mctrl()
+            .write(|w| unsafe { w.rdterm().bits(rdterm) }.request().autoibi().ibiresp().ack());
+
+        self.wait_for(IBIWON).await?;
+        self.clear(IBIWON | MCTRLDONE);
+
+        let status = regs.mstatus().read();
+        let address = status.ibiaddr().bits();
+        let kind = if status.ibitype().is_hj() {
+            IbiKind::HotJoin
+        } else if status.ibitype().is_mr() {
+            IbiKind::ControllerRequest
+        } else {
+            IbiKind::Interrupt
+        };
+
+        let mut len = 0;
+        if kind == IbiKind::Interrupt {
+            loop {
+                let status = self.wait_for(RXPEND | COMPLETE).await?;
+
+                while regs.mdatactrl().read().rxempty().bit_is_clear() {
+                    let byte = regs.mrdatab().read().value().bits();
+                    if let Some(slot) = payload.get_mut(len) {
+                        *slot = byte;
+                        len += 1;
+                    }
+                }
+
+                if status & COMPLETE != 0 || !regs.mstatus().read().state().is_ibircv() {
+                    break;
+                }
+            }
+            self.clear(COMPLETE);
+        }
+
+        Ok(Ibi { kind, address, len })
+    }
+}
+
+impl Drop for I3cController<'_> {
+    fn drop(&mut self) {
+        self.info.regs.mconfig().modify(|_, w| w.mstena().master_off());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserved_addresses() {
+        assert!(is_reserved(0x02));
+        assert!(is_reserved(0x7E));
+        assert!(is_reserved(0x3E));
+        assert!(is_reserved(0x6E));
+        assert!(is_reserved(0x79));
+        assert!(!is_reserved(0x08));
+        assert!(!is_reserved(0x3F));
+        assert!(!is_reserved(0x77));
+    }
+
+    #[test]
+    fn baud_rounds_frequencies_down() {
+        // 48 MHz / (2 * 2) is 12 MHz, 48 MHz / (2 * 12) is 2 MHz
+        assert_eq!(baud(48_000_000, 12_000_000, 2_000_000), Some((1, 10)));
+        // 12.5 MHz isn't reachable, 12 MHz is the closest from below
+        assert_eq!(baud(48_000_000, 12_500_000, 2_000_000), Some((1, 10)));
+        assert_eq!(baud(48_000_000, 12_000_000, 1_000_000), Some((1, 22)));
+    }
+
+    #[test]
+    fn timings_follow_the_slowest_i2c_device() {
+        let config = Config::default();
+
+        // 2 MHz open-drain with a push-pull high, 400 kHz I2C over 3 open-drain lows
+        assert_eq!(
+            timings(48_000_000, &config, None),
+            Some(Timings {
+                ppbaud: 1,
+                odbaud: 10,
+                odhpp: true,
+                i2cbaud: 2
+            })
+        );
+        // a Fast-mode device slows the open-drain phases down to 400 kHz
+        assert_eq!(
+            timings(48_000_000, &config, Some(400_000)),
+            Some(Timings {
+                ppbaud: 1,
+                odbaud: 29,
+                odhpp: false,
+                i2cbaud: 0
+            })
+        );
+        // I2C SCL can run below the open-drain phases
+        let config = Config {
+            i2c_frequency: 100_000,
+            ..config
+        };
+        assert_eq!(
+            timings(48_000_000, &config, Some(1_000_000)),
+            Some(Timings {
+                ppbaud: 1,
+                odbaud: 11,
+                odhpp: false,
+                i2cbaud: 9
+            })
+        );
+    }
+
+    #[test]
+    fn baud_rejects_unreachable_frequencies() {
+        assert_eq!(baud(48_000_000, 0, 2_000_000), None);
+        assert_eq!(baud(48_000_000, 1_000_000, 2_000_000), None);
+        assert_eq!(baud(48_000_000, 1_000_000, 1_000_000), None);
+        assert_eq!(baud(48_000_000, 12_000_000, 10_000), None);
+    }
+}
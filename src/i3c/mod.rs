@@ -0,0 +1,186 @@
+//! Improved Inter-Integrated Circuit (I3C)
+//!
+//! The I3C block either drives the bus as its controller, see [`controller::I3cController`], or
+//! runs as a target on the bus of another controller, see [`target::I3cTarget`].
+
+use core::marker::PhantomData;
+
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::clocks::{SysconPeripheral, enable_and_reset};
+use crate::iopctl::{DriveMode, DriveStrength, Function, Inverter, IopctlPin as Pin, Pull, SlewRate};
+use crate::{PeripheralType, interrupt, peripherals};
+
+/// I3C Controller Driver
+pub mod controller;
+/// I3C Target Driver
+pub mod target;
+
+/// shorthand for -> `Result<T>`
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Error information type
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// configuration requested is not supported
+    UnsupportedConfiguration,
+    /// received data was lost, the receive FIFO was full
+    Overrun,
+    /// the transmit FIFO ran empty while the controller was reading
+    Underrun,
+    /// the controller ended a read before all the data was sent
+    Terminated,
+    /// parity or CRC error on received data
+    Parity,
+    /// invalid START, or S0/S1 error on the bus
+    Protocol,
+    /// the address or the data was not acknowledged
+    Nack,
+    /// the bus stalled in the middle of a message
+    Timeout,
+    /// the controller disabled in-band interrupts, or no dynamic address was assigned yet
+    IbiDisabled,
+    /// no room left in the device table of the controller
+    TableFull,
+}
+
+/// Function clock, the undivided FFRO
+const FCLK_HZ: u32 = 48_000_000;
+
+/// Slow clock, FFRO / 2, it times the bus available and idle conditions
+const FCLKS_HZ: u32 = 24_000_000;
+
+/// Bus available condition, 1 us of slow clock cycles
+const BUS_AVAILABLE_MATCH: u8 = (FCLKS_HZ / 1_000_000) as u8;
+
+mod sealed {
+    /// simply seal a trait
+    pub trait Sealed {}
+}
+
+impl<T: Pin> sealed::Sealed for T {}
+
+struct Info {
+    regs: &'static crate::pac::i3c::RegisterBlock,
+    waker: &'static AtomicWaker,
+}
+
+// SAFETY: safety for Send here is the same as the other accessors to unsafe blocks: it must be done from a single executor context.
+//         This is a temporary workaround -- a better solution might be to refactor Info to no longer maintain a reference to regs,
+//         but instead look up the correct register set and then perform operations within an unsafe block as we do for other peripherals
+unsafe impl Send for Info {}
+
+trait SealedInstance {
+    fn info() -> Info;
+}
+
+/// I3C instance trait.
+#[allow(private_bounds)]
+pub trait Instance: SealedInstance + PeripheralType + SysconPeripheral + 'static + Send {
+    /// Interrupt for this I3C instance.
+    type Interrupt: interrupt::typelevel::Interrupt;
+}
+
+impl SealedInstance for peripherals::I3C0 {
+    fn info() -> Info {
+        static WAKER: AtomicWaker = AtomicWaker::new();
+
+        Info {
+            regs: unsafe { &*crate::pac::I3c::ptr() },
+            waker: &WAKER,
+        }
+    }
+}
+
+impl Instance for peripherals::I3C0 {
+    type Interrupt = crate::interrupt::typelevel::I3C0;
+}
+
+/// Clock and reset the I3C block
+fn init<T: Instance>() {
+    // SAFETY: safe from single executor
+    let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
+
+    clkctl1.i3c0fclksel().write(|w| w.sel().ffro_clk());
+    // SAFETY: unsafe needed to write the bits for the dividers
+    clkctl1.i3c0fclkdiv().modify(|_, w| {
+        unsafe { w.div().bits((48_000_000 / FCLK_HZ - 1) as u8) }
+            .halt()
+            .clear_bit()
+    });
+    while clkctl1.i3c0fclkdiv().read().reqflag().bit_is_set() {}
+
+    clkctl1.i3c0fclksdiv().modify(|_, w| {
+        unsafe { w.div().bits((48_000_000 / FCLKS_HZ - 1) as u8) }
+            .halt()
+            .clear_bit()
+    });
+    while clkctl1.i3c0fclksdiv().read().reqflag().bit_is_set() {}
+
+    enable_and_reset::<T>();
+}
+
+/// I3C interrupt handler.
+pub struct InterruptHandler<T: Instance> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandler<T> {
+    unsafe fn on_interrupt() {
+        let regs = T::info().regs;
+
+        let pending = regs.sintmasked().read().bits();
+        if pending != 0 {
+            // SAFETY: SINTCLR mirrors the layout of SINTMASKED
+            regs.sintclr().write(|w| unsafe { w.bits(pending) });
+        }
+
+        let pending = regs.mintmasked().read().bits();
+        if pending != 0 {
+            // SAFETY: MINTCLR mirrors the layout of MINTMASKED
+            regs.mintclr().write(|w| unsafe { w.bits(pending) });
+        }
+
+        T::info().waker.wake();
+    }
+}
+
+/// io configuration trait for easier configuration
+pub trait SclPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for SCL usage
+    fn as_scl(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait SdaPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for SDA usage
+    fn as_sda(&self);
+}
+
+/// The I3C block switches the pads between open-drain and push-pull by itself
+fn configure_bus_pin(pin: &impl Pin, function: Function) {
+    pin.set_function(function)
+        .set_pull(Pull::None)
+        .enable_input_buffer()
+        .set_slew_rate(SlewRate::Standard)
+        .set_drive_strength(DriveStrength::Full)
+        .disable_analog_multiplex()
+        .set_drive_mode(DriveMode::PushPull)
+        .set_input_inverter(Inverter::Disabled);
+}
+
+macro_rules! impl_pin {
+    ($piom_n:ident, $fn:ident, $mode:ident) => {
+        paste::paste! {
+            impl [<$mode:camel Pin>]<peripherals::I3C0> for peripherals::$piom_n {
+                fn [<as_ $mode>](&self) {
+                    configure_bus_pin(self, Function::$fn);
+                }
+            }
+        }
+    };
+}
+
+impl_pin!(PIO2_29, F1, scl);
+impl_pin!(PIO2_30, F1, sda);
@@ -0,0 +1,417 @@
+//! Implements the I3C target, answering the controller of the bus
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_hal_internal::Peri;
+
+use super::{BUS_AVAILABLE_MATCH, Error, Info, Instance, InterruptHandler, Result, SclPin, SdaPin};
+use crate::interrupt;
+use crate::interrupt::typelevel::Interrupt;
+
+/// Broadcast address, which no target may use
+const BROADCAST_ADDRESS: u8 = 0x7E;
+
+/// BCR bit: the target raises in-band interrupts
+pub const BCR_IBI_CAPABLE: u8 = 1 << 1;
+/// BCR bit: the in-band interrupts of the target carry a payload
+pub const BCR_IBI_PAYLOAD: u8 = 1 << 2;
+
+/// Configuration for I3C Target
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Static address, for a controller assigning the dynamic address with SETDASA or talking
+    /// I2C to the target, `None` to wait for ENTDAA
+    pub static_address: Option<u8>,
+    /// MIPI manufacturer ID, the upper 15 bits of the provisioned ID
+    pub vendor_id: u16,
+    /// Part number, the lower 32 bits of the provisioned ID
+    pub part_number: u32,
+    /// Bus Characteristics Register, with [`BCR_IBI_CAPABLE`] to raise in-band interrupts
+    pub bcr: u8,
+    /// Device Characteristics Register
+    pub dcr: u8,
+    /// Longest private read, reported by GETMRL
+    pub max_read_len: u16,
+    /// Longest private write, reported by GETMWL
+    pub max_write_len: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            static_address: None,
+            vendor_id: 0,
+            part_number: 0,
+            bcr: 0,
+            dcr: 0,
+            max_read_len: 256,
+            max_write_len: 256,
+        }
+    }
+}
+
+impl Config {
+    /// 48-bit provisioned ID, reported during ENTDAA
+    pub fn pid(&self) -> u64 {
+        (u64::from(self.vendor_id) << 33) | u64::from(self.part_number)
+    }
+}
+
+/// Message addressed to the target
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Command {
+    /// controller writes a private message, receive it with [`I3cTarget::respond_to_write`]
+    Write,
+    /// controller reads a private message, answer it with [`I3cTarget::respond_to_read`]
+    Read,
+    /// controller sent a common command code the target doesn't handle by itself
+    ///
+    /// Receive it with [`I3cTarget::respond_to_write`]: the command code comes first, followed by
+    /// its data, if any.
+    Ccc,
+    /// dynamic address was assigned, changed or reset by the controller
+    DynamicAddressChanged(Option<u8>),
+}
+
+/// Result of a response to the controller
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Response {
+    /// message complete, with the number of bytes transferred
+    Complete(usize),
+    /// buffer full before the end of the message, with the number of bytes transferred
+    Pending(usize),
+}
+
+/// use the I3C block as a target
+pub struct I3cTarget<'a> {
+    info: Info,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> I3cTarget<'a> {
+    /// use I3C with Pins scl, sda as a target
+    pub fn new<T: Instance>(
+        _bus: Peri<'a, T>,
+        scl: Peri<'a, impl SclPin<T>>,
+        sda: Peri<'a, impl SdaPin<T>>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'a,
+        config: Config,
+    ) -> Result<Self> {
+        let static_address = config.static_address.unwrap_or(0);
+        if static_address > 0x7F
+            || static_address == BROADCAST_ADDRESS
+            || config.vendor_id >= 1 << 15
+            || config.max_read_len >= 1 << 12
+            || config.max_write_len >= 1 << 12
+        {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        super::init::<T>();
+
+        scl.as_scl();
+        sda.as_sda();
+
+        let info = T::info();
+        let regs = info.regs;
+
+        regs.sconfig().write(|w| w.slvena().clear_bit());
+
+        // SAFETY: only unsafe due to .bits usage, values checked above
+        regs.sidpartno()
+            .write(|w| unsafe { w.partno().bits(config.part_number) });
+        regs.svendorid().write(|w| unsafe { w.vid().bits(config.vendor_id) });
+        regs.sidext()
+            .write(|w| unsafe { w.bcr().bits(config.bcr).dcr().bits(config.dcr) });
+        regs.smaxlimits()
+            .write(|w| unsafe { w.maxrd().bits(config.max_read_len).maxwr().bits(config.max_write_len) });
+
+        regs.sdatactrl().write(|w| {
+            w.flushtb()
+                .set_bit()
+                .flushfb()
+                .set_bit()
+                .txtrig()
+                .triggronelless()
+                .rxtrig()
+                .triggrnotempty()
+        });
+
+        regs.sintclr().write(|w| unsafe { w.bits(u32::MAX) });
+
+        // report START and STOP only around messages to this target
+        regs.sconfig().write(|w| {
+            unsafe { w.saddr().bits(static_address).bamatch().bits(BUS_AVAILABLE_MATCH) }
+                .matchss()
+                .set_bit()
+                .slvena()
+                .set_bit()
+        });
+
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        Ok(Self {
+            info,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Dynamic address assigned by the controller, if any
+    pub fn dynamic_address(&self) -> Option<u8> {
+        let dynaddr = self.info.regs.sdynaddr().read();
+
+        dynaddr.davalid().is_daassigned().then(|| dynaddr.daddr().bits())
+    }
+
+    /// Take the first error flagged by the hardware, clearing all of them
+    fn check_errors(&self) -> Result<()> {
+        let regs = self.info.regs;
+
+        if regs.sstatus().read().errwarn().bit_is_clear() {
+            return Ok(());
+        }
+
+        let errors = regs.serrwarn().read();
+        // SAFETY: writing ones only clears the flags just read
+        regs.serrwarn().write(|w| unsafe { w.bits(errors.bits()) });
+
+        if errors.orun().bit_is_set() {
+            Err(Error::Overrun)
+        } else if errors.urun().bit_is_set() || errors.urunnack().bit_is_set() {
+            Err(Error::Underrun)
+        } else if errors.term().bit_is_set() {
+            Err(Error::Terminated)
+        } else if errors.spar().bit_is_set() || errors.hpar().bit_is_set() || errors.hcrc().bit_is_set() {
+            Err(Error::Parity)
+        } else {
+            Err(Error::Protocol)
+        }
+    }
+
+    /// Wait for a message addressed to this target, or a change of its dynamic address
+    pub async fn listen(&mut self) -> Result<Command> {
+        let regs = self.info.regs;
+
+        poll_fn(|cx| {
+            self.info.waker.register(cx.waker());
+
+            if let Err(e) = self.check_errors() {
+                return Poll::Ready(Err(e));
+            }
+
+            let status = regs.sstatus().read();
+
+            if status.dachg().bit_is_set() {
+                regs.sstatus().write(|w| w.dachg().set_bit());
+                return Poll::Ready(Ok(Command::DynamicAddressChanged(self.dynamic_address())));
+            }
+
+            if status.ccc().bit_is_set() {
+                regs.sstatus().write(|w| w.ccc().set_bit().start().set_bit());
+                return Poll::Ready(Ok(Command::Ccc));
+            }
+
+            if status.matched().bit_is_set() && (status.streqrd().bit_is_set() || status.streqwr().bit_is_set()) {
+                // from now on, START flags a repeated START ending this message
+                regs.sstatus().write(|w| w.matched().set_bit().start().set_bit());
+
+                return Poll::Ready(Ok(if status.streqrd().bit_is_set() {
+                    Command::Read
+                } else {
+                    Command::Write
+                }));
+            }
+
+            regs.sintset().write(|w| {
+                w.matched()
+                    .set_bit()
+                    .dachg()
+                    .set_bit()
+                    .ccc()
+                    .set_bit()
+                    .errwarn()
+                    .set_bit()
+            });
+
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Receive the message written by the controller into `buf`
+    pub async fn respond_to_write(&mut self, buf: &mut [u8]) -> Result<Response> {
+        let regs = self.info.regs;
+        let mut len = 0;
+
+        poll_fn(|cx| {
+            self.info.waker.register(cx.waker());
+
+            // sample the end of the message first, the data before it is all in the FIFO then
+            let status = regs.sstatus().read();
+            let ended = status.stnotstop().bit_is_clear() || status.start().bit_is_set();
+
+            while regs.sdatactrl().read().rxempty().is_rxisnotempty() {
+                let Some(byte) = buf.get_mut(len) else {
+                    return Poll::Ready(Ok(Response::Pending(len)));
+                };
+
+                *byte = regs.srdatab().read().data0().bits();
+                len += 1;
+            }
+
+            if let Err(e) = self.check_errors() {
+                return Poll::Ready(Err(e));
+            }
+
+            if ended {
+                return Poll::Ready(Ok(Response::Complete(len)));
+            }
+
+            regs.sintset().write(|w| {
+                w.rxpend()
+                    .set_bit()
+                    .start()
+                    .set_bit()
+                    .stop()
+                    .set_bit()
+                    .errwarn()
+                    .set_bit()
+            });
+
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Send `buf` to the controller
+    ///
+    /// The data can be queued before the controller reads, a target with an empty FIFO NACKs the
+    /// read. Returns once the controller has read all of it.
+    pub async fn respond_to_read(&mut self, buf: &[u8]) -> Result<Response> {
+        let regs = self.info.regs;
+        let mut queued = 0;
+
+        let result = poll_fn(|cx| {
+            self.info.waker.register(cx.waker());
+
+            if let Err(e) = self.check_errors() {
+                return Poll::Ready(Err(e));
+            }
+
+            while regs.sstatus().read().txnotfull().bit_is_set() {
+                let Some(&byte) = buf.get(queued) else {
+                    break;
+                };
+
+                queued += 1;
+                // SAFETY: only unsafe due to .bits usage
+                if queued == buf.len() {
+                    regs.swdatabe().write(|w| unsafe { w.data().bits(byte) });
+                } else {
+                    regs.swdatab().write(|w| unsafe { w.data().bits(byte) });
+                }
+            }
+
+            let drained = regs.sdatactrl().read().txcount().bits() == 0;
+            if queued == buf.len() && drained && regs.sstatus().read().stnotstop().bit_is_clear() {
+                return Poll::Ready(Ok(Response::Complete(queued)));
+            }
+
+            regs.sintset()
+                .write(|w| w.txsend().set_bit().stop().set_bit().errwarn().set_bit());
+
+            Poll::Pending
+        })
+        .await;
+
+        if result.is_err() {
+            // drop what the controller didn't read, it must not leak into the next message
+            regs.sdatactrl().modify(|_, w| w.flushtb().set_bit());
+        }
+
+        result
+    }
+
+    /// Raise an in-band interrupt, carrying `payload` if not empty
+    ///
+    /// The first byte of the payload is the mandatory data byte, the controller reads the rest
+    /// right after it, so the rest must fit in the transmit FIFO. Returns once the controller
+    /// acknowledged the interrupt, or fails with [`Error::Nack`] for the application to raise it
+    /// again later.
+    pub async fn raise_ibi(&mut self, payload: &[u8]) -> Result<()> {
+        let regs = self.info.regs;
+
+        let bcr = regs.sidext().read().bcr().bits();
+        if bcr & BCR_IBI_CAPABLE == 0 || (!payload.is_empty() && bcr & BCR_IBI_PAYLOAD == 0) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        if regs.sstatus().read().ibidis().bit_is_set() || self.dynamic_address().is_none() {
+            return Err(Error::IbiDisabled);
+        }
+
+        let (&first, rest) = payload.split_first().unwrap_or((&0, &[]));
+        for (i, &byte) in rest.iter().enumerate() {
+            // the whole payload must be queued before the controller starts reading it
+            if regs.sstatus().read().txnotfull().bit_is_clear() {
+                regs.sdatactrl().modify(|_, w| w.flushtb().set_bit());
+                return Err(Error::UnsupportedConfiguration);
+            }
+
+            // SAFETY: only unsafe due to .bits usage
+            if i + 1 == rest.len() {
+                regs.swdatabe().write(|w| unsafe { w.data().bits(byte) });
+            } else {
+                regs.swdatab().write(|w| unsafe { w.data().bits(byte) });
+            }
+        }
+
+        regs.sstatus().write(|w| w.event().set_bit());
+        // SAFETY: only unsafe due to .bits usage
+        regs.sctrl()
+            .modify(|_, w| unsafe { w.ibidata().bits(first) }.event().ibi());
+
+        let result = poll_fn(|cx| {
+            self.info.waker.register(cx.waker());
+
+            if let Err(e) = self.check_errors() {
+                return Poll::Ready(Err(e));
+            }
+
+            let status = regs.sstatus().read();
+            if status.event().bit_is_set() {
+                regs.sstatus().write(|w| w.event().set_bit());
+
+                if status.evdet().is_acked() {
+                    return Poll::Ready(Ok(()));
+                }
+                if status.evdet().is_nacked() {
+                    return Poll::Ready(Err(Error::Nack));
+                }
+            }
+
+            regs.sintset().write(|w| w.event().set_bit().errwarn().set_bit());
+
+            Poll::Pending
+        })
+        .await;
+
+        if result.is_err() {
+            regs.sctrl().modify(|_, w| w.event().normal_mode());
+            regs.sdatactrl().modify(|_, w| w.flushtb().set_bit());
+        }
+
+        result
+    }
+}
+
+impl Drop for I3cTarget<'_> {
+    fn drop(&mut self) {
+        self.info.regs.sconfig().modify(|_, w| w.slvena().clear_bit());
+    }
+}
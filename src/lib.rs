@@ -17,10 +17,13 @@ compile_error!(
 // This mod MUST go first, so that the others see its macros.
 pub(crate) mod fmt;
 
+pub mod acmp;
 pub mod adc;
+pub mod casper;
 pub mod clocks;
 pub mod crc;
 pub mod dma;
+pub mod dmic;
 
 #[cfg(feature = "_espi")]
 #[allow(clippy::indexing_slicing)]
@@ -33,9 +36,15 @@ pub mod flexspi;
 pub mod gpio;
 pub mod hashcrypt;
 pub mod i2c;
+pub mod i2s;
+pub mod i3c;
 pub mod iopctl;
+pub mod mrt;
+pub mod otp;
+pub mod puf;
 pub mod pwm;
 pub mod rng;
+pub mod sct;
 pub mod spi;
 pub mod uuid;
 
@@ -49,8 +58,13 @@ pub mod time_driver;
 /// Module provides functionality for
 /// - Counting Timer
 /// - Capture Timer
+/// - Event Counter
+/// - PWM Input
 pub mod timer;
 pub mod uart;
+pub mod usb;
+pub mod usdhc;
+pub mod utick;
 pub mod wwdt;
 
 // This mod MUST go last, so that it sees all the `impl_foo!' macros
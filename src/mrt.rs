@@ -0,0 +1,327 @@
+//! Multi-Rate Timer (MRT)
+//!
+//! The MRT has four independent 24-bit down-counters, clocked from the bus clock. Each channel
+//! is a repeating or one-shot timer, and [`Channel::tick`] waits for its interrupt, which gives
+//! cheap fixed-rate scheduling without using a CTIMER. [`Channel::stall_us`] uses the one-shot
+//! stall mode for short delays accurate to a bus clock cycle, and a channel given up with
+//! [`Channel::into_stall`] serves [`stall_us`] anywhere in the application.
+
+use core::cell::Cell;
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_hal_internal::{Peri, PeripheralType};
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::clocks::{ClockError, ConfigurableClock, enable_and_reset};
+use crate::interrupt;
+use crate::interrupt::typelevel::{Binding, Interrupt};
+
+const CHANNEL_COUNT: usize = 4;
+/// Longest interval of the 24-bit counters, in clock cycles
+const MAX_INTERVAL: u32 = (1 << 24) - 1;
+
+static WAKERS: [AtomicWaker; CHANNEL_COUNT] = [const { AtomicWaker::new() }; CHANNEL_COUNT];
+/// Index and clock rate of the channel serving [`stall_us`], while its [`StallChannel`] lives
+static STALL_CHANNEL: Mutex<CriticalSectionRawMutex, Cell<Option<(usize, u32)>>> =
+    Mutex::const_new(CriticalSectionRawMutex::new(), Cell::new(None));
+
+/// MRT error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Error {
+    /// Clock error
+    Clock(ClockError),
+    /// Interval is zero or longer than the 24-bit counter at the bus clock
+    InvalidInterval,
+    /// No channel serves [`stall_us`], see [`Channel::into_stall`]
+    Uninitialized,
+}
+
+/// MRT interrupt handler
+pub struct InterruptHandler<T: Instance> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandler<T> {
+    unsafe fn on_interrupt() {
+        let regs = T::info().regs;
+
+        for (n, waker) in WAKERS.iter().enumerate() {
+            let channel = regs.channel(n);
+
+            if channel.stat().read().intflag().is_pending_interrupt() && channel.ctrl().read().inten().is_enabled() {
+                // Disable the interrupt, keeping the flag for the task
+                channel.ctrl().modify(|_, w| w.inten().disabled());
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// MRT driver, split into its four channels
+pub struct Mrt<'d> {
+    /// Channel 0
+    pub channel0: Channel<'d>,
+    /// Channel 1
+    pub channel1: Channel<'d>,
+    /// Channel 2
+    pub channel2: Channel<'d>,
+    /// Channel 3
+    pub channel3: Channel<'d>,
+}
+
+impl<'d> Mrt<'d> {
+    /// Create MRT driver, `clk` being the bus clock which drives the counters.
+    ///
+    /// Returns [`Error::Clock`] if the rate of `clk` is unknown.
+    pub fn new<T: Instance>(
+        _mrt: Peri<'d, T>,
+        _irq: impl Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        clk: impl ConfigurableClock,
+    ) -> Result<Self, Error> {
+        let clk_freq = clk.get_clock_rate().map_err(Error::Clock)?;
+
+        enable_and_reset::<T>();
+
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        let channel = |index| Channel {
+            info: T::info(),
+            index,
+            clk_freq,
+            _lifetime: PhantomData,
+        };
+
+        Ok(Self {
+            channel0: channel(0),
+            channel1: channel(1),
+            channel2: channel(2),
+            channel3: channel(3),
+        })
+    }
+}
+
+/// Busy-wait for `count_us` by stalling the bus on the channel given up with
+/// [`Channel::into_stall`].
+///
+/// The CPU stalls on the write which starts the timer, so the delay doesn't depend on the code
+/// around it. Interrupts are held off meanwhile, so keep the delay short.
+///
+/// Returns [`Error::Uninitialized`] while no [`StallChannel`] exists, and
+/// [`Error::InvalidInterval`] if the count is zero or too long for the 24-bit counter.
+pub fn stall_us(count_us: u32) -> Result<(), Error> {
+    // Callers from interrupt context share the channel
+    critical_section::with(|cs| {
+        let (index, clk_freq) = STALL_CHANNEL.borrow(cs).get().ok_or(Error::Uninitialized)?;
+
+        let mut channel = Channel {
+            info: <crate::peripherals::MRT0 as SealedInstance>::info(),
+            index,
+            clk_freq,
+            _lifetime: PhantomData,
+        };
+        channel.stall_us(count_us)
+    })
+}
+
+/// A [`Channel`] serving [`stall_us`], until dropped
+pub struct StallChannel<'d> {
+    channel: Channel<'d>,
+}
+
+impl Drop for StallChannel<'_> {
+    fn drop(&mut self) {
+        critical_section::with(|cs| {
+            let stall = STALL_CHANNEL.borrow(cs);
+
+            // Another channel may have replaced this one
+            if stall.get().is_some_and(|(index, _)| index == self.channel.index) {
+                stall.set(None);
+            }
+        });
+    }
+}
+
+/// Timer mode of a [`Channel`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TimerMode {
+    Repeat,
+    OneShot,
+    OneShotStall,
+}
+
+/// One of the four MRT channels
+pub struct Channel<'d> {
+    info: Info,
+    index: usize,
+    clk_freq: u32,
+    _lifetime: PhantomData<&'d ()>,
+}
+
+impl<'d> Channel<'d> {
+    fn us_to_cycles(&self, us: u32) -> Result<u32, Error> {
+        let cycles = u64::from(us) * u64::from(self.clk_freq) / 1_000_000;
+
+        match u32::try_from(cycles) {
+            Ok(cycles) if cycles > 0 && cycles <= MAX_INTERVAL => Ok(cycles),
+            _ => Err(Error::InvalidInterval),
+        }
+    }
+
+    fn start(&mut self, cycles: u32, mode: TimerMode) {
+        let channel = self.info.regs.channel(self.index);
+
+        channel.ctrl().write(|w| match mode {
+            TimerMode::Repeat => w.mode().repeat_interrupt_mode().inten().disabled(),
+            TimerMode::OneShot => w.mode().one_shot_interrupt_mode().inten().disabled(),
+            TimerMode::OneShotStall => w.mode().one_shot_stall_mode().inten().disabled(),
+        });
+
+        // Clear a stale interrupt flag, then load the interval right away
+        channel.stat().write(|w| w.intflag().pending_interrupt());
+        // SAFETY: cycles checked against the 24-bit counter by us_to_cycles
+        channel
+            .intval()
+            .write(|w| unsafe { w.ivalue().bits(cycles).load().force_load() });
+    }
+
+    /// Fire every `period_us` until stopped.
+    ///
+    /// Returns [`Error::InvalidInterval`] if the period is zero or too long for the 24-bit counter.
+    pub fn start_repeat(&mut self, period_us: u32) -> Result<(), Error> {
+        let cycles = self.us_to_cycles(period_us)?;
+        self.start(cycles, TimerMode::Repeat);
+        Ok(())
+    }
+
+    /// Fire once, `count_us` from now.
+    ///
+    /// Returns [`Error::InvalidInterval`] if the count is zero or too long for the 24-bit counter.
+    pub fn start_one_shot(&mut self, count_us: u32) -> Result<(), Error> {
+        let cycles = self.us_to_cycles(count_us)?;
+        self.start(cycles, TimerMode::OneShot);
+        Ok(())
+    }
+
+    /// Busy-wait for `count_us` by stalling the bus until the timer expires.
+    ///
+    /// The CPU stalls on the write which starts the timer, so the delay doesn't depend on the
+    /// code around it. Interrupts are held off meanwhile, so keep the delay short.
+    ///
+    /// Returns [`Error::InvalidInterval`] if the count is zero or too long for the 24-bit counter.
+    pub fn stall_us(&mut self, count_us: u32) -> Result<(), Error> {
+        let cycles = self.us_to_cycles(count_us)?;
+        self.stall_cycles(cycles)
+    }
+
+    /// Busy-wait for `cycles` bus clock cycles by stalling the bus until the timer expires, for
+    /// delays shorter than a microsecond.
+    ///
+    /// Returns [`Error::InvalidInterval`] if the count is zero or too long for the 24-bit counter.
+    pub fn stall_cycles(&mut self, cycles: u32) -> Result<(), Error> {
+        if cycles == 0 || cycles > MAX_INTERVAL {
+            return Err(Error::InvalidInterval);
+        }
+
+        // Returns once the timer expired
+        self.start(cycles, TimerMode::OneShotStall);
+        self.info
+            .regs
+            .channel(self.index)
+            .stat()
+            .write(|w| w.intflag().pending_interrupt());
+
+        Ok(())
+    }
+
+    /// Give up the channel to serve [`stall_us`], until the returned [`StallChannel`] is dropped.
+    ///
+    /// Replaces the channel given up before, if any.
+    pub fn into_stall(mut self) -> StallChannel<'d> {
+        self.stop();
+        critical_section::with(|cs| STALL_CHANNEL.borrow(cs).set(Some((self.index, self.clk_freq))));
+        StallChannel { channel: self }
+    }
+
+    /// Stop the timer. A pending tick is discarded.
+    pub fn stop(&mut self) {
+        let channel = self.info.regs.channel(self.index);
+
+        channel.ctrl().modify(|_, w| w.inten().disabled());
+        // SAFETY: a zero interval stops the counter
+        channel
+            .intval()
+            .write(|w| unsafe { w.ivalue().bits(0).load().force_load() });
+        channel.stat().write(|w| w.intflag().pending_interrupt());
+    }
+
+    /// Whether the timer is counting down.
+    pub fn is_running(&self) -> bool {
+        self.info.regs.channel(self.index).stat().read().run().is_running()
+    }
+
+    /// Wait for the timer to fire.
+    ///
+    /// Completes immediately if it fired since the last tick, ticks missed in between are
+    /// merged into one. Never completes for a stopped timer.
+    pub async fn tick(&mut self) {
+        let channel = self.info.regs.channel(self.index);
+
+        poll_fn(|cx| {
+            if let Some(waker) = WAKERS.get(self.index) {
+                waker.register(cx.waker());
+            }
+
+            if channel.stat().read().intflag().is_pending_interrupt() {
+                channel.stat().write(|w| w.intflag().pending_interrupt());
+                return Poll::Ready(());
+            }
+
+            // The interrupt disables itself when it fires
+            channel.ctrl().modify(|_, w| w.inten().enabled());
+
+            Poll::Pending
+        })
+        .await;
+    }
+}
+
+impl Drop for Channel<'_> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+struct Info {
+    regs: crate::pac::Mrt0,
+}
+
+trait SealedInstance {
+    fn info() -> Info;
+}
+
+/// MRT instance trait.
+#[allow(private_bounds)]
+pub trait Instance: SealedInstance + PeripheralType + crate::clocks::SysconPeripheral + 'static + Send {
+    /// Interrupt for this MRT instance.
+    type Interrupt: interrupt::typelevel::Interrupt;
+}
+
+impl Instance for crate::peripherals::MRT0 {
+    type Interrupt = crate::interrupt::typelevel::MRT0;
+}
+
+impl SealedInstance for crate::peripherals::MRT0 {
+    fn info() -> Info {
+        // SAFETY: safe from single executor
+        Info {
+            regs: unsafe { crate::pac::Mrt0::steal() },
+        }
+    }
+}
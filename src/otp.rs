@@ -0,0 +1,136 @@
+//! One-Time Programmable (OTP) fuses
+//!
+//! Fuses are accessed through the OTP driver of the boot ROM. Reading is always available,
+//! programming requires the `otp-program` feature: blowing a fuse cannot be undone, and the
+//! wrong fuse can permanently lock the device.
+
+use core::ffi::c_void;
+
+use embassy_hal_internal::Peri;
+
+use crate::peripherals;
+
+/// Error information type
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// the ROM driver failed with this status code
+    Rom(u32),
+    /// fuse did not read back with the programmed value
+    VerifyFailed,
+}
+
+/// Root of the boot ROM API
+#[repr(C)]
+#[allow(dead_code)]
+struct BootloaderTree {
+    run_bootloader: unsafe extern "C" fn(arg: *mut c_void),
+    version: u32,
+    copyright: *const u8,
+    reserved0: u32,
+    flexspi_nor_driver: *const c_void,
+    reserved1: [u32; 2],
+    otp_driver: *const OtpDriver,
+}
+
+/// Boot ROM OTP driver, every function returns a status code
+#[repr(C)]
+#[allow(dead_code)]
+struct OtpDriver {
+    version: u32,
+    init: unsafe extern "C" fn(src_clk_freq: u32) -> u32,
+    deinit: unsafe extern "C" fn() -> u32,
+    fuse_read: unsafe extern "C" fn(addr: u32, data: *mut u32) -> u32,
+    fuse_program: unsafe extern "C" fn(addr: u32, data: u32, lock: bool) -> u32,
+    reload: unsafe extern "C" fn() -> u32,
+    crc_check: unsafe extern "C" fn(start_addr: u32, end_addr: u32, crc_addr: u32) -> u32,
+    crc_calc: unsafe extern "C" fn(src: *mut u32, number_of_words: u32, crc_checksum: *mut u32) -> u32,
+    shadow_read: unsafe extern "C" fn(addr: u32, data: *mut u32) -> u32,
+}
+
+const BOOTLOADER_TREE: *const BootloaderTree = 0x1303_fc00 as *const BootloaderTree;
+
+const STATUS_SUCCESS: u32 = 0;
+
+fn driver() -> &'static OtpDriver {
+    // SAFETY: the boot ROM is always mapped and its API tree never changes
+    unsafe { &*(*BOOTLOADER_TREE).otp_driver }
+}
+
+fn check(status: u32) -> Result<(), Error> {
+    match status {
+        STATUS_SUCCESS => Ok(()),
+        status => Err(Error::Rom(status)),
+    }
+}
+
+/// OTP driver
+///
+/// The ROM driver is not reentrant, owning the OTP peripheral ensures only one instance exists.
+pub struct Otp<'d> {
+    _p: Peri<'d, peripherals::OTP>,
+}
+
+impl<'d> Otp<'d> {
+    /// Initialize the ROM OTP driver, `src_clk_hz` is the frequency of the system clock
+    pub fn new(otp: Peri<'d, peripherals::OTP>, src_clk_hz: u32) -> Result<Self, Error> {
+        let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
+        clkctl0.pscctl0_set().write(|w| w.otp_clk().set_clock());
+
+        // SAFETY: the clock of the OTP controller is running
+        check(unsafe { (driver().init)(src_clk_hz) })?;
+
+        Ok(Self { _p: otp })
+    }
+
+    /// Read fuse word `index` straight from the fuse array
+    pub fn read(&mut self, index: u32) -> Result<u32, Error> {
+        let mut value = 0;
+        // SAFETY: the driver was initialized in new() and the pointer is valid for the call
+        check(unsafe { (driver().fuse_read)(index, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Read the shadow register of fuse word `index`, loaded from the fuses at boot
+    pub fn read_shadow(&mut self, index: u32) -> Result<u32, Error> {
+        let mut value = 0;
+        // SAFETY: the driver was initialized in new() and the pointer is valid for the call
+        check(unsafe { (driver().shadow_read)(index, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Reload the shadow registers from the fuses
+    pub fn reload(&mut self) -> Result<(), Error> {
+        // SAFETY: the driver was initialized in new()
+        check(unsafe { (driver().reload)() })
+    }
+
+    /// Blow the bits of `value` into fuse word `index`, then read it back to check them
+    ///
+    /// Bits already blown stay set. If `lock` is set, the word is write-locked afterwards.
+    ///
+    /// # Safety
+    ///
+    /// Programming fuses is irreversible. Fuses control boot, debug access and secure boot keys,
+    /// blowing the wrong ones can permanently brick the device: check `index` and `value` against
+    /// the fuse map of the reference manual.
+    #[cfg(feature = "otp-program")]
+    pub unsafe fn program(&mut self, index: u32, value: u32, lock: bool) -> Result<(), Error> {
+        let previous = self.read(index)?;
+
+        // SAFETY: the driver was initialized in new(), the caller vouches for the fuse word
+        check(unsafe { (driver().fuse_program)(index, value, lock) })?;
+
+        if self.read(index)? != previous | value {
+            return Err(Error::VerifyFailed);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Otp<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the driver was initialized in new()
+        let _ = unsafe { (driver().deinit)() };
+    }
+}
@@ -0,0 +1,133 @@
+//! Per-device identity keypair, derived from the PUF and the UUID
+//!
+//! The identity is a P-256 keypair reconstructed on every boot from a key code, created once
+//! during provisioning and stored in flash next to the activation code. The private key only
+//! ever exists in RAM, where it is wiped once dropped, and the public key can be registered with
+//! a backend for attestation.
+use core::sync::atomic::{Ordering, compiler_fence};
+
+use rand_core::TryCryptoRng;
+
+use super::{Error, MAX_KEY_INDEX, Puf, key_code_len};
+use crate::casper::Casper;
+use crate::casper::ecdsa::{PUBLIC_KEY_LEN, SCALAR_LEN, SIGNATURE_LEN};
+use crate::hashcrypt::Hashcrypt;
+use crate::hashcrypt::hmac::MAC_LEN;
+use crate::uuid::Uuid;
+
+/// Length of the PUF wrapped seed, in bytes
+const SEED_LEN: usize = 32;
+
+/// Length of the identity key code, in bytes
+pub const KEY_CODE_LEN: usize = key_code_len(SEED_LEN);
+
+/// Key index the identity seed is wrapped with
+pub const KEY_INDEX: u8 = MAX_KEY_INDEX;
+
+/// Domain separation label of the derivation
+const LABEL: &[u8] = b"embassy-imxrt device identity";
+
+/// Candidate private keys to try before giving up, each one fails with probability 2^-32
+const MAX_ATTEMPTS: u8 = 8;
+
+/// Overwrite key material, the writes can't be optimized away as dead stores
+fn wipe(buffer: &mut [u8]) {
+    for byte in buffer.iter_mut() {
+        // SAFETY: the pointer comes from a valid mutable reference
+        unsafe { core::ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// Key material wiped when dropped, on every exit path
+struct Secret<const N: usize>([u8; N]);
+
+impl<const N: usize> Drop for Secret<N> {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+/// Device identity keypair, the private key is wiped when dropped
+pub struct Identity {
+    private_key: [u8; SCALAR_LEN],
+    public_key: [u8; PUBLIC_KEY_LEN],
+}
+
+impl Identity {
+    /// Private key, big-endian
+    pub fn private_key(&self) -> &[u8; SCALAR_LEN] {
+        &self.private_key
+    }
+
+    /// Public key, x followed by y, both big-endian
+    pub fn public_key(&self) -> &[u8; PUBLIC_KEY_LEN] {
+        &self.public_key
+    }
+
+    /// Sign a message hash with ECDSA P-256, see [`Casper::p256_sign`]
+    pub fn sign<R: TryCryptoRng>(
+        &self,
+        casper: &mut Casper<'_, crate::casper::Blocking>,
+        hash: &[u8; SCALAR_LEN],
+        rng: &mut R,
+        signature: &mut [u8; SIGNATURE_LEN],
+    ) -> Result<(), Error> {
+        Ok(casper.p256_sign(&self.private_key, hash, rng, signature)?)
+    }
+}
+
+impl Drop for Identity {
+    fn drop(&mut self) {
+        wipe(&mut self.private_key);
+    }
+}
+
+impl<'d> Puf<'d> {
+    /// Create the key code of a new device identity, to be stored for [`Puf::derive_identity`]
+    pub fn generate_identity(&mut self, key_code: &mut [u8; KEY_CODE_LEN]) -> Result<(), Error> {
+        self.generate_key(KEY_INDEX, SEED_LEN, key_code)
+    }
+
+    /// Reconstruct the device identity from the key code made by [`Puf::generate_identity`]
+    ///
+    /// The private key is HMAC-SHA256 of the UUID, keyed with the PUF seed, so the same key code
+    /// always yields the same identity on this device and nothing useful anywhere else.
+    pub fn derive_identity(
+        &mut self,
+        key_code: &[u8; KEY_CODE_LEN],
+        hashcrypt: &mut Hashcrypt<'_, crate::hashcrypt::Blocking>,
+        casper: &mut Casper<'_, crate::casper::Blocking>,
+    ) -> Result<Identity, Error> {
+        let mut seed = Secret([0u8; SEED_LEN]);
+        if self.get_key(key_code, &mut seed.0)? != KEY_INDEX {
+            return Err(Error::UnsupportedConfiguration);
+        }
+        let uuid = Uuid::read().to_le_bytes();
+
+        // Retry the rare candidates which are not valid scalars
+        for attempt in 0..MAX_ATTEMPTS {
+            let mut private_key = Secret([0u8; MAC_LEN]);
+            let mut hmac = hashcrypt.new_hmac_sha256(&seed.0)?;
+            hmac.update(LABEL)?;
+            hmac.update(&uuid)?;
+            hmac.update(&[attempt])?;
+            hmac.finalize(&mut private_key.0)?;
+
+            let mut public_key = [0u8; PUBLIC_KEY_LEN];
+            match casper.p256_public_key(&private_key.0, &mut public_key) {
+                Ok(()) => {
+                    // The candidate is wiped when dropped, only the identity keeps a copy
+                    return Ok(Identity {
+                        private_key: private_key.0,
+                        public_key,
+                    });
+                }
+                Err(crate::casper::Error::InvalidKey) => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        Err(Error::OperationFailed)
+    }
+}
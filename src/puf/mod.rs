@@ -0,0 +1,353 @@
+//! Physically Unclonable Function (PUF)
+//!
+//! The PUF derives a device-unique secret from the start-up state of its SRAM. Enrolling the
+//! device produces an activation code, which is stored in flash and handed back to
+//! [`Puf::start`] after every reset. Once started, the PUF wraps keys into key codes, which only
+//! this very device can unwrap: keys stored that way never exist in plaintext flash.
+
+use core::marker::PhantomData;
+
+use crate::clocks::{SysconPeripheral, enable_and_reset};
+use crate::{Peri, PeripheralType, peripherals};
+
+/// Device identity module
+pub mod identity;
+
+/// Length of the activation code, in bytes
+pub const ACTIVATION_CODE_LEN: usize = 1192;
+
+/// Shortest key the PUF can wrap, in bytes
+pub const MIN_KEY_LEN: usize = 8;
+
+/// Longest key the PUF can wrap, in bytes
+pub const MAX_KEY_LEN: usize = 512;
+
+/// Highest key index
+pub const MAX_KEY_INDEX: u8 = 15;
+
+/// Header prepended to the wrapped key in a key code, in bytes
+const KEY_CODE_HEADER_LEN: usize = 20;
+
+/// Length of the key code wrapping a `key_len` byte key
+#[must_use]
+pub const fn key_code_len(key_len: usize) -> usize {
+    KEY_CODE_HEADER_LEN + key_len
+}
+
+/// Hardware key slot, loaded over the secret key bus from key codes of index 0
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KeySlot {
+    /// Slot 0, the Hashcrypt AES secret key, see [`crate::hashcrypt::aes::AesKey::Secret`]
+    Slot0,
+    /// Slot 1
+    Slot1,
+}
+
+/// Error information type
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// configuration requested is not supported
+    UnsupportedConfiguration,
+    /// operation is not allowed in the current state of the PUF
+    NotAllowed,
+    /// operation failed, for instance on a corrupted activation or key code
+    OperationFailed,
+    /// hashing failed
+    Hashcrypt(crate::hashcrypt::Error),
+    /// public key operation failed
+    Casper(crate::casper::Error),
+}
+
+impl From<crate::hashcrypt::Error> for Error {
+    fn from(error: crate::hashcrypt::Error) -> Self {
+        Self::Hashcrypt(error)
+    }
+}
+
+impl From<crate::casper::Error> for Error {
+    fn from(error: crate::casper::Error) -> Self {
+        Self::Casper(error)
+    }
+}
+
+/// PUF operations
+#[derive(Debug, Copy, Clone)]
+enum Command {
+    Enroll,
+    Start,
+    GenerateKey,
+    SetKey,
+    GetKey,
+}
+
+/// Data exchanged with the PUF while a command runs
+#[derive(Default)]
+struct Transfer<'a> {
+    key_in: &'a [u8],
+    code_in: &'a [u8],
+    code_out: &'a mut [u8],
+    key_out: &'a mut [u8],
+}
+
+/// PUF driver.
+pub struct Puf<'d> {
+    info: Info,
+    _lifetime: PhantomData<&'d ()>,
+}
+
+impl<'d> Puf<'d> {
+    /// Power up the PUF and wait until it is ready
+    pub fn new<T: Instance>(_peripheral: Peri<'d, T>) -> Self {
+        enable_and_reset::<T>();
+
+        let puf = Self {
+            info: T::info(),
+            _lifetime: PhantomData,
+        };
+
+        puf.info.regs.pwrctrl().write(|w| w.ram_on().power_on());
+        while puf.info.regs.intstat().read().ready().bit_is_clear() {}
+
+        puf
+    }
+
+    /// Enroll the device, filling `activation_code`
+    ///
+    /// Enrollment is done once in the lifetime of the device, the activation code must be stored
+    /// for [`Puf::start`]. Key operations are allowed right after enrollment.
+    pub fn enroll(&mut self, activation_code: &mut [u8; ACTIVATION_CODE_LEN]) -> Result<(), Error> {
+        if self.info.regs.allow().read().allowenroll().bit_is_clear() {
+            return Err(Error::NotAllowed);
+        }
+
+        self.execute(
+            Command::Enroll,
+            Transfer {
+                code_out: activation_code,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Reconstruct the device secret from the activation code produced by [`Puf::enroll`]
+    pub fn start(&mut self, activation_code: &[u8; ACTIVATION_CODE_LEN]) -> Result<(), Error> {
+        if self.info.regs.allow().read().allowstart().bit_is_clear() {
+            return Err(Error::NotAllowed);
+        }
+
+        self.execute(
+            Command::Start,
+            Transfer {
+                code_in: activation_code,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Wrap a user provided `key` into `key_code`, for use at key index `index`
+    ///
+    /// `key` is a multiple of 8 bytes, between [`MIN_KEY_LEN`] and [`MAX_KEY_LEN`], and
+    /// `key_code` is [`key_code_len`] bytes long.
+    pub fn set_key(&mut self, index: u8, key: &[u8], key_code: &mut [u8]) -> Result<(), Error> {
+        self.configure_key(index, key.len(), key_code.len())?;
+
+        self.execute(
+            Command::SetKey,
+            Transfer {
+                key_in: key,
+                code_out: key_code,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Generate a random `key_len` byte key, derived from the device secret, into `key_code`
+    ///
+    /// The key never leaves the PUF, only its key code does. Sizes follow [`Puf::set_key`].
+    pub fn generate_key(&mut self, index: u8, key_len: usize, key_code: &mut [u8]) -> Result<(), Error> {
+        self.configure_key(index, key_len, key_code.len())?;
+
+        self.execute(
+            Command::GenerateKey,
+            Transfer {
+                code_out: key_code,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Unwrap `key_code` into `key`, returning the key index it was created with
+    ///
+    /// `key` must be as long as the wrapped key. Keys at index 0 are reserved for the hardware
+    /// key bus and are never output, see [`Puf::get_hw_key`].
+    pub fn get_key(&mut self, key_code: &[u8], key: &mut [u8]) -> Result<u8, Error> {
+        if key_code.len() != key_code_len(key.len()) || !Self::is_valid_key_len(key.len()) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+        if self.info.regs.allow().read().allowgetkey().bit_is_clear() {
+            return Err(Error::NotAllowed);
+        }
+
+        self.execute(
+            Command::GetKey,
+            Transfer {
+                code_in: key_code,
+                key_out: key,
+                ..Default::default()
+            },
+        )?;
+
+        match self.info.regs.keyoutindex().read().keyoutidx().bits() {
+            0 => Err(Error::UnsupportedConfiguration),
+            index => Ok(index),
+        }
+    }
+
+    /// Unwrap `key_code`, created at key index 0, straight into the hardware key slot `slot`
+    ///
+    /// The key travels over the secret key bus, masked with the random word `mask`, and never
+    /// transits CPU-visible memory.
+    pub fn get_hw_key(&mut self, key_code: &[u8], slot: KeySlot, mask: u32) -> Result<(), Error> {
+        let key_len = key_code.len().saturating_sub(KEY_CODE_HEADER_LEN);
+        if !Self::is_valid_key_len(key_len) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+        if self.info.regs.allow().read().allowgetkey().bit_is_clear() {
+            return Err(Error::NotAllowed);
+        }
+
+        let regs = &self.info.regs;
+        match slot {
+            KeySlot::Slot0 => {
+                regs.keyreset().write(|w| w.key0().reset());
+                regs.keymask(0).write(|w| unsafe { w.keymask().bits(mask) });
+                regs.keyenable().modify(|_, w| w.key0().enabled());
+            }
+            KeySlot::Slot1 => {
+                regs.keyreset().write(|w| w.key1().reset());
+                regs.keymask(1).write(|w| unsafe { w.keymask().bits(mask) });
+                regs.keyenable().modify(|_, w| w.key1().enabled());
+            }
+        }
+
+        self.execute(
+            Command::GetKey,
+            Transfer {
+                code_in: key_code,
+                ..Default::default()
+            },
+        )?;
+
+        // Any other index was output to the CPU instead, and discarded
+        match self.info.regs.keyoutindex().read().keyoutidx().bits() {
+            0 => Ok(()),
+            _ => Err(Error::UnsupportedConfiguration),
+        }
+    }
+
+    fn is_valid_key_len(key_len: usize) -> bool {
+        (MIN_KEY_LEN..=MAX_KEY_LEN).contains(&key_len) && key_len.is_multiple_of(8)
+    }
+
+    /// Check the key geometry and program it for SetKey and GenerateKey
+    fn configure_key(&mut self, index: u8, key_len: usize, code_len: usize) -> Result<(), Error> {
+        if index > MAX_KEY_INDEX || !Self::is_valid_key_len(key_len) || code_len != key_code_len(key_len) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+        if self.info.regs.allow().read().allowsetkey().bit_is_clear() {
+            return Err(Error::NotAllowed);
+        }
+
+        // The size is counted in 64-bit words, the largest key wrapping around to 0
+        let size = ((key_len / 8) & 0x3f) as u8;
+        self.info.regs.keysize().write(|w| unsafe { w.keysize().bits(size) });
+        self.info.regs.keyindex().write(|w| unsafe { w.keyidx().bits(index) });
+        Ok(())
+    }
+
+    /// Run a command, feeding and draining its data one word at a time as the PUF requests it
+    fn execute(&mut self, command: Command, transfer: Transfer<'_>) -> Result<(), Error> {
+        let regs = &self.info.regs;
+        let mut key_in = transfer.key_in.as_chunks::<4>().0.iter();
+        let mut code_in = transfer.code_in.as_chunks::<4>().0.iter();
+        let mut code_out = transfer.code_out.as_chunks_mut::<4>().0.iter_mut();
+        let mut key_out = transfer.key_out.as_chunks_mut::<4>().0.iter_mut();
+
+        regs.ctrl().write(|w| match command {
+            Command::Enroll => w.enroll().set_bit(),
+            Command::Start => w.start().set_bit(),
+            Command::GenerateKey => w.generatekey().set_bit(),
+            Command::SetKey => w.setkey().set_bit(),
+            Command::GetKey => w.getkey().set_bit(),
+        });
+
+        // Wait for the command to be accepted
+        loop {
+            let stat = regs.stat().read();
+            if stat.busy().bit_is_set() || stat.error().bit_is_set() {
+                break;
+            }
+        }
+
+        loop {
+            let stat = regs.stat().read();
+            if stat.busy().bit_is_clear() {
+                break;
+            }
+
+            // The PUF stalls until each requested word is supplied, so always answer, with zeros
+            // past the end of the data
+            if stat.keyinreq().bit_is_set() {
+                let word = key_in.next().map_or(0, |bytes| u32::from_le_bytes(*bytes));
+                regs.keyinput().write(|w| unsafe { w.keyin().bits(word) });
+            }
+            if stat.codeinreq().bit_is_set() {
+                let word = code_in.next().map_or(0, |bytes| u32::from_le_bytes(*bytes));
+                regs.codeinput().write(|w| unsafe { w.codein().bits(word) });
+            }
+            if stat.codeoutavail().bit_is_set() {
+                let word = regs.codeoutput().read().codeout().bits();
+                if let Some(bytes) = code_out.next() {
+                    *bytes = word.to_le_bytes();
+                }
+            }
+            if stat.keyoutavail().bit_is_set() {
+                let word = regs.keyoutput().read().keyout().bits();
+                if let Some(bytes) = key_out.next() {
+                    *bytes = word.to_le_bytes();
+                }
+            }
+        }
+
+        if regs.stat().read().success().bit_is_set() {
+            Ok(())
+        } else {
+            Err(Error::OperationFailed)
+        }
+    }
+}
+
+struct Info {
+    regs: crate::pac::Puf,
+}
+
+trait SealedInstance {
+    fn info() -> Info;
+}
+
+/// PUF instance trait.
+#[allow(private_bounds)]
+pub trait Instance: SealedInstance + PeripheralType + SysconPeripheral + 'static + Send {}
+
+impl Instance for peripherals::PUF {}
+
+impl SealedInstance for peripherals::PUF {
+    fn info() -> Info {
+        // SAFETY: safe from single executor
+        Info {
+            regs: unsafe { crate::pac::Puf::steal() },
+        }
+    }
+}
@@ -133,7 +133,7 @@ impl Channel {
 }
 
 // non-reexported (sealed) traits
-mod sealed {
+pub(crate) mod sealed {
     use crate::PeripheralType;
     use crate::clocks::SysconPeripheral;
 
@@ -0,0 +1,160 @@
+//! ChaCha20 CSPRNG seeded from the TRNG
+
+use rand_core::{CryptoRng, RngCore};
+
+use super::Rng;
+
+/// Number of bytes generated before the key is replaced with fresh TRNG output.
+pub const RESEED_INTERVAL: usize = 64 * 1024;
+
+/// "expand 32-byte k"
+const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+const BLOCK_LEN: usize = 64;
+
+#[allow(clippy::indexing_slicing)]
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    // Panic safety: only called below with constant indices smaller than 16
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// ChaCha20 block function, `input` holds the counter and nonce words
+fn block(key: &[u32; 8], input: &[u32; 4]) -> [u32; 16] {
+    let mut initial = [0u32; 16];
+    for (word, value) in initial.iter_mut().zip(CONSTANTS.iter().chain(key).chain(input)) {
+        *word = *value;
+    }
+
+    let mut state = initial;
+    for _ in 0..10 {
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+    }
+
+    for (word, value) in state.iter_mut().zip(initial) {
+        *word = word.wrapping_add(value);
+    }
+    state
+}
+
+/// ChaCha20 CSPRNG, keyed and periodically rekeyed from the TRNG.
+///
+/// Generating random numbers is much faster than reading the TRNG, which only has to produce
+/// a fresh 256-bit key every [`RESEED_INTERVAL`] bytes.
+pub struct ChaChaRng<'a, 'd> {
+    trng: &'a mut Rng<'d>,
+    key: [u32; 8],
+    counter: u64,
+    buffer: [u8; BLOCK_LEN],
+    used: usize,
+    since_reseed: usize,
+}
+
+impl<'a, 'd> ChaChaRng<'a, 'd> {
+    /// Create a new CSPRNG, seeded from `trng`.
+    pub fn from_trng(trng: &'a mut Rng<'d>) -> Self {
+        let mut rng = Self {
+            trng,
+            key: [0; 8],
+            counter: 0,
+            buffer: [0; BLOCK_LEN],
+            used: BLOCK_LEN,
+            since_reseed: 0,
+        };
+        rng.reseed();
+        rng
+    }
+
+    /// Replace the key with fresh TRNG output, discarding any buffered output.
+    pub fn reseed(&mut self) {
+        let mut seed = [0u8; 32];
+        self.trng.blocking_fill_bytes(&mut seed);
+        for (word, bytes) in self.key.iter_mut().zip(seed.as_chunks::<4>().0) {
+            *word = u32::from_le_bytes(*bytes);
+        }
+
+        self.counter = 0;
+        self.used = BLOCK_LEN;
+        self.since_reseed = 0;
+    }
+
+    fn refill(&mut self) {
+        if self.since_reseed >= RESEED_INTERVAL {
+            self.reseed();
+        }
+
+        let input = [self.counter as u32, (self.counter >> 32) as u32, 0, 0];
+        let words = block(&self.key, &input);
+        for (bytes, word) in self.buffer.as_chunks_mut::<4>().0.iter_mut().zip(words) {
+            *bytes = word.to_le_bytes();
+        }
+
+        self.counter = self.counter.wrapping_add(1);
+        self.used = 0;
+        self.since_reseed += BLOCK_LEN;
+    }
+}
+
+impl RngCore for ChaChaRng<'_, '_> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_ne_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_ne_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, mut dest: &mut [u8]) {
+        while !dest.is_empty() {
+            if self.used == BLOCK_LEN {
+                self.refill();
+            }
+
+            let available = self.buffer.get_mut(self.used..).unwrap_or_default();
+            let count = available.len().min(dest.len());
+            let (head, tail) = dest.split_at_mut(count);
+            let (output, _) = available.split_at_mut(count);
+            head.copy_from_slice(output);
+
+            // Never hand out the same keystream twice
+            output.fill(0);
+            self.used += count;
+            dest = tail;
+        }
+    }
+}
+
+impl CryptoRng for ChaChaRng<'_, '_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc8439_block() {
+        // RFC 8439 2.3.2
+        let key = core::array::from_fn(|i| u32::from_le_bytes(core::array::from_fn(|j| (4 * i + j) as u8)));
+        let output = block(&key, &[1, 0x0900_0000, 0x4a00_0000, 0]);
+
+        assert_eq!(output[0], 0xe4e7_f110);
+        assert_eq!(output[1], 0x1559_3bd1);
+        assert_eq!(output[15], 0x4e3c_50a2);
+    }
+}
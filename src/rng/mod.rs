@@ -5,12 +5,17 @@ use core::marker::PhantomData;
 use core::task::Poll;
 
 use embassy_sync::waitqueue::AtomicWaker;
-use rand_core::{TryCryptoRng, TryRngCore};
+use rand_core::{CryptoRng, RngCore};
 
 use crate::clocks::{SysconPeripheral, enable_and_reset};
 use crate::interrupt::typelevel::Interrupt;
 use crate::{Peri, PeripheralType, interrupt, peripherals};
 
+mod chacha;
+mod pool;
+pub use chacha::{ChaChaRng, RESEED_INTERVAL};
+pub use pool::EntropyPool;
+
 static RNG_WAKER: AtomicWaker = AtomicWaker::new();
 
 // The values are based on the NIST SP 800-90B recommendations for entropy source testing
@@ -47,6 +52,65 @@ impl core::fmt::Display for Error {
     }
 }
 
+/// Snapshot of the TRNG online health tests, see [`Rng::health_report`].
+///
+/// Test results reflect the last entropy sample generated.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HealthReport {
+    /// A hardware error was flagged.
+    pub hw_error: bool,
+
+    /// The ring oscillator frequency was out of range.
+    pub frequency_count_fail: bool,
+
+    /// One of the 1 to 6+ bit run length tests failed.
+    pub run_length_fail: bool,
+
+    /// The sparse bit test failed.
+    pub sparse_bit_fail: bool,
+
+    /// The long run test failed.
+    pub long_run_fail: bool,
+
+    /// The poker test failed.
+    pub poker_fail: bool,
+
+    /// The mono bit test failed.
+    pub mono_bit_fail: bool,
+
+    /// Retries left before a failing test is reported as an error.
+    pub retry_count: u8,
+
+    /// Ring oscillator frequency count.
+    pub frequency_count: u32,
+
+    /// Number of ones in the sample, as counted by the mono bit test.
+    pub mono_bit_count: u16,
+
+    /// Poker test square calculation result.
+    pub poker_square: u32,
+
+    /// Number of entropy samples taken to generate the sample.
+    pub total_samples: u32,
+}
+
+impl HealthReport {
+    /// Check whether every health test passed.
+    pub fn is_healthy(&self) -> bool {
+        !(self.hw_error
+            || self.frequency_count_fail
+            || self.run_length_fail
+            || self.sparse_bit_fail
+            || self.long_run_fail
+            || self.poker_fail
+            || self.mono_bit_fail)
+    }
+}
+
+/// Run length test failure flags, TF1BR0 through TF6PBR1.
+const RUN_LENGTH_FAILURES: u32 = 0xfff;
+
 /// RNG interrupt handler.
 pub struct InterruptHandler<T: Instance> {
     _phantom: PhantomData<T>,
@@ -168,20 +232,31 @@ impl<'d> Rng<'d> {
     }
 
     /// Fill the given slice with random values.
-    pub fn blocking_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+    ///
+    /// Samples failing the entropy tests are discarded and regenerated until one passes, so this
+    /// blocks for as long as the TRNG keeps failing.
+    pub fn blocking_fill_bytes(&mut self, dest: &mut [u8]) {
         // We have a total of 16 words (512 bits) of entropy at our
         // disposal. The idea here is to read all bits and copy the
         // necessary bytes to the slice.
         for chunk in dest.chunks_mut(64) {
-            self.blocking_fill_chunk(chunk)?;
+            while let Err(e) = self.blocking_fill_chunk(chunk) {
+                warn!("Entropy sample rejected, retrying: {}", e);
+                self.refresh();
+            }
         }
-
-        Ok(())
     }
 
     fn blocking_fill_chunk(&mut self, chunk: &mut [u8]) -> Result<(), Error> {
         // wait for valid entropy
-        while self.info.regs.mctl().read().ent_val().bit_is_clear() {}
+        loop {
+            let mctl = self.info.regs.mctl().read();
+            if mctl.ent_val().bit_is_set() {
+                break;
+            } else if mctl.err().bit_is_set() {
+                return Err(Error::HwError);
+            }
+        }
 
         self.fill_chunk_inner(chunk)?;
 
@@ -234,13 +309,7 @@ impl<'d> Rng<'d> {
 
         // Exit early if we got an error
         if res.is_err() {
-            // Clear HW error
-            self.info.regs.mctl().modify(|_, w| w.err().clear_bit_by_one());
-
-            // Reading the last element restarts the generation
-            if let Some(ent) = self.info.regs.ent_iter().last() {
-                ent.read().bits();
-            }
+            self.refresh();
             return res;
         }
 
@@ -253,6 +322,44 @@ impl<'d> Rng<'d> {
         res
     }
 
+    /// Check whether a sample of entropy is ready to be read, without waiting.
+    pub fn entropy_ready(&self) -> bool {
+        self.info.regs.mctl().read().ent_val().bit_is_set()
+    }
+
+    /// Read the results of the hardware health tests.
+    pub fn health_report(&self) -> HealthReport {
+        let regs = &self.info.regs;
+        let mctl = regs.mctl().read();
+        let status = regs.status().read();
+
+        HealthReport {
+            hw_error: mctl.err().bit_is_set(),
+            frequency_count_fail: mctl.fct_fail().bit_is_set(),
+            run_length_fail: status.bits() & RUN_LENGTH_FAILURES != 0,
+            sparse_bit_fail: status.tfsb().bit_is_set(),
+            long_run_fail: status.tflr().bit_is_set(),
+            poker_fail: status.tfp().bit_is_set(),
+            mono_bit_fail: status.tfmb().bit_is_set(),
+            retry_count: status.retry_ct().bits(),
+            frequency_count: regs.frqcnt().read().frq_ct().bits(),
+            mono_bit_count: regs.scmc().read().mono_ct().bits(),
+            poker_square: regs.pkrsq().read().pkr_sq().bits(),
+            total_samples: regs.totsam().read().tot_sam().bits(),
+        }
+    }
+
+    /// Discard the current sample after a failure and start generating a new one.
+    fn refresh(&mut self) {
+        // Clear HW error
+        self.info.regs.mctl().modify(|_, w| w.err().clear_bit_by_one());
+
+        // Reading the last element restarts the generation
+        if let Some(ent) = self.info.regs.ent_iter().last() {
+            ent.read().bits();
+        }
+    }
+
     fn mask_interrupts(&mut self) {
         self.info.regs.int_mask().write(|w| {
             w.ent_val()
@@ -353,27 +460,27 @@ impl<'d> Rng<'d> {
     }
 }
 
-impl TryRngCore for Rng<'_> {
-    type Error = Error;
-
-    fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+// Failed samples are retried internally, `TryRngCore` comes from the blanket impl with an
+// infallible error
+impl RngCore for Rng<'_> {
+    fn next_u32(&mut self) -> u32 {
         let mut bytes = [0u8; 4];
-        self.blocking_fill_bytes(&mut bytes)?;
-        Ok(u32::from_ne_bytes(bytes))
+        self.blocking_fill_bytes(&mut bytes);
+        u32::from_ne_bytes(bytes)
     }
 
-    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+    fn next_u64(&mut self) -> u64 {
         let mut bytes = [0u8; 8];
-        self.blocking_fill_bytes(&mut bytes)?;
-        Ok(u64::from_ne_bytes(bytes))
+        self.blocking_fill_bytes(&mut bytes);
+        u64::from_ne_bytes(bytes)
     }
 
-    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Self::Error> {
-        self.blocking_fill_bytes(dest)
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.blocking_fill_bytes(dest);
     }
 }
 
-impl TryCryptoRng for Rng<'_> {}
+impl CryptoRng for Rng<'_> {}
 
 struct Info {
     regs: crate::pac::Trng,
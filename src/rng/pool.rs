@@ -0,0 +1,74 @@
+//! Pool of fresh entropy, kept full by a background task
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::pipe::Pipe;
+
+use super::Rng;
+
+/// Size of the chunks the filler reads from the TRNG, one full sample.
+const CHUNK_LEN: usize = 64;
+
+/// Buffer of `N` bytes of TRNG output, so tasks can take random bytes without waiting for the
+/// hardware.
+///
+/// The pool is filled by [`EntropyPool::run`], which is meant to be spawned as its own task.
+/// Every byte is handed out once at most.
+pub struct EntropyPool<const N: usize> {
+    pipe: Pipe<CriticalSectionRawMutex, N>,
+}
+
+impl<const N: usize> EntropyPool<N> {
+    /// Create a new, empty pool.
+    pub const fn new() -> Self {
+        Self { pipe: Pipe::new() }
+    }
+
+    /// Keep the pool full, never returns.
+    pub async fn run(&self, mut rng: Rng<'_>) -> ! {
+        let mut chunk = [0u8; CHUNK_LEN];
+        loop {
+            while let Err(e) = rng.async_fill_bytes(&mut chunk).await {
+                warn!("Entropy sample rejected, retrying: {}", e);
+            }
+            self.pipe.write_all(&chunk).await;
+        }
+    }
+
+    /// Number of bytes currently available.
+    pub fn available(&self) -> usize {
+        self.pipe.len()
+    }
+
+    /// Fill `dest` with random bytes, waiting for the filler if the pool runs dry.
+    pub async fn take(&self, mut dest: &mut [u8]) {
+        while !dest.is_empty() {
+            let count = self.pipe.read(dest).await;
+            dest = dest.get_mut(count..).unwrap_or_default();
+        }
+    }
+
+    /// Fill `dest` with random bytes without waiting.
+    ///
+    /// Returns `false` if the pool does not hold enough bytes, the contents of `dest` are then
+    /// unspecified.
+    pub fn try_take(&self, mut dest: &mut [u8]) -> bool {
+        if self.pipe.len() < dest.len() {
+            return false;
+        }
+
+        // Another task may drain the pool in the meantime, the bytes read are then discarded
+        while !dest.is_empty() {
+            match self.pipe.try_read(dest) {
+                Ok(count) => dest = dest.get_mut(count..).unwrap_or_default(),
+                Err(_) => return false,
+            }
+        }
+        true
+    }
+}
+
+impl<const N: usize> Default for EntropyPool<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
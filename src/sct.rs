@@ -0,0 +1,753 @@
+//! SCTimer/PWM (SCT) event and state machine
+//!
+//! The SCT is a 32-bit counter with 16 match registers and 16 events. Each event fires on a
+//! match, an input or output condition, or a combination, and only in the states it is enabled
+//! in. Events can move the state machine to another state, set or clear outputs, and limit,
+//! stop, start or halt the counter, which is enough for custom waveforms and protocol generators.
+//!
+//! A [`Program`] describes the matches, events and outputs, and [`Sct::new`] loads it:
+//!
+//! ```rust,ignore
+//! let mut program = Program::new();
+//! let period = program.add_match(1000)?;
+//! let duty = program.add_match(250)?;
+//! program.add_event(Event::new(Condition::Match(period)).limit().set_output(0))?;
+//! program.add_event(Event::new(Condition::Match(duty)).clear_output(0))?;
+//!
+//! let mut sct = Sct::new(p.SCT0, Irqs, SCTClockSource::FFRO, &program);
+//! sct.start();
+//! ```
+//!
+//! The SCT inputs and outputs have to be routed to pins by the caller.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_hal_internal::Peri;
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::interrupt;
+use crate::interrupt::typelevel::{Binding, Interrupt};
+use crate::peripherals::SCT0;
+use crate::pwm::SCTClockSource;
+use crate::pwm::sealed::SCTimer;
+
+static WAKER: AtomicWaker = AtomicWaker::new();
+
+const MATCH_COUNT: usize = 16;
+const EVENT_COUNT: usize = 16;
+const OUTPUT_COUNT: u8 = 10;
+const INPUT_COUNT: u8 = 8;
+/// The event state masks only cover the first 16 states
+const STATE_COUNT: u8 = 16;
+
+/// SCT error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Error {
+    /// All 16 match registers are in use
+    TooManyMatches,
+    /// All 16 events are in use
+    TooManyEvents,
+    /// Match register doesn't belong to this program
+    InvalidMatch,
+    /// Event doesn't belong to this program
+    InvalidEvent,
+    /// State number is 16 or above
+    InvalidState,
+    /// Input number is 8 or above
+    InvalidInput,
+    /// Output number is 10 or above
+    InvalidOutput,
+}
+
+/// Match register of a [`Program`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MatchId(u8);
+
+/// Event of a [`Program`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EventId(u8);
+
+/// SCT input or output signal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Signal {
+    /// SCT input 0 to 7
+    Input(u8),
+    /// SCT output 0 to 9
+    Output(u8),
+}
+
+/// Level or edge of a [`Signal`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IoCondition {
+    /// Signal is low
+    Low,
+    /// Signal rises
+    Rise,
+    /// Signal falls
+    Fall,
+    /// Signal is high
+    High,
+}
+
+/// What makes an event fire
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Condition {
+    /// Counter matches the match register
+    Match(MatchId),
+    /// Signal condition
+    Io(Signal, IoCondition),
+    /// Counter matches the match register while the signal condition holds
+    And(MatchId, Signal, IoCondition),
+    /// Counter matches the match register or the signal condition holds
+    Or(MatchId, Signal, IoCondition),
+}
+
+/// Counting direction an event fires in, only relevant with [`Program::with_bidirectional`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    /// Both directions
+    #[default]
+    Any,
+    /// Counting up
+    Up,
+    /// Counting down
+    Down,
+}
+
+/// Output level when one event sets and another clears an output at the same time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Conflict {
+    /// Keep the output
+    #[default]
+    NoChange,
+    /// Set the output
+    Set,
+    /// Clear the output
+    Clear,
+    /// Toggle the output
+    Toggle,
+}
+
+impl From<Conflict> for u32 {
+    fn from(value: Conflict) -> Self {
+        match value {
+            Conflict::NoChange => 0,
+            Conflict::Set => 1,
+            Conflict::Clear => 2,
+            Conflict::Toggle => 3,
+        }
+    }
+}
+
+/// Event of the state machine
+///
+/// An event is enabled in state 0 only, unless [`Event::in_state`] or [`Event::in_all_states`]
+/// say otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Event {
+    condition: Condition,
+    direction: Direction,
+    states: u32,
+    next_state: Option<u8>,
+    set_outputs: u16,
+    clear_outputs: u16,
+    limit: bool,
+    halt: bool,
+    stop: bool,
+    start: bool,
+}
+
+impl Event {
+    /// Event firing on `condition`
+    pub fn new(condition: Condition) -> Self {
+        Self {
+            condition,
+            direction: Direction::Any,
+            states: 0,
+            next_state: None,
+            set_outputs: 0,
+            clear_outputs: 0,
+            limit: false,
+            halt: false,
+            stop: false,
+            start: false,
+        }
+    }
+
+    /// Enable the event in `state`, in addition to the states already given
+    pub fn in_state(mut self, state: u8) -> Self {
+        self.states |= 1u32.checked_shl(u32::from(state)).unwrap_or(u32::MAX);
+        self
+    }
+
+    /// Enable the event in every state
+    pub fn in_all_states(mut self) -> Self {
+        self.states = (1 << STATE_COUNT) - 1;
+        self
+    }
+
+    /// Only fire while counting in `direction`
+    pub fn when_counting(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Move the state machine to `state` when the event fires
+    pub fn goto_state(mut self, state: u8) -> Self {
+        self.next_state = Some(state);
+        self
+    }
+
+    /// Set `output` when the event fires
+    pub fn set_output(mut self, output: u8) -> Self {
+        self.set_outputs |= 1u16.checked_shl(u32::from(output)).unwrap_or(u16::MAX);
+        self
+    }
+
+    /// Clear `output` when the event fires
+    pub fn clear_output(mut self, output: u8) -> Self {
+        self.clear_outputs |= 1u16.checked_shl(u32::from(output)).unwrap_or(u16::MAX);
+        self
+    }
+
+    /// Reset the counter to 0 when the event fires, or reverse it when counting both ways
+    pub fn limit(mut self) -> Self {
+        self.limit = true;
+        self
+    }
+
+    /// Halt the counter when the event fires, only [`Sct::start`] restarts it
+    pub fn halt(mut self) -> Self {
+        self.halt = true;
+        self
+    }
+
+    /// Stop the counter when the event fires, events can still fire on signal conditions
+    pub fn stop(mut self) -> Self {
+        self.stop = true;
+        self
+    }
+
+    /// Restart a stopped counter when the event fires
+    pub fn start(mut self) -> Self {
+        self.start = true;
+        self
+    }
+
+    fn validate(&self, match_count: usize) -> Result<(), Error> {
+        let check_match = |id: MatchId| {
+            if usize::from(id.0) < match_count {
+                Ok(())
+            } else {
+                Err(Error::InvalidMatch)
+            }
+        };
+        let check_signal = |signal: Signal| match signal {
+            Signal::Input(n) if n >= INPUT_COUNT => Err(Error::InvalidInput),
+            Signal::Output(n) if n >= OUTPUT_COUNT => Err(Error::InvalidOutput),
+            _ => Ok(()),
+        };
+
+        match self.condition {
+            Condition::Match(id) => check_match(id)?,
+            Condition::Io(signal, _) => check_signal(signal)?,
+            Condition::And(id, signal, _) | Condition::Or(id, signal, _) => {
+                check_match(id)?;
+                check_signal(signal)?;
+            }
+        }
+
+        if self.states >> STATE_COUNT != 0 || self.next_state.is_some_and(|state| state >= STATE_COUNT) {
+            return Err(Error::InvalidState);
+        }
+
+        let outputs = self.set_outputs | self.clear_outputs;
+        if outputs >> OUTPUT_COUNT != 0 {
+            return Err(Error::InvalidOutput);
+        }
+
+        Ok(())
+    }
+}
+
+/// Matches, events and outputs of the SCT, loaded by [`Sct::new`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Program {
+    matches: [u32; MATCH_COUNT],
+    match_count: usize,
+    events: [Option<Event>; EVENT_COUNT],
+    event_count: usize,
+    initial_state: u8,
+    initial_outputs: u16,
+    conflicts: u32,
+    prescaler: u8,
+    bidirectional: bool,
+}
+
+impl Default for Program {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Program {
+    /// Empty program, counting up from state 0 with all outputs low
+    pub fn new() -> Self {
+        Self {
+            matches: [0; MATCH_COUNT],
+            match_count: 0,
+            events: [None; EVENT_COUNT],
+            event_count: 0,
+            initial_state: 0,
+            initial_outputs: 0,
+            conflicts: 0,
+            prescaler: 0,
+            bidirectional: false,
+        }
+    }
+
+    /// Add a match register, compared against the counter.
+    pub fn add_match(&mut self, value: u32) -> Result<MatchId, Error> {
+        let slot = self.matches.get_mut(self.match_count).ok_or(Error::TooManyMatches)?;
+        *slot = value;
+
+        let id = MatchId(self.match_count as u8);
+        self.match_count += 1;
+        Ok(id)
+    }
+
+    /// Add an event to the state machine.
+    pub fn add_event(&mut self, event: Event) -> Result<EventId, Error> {
+        event.validate(self.match_count)?;
+
+        let slot = self.events.get_mut(self.event_count).ok_or(Error::TooManyEvents)?;
+        *slot = Some(event);
+
+        let id = EventId(self.event_count as u8);
+        self.event_count += 1;
+        Ok(id)
+    }
+
+    /// Start the state machine in `state` instead of state 0.
+    pub fn with_initial_state(mut self, state: u8) -> Result<Self, Error> {
+        if state >= STATE_COUNT {
+            return Err(Error::InvalidState);
+        }
+        self.initial_state = state;
+        Ok(self)
+    }
+
+    /// Start `output` high instead of low.
+    pub fn with_initial_output(mut self, output: u8, high: bool) -> Result<Self, Error> {
+        if output >= OUTPUT_COUNT {
+            return Err(Error::InvalidOutput);
+        }
+        let bit = 1 << output;
+        self.initial_outputs = if high {
+            self.initial_outputs | bit
+        } else {
+            self.initial_outputs & !bit
+        };
+        Ok(self)
+    }
+
+    /// Resolve simultaneous set and clear of `output` with `conflict`.
+    pub fn with_conflict(mut self, output: u8, conflict: Conflict) -> Result<Self, Error> {
+        if output >= OUTPUT_COUNT {
+            return Err(Error::InvalidOutput);
+        }
+        let shift = 2 * u32::from(output);
+        self.conflicts = (self.conflicts & !(0b11 << shift)) | (u32::from(conflict) << shift);
+        Ok(self)
+    }
+
+    /// Divide the SCT clock by `prescaler + 1` for the counter.
+    pub fn with_prescaler(mut self, prescaler: u8) -> Self {
+        self.prescaler = prescaler;
+        self
+    }
+
+    /// Count up to the limit, then back down to 0, instead of restarting from 0.
+    pub fn with_bidirectional(mut self, bidirectional: bool) -> Self {
+        self.bidirectional = bidirectional;
+        self
+    }
+
+    fn events(&self) -> impl Iterator<Item = (usize, &Event)> {
+        self.events
+            .iter()
+            .enumerate()
+            .filter_map(|(n, event)| event.as_ref().map(|event| (n, event)))
+    }
+
+    fn event_mask(&self, f: impl Fn(&Event) -> bool) -> u16 {
+        self.events()
+            .filter(|(_, event)| f(event))
+            .fold(0, |mask, (n, _)| mask | (1 << n))
+    }
+}
+
+/// SCT interrupt handler
+pub struct InterruptHandler<T> {
+    _phantom: PhantomData<T>,
+}
+
+impl interrupt::typelevel::Handler<interrupt::typelevel::SCT0> for InterruptHandler<SCT0> {
+    unsafe fn on_interrupt() {
+        // SAFETY: only disables the pending event interrupts, the task clears the flags
+        let regs = unsafe { crate::pac::Sct0::steal() };
+
+        let pending = regs.evflag().read().flag().bits();
+        regs.even()
+            .modify(|r, w| unsafe { w.ien().bits(r.ien().bits() & !pending) });
+        WAKER.wake();
+    }
+}
+
+/// SCT driver
+pub struct Sct<'d> {
+    regs: crate::pac::Sct0,
+    event_count: usize,
+    _lifetime: PhantomData<&'d ()>,
+}
+
+impl<'d> Sct<'d> {
+    /// Load `program` into the SCT, clocked from `clock`. The counter is halted until
+    /// [`Sct::start`].
+    pub fn new(
+        _sct: Peri<'d, SCT0>,
+        _irq: impl Binding<interrupt::typelevel::SCT0, InterruptHandler<SCT0>> + 'd,
+        clock: SCTClockSource,
+        program: &Program,
+    ) -> Self {
+        SCT0::set_clock_source(clock);
+        SCT0::set_divisor(0);
+
+        // SAFETY: the SCT0 singleton is owned by this driver
+        let regs = unsafe { crate::pac::Sct0::steal() };
+        let inst = Self {
+            regs,
+            event_count: program.event_count,
+            _lifetime: PhantomData,
+        };
+
+        inst.load(program);
+
+        interrupt::typelevel::SCT0::unpend();
+        unsafe { interrupt::typelevel::SCT0::enable() };
+
+        inst
+    }
+
+    fn load(&self, program: &Program) {
+        let regs = &self.regs;
+
+        // unified (32 bit) counter, halted while being configured
+        regs.config()
+            .modify(|_, w| w.unify().unified_counter().clkmode().system_clock_mode());
+        regs.ctrl().modify(|_, w| w.halt_l().set_bit());
+        regs.ctrl().modify(|_, w| unsafe {
+            w.clrctr_l()
+                .set_bit()
+                .bidir_l()
+                .bit(program.bidirectional)
+                .pre_l()
+                .bits(program.prescaler)
+        });
+
+        // all registers in match mode
+        regs.regmode().write(|w| unsafe { w.regmod_l().bits(0) });
+        for (n, value) in program.matches.iter().take(program.match_count).enumerate() {
+            self.write_match(n, *value, false);
+        }
+
+        for n in 0..EVENT_COUNT {
+            let event = program.events.get(n).copied().flatten();
+            let ev = regs.ev(n);
+
+            let Some(event) = event else {
+                // disable unused events in all states
+                ev.ev_state().write(|w| unsafe { w.statemskn().bits(0) });
+                continue;
+            };
+
+            // states validated by Program::add_event
+            let states = if event.states == 0 { 1 } else { event.states as u16 };
+            ev.ev_state().write(|w| unsafe { w.statemskn().bits(states) });
+
+            ev.ev_ctrl().write(|w| {
+                let (m, io) = match event.condition {
+                    Condition::Match(m) => {
+                        w.combmode().match_();
+                        (Some(m), None)
+                    }
+                    Condition::Io(signal, cond) => {
+                        w.combmode().io();
+                        (None, Some((signal, cond)))
+                    }
+                    Condition::And(m, signal, cond) => {
+                        w.combmode().and();
+                        (Some(m), Some((signal, cond)))
+                    }
+                    Condition::Or(m, signal, cond) => {
+                        w.combmode().or();
+                        (Some(m), Some((signal, cond)))
+                    }
+                };
+
+                if let Some(m) = m {
+                    // SAFETY: match number validated by Program::add_event
+                    unsafe { w.matchsel().bits(m.0) };
+                }
+
+                if let Some((signal, cond)) = io {
+                    let number = match signal {
+                        Signal::Input(n) => {
+                            w.outsel().input();
+                            n
+                        }
+                        Signal::Output(n) => {
+                            w.outsel().output();
+                            n
+                        }
+                    };
+                    // SAFETY: signal number validated by Program::add_event
+                    unsafe { w.iosel().bits(number) };
+
+                    match cond {
+                        IoCondition::Low => w.iocond().low(),
+                        IoCondition::Rise => w.iocond().rise(),
+                        IoCondition::Fall => w.iocond().fall(),
+                        IoCondition::High => w.iocond().high(),
+                    };
+                }
+
+                match event.next_state {
+                    // SAFETY: state number validated by Program::add_event
+                    Some(state) => unsafe { w.stateld().load().statev().bits(state) },
+                    None => unsafe { w.stateld().add().statev().bits(0) },
+                };
+
+                match event.direction {
+                    Direction::Any => w.direction().direction_independent(),
+                    Direction::Up => w.direction().counting_up(),
+                    Direction::Down => w.direction().counting_down(),
+                }
+            });
+        }
+
+        regs.limit()
+            .write(|w| unsafe { w.limmsk_l().bits(program.event_mask(|e| e.limit)) });
+        regs.halt()
+            .write(|w| unsafe { w.haltmsk_l().bits(program.event_mask(|e| e.halt)) });
+        regs.stop()
+            .write(|w| unsafe { w.stopmsk_l().bits(program.event_mask(|e| e.stop)) });
+        regs.start()
+            .write(|w| unsafe { w.startmsk_l().bits(program.event_mask(|e| e.start)) });
+
+        for output in 0..OUTPUT_COUNT {
+            let bit = 1 << output;
+            let out = regs.out(usize::from(output));
+
+            out.out_set()
+                .write(|w| unsafe { w.set_().bits(program.event_mask(|e| e.set_outputs & bit != 0)) });
+            out.out_clr()
+                .write(|w| unsafe { w.clr().bits(program.event_mask(|e| e.clear_outputs & bit != 0)) });
+        }
+
+        // outputs follow the events in both counting directions
+        regs.outputdirctrl().write(|w| unsafe { w.bits(0) });
+        regs.res().write(|w| unsafe { w.bits(program.conflicts) });
+        regs.output()
+            .write(|w| unsafe { w.out().bits(program.initial_outputs) });
+        regs.state()
+            .write(|w| unsafe { w.state_l().bits(program.initial_state) });
+
+        // no event interrupts until waited for, and no stale flags
+        regs.even().write(|w| unsafe { w.ien().bits(0) });
+        regs.evflag().write(|w| unsafe { w.flag().bits(u16::MAX) });
+    }
+
+    fn write_match(&self, n: usize, value: u32, reload_only: bool) {
+        let regs = &self.regs;
+
+        macro_rules! write_match {
+            ($($n:literal),+) => {
+                paste::paste! {
+                    match n {
+                        $($n => {
+                            if !reload_only {
+                                regs.[<match $n>]().write(|w| unsafe { w.bits(value) });
+                            }
+                            regs.[<matchrel $n>]().write(|w| unsafe { w.bits(value) });
+                        })+
+                        _ => {}
+                    }
+                }
+            };
+        }
+
+        write_match!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+    }
+
+    /// Run the counter.
+    pub fn start(&mut self) {
+        self.regs
+            .ctrl()
+            .modify(|_, w| w.halt_l().clear_bit().stop_l().clear_bit());
+    }
+
+    /// Halt the counter, no events fire until [`Sct::start`].
+    pub fn halt(&mut self) {
+        self.regs.ctrl().modify(|_, w| w.halt_l().set_bit());
+    }
+
+    /// Current counter value.
+    pub fn count(&self) -> u32 {
+        self.regs.count().read().bits()
+    }
+
+    /// Current state of the state machine.
+    pub fn state(&self) -> u8 {
+        self.regs.state().read().state_l().bits()
+    }
+
+    /// Change the value of a match register, taking effect when the counter next reaches its
+    /// limit so a waveform period is never cut short.
+    pub fn set_match(&mut self, id: MatchId, value: u32) {
+        self.write_match(usize::from(id.0), value, true);
+    }
+
+    /// Whether `event` fired since the last check, clearing its flag.
+    pub fn event_fired(&mut self, event: EventId) -> bool {
+        let bit = 1 << event.0;
+
+        if self.regs.evflag().read().flag().bits() & bit == 0 {
+            return false;
+        }
+
+        self.regs.evflag().write(|w| unsafe { w.flag().bits(bit) });
+        true
+    }
+
+    /// Wait for `event` to fire.
+    pub async fn wait_for_event(&mut self, event: EventId) -> Result<(), Error> {
+        if usize::from(event.0) >= self.event_count {
+            return Err(Error::InvalidEvent);
+        }
+
+        let bit = 1 << event.0;
+        let regs = &self.regs;
+
+        regs.evflag().write(|w| unsafe { w.flag().bits(bit) });
+        regs.even().modify(|r, w| unsafe { w.ien().bits(r.ien().bits() | bit) });
+
+        poll_fn(|cx| {
+            WAKER.register(cx.waker());
+
+            if regs.evflag().read().flag().bits() & bit != 0 {
+                regs.evflag().write(|w| unsafe { w.flag().bits(bit) });
+                regs.even()
+                    .modify(|r, w| unsafe { w.ien().bits(r.ien().bits() & !bit) });
+                return Poll::Ready(());
+            }
+
+            Poll::Pending
+        })
+        .await;
+
+        Ok(())
+    }
+}
+
+impl Drop for Sct<'_> {
+    fn drop(&mut self) {
+        self.regs.even().write(|w| unsafe { w.ien().bits(0) });
+        self.regs.ctrl().modify(|_, w| w.halt_l().set_bit());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_rejects_foreign_match() {
+        let mut program = Program::new();
+        assert!(matches!(
+            program.add_event(Event::new(Condition::Match(MatchId(0)))),
+            Err(Error::InvalidMatch)
+        ));
+
+        let id = program.add_match(100);
+        assert!(matches!(id, Ok(MatchId(0))));
+        assert!(program.add_event(Event::new(Condition::Match(MatchId(0)))).is_ok());
+    }
+
+    #[test]
+    fn test_program_rejects_bad_outputs_and_states() {
+        let mut program = Program::new();
+        let event = Event::new(Condition::Io(Signal::Input(0), IoCondition::Rise));
+
+        assert!(matches!(
+            program.add_event(event.set_output(OUTPUT_COUNT)),
+            Err(Error::InvalidOutput)
+        ));
+        assert!(matches!(
+            program.add_event(event.goto_state(STATE_COUNT)),
+            Err(Error::InvalidState)
+        ));
+        assert!(matches!(
+            program.add_event(event.in_state(STATE_COUNT)),
+            Err(Error::InvalidState)
+        ));
+        assert!(matches!(
+            program.add_event(Event::new(Condition::Io(Signal::Input(INPUT_COUNT), IoCondition::Low))),
+            Err(Error::InvalidInput)
+        ));
+    }
+
+    #[test]
+    fn test_program_capacity() {
+        let mut program = Program::new();
+        for _ in 0..MATCH_COUNT {
+            assert!(program.add_match(0).is_ok());
+        }
+        assert!(matches!(program.add_match(0), Err(Error::TooManyMatches)));
+
+        let event = Event::new(Condition::Match(MatchId(0)));
+        for _ in 0..EVENT_COUNT {
+            assert!(program.add_event(event).is_ok());
+        }
+        assert!(matches!(program.add_event(event), Err(Error::TooManyEvents)));
+    }
+
+    #[test]
+    fn test_event_masks() {
+        let mut program = Program::new();
+        let m = program.add_match(10);
+        assert!(m.is_ok());
+
+        let event = Event::new(Condition::Match(MatchId(0)));
+        assert!(program.add_event(event.limit().set_output(3)).is_ok());
+        assert!(program.add_event(event.clear_output(3)).is_ok());
+        assert!(program.add_event(event.limit()).is_ok());
+
+        assert_eq!(program.event_mask(|e| e.limit), 0b101);
+        assert_eq!(program.event_mask(|e| e.set_outputs & (1 << 3) != 0), 0b001);
+        assert_eq!(program.event_mask(|e| e.clear_outputs & (1 << 3) != 0), 0b010);
+    }
+}
@@ -1,15 +1,32 @@
 //! Serial Peripheral Interface (SPI) driver.
+//!
+//! FLEXCOMM14 is the high-speed SPI: its pins are dedicated to it and driven at full strength,
+//! so it can run at the full 48 MHz of the FFRO. FLEXCOMM15 only supports I2C.
+//!
+//! Async drivers either move data with a pair of DMA channels, see [`Spi::new_async_with_dma`],
+//! or from the FIFO interrupts alone, see [`Spi::new_async`] and its TX-only and RX-only
+//! variants. Both flavors offer the same API.
+//!
+//! [`Spi`] implements [`SetConfig`], so several devices with their own chip select and
+//! [`Config`] can share a bus through `embassy_embedded_hal::shared_bus`: `SpiDeviceWithConfig`
+//! applies the device configuration, frequency included, before each transaction.
 
 use core::future::{Future, poll_fn};
 use core::marker::PhantomData;
 use core::task::Poll;
 
 use embassy_embedded_hal::SetConfig;
+use embassy_futures::join::join;
+use embassy_futures::select::{Either, select};
+use embassy_hal_internal::drop::OnDrop;
 use embassy_hal_internal::{Peri, PeripheralType};
 use embassy_sync::waitqueue::AtomicWaker;
 pub use embedded_hal_1::spi::{MODE_0, MODE_1, MODE_2, MODE_3, Mode, Phase, Polarity};
 use paste::paste;
 
+use crate::dma::LinkedDescriptor;
+use crate::dma::channel::Channel;
+use crate::dma::transfer::{Transfer, TransferOptions, Width};
 use crate::flexcomm::{Clock, FlexcommRef};
 use crate::gpio::{AnyPin, GpioPin as Pin};
 use crate::interrupt::typelevel::Interrupt;
@@ -36,13 +53,220 @@ impl IoMode for Async {}
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[non_exhaustive]
 pub enum Error {
-    // No errors for now.
+    /// RX FIFO overflow, the host clocked in data faster than it could be stored
+    Overrun,
+    /// The requested configuration is not supported, e.g. a DMA channel is unavailable
+    UnsupportedConfiguration,
+    /// The configured frames are larger than the words of the transfer, use the `_u16` methods
+    WordTooSmall,
+}
+
+/// FIFOWR control bits, as seen from a halfword write to the upper half of the register.
+const fn fifowr_ctrl(frame_len: u8) -> u16 {
+    (frame_len as u16) << 8
+}
+const FIFOWR_CTRL_LEN_8BIT: u16 = fifowr_ctrl(7);
+const FIFOWR_CTRL_RXIGNORE: u16 = 1 << 6;
+
+/// Spi word type, `u8` or `u16`.
+///
+/// Words are sent and received in frames of [`Config::data_bits`] bits, use `u16` for frames
+/// larger than 8 bits: transfers of `u8` words fail with [`Error::WordTooSmall`] then.
+#[allow(private_bounds)]
+pub trait Word: SealedWord + Copy + Default + 'static {}
+
+trait SealedWord {
+    const WIDTH: Width;
+
+    const BITS: u8;
+
+    fn to_fifo(self) -> u16;
+
+    fn from_fifo(data: u16) -> Self;
+}
+
+impl SealedWord for u8 {
+    const WIDTH: Width = Width::Bit8;
+
+    const BITS: u8 = 8;
+
+    fn to_fifo(self) -> u16 {
+        self as u16
+    }
+
+    fn from_fifo(data: u16) -> Self {
+        data as u8
+    }
+}
+impl Word for u8 {}
+
+impl SealedWord for u16 {
+    const WIDTH: Width = Width::Bit16;
+
+    const BITS: u8 = 16;
+
+    fn to_fifo(self) -> u16 {
+        self
+    }
+
+    fn from_fifo(data: u16) -> Self {
+        data
+    }
+}
+impl Word for u16 {}
+
+/// Spi frame size, 4 to 16 bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DataBits(u8);
+
+impl DataBits {
+    /// 8-bit frames.
+    pub const EIGHT: Self = Self(8);
+
+    /// 16-bit frames.
+    pub const SIXTEEN: Self = Self(16);
+
+    /// Frames of `bits` bits, `None` unless 4 to 16.
+    #[must_use]
+    pub const fn new(bits: u8) -> Option<Self> {
+        match bits {
+            4..=16 => Some(Self(bits)),
+            _ => None,
+        }
+    }
+
+    /// Size of the frames in bits.
+    #[must_use]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for DataBits {
+    fn default() -> Self {
+        Self::EIGHT
+    }
+}
+
+/// STAT slave select flags, the register is not readable per field.
+const STAT_SSA: u32 = 1 << 4;
+const STAT_SSD: u32 = 1 << 5;
+
+/// Byte sent by the slave when it has nothing to send.
+const SLAVE_DUMMY_BYTE: u8 = 0xff;
+
+/// Number of entries of the TX and RX FIFOs.
+const FIFO_DEPTH: u8 = 8;
+
+/// Largest divider of the source clock, DIVVAL + 1.
+const MAX_DIVIDER: u32 = u16::MAX as u32 + 1;
+
+/// Number of linked descriptors chained behind each DMA channel descriptor.
+const DMA_LINKED_DESCRIPTORS: usize = 7;
+
+/// Words moved by a single run of chained DMA descriptors, longer transfers take several runs.
+const DMA_RUN_LEN: usize = dma::MAX_TRANSFER_COUNT * (DMA_LINKED_DESCRIPTORS + 1);
+
+/// DMA channels of an [`Spi`] and the descriptors chained behind them.
+struct SpiDma<'a> {
+    tx: Channel<'a>,
+    rx: Channel<'a>,
+    tx_descriptors: [LinkedDescriptor; DMA_LINKED_DESCRIPTORS],
+    rx_descriptors: [LinkedDescriptor; DMA_LINKED_DESCRIPTORS],
+}
+
+impl<'a> SpiDma<'a> {
+    fn new(tx: Channel<'a>, rx: Channel<'a>) -> Self {
+        Self {
+            tx,
+            rx,
+            tx_descriptors: [LinkedDescriptor::new(); DMA_LINKED_DESCRIPTORS],
+            rx_descriptors: [LinkedDescriptor::new(); DMA_LINKED_DESCRIPTORS],
+        }
+    }
+
+    /// Clock `count` words out of `tx`, storing the received words to `rx` unless it is `None`.
+    /// `frame_len` is the value of the FIFOWR LEN field.
+    ///
+    /// # Safety
+    ///
+    /// `tx` must be valid for reads and `rx` for writes of `count` words, at most
+    /// [`DMA_RUN_LEN`]. They may overlap: each word is sent before the one replacing it is
+    /// received.
+    async unsafe fn transfer<W: Word>(
+        &mut self,
+        regs: &'static crate::pac::spi0::RegisterBlock,
+        frame_len: u8,
+        tx: *const W,
+        rx: Option<*mut W>,
+        count: usize,
+    ) -> Result<(), Error> {
+        // Disable DMA on completion/cancellation
+        let _dma_guard = OnDrop::new(|| {
+            regs.fifocfg().modify(|_, w| w.dmatx().disabled().dmarx().disabled());
+        });
+
+        regs.fifostat().modify(|_, w| w.txerr().set_bit().rxerr().set_bit());
+
+        // The control bits of a halfword write to the upper half of FIFOWR apply to all the
+        // following data writes, so DMA only has to write the data.
+        let ctrl = fifowr_ctrl(frame_len) | if rx.is_none() { FIFOWR_CTRL_RXIGNORE } else { 0 };
+        // SAFETY: FIFOWR is a valid, aligned 32-bit register
+        unsafe { (regs.fifowr().as_ptr() as *mut u16).add(1).write_volatile(ctrl) };
+
+        let mut options = TransferOptions::default();
+        options.width = W::WIDTH;
+        let len = count * size_of::<W>();
+
+        let Self {
+            tx: tx_dma,
+            rx: rx_dma,
+            tx_descriptors,
+            rx_descriptors,
+        } = self;
+
+        let fifowr = regs.fifowr().as_ptr() as *mut u8;
+        match rx {
+            Some(rx) => {
+                regs.fifocfg().modify(|_, w| w.dmatx().enabled().dmarx().enabled());
+
+                // Start reception first so no received word is missed
+                let fiford = regs.fiford().as_ptr() as *const u8;
+                // SAFETY: the caller guarantees `rx` and `tx` are valid for the whole transfer,
+                // which is aborted if this future is dropped.
+                let rx_transfer =
+                    unsafe { Transfer::new_read_linked(rx_dma, fiford, rx as *mut u8, len, rx_descriptors, options) }
+                        .map_err(|_| Error::UnsupportedConfiguration)?;
+                let tx_transfer = unsafe {
+                    Transfer::new_write_linked(tx_dma, tx as *const u8, len, fifowr, tx_descriptors, options)
+                }
+                .map_err(|_| Error::UnsupportedConfiguration)?;
+
+                join(rx_transfer, tx_transfer).await;
+            }
+            None => {
+                regs.fifocfg().modify(|_, w| w.dmatx().enabled());
+
+                // SAFETY: the caller guarantees `tx` is valid for the whole transfer, which is
+                // aborted if this future is dropped.
+                unsafe { Transfer::new_write_linked(tx_dma, tx as *const u8, len, fifowr, tx_descriptors, options) }
+                    .map_err(|_| Error::UnsupportedConfiguration)?
+                    .await;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Spi driver.
 pub struct Spi<'a, M: IoMode> {
     info: Info,
-    _flexcomm: FlexcommRef,
+    flexcomm: FlexcommRef,
+    dma: Option<SpiDma<'a>>,
+    data_bits: DataBits,
+    fill_word: u16,
     _phantom: PhantomData<&'a M>,
 }
 
@@ -99,7 +323,23 @@ impl<'a> Spi<'a, Blocking> {
 
 impl<'a, M: IoMode> Spi<'a, M> {
     /// Read data from Spi blocking execution until done.
+    ///
+    /// [`Config::fill_word`] is sent for every word read.
     pub fn blocking_read(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.blocking_read_words(data)
+    }
+
+    /// [`Self::blocking_read`] with 16-bit words, for frames larger than 8 bits.
+    pub fn blocking_read_u16(&mut self, data: &mut [u16]) -> Result<(), Error> {
+        self.blocking_read_words(data)
+    }
+
+    fn blocking_read_words<W: Word>(&mut self, data: &mut [W]) -> Result<(), Error> {
+        self.check_word::<W>()?;
+
+        let len = self.frame_len();
+        let fill = self.fill_word;
+
         critical_section::with(|_| {
             self.info
                 .regs
@@ -107,15 +347,15 @@ impl<'a, M: IoMode> Spi<'a, M> {
                 .modify(|_, w| w.txerr().set_bit().rxerr().set_bit());
 
             for word in data.iter_mut() {
-                // wait until we have data in the RxFIFO.
-                while self.info.regs.fifostat().read().rxnotempty().bit_is_clear() {}
-
                 self.info
                     .regs
                     .fifowr()
-                    .write(|w| unsafe { w.txdata().bits(*word as u16).len().bits(7) });
+                    .write(|w| unsafe { w.txdata().bits(fill).len().bits(len) });
+
+                // wait until we have data in the RxFIFO.
+                while self.info.regs.fifostat().read().rxnotempty().bit_is_clear() {}
 
-                *word = self.info.regs.fiford().read().rxdata().bits() as u8;
+                *word = W::from_fifo(self.info.regs.fiford().read().rxdata().bits());
             }
         });
 
@@ -124,6 +364,19 @@ impl<'a, M: IoMode> Spi<'a, M> {
 
     /// Write data to Spi blocking execution until done.
     pub fn blocking_write(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.blocking_write_words(data)
+    }
+
+    /// [`Self::blocking_write`] with 16-bit words, for frames larger than 8 bits.
+    pub fn blocking_write_u16(&mut self, data: &[u16]) -> Result<(), Error> {
+        self.blocking_write_words(data)
+    }
+
+    fn blocking_write_words<W: Word>(&mut self, data: &[W]) -> Result<(), Error> {
+        self.check_word::<W>()?;
+
+        let len = self.frame_len();
+
         critical_section::with(|_| {
             self.info
                 .regs
@@ -135,7 +388,7 @@ impl<'a, M: IoMode> Spi<'a, M> {
                 while self.info.regs.fifostat().read().txnotfull().bit_is_clear() {}
 
                 self.info.regs.fifowr().write(|w| {
-                    unsafe { w.txdata().bits(*word as u16).len().bits(7) }
+                    unsafe { w.txdata().bits(word.to_fifo()).len().bits(len) }
                         .rxignore()
                         .set_bit();
 
@@ -152,8 +405,24 @@ impl<'a, M: IoMode> Spi<'a, M> {
     }
 
     /// Transfer data to SPI blocking execution until done.
+    ///
+    /// `read` and `write` may differ in length: [`Config::fill_word`] is sent past the end of
+    /// `write`, and words received past the end of `read` are discarded.
     pub fn blocking_transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Error> {
+        self.blocking_transfer_words(read, write)
+    }
+
+    /// [`Self::blocking_transfer`] with 16-bit words, for frames larger than 8 bits.
+    pub fn blocking_transfer_u16(&mut self, read: &mut [u16], write: &[u16]) -> Result<(), Error> {
+        self.blocking_transfer_words(read, write)
+    }
+
+    fn blocking_transfer_words<W: Word>(&mut self, read: &mut [W], write: &[W]) -> Result<(), Error> {
+        self.check_word::<W>()?;
+
         let len = read.len().max(write.len());
+        let frame_len = self.frame_len();
+        let fill = self.fill_word;
 
         critical_section::with(|_| {
             self.info
@@ -162,13 +431,13 @@ impl<'a, M: IoMode> Spi<'a, M> {
                 .modify(|_, w| w.txerr().set_bit().rxerr().set_bit());
 
             for i in 0..len {
-                let wb = write.get(i).copied().unwrap_or(0);
+                let wb = write.get(i).map_or(fill, |w| w.to_fifo());
 
                 // wait until we have space in the TxFIFO.
                 while self.info.regs.fifostat().read().txnotfull().bit_is_clear() {}
 
                 self.info.regs.fifowr().write(|w| {
-                    unsafe { w.txdata().bits(wb as u16).len().bits(7) };
+                    unsafe { w.txdata().bits(wb).len().bits(frame_len) };
 
                     if i == len - 1 {
                         w.eot().set_bit();
@@ -180,10 +449,10 @@ impl<'a, M: IoMode> Spi<'a, M> {
                 // wait until we have data in the RxFIFO.
                 while self.info.regs.fifostat().read().rxnotempty().bit_is_clear() {}
 
-                let rb = self.info.regs.fiford().read().rxdata().bits() as u8;
+                let rb = self.info.regs.fiford().read().rxdata().bits();
 
                 if let Some(r) = read.get_mut(i) {
-                    *r = rb;
+                    *r = W::from_fifo(rb);
                 }
             }
         });
@@ -192,7 +461,22 @@ impl<'a, M: IoMode> Spi<'a, M> {
     }
 
     /// Transfer data in place to SPI blocking execution until done.
+    ///
+    /// Each word is replaced with the one received while it was sent.
     pub fn blocking_transfer_in_place(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.blocking_transfer_in_place_words(data)
+    }
+
+    /// [`Self::blocking_transfer_in_place`] with 16-bit words, for frames larger than 8 bits.
+    pub fn blocking_transfer_in_place_u16(&mut self, data: &mut [u16]) -> Result<(), Error> {
+        self.blocking_transfer_in_place_words(data)
+    }
+
+    fn blocking_transfer_in_place_words<W: Word>(&mut self, data: &mut [W]) -> Result<(), Error> {
+        self.check_word::<W>()?;
+
+        let len = self.frame_len();
+
         critical_section::with(|_| {
             self.info
                 .regs
@@ -205,11 +489,11 @@ impl<'a, M: IoMode> Spi<'a, M> {
                 self.info
                     .regs
                     .fifowr()
-                    .write(|w| unsafe { w.txdata().bits(*word as u16) });
+                    .write(|w| unsafe { w.txdata().bits(word.to_fifo()).len().bits(len) });
 
                 // wait until we have data in the RxFIFO.
                 while self.info.regs.fifostat().read().rxnotempty().bit_is_clear() {}
-                *word = self.info.regs.fiford().read().rxdata().bits() as u8;
+                *word = W::from_fifo(self.info.regs.fiford().read().rxdata().bits());
             }
         });
 
@@ -222,10 +506,27 @@ impl<'a, M: IoMode> Spi<'a, M> {
         while regs.stat().read().mstidle().bit_is_clear() {}
         Ok(())
     }
+
+    /// Check that words of type `W` hold the configured frames.
+    fn check_word<W: Word>(&self) -> Result<(), Error> {
+        if self.data_bits.bits() > W::BITS {
+            return Err(Error::WordTooSmall);
+        }
+
+        Ok(())
+    }
+
+    /// Value of the FIFOWR LEN field for the configured frame size.
+    fn frame_len(&self) -> u8 {
+        self.data_bits.bits() - 1
+    }
 }
 
 impl<'a> Spi<'a, Async> {
     /// Create a SPI driver in async mode.
+    ///
+    /// Transfers are driven by the FIFO interrupts alone, leaving every DMA channel free for
+    /// other peripherals. Use [`Spi::new_async_with_dma`] to keep the CPU out of long transfers.
     pub fn new_async<T: Instance>(
         _inner: Peri<'a, T>,
         sck: Peri<'a, impl SckPin<T> + 'a>,
@@ -244,6 +545,29 @@ impl<'a> Spi<'a, Async> {
         Self::new_inner(_inner, Some(sck.into()), Some(mosi.into()), Some(miso.into()), config)
     }
 
+    /// Create a SPI driver in async mode, moving data with a pair of DMA channels.
+    ///
+    /// The CPU is only involved at the start and the end of each transfer. Buffers longer than
+    /// the DMA transfer count limit are moved through chained descriptors.
+    pub fn new_async_with_dma<T: Instance>(
+        _inner: Peri<'a, T>,
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        mosi: Peri<'a, impl MosiPin<T> + 'a>,
+        miso: Peri<'a, impl MisoPin<T> + 'a>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'a,
+        tx_dma: Peri<'a, impl TxDma<T>>,
+        rx_dma: Peri<'a, impl RxDma<T>>,
+        config: Config,
+    ) -> Self {
+        let mut spi = Self::new_async(_inner, sck, mosi, miso, _irq, config);
+
+        spi.dma = dma::Dma::reserve_channel(tx_dma)
+            .zip(dma::Dma::reserve_channel(rx_dma))
+            .map(|(tx, rx)| SpiDma::new(tx, rx));
+
+        spi
+    }
+
     /// Create a TX-only SPI driver in async mode.
     pub fn new_async_txonly<T: Instance>(
         _inner: Peri<'a, T>,
@@ -294,7 +618,38 @@ impl<'a> Spi<'a, Async> {
     }
 
     /// Read data from Spi async execution until done.
+    ///
+    /// [`Config::fill_word`] is sent for every word read.
     pub async fn async_read(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.async_read_words(data).await
+    }
+
+    /// [`Self::async_read`] with 16-bit words, for frames larger than 8 bits.
+    pub async fn async_read_u16(&mut self, data: &mut [u16]) -> Result<(), Error> {
+        self.async_read_words(data).await
+    }
+
+    async fn async_read_words<W: Word>(&mut self, data: &mut [W]) -> Result<(), Error> {
+        self.check_word::<W>()?;
+
+        let len = self.frame_len();
+        let fill = self.fill_word;
+
+        let regs = self.info.regs;
+        if let Some(dma) = self.dma.as_mut() {
+            // The buffer is sent while it is filled, no separate fill buffer is needed
+            data.fill(W::from_fifo(fill));
+            for chunk in data.chunks_mut(DMA_RUN_LEN) {
+                let ptr = chunk.as_mut_ptr();
+                // SAFETY: `chunk` is exclusively borrowed until the transfer completes
+                unsafe { dma.transfer(regs, len, ptr, Some(ptr), chunk.len()) }.await?;
+            }
+
+            self.async_flush().await;
+
+            return Ok(());
+        }
+
         critical_section::with(|_| {
             self.info
                 .regs
@@ -303,6 +658,11 @@ impl<'a> Spi<'a, Async> {
         });
 
         for word in data.iter_mut() {
+            self.info
+                .regs
+                .fifowr()
+                .write(|w| unsafe { w.txdata().bits(fill).len().bits(len) });
+
             // wait until we have data in the RxFIFO.
             self.wait_for(
                 |me| {
@@ -321,12 +681,7 @@ impl<'a> Spi<'a, Async> {
             )
             .await;
 
-            self.info
-                .regs
-                .fifowr()
-                .write(|w| unsafe { w.txdata().bits(*word as u16).len().bits(7) });
-
-            *word = self.info.regs.fiford().read().rxdata().bits() as u8;
+            *word = W::from_fifo(self.info.regs.fiford().read().rxdata().bits());
         }
 
         self.async_flush().await;
@@ -336,6 +691,31 @@ impl<'a> Spi<'a, Async> {
 
     /// Write data to Spi async execution until done.
     pub async fn async_write(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.async_write_words(data).await
+    }
+
+    /// [`Self::async_write`] with 16-bit words, for frames larger than 8 bits.
+    pub async fn async_write_u16(&mut self, data: &[u16]) -> Result<(), Error> {
+        self.async_write_words(data).await
+    }
+
+    async fn async_write_words<W: Word>(&mut self, data: &[W]) -> Result<(), Error> {
+        self.check_word::<W>()?;
+
+        let len = self.frame_len();
+
+        let regs = self.info.regs;
+        if let Some(dma) = self.dma.as_mut() {
+            for chunk in data.chunks(DMA_RUN_LEN) {
+                // SAFETY: `chunk` is borrowed until the transfer completes
+                unsafe { dma.transfer(regs, len, chunk.as_ptr(), None, chunk.len()) }.await?;
+            }
+
+            self.async_flush().await;
+
+            return Ok(());
+        }
+
         critical_section::with(|_| {
             self.info
                 .regs
@@ -363,7 +743,7 @@ impl<'a> Spi<'a, Async> {
             .await;
 
             self.info.regs.fifowr().write(|w| {
-                unsafe { w.txdata().bits(*word as u16).len().bits(7) }
+                unsafe { w.txdata().bits(word.to_fifo()).len().bits(len) }
                     .rxignore()
                     .set_bit();
 
@@ -381,7 +761,53 @@ impl<'a> Spi<'a, Async> {
     }
 
     /// Transfer data to SPI async execution until done.
+    ///
+    /// `read` and `write` may differ in length: [`Config::fill_word`] is sent past the end of
+    /// `write`, and words received past the end of `read` are discarded.
     pub async fn async_transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Error> {
+        self.async_transfer_words(read, write).await
+    }
+
+    /// [`Self::async_transfer`] with 16-bit words, for frames larger than 8 bits.
+    pub async fn async_transfer_u16(&mut self, read: &mut [u16], write: &[u16]) -> Result<(), Error> {
+        self.async_transfer_words(read, write).await
+    }
+
+    async fn async_transfer_words<W: Word>(&mut self, read: &mut [W], write: &[W]) -> Result<(), Error> {
+        self.check_word::<W>()?;
+
+        let frame_len = self.frame_len();
+        let fill = self.fill_word;
+
+        let regs = self.info.regs;
+        if let Some(dma) = self.dma.as_mut() {
+            let common = read.len().min(write.len());
+            let (read, read_rest) = read.split_at_mut(common);
+            let (write, write_rest) = write.split_at(common);
+
+            for (r, w) in read.chunks_mut(DMA_RUN_LEN).zip(write.chunks(DMA_RUN_LEN)) {
+                // SAFETY: both chunks have the same length and are borrowed until the transfer completes
+                unsafe { dma.transfer(regs, frame_len, w.as_ptr(), Some(r.as_mut_ptr()), r.len()) }.await?;
+            }
+
+            for chunk in write_rest.chunks(DMA_RUN_LEN) {
+                // SAFETY: `chunk` is borrowed until the transfer completes
+                unsafe { dma.transfer(regs, frame_len, chunk.as_ptr(), None, chunk.len()) }.await?;
+            }
+
+            // The fill word is sent past the end of `write`
+            read_rest.fill(W::from_fifo(fill));
+            for chunk in read_rest.chunks_mut(DMA_RUN_LEN) {
+                let ptr = chunk.as_mut_ptr();
+                // SAFETY: `chunk` is exclusively borrowed until the transfer completes
+                unsafe { dma.transfer(regs, frame_len, ptr, Some(ptr), chunk.len()) }.await?;
+            }
+
+            self.async_flush().await;
+
+            return Ok(());
+        }
+
         let len = read.len().max(write.len());
 
         critical_section::with(|_| {
@@ -392,7 +818,7 @@ impl<'a> Spi<'a, Async> {
         });
 
         for i in 0..len {
-            let wb = write.get(i).copied().unwrap_or(0);
+            let wb = write.get(i).map_or(fill, |w| w.to_fifo());
 
             // wait until we have space in the TxFIFO.
             self.wait_for(
@@ -404,7 +830,6 @@ impl<'a> Spi<'a, Async> {
                     }
                 },
                 |me| {
-                    me.info.regs.fifotrig().write(|w| w.txlvlena().set_bit());
                     me.info
                         .regs
                         .fifointenset()
@@ -414,7 +839,7 @@ impl<'a> Spi<'a, Async> {
             .await;
 
             self.info.regs.fifowr().write(|w| {
-                unsafe { w.txdata().bits(wb as u16).len().bits(7) };
+                unsafe { w.txdata().bits(wb).len().bits(frame_len) };
 
                 if i == len - 1 {
                     w.eot().set_bit();
@@ -433,7 +858,6 @@ impl<'a> Spi<'a, Async> {
                     }
                 },
                 |me| {
-                    me.info.regs.fifotrig().write(|w| w.rxlvlena().set_bit());
                     me.info
                         .regs
                         .fifointenset()
@@ -442,10 +866,10 @@ impl<'a> Spi<'a, Async> {
             )
             .await;
 
-            let rb = self.info.regs.fiford().read().rxdata().bits() as u8;
+            let rb = self.info.regs.fiford().read().rxdata().bits();
 
             if let Some(r) = read.get_mut(i) {
-                *r = rb;
+                *r = W::from_fifo(rb);
             }
         }
 
@@ -455,7 +879,35 @@ impl<'a> Spi<'a, Async> {
     }
 
     /// Transfer data in place to SPI async execution until done.
+    ///
+    /// Each word is replaced with the one received while it was sent.
     pub async fn async_transfer_in_place(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.async_transfer_in_place_words(data).await
+    }
+
+    /// [`Self::async_transfer_in_place`] with 16-bit words, for frames larger than 8 bits.
+    pub async fn async_transfer_in_place_u16(&mut self, data: &mut [u16]) -> Result<(), Error> {
+        self.async_transfer_in_place_words(data).await
+    }
+
+    async fn async_transfer_in_place_words<W: Word>(&mut self, data: &mut [W]) -> Result<(), Error> {
+        self.check_word::<W>()?;
+
+        let len = self.frame_len();
+
+        let regs = self.info.regs;
+        if let Some(dma) = self.dma.as_mut() {
+            for chunk in data.chunks_mut(DMA_RUN_LEN) {
+                let ptr = chunk.as_mut_ptr();
+                // SAFETY: `chunk` is exclusively borrowed until the transfer completes
+                unsafe { dma.transfer(regs, len, ptr, Some(ptr), chunk.len()) }.await?;
+            }
+
+            self.async_flush().await;
+
+            return Ok(());
+        }
+
         critical_section::with(|_| {
             self.info
                 .regs
@@ -485,7 +937,7 @@ impl<'a> Spi<'a, Async> {
             self.info
                 .regs
                 .fifowr()
-                .write(|w| unsafe { w.txdata().bits(*word as u16) });
+                .write(|w| unsafe { w.txdata().bits(word.to_fifo()).len().bits(len) });
 
             // wait until we have data in the RxFIFO.
             self.wait_for(
@@ -505,7 +957,7 @@ impl<'a> Spi<'a, Async> {
             )
             .await;
 
-            *word = self.info.regs.fiford().read().rxdata().bits() as u8;
+            *word = W::from_fifo(self.info.regs.fiford().read().rxdata().bits());
         }
 
         self.async_flush().await;
@@ -565,7 +1017,7 @@ impl<'a, M: IoMode> Spi<'a, M> {
         let flexcomm = T::enable(clk);
         T::into_spi();
 
-        Self::apply_config(T::info().regs, &config);
+        Self::apply_config(&T::info(), &config);
 
         let info = T::info();
         let regs = info.regs;
@@ -623,15 +1075,40 @@ impl<'a, M: IoMode> Spi<'a, M> {
             _ => {}
         });
 
+        // Level triggers for the interrupt-driven async paths, they only raise an interrupt once
+        // enabled in FIFOINTENSET: TX as soon as the FIFO has room, RX as soon as a word arrives.
+        regs.fifotrig().write(|w| {
+            unsafe { w.txlvl().bits(FIFO_DEPTH - 1).rxlvl().bits(0) }
+                .txlvlena()
+                .set_bit()
+                .rxlvlena()
+                .set_bit()
+        });
+
         Self {
             info,
-            _flexcomm: flexcomm,
+            flexcomm,
+            dma: None,
+            data_bits: config.data_bits,
+            fill_word: config.fill_word,
             _phantom: PhantomData,
         }
     }
 
-    fn set_config(&mut self, config: &Config) {
-        Self::apply_config(self.info.regs, config);
+    fn set_config(&mut self, config: &Config) -> Result<(), Error> {
+        if !Self::frequency_in_range(config) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        // The source clock depends on the frequency, switch it while SPI is disabled
+        self.info.regs.cfg().modify(|_, w| w.enable().disabled());
+        self.flexcomm.set_clock(Self::clock(config));
+
+        Self::apply_config(&self.info, config);
+        self.data_bits = config.data_bits;
+        self.fill_word = config.fill_word;
+
+        Ok(())
     }
 
     fn clock(config: &Config) -> Clock {
@@ -652,21 +1129,26 @@ impl<'a, M: IoMode> Spi<'a, M> {
         }
     }
 
-    fn apply_config(regs: &'static crate::pac::spi0::RegisterBlock, config: &Config) {
-        let polarity = if config.mode.polarity == Polarity::IdleLow {
-            Cpol::Low
-        } else {
-            Cpol::High
-        };
+    /// Divider of the source clock for `config`, rounded up so the bus never runs faster than
+    /// requested, and clamped to what the divider can do.
+    fn divider(config: &Config) -> u32 {
+        Self::clock_frequency(Self::clock(config))
+            .div_ceil(config.frequency.max(1))
+            .clamp(1, MAX_DIVIDER)
+    }
 
-        let phase = if config.mode.phase == Phase::CaptureOnFirstTransition {
-            Cpha::Change
-        } else {
-            Cpha::Capture
-        };
+    /// Whether the divider can reach `config.frequency`, see [`Config::frequency`].
+    fn frequency_in_range(config: &Config) -> bool {
+        let clock_hz = Self::clock_frequency(Self::clock(config));
+        config.frequency != 0 && config.frequency <= clock_hz && clock_hz.div_ceil(config.frequency) <= MAX_DIVIDER
+    }
 
-        let clk = Self::clock(config);
-        let div = Self::clock_frequency(clk) / config.frequency - 1;
+    fn apply_config(info: &Info, config: &Config) {
+        let regs = info.regs;
+        let (polarity, phase) = clock_mode(&config.mode);
+
+        // The constructors can't fail, an unreachable frequency runs at the closest one
+        let div = Self::divider(config);
 
         critical_section::with(|_| {
             // disable SPI every time we need to modify configuration.
@@ -683,20 +1165,277 @@ impl<'a, M: IoMode> Spi<'a, M> {
                     .master_mode()
             });
 
-            regs.div().write(|w| unsafe { w.divval().bits(div as u16) });
+            // SAFETY: the divider is within 1..=MAX_DIVIDER
+            regs.div().write(|w| unsafe { w.divval().bits((div - 1) as u16) });
 
             regs.cfg().modify(|_, w| w.enable().enabled());
         });
     }
 }
 
+fn clock_mode(mode: &Mode) -> (Cpol, Cpha) {
+    let polarity = if mode.polarity == Polarity::IdleLow {
+        Cpol::Low
+    } else {
+        Cpol::High
+    };
+
+    let phase = if mode.phase == Phase::CaptureOnFirstTransition {
+        Cpha::Change
+    } else {
+        Cpha::Capture
+    };
+
+    (polarity, phase)
+}
+
+/// Bytes moved so far by an unfinished DMA transfer of `len` bytes
+fn dma_progress(dma: &Channel, len: usize) -> usize {
+    // The transfer is still active, xfercount is the number of remaining bytes minus one
+    if dma.is_active() {
+        len.saturating_sub(dma.get_xfer_count() as usize + 1)
+    } else {
+        len
+    }
+}
+
+/// Spi slave driver.
+///
+/// Every transaction is framed by SSEL0: it starts when the host asserts SSEL and ends when the
+/// host deasserts it, however many bytes were exchanged. Start each transaction before the host
+/// asserts SSEL, the TX FIFO is loaded in advance so the first byte is ready on time.
+pub struct SpiSlave<'a> {
+    info: Info,
+    _flexcomm: FlexcommRef,
+    tx_dma: Channel<'a>,
+    rx_dma: Channel<'a>,
+}
+
+impl<'a> SpiSlave<'a> {
+    /// Create a SPI slave driver, moving data with a pair of DMA channels.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_async<T: Instance>(
+        _inner: Peri<'a, T>,
+        sck: Peri<'a, impl SckPin<T> + 'a>,
+        mosi: Peri<'a, impl MosiPin<T> + 'a>,
+        miso: Peri<'a, impl MisoPin<T> + 'a>,
+        ssel: Peri<'a, impl SselPin<T> + 'a>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'a,
+        tx_dma: Peri<'a, impl TxDma<T>>,
+        rx_dma: Peri<'a, impl RxDma<T>>,
+        config: SlaveConfig,
+    ) -> Result<Self, Error> {
+        let tx_dma = dma::Dma::reserve_channel(tx_dma).ok_or(Error::UnsupportedConfiguration)?;
+        let rx_dma = dma::Dma::reserve_channel(rx_dma).ok_or(Error::UnsupportedConfiguration)?;
+
+        sck.as_sck();
+        mosi.as_mosi();
+        miso.as_miso();
+        ssel.as_ssel();
+
+        // SCK comes from the host, the function clock only has to keep the FIFOs in sync with it.
+        let flexcomm = T::enable(Clock::Ffro);
+        T::into_spi();
+
+        let info = T::info();
+        let regs = info.regs;
+        let (polarity, phase) = clock_mode(&config.mode);
+
+        critical_section::with(|_| {
+            regs.cfg().modify(|_, w| w.enable().disabled());
+
+            regs.cfg().modify(|_, w| {
+                w.cpha()
+                    .variant(phase)
+                    .cpol()
+                    .variant(polarity)
+                    .loop_()
+                    .disabled()
+                    .master()
+                    .slave_mode()
+            });
+
+            regs.fifocfg().modify(|_, w| {
+                w.enabletx()
+                    .set_bit()
+                    .emptytx()
+                    .set_bit()
+                    .enablerx()
+                    .set_bit()
+                    .emptyrx()
+                    .set_bit()
+            });
+
+            regs.cfg().modify(|_, w| w.enable().enabled());
+        });
+
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        Ok(Self {
+            info,
+            _flexcomm: flexcomm,
+            tx_dma,
+            rx_dma,
+        })
+    }
+
+    /// Receive one transaction into `buf`, sending `0xff` back to the host.
+    ///
+    /// Returns the number of bytes received, bytes past the end of `buf` are discarded.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        buf.fill(SLAVE_DUMMY_BYTE);
+
+        let ptr = buf.as_mut_ptr();
+        // SAFETY: `buf` is exclusively borrowed until the transaction completes
+        unsafe { self.transaction(ptr, Some(ptr), buf.len()) }.await
+    }
+
+    /// Send `buf` to the host in one transaction, ignoring the received data.
+    ///
+    /// Returns the number of bytes the host clocked out.
+    pub async fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        // SAFETY: `buf` is borrowed until the transaction completes
+        unsafe { self.transaction(buf.as_ptr(), None, buf.len()) }.await
+    }
+
+    /// Send `write` while receiving into `read` in one transaction.
+    ///
+    /// Only the first `min(read.len(), write.len())` bytes are exchanged, the rest of the
+    /// transaction is discarded. Returns the number of bytes received.
+    pub async fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<usize, Error> {
+        let len = read.len().min(write.len());
+
+        // SAFETY: both buffers are borrowed until the transaction completes
+        unsafe { self.transaction(write.as_ptr(), Some(read.as_mut_ptr()), len) }.await
+    }
+
+    /// Exchange `len` bytes of `tx` and `rx` in a transaction framed by SSEL.
+    ///
+    /// # Safety
+    ///
+    /// `tx` must be valid for reads and `rx` for writes of `len` bytes. They may overlap: each
+    /// byte is sent before the one replacing it is received.
+    async unsafe fn transaction(&mut self, tx: *const u8, rx: Option<*mut u8>, len: usize) -> Result<usize, Error> {
+        let regs = self.info.regs;
+
+        // Disable DMA and drop whatever is left in the FIFOs on completion/cancellation
+        let _dma_guard = OnDrop::new(|| {
+            regs.fifocfg().modify(|_, w| {
+                w.dmatx()
+                    .disabled()
+                    .dmarx()
+                    .disabled()
+                    .emptytx()
+                    .set_bit()
+                    .emptyrx()
+                    .set_bit()
+            });
+        });
+
+        regs.fifocfg().modify(|_, w| w.emptytx().set_bit().emptyrx().set_bit());
+        regs.fifostat().write(|w| w.txerr().set_bit().rxerr().set_bit());
+        // SAFETY: writing ones only clears the slave select flags
+        regs.stat().write(|w| unsafe { w.bits(STAT_SSA | STAT_SSD) });
+
+        let ctrl = FIFOWR_CTRL_LEN_8BIT | if rx.is_none() { FIFOWR_CTRL_RXIGNORE } else { 0 };
+        // SAFETY: FIFOWR is a valid, aligned 32-bit register
+        unsafe { (regs.fifowr().as_ptr() as *mut u16).add(1).write_volatile(ctrl) };
+
+        regs.fifocfg().modify(|_, w| {
+            w.dmatx().enabled();
+            if rx.is_some() {
+                w.dmarx().enabled();
+            }
+            w
+        });
+
+        let fifowr = regs.fifowr().as_ptr() as *mut u8;
+        let fiford = regs.fiford().as_ptr() as *const u8;
+        let mut done = 0;
+
+        while done < len {
+            let chunk = (len - done).min(dma::MAX_TRANSFER_COUNT);
+
+            // SAFETY: the caller guarantees both buffers are valid for `len` bytes, the transfers
+            // are aborted when dropped.
+            let rx_transfer = rx.map(|rx| unsafe {
+                Transfer::new_read_raw(&self.rx_dma, fiford, rx.add(done), chunk, Default::default())
+            });
+            let tx_transfer =
+                unsafe { Transfer::new_write_raw(&self.tx_dma, tx.add(done), chunk, fifowr, Default::default()) };
+
+            let transfers = async {
+                match rx_transfer {
+                    Some(rx_transfer) => {
+                        join(rx_transfer, tx_transfer).await;
+                    }
+                    None => tx_transfer.await,
+                }
+            };
+
+            // The transfers are still running when the host ends the transaction, so their
+            // progress is taken before they are dropped.
+            let ended = async {
+                self.info.wait_for_ssd().await;
+
+                if rx.is_some() {
+                    // Let DMA drain the last bytes clocked in
+                    while self.rx_dma.is_active() && regs.fifostat().read().rxnotempty().bit_is_set() {}
+                    dma_progress(&self.rx_dma, chunk)
+                } else {
+                    let queued = regs.fifostat().read().txlvl().bits() as usize;
+                    dma_progress(&self.tx_dma, chunk).saturating_sub(queued)
+                }
+            };
+
+            match select(transfers, ended).await {
+                Either::First(()) => done += chunk,
+                Either::Second(count) => {
+                    if rx.is_some() && regs.fifostat().read().rxerr().bit_is_set() {
+                        return Err(Error::Overrun);
+                    }
+                    return Ok(done + count);
+                }
+            }
+        }
+
+        // Buffers exhausted, wait for the host to end the transaction
+        self.info.wait_for_ssd().await;
+
+        Ok(len)
+    }
+}
+
+/// Spi slave config.
+#[derive(Clone)]
+pub struct SlaveConfig {
+    /// SPI operating mode.
+    pub mode: Mode,
+}
+
+impl Default for SlaveConfig {
+    fn default() -> Self {
+        Self { mode: MODE_0 }
+    }
+}
+
 /// Spi config.
 #[derive(Clone)]
 pub struct Config {
     /// Frequency in Hertz.
+    ///
+    /// Rounded down to what the source clock can be divided to, from 245 Hz to 48 MHz.
+    /// [`SetConfig`] rejects frequencies out of that range, the constructors run at the closest
+    /// one.
     pub frequency: u32,
     /// SPI operating mode.
     pub mode: Mode,
+    /// Frame size, see [`Word`].
+    pub data_bits: DataBits,
+    /// Word sent when there is no data to send: by reads, and past the end of the write buffer
+    /// of a transfer.
+    pub fill_word: u16,
 }
 
 impl Default for Config {
@@ -704,6 +1443,8 @@ impl Default for Config {
         Self {
             frequency: 1_000_000,
             mode: MODE_0,
+            data_bits: DataBits::default(),
+            fill_word: 0,
         }
     }
 }
@@ -713,6 +1454,24 @@ struct Info {
     waker: &'static AtomicWaker,
 }
 
+impl Info {
+    /// Wait for the host to deassert SSEL.
+    fn wait_for_ssd(&self) -> impl Future<Output = ()> + '_ {
+        poll_fn(|cx| {
+            self.waker.register(cx.waker());
+
+            if self.regs.stat().read().bits() & STAT_SSD != 0 {
+                // SAFETY: writing ones only clears the slave select flags
+                self.regs.stat().write(|w| unsafe { w.bits(STAT_SSD) });
+                Poll::Ready(())
+            } else {
+                self.regs.intenset().write(|w| w.ssden().set_bit());
+                Poll::Pending
+            }
+        })
+    }
+}
+
 // SAFETY: safety for Send here is the same as the other accessors to
 // unsafe blocks: it must be done from a single executor context.
 //
@@ -737,7 +1496,10 @@ impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandl
         let stat = T::info().regs.fifointstat().read();
 
         if stat.perint().bit_is_set() {
-            T::info().regs.intenclr().write(|w| w.mstidle().clear_bit_by_one());
+            T::info()
+                .regs
+                .intenclr()
+                .write(|w| w.mstidle().clear_bit_by_one().ssden().clear_bit_by_one());
         }
 
         if stat.txlvl().bit_is_set() {
@@ -818,8 +1580,21 @@ pub trait MisoPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
     fn as_miso(&self);
 }
 
+/// IO configuration trait for Spi slave select
+pub trait SselPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for Spi slave select usage.
+    fn as_ssel(&self);
+}
+
 macro_rules! impl_pin_trait {
     ($fcn:ident, $mode:ident, $($pin:ident, $fn:ident),*) => {
+        impl_pin_trait!(@impl Normal, $fcn, $mode, $($pin, $fn),*);
+    };
+    // High-speed SPI pins, full drive strength keeps the edges sharp at 48 MHz
+    (hs $fcn:ident, $mode:ident, $($pin:ident, $fn:ident),*) => {
+        impl_pin_trait!(@impl Full, $fcn, $mode, $($pin, $fn),*);
+    };
+    (@impl $drive:ident, $fcn:ident, $mode:ident, $($pin:ident, $fn:ident),*) => {
         paste! {
             $(
                 impl [<$mode:camel Pin>]<crate::peripherals::$fcn> for crate::peripherals::$pin {
@@ -829,7 +1604,7 @@ macro_rules! impl_pin_trait {
                             .set_pull(Pull::None)
                             .enable_input_buffer()
                             .set_slew_rate(SlewRate::Standard)
-                            .set_drive_strength(DriveStrength::Normal)
+                            .set_drive_strength(DriveStrength::$drive)
                             .disable_analog_multiplex()
                             .set_drive_mode(DriveMode::PushPull)
                             .set_input_inverter(Inverter::Disabled);
@@ -844,46 +1619,55 @@ macro_rules! impl_pin_trait {
 impl_pin_trait!(FLEXCOMM0, sck, PIO0_0, F1, PIO3_0, F5);
 impl_pin_trait!(FLEXCOMM0, miso, PIO0_1, F1, PIO3_1, F5);
 impl_pin_trait!(FLEXCOMM0, mosi, PIO0_2, F1, PIO3_2, F5);
+impl_pin_trait!(FLEXCOMM0, ssel, PIO0_3, F1);
 
 // FLEXCOMM1
 impl_pin_trait!(FLEXCOMM1, sck, PIO0_7, F1, PIO7_25, F1);
 impl_pin_trait!(FLEXCOMM1, miso, PIO0_8, F1, PIO7_26, F1);
 impl_pin_trait!(FLEXCOMM1, mosi, PIO0_9, F1, PIO7_28, F1);
+impl_pin_trait!(FLEXCOMM1, ssel, PIO0_10, F1);
 
 // FLEXCOMM2
 impl_pin_trait!(FLEXCOMM2, sck, PIO0_14, F1, PIO7_29, F5);
 impl_pin_trait!(FLEXCOMM2, miso, PIO0_15, F1, PIO7_30, F5);
 impl_pin_trait!(FLEXCOMM2, mosi, PIO0_16, F1, PIO7_31, F5);
+impl_pin_trait!(FLEXCOMM2, ssel, PIO0_17, F1);
 
 // FLEXCOMM3
 impl_pin_trait!(FLEXCOMM3, sck, PIO0_21, F1);
 impl_pin_trait!(FLEXCOMM3, miso, PIO0_22, F1);
 impl_pin_trait!(FLEXCOMM3, mosi, PIO0_23, F1);
+impl_pin_trait!(FLEXCOMM3, ssel, PIO0_24, F1);
 
 // FLEXCOMM4
 impl_pin_trait!(FLEXCOMM4, sck, PIO0_28, F1);
 impl_pin_trait!(FLEXCOMM4, miso, PIO0_29, F1);
 impl_pin_trait!(FLEXCOMM4, mosi, PIO0_30, F1);
+impl_pin_trait!(FLEXCOMM4, ssel, PIO0_31, F1);
 
 // FLEXCOMM5
 impl_pin_trait!(FLEXCOMM5, sck, PIO1_3, F1, PIO3_15, F5);
 impl_pin_trait!(FLEXCOMM5, miso, PIO1_4, F1, PIO3_16, F5);
 impl_pin_trait!(FLEXCOMM5, mosi, PIO1_5, F1, PIO3_17, F5);
+impl_pin_trait!(FLEXCOMM5, ssel, PIO1_6, F1);
 
 // FLEXCOMM6
 impl_pin_trait!(FLEXCOMM6, sck, PIO3_25, F1);
 impl_pin_trait!(FLEXCOMM6, miso, PIO3_26, F1);
 impl_pin_trait!(FLEXCOMM6, mosi, PIO3_27, F1);
+impl_pin_trait!(FLEXCOMM6, ssel, PIO3_28, F1);
 
 // FLEXCOMM7
 impl_pin_trait!(FLEXCOMM7, sck, PIO4_0, F1);
 impl_pin_trait!(FLEXCOMM7, miso, PIO4_1, F1);
 impl_pin_trait!(FLEXCOMM7, mosi, PIO4_2, F1);
+impl_pin_trait!(FLEXCOMM7, ssel, PIO4_3, F1);
 
-// FLEXCOMM14
-impl_pin_trait!(FLEXCOMM14, sck, PIO1_11, F1);
-impl_pin_trait!(FLEXCOMM14, miso, PIO1_12, F1);
-impl_pin_trait!(FLEXCOMM14, mosi, PIO1_13, F1);
+// FLEXCOMM14, high-speed SPI
+impl_pin_trait!(hs FLEXCOMM14, sck, PIO1_11, F1);
+impl_pin_trait!(hs FLEXCOMM14, miso, PIO1_12, F1);
+impl_pin_trait!(hs FLEXCOMM14, mosi, PIO1_13, F1);
+impl_pin_trait!(hs FLEXCOMM14, ssel, PIO1_14, F1);
 
 /// Spi Tx DMA trait.
 #[allow(private_bounds)]
@@ -948,7 +1732,11 @@ impl<'d, M: IoMode> embedded_hal_02::blocking::spi::Write<u8> for Spi<'d, M> {
 
 impl embedded_hal_1::spi::Error for Error {
     fn kind(&self) -> embedded_hal_1::spi::ErrorKind {
-        match *self {}
+        match *self {
+            Self::Overrun => embedded_hal_1::spi::ErrorKind::Overrun,
+            Self::UnsupportedConfiguration => embedded_hal_1::spi::ErrorKind::Other,
+            Self::WordTooSmall => embedded_hal_1::spi::ErrorKind::Other,
+        }
     }
 }
 
@@ -956,58 +1744,56 @@ impl<'d, M: IoMode> embedded_hal_1::spi::ErrorType for Spi<'d, M> {
     type Error = Error;
 }
 
-impl<'d, M: IoMode> embedded_hal_1::spi::SpiBus<u8> for Spi<'d, M> {
+impl<'d, M: IoMode, W: Word> embedded_hal_1::spi::SpiBus<W> for Spi<'d, M> {
     fn flush(&mut self) -> Result<(), Self::Error> {
         self.flush()
     }
 
-    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
-        self.blocking_read(words)
+    fn read(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        self.blocking_read_words(words)
     }
 
-    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
-        self.blocking_write(words)
+    fn write(&mut self, words: &[W]) -> Result<(), Self::Error> {
+        self.blocking_write_words(words)
     }
 
-    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
-        self.blocking_transfer(read, write)
+    fn transfer(&mut self, read: &mut [W], write: &[W]) -> Result<(), Self::Error> {
+        self.blocking_transfer_words(read, write)
     }
 
-    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
-        self.blocking_transfer_in_place(words)
+    fn transfer_in_place(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        self.blocking_transfer_in_place_words(words)
     }
 }
 
-impl<'d> embedded_hal_async::spi::SpiBus<u8> for Spi<'d, Async> {
+impl<'d, W: Word> embedded_hal_async::spi::SpiBus<W> for Spi<'d, Async> {
     async fn flush(&mut self) -> Result<(), Self::Error> {
         self.async_flush().await;
 
         Ok(())
     }
 
-    async fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
-        self.async_write(words).await
+    async fn write(&mut self, words: &[W]) -> Result<(), Self::Error> {
+        self.async_write_words(words).await
     }
 
-    async fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
-        self.async_read(words).await
+    async fn read(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        self.async_read_words(words).await
     }
 
-    async fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
-        self.async_transfer(read, write).await
+    async fn transfer(&mut self, read: &mut [W], write: &[W]) -> Result<(), Self::Error> {
+        self.async_transfer_words(read, write).await
     }
 
-    async fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
-        self.async_transfer_in_place(words).await
+    async fn transfer_in_place(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        self.async_transfer_in_place_words(words).await
     }
 }
 
 impl<'d, M: IoMode> SetConfig for Spi<'d, M> {
     type Config = Config;
-    type ConfigError = ();
-    fn set_config(&mut self, config: &Self::Config) -> Result<(), ()> {
-        self.set_config(config);
-
-        Ok(())
+    type ConfigError = Error;
+    fn set_config(&mut self, config: &Self::Config) -> Result<(), Error> {
+        self.set_config(config)
     }
 }
@@ -11,6 +11,7 @@ use crate::interrupt::typelevel::Interrupt;
 use crate::iopctl::{DriveMode, DriveStrength, Inverter, IopctlPin as Pin, Pull, SlewRate};
 use crate::pac::Clkctl1;
 use crate::pac::clkctl1::ct32bitfclksel::Sel;
+use crate::pac::ctimer0::ctcr::Selcc;
 use crate::pwm::{CentiPercent, Hertz, MicroSeconds};
 use crate::{Peri, PeripheralType, interrupt, peripherals};
 
@@ -63,6 +64,21 @@ pub enum Error {
 
     /// Pwm length channel and output channel does not belong to same CTimer
     PwmChannelMismatch,
+
+    /// Counting timer period is zero or too long for the timer clock
+    InvalidCountPeriod,
+
+    /// Event counter capture channel and count channel do not belong to same CTimer
+    CounterChannelMismatch,
+
+    /// Event count is zero
+    InvalidEventCount,
+
+    /// PWM input capture channels do not belong to same CTimer
+    PwmInputChannelMismatch,
+
+    /// PWM input rising edge channel cannot clear the timer, only channels 0 to 2 can
+    InvalidPwmInputChannel,
 }
 
 /// Enum representing the logical capture channel input.
@@ -128,6 +144,17 @@ pub enum CaptureChEdge {
     Falling,
 }
 
+/// Enum representing the edges counted by an [`EventCounter`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CountEdge {
+    /// Rising edge
+    Rising,
+    /// Falling edge
+    Falling,
+    /// Both edges
+    Both,
+}
+
 mod sealed {
     /// simply seal a trait
     pub trait Sealed {}
@@ -160,10 +187,50 @@ pub struct CaptureTimer<'p, M: Mode, P: CaptureEvent> {
 pub struct CountingTimer<'p, M: Mode> {
     clk_freq: u32,
     timeout: u32,
+    deadline: u32,
+    period: Option<u32>,
     _phantom: core::marker::PhantomData<&'p M>,
     info: Info,
 }
 
+/// A counter clocked by the edges of a capture pin instead of the timer clock.
+///
+/// Counter mode applies to the whole CTimer module: the other channels of the module count the
+/// same edges, so they must not be used as capture or counting timers meanwhile. Edges must be
+/// slower than half the CTimer functional clock to be counted.
+///
+/// There is no overflow notification: the count wraps at `u32::MAX` without an interrupt. Waits
+/// use wrapping deadlines, so they are not affected, but callers tracking totals past `u32::MAX`
+/// edges must read [`Self::count`] at least once per wrap.
+pub struct EventCounter<'p, P: CaptureEvent> {
+    deadline: u32,
+    interval: Option<u32>,
+    info: Info,
+    _event_pin: Peri<'p, P>,
+}
+
+/// Measures the period and high time of a PWM signal with two capture channels on one input.
+///
+/// Each rising edge captures the period and clears the timer, each falling edge then captures the
+/// high time. Clearing the timer applies to the whole CTimer module, so the other channels of the
+/// module must not be used as capture or counting timers meanwhile.
+pub struct PwmInput<'p, P: CaptureEvent> {
+    clk_freq: u32,
+    primed: bool,
+    rising: Info,
+    info: Info,
+    _event_pin: Peri<'p, P>,
+}
+
+/// One pulse of a PWM signal measured by a [`PwmInput`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PwmMeasurement {
+    period_cycles: u32,
+    high_cycles: u32,
+    clk_freq: u32,
+}
+
 struct Info {
     regs: &'static crate::pac::ctimer0::RegisterBlock,
     inputmux: &'static crate::pac::inputmux::RegisterBlock,
@@ -349,6 +416,60 @@ impl Info {
         }
     }
 
+    fn count_timer_clear_interrupt(&self) {
+        let reg = self.regs;
+        match self.channel {
+            TimerChannelNum::Channel0 => {
+                reg.ir().write(|w| w.mr0int().clear_bit_by_one());
+            }
+            TimerChannelNum::Channel1 => {
+                reg.ir().write(|w| w.mr1int().clear_bit_by_one());
+            }
+            TimerChannelNum::Channel2 => {
+                reg.ir().write(|w| w.mr2int().clear_bit_by_one());
+            }
+            TimerChannelNum::Channel3 => {
+                reg.ir().write(|w| w.mr3int().clear_bit_by_one());
+            }
+        }
+    }
+
+    fn cap_timer_clear_interrupt(&self) {
+        let reg = self.regs;
+        match self.channel {
+            TimerChannelNum::Channel0 => {
+                reg.ir().write(|w| w.cr0int().clear_bit_by_one());
+            }
+            TimerChannelNum::Channel1 => {
+                reg.ir().write(|w| w.cr1int().clear_bit_by_one());
+            }
+            TimerChannelNum::Channel2 => {
+                reg.ir().write(|w| w.cr2int().clear_bit_by_one());
+            }
+            TimerChannelNum::Channel3 => {
+                reg.ir().write(|w| w.cr3int().clear_bit_by_one());
+            }
+        }
+    }
+
+    /// Arm the match channel at `deadline`, `count` ticks after `from`
+    ///
+    /// A deadline which already passed is left expired.
+    fn count_timer_arm(&self, deadline: u32, from: u32, count: u32) {
+        let reg = self.regs;
+
+        // SAFETY: It has no safety impact as we are writing new value to match register here
+        reg.mr(self.channel.into())
+            .write(|w| unsafe { w.match_().bits(deadline) });
+        self.count_timer_clear_interrupt();
+        self.count_timer_enable_interrupt();
+
+        // A match only fires on the exact count, a passed deadline would wait for a full rollover
+        if reg.tc().read().bits().wrapping_sub(from) >= count {
+            self.count_timer_disable_interrupt();
+        }
+    }
+
     fn has_count_timer_expired(&self) -> bool {
         let reg = self.regs;
 
@@ -737,6 +858,7 @@ impl<'p, M: Mode> CountingTimer<'p, M> {
         }
 
         self.timeout = cycles;
+        self.period = None;
 
         if curr_time as u64 + cycles as u64 > u32::MAX as u64 {
             let leftover = (curr_time as u64 + cycles as u64) - u32::MAX as u64;
@@ -756,6 +878,23 @@ impl<'p, M: Mode> CountingTimer<'p, M> {
 
         self.reset_and_enable();
     }
+
+    fn count_to_cycles(&self, count_us: u32) -> Result<u32> {
+        let cycles = (count_us as u64 * self.clk_freq as u64) / 1000000;
+
+        match u32::try_from(cycles) {
+            Ok(cycles) if cycles > 0 => Ok(cycles),
+            _ => Err(Error::InvalidCountPeriod),
+        }
+    }
+
+    /// Arm the match channel at `deadline`, `count` cycles after `from`
+    ///
+    /// A deadline which already passed is left expired.
+    fn arm(&mut self, deadline: u32, from: u32, count: u32) {
+        self.deadline = deadline;
+        self.info.count_timer_arm(deadline, from, count);
+    }
 }
 
 impl<'p> CountingTimer<'p, Async> {
@@ -775,6 +914,8 @@ impl<'p> CountingTimer<'p, Async> {
         Ok(Self {
             clk_freq: clk.get_clock_rate().map_err(Error::Clock)?,
             timeout: 0,
+            deadline: 0,
+            period: None,
             _phantom: core::marker::PhantomData,
             info,
         })
@@ -794,6 +935,59 @@ impl<'p> CountingTimer<'p, Async> {
             Poll::Pending
         })
     }
+
+    /// Starts a one-shot countdown of `count_us`, completed by [`Self::wait`].
+    ///
+    /// Returns [`Error::InvalidCountPeriod`] if the count is zero or overflows the timer clock.
+    pub fn start_one_shot(&mut self, count_us: u32) -> Result<()> {
+        let cycles = self.count_to_cycles(count_us)?;
+
+        self.reset_and_enable();
+        self.period = None;
+        let now = self.info.regs.tc().read().bits();
+        self.arm(now.wrapping_add(cycles), now, cycles);
+
+        Ok(())
+    }
+
+    /// Starts a periodic countdown every `period_us`, each period completed by [`Self::wait`].
+    ///
+    /// Periods are counted from the previous deadline, not from the end of the previous wait, so
+    /// the ticks don't drift. When a wait comes late, the missed periods complete immediately.
+    ///
+    /// Returns [`Error::InvalidCountPeriod`] if the period is zero or overflows the timer clock.
+    pub fn start_periodic(&mut self, period_us: u32) -> Result<()> {
+        let cycles = self.count_to_cycles(period_us)?;
+
+        self.reset_and_enable();
+        self.period = Some(cycles);
+        let now = self.info.regs.tc().read().bits();
+        self.arm(now.wrapping_add(cycles), now, cycles);
+
+        Ok(())
+    }
+
+    /// Waits asynchronously for the countdown started by [`Self::start_one_shot`] or
+    /// [`Self::start_periodic`] to complete.
+    ///
+    /// Completes immediately once a one-shot countdown has expired, or if none was started.
+    pub fn wait(&mut self) -> impl Future<Output = ()> + use<'_, 'p> {
+        poll_fn(|cx| {
+            // Register the waker
+            self.info.waker.register(cx.waker());
+
+            if !self.info.has_count_timer_expired() {
+                return Poll::Pending;
+            }
+
+            if let Some(period) = self.period {
+                let deadline = self.deadline;
+                self.arm(deadline.wrapping_add(period), deadline, period);
+            }
+
+            Poll::Ready(())
+        })
+    }
 }
 
 impl<'p, M: Mode> Drop for CountingTimer<'p, M> {
@@ -814,6 +1008,302 @@ impl<M: Mode, P: CaptureEvent> Drop for CaptureTimer<'_, M, P> {
     }
 }
 
+impl<'p, P: CaptureEvent> EventCounter<'p, P> {
+    /// Creates a new `EventCounter` counting `edge`s of `pin`, through the input of
+    /// `capture_channel`. Waits are raised by the match interrupt of `count_channel`.
+    ///
+    /// Returns [`Error::CounterChannelMismatch`] if both channels do not belong to same CTimer.
+    pub fn new_async<C: Instance, T: Instance>(
+        _capture_channel: Peri<'p, C>,
+        _count_channel: Peri<'p, T>,
+        pin: Peri<'p, P>,
+        edge: CountEdge,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'p,
+    ) -> Result<Self> {
+        let capture = C::info();
+        let info = T::info();
+
+        if capture.timer_type != TimerType::Capture
+            || info.timer_type != TimerType::Count
+            || capture.module != info.module
+        {
+            return Err(Error::CounterChannelMismatch);
+        }
+
+        pin.configure_for_event_capture();
+
+        info.inputmux
+            .ct32bit_cap(info.module)
+            .ct32bit_cap_sel(capture.channel.into())
+            .modify(|_, w| w.capn_sel().variant(pin.get_trigger_input().into()));
+
+        let reg = info.regs;
+        reg.tcr().write(|w| w.cen().disabled());
+        // SAFETY: It has no safety impact as we are writing new value to prescale register here
+        reg.pr().write(|w| unsafe { w.prval().bits(0) });
+        reg.ctcr().write(|w| {
+            match edge {
+                CountEdge::Rising => w.ctmode().counter_rising_edge(),
+                CountEdge::Falling => w.ctmode().counter_falling_edge(),
+                CountEdge::Both => w.ctmode().counter_dual_edge(),
+            };
+            match capture.channel {
+                TimerChannelNum::Channel0 => w.cinsel().channel_0(),
+                TimerChannelNum::Channel1 => w.cinsel().channel_1(),
+                TimerChannelNum::Channel2 => w.cinsel().channel_2(),
+                TimerChannelNum::Channel3 => w.cinsel().channel_3(),
+            }
+        });
+        reg.tcr().write(|w| w.crst().enabled());
+        reg.tcr().write(|w| w.crst().disabled());
+        reg.tcr().write(|w| w.cen().enabled());
+
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        Ok(Self {
+            deadline: 0,
+            interval: None,
+            info,
+            _event_pin: pin,
+        })
+    }
+
+    /// Number of edges counted since creation or the last [`Self::reset`], wrapping at `u32::MAX`.
+    pub fn count(&self) -> u32 {
+        self.info.regs.tc().read().bits()
+    }
+
+    /// Restarts counting from zero. A periodic wait restarts its interval from zero too.
+    pub fn reset(&mut self) {
+        let reg = self.info.regs;
+
+        reg.tcr().write(|w| w.crst().enabled());
+        reg.tcr().write(|w| w.crst().disabled());
+        reg.tcr().write(|w| w.cen().enabled());
+
+        if let Some(interval) = self.interval {
+            self.deadline = interval;
+            self.info.count_timer_arm(interval, 0, interval);
+        }
+    }
+
+    /// Waits asynchronously for `count` more edges.
+    ///
+    /// Stops the periodic interrupts started by [`Self::start_periodic`].
+    ///
+    /// Returns [`Error::InvalidEventCount`] if the count is zero.
+    pub async fn wait_for_events(&mut self, count: u32) -> Result<()> {
+        if count == 0 {
+            return Err(Error::InvalidEventCount);
+        }
+
+        self.interval = None;
+        let now = self.count();
+        self.deadline = now.wrapping_add(count);
+        self.info.count_timer_arm(self.deadline, now, count);
+
+        self.wait().await;
+        Ok(())
+    }
+
+    /// Starts interrupting every `interval` edges, each interval completed by [`Self::wait`].
+    ///
+    /// Intervals are counted from the previous deadline, so no edge is lost between waits. When a
+    /// wait comes late, the missed intervals complete immediately.
+    ///
+    /// Returns [`Error::InvalidEventCount`] if the interval is zero.
+    pub fn start_periodic(&mut self, interval: u32) -> Result<()> {
+        if interval == 0 {
+            return Err(Error::InvalidEventCount);
+        }
+
+        self.interval = Some(interval);
+        let now = self.count();
+        self.deadline = now.wrapping_add(interval);
+        self.info.count_timer_arm(self.deadline, now, interval);
+
+        Ok(())
+    }
+
+    /// Waits asynchronously for the interval started by [`Self::start_periodic`] to complete.
+    ///
+    /// Completes immediately if no interval was started.
+    pub fn wait(&mut self) -> impl Future<Output = ()> + use<'_, 'p, P> {
+        poll_fn(|cx| {
+            // Register the waker
+            self.info.waker.register(cx.waker());
+
+            if !self.info.has_count_timer_expired() {
+                return Poll::Pending;
+            }
+
+            if let Some(interval) = self.interval {
+                let deadline = self.deadline;
+                self.deadline = deadline.wrapping_add(interval);
+                self.info.count_timer_arm(self.deadline, deadline, interval);
+            }
+
+            Poll::Ready(())
+        })
+    }
+}
+
+impl<P: CaptureEvent> Drop for EventCounter<'_, P> {
+    fn drop(&mut self) {
+        let reg = self.info.regs;
+
+        self.info.count_timer_disable_interrupt();
+        reg.mr(self.info.channel.into()).write(|w| unsafe {
+            // SAFETY: It has no safety impact as we are clearing match register here
+            w.match_().bits(0)
+        });
+        // Give the module back to the timer clock for the other channels, which share the running
+        // counter
+        reg.ctcr().write(|w| w.ctmode().timer());
+    }
+}
+
+impl PwmMeasurement {
+    fn cycles_to_us(&self, cycles: u32) -> MicroSeconds {
+        MicroSeconds((u64::from(cycles) * 1_000_000 / u64::from(self.clk_freq)) as u32)
+    }
+
+    /// Time from rising edge to rising edge
+    pub fn period(&self) -> MicroSeconds {
+        self.cycles_to_us(self.period_cycles)
+    }
+
+    /// Time from rising edge to falling edge
+    pub fn high_time(&self) -> MicroSeconds {
+        self.cycles_to_us(self.high_cycles)
+    }
+
+    /// Signal frequency
+    pub fn frequency(&self) -> Hertz {
+        Hertz(self.clk_freq / self.period_cycles)
+    }
+
+    /// Share of the period the signal is high
+    pub fn duty_cycle(&self) -> CentiPercent {
+        CentiPercent::from_scaled(self.high_cycles.min(self.period_cycles), self.period_cycles)
+    }
+}
+
+impl<'p, P: CaptureEvent> PwmInput<'p, P> {
+    /// Creates a new `PwmInput` on `pin`, capturing rising edges with `rising_channel` and
+    /// falling edges with `falling_channel`. Measurements are raised by the capture interrupt of
+    /// `falling_channel`.
+    ///
+    /// Returns [`Error::Clock`] if an invalid clock configuration is used,
+    /// [`Error::PwmInputChannelMismatch`] if both channels do not belong to same CTimer and
+    /// [`Error::InvalidPwmInputChannel`] if `rising_channel` is channel 3.
+    pub fn new_async<R: Instance, F: Instance>(
+        _rising_channel: Peri<'p, R>,
+        _falling_channel: Peri<'p, F>,
+        pin: Peri<'p, P>,
+        clk: impl ConfigurableClock,
+        _irq: impl interrupt::typelevel::Binding<F::Interrupt, InterruptHandler<F>> + 'p,
+    ) -> Result<Self> {
+        let rising = R::info();
+        let info = F::info();
+
+        if rising.timer_type != TimerType::Capture
+            || info.timer_type != TimerType::Capture
+            || rising.module != info.module
+        {
+            return Err(Error::PwmInputChannelMismatch);
+        }
+
+        let clear = match rising.channel {
+            TimerChannelNum::Channel0 => Selcc::Channel0Rising,
+            TimerChannelNum::Channel1 => Selcc::Channel1Rising,
+            TimerChannelNum::Channel2 => Selcc::Channel2Rising,
+            TimerChannelNum::Channel3 => return Err(Error::InvalidPwmInputChannel),
+        };
+
+        let clk_freq = clk.get_clock_rate().map_err(Error::Clock)?;
+
+        pin.configure_for_event_capture();
+
+        for channel in [rising.channel, info.channel] {
+            info.inputmux
+                .ct32bit_cap(info.module)
+                .ct32bit_cap_sel(channel.into())
+                .modify(|_, w| w.capn_sel().variant(pin.get_trigger_input().into()));
+        }
+
+        let reg = info.regs;
+        reg.tcr().write(|w| w.cen().disabled());
+        reg.ctcr()
+            .write(|w| w.ctmode().timer().encc().set_bit().selcc().variant(clear));
+        rising.cap_timer_enable_rising_edge_event();
+        info.cap_timer_enable_falling_edge_event();
+        reg.tcr().write(|w| w.crst().enabled());
+        reg.tcr().write(|w| w.crst().disabled());
+        reg.tcr().write(|w| w.cen().enabled());
+
+        F::Interrupt::unpend();
+        unsafe { F::Interrupt::enable() };
+
+        Ok(Self {
+            clk_freq,
+            primed: false,
+            rising,
+            info,
+            _event_pin: pin,
+        })
+    }
+
+    /// Waits asynchronously for the next falling edge and returns the measured pulse.
+    ///
+    /// The first pulse after creation is skipped, as its period started before the first rising
+    /// edge. When called late, the period and high time may come from adjacent pulses. Never
+    /// completes for a signal stuck low or high.
+    pub async fn measure(&mut self) -> PwmMeasurement {
+        loop {
+            // Only wait for falling edges coming after this call
+            self.info.cap_timer_clear_interrupt();
+            self.info.cap_timer_interrupt_enable();
+
+            poll_fn(|cx| {
+                // Register the waker
+                self.info.waker.register(cx.waker());
+
+                if self.info.input_event_captured() {
+                    return Poll::Ready(());
+                }
+                Poll::Pending
+            })
+            .await;
+
+            let period_cycles = self.rising.regs.cr(self.rising.channel.into()).read().bits();
+            let high_cycles = self.info.regs.cr(self.info.channel.into()).read().bits();
+
+            // A zero period means no rising edge was captured yet
+            if period_cycles != 0 {
+                if self.primed {
+                    return PwmMeasurement {
+                        period_cycles,
+                        high_cycles,
+                        clk_freq: self.clk_freq,
+                    };
+                }
+                self.primed = true;
+            }
+        }
+    }
+}
+
+impl<P: CaptureEvent> Drop for PwmInput<'_, P> {
+    fn drop(&mut self) {
+        self.info.cap_timer_interrupt_disable();
+        self.info.cap_timer_disable_falling_edge_event();
+        self.rising.cap_timer_disable_rising_edge_event();
+        self.info.regs.ctcr().write(|w| w.ctmode().timer());
+    }
+}
+
 /// Basic PWM Object, Consumes `CTimer` peripheral hardware instances for match channel and PWM length channel on construction
 pub struct CTimerPwm<'p> {
     _lifetime: PhantomData<&'p ()>,
@@ -1,15 +1,18 @@
 //! Universal Asynchronous Receiver Transmitter (UART) driver.
 
-use core::future::{Future, poll_fn};
+use core::future::{Future, pending, poll_fn};
 use core::marker::PhantomData;
+use core::pin::pin;
+use core::sync::atomic::{AtomicU32, Ordering};
 use core::task::Poll;
 
-use embassy_futures::select::{Either, select};
+use embassy_futures::select::{Either, Either3, select, select3};
 use embassy_hal_internal::drop::OnDrop;
 use embassy_hal_internal::{Peri, PeripheralType};
 use embassy_sync::waitqueue::AtomicWaker;
 use paste::paste;
 
+use crate::clocks::{SleepDomain, StartEnable, WakeupGuard, enable_wakeup};
 use crate::dma::channel::Channel;
 use crate::dma::transfer::Transfer;
 use crate::flexcomm::{Clock, FlexcommRef};
@@ -53,6 +56,7 @@ pub struct UartTx<'a, M: Mode> {
 pub struct UartRx<'a, M: Mode> {
     info: Info,
     _flexcomm: FlexcommRef,
+    _wakeup: Option<WakeupGuard>,
     _buffer_config: Option<BufferConfig>,
     _rx_dma: Option<Channel<'a>>,
     _phantom: PhantomData<(&'a (), M)>,
@@ -81,6 +85,16 @@ pub struct Config {
     pub loopback_mode: Loop,
     /// Clock type
     pub clock: Clock,
+    /// Slave address for multidrop buses, forces 9-bit data
+    ///
+    /// When set, the receiver ignores the bus until an address character (9th bit set) matching
+    /// this address is received, see [`UartRx::blocking_wait_for_address`].
+    pub address: Option<u8>,
+    /// Wake the chip from deep sleep when a character is received
+    ///
+    /// The clock source is kept powered in deep sleep until the receiver is dropped, and the
+    /// character which woke the chip is kept in the RX FIFO for the pending read.
+    pub wake_on_rx: bool,
 }
 
 impl Default for Config {
@@ -97,6 +111,8 @@ impl Default for Config {
             continuous_clock: Cc::ClockOnCharacter,
             loopback_mode: Loop::Normal,
             clock: crate::flexcomm::Clock::Sfro,
+            address: None,
+            wake_on_rx: false,
         }
     }
 }
@@ -137,10 +153,30 @@ pub enum Error {
 
     /// TX Busy
     TxBusy,
+
+    /// Operation did not complete before the deadline
+    Timeout,
 }
 /// shorthand for -> `Result<T>`
 pub type Result<T> = core::result::Result<T, Error>;
 
+/// Character times without a start bit after which the RX line is considered idle
+#[cfg(feature = "time")]
+const IDLE_CHARACTERS: u64 = 2;
+
+/// 9th data bit, set on address characters in multidrop mode
+const ADDRESS_BIT: u16 = 1 << 8;
+
+/// Bytes moved so far by an unfinished DMA transfer of `len` bytes
+fn dma_progress(dma: &Channel, len: usize) -> usize {
+    // The transfer is still active, xfercount is the number of remaining bytes minus one
+    if dma.is_active() {
+        len.saturating_sub(dma.get_xfer_count() as usize + 1)
+    } else {
+        len
+    }
+}
+
 impl<'a, M: Mode> UartTx<'a, M> {
     fn new_inner<T: Instance>(_flexcomm: FlexcommRef, _tx_dma: Option<Channel<'a>>) -> Self {
         Self {
@@ -158,7 +194,7 @@ impl<'a> UartTx<'a, Blocking> {
     pub fn new_blocking<T: Instance>(_inner: Peri<'a, T>, tx: Peri<'a, impl TxPin<T>>, config: Config) -> Result<Self> {
         tx.as_tx();
 
-        let flexcomm = Uart::<Blocking>::init::<T>(Some(tx.into().reborrow()), None, None, None, config)?;
+        let (flexcomm, _) = Uart::<Blocking>::init::<T>(Some(tx.into().reborrow()), None, None, None, config)?;
 
         Ok(Self::new_inner::<T>(flexcomm, None))
     }
@@ -206,13 +242,13 @@ impl<'a> UartTx<'a, Blocking> {
 
     /// Flush UART TX blocking execution until done.
     pub fn blocking_flush(&mut self) -> Result<()> {
-        while self.info.regs.stat().read().txidle().bit_is_clear() {}
+        while !self.info.tx_done() {}
         Ok(())
     }
 
     /// Flush UART TX.
     pub fn flush(&mut self) -> Result<()> {
-        if self.info.regs.stat().read().txidle().bit_is_clear() {
+        if !self.info.tx_done() {
             Err(Error::TxBusy)
         } else {
             Ok(())
@@ -220,6 +256,23 @@ impl<'a> UartTx<'a, Blocking> {
     }
 }
 
+impl<M: Mode> UartTx<'_, M> {
+    /// Transmit an address character, with the 9th bit set, blocking execution until queued.
+    ///
+    /// Only meaningful with 9-bit data, the addressed slave receives the data written afterwards.
+    pub fn blocking_write_address(&mut self, address: u8) -> Result<()> {
+        while self.info.regs.fifostat().read().txnotfull().bit_is_clear() {}
+
+        // SAFETY: unsafe only used for .bits()
+        self.info
+            .regs
+            .fifowr()
+            .write(|w| unsafe { w.txdata().bits(ADDRESS_BIT | u16::from(address)) });
+
+        Ok(())
+    }
+}
+
 struct BufferConfig {
     #[cfg(feature = "time")]
     buffer_a: &'static mut [u8],
@@ -236,32 +289,21 @@ struct BufferConfig {
 impl<'a, M: Mode> UartRx<'a, M> {
     fn new_inner<T: Instance>(
         _flexcomm: FlexcommRef,
+        _wakeup: Option<WakeupGuard>,
         _rx_dma: Option<Channel<'a>>,
         _buffer_config: Option<BufferConfig>,
     ) -> Self {
         Self {
             info: T::info(),
             _flexcomm,
+            _wakeup,
             _buffer_config,
             _rx_dma,
             _phantom: PhantomData,
         }
     }
-}
-
-impl<'a> UartRx<'a, Blocking> {
-    /// Create a new blocking UART which can only receive data
-    pub fn new_blocking<T: Instance>(_inner: Peri<'a, T>, rx: Peri<'a, impl RxPin<T>>, config: Config) -> Result<Self> {
-        rx.as_rx();
-
-        let flexcomm = Uart::<Blocking>::init::<T>(None, Some(rx.into().reborrow()), None, None, config)?;
 
-        Ok(Self::new_inner::<T>(flexcomm, None, None))
-    }
-}
-
-impl UartRx<'_, Blocking> {
-    fn read_byte_internal(&mut self) -> Result<u8> {
+    fn read_fifo(&mut self) -> Result<u16> {
         if self.info.regs.fifostat().read().rxerr().bit_is_set() {
             self.info.regs.fifocfg().modify(|_, w| w.emptyrx().set_bit());
             self.info.regs.fifostat().modify(|_, w| w.rxerr().set_bit());
@@ -276,11 +318,82 @@ impl UartRx<'_, Blocking> {
             self.info.regs.stat().modify(|_, w| w.rxnoiseint().clear_bit_by_one());
             Err(Error::Noise)
         } else {
-            let byte = self.info.regs.fiford().read().rxdata().bits() as u8;
-            Ok(byte)
+            Ok(self.info.regs.fiford().read().rxdata().bits())
         }
     }
 
+    /// Change the multidrop slave address, `None` disables address matching.
+    ///
+    /// The receiver goes back to ignoring the bus until it is addressed.
+    pub fn set_address(&mut self, address: Option<u8>) {
+        let regs = self.info.regs;
+
+        regs.cfg().modify(|_, w| w.enable().disabled());
+        set_address_inner(regs, address);
+        regs.cfg().modify(|_, w| w.enable().enabled());
+    }
+
+    /// Ignore the bus again until the next matching address character, typically once the end
+    /// of a frame has been received.
+    pub fn listen(&mut self) {
+        self.info.regs.ctl().modify(|_, w| w.addrdet().enabled());
+    }
+
+    /// Discard received data which was not read yet, and clear pending receive errors.
+    ///
+    /// For a receiver created with a ping-pong buffer, the unread buffered data is dropped too.
+    pub fn flush_fifo(&mut self) {
+        self.info.flush_rx_fifo();
+
+        #[cfg(feature = "time")]
+        if let (Some(rx_dma), Some(buffer_config)) = (self._rx_dma.as_ref(), self._buffer_config.as_mut()) {
+            ping_pong_discard(
+                rx_dma,
+                &mut buffer_config.consumer_buf,
+                &mut buffer_config.read_off,
+                buffer_config.buffer_a.len(),
+            );
+        }
+    }
+
+    /// Stop receiving, characters arriving while paused are dropped.
+    pub fn pause(&mut self) {
+        self.info.pause_rx();
+    }
+
+    /// Resume receiving after [`UartRx::pause`], discarding unread data.
+    pub fn resume(&mut self) {
+        self.flush_fifo();
+        self.info.resume_rx();
+    }
+
+    /// Stop ignoring the bus, once the matching address character has been dequeued.
+    fn addressed(&mut self, data: u16) -> bool {
+        if data & ADDRESS_BIT == 0 {
+            return false;
+        }
+
+        self.info.regs.ctl().modify(|_, w| w.addrdet().disabled());
+        true
+    }
+}
+
+impl<'a> UartRx<'a, Blocking> {
+    /// Create a new blocking UART which can only receive data
+    pub fn new_blocking<T: Instance>(_inner: Peri<'a, T>, rx: Peri<'a, impl RxPin<T>>, config: Config) -> Result<Self> {
+        rx.as_rx();
+
+        let (flexcomm, wakeup) = Uart::<Blocking>::init::<T>(None, Some(rx.into().reborrow()), None, None, config)?;
+
+        Ok(Self::new_inner::<T>(flexcomm, wakeup, None, None))
+    }
+}
+
+impl UartRx<'_, Blocking> {
+    fn read_byte_internal(&mut self) -> Result<u8> {
+        self.read_fifo().map(|data| data as u8)
+    }
+
     fn read_byte(&mut self) -> Result<u8> {
         if self.info.regs.fifostat().read().rxnotempty().bit_is_clear() {
             Err(Error::RxFifoEmpty)
@@ -311,6 +424,20 @@ impl UartRx<'_, Blocking> {
 
         Ok(())
     }
+
+    /// Wait until this slave is addressed in multidrop mode, blocking execution until done.
+    ///
+    /// Data received afterwards is read normally, until [`UartRx::listen`] is called.
+    pub fn blocking_wait_for_address(&mut self) -> Result<()> {
+        loop {
+            while self.info.regs.fifostat().read().rxnotempty().bit_is_clear() {}
+
+            let data = self.read_fifo()?;
+            if self.addressed(data) {
+                return Ok(());
+            }
+        }
+    }
 }
 
 impl<'a, M: Mode> Uart<'a, M> {
@@ -320,7 +447,7 @@ impl<'a, M: Mode> Uart<'a, M> {
         rts: Option<Peri<'a, AnyPin>>,
         cts: Option<Peri<'a, AnyPin>>,
         config: Config,
-    ) -> Result<FlexcommRef> {
+    ) -> Result<(FlexcommRef, Option<WakeupGuard>)> {
         let flexcomm = T::enable(config.clock);
         T::into_usart();
 
@@ -348,10 +475,32 @@ impl<'a, M: Mode> Uart<'a, M> {
             regs.cfg().modify(|_, w| w.ctsen().enabled());
         }
 
-        Self::set_baudrate_inner::<T>(config.baudrate, config.clock)?;
-        Self::set_uart_config::<T>(config);
+        let wakeup = if rx.is_some() && config.wake_on_rx {
+            Some(Self::enable_wakeup(&T::info(), config.clock)?)
+        } else {
+            None
+        };
+
+        let info = T::info();
+        info.state
+            .source_clock_hz
+            .store(Self::get_fc_freq(config.clock)?, Ordering::Relaxed);
+
+        Self::set_baudrate_inner(&info, config.baudrate)?;
+        Self::set_uart_config(&info, config);
+
+        Ok((flexcomm, wakeup))
+    }
+
+    fn enable_wakeup(info: &Info, clock: Clock) -> Result<WakeupGuard> {
+        // The RX FIFO level wakes the chip (WAKERX), as long as the USART keeps its clock
+        let domains: &[SleepDomain] = match clock {
+            Clock::Sfro => &[SleepDomain::Sfro],
+            Clock::Ffro => &[SleepDomain::Ffro],
+            _ => return Err(Error::InvalidArgument),
+        };
 
-        Ok(flexcomm)
+        Ok(enable_wakeup(domains, info.start_enable))
     }
 
     fn get_fc_freq(clock: Clock) -> Result<u32> {
@@ -363,15 +512,15 @@ impl<'a, M: Mode> Uart<'a, M> {
         }
     }
 
-    fn set_baudrate_inner<T: Instance>(baudrate: u32, clock: Clock) -> Result<()> {
-        // Get source clock frequency according to clock type.
-        let source_clock_hz = Self::get_fc_freq(clock)?;
+    fn set_baudrate_inner(info: &Info, baudrate: u32) -> Result<()> {
+        // Source clock frequency, as selected by init()
+        let source_clock_hz = info.state.source_clock_hz.load(Ordering::Relaxed);
 
         if baudrate == 0 {
             return Err(Error::InvalidArgument);
         }
 
-        let regs = T::info().regs;
+        let regs = info.regs;
 
         // If synchronous master mode is enabled, only configure the BRG value.
         if regs.cfg().read().syncen().is_synchronous_mode() {
@@ -431,11 +580,13 @@ impl<'a, M: Mode> Uart<'a, M> {
             regs.brg().write(|w| unsafe { w.brgval().bits(brg as u16) });
         }
 
+        info.state.baudrate.store(baudrate, Ordering::Relaxed);
+
         Ok(())
     }
 
-    fn set_uart_config<T: Instance>(config: Config) {
-        let regs = T::info().regs;
+    fn set_uart_config(info: &Info, config: Config) {
+        let regs = info.regs;
 
         regs.cfg().modify(|_, w| w.enable().disabled());
 
@@ -454,13 +605,48 @@ impl<'a, M: Mode> Uart<'a, M> {
                 .variant(config.clock_polarity)
         });
 
+        set_address_inner(regs, config.address);
+
         regs.cfg().modify(|_, w| w.enable().enabled());
     }
 
+    /// Change the baud rate of a live instance, once pending transmissions are done.
+    pub fn set_baudrate(&mut self, baudrate: u32) -> Result<()> {
+        while !self.info.tx_done() {}
+
+        Self::set_baudrate_inner(&self.info, baudrate)
+    }
+
+    /// Change the baud rate, character format, multidrop address and deep sleep wake-up of a live
+    /// instance, once pending transmissions are done.
+    ///
+    /// The clock source cannot change at runtime, `config.clock` must match the one the instance
+    /// was created with.
+    pub fn set_config(&mut self, config: &Config) -> Result<()> {
+        if Self::get_fc_freq(config.clock)? != self.info.state.source_clock_hz.load(Ordering::Relaxed) {
+            return Err(Error::InvalidArgument);
+        }
+
+        while !self.info.tx_done() {}
+
+        if config.wake_on_rx != self.rx._wakeup.is_some() {
+            self.rx._wakeup = if config.wake_on_rx {
+                Some(Self::enable_wakeup(&self.info, config.clock)?)
+            } else {
+                None
+            };
+        }
+
+        Self::set_baudrate_inner(&self.info, config.baudrate)?;
+        Self::set_uart_config(&self.info, *config);
+
+        Ok(())
+    }
+
     /// Deinitializes a USART instance.
     pub fn deinit(&self) -> Result<()> {
         // This function waits for TX complete, disables TX and RX, and disables the USART clock
-        while self.info.regs.stat().read().txidle().bit_is_clear() {
+        while !self.info.tx_done() {
             // When 0, indicates that the transmitter is currently in the process of sending data.
         }
 
@@ -502,6 +688,22 @@ impl<'a, M: Mode> Uart<'a, M> {
     }
 }
 
+/// Configure address matching, the USART must be disabled
+fn set_address_inner(regs: &crate::pac::usart0::RegisterBlock, address: Option<u8>) {
+    match address {
+        Some(address) => {
+            // SAFETY: unsafe only used for .bits()
+            regs.addr().write(|w| unsafe { w.address().bits(address) });
+            regs.cfg().modify(|_, w| w.datalen().bit_9().autoaddr().enabled());
+            regs.ctl().modify(|_, w| w.addrdet().enabled());
+        }
+        None => {
+            regs.cfg().modify(|_, w| w.autoaddr().disabled());
+            regs.ctl().modify(|_, w| w.addrdet().disabled());
+        }
+    }
+}
+
 impl<'a> Uart<'a, Blocking> {
     /// Create a new blocking UART
     pub fn new_blocking<T: Instance>(
@@ -513,12 +715,12 @@ impl<'a> Uart<'a, Blocking> {
         tx.as_tx();
         rx.as_rx();
 
-        let flexcomm = Self::init::<T>(Some(tx.into()), Some(rx.into()), None, None, config)?;
+        let (flexcomm, wakeup) = Self::init::<T>(Some(tx.into()), Some(rx.into()), None, None, config)?;
 
         Ok(Self {
             info: T::info(),
             tx: UartTx::new_inner::<T>(flexcomm.clone(), None),
-            rx: UartRx::new_inner::<T>(flexcomm, None, None),
+            rx: UartRx::new_inner::<T>(flexcomm, wakeup, None, None),
         })
     }
 
@@ -564,7 +766,7 @@ impl<'a> UartTx<'a, Async> {
     ) -> Result<Self> {
         tx.as_tx();
 
-        let flexcomm = Uart::<Async>::init::<T>(Some(tx.into()), None, None, None, config)?;
+        let (flexcomm, _) = Uart::<Async>::init::<T>(Some(tx.into()), None, None, None, config)?;
 
         T::Interrupt::unpend();
         unsafe { T::Interrupt::enable() };
@@ -576,54 +778,75 @@ impl<'a> UartTx<'a, Async> {
 
     /// Transmit the provided buffer asynchronously.
     pub async fn write(&mut self, buf: &[u8]) -> Result<()> {
+        self.write_inner(buf, pending()).await.map(|_| ())
+    }
+
+    /// Transmit the provided buffer asynchronously, giving up after `timeout`.
+    ///
+    /// Returns the number of bytes queued for transmission, fewer than `buf.len()` if the
+    /// deadline passed, or [`Error::Timeout`] if nothing could be queued at all.
+    #[cfg(feature = "time")]
+    pub async fn write_with_timeout(&mut self, buf: &[u8], timeout: embassy_time::Duration) -> Result<usize> {
+        self.write_inner(buf, embassy_time::Timer::after(timeout)).await
+    }
+
+    async fn write_inner(&mut self, buf: &[u8], timeout: impl Future<Output = ()>) -> Result<usize> {
         let regs = self.info.regs;
+        // an async UART instance cannot be created without a dma channel
+        let tx_dma = self._tx_dma.as_ref().ok_or(Error::Fail)?;
+        let mut timeout = pin!(timeout);
 
         // Disable DMA on completion/cancellation
         let _dma_guard = OnDrop::new(|| {
             regs.fifocfg().modify(|_, w| w.dmatx().disabled());
         });
 
+        let mut bytes_written = 0;
+
         for chunk in buf.chunks(1024) {
+            let len = chunk.len();
+
             regs.fifocfg().modify(|_, w| w.dmatx().enabled());
 
-            let transfer = Transfer::new_write(
-                // an async UART instance cannot be created without a dma channel
-                self._tx_dma.as_ref().ok_or(Error::Fail)?,
-                chunk,
-                regs.fifowr().as_ptr() as *mut u8,
-                Default::default(),
-            );
+            let transfer = Transfer::new_write(tx_dma, chunk, regs.fifowr().as_ptr() as *mut u8, Default::default());
 
-            let res = select(
-                transfer,
-                poll_fn(|cx| {
-                    self.info.tx_waker.register(cx.waker());
+            let error = poll_fn(|cx| {
+                self.info.tx_waker.register(cx.waker());
 
-                    self.info.regs.fifointenset().write(|w| w.txerr().set_bit());
+                self.info.regs.fifointenset().write(|w| w.txerr().set_bit());
 
-                    let fifointstat = self.info.regs.fifointstat().read();
+                let fifointstat = self.info.regs.fifointstat().read();
 
-                    self.info.regs.fifostat().write(|w| w.txerr().set_bit());
+                self.info.regs.fifostat().write(|w| w.txerr().set_bit());
 
-                    if fifointstat.txerr().bit_is_set() {
-                        Poll::Ready(Err(Error::Overrun))
-                    } else {
-                        Poll::Pending
-                    }
-                }),
-            )
-            .await;
+                if fifointstat.txerr().bit_is_set() {
+                    Poll::Ready(Error::Overrun)
+                } else {
+                    Poll::Pending
+                }
+            });
 
-            match res {
-                Either::First(()) | Either::Second(Ok(())) => (),
-                Either::Second(e) => return e,
+            match select3(transfer, error, timeout.as_mut()).await {
+                Either3::First(()) => bytes_written += len,
+                Either3::Second(e) => return Err(e),
+                Either3::Third(()) => {
+                    bytes_written += dma_progress(tx_dma, len);
+                    return if bytes_written == 0 {
+                        Err(Error::Timeout)
+                    } else {
+                        Ok(bytes_written)
+                    };
+                }
             }
         }
 
-        Ok(())
+        Ok(bytes_written)
     }
 
-    /// Flush UART TX asynchronously.
+    /// Flush UART TX asynchronously, waiting until the last stop bit has left the wire.
+    ///
+    /// After [`UartTx::write`] returns, the DMA is done but data may still sit in the TX FIFO. Wait
+    /// for this before turning an RS-485 transceiver around or entering sleep.
     pub fn flush(&mut self) -> impl Future<Output = Result<()>> + use<'_, 'a> {
         poll_fn(|cx| {
             self.info.tx_waker.register(cx.waker());
@@ -635,7 +858,7 @@ impl<'a> UartTx<'a, Async> {
 
             self.info.regs.fifostat().write(|w| w.txerr().set_bit());
 
-            if self.info.regs.stat().read().txidle().bit_is_set() {
+            if self.info.tx_done() {
                 Poll::Ready(Ok(()))
             } else if fifointstat.txerr().bit_is_set() {
                 Poll::Ready(Err(Error::Overrun))
@@ -657,14 +880,14 @@ impl<'a> UartRx<'a, Async> {
     ) -> Result<Self> {
         rx.as_rx();
 
-        let flexcomm = Uart::<Async>::init::<T>(None, Some(rx.into()), None, None, config)?;
+        let (flexcomm, wakeup) = Uart::<Async>::init::<T>(None, Some(rx.into()), None, None, config)?;
 
         T::Interrupt::unpend();
         unsafe { T::Interrupt::enable() };
 
         let rx_dma = dma::Dma::reserve_channel(rx_dma);
 
-        Ok(Self::new_inner::<T>(flexcomm, rx_dma, None))
+        Ok(Self::new_inner::<T>(flexcomm, wakeup, rx_dma, None))
     }
 
     /// Create a new DMA enabled UART which can only receive data, using a ping-pong buffer to enable continuous DMA reception.
@@ -684,7 +907,7 @@ impl<'a> UartRx<'a, Async> {
         rx.as_rx();
 
         let mut rx = rx.into();
-        let flexcomm = Uart::<Async>::init::<T>(None, Some(rx.reborrow()), None, None, config)?;
+        let (flexcomm, wakeup) = Uart::<Async>::init::<T>(None, Some(rx.reborrow()), None, None, config)?;
 
         T::Interrupt::unpend();
         unsafe { T::Interrupt::enable() };
@@ -714,6 +937,7 @@ impl<'a> UartRx<'a, Async> {
 
         Ok(Self::new_inner::<T>(
             flexcomm,
+            wakeup,
             Some(rx_dma),
             Some(BufferConfig {
                 buffer_a,
@@ -730,88 +954,191 @@ impl<'a> UartRx<'a, Async> {
         #[cfg(feature = "time")]
         {
             if self._buffer_config.is_some() {
-                self.read_buffered(buf).await
+                self.read_buffered(buf, buf.len(), None).await
             } else {
-                self.read_unbuffered(buf).await
+                self.read_unbuffered(buf, pending()).await
             }
         }
 
         #[cfg(not(feature = "time"))]
         {
-            self.read_unbuffered(buf).await
+            self.read_unbuffered(buf, pending()).await
+        }
+    }
+
+    /// Read from UART RX asynchronously, giving up after `timeout`.
+    ///
+    /// Returns the number of bytes read, fewer than `buf.len()` if the deadline passed, or
+    /// [`Error::Timeout`] if nothing was received at all.
+    #[cfg(feature = "time")]
+    pub async fn read_with_timeout(&mut self, buf: &mut [u8], timeout: embassy_time::Duration) -> Result<usize> {
+        let deadline = embassy_time::Instant::now() + timeout;
+
+        if self._buffer_config.is_some() {
+            self.read_buffered(buf, buf.len(), Some(deadline)).await
+        } else {
+            self.read_unbuffered(buf, embassy_time::Timer::at(deadline)).await
+        }
+    }
+
+    /// Wait until this slave is addressed in multidrop mode.
+    ///
+    /// Data received afterwards is read normally, until [`UartRx::listen`] is called.
+    pub async fn wait_for_address(&mut self) -> Result<()> {
+        poll_fn(|cx| {
+            self.info.rx_waker.register(cx.waker());
+
+            while self.info.regs.fifostat().read().rxnotempty().bit_is_set() {
+                let data = self.read_fifo()?;
+                if self.addressed(data) {
+                    return Poll::Ready(Ok(()));
+                }
+            }
+
+            self.info.regs.fifointenset().write(|w| w.rxlvl().set_bit());
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Wait for data, then read what was already received, up to `buf.len()` bytes.
+    ///
+    /// Unlike [`UartRx::read`], this completes as soon as a byte is available, which is what
+    /// generic [`embedded_io_async::Read`] users expect. The receive FIFO is drained by the CPU.
+    async fn read_available(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
         }
+
+        poll_fn(|cx| {
+            self.info.rx_waker.register(cx.waker());
+
+            if self.info.regs.fifostat().read().rxnotempty().bit_is_set() {
+                return Poll::Ready(());
+            }
+
+            self.info.regs.fifointenset().write(|w| w.rxlvl().set_bit());
+            Poll::Pending
+        })
+        .await;
+
+        let mut count = 0;
+        for byte in buf.iter_mut() {
+            if self.info.regs.fifostat().read().rxnotempty().bit_is_clear() {
+                break;
+            }
+            *byte = self.read_fifo()? as u8;
+            count += 1;
+        }
+
+        Ok(count)
     }
 
-    async fn read_unbuffered(&mut self, buf: &mut [u8]) -> Result<usize> {
+    async fn read_unbuffered(&mut self, buf: &mut [u8], timeout: impl Future<Output = ()>) -> Result<usize> {
         let regs = self.info.regs;
+        let rx_dma = self._rx_dma.as_ref().ok_or(Error::Fail)?;
+        let mut timeout = pin!(timeout);
+
+        let mut bytes_read = 0;
 
         for chunk in buf.chunks_mut(1024) {
+            let len = chunk.len();
+
             regs.fifocfg().modify(|_, w| w.dmarx().enabled());
 
-            let transfer = Transfer::new_read(
-                self._rx_dma.as_ref().ok_or(Error::Fail)?,
-                regs.fiford().as_ptr() as *mut u8,
-                chunk,
-                Default::default(),
-            );
+            let transfer = Transfer::new_read(rx_dma, regs.fiford().as_ptr() as *mut u8, chunk, Default::default());
 
             // Disable DMA on completion/cancellation
             let _dma_guard = OnDrop::new(|| {
                 regs.fifocfg().modify(|_, w| w.dmarx().disabled());
             });
 
-            let res = select(
-                transfer,
-                poll_fn(|cx| {
-                    self.info.rx_waker.register(cx.waker());
+            match select3(transfer, self.info.wait_for_rx_error(), timeout.as_mut()).await {
+                Either3::First(()) => bytes_read += len,
+                Either3::Second(e) => return Err(e),
+                Either3::Third(()) => {
+                    bytes_read += dma_progress(rx_dma, len);
+                    return if bytes_read == 0 {
+                        Err(Error::Timeout)
+                    } else {
+                        Ok(bytes_read)
+                    };
+                }
+            }
+        }
 
-                    self.info
-                        .regs
-                        .intenset()
-                        .write(|w| w.framerren().set_bit().parityerren().set_bit().rxnoiseen().set_bit());
+        Ok(bytes_read)
+    }
 
-                    self.info.regs.fifointenset().write(|w| w.rxerr().set_bit());
+    /// Read from UART RX asynchronously until the line goes idle or `buf` is full, returning the
+    /// number of bytes read.
+    ///
+    /// The line is idle once no character started for two character times. Waiting for the first
+    /// character does not time out.
+    /// Note: requires time-driver, as this processor has no UART Idle bus indicator the idle time
+    ///       is measured with a timer, whose tick limits its resolution.
+    #[cfg(feature = "time")]
+    pub async fn read_until_idle(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let regs = self.info.regs;
+        let rx_dma = self._rx_dma.as_ref().ok_or(Error::Fail)?;
+        let idle_timeout_us = self.info.idle_timeout_us();
 
-                    let stat = self.info.regs.stat().read();
-                    let fifointstat = self.info.regs.fifointstat().read();
+        let mut bytes_read = 0;
 
-                    self.info.regs.stat().write(|w| {
-                        w.framerrint()
-                            .clear_bit_by_one()
-                            .parityerrint()
-                            .clear_bit_by_one()
-                            .rxnoiseint()
-                            .clear_bit_by_one()
-                    });
+        for chunk in buf.chunks_mut(1024) {
+            let len = chunk.len();
 
-                    self.info.regs.fifostat().write(|w| w.rxerr().set_bit());
+            regs.fifocfg().modify(|_, w| w.dmarx().enabled());
 
-                    if stat.framerrint().bit_is_set() {
-                        Poll::Ready(Err(Error::Framing))
-                    } else if stat.parityerrint().bit_is_set() {
-                        Poll::Ready(Err(Error::Parity))
-                    } else if stat.rxnoiseint().bit_is_set() {
-                        Poll::Ready(Err(Error::Noise))
-                    } else if fifointstat.rxerr().bit_is_set() {
-                        Poll::Ready(Err(Error::Overrun))
-                    } else {
-                        Poll::Pending
+            let transfer = Transfer::new_read(rx_dma, regs.fiford().as_ptr() as *mut u8, chunk, Default::default());
+
+            // Disable DMA on completion/cancellation
+            let _dma_guard = OnDrop::new(|| {
+                regs.fifocfg().modify(|_, w| w.dmarx().disabled());
+            });
+
+            let idle = async {
+                loop {
+                    if bytes_read == 0 {
+                        self.info.wait_for_start().await;
                     }
-                }),
-            )
-            .await;
 
+                    while let Either::First(()) = select(
+                        self.info.wait_for_start(),
+                        embassy_time::Timer::after_micros(idle_timeout_us),
+                    )
+                    .await
+                    {}
+
+                    let count = dma_progress(rx_dma, len);
+
+                    // A stale start flag does not end the read before anything was received
+                    if count > 0 || bytes_read > 0 {
+                        break count;
+                    }
+                }
+            };
+
+            let res = select3(transfer, self.info.wait_for_rx_error(), idle).await;
             match res {
-                Either::First(()) | Either::Second(Ok(())) => (),
-                Either::Second(Err(e)) => return Err(e),
+                Either3::First(()) => bytes_read += len,
+                Either3::Second(e) => return Err(e),
+                Either3::Third(count) => return Ok(bytes_read + count),
             }
         }
 
-        Ok(buf.len())
+        Ok(bytes_read)
     }
 
+    /// Read from the ping-pong buffer until at least `min_len` bytes were read, and no more than
+    /// `buf.len()`
     #[cfg(feature = "time")]
-    async fn read_buffered(&mut self, buf: &mut [u8]) -> Result<usize> {
+    async fn read_buffered(
+        &mut self,
+        buf: &mut [u8],
+        min_len: usize,
+        deadline: Option<embassy_time::Instant>,
+    ) -> Result<usize> {
         let rx_dma = self._rx_dma.as_ref().ok_or(Error::Fail)?;
         let buffer_config = self._buffer_config.as_mut().ok_or(Error::Fail)?;
 
@@ -820,8 +1147,18 @@ impl<'a> UartRx<'a, Async> {
         // Total bytes read into user buffer
         let mut bytes_read = 0;
 
+        let min_len = min_len.min(buf.len());
+
         // As the Rx Idle interrupt is not present for this processor, we must poll to see if new data is available
-        while bytes_read < buf.len() {
+        while bytes_read < min_len {
+            if deadline.is_some_and(|deadline| embassy_time::Instant::now() >= deadline) {
+                return if bytes_read == 0 {
+                    Err(Error::Timeout)
+                } else {
+                    Ok(bytes_read)
+                };
+            }
+
             // Check for ping-pong buffer overrun error from DMA
             if rx_dma.check_and_clear_overrun_error() {
                 return Err(Error::Overrun);
@@ -909,7 +1246,7 @@ impl<'a> UartRx<'a, Async> {
                 }
 
                 // No need to delay if we already has all the data we requested
-                if bytes_read < buf.len() {
+                if bytes_read < min_len {
                     embassy_time::Timer::after_micros(buffer_config.polling_rate).await;
                 }
             } else {
@@ -965,7 +1302,13 @@ impl<'a> UartRx<'a, Async> {
                 });
 
                 if bytes_read == 0 {
-                    rx_active.await?;
+                    match deadline {
+                        Some(deadline) => match select(rx_active, embassy_time::Timer::at(deadline)).await {
+                            Either::First(r) => r?,
+                            Either::Second(()) => return Err(Error::Timeout),
+                        },
+                        None => rx_active.await?,
+                    }
                 } else {
                     let res = select(rx_active, embassy_time::Timer::after_micros(buffer_config.polling_rate)).await;
 
@@ -984,6 +1327,253 @@ impl<'a> UartRx<'a, Async> {
     }
 }
 
+impl<'a> UartRx<'a, Async> {
+    /// Turn this receiver into a [`RingBufferedUartRx`] continuously receiving into `buffer`.
+    ///
+    /// `buffer` is used as two halves, its length must be even and at most twice
+    /// [`MAX_TRANSFER_COUNT`](dma::MAX_TRANSFER_COUNT).
+    #[cfg(feature = "time")]
+    pub fn into_ring_buffered(self, buffer: &'static mut [u8]) -> Result<RingBufferedUartRx<'a>> {
+        if buffer.is_empty() || !buffer.len().is_multiple_of(2) || buffer.len() > 2 * dma::MAX_TRANSFER_COUNT {
+            return Err(Error::InvalidArgument);
+        }
+
+        // A receiver created with a ping-pong buffer already owns a running channel
+        if self._buffer_config.is_some() {
+            return Err(Error::InvalidArgument);
+        }
+
+        let rx_dma = self._rx_dma.ok_or(Error::Fail)?;
+        let regs = self.info.regs;
+
+        let (buffer_a, buffer_b) = buffer.split_at_mut(buffer.len() / 2);
+        regs.fifocfg().modify(|_, w| w.dmarx().enabled());
+        rx_dma.configure_channel_ping_pong(
+            dma::transfer::Direction::PeripheralToMemory,
+            regs.fiford().as_ptr() as *const u8 as *const u32,
+            buffer_a.as_mut_ptr() as *mut u32,
+            buffer_b.as_mut_ptr() as *mut u32,
+            buffer_a.len(),
+            dma::transfer::TransferOptions {
+                width: dma::transfer::Width::Bit8,
+                priority: dma::transfer::Priority::Priority0,
+            },
+        );
+        rx_dma.enable_channel();
+        rx_dma.trigger_channel();
+
+        Ok(RingBufferedUartRx {
+            info: self.info,
+            _flexcomm: self._flexcomm,
+            _wakeup: self._wakeup,
+            rx_dma,
+            buffer_a,
+            buffer_b,
+            read_off: 0,
+            consumer_buf: dma::PingPongSelector::BufferA,
+        })
+    }
+}
+
+/// Uart RX driver receiving into a circular DMA buffer.
+///
+/// Reception never stops, the application reads data out at its own pace: bursts arriving while
+/// the executor is busy are kept, as long as the buffer does not wrap around unread data.
+/// Note: requires time-driver, as this processor has no UART Idle bus indicator a partially
+/// filled half of the buffer is checked one idle time after each start bit.
+#[cfg(feature = "time")]
+pub struct RingBufferedUartRx<'a> {
+    info: Info,
+    _flexcomm: FlexcommRef,
+    _wakeup: Option<WakeupGuard>,
+    rx_dma: Channel<'a>,
+    buffer_a: &'static mut [u8],
+    buffer_b: &'static mut [u8],
+    read_off: usize,
+    consumer_buf: dma::PingPongSelector,
+}
+
+#[cfg(feature = "time")]
+impl RingBufferedUartRx<'_> {
+    /// Read received data into `buf`, waiting until at least one byte is available.
+    ///
+    /// Returns the number of bytes read, or [`Error::Overrun`] if unread data was overwritten.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            if self.rx_dma.check_and_clear_overrun_error() {
+                return Err(Error::Overrun);
+            }
+
+            let count = self.read_available(buf)?;
+            if count > 0 {
+                return Ok(count);
+            }
+
+            let half_done = poll_fn(|cx| {
+                self.rx_dma.get_waker().register(cx.waker());
+
+                if self.rx_dma.buffer_status(self.consumer_buf) == dma::BufferStatus::Granted {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            });
+
+            // A start bit announces a character, give it time to land in the buffer
+            let received = async {
+                self.info.wait_for_start().await;
+                embassy_time::Timer::after_micros(self.info.idle_timeout_us()).await;
+            };
+
+            if let Either3::Third(e) = select3(half_done, received, self.info.wait_for_rx_error()).await {
+                return Err(e);
+            }
+        }
+    }
+
+    /// Read received data into `buf`, waiting at most `timeout` for the first byte.
+    ///
+    /// Returns [`Error::Timeout`] if nothing was received in time.
+    pub async fn read_with_timeout(&mut self, buf: &mut [u8], timeout: embassy_time::Duration) -> Result<usize> {
+        match select(self.read(buf), embassy_time::Timer::after(timeout)).await {
+            Either::First(res) => res,
+            Either::Second(()) => Err(Error::Timeout),
+        }
+    }
+
+    /// Discard received data which was not read yet, including the data still in the RX FIFO.
+    pub fn flush_fifo(&mut self) {
+        self.info.flush_rx_fifo();
+        // Let the DMA drain what was in flight before the FIFO was emptied
+        ping_pong_discard(
+            &self.rx_dma,
+            &mut self.consumer_buf,
+            &mut self.read_off,
+            self.buffer_a.len(),
+        );
+    }
+
+    /// Stop receiving, characters arriving while paused are dropped.
+    pub fn pause(&mut self) {
+        self.info.pause_rx();
+    }
+
+    /// Resume receiving after [`RingBufferedUartRx::pause`], discarding unread data.
+    pub fn resume(&mut self) {
+        self.flush_fifo();
+        self.info.resume_rx();
+    }
+
+    fn read_available(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let half_size = self.buffer_a.len();
+        let available = ping_pong_available(&self.rx_dma, self.consumer_buf, half_size, self.read_off);
+        let count = available.min(buf.len());
+        if count == 0 {
+            return Ok(0);
+        }
+
+        let half = match self.consumer_buf {
+            dma::PingPongSelector::BufferA => &self.buffer_a,
+            dma::PingPongSelector::BufferB => &self.buffer_b,
+        };
+        let src = half.get(self.read_off..self.read_off + count).ok_or(Error::Read)?;
+        let dst = buf.get_mut(..count).ok_or(Error::Read)?;
+        dst.copy_from_slice(src);
+
+        ping_pong_consume(
+            &self.rx_dma,
+            &mut self.consumer_buf,
+            &mut self.read_off,
+            half_size,
+            count,
+        );
+
+        Ok(count)
+    }
+}
+
+/// Number of bytes received into the current half of a ping-pong buffer, not read yet
+#[cfg(feature = "time")]
+fn ping_pong_available(
+    rx_dma: &Channel,
+    consumer_buf: dma::PingPongSelector,
+    half_size: usize,
+    read_off: usize,
+) -> usize {
+    if rx_dma.buffer_status(consumer_buf) == dma::BufferStatus::Granted {
+        return half_size - read_off;
+    }
+
+    // DMA writes to the other half only once this one is granted
+    if rx_dma.current_buffer() != consumer_buf {
+        return 0;
+    }
+
+    // xfercount counts down to 0x3FF at the end of transfer, which means the half was just
+    // granted after the check above
+    let xfercount = rx_dma.get_xfer_count();
+    if xfercount == 0x3FF {
+        return 0;
+    }
+
+    let written = half_size.saturating_sub(xfercount as usize + 1);
+    written.saturating_sub(read_off)
+}
+
+/// Mark `count` bytes of the current half of a ping-pong buffer as read
+#[cfg(feature = "time")]
+fn ping_pong_consume(
+    rx_dma: &Channel,
+    consumer_buf: &mut dma::PingPongSelector,
+    read_off: &mut usize,
+    half_size: usize,
+    count: usize,
+) {
+    *read_off += count;
+
+    // The whole half has been read, hand it back to the DMA
+    if *read_off == half_size {
+        *read_off = 0;
+        // SAFETY: the DMA is writing to the other half, it only comes back to this one once
+        //         the other half is full
+        unsafe { rx_dma.commit_buffer(*consumer_buf) };
+
+        *consumer_buf = match *consumer_buf {
+            dma::PingPongSelector::BufferA => dma::PingPongSelector::BufferB,
+            dma::PingPongSelector::BufferB => dma::PingPongSelector::BufferA,
+        };
+    }
+}
+
+/// Mark everything received into a ping-pong buffer as read
+#[cfg(feature = "time")]
+fn ping_pong_discard(
+    rx_dma: &Channel,
+    consumer_buf: &mut dma::PingPongSelector,
+    read_off: &mut usize,
+    half_size: usize,
+) {
+    loop {
+        let count = ping_pong_available(rx_dma, *consumer_buf, half_size, *read_off);
+        if count == 0 {
+            break;
+        }
+        ping_pong_consume(rx_dma, consumer_buf, read_off, half_size, count);
+    }
+}
+
+#[cfg(feature = "time")]
+impl Drop for RingBufferedUartRx<'_> {
+    fn drop(&mut self) {
+        self.rx_dma.abort();
+        self.info.regs.fifocfg().modify(|_, w| w.dmarx().disabled());
+    }
+}
+
 impl<'a> Uart<'a, Async> {
     /// Create a new DMA enabled UART
     pub fn new_async<T: Instance>(
@@ -1007,12 +1597,12 @@ impl<'a> Uart<'a, Async> {
         let tx_dma = dma::Dma::reserve_channel(tx_dma);
         let rx_dma = dma::Dma::reserve_channel(rx_dma);
 
-        let flexcomm = Self::init::<T>(Some(tx.into()), Some(rx.into()), None, None, config)?;
+        let (flexcomm, wakeup) = Self::init::<T>(Some(tx.into()), Some(rx.into()), None, None, config)?;
 
         Ok(Self {
             info: T::info(),
             tx: UartTx::new_inner::<T>(flexcomm.clone(), tx_dma),
-            rx: UartRx::new_inner::<T>(flexcomm, rx_dma, None),
+            rx: UartRx::new_inner::<T>(flexcomm, wakeup, rx_dma, None),
         })
     }
 
@@ -1041,7 +1631,7 @@ impl<'a> Uart<'a, Async> {
         let tx_dma = dma::Dma::reserve_channel(tx_dma);
         let rx_dma: Channel<'_> = dma::Dma::reserve_channel(rx_dma).ok_or(Error::Fail)?;
 
-        let flexcomm = Self::init::<T>(Some(tx.into()), Some(rx.into()), None, None, config)?;
+        let (flexcomm, wakeup) = Self::init::<T>(Some(tx.into()), Some(rx.into()), None, None, config)?;
 
         if !buffer.len().is_multiple_of(2) {
             return Err(Error::InvalidArgument);
@@ -1069,6 +1659,7 @@ impl<'a> Uart<'a, Async> {
             tx: UartTx::new_inner::<T>(flexcomm.clone(), tx_dma),
             rx: UartRx::new_inner::<T>(
                 flexcomm,
+                wakeup,
                 Some(rx_dma),
                 Some(BufferConfig {
                     buffer_a,
@@ -1108,7 +1699,7 @@ impl<'a> Uart<'a, Async> {
         let tx_dma = dma::Dma::reserve_channel(tx_dma);
         let rx_dma = dma::Dma::reserve_channel(rx_dma);
 
-        let flexcomm = Self::init::<T>(
+        let (flexcomm, wakeup) = Self::init::<T>(
             Some(tx.into()),
             Some(rx.into()),
             Some(rts.into()),
@@ -1119,7 +1710,7 @@ impl<'a> Uart<'a, Async> {
         Ok(Self {
             info: T::info(),
             tx: UartTx::new_inner::<T>(flexcomm.clone(), tx_dma),
-            rx: UartRx::new_inner::<T>(flexcomm, rx_dma, None),
+            rx: UartRx::new_inner::<T>(flexcomm, wakeup, rx_dma, None),
         })
     }
 
@@ -1155,7 +1746,7 @@ impl<'a> Uart<'a, Async> {
         let tx_dma = dma::Dma::reserve_channel(tx_dma);
         let rx_dma = dma::Dma::reserve_channel(rx_dma).ok_or(Error::Fail)?;
 
-        let flexcomm = Self::init::<T>(
+        let (flexcomm, wakeup) = Self::init::<T>(
             Some(tx.into()),
             Some(rx.into()),
             Some(rts.into()),
@@ -1189,6 +1780,7 @@ impl<'a> Uart<'a, Async> {
             tx: UartTx::new_inner::<T>(flexcomm.clone(), tx_dma),
             rx: UartRx::new_inner::<T>(
                 flexcomm,
+                wakeup,
                 Some(rx_dma),
                 Some(BufferConfig {
                     buffer_a,
@@ -1206,6 +1798,35 @@ impl<'a> Uart<'a, Async> {
         self.rx.read(buf)
     }
 
+    /// Read from UART RX, giving up after `timeout`, see [`UartRx::read_with_timeout`].
+    #[cfg(feature = "time")]
+    pub fn read_with_timeout<'buf>(
+        &mut self,
+        buf: &'buf mut [u8],
+        timeout: embassy_time::Duration,
+    ) -> impl Future<Output = Result<usize>> + use<'_, 'a, 'buf> {
+        self.rx.read_with_timeout(buf, timeout)
+    }
+
+    /// Transmit the provided buffer, giving up after `timeout`, see [`UartTx::write_with_timeout`].
+    #[cfg(feature = "time")]
+    pub fn write_with_timeout<'buf>(
+        &mut self,
+        buf: &'buf [u8],
+        timeout: embassy_time::Duration,
+    ) -> impl Future<Output = Result<usize>> + use<'_, 'a, 'buf> {
+        self.tx.write_with_timeout(buf, timeout)
+    }
+
+    /// Read from UART RX until the line goes idle, see [`UartRx::read_until_idle`].
+    #[cfg(feature = "time")]
+    pub fn read_until_idle<'buf>(
+        &mut self,
+        buf: &'buf mut [u8],
+    ) -> impl Future<Output = Result<usize>> + use<'_, 'a, 'buf> {
+        self.rx.read_until_idle(buf)
+    }
+
     /// Transmit the provided buffer.
     pub fn write<'buf>(&mut self, buf: &'buf [u8]) -> impl Future<Output = Result<()>> + use<'_, 'a, 'buf> {
         self.tx.write(buf)
@@ -1367,7 +1988,10 @@ impl embedded_hal_nb::serial::Write for Uart<'_, Blocking> {
 
 impl embedded_io::Error for Error {
     fn kind(&self) -> embedded_io::ErrorKind {
-        embedded_io::ErrorKind::Other
+        match *self {
+            Self::Timeout => embedded_io::ErrorKind::TimedOut,
+            _ => embedded_io::ErrorKind::Other,
+        }
     }
 }
 
@@ -1427,9 +2051,17 @@ impl embedded_io_async::ErrorType for Uart<'_, Async> {
     type Error = Error;
 }
 
+/// Completes as soon as some data was received, instead of once `buf` is full like
+/// [`UartRx::read`].
 impl embedded_io_async::Read for UartRx<'_, Async> {
     async fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, Self::Error> {
-        self.read(buf).await
+        // The ping-pong buffer owns the receive FIFO, take whatever it holds
+        #[cfg(feature = "time")]
+        if self._buffer_config.is_some() {
+            return self.read_buffered(buf, 1, None).await;
+        }
+
+        self.read_available(buf).await
     }
 }
 
@@ -1443,6 +2075,18 @@ impl embedded_io_async::Write for UartTx<'_, Async> {
     }
 }
 
+#[cfg(feature = "time")]
+impl embedded_io_async::ErrorType for RingBufferedUartRx<'_> {
+    type Error = Error;
+}
+
+#[cfg(feature = "time")]
+impl embedded_io_async::Read for RingBufferedUartRx<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, Self::Error> {
+        self.read(buf).await
+    }
+}
+
 impl embedded_io_async::Read for Uart<'_, Async> {
     async fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, Self::Error> {
         embedded_io_async::Read::read(&mut self.rx, buf).await
@@ -1463,6 +2107,128 @@ struct Info {
     regs: &'static crate::pac::usart0::RegisterBlock,
     tx_waker: &'static AtomicWaker,
     rx_waker: &'static AtomicWaker,
+    state: &'static State,
+    start_enable: StartEnable,
+}
+
+/// Line settings of an instance, shared by its transmitter and receiver
+struct State {
+    source_clock_hz: AtomicU32,
+    baudrate: AtomicU32,
+}
+
+impl State {
+    const fn new() -> Self {
+        Self {
+            source_clock_hz: AtomicU32::new(0),
+            baudrate: AtomicU32::new(0),
+        }
+    }
+}
+
+impl Info {
+    /// Drop the contents of the RX FIFO and clear pending receive errors.
+    fn flush_rx_fifo(&self) {
+        self.regs.fifocfg().modify(|_, w| w.emptyrx().set_bit());
+        self.regs.fifostat().write(|w| w.rxerr().set_bit());
+        self.regs.stat().write(|w| {
+            w.framerrint()
+                .clear_bit_by_one()
+                .parityerrint()
+                .clear_bit_by_one()
+                .rxnoiseint()
+                .clear_bit_by_one()
+                .start()
+                .clear_bit_by_one()
+        });
+    }
+
+    /// Disable the RX FIFO, the receiver then drops incoming characters.
+    fn pause_rx(&self) {
+        self.regs.fifocfg().modify(|_, w| w.enablerx().disabled());
+    }
+
+    fn resume_rx(&self) {
+        self.regs.fifocfg().modify(|_, w| w.enablerx().enabled());
+    }
+
+    /// The TX FIFO is empty and the last stop bit has left the wire.
+    ///
+    /// TXIDLE alone is briefly set between characters while the FIFO still holds data.
+    fn tx_done(&self) -> bool {
+        self.regs.fifostat().read().txempty().bit_is_set() && self.regs.stat().read().txidle().bit_is_set()
+    }
+
+    /// Wait for a framing, parity, noise or overrun error while DMA receives.
+    fn wait_for_rx_error(&self) -> impl Future<Output = Error> + '_ {
+        poll_fn(|cx| {
+            self.rx_waker.register(cx.waker());
+
+            self.regs
+                .intenset()
+                .write(|w| w.framerren().set_bit().parityerren().set_bit().rxnoiseen().set_bit());
+
+            self.regs.fifointenset().write(|w| w.rxerr().set_bit());
+
+            let stat = self.regs.stat().read();
+            let fifointstat = self.regs.fifointstat().read();
+
+            self.regs.stat().write(|w| {
+                w.framerrint()
+                    .clear_bit_by_one()
+                    .parityerrint()
+                    .clear_bit_by_one()
+                    .rxnoiseint()
+                    .clear_bit_by_one()
+            });
+
+            self.regs.fifostat().write(|w| w.rxerr().set_bit());
+
+            if stat.framerrint().bit_is_set() {
+                Poll::Ready(Error::Framing)
+            } else if stat.parityerrint().bit_is_set() {
+                Poll::Ready(Error::Parity)
+            } else if stat.rxnoiseint().bit_is_set() {
+                Poll::Ready(Error::Noise)
+            } else if fifointstat.rxerr().bit_is_set() {
+                Poll::Ready(Error::Overrun)
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Wait for a start bit on the RX line.
+    #[cfg(feature = "time")]
+    fn wait_for_start(&self) -> impl Future<Output = ()> + '_ {
+        poll_fn(|cx| {
+            self.rx_waker.register(cx.waker());
+
+            self.regs.intenset().write(|w| w.starten().set_bit());
+
+            if self.regs.stat().read().start().bit_is_set() {
+                self.regs.stat().write(|w| w.start().clear_bit_by_one());
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Idle line timeout, in microseconds, for the current baud rate and character format.
+    #[cfg(feature = "time")]
+    fn idle_timeout_us(&self) -> u64 {
+        let cfg = self.regs.cfg().read();
+
+        // start bit, 7 to 9 data bits, parity bit and 1 or 2 stop bits
+        let data_bits = 7 + u64::from(cfg.datalen().bits());
+        let parity_bits = u64::from(cfg.paritysel().bits() != 0);
+        let stop_bits = 1 + u64::from(cfg.stoplen().bit());
+        let character_bits = 1 + data_bits + parity_bits + stop_bits;
+
+        let baudrate = u64::from(self.state.baudrate.load(Ordering::Relaxed).max(1));
+        (IDLE_CHARACTERS * character_bits * 1_000_000).div_ceil(baudrate)
+    }
 }
 
 // SAFETY: safety for Send here is the same as the other accessors to unsafe blocks: it must be done from a single executor context.
@@ -1474,6 +2240,7 @@ trait SealedInstance {
     fn info() -> Info;
     fn tx_waker() -> &'static AtomicWaker;
     fn rx_waker() -> &'static AtomicWaker;
+    const START_ENABLE: StartEnable;
 }
 
 /// UART interrupt handler.
@@ -1533,7 +2300,7 @@ pub trait Instance: crate::flexcomm::IntoUsart + SealedInstance + PeripheralType
 }
 
 macro_rules! impl_instance {
-    ($($n:expr),*) => {
+    ($($n:expr => $start:expr),*) => {
         $(
             paste!{
                 impl SealedInstance for crate::peripherals::[<FLEXCOMM $n>] {
@@ -1542,6 +2309,11 @@ macro_rules! impl_instance {
                             regs: unsafe { &*crate::pac::[<Usart $n>]::ptr() },
                             tx_waker: Self::tx_waker(),
                             rx_waker: Self::rx_waker(),
+                            state: {
+                                static STATE: State = State::new();
+                                &STATE
+                            },
+                            start_enable: Self::START_ENABLE,
                         }
                     }
 
@@ -1554,6 +2326,8 @@ macro_rules! impl_instance {
                         static RX_WAKER: AtomicWaker = AtomicWaker::new();
                         &RX_WAKER
                     }
+
+                    const START_ENABLE: StartEnable = $start;
                 }
 
                 impl Instance for crate::peripherals::[<FLEXCOMM $n>] {
@@ -1564,7 +2338,16 @@ macro_rules! impl_instance {
     };
 }
 
-impl_instance!(0, 1, 2, 3, 4, 5, 6, 7);
+impl_instance!(
+    0 => StartEnable::Starten0(14),
+    1 => StartEnable::Starten0(15),
+    2 => StartEnable::Starten0(16),
+    3 => StartEnable::Starten0(17),
+    4 => StartEnable::Starten0(18),
+    5 => StartEnable::Starten0(19),
+    6 => StartEnable::Starten1(11),
+    7 => StartEnable::Starten1(12)
+);
 
 mod sealed {
     /// simply seal a trait
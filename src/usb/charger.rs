@@ -0,0 +1,145 @@
+//! USB battery charger detection
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_hal_internal::Peri;
+use embassy_sync::waitqueue::AtomicWaker;
+
+use super::Driver;
+use crate::{interrupt, peripherals};
+
+static WAKER: AtomicWaker = AtomicWaker::new();
+
+/// Clock of the detector in MHz, the 24 MHz crystal of the PHY
+const CLOCK_MHZ: u16 = 24;
+
+/// STATUS: SEQ_RES, the kind of port found
+const SEQ_RES_SDP: u8 = 0b01;
+const SEQ_RES_CP: u8 = 0b10;
+const SEQ_RES_DCP: u8 = 0b11;
+
+/// STATUS: SEQ_STAT, once the charger type is known
+const SEQ_STAT_CT_DET_DONE: u8 = 0b11;
+
+/// Kind of port the cable is plugged into, per the USB Battery Charging 1.2 specification
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PortType {
+    /// Standard downstream port, 100 mA until configured, 500 mA after
+    Sdp,
+    /// Charging downstream port, up to 1.5 A while enumerating as usual
+    Cdp,
+    /// Dedicated charging port, up to 1.5 A without any data on the bus
+    Dcp,
+}
+
+/// Charger detection error
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChargerError {
+    /// the data pins never made contact, the cable is likely unplugged
+    Timeout,
+    /// the port answered in a way the detector does not know
+    Sequence,
+}
+
+/// Charger detection interrupt handler.
+pub struct ChargerInterruptHandler {
+    _private: (),
+}
+
+impl interrupt::typelevel::Handler<interrupt::typelevel::USBPHY_DCD> for ChargerInterruptHandler {
+    unsafe fn on_interrupt() {
+        // SAFETY: the detector is only touched by this handler and ChargerDetector
+        let dcd = unsafe { crate::pac::Usbhsdcd::steal() };
+
+        if dcd.control().read().if_().bit_is_set() {
+            // the sequence is over, the pending future reads the result
+            dcd.control().modify(|_, w| w.iack().int_clear().ie().dis_int());
+            WAKER.wake();
+        }
+    }
+}
+
+/// Battery charger detector
+///
+/// Run the detection with VBUS present and before the bus is enabled: the detector drives the
+/// data pins, and the host would see a device that never answers.
+pub struct ChargerDetector<'d> {
+    regs: &'static crate::pac::usbhsdcd::RegisterBlock,
+    _phantom: PhantomData<&'d ()>,
+}
+
+impl<'d> ChargerDetector<'d> {
+    /// Create the charger detector, it runs on the PHY the USB driver powers up
+    pub fn new(
+        _dcd: Peri<'d, peripherals::USBHSDCD>,
+        _irq: impl interrupt::typelevel::Binding<interrupt::typelevel::USBPHY_DCD, ChargerInterruptHandler> + 'd,
+        _driver: &Driver<'d>,
+    ) -> Self {
+        use crate::interrupt::typelevel::Interrupt;
+
+        interrupt::typelevel::USBPHY_DCD::unpend();
+        // SAFETY: the handler is bound above
+        unsafe { interrupt::typelevel::USBPHY_DCD::enable() };
+
+        Self {
+            // SAFETY: the peripheral is owned for 'd
+            regs: unsafe { &*crate::pac::Usbhsdcd::ptr() },
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Detect the kind of port, so the charge current can be set before enumerating
+    ///
+    /// Completes within about a second, after which the detector gives up on data pin contact.
+    pub async fn detect(&mut self) -> Result<PortType, ChargerError> {
+        let regs = self.regs;
+
+        regs.control().write(|w| w.sr().sw_reset());
+        // SAFETY: unsafe due to .bits usage
+        regs.clock()
+            .write(|w| unsafe { w.clock_unit().mhz_clk().clock_speed().bits(CLOCK_MHZ) });
+
+        regs.control()
+            .write(|w| w.bc12().bc12().iack().int_clear().ie().en_int().start().start());
+
+        poll_fn(|cx| {
+            WAKER.register(cx.waker());
+
+            if regs.control().read().ie().bit_is_set() {
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+        .await;
+
+        let status = regs.status().read();
+
+        // leave the data pins to the PHY
+        regs.control().write(|w| w.sr().sw_reset());
+
+        if status.to().bit_is_set() {
+            return Err(ChargerError::Timeout);
+        }
+        if status.err().bit_is_set() {
+            return Err(ChargerError::Sequence);
+        }
+
+        match (status.seq_res().bits(), status.seq_stat().bits()) {
+            (SEQ_RES_SDP, _) => Ok(PortType::Sdp),
+            (SEQ_RES_CP, SEQ_STAT_CT_DET_DONE) => Ok(PortType::Cdp),
+            (SEQ_RES_DCP, _) => Ok(PortType::Dcp),
+            _ => Err(ChargerError::Sequence),
+        }
+    }
+}
+
+impl Drop for ChargerDetector<'_> {
+    fn drop(&mut self) {
+        self.regs.control().write(|w| w.sr().sw_reset());
+    }
+}
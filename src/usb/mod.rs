@@ -0,0 +1,1022 @@
+//! Universal Serial Bus (USB) device
+//!
+//! Drives the high-speed device controller through [`embassy_usb_driver`], so the classes of
+//! `embassy-usb` run on top of it. Endpoints 1 to 5 can be of any type in each direction:
+//! their packets go through two buffers in the USB RAM, the controller filling or sending one
+//! while the driver copies the other, which keeps isochronous streams going while the
+//! application is busy.
+//!
+//! The polling interval of interrupt and isochronous endpoints is only given to the host in the
+//! endpoint descriptors, the controller serves the endpoints whenever the host asks.
+//!
+//! Bus-powered devices leave [`Config::vbus_detection`] off, they only run with VBUS present.
+//! Self-powered devices turn it on, so the bus reports the cable being plugged and unplugged and
+//! the pull-up on D+ only connects while the host powers the bus. A [`Connection`] handle from
+//! [`Driver::connection`] disconnects and reconnects the device at any time, which makes the host
+//! enumerate it again after a firmware update.
+//!
+//! [`ChargerDetector`] tells a charging port from a standard one before enumerating, so the
+//! device knows how much current it may draw.
+
+mod charger;
+mod phy;
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicU16, Ordering, compiler_fence};
+use core::task::Poll;
+
+use embassy_hal_internal::Peri;
+use embassy_sync::waitqueue::AtomicWaker;
+use embassy_usb_driver::{
+    Direction, EndpointAddress, EndpointAllocError, EndpointError, EndpointInfo, EndpointType, Event, Unsupported,
+};
+
+use crate::clocks::{SysconPeripheral, enable_and_reset};
+use crate::interrupt::typelevel::Interrupt;
+use crate::{PeripheralType, interrupt, peripherals};
+
+pub use charger::{ChargerDetector, ChargerError, ChargerInterruptHandler, PortType};
+pub use phy::{DisconnectThreshold, EnvelopeThreshold, PhyConfig, Trim};
+
+/// Endpoints of the controller, including the control endpoint 0
+const ENDPOINT_COUNT: usize = 6;
+
+/// Base of the USB RAM, holding the endpoint list and the packet buffers
+const USB_RAM_BASE: usize = 0x4014_0000;
+
+/// Size of a USB RAM block, the unit of buffer offsets
+const BLOCK_SIZE: usize = 64;
+
+/// Blocks of the USB RAM, 16 KiB
+const USB_RAM_BLOCKS: u16 = 256;
+
+/// Blocks of the endpoint list: 4 entries of 32 bits for each endpoint
+const EP_LIST_BLOCKS: u16 = 4;
+
+/// Buffer of the SETUP packets
+const SETUP_BLOCK: u16 = EP_LIST_BLOCKS;
+
+/// Buffer of the control OUT data
+const EP0_OUT_BLOCK: u16 = SETUP_BLOCK + 1;
+
+/// Buffer of the control IN data
+const EP0_IN_BLOCK: u16 = EP0_OUT_BLOCK + 1;
+
+/// First block for the other endpoint buffers
+const FIRST_FREE_BLOCK: u16 = EP0_IN_BLOCK + 1;
+
+/// Maximum packet size of the control endpoint
+const EP0_MAX_PACKET_SIZE: u16 = 64;
+
+/// Maximum packet size of the other endpoints, for high-speed isochronous endpoints
+const MAX_PACKET_SIZE: u16 = 1024;
+
+/// Endpoint list entry: the controller is using the buffer
+const EP_ACTIVE: u32 = 1 << 31;
+/// Endpoint list entry: the endpoint is disabled
+const EP_DISABLED: u32 = 1 << 30;
+/// Endpoint list entry: the endpoint answers with a STALL
+const EP_STALL: u32 = 1 << 29;
+/// Endpoint list entry: restart the data toggle at DATA0
+const EP_TOGGLE_RESET: u32 = 1 << 28;
+/// Endpoint list entry: isochronous endpoint, without handshake nor data toggle
+const EP_ISOCHRONOUS: u32 = 1 << 26;
+/// Endpoint list entry: bytes to send, or still to receive
+const EP_NBYTES_SHIFT: u32 = 11;
+const EP_NBYTES_MASK: u32 = 0x7FFF;
+
+/// DEVCMDSTAT flags cleared by writing 1, kept out of read-modify-write sequences
+const DEVCMDSTAT_W1C: u32 = DEVCMDSTAT_SETUP | DEVCMDSTAT_DCON_C | DEVCMDSTAT_DSUS_C | DEVCMDSTAT_DRES_C;
+const DEVCMDSTAT_DEV_ADDR: u32 = 0x7F;
+const DEVCMDSTAT_DEV_EN: u32 = 1 << 7;
+const DEVCMDSTAT_SETUP: u32 = 1 << 8;
+const DEVCMDSTAT_FORCE_VBUS: u32 = 1 << 10;
+const DEVCMDSTAT_DCON: u32 = 1 << 16;
+const DEVCMDSTAT_DSUS: u32 = 1 << 17;
+const DEVCMDSTAT_DCON_C: u32 = 1 << 24;
+const DEVCMDSTAT_DSUS_C: u32 = 1 << 25;
+const DEVCMDSTAT_DRES_C: u32 = 1 << 26;
+
+/// INTSTAT: device status change
+const INTSTAT_DEV_INT: u32 = 1 << 31;
+
+/// Physical endpoint of `index` in direction `dir`, also its bit in the endpoint registers
+fn physical(index: usize, dir: Direction) -> usize {
+    2 * index + usize::from(dir == Direction::In)
+}
+
+/// Endpoint list entry of `buffer` of the physical endpoint
+///
+/// The SETUP buffer is the second entry of the control OUT endpoint.
+fn entry(physical: usize, buffer: usize) -> *mut u32 {
+    (USB_RAM_BASE as *mut u32).wrapping_add(2 * physical + buffer)
+}
+
+fn read_entry(physical: usize, buffer: usize) -> u32 {
+    // SAFETY: the entry lies in the endpoint list of the USB RAM
+    unsafe { ptr::read_volatile(entry(physical, buffer)) }
+}
+
+fn write_entry(physical: usize, buffer: usize, value: u32) {
+    compiler_fence(Ordering::SeqCst);
+    // SAFETY: the entry lies in the endpoint list of the USB RAM
+    unsafe { ptr::write_volatile(entry(physical, buffer), value) }
+}
+
+fn block_ptr(block: u16) -> *mut u8 {
+    (USB_RAM_BASE + usize::from(block) * BLOCK_SIZE) as *mut u8
+}
+
+/// Entry handing `block` to the controller for `len` bytes
+fn prime(block: u16, len: usize, flags: u32) -> u32 {
+    EP_ACTIVE | flags | ((len as u32 & EP_NBYTES_MASK) << EP_NBYTES_SHIFT) | u32::from(block)
+}
+
+/// Bytes the controller moved out of `expected`, from a completed entry
+fn transferred(entry: u32, expected: usize) -> usize {
+    expected.saturating_sub(((entry >> EP_NBYTES_SHIFT) & EP_NBYTES_MASK) as usize)
+}
+
+struct State {
+    bus_waker: AtomicWaker,
+    /// Woken on device status changes, for [`Connection`]
+    vbus_waker: AtomicWaker,
+    ep_wakers: [AtomicWaker; 2 * ENDPOINT_COUNT],
+    /// Enabled endpoints, one bit per physical endpoint
+    enabled: AtomicU16,
+    /// Endpoints enabled or unstalled since their last transfer, which restart from their first
+    /// buffer and DATA0
+    fresh: AtomicU16,
+    /// The application lets the pull-up connect, see [`Connection`]
+    soft_connected: AtomicBool,
+}
+
+impl State {
+    const fn new() -> Self {
+        Self {
+            bus_waker: AtomicWaker::new(),
+            vbus_waker: AtomicWaker::new(),
+            ep_wakers: [const { AtomicWaker::new() }; 2 * ENDPOINT_COUNT],
+            enabled: AtomicU16::new(0),
+            fresh: AtomicU16::new(0),
+            soft_connected: AtomicBool::new(true),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Info {
+    regs: &'static crate::pac::usbhsd::RegisterBlock,
+    phy: &'static crate::pac::usbphy::RegisterBlock,
+    state: &'static State,
+}
+
+// SAFETY: safety for Send here is the same as the other accessors to unsafe blocks: it must be done from a single executor context.
+//         This is a temporary workaround -- a better solution might be to refactor Info to no longer maintain a reference to regs,
+//         but instead look up the correct register set and then perform operations within an unsafe block as we do for other peripherals
+unsafe impl Send for Info {}
+
+impl Info {
+    fn ep_waker(&self, physical: usize) -> Option<&AtomicWaker> {
+        self.state.ep_wakers.get(physical)
+    }
+
+    fn wake_endpoint(&self, physical: usize) {
+        if let Some(waker) = self.ep_waker(physical) {
+            waker.wake();
+        }
+    }
+
+    fn register_endpoint(&self, physical: usize, cx: &core::task::Context<'_>) {
+        if let Some(waker) = self.ep_waker(physical) {
+            waker.register(cx.waker());
+        }
+    }
+
+    fn is_enabled(&self, physical: usize) -> bool {
+        self.state.enabled.load(Ordering::Acquire) & (1 << physical) != 0
+    }
+
+    fn take_fresh(&self, physical: usize) -> bool {
+        self.state.fresh.fetch_and(!(1 << physical), Ordering::AcqRel) & (1 << physical) != 0
+    }
+
+    fn vbus_present(&self) -> bool {
+        self.regs.devcmdstat().read().vbus_debounced().bit_is_set()
+    }
+
+    /// Set `set` in DEVCMDSTAT, clear `clear`, and clear the change flags in `ack`
+    fn modify_devcmdstat(&self, set: u32, clear: u32, ack: u32) {
+        // SAFETY: unsafe due to .bits usage, the change flags are only written on purpose
+        self.regs
+            .devcmdstat()
+            .modify(|r, w| unsafe { w.bits((r.bits() & !DEVCMDSTAT_W1C & !clear) | set | (ack & DEVCMDSTAT_W1C)) });
+    }
+
+    /// Take back both buffers of a physical endpoint from the controller
+    fn deactivate(&self, physical: usize) {
+        for _ in 0..2 {
+            if read_entry(physical, 0) & EP_ACTIVE == 0 && read_entry(physical, 1) & EP_ACTIVE == 0 {
+                break;
+            }
+
+            // SAFETY: unsafe due to .bits usage
+            self.regs.epskip().write(|w| unsafe { w.skip().bits(1 << physical) });
+            while self.regs.epskip().read().skip().bits() & (1 << physical) != 0 {}
+        }
+    }
+
+    /// Start a physical endpoint over from its first buffer and DATA0, once enabled or unstalled:
+    /// its buffers stay idle until the next transfer primes them
+    fn restart_endpoint(&self, physical: usize) {
+        write_entry(physical, 0, EP_TOGGLE_RESET);
+        write_entry(physical, 1, 0);
+        // SAFETY: unsafe due to .bits usage, the controller starts again from the first buffer
+        self.regs
+            .epinuse()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << physical)) });
+        self.state.fresh.fetch_or(1 << physical, Ordering::AcqRel);
+    }
+
+    /// Disable every endpoint but the control one, after a bus reset
+    fn reset_endpoints(&self) {
+        for physical in 2..2 * ENDPOINT_COUNT {
+            self.deactivate(physical);
+        }
+
+        self.init_endpoint_list();
+
+        for physical in 2..2 * ENDPOINT_COUNT {
+            self.wake_endpoint(physical);
+        }
+    }
+
+    /// Fill the endpoint list with only the control endpoint enabled
+    fn init_endpoint_list(&self) {
+        self.state.enabled.store(0b11, Ordering::Release);
+        for physical in 2..2 * ENDPOINT_COUNT {
+            write_entry(physical, 0, EP_DISABLED);
+            write_entry(physical, 1, EP_DISABLED);
+        }
+
+        write_entry(0, 0, 0);
+        write_entry(0, 1, u32::from(SETUP_BLOCK));
+        write_entry(1, 0, 0);
+        write_entry(1, 1, 0);
+    }
+}
+
+trait SealedInstance {
+    fn info() -> Info;
+}
+
+/// USB instance trait.
+#[allow(private_bounds)]
+pub trait Instance: SealedInstance + PeripheralType + SysconPeripheral + 'static + Send {
+    /// Interrupt for this USB instance.
+    type Interrupt: interrupt::typelevel::Interrupt;
+}
+
+impl SealedInstance for peripherals::USBHSD {
+    fn info() -> Info {
+        static STATE: State = State::new();
+
+        Info {
+            regs: unsafe { &*crate::pac::Usbhsd::ptr() },
+            phy: unsafe { &*crate::pac::Usbphy::ptr() },
+            state: &STATE,
+        }
+    }
+}
+
+impl Instance for peripherals::USBHSD {
+    type Interrupt = crate::interrupt::typelevel::USB;
+}
+
+/// USB interrupt handler.
+pub struct InterruptHandler<T: Instance> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandler<T> {
+    unsafe fn on_interrupt() {
+        let info = T::info();
+        let regs = info.regs;
+
+        let pending = regs.intstat().read().bits();
+        // SAFETY: INTSTAT flags are cleared by writing 1
+        regs.intstat().write(|w| unsafe { w.bits(pending) });
+
+        if pending & INTSTAT_DEV_INT != 0 {
+            info.state.bus_waker.wake();
+            info.state.vbus_waker.wake();
+        }
+
+        // SETUP packets interrupt the OUT direction, and abort the IN data stage too
+        if pending & 0b11 != 0 {
+            info.wake_endpoint(0);
+            info.wake_endpoint(1);
+        }
+
+        for physical in 2..2 * ENDPOINT_COUNT {
+            if pending & (1 << physical) != 0 {
+                info.wake_endpoint(physical);
+            }
+        }
+    }
+}
+
+/// Configuration for the USB device
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Config {
+    /// Follow VBUS to detect the host, instead of assuming it is always present
+    ///
+    /// Needed by self-powered devices, which keep running with the cable unplugged.
+    pub vbus_detection: bool,
+    /// Analog tuning of the PHY, for boards that miss the eye diagram with the factory trim
+    pub phy: PhyConfig,
+}
+
+/// USB device driver
+pub struct Driver<'d> {
+    info: Info,
+    config: Config,
+    /// Allocated endpoints, one bit per physical endpoint
+    allocated: u16,
+    /// Next free block of the USB RAM
+    next_block: u16,
+    _phantom: PhantomData<&'d ()>,
+}
+
+impl<'d> Driver<'d> {
+    /// Create the USB device driver, the crystal must run at 24 MHz
+    pub fn new<T: Instance>(
+        _usb: Peri<'d, T>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        config: Config,
+    ) -> Self {
+        let info = T::info();
+
+        init::<T>(&info, &config.phy);
+
+        T::Interrupt::unpend();
+        // SAFETY: the handler is bound above
+        unsafe { T::Interrupt::enable() };
+
+        Self {
+            info,
+            config,
+            allocated: 0b11,
+            next_block: FIRST_FREE_BLOCK,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Handle to disconnect the device from the host and connect it again
+    pub fn connection(&self) -> Connection<'d> {
+        Connection {
+            info: self.info,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn alloc_endpoint<D: Dir>(
+        &mut self,
+        ep_type: EndpointType,
+        ep_addr: Option<EndpointAddress>,
+        max_packet_size: u16,
+        interval_ms: u8,
+    ) -> Result<Endpoint<'d, D>, EndpointAllocError> {
+        if ep_type == EndpointType::Control || max_packet_size == 0 || max_packet_size > MAX_PACKET_SIZE {
+            return Err(EndpointAllocError);
+        }
+
+        let is_free = |index: usize| self.allocated & (1 << physical(index, D::DIRECTION)) == 0;
+        let index = match ep_addr {
+            Some(addr) if addr.index() != 0 && addr.index() < ENDPOINT_COUNT && is_free(addr.index()) => addr.index(),
+            Some(_) => return Err(EndpointAllocError),
+            None => (1..ENDPOINT_COUNT)
+                .find(|&index| is_free(index))
+                .ok_or(EndpointAllocError)?,
+        };
+
+        let buffer_blocks = max_packet_size.div_ceil(BLOCK_SIZE as u16);
+        let block = self.next_block;
+        if block + 2 * buffer_blocks > USB_RAM_BLOCKS {
+            return Err(EndpointAllocError);
+        }
+
+        self.next_block += 2 * buffer_blocks;
+        self.allocated |= 1 << physical(index, D::DIRECTION);
+
+        Ok(Endpoint {
+            info: self.info,
+            ep_info: EndpointInfo {
+                addr: EndpointAddress::from_parts(index, D::DIRECTION),
+                ep_type,
+                max_packet_size,
+                interval_ms,
+            },
+            blocks: [block, block + buffer_blocks],
+            next: 0,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<'d> embassy_usb_driver::Driver<'d> for Driver<'d> {
+    type EndpointOut = Endpoint<'d, Out>;
+    type EndpointIn = Endpoint<'d, In>;
+    type ControlPipe = ControlPipe<'d>;
+    type Bus = Bus<'d>;
+
+    fn alloc_endpoint_out(
+        &mut self,
+        ep_type: EndpointType,
+        ep_addr: Option<EndpointAddress>,
+        max_packet_size: u16,
+        interval_ms: u8,
+    ) -> Result<Self::EndpointOut, EndpointAllocError> {
+        self.alloc_endpoint(ep_type, ep_addr, max_packet_size, interval_ms)
+    }
+
+    fn alloc_endpoint_in(
+        &mut self,
+        ep_type: EndpointType,
+        ep_addr: Option<EndpointAddress>,
+        max_packet_size: u16,
+        interval_ms: u8,
+    ) -> Result<Self::EndpointIn, EndpointAllocError> {
+        self.alloc_endpoint(ep_type, ep_addr, max_packet_size, interval_ms)
+    }
+
+    fn start(self, control_max_packet_size: u16) -> (Self::Bus, Self::ControlPipe) {
+        let regs = self.info.regs;
+
+        self.info.init_endpoint_list();
+
+        // SAFETY: unsafe due to .bits usage, the USB RAM is 256-byte aligned
+        regs.epliststart().write(|w| unsafe { w.bits(USB_RAM_BASE as u32) });
+        regs.databufstart().write(|w| unsafe { w.bits(USB_RAM_BASE as u32) });
+
+        // every other endpoint alternates between its two buffers
+        // SAFETY: unsafe due to .bits usage
+        regs.epbufcfg()
+            .write(|w| unsafe { w.buf_sb().bits((1 << (2 * ENDPOINT_COUNT - 2)) - 1) });
+
+        // SAFETY: unsafe due to .bits usage
+        regs.inten().write(|w| {
+            unsafe { w.ep_int_en().bits((1 << (2 * ENDPOINT_COUNT)) - 1) }
+                .dev_int_en()
+                .set_bit()
+        });
+
+        (
+            Bus {
+                info: self.info,
+                vbus_detection: self.config.vbus_detection,
+                powered: false,
+                _phantom: PhantomData,
+            },
+            ControlPipe {
+                info: self.info,
+                max_packet_size: control_max_packet_size.min(EP0_MAX_PACKET_SIZE),
+                _phantom: PhantomData,
+            },
+        )
+    }
+}
+
+/// USB bus
+pub struct Bus<'d> {
+    info: Info,
+    vbus_detection: bool,
+    powered: bool,
+    _phantom: PhantomData<&'d ()>,
+}
+
+impl embassy_usb_driver::Bus for Bus<'_> {
+    async fn enable(&mut self) {
+        // without VBUS sensing the controller behaves as if VBUS was always present
+        let mut set = DEVCMDSTAT_DEV_EN;
+        if !self.vbus_detection {
+            set |= DEVCMDSTAT_FORCE_VBUS;
+        }
+        if self.info.state.soft_connected.load(Ordering::Acquire) {
+            set |= DEVCMDSTAT_DCON;
+        }
+
+        self.info.modify_devcmdstat(set, 0, 0);
+    }
+
+    async fn disable(&mut self) {
+        self.info.modify_devcmdstat(0, DEVCMDSTAT_DEV_EN | DEVCMDSTAT_DCON, 0);
+    }
+
+    async fn poll(&mut self) -> Event {
+        poll_fn(|cx| {
+            self.info.state.bus_waker.register(cx.waker());
+
+            let status = self.info.regs.devcmdstat().read();
+
+            // the controller drops the pull-up by itself when VBUS goes away
+            if status.dcon_c().bit_is_set() {
+                self.info.modify_devcmdstat(0, 0, DEVCMDSTAT_DCON_C);
+            }
+
+            let vbus = !self.vbus_detection || status.vbus_debounced().bit_is_set();
+            if vbus != self.powered {
+                self.powered = vbus;
+                if vbus {
+                    return Poll::Ready(Event::PowerDetected);
+                }
+
+                self.info.reset_endpoints();
+                return Poll::Ready(Event::PowerRemoved);
+            }
+
+            if !self.powered {
+                return Poll::Pending;
+            }
+
+            if status.dres_c().bit_is_set() {
+                // the address goes back to 0
+                self.info.modify_devcmdstat(0, DEVCMDSTAT_DEV_ADDR, DEVCMDSTAT_DRES_C);
+                self.info.reset_endpoints();
+                return Poll::Ready(Event::Reset);
+            }
+
+            if status.dsus_c().bit_is_set() {
+                self.info.modify_devcmdstat(0, 0, DEVCMDSTAT_DSUS_C);
+                return Poll::Ready(if status.dsus().bit_is_set() {
+                    Event::Suspend
+                } else {
+                    Event::Resume
+                });
+            }
+
+            Poll::Pending
+        })
+        .await
+    }
+
+    fn endpoint_set_enabled(&mut self, ep_addr: EndpointAddress, enabled: bool) {
+        let index = ep_addr.index();
+        if index == 0 || index >= ENDPOINT_COUNT {
+            return;
+        }
+
+        let physical = physical(index, ep_addr.direction());
+        let state = self.info.state;
+
+        self.info.deactivate(physical);
+
+        if enabled {
+            self.info.restart_endpoint(physical);
+            state.enabled.fetch_or(1 << physical, Ordering::AcqRel);
+        } else {
+            state.enabled.fetch_and(!(1 << physical), Ordering::AcqRel);
+            write_entry(physical, 0, EP_DISABLED);
+            write_entry(physical, 1, EP_DISABLED);
+        }
+
+        self.info.wake_endpoint(physical);
+    }
+
+    fn endpoint_set_stalled(&mut self, ep_addr: EndpointAddress, stalled: bool) {
+        let index = ep_addr.index();
+        if index >= ENDPOINT_COUNT {
+            return;
+        }
+
+        if index == 0 {
+            // both directions of the control endpoint stall together, until the next SETUP
+            let stall = if stalled { EP_STALL } else { 0 };
+            write_entry(0, 0, stall);
+            write_entry(1, 0, stall);
+            return;
+        }
+
+        let physical = physical(index, ep_addr.direction());
+        self.info.deactivate(physical);
+
+        if stalled {
+            write_entry(physical, 0, EP_STALL);
+            write_entry(physical, 1, EP_STALL);
+        } else {
+            self.info.restart_endpoint(physical);
+        }
+
+        self.info.wake_endpoint(physical);
+    }
+
+    fn endpoint_is_stalled(&mut self, ep_addr: EndpointAddress) -> bool {
+        let index = ep_addr.index();
+        if index >= ENDPOINT_COUNT {
+            return false;
+        }
+
+        read_entry(physical(index, ep_addr.direction()), 0) & EP_STALL != 0
+    }
+
+    async fn remote_wakeup(&mut self) -> Result<(), Unsupported> {
+        // clearing DSUS while suspended signals resume to the host
+        self.info.modify_devcmdstat(0, DEVCMDSTAT_DSUS, 0);
+        Ok(())
+    }
+}
+
+/// Soft connection of the device, and VBUS state
+///
+/// The device stays disconnected until [`Connection::connect`] once [`Connection::disconnect`] is
+/// called, even across VBUS changes.
+#[derive(Clone, Copy)]
+pub struct Connection<'d> {
+    info: Info,
+    _phantom: PhantomData<&'d ()>,
+}
+
+impl Connection<'_> {
+    /// Connect the pull-up on D+, the host then enumerates the device
+    ///
+    /// The pull-up waits for the bus to be enabled, and for VBUS with [`Config::vbus_detection`].
+    pub fn connect(&self) {
+        self.info.state.soft_connected.store(true, Ordering::Release);
+
+        if self.info.regs.devcmdstat().read().dev_en().bit_is_set() {
+            self.info.modify_devcmdstat(DEVCMDSTAT_DCON, 0, 0);
+        }
+    }
+
+    /// Disconnect the pull-up on D+, the host sees the device unplugged
+    pub fn disconnect(&self) {
+        self.info.state.soft_connected.store(false, Ordering::Release);
+        self.info.modify_devcmdstat(0, DEVCMDSTAT_DCON, 0);
+    }
+
+    /// Whether the pull-up on D+ is connected
+    pub fn is_connected(&self) -> bool {
+        self.info.regs.devcmdstat().read().dcon().bit_is_set()
+    }
+
+    /// Whether the PHY sees VBUS, debounced
+    pub fn is_vbus_present(&self) -> bool {
+        self.info.vbus_present()
+    }
+
+    /// Wait until VBUS is `present`, or not
+    pub async fn wait_for_vbus(&self, present: bool) {
+        poll_fn(|cx| {
+            self.info.state.vbus_waker.register(cx.waker());
+
+            if self.info.vbus_present() == present {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+trait SealedDir {
+    const DIRECTION: Direction;
+}
+
+/// Endpoint direction
+#[allow(private_bounds)]
+pub trait Dir: SealedDir {}
+
+/// Marker of IN endpoints, from the device to the host
+pub enum In {}
+impl SealedDir for In {
+    const DIRECTION: Direction = Direction::In;
+}
+impl Dir for In {}
+
+/// Marker of OUT endpoints, from the host to the device
+pub enum Out {}
+impl SealedDir for Out {
+    const DIRECTION: Direction = Direction::Out;
+}
+impl Dir for Out {}
+
+/// USB endpoint, other than the control endpoint
+pub struct Endpoint<'d, D> {
+    info: Info,
+    ep_info: EndpointInfo,
+    /// First block of each buffer
+    blocks: [u16; 2],
+    /// Buffer the controller uses next
+    next: usize,
+    _phantom: PhantomData<(&'d (), D)>,
+}
+
+impl<D: Dir> Endpoint<'_, D> {
+    fn physical(&self) -> usize {
+        physical(self.ep_info.addr.index(), D::DIRECTION)
+    }
+
+    fn block(&self, buffer: usize) -> u16 {
+        let [first, second] = self.blocks;
+        if buffer == 0 { first } else { second }
+    }
+
+    fn type_flags(&self) -> u32 {
+        if self.ep_info.ep_type == EndpointType::Isochronous {
+            EP_ISOCHRONOUS
+        } else {
+            0
+        }
+    }
+}
+
+impl<D: Dir> embassy_usb_driver::Endpoint for Endpoint<'_, D> {
+    fn info(&self) -> &EndpointInfo {
+        &self.ep_info
+    }
+
+    async fn wait_enabled(&mut self) {
+        let physical = self.physical();
+
+        poll_fn(|cx| {
+            self.info.register_endpoint(physical, cx);
+
+            if self.info.is_enabled(physical) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl Endpoint<'_, Out> {
+    /// Hand both buffers to the controller, the first one with a data toggle reset
+    fn prime_both(&mut self) {
+        let physical = self.physical();
+        let len = usize::from(self.ep_info.max_packet_size);
+        let flags = self.type_flags();
+
+        self.next = 0;
+        write_entry(physical, 1, prime(self.block(1), len, flags));
+        write_entry(physical, 0, prime(self.block(0), len, flags | EP_TOGGLE_RESET));
+    }
+}
+
+impl embassy_usb_driver::EndpointOut for Endpoint<'_, Out> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, EndpointError> {
+        let physical = self.physical();
+        let max_packet_size = usize::from(self.ep_info.max_packet_size);
+
+        let entry = poll_fn(|cx| {
+            self.info.register_endpoint(physical, cx);
+
+            if !self.info.is_enabled(physical) {
+                return Poll::Ready(Err(EndpointError::Disabled));
+            }
+
+            if read_entry(physical, 0) & EP_STALL != 0 {
+                return Poll::Pending;
+            }
+
+            if self.info.take_fresh(physical) {
+                self.prime_both();
+                return Poll::Pending;
+            }
+
+            let entry = read_entry(physical, self.next);
+            if entry & EP_ACTIVE != 0 {
+                Poll::Pending
+            } else {
+                Poll::Ready(Ok(entry))
+            }
+        })
+        .await?;
+
+        let buffer = self.next;
+        let block = self.block(buffer);
+        let len = transferred(entry, max_packet_size);
+
+        let result = match buf.get_mut(..len) {
+            Some(dst) => {
+                // SAFETY: the controller is done with the buffer, which holds a whole packet
+                unsafe { ptr::copy_nonoverlapping(block_ptr(block), dst.as_mut_ptr(), len) };
+                Ok(len)
+            }
+            None => Err(EndpointError::BufferOverflow),
+        };
+
+        // hand the buffer back while the controller fills the other one
+        write_entry(physical, buffer, prime(block, max_packet_size, self.type_flags()));
+        self.next ^= 1;
+
+        result
+    }
+}
+
+impl embassy_usb_driver::EndpointIn for Endpoint<'_, In> {
+    async fn write(&mut self, buf: &[u8]) -> Result<(), EndpointError> {
+        if buf.len() > usize::from(self.ep_info.max_packet_size) {
+            return Err(EndpointError::BufferOverflow);
+        }
+
+        let physical = self.physical();
+        let mut toggle_reset = 0;
+
+        poll_fn(|cx| {
+            self.info.register_endpoint(physical, cx);
+
+            if !self.info.is_enabled(physical) {
+                return Poll::Ready(Err(EndpointError::Disabled));
+            }
+
+            if read_entry(physical, 0) & EP_STALL != 0 {
+                return Poll::Pending;
+            }
+
+            if self.info.take_fresh(physical) {
+                self.next = 0;
+                toggle_reset = EP_TOGGLE_RESET;
+            }
+
+            if read_entry(physical, self.next) & EP_ACTIVE != 0 {
+                Poll::Pending
+            } else {
+                Poll::Ready(Ok(()))
+            }
+        })
+        .await?;
+
+        let buffer = self.next;
+        let block = self.block(buffer);
+
+        // SAFETY: the controller is done with the buffer, which holds a whole packet
+        unsafe { ptr::copy_nonoverlapping(buf.as_ptr(), block_ptr(block), buf.len()) };
+
+        // the packet goes out while the next one is copied into the other buffer
+        write_entry(
+            physical,
+            buffer,
+            prime(block, buf.len(), self.type_flags() | toggle_reset),
+        );
+        self.next ^= 1;
+
+        Ok(())
+    }
+}
+
+/// USB control pipe, on endpoint 0
+pub struct ControlPipe<'d> {
+    info: Info,
+    max_packet_size: u16,
+    _phantom: PhantomData<&'d ()>,
+}
+
+impl ControlPipe<'_> {
+    fn setup_pending(&self) -> bool {
+        self.info.regs.devcmdstat().read().setup().bit_is_set()
+    }
+
+    /// Wait for the controller to be done with the control buffer of `physical`
+    async fn wait_done(&self, physical: usize) -> Result<u32, EndpointError> {
+        poll_fn(|cx| {
+            self.info.register_endpoint(physical, cx);
+
+            // a new SETUP packet aborts the current transfer
+            if self.setup_pending() {
+                return Poll::Ready(Err(EndpointError::Disabled));
+            }
+
+            let entry = read_entry(physical, 0);
+            if entry & EP_ACTIVE != 0 {
+                Poll::Pending
+            } else {
+                Poll::Ready(Ok(entry))
+            }
+        })
+        .await
+    }
+}
+
+impl embassy_usb_driver::ControlPipe for ControlPipe<'_> {
+    fn max_packet_size(&self) -> usize {
+        usize::from(self.max_packet_size)
+    }
+
+    async fn setup(&mut self) -> [u8; 8] {
+        poll_fn(|cx| {
+            self.info.register_endpoint(0, cx);
+
+            if self.setup_pending() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+
+        // drop whatever was left of the previous transfer
+        write_entry(0, 0, 0);
+        write_entry(1, 0, 0);
+
+        let mut setup = [0; 8];
+        compiler_fence(Ordering::SeqCst);
+        // SAFETY: the SETUP buffer holds the 8 bytes of the packet
+        unsafe { ptr::copy_nonoverlapping(block_ptr(SETUP_BLOCK), setup.as_mut_ptr(), setup.len()) };
+
+        // the controller NAKs the data and status stages until the flag is cleared
+        self.info.modify_devcmdstat(0, 0, DEVCMDSTAT_SETUP);
+
+        setup
+    }
+
+    async fn data_out(&mut self, buf: &mut [u8], _first: bool, _last: bool) -> Result<usize, EndpointError> {
+        let max_packet_size = usize::from(self.max_packet_size);
+
+        write_entry(0, 0, prime(EP0_OUT_BLOCK, max_packet_size, 0));
+        let entry = self.wait_done(0).await?;
+
+        let len = transferred(entry, max_packet_size);
+        let dst = buf.get_mut(..len).ok_or(EndpointError::BufferOverflow)?;
+        compiler_fence(Ordering::SeqCst);
+        // SAFETY: the controller is done with the buffer, which holds a whole packet
+        unsafe { ptr::copy_nonoverlapping(block_ptr(EP0_OUT_BLOCK), dst.as_mut_ptr(), len) };
+
+        Ok(len)
+    }
+
+    async fn data_in(&mut self, data: &[u8], _first: bool, last: bool) -> Result<(), EndpointError> {
+        if data.len() > usize::from(self.max_packet_size) {
+            return Err(EndpointError::BufferOverflow);
+        }
+
+        // SAFETY: the IN buffer holds a whole packet, and is not in use between transfers
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), block_ptr(EP0_IN_BLOCK), data.len()) };
+        write_entry(1, 0, prime(EP0_IN_BLOCK, data.len(), 0));
+        self.wait_done(1).await?;
+
+        if last {
+            // accept the zero-length packet of the status stage
+            write_entry(0, 0, prime(EP0_OUT_BLOCK, 0, 0));
+        }
+
+        Ok(())
+    }
+
+    async fn accept(&mut self) {
+        write_entry(1, 0, prime(EP0_IN_BLOCK, 0, 0));
+        // an error means a new SETUP packet, which the next call to setup() takes
+        let _ = self.wait_done(1).await;
+    }
+
+    async fn reject(&mut self) {
+        write_entry(0, 0, EP_STALL);
+        write_entry(1, 0, EP_STALL);
+    }
+
+    async fn accept_set_address(&mut self, addr: u8) {
+        // the new address applies once the status stage is done
+        self.accept().await;
+        self.info
+            .modify_devcmdstat(u32::from(addr) & DEVCMDSTAT_DEV_ADDR, DEVCMDSTAT_DEV_ADDR, 0);
+    }
+}
+
+/// Power, clock and reset the controller, its PHY and its RAM
+fn init<T: Instance>(info: &Info, phy_config: &PhyConfig) {
+    // SAFETY: safe from single executor
+    let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
+    let rstctl0 = unsafe { crate::pac::Rstctl0::steal() };
+    let sysctl0 = unsafe { crate::pac::Sysctl0::steal() };
+
+    sysctl0
+        .pdruncfg1_clr()
+        .write(|w| w.usbhs_sram_apd().clr_pdruncfg1().usbhs_sram_ppd().clr_pdruncfg1());
+
+    clkctl0.pscctl0_set().write(|w| w.usbhs_sram_clk().set_clock());
+    rstctl0.prstctl0_clr().write(|w| w.usbhs_sram().clr_reset());
+
+    clkctl0.usbhsfclksel().write(|w| w.sel().xtalin_clk());
+    // SAFETY: unsafe needed to write the bits for the divider
+    clkctl0
+        .usbhsfclkdiv()
+        .modify(|_, w| unsafe { w.div().bits(0) }.halt().clear_bit());
+    while clkctl0.usbhsfclkdiv().read().reqflag().bit_is_set() {}
+
+    enable_and_reset::<peripherals::USBPHY>();
+    phy::init(info.phy, phy_config);
+
+    enable_and_reset::<T>();
+
+    // the port belongs to the host controller until told otherwise, which takes its clock
+    enable_and_reset::<peripherals::USBHSH>();
+    while sysctl0.usbclkstat().read().host_need_clkst().is_high() {}
+    // SAFETY: safe from single executor
+    let host = unsafe { crate::pac::Usbhsh::steal() };
+    host.portmode().modify(|_, w| w.dev_enable().set_bit());
+    clkctl0.pscctl0_clr().write(|w| w.usbhs_host_clk().clr_clock());
+}
@@ -0,0 +1,183 @@
+//! High-speed USB PHY
+
+/// Bypass of the PLL, cleared to clock the PHY from it
+const PLL_SIC_PLL_BYPASS: u32 = 1 << 16;
+
+/// Divider field of the PLL reference, cleared for the 24 MHz crystal
+const PLL_SIC_PLL_DIV_SEL: u32 = 0b111 << 22;
+
+/// 4-bit trim of the PHY, 0 to 15.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Trim(u8);
+
+impl Trim {
+    /// Middle of the range, the nominal value of the design.
+    pub const NOMINAL: Self = Self(7);
+
+    /// Trim of `value`, `None` above 15.
+    #[must_use]
+    pub const fn new(value: u8) -> Option<Self> {
+        match value {
+            0..=15 => Some(Self(value)),
+            _ => None,
+        }
+    }
+
+    /// Value of the trim.
+    #[must_use]
+    pub const fn value(self) -> u8 {
+        self.0
+    }
+}
+
+/// Trip point of the squelch detector, telling high-speed data from noise
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EnvelopeThreshold {
+    /// 87.5 mV
+    Mv87_5,
+    /// 100 mV
+    Mv100,
+    /// 112.5 mV
+    Mv112_5,
+    /// 125 mV
+    Mv125,
+}
+
+impl EnvelopeThreshold {
+    fn bits(self) -> u32 {
+        match self {
+            Self::Mv100 => 0,
+            Self::Mv112_5 => 1,
+            Self::Mv125 => 2,
+            Self::Mv87_5 => 3,
+        }
+    }
+}
+
+/// Trip point of the high-speed disconnect detector
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DisconnectThreshold {
+    /// 550 mV
+    Mv550,
+    /// 568.75 mV
+    Mv568_75,
+    /// 581.25 mV
+    Mv581_25,
+    /// 600 mV
+    Mv600,
+}
+
+impl DisconnectThreshold {
+    fn bits(self) -> u32 {
+        match self {
+            Self::Mv568_75 => 0,
+            Self::Mv550 => 1,
+            Self::Mv581_25 => 2,
+            Self::Mv600 => 3,
+        }
+    }
+}
+
+/// Analog tuning of the PHY, from the calibration of the board
+///
+/// Every field left at `None` keeps the factory trim, or the reset value of the PHY.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PhyConfig {
+    /// Current of the high-speed drivers, which sets the amplitude of the eye: 0 is about 19%
+    /// above nominal, 15 about 19% below
+    pub tx_current: Option<Trim>,
+    /// Series termination of USB_DP, around 45 ohm: higher values lower the resistance
+    pub tx_dp_termination: Option<Trim>,
+    /// Series termination of USB_DM, around 45 ohm: higher values lower the resistance
+    pub tx_dm_termination: Option<Trim>,
+    /// Trip point of the squelch detector
+    pub envelope_threshold: Option<EnvelopeThreshold>,
+    /// Trip point of the high-speed disconnect detector
+    pub disconnect_threshold: Option<DisconnectThreshold>,
+}
+
+/// TX: field of D_CAL
+const TX_D_CAL_SHIFT: u32 = 0;
+/// TX: field of TXCAL45DM
+const TX_CAL45DM_SHIFT: u32 = 8;
+/// TX: field of TXCAL45DP
+const TX_CAL45DP_SHIFT: u32 = 16;
+/// RX: field of ENVADJ
+const RX_ENVADJ_SHIFT: u32 = 0;
+/// RX: field of DISCONADJ
+const RX_DISCONADJ_SHIFT: u32 = 4;
+
+/// Replace the field `mask` at `shift` of `bits` with `value`
+fn with_field(bits: u32, shift: u32, mask: u32, value: u32) -> u32 {
+    (bits & !(mask << shift)) | ((value & mask) << shift)
+}
+
+/// Apply the trims of `config`, the TX ones override the factory values
+fn trim(regs: &crate::pac::usbphy::RegisterBlock, config: &PhyConfig) {
+    let tx = [
+        (config.tx_current, TX_D_CAL_SHIFT),
+        (config.tx_dm_termination, TX_CAL45DM_SHIFT),
+        (config.tx_dp_termination, TX_CAL45DP_SHIFT),
+    ];
+
+    // SAFETY: unsafe due to .bits usage, every trim is 4 bits wide
+    regs.tx().modify(|r, w| {
+        let bits = tx.iter().fold(r.bits(), |bits, &(trim, shift)| match trim {
+            Some(trim) => with_field(bits, shift, 0xF, u32::from(trim.value())),
+            None => bits,
+        });
+        unsafe { w.bits(bits) }
+    });
+
+    regs.trim_override_en_set().write(|w| {
+        w.trim_tx_d_cal_override()
+            .bit(config.tx_current.is_some())
+            .trim_tx_cal45dm_override()
+            .bit(config.tx_dm_termination.is_some())
+            .trim_tx_cal45dp_override()
+            .bit(config.tx_dp_termination.is_some())
+    });
+
+    // SAFETY: unsafe due to .bits usage, both fields are 3 bits wide
+    regs.rx().modify(|r, w| {
+        let mut bits = r.bits();
+        if let Some(threshold) = config.envelope_threshold {
+            bits = with_field(bits, RX_ENVADJ_SHIFT, 0x7, threshold.bits());
+        }
+        if let Some(threshold) = config.disconnect_threshold {
+            bits = with_field(bits, RX_DISCONADJ_SHIFT, 0x7, threshold.bits());
+        }
+        unsafe { w.bits(bits) }
+    });
+}
+
+/// Bring the PHY out of reset and lock its 480 MHz PLL on the 24 MHz crystal
+pub(super) fn init(regs: &crate::pac::usbphy::RegisterBlock, config: &PhyConfig) {
+    regs.ctrl_clr().write(|w| w.sftrst().set_bit());
+
+    // SAFETY: unsafe due to .bits usage, PLL_DIV_SEL 0 selects the 24 MHz reference
+    regs.pll_sic_clr()
+        .write(|w| unsafe { w.bits(PLL_SIC_PLL_DIV_SEL | PLL_SIC_PLL_BYPASS) });
+    regs.pll_sic_set()
+        .write(|w| w.pll_reg_enable().set_bit().pll_power().set_bit());
+    regs.pll_sic_set()
+        .write(|w| w.pll_enable().set_bit().pll_en_usb_clks().set_bit());
+
+    regs.ctrl_clr().write(|w| w.clkgate().set_bit());
+
+    // the soft reset above brought TX and RX back to their reset values
+    trim(regs, config);
+
+    // SAFETY: unsafe due to .bits usage, powers up every part of the PHY
+    regs.pwd().write(|w| unsafe { w.bits(0) });
+
+    while regs.pll_sic().read().pll_lock().bit_is_clear() {}
+
+    // VBUS_VALID comes from its own comparator, debounced by the controller
+    regs.usb1_vbus_detect_set()
+        .write(|w| w.pwrup_cmps().set_bit().vbusvalid_to_sessvalid().set_bit());
+    regs.usb1_vbus_detect_clr().write(|w| w.discharge_vbus().set_bit());
+}
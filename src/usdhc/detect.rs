@@ -0,0 +1,89 @@
+//! Card-detect and write-protect switches of the slot
+
+use embassy_hal_internal::Peri;
+
+use super::{CdPin, INT_CINS, INT_CRM, Instance, Protocol, Result, Usdhc, WpPin};
+
+/// Time the contacts of a new card take to settle before it is initialized
+#[cfg(feature = "time")]
+const CARD_SETTLE_MS: u64 = 100;
+
+impl<'d> Usdhc<'d> {
+    /// Route the card-detect switch of the slot to the controller
+    pub fn enable_card_detect<T: Instance>(&mut self, cd: Peri<'d, impl CdPin<T>>) {
+        cd.as_cd();
+
+        // the pin, not the test level, tells whether a card is in
+        self.info.regs.prot_ctrl().modify(|_, w| w.cdss().clear_bit());
+    }
+
+    /// Route the write-protect switch of the slot to the controller, writes then fail with
+    /// [`super::Error::WriteProtected`] while it is on
+    pub fn enable_write_protect<T: Instance>(&mut self, wp: Peri<'d, impl WpPin<T>>) {
+        wp.as_wp();
+        self.write_protect = true;
+    }
+
+    /// A card is in the slot, as far as the card-detect switch tells
+    pub fn is_card_present(&self) -> bool {
+        self.info.regs.pres_state().read().cinst().bit_is_set()
+    }
+
+    /// The write-protect switch of the card is on, always `false` without the switch
+    pub fn is_write_protected(&self) -> bool {
+        // WPSPL reads low when the switch is on
+        self.write_protect && self.info.regs.pres_state().read().wpspl().bit_is_clear()
+    }
+
+    /// Wait for a card to be in the slot, then initialize it the way the last card was
+    ///
+    /// Returns at once when a card is already in. Without a successful initialization before,
+    /// the card is left for the caller to identify.
+    pub async fn wait_for_insertion(&mut self) -> Result<()> {
+        if self.wait_card_state(INT_CINS, true).await {
+            #[cfg(feature = "time")]
+            embassy_time::Timer::after_millis(CARD_SETTLE_MS).await;
+
+            self.reinit().await
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Wait for the card to leave the slot, forgetting it
+    pub async fn wait_for_removal(&mut self) {
+        self.wait_card_state(INT_CRM, false).await;
+        self.card = None;
+        self.sdio = None;
+    }
+
+    /// Initialize the card again the way the last successful initialization did, such as after
+    /// it lost power
+    pub async fn reinit(&mut self) -> Result<()> {
+        match self.protocol {
+            Some(Protocol::Sd) => self.init_sd_card().await,
+            Some(Protocol::Mmc) => self.init_mmc_card().await,
+            Some(Protocol::Sdio) => self.init_sdio_card().await.map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Wait for the presence of a card to be `present`, latching in `flag`, `true` when it had to
+    /// change
+    async fn wait_card_state(&self, flag: u32, present: bool) -> bool {
+        let regs = self.info.regs;
+        let mut changed = false;
+
+        loop {
+            // cleared before the check, a change right after it still latches
+            // SAFETY: unsafe due to .bits usage, the flags are cleared by writing 1
+            regs.int_status().write(|w| unsafe { w.bits(flag) });
+            if self.is_card_present() == present {
+                return changed;
+            }
+
+            self.wait_status(flag).await;
+            changed = true;
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! eMMC protocol
+
+use super::sd::Aligned;
+use super::{BLOCK_SIZE, BusWidth, Card, CardType, Command, Error, Protocol, Response, Result, Usdhc, long_response};
+
+/// OCR: power-up done
+const OCR_BUSY: u32 = 1 << 31;
+/// OCR: access mode field
+const OCR_ACCESS_MODE: u32 = 0b11 << 29;
+/// OCR: sector access mode, for devices above 2 GB and a host that handles them
+const OCR_SECTOR_MODE: u32 = 0b10 << 29;
+/// OCR: 2.7 V to 3.6 V, and 1.70 V to 1.95 V
+const OCR_VOLTAGES: u32 = 0x00FF_8080;
+
+/// CMD1 attempts before giving up on the device, about a second at 400 kHz
+const CMD1_RETRIES: usize = 2000;
+
+/// Relative address the host gives the device, the only one on the bus
+const MMC_RCA: u16 = 1;
+
+/// R1: the last CMD6 was refused
+const R1_SWITCH_ERROR: u32 = 1 << 7;
+
+/// CMD6 argument: write a byte of the extended CSD
+const SWITCH_WRITE_BYTE: u32 = 0b11 << 24;
+
+/// Extended CSD: BUS_WIDTH
+const EXT_CSD_BUS_WIDTH: u8 = 183;
+/// Extended CSD: HS_TIMING
+const EXT_CSD_HS_TIMING: u8 = 185;
+/// Extended CSD: DEVICE_TYPE
+const EXT_CSD_DEVICE_TYPE: usize = 196;
+/// Extended CSD: SEC_COUNT, 4 bytes little endian
+const EXT_CSD_SEC_COUNT: usize = 212;
+
+/// DEVICE_TYPE: high speed up to 52 MHz
+const DEVICE_TYPE_HS52: u8 = 1 << 1;
+/// DEVICE_TYPE: HS200 with 1.8 V signaling
+const DEVICE_TYPE_HS200_1V8: u8 = 1 << 4;
+
+/// HS_TIMING: high speed
+const HS_TIMING_HS: u8 = 1;
+/// HS_TIMING: HS200
+const HS_TIMING_HS200: u8 = 2;
+
+/// Clock of default speed devices
+const DEFAULT_SPEED_HZ: u32 = 26_000_000;
+/// Clock of high speed devices
+const HIGH_SPEED_HZ: u32 = 52_000_000;
+/// Clock of HS200 devices, above what the controller reaches
+const HS200_HZ: u32 = 200_000_000;
+
+/// Tuning command of HS200
+const CMD21_SEND_TUNING_BLOCK: u8 = 21;
+
+/// CMD6 argument writing `value` to byte `index` of the extended CSD
+fn switch_arg(index: u8, value: u8) -> u32 {
+    SWITCH_WRITE_BYTE | (u32::from(index) << 16) | (u32::from(value) << 8)
+}
+
+/// SEC_COUNT of the extended CSD `ext_csd`
+fn sector_count(ext_csd: &[u8]) -> u32 {
+    ext_csd
+        .get(EXT_CSD_SEC_COUNT..EXT_CSD_SEC_COUNT + 4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map_or(0, u32::from_le_bytes)
+}
+
+impl Usdhc<'_> {
+    /// Identify the eMMC device and bring it to the transfer state
+    ///
+    /// The device ends on the widest bus of the driver. It runs HS200 when the board has
+    /// [`super::Config::low_voltage_signaling`] and a 4-bit or 8-bit bus, high speed otherwise,
+    /// at the highest clock up to [`super::Config::frequency`] it supports.
+    pub async fn init_mmc_card(&mut self) -> Result<()> {
+        self.start_identification()?;
+
+        // eMMC signals at its VCCQ from power-up, there is no switch later on
+        self.info
+            .regs
+            .vend_spec()
+            .modify(|_, w| w.vselect().bit(self.config.low_voltage_signaling));
+
+        self.command(Command::new(0, 0, Response::None), None).await?;
+
+        let mut ocr = 0;
+        for _ in 0..CMD1_RETRIES {
+            let [r3, ..] = self
+                .command(Command::new(1, OCR_SECTOR_MODE | OCR_VOLTAGES, Response::Ocr), None)
+                .await?;
+            if r3 & OCR_BUSY != 0 {
+                ocr = r3;
+                break;
+            }
+        }
+
+        if ocr & OCR_BUSY == 0 || ocr & OCR_VOLTAGES == 0 {
+            return Err(Error::UnsupportedCard);
+        }
+
+        let card_type = if ocr & OCR_ACCESS_MODE == OCR_SECTOR_MODE {
+            CardType::MmcHc
+        } else {
+            CardType::Mmc
+        };
+
+        let cid = long_response(self.command(Command::new(2, 0, Response::Long), None).await?);
+        // the host hands out the address on MMC
+        self.command_r1(Command::new(3, u32::from(MMC_RCA) << 16, Response::Short), None)
+            .await?;
+        let csd = long_response(
+            self.command(Command::new(9, u32::from(MMC_RCA) << 16, Response::Long), None)
+                .await?,
+        );
+
+        self.command_r1(Command::new(7, u32::from(MMC_RCA) << 16, Response::ShortBusy), None)
+            .await?;
+
+        if card_type == CardType::Mmc {
+            self.command_r1(Command::new(16, BLOCK_SIZE as u32, Response::Short), None)
+                .await?;
+        }
+
+        let mut ext_csd = Aligned([0; BLOCK_SIZE]);
+        self.read_register(Command::new(8, 0, Response::Short), &mut ext_csd.0)
+            .await?;
+        let device_type = ext_csd.0.get(EXT_CSD_DEVICE_TYPE).copied().unwrap_or(0);
+
+        self.card = Some(Card {
+            card_type,
+            rca: MMC_RCA,
+            ocr,
+            cid,
+            csd,
+            scr: 0,
+            sectors: sector_count(&ext_csd.0),
+        });
+
+        let width = self.bus_width;
+        if width != BusWidth::One {
+            let value = if width == BusWidth::Eight { 2 } else { 1 };
+            self.switch(EXT_CSD_BUS_WIDTH, value).await?;
+            self.set_bus_width(width);
+        }
+
+        let frequency = self.config.frequency;
+        if self.config.low_voltage_signaling
+            && width != BusWidth::One
+            && device_type & DEVICE_TYPE_HS200_1V8 != 0
+            && frequency > HIGH_SPEED_HZ
+        {
+            self.switch(EXT_CSD_HS_TIMING, HS_TIMING_HS200).await?;
+            self.set_clock(frequency.min(HS200_HZ))?;
+            self.execute_tuning(CMD21_SEND_TUNING_BLOCK).await?;
+        } else if device_type & DEVICE_TYPE_HS52 != 0 && frequency > DEFAULT_SPEED_HZ {
+            self.switch(EXT_CSD_HS_TIMING, HS_TIMING_HS).await?;
+            self.set_clock(frequency.min(HIGH_SPEED_HZ))?;
+        } else {
+            self.set_clock(frequency.min(DEFAULT_SPEED_HZ))?;
+        }
+        self.protocol = Some(Protocol::Mmc);
+
+        Ok(())
+    }
+
+    /// Write `value` to byte `index` of the extended CSD with CMD6, once the device is done
+    async fn switch(&mut self, index: u8, value: u8) -> Result<()> {
+        self.command_r1(Command::new(6, switch_arg(index, value), Response::ShortBusy), None)
+            .await?;
+
+        let status = self.wait_ready().await?;
+        if status & R1_SWITCH_ERROR != 0 {
+            return Err(Error::Card(status));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switch_arguments() {
+        // 8-bit bus
+        assert_eq!(switch_arg(EXT_CSD_BUS_WIDTH, 2), 0x03B7_0200);
+        // HS200
+        assert_eq!(switch_arg(EXT_CSD_HS_TIMING, HS_TIMING_HS200), 0x03B9_0200);
+    }
+
+    #[test]
+    fn extended_csd_sectors() {
+        let mut ext_csd = [0; BLOCK_SIZE];
+        for (byte, value) in ext_csd.iter_mut().skip(EXT_CSD_SEC_COUNT).zip([0x00, 0xA0, 0x74, 0x00]) {
+            *byte = value;
+        }
+        assert_eq!(sector_count(&ext_csd), 0x0074_A000);
+        assert_eq!(sector_count(&[]), 0);
+    }
+}
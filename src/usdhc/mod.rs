@@ -0,0 +1,1158 @@
+//! Ultra Secured Digital Host Controller (uSDHC)
+//!
+//! Drives SD cards on the 1-bit or 4-bit bus, see [`Usdhc::init_sd_card`], eMMC devices up to
+//! the 8-bit bus, see [`Usdhc::init_mmc_card`], and SDIO cards such as WiFi modules, see
+//! [`Usdhc::init_sdio_card`]. Blocks move between
+//! the card and memory with the ADMA2 engine of the controller, the task only waits for the
+//! transfer to complete.
+//!
+//! With a card-detect switch, [`Usdhc::wait_for_insertion`] and [`Usdhc::wait_for_removal`]
+//! follow the slot, the card coming back initialized the way it was before.
+//!
+//! With the `embedded-sdmmc` feature, `SdBlockDevice` mounts the card with the blocking FAT
+//! filesystem of `embedded-sdmmc`. With the `block-device-driver` feature, [`Usdhc`] itself is an
+//! async block device for filesystems such as `embedded-fatfs`.
+
+mod detect;
+mod mmc;
+mod sd;
+mod sdio;
+mod storage;
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_hal_internal::Peri;
+use embassy_hal_internal::drop::OnDrop;
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::clocks::{SysconPeripheral, enable_and_reset};
+use crate::interrupt::typelevel::Interrupt;
+use crate::iopctl::{DriveMode, DriveStrength, Function, Inverter, IopctlPin as Pin, Pull, SlewRate};
+use crate::{PeripheralType, interrupt, peripherals};
+
+pub use sd::{Card, CardType};
+pub use sdio::SdioCard;
+#[cfg(feature = "embedded-sdmmc")]
+pub use storage::SdBlockDevice;
+
+/// shorthand for -> `Result<T>`
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Error information type
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// configuration requested is not supported
+    UnsupportedConfiguration,
+    /// no card answered the command
+    CommandTimeout,
+    /// the response to the command was corrupted
+    CommandCrc,
+    /// the response to the command was malformed, or for another command
+    Command,
+    /// the card stopped sending or accepting data
+    DataTimeout,
+    /// the data was corrupted on the bus
+    DataCrc,
+    /// the data block was malformed
+    Data,
+    /// the ADMA engine failed to fetch a descriptor or to reach the buffer
+    Dma,
+    /// the card reported an error in its status, given here
+    Card(u32),
+    /// the card does not support the operating conditions of the host
+    UnsupportedCard,
+    /// the card was not initialized yet
+    NoCard,
+    /// the block address is past the end of the card
+    OutOfRange,
+    /// no sampling point of the card clock reads the tuning block right
+    Tuning,
+    /// the write-protect switch of the card is on
+    WriteProtected,
+    /// the card did not come up at 1.8 V signaling, it needs a power cycle
+    VoltageSwitch,
+    /// the card or the controller stayed busy for too long
+    Timeout,
+}
+
+/// Size of a data block, the only one used for reads and writes
+pub const BLOCK_SIZE: usize = 512;
+
+/// Data block, aligned for the ADMA engine
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(align(4))]
+pub struct DataBlock(pub [u8; BLOCK_SIZE]);
+
+impl DataBlock {
+    /// Block filled with zeros
+    pub const fn new() -> Self {
+        Self([0; BLOCK_SIZE])
+    }
+}
+
+impl Default for DataBlock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::ops::Deref for DataBlock {
+    type Target = [u8; BLOCK_SIZE];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl core::ops::DerefMut for DataBlock {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Divider of the main PLL into the function clock
+const FCLK_DIV: u8 = 3;
+
+/// Clock of the card while it is identified
+const IDENTIFICATION_HZ: u32 = 400_000;
+
+/// Descriptors of the ADMA2 table, each moving up to [`ADMA_MAX_LEN`] bytes
+const ADMA_DESCRIPTORS: usize = 8;
+
+/// Largest transfer of an ADMA2 descriptor, a whole number of blocks
+const ADMA_MAX_LEN: usize = 127 * BLOCK_SIZE;
+
+/// Largest transfer of a single command
+const MAX_BLOCKS: usize = ADMA_DESCRIPTORS * ADMA_MAX_LEN / BLOCK_SIZE;
+
+/// ADMA2 descriptor attributes
+const ADMA_VALID: u64 = 1 << 0;
+const ADMA_END: u64 = 1 << 1;
+const ADMA_TRANSFER: u64 = 0b10 << 4;
+
+/// INT_STATUS flags
+const INT_CC: u32 = 1 << 0;
+const INT_TC: u32 = 1 << 1;
+const INT_BRR: u32 = 1 << 5;
+const INT_CINS: u32 = 1 << 6;
+const INT_CRM: u32 = 1 << 7;
+const INT_CINT: u32 = 1 << 8;
+const INT_CTOE: u32 = 1 << 16;
+const INT_CCE: u32 = 1 << 17;
+const INT_CEBE: u32 = 1 << 18;
+const INT_CIE: u32 = 1 << 19;
+const INT_DTOE: u32 = 1 << 20;
+const INT_DCE: u32 = 1 << 21;
+const INT_DEBE: u32 = 1 << 22;
+const INT_AC12E: u32 = 1 << 24;
+const INT_TNE: u32 = 1 << 26;
+const INT_DMAE: u32 = 1 << 28;
+
+/// Errors of the command phase
+const INT_COMMAND_ERRORS: u32 = INT_CTOE | INT_CCE | INT_CEBE | INT_CIE;
+
+/// Errors of the data phase
+const INT_DATA_ERRORS: u32 = INT_DTOE | INT_DCE | INT_DEBE | INT_AC12E | INT_DMAE;
+
+/// CMD_XFR_TYP fields
+const XFR_RSPTYP_136: u32 = 0b01 << 16;
+const XFR_RSPTYP_48: u32 = 0b10 << 16;
+const XFR_RSPTYP_48_BUSY: u32 = 0b11 << 16;
+const XFR_CCCEN: u32 = 1 << 19;
+const XFR_CICEN: u32 = 1 << 20;
+const XFR_DPSEL: u32 = 1 << 21;
+const XFR_CMDTYP_ABORT: u32 = 0b11 << 22;
+const XFR_CMDINX_SHIFT: u32 = 24;
+
+/// MIX_CTRL fields of the data transfer, the others belong to tuning
+const MIX_DMAEN: u32 = 1 << 0;
+const MIX_BCEN: u32 = 1 << 1;
+const MIX_AC12EN: u32 = 1 << 2;
+const MIX_DTDSEL: u32 = 1 << 4;
+const MIX_MSBSEL: u32 = 1 << 5;
+const MIX_TRANSFER: u32 = MIX_DMAEN | MIX_BCEN | MIX_AC12EN | MIX_DTDSEL | MIX_MSBSEL;
+
+/// Reads of the present state for the CMD and DATA lines to be free, well over the longest
+/// busy time of a card between two commands
+const INHIBIT_SPINS: usize = 1_000_000;
+
+/// CMD13 sent while the card programs written data: each takes at least 2 us on the fastest bus,
+/// covering the 500 ms write timeout of SDXC cards
+const READY_RETRIES: usize = 250_000;
+
+/// Tuning commands the controller gets to find the sampling point, one per tap it tries
+const TUNING_COMMANDS: usize = 40;
+
+/// Error bits of the R1 card status
+const R1_ERRORS: u32 = 0xFDF9_8008;
+/// R1: the card accepts data
+const R1_READY_FOR_DATA: u32 = 1 << 8;
+/// R1: CURRENT_STATE field
+const R1_STATE_SHIFT: u32 = 9;
+const R1_STATE_MASK: u32 = 0xF;
+/// R1: the transfer state, ready for data commands
+const R1_STATE_TRAN: u32 = 4;
+
+/// Data bus width
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BusWidth {
+    /// DAT0 only
+    One,
+    /// DAT0 to DAT3
+    Four,
+    /// DAT0 to DAT7, eMMC only
+    Eight,
+}
+
+impl BusWidth {
+    fn dtw(self) -> u8 {
+        match self {
+            Self::One => 0,
+            Self::Four => 1,
+            Self::Eight => 2,
+        }
+    }
+}
+
+/// Configuration for uSDHC
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Highest card clock once the card is identified, above 25 MHz only with high-speed cards,
+    /// above 50 MHz only with UHS-I SD cards or HS200 eMMC devices
+    pub frequency: u32,
+    /// The board can run the card I/O at 1.8 V, following VSELECT: UHS-I SD cards then run SDR25,
+    /// DDR50 or SDR50, and eMMC devices HS200
+    pub low_voltage_signaling: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            frequency: 25_000_000,
+            low_voltage_signaling: false,
+        }
+    }
+}
+
+/// Response the host expects to a command
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Response {
+    /// no response
+    None,
+    /// R1, R5, R6 and R7: 48 bits with CRC and command index
+    Short,
+    /// R1b: like R1, then the card holds DAT0 low while busy
+    ShortBusy,
+    /// R2: 136 bits, the CID or the CSD
+    Long,
+    /// R3 and R4: 48 bits without CRC nor command index
+    Ocr,
+}
+
+impl Response {
+    fn bits(self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Short => XFR_RSPTYP_48 | XFR_CCCEN | XFR_CICEN,
+            Self::ShortBusy => XFR_RSPTYP_48_BUSY | XFR_CCCEN | XFR_CICEN,
+            Self::Long => XFR_RSPTYP_136 | XFR_CCCEN,
+            Self::Ocr => XFR_RSPTYP_48,
+        }
+    }
+}
+
+/// Command on the CMD line
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Command {
+    index: u8,
+    arg: u32,
+    response: Response,
+}
+
+impl Command {
+    const fn new(index: u8, arg: u32, response: Response) -> Self {
+        Self { index, arg, response }
+    }
+}
+
+/// Direction of the data phase of a command
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Direction {
+    /// from the card
+    Read,
+    /// to the card
+    Write,
+}
+
+/// Data phase of a command
+struct Transfer {
+    direction: Direction,
+    buffer: *mut u8,
+    block_size: usize,
+    blocks: usize,
+    /// end multiple blocks with an automatic CMD12, SDIO transfers stop by themselves
+    auto_stop: bool,
+}
+
+/// Division of the SDCLKFS prescaler: its bits hold half the division, 0 leaves the clock undivided
+fn prescaler(sdclkfs: u8) -> u32 {
+    if sdclkfs == 0 { 1 } else { u32::from(sdclkfs) * 2 }
+}
+
+/// Clock out of the SDCLKFS prescaler and DVS divisor
+fn divided_clock(base_hz: u32, sdclkfs: u8, dvs: u8) -> u32 {
+    base_hz / (prescaler(sdclkfs) * (u32::from(dvs) + 1))
+}
+
+/// SDCLKFS prescaler and DVS divisor of the fastest clock not going over `target_hz`, and that clock
+fn clock_dividers(base_hz: u32, target_hz: u32) -> Option<(u8, u8, u32)> {
+    if target_hz == 0 {
+        return None;
+    }
+
+    (0..=8)
+        .flat_map(|prescaler: u32| (1..=16).map(move |divisor: u32| (prescaler, divisor)))
+        .map(|(prescaler, divisor)| (prescaler, divisor, base_hz / ((1 << prescaler) * divisor)))
+        .filter(|&(_, _, hz)| hz <= target_hz)
+        .max_by_key(|&(_, _, hz)| hz)
+        .map(|(prescaler, divisor, hz)| (((1 << prescaler) >> 1) as u8, (divisor - 1) as u8, hz))
+}
+
+/// Raw response of a 136-bit R2: the controller drops the CRC, leaving bits 127 to 8 in place
+fn long_response(rsp: [u32; 4]) -> u128 {
+    let [rsp0, rsp1, rsp2, rsp3] = rsp.map(u128::from);
+    ((rsp3 << 96) | (rsp2 << 64) | (rsp1 << 32) | rsp0) << 8
+}
+
+/// Check the R1 card status of a response for errors
+fn check_r1(status: u32) -> Result<u32> {
+    if status & R1_ERRORS != 0 {
+        Err(Error::Card(status))
+    } else {
+        Ok(status)
+    }
+}
+
+mod sealed {
+    /// simply seal a trait
+    pub trait Sealed {}
+}
+
+impl<T: Pin> sealed::Sealed for T {}
+
+struct Info {
+    regs: &'static crate::pac::usdhc0::RegisterBlock,
+    waker: &'static AtomicWaker,
+}
+
+// SAFETY: safety for Send here is the same as the other accessors to unsafe blocks: it must be done from a single executor context.
+//         This is a temporary workaround -- a better solution might be to refactor Info to no longer maintain a reference to regs,
+//         but instead look up the correct register set and then perform operations within an unsafe block as we do for other peripherals
+unsafe impl Send for Info {}
+
+trait SealedInstance {
+    fn info() -> Info;
+    /// Route and divide the function clock, returning its rate (Hz) if the source is running
+    fn select_clock() -> Option<u32>;
+}
+
+/// uSDHC instance trait.
+#[allow(private_bounds)]
+pub trait Instance: SealedInstance + PeripheralType + SysconPeripheral + 'static + Send {
+    /// Interrupt for this uSDHC instance.
+    type Interrupt: interrupt::typelevel::Interrupt;
+}
+
+impl SealedInstance for peripherals::USDHC0 {
+    fn info() -> Info {
+        static WAKER: AtomicWaker = AtomicWaker::new();
+
+        Info {
+            regs: unsafe { &*crate::pac::Usdhc0::ptr() },
+            waker: &WAKER,
+        }
+    }
+
+    fn select_clock() -> Option<u32> {
+        // SAFETY: safe from single executor
+        let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
+
+        clkctl0.sdio0fclksel().write(|w| w.sel().main_sys_pll_clk());
+        // SAFETY: unsafe needed to write the bits for the divider
+        clkctl0
+            .sdio0fclkdiv()
+            .modify(|_, w| unsafe { w.div().bits(FCLK_DIV - 1) }.halt().clear_bit());
+        while clkctl0.sdio0fclkdiv().read().reqflag().bit_is_set() {}
+
+        let div = u32::from(clkctl0.sdio0fclkdiv().read().div().bits()) + 1;
+        crate::clocks::main_pll_clk_frequency().map(|hz| hz / div)
+    }
+}
+
+impl Instance for peripherals::USDHC0 {
+    type Interrupt = crate::interrupt::typelevel::USDHC0;
+}
+
+/// uSDHC interrupt handler.
+pub struct InterruptHandler<T: Instance> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandler<T> {
+    unsafe fn on_interrupt() {
+        let info = T::info();
+
+        // the flags stay set for the task, which enables their interrupts again while waiting
+        // SAFETY: unsafe due to .bits usage
+        info.regs.int_signal_en().write(|w| unsafe { w.bits(0) });
+        info.waker.wake();
+    }
+}
+
+/// io configuration trait for easier configuration
+pub trait ClkPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for CLK usage
+    fn as_clk(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait CmdPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for CMD usage
+    fn as_cmd(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait D0Pin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for DAT0 usage
+    fn as_d0(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait D1Pin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for DAT1 usage
+    fn as_d1(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait D2Pin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for DAT2 usage
+    fn as_d2(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait D3Pin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for DAT3 usage
+    fn as_d3(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait D4Pin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for DAT4 usage
+    fn as_d4(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait D5Pin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for DAT5 usage
+    fn as_d5(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait D6Pin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for DAT6 usage
+    fn as_d6(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait D7Pin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for DAT7 usage
+    fn as_d7(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait CdPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for card-detect usage
+    fn as_cd(&self);
+}
+
+/// io configuration trait for easier configuration
+pub trait WpPin<T: Instance>: Pin + sealed::Sealed + PeripheralType {
+    /// convert the pin to appropriate function for write-protect usage
+    fn as_wp(&self);
+}
+
+/// CMD and DAT lines are open to the card, pulled up when nobody drives them
+fn configure_bus_pin(pin: &impl Pin, function: Function, pull: Pull) {
+    pin.set_function(function)
+        .set_pull(pull)
+        .enable_input_buffer()
+        .set_slew_rate(SlewRate::Standard)
+        .set_drive_strength(DriveStrength::Full)
+        .disable_analog_multiplex()
+        .set_drive_mode(DriveMode::PushPull)
+        .set_input_inverter(Inverter::Disabled);
+}
+
+macro_rules! impl_pin {
+    ($piom_n:ident, $fn:ident, $mode:ident, $pull:ident) => {
+        paste::paste! {
+            impl [<$mode:camel Pin>]<peripherals::USDHC0> for peripherals::$piom_n {
+                fn [<as_ $mode>](&self) {
+                    configure_bus_pin(self, Function::$fn, Pull::$pull);
+                }
+            }
+        }
+    };
+}
+
+impl_pin!(PIO1_30, F1, clk, None);
+impl_pin!(PIO1_31, F1, cmd, Up);
+impl_pin!(PIO2_0, F1, d0, Up);
+impl_pin!(PIO2_1, F1, d1, Up);
+impl_pin!(PIO2_2, F1, d2, Up);
+impl_pin!(PIO2_3, F1, d3, Up);
+impl_pin!(PIO2_4, F1, d4, Up);
+impl_pin!(PIO2_5, F1, d5, Up);
+impl_pin!(PIO2_6, F1, d6, Up);
+impl_pin!(PIO2_7, F1, d7, Up);
+impl_pin!(PIO2_9, F1, cd, Up);
+impl_pin!(PIO2_10, F1, wp, Up);
+
+/// Protocol of the last successful initialization, which a new card goes through again
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Protocol {
+    Sd,
+    Mmc,
+    Sdio,
+}
+
+/// uSDHC driver
+pub struct Usdhc<'d> {
+    info: Info,
+    config: Config,
+    bus_width: BusWidth,
+    card: Option<Card>,
+    sdio: Option<SdioCard>,
+    protocol: Option<Protocol>,
+    /// the write-protect switch is wired to the controller
+    write_protect: bool,
+    /// ADMA2 descriptor table, rebuilt for every transfer
+    descriptors: [u64; ADMA_DESCRIPTORS],
+    /// function clock (Hz), unknown while its source is not running
+    fclk_hz: Option<u32>,
+    _phantom: PhantomData<&'d ()>,
+}
+
+impl<'d> Usdhc<'d> {
+    /// Create the driver on the 1-bit bus
+    pub fn new_1bit<T: Instance>(
+        _usdhc: Peri<'d, T>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        clk: Peri<'d, impl ClkPin<T>>,
+        cmd: Peri<'d, impl CmdPin<T>>,
+        d0: Peri<'d, impl D0Pin<T>>,
+        config: Config,
+    ) -> Self {
+        clk.as_clk();
+        cmd.as_cmd();
+        d0.as_d0();
+
+        Self::new_inner::<T>(BusWidth::One, config)
+    }
+
+    /// Create the driver on the 4-bit bus
+    pub fn new_4bit<T: Instance>(
+        _usdhc: Peri<'d, T>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        clk: Peri<'d, impl ClkPin<T>>,
+        cmd: Peri<'d, impl CmdPin<T>>,
+        d0: Peri<'d, impl D0Pin<T>>,
+        d1: Peri<'d, impl D1Pin<T>>,
+        d2: Peri<'d, impl D2Pin<T>>,
+        d3: Peri<'d, impl D3Pin<T>>,
+        config: Config,
+    ) -> Self {
+        clk.as_clk();
+        cmd.as_cmd();
+        d0.as_d0();
+        d1.as_d1();
+        d2.as_d2();
+        d3.as_d3();
+
+        Self::new_inner::<T>(BusWidth::Four, config)
+    }
+
+    /// Create the driver on the 8-bit bus, for eMMC devices
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_8bit<T: Instance>(
+        _usdhc: Peri<'d, T>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        clk: Peri<'d, impl ClkPin<T>>,
+        cmd: Peri<'d, impl CmdPin<T>>,
+        d0: Peri<'d, impl D0Pin<T>>,
+        d1: Peri<'d, impl D1Pin<T>>,
+        d2: Peri<'d, impl D2Pin<T>>,
+        d3: Peri<'d, impl D3Pin<T>>,
+        d4: Peri<'d, impl D4Pin<T>>,
+        d5: Peri<'d, impl D5Pin<T>>,
+        d6: Peri<'d, impl D6Pin<T>>,
+        d7: Peri<'d, impl D7Pin<T>>,
+        config: Config,
+    ) -> Self {
+        clk.as_clk();
+        cmd.as_cmd();
+        d0.as_d0();
+        d1.as_d1();
+        d2.as_d2();
+        d3.as_d3();
+        d4.as_d4();
+        d5.as_d5();
+        d6.as_d6();
+        d7.as_d7();
+
+        Self::new_inner::<T>(BusWidth::Eight, config)
+    }
+
+    fn new_inner<T: Instance>(bus_width: BusWidth, config: Config) -> Self {
+        let fclk_hz = T::select_clock();
+        enable_and_reset::<T>();
+
+        let info = T::info();
+        init(info.regs);
+
+        T::Interrupt::unpend();
+        // SAFETY: the handler is bound above
+        unsafe { T::Interrupt::enable() };
+
+        Self {
+            info,
+            config,
+            bus_width,
+            card: None,
+            sdio: None,
+            protocol: None,
+            write_protect: false,
+            descriptors: [0; ADMA_DESCRIPTORS],
+            fclk_hz,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The card found by the last initialization
+    pub fn card(&self) -> Result<&Card> {
+        self.card.as_ref().ok_or(Error::NoCard)
+    }
+
+    /// Clock of the card, 0 while the function clock is not running
+    pub fn clock_frequency(&self) -> u32 {
+        let sys_ctrl = self.info.regs.sys_ctrl().read();
+        self.base_clock().map_or(0, |base| {
+            divided_clock(base, sys_ctrl.sdclkfs().bits(), sys_ctrl.dvs().bits())
+        })
+    }
+
+    /// Clock into the prescaler, which divides by 2 more on dual data rate
+    fn base_clock(&self) -> Result<u32> {
+        let fclk_hz = self.fclk_hz.ok_or(Error::UnsupportedConfiguration)?;
+
+        if self.info.regs.mix_ctrl().read().ddr_en().bit_is_set() {
+            Ok(fclk_hz / 2)
+        } else {
+            Ok(fclk_hz)
+        }
+    }
+
+    /// Run the card clock at the highest rate up to `hz`, returning that rate
+    fn set_clock(&mut self, hz: u32) -> Result<u32> {
+        let regs = self.info.regs;
+        let (sdclkfs, dvs, actual) = clock_dividers(self.base_clock()?, hz).ok_or(Error::UnsupportedConfiguration)?;
+
+        regs.vend_spec().modify(|_, w| w.frc_sdclk_on().clear_bit());
+        // SAFETY: unsafe due to .bits usage
+        regs.sys_ctrl()
+            .modify(|_, w| unsafe { w.sdclkfs().bits(sdclkfs).dvs().bits(dvs) });
+        while regs.pres_state().read().sdstb().bit_is_clear() {}
+
+        Ok(actual)
+    }
+
+    fn set_bus_width(&mut self, width: BusWidth) {
+        // SAFETY: unsafe due to .bits usage
+        self.info
+            .regs
+            .prot_ctrl()
+            .modify(|_, w| unsafe { w.dtw().bits(width.dtw()) });
+    }
+
+    /// Forget the card and go back to the 1-bit bus at the identification clock, on single data
+    /// rate and sampling the data without tuning
+    fn start_identification(&mut self) -> Result<()> {
+        self.card = None;
+        self.sdio = None;
+        self.set_bus_width(BusWidth::One);
+
+        let regs = self.info.regs;
+        regs.mix_ctrl().modify(|_, w| {
+            w.exe_tune()
+                .clear_bit()
+                .smp_clk_sel()
+                .clear_bit()
+                .auto_tune_en()
+                .clear_bit()
+                .fbclk_sel()
+                .clear_bit()
+                .ddr_en()
+                .clear_bit()
+        });
+        regs.tuning_ctrl().modify(|_, w| w.std_tuning_en().clear_bit());
+
+        self.set_clock(IDENTIFICATION_HZ)?;
+        self.initialization_clocks();
+        Ok(())
+    }
+
+    /// Send the 80 clock cycles the card waits for after power-up
+    fn initialization_clocks(&mut self) {
+        let regs = self.info.regs;
+        regs.sys_ctrl().modify(|_, w| w.inita().set_bit());
+        while regs.sys_ctrl().read().inita().bit_is_set() {}
+    }
+
+    /// Reset the CMD line, and the DATA line with `data`, after an error
+    fn reset_lines(&mut self, data: bool) {
+        let regs = self.info.regs;
+        regs.sys_ctrl().modify(|_, w| w.rstc().set_bit().rstd().bit(data));
+        while regs.sys_ctrl().read().rstc().bit_is_set() || regs.sys_ctrl().read().rstd().bit_is_set() {}
+    }
+
+    /// Wait for any of the INT_STATUS flags of `mask`, returning all of them
+    async fn wait_status(&self, mask: u32) -> u32 {
+        let regs = self.info.regs;
+
+        poll_fn(|cx| {
+            self.info.waker.register(cx.waker());
+
+            let status = regs.int_status().read().bits();
+            if status & mask != 0 {
+                return Poll::Ready(status);
+            }
+
+            // SAFETY: unsafe due to .bits usage, INT_SIGNAL_EN mirrors INT_STATUS
+            regs.int_signal_en().write(|w| unsafe { w.bits(mask) });
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Fill the ADMA2 table for `len` bytes at `buffer`
+    fn build_descriptors(&mut self, buffer: *mut u8, len: usize) -> Result<()> {
+        if len > ADMA_DESCRIPTORS * ADMA_MAX_LEN || buffer as usize % 4 != 0 {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let count = len.div_ceil(ADMA_MAX_LEN);
+        for (n, descriptor) in self.descriptors.iter_mut().enumerate().take(count) {
+            let offset = n * ADMA_MAX_LEN;
+            let chunk = (len - offset).min(ADMA_MAX_LEN);
+            let address = buffer.wrapping_add(offset) as u32;
+            let end = if n + 1 == count { ADMA_END } else { 0 };
+
+            *descriptor = (u64::from(address) << 32) | ((chunk as u64) << 16) | ADMA_TRANSFER | ADMA_VALID | end;
+        }
+
+        Ok(())
+    }
+
+    /// Wait for the CMD line to be free, and the DATA line with `data`
+    fn wait_inhibit(&mut self, data: bool) -> Result<()> {
+        for _ in 0..INHIBIT_SPINS {
+            let state = self.info.regs.pres_state().read();
+            if !state.cihb().bit_is_set() && !(data && state.cdihb().bit_is_set()) {
+                return Ok(());
+            }
+        }
+
+        self.reset_lines(data);
+        Err(Error::Timeout)
+    }
+
+    /// Send `cmd` and wait for its response, then for its data phase if any
+    async fn command(&mut self, cmd: Command, transfer: Option<Transfer>) -> Result<[u32; 4]> {
+        let regs = self.info.regs;
+
+        self.wait_inhibit(transfer.is_some() || cmd.response == Response::ShortBusy)?;
+
+        // SAFETY: unsafe due to .bits usage, the flags are cleared by writing 1
+        regs.int_status().write(|w| unsafe { w.bits(u32::MAX) });
+
+        let mut xfr_typ = cmd.response.bits() | (u32::from(cmd.index) << XFR_CMDINX_SHIFT);
+
+        let mut mix = 0;
+        if let Some(transfer) = &transfer {
+            let len = transfer.block_size * transfer.blocks;
+            self.build_descriptors(transfer.buffer, len)?;
+
+            // the FIFO asks for the ADMA engine once a block, or 512 bytes, is in
+            let watermark = (transfer.block_size / 4).clamp(1, 128) as u8;
+            // SAFETY: unsafe due to .bits usage
+            regs.wtmk_lvl()
+                .modify(|_, w| unsafe { w.rd_wml().bits(watermark).wr_wml().bits(watermark) });
+
+            // SAFETY: unsafe due to .bits usage, the table lives until the transfer is over
+            regs.adma_sys_addr()
+                .write(|w| unsafe { w.bits(self.descriptors.as_ptr() as u32) });
+            regs.blk_att().write(|w| unsafe {
+                w.blksize()
+                    .bits(transfer.block_size as u16)
+                    .blkcnt()
+                    .bits(transfer.blocks as u16)
+            });
+
+            mix = MIX_DMAEN | MIX_BCEN;
+            if transfer.direction == Direction::Read {
+                mix |= MIX_DTDSEL;
+            }
+            if transfer.blocks > 1 {
+                mix |= MIX_MSBSEL;
+            }
+            if transfer.blocks > 1 && transfer.auto_stop {
+                mix |= MIX_AC12EN;
+            }
+            xfr_typ |= XFR_DPSEL;
+        }
+
+        // SAFETY: unsafe due to .bits usage
+        regs.mix_ctrl()
+            .modify(|r, w| unsafe { w.bits((r.bits() & !MIX_TRANSFER) | mix) });
+        regs.cmd_arg().write(|w| unsafe { w.bits(cmd.arg) });
+        regs.cmd_xfr_typ().write(|w| unsafe { w.bits(xfr_typ) });
+
+        // the ADMA engine must not reach the buffer of the transfer once its future is dropped
+        let stop = transfer.as_ref().is_some_and(|t| t.blocks > 1 && t.auto_stop);
+        let on_drop = OnDrop::new(|| abort(regs, stop));
+
+        let result = self.finish_command(cmd, transfer.is_some()).await;
+
+        on_drop.defuse();
+
+        result
+    }
+
+    /// Wait for the response of `cmd`, then for its data phase if any
+    async fn finish_command(&mut self, cmd: Command, transfer: bool) -> Result<[u32; 4]> {
+        let regs = self.info.regs;
+
+        let status = self.wait_status(INT_CC | INT_COMMAND_ERRORS).await;
+        if status & INT_COMMAND_ERRORS != 0 {
+            self.reset_lines(transfer);
+            return Err(if status & INT_CTOE != 0 {
+                Error::CommandTimeout
+            } else if status & INT_CCE != 0 {
+                Error::CommandCrc
+            } else {
+                Error::Command
+            });
+        }
+
+        let response = [
+            regs.cmd_rsp0().read().bits(),
+            regs.cmd_rsp1().read().bits(),
+            regs.cmd_rsp2().read().bits(),
+            regs.cmd_rsp3().read().bits(),
+        ];
+
+        if transfer || cmd.response == Response::ShortBusy {
+            let status = self.wait_status(INT_TC | INT_DATA_ERRORS).await;
+            if status & INT_DATA_ERRORS != 0 {
+                self.reset_lines(true);
+                return Err(if status & INT_DMAE != 0 {
+                    Error::Dma
+                } else if status & INT_DTOE != 0 {
+                    Error::DataTimeout
+                } else if status & INT_DCE != 0 {
+                    Error::DataCrc
+                } else {
+                    Error::Data
+                });
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Send `cmd`, an R1 or R1b, and check the card status it answers with
+    async fn command_r1(&mut self, cmd: Command, transfer: Option<Transfer>) -> Result<u32> {
+        let [status, ..] = self.command(cmd, transfer).await?;
+        check_r1(status)
+    }
+
+    /// Find the sampling point of the card clock with the standard tuning of the controller,
+    /// sending the tuning command `index` until the controller is done with it
+    async fn execute_tuning(&mut self, index: u8) -> Result<()> {
+        let regs = self.info.regs;
+
+        // the tuning block is 128 bytes on the 8-bit bus, 64 bytes otherwise
+        let eight_bit = regs.prot_ctrl().read().dtw().bits() == BusWidth::Eight.dtw();
+        let block_size = if eight_bit { 128 } else { 64 };
+
+        regs.vend_spec2().modify(|_, w| w.tuning_8bit_en().bit(eight_bit));
+        regs.tuning_ctrl().modify(|_, w| w.std_tuning_en().set_bit());
+        regs.mix_ctrl()
+            .modify(|_, w| w.exe_tune().set_bit().smp_clk_sel().clear_bit().fbclk_sel().set_bit());
+
+        for _ in 0..TUNING_COMMANDS {
+            self.tuning_command(index, block_size).await?;
+            if regs.mix_ctrl().read().exe_tune().bit_is_clear() {
+                break;
+            }
+        }
+
+        let mix_ctrl = regs.mix_ctrl().read();
+        if mix_ctrl.exe_tune().bit_is_set() || mix_ctrl.smp_clk_sel().bit_is_clear() {
+            regs.mix_ctrl()
+                .modify(|_, w| w.exe_tune().clear_bit().smp_clk_sel().clear_bit());
+            regs.tuning_ctrl().modify(|_, w| w.std_tuning_en().clear_bit());
+            return Err(Error::Tuning);
+        }
+
+        // the controller follows the drift of the sampling point from now on
+        regs.mix_ctrl().modify(|_, w| w.auto_tune_en().set_bit());
+        Ok(())
+    }
+
+    /// Send the tuning command `index`, the controller reads the tuning block itself
+    ///
+    /// Taps away from the sampling point corrupt the block, only a card that doesn't answer
+    /// fails the command.
+    async fn tuning_command(&mut self, index: u8, block_size: u16) -> Result<()> {
+        let regs = self.info.regs;
+
+        self.wait_inhibit(true)?;
+
+        // SAFETY: unsafe due to .bits usage, the flags are cleared by writing 1
+        regs.int_status().write(|w| unsafe { w.bits(u32::MAX) });
+        regs.blk_att()
+            .write(|w| unsafe { w.blksize().bits(block_size).blkcnt().bits(1) });
+        regs.wtmk_lvl()
+            .modify(|_, w| unsafe { w.rd_wml().bits((block_size / 4) as u8) });
+        regs.mix_ctrl()
+            .modify(|r, w| unsafe { w.bits((r.bits() & !MIX_TRANSFER) | MIX_DTDSEL) });
+        regs.cmd_arg().write(|w| unsafe { w.bits(0) });
+        regs.cmd_xfr_typ()
+            .write(|w| unsafe { w.bits(Response::Short.bits() | XFR_DPSEL | (u32::from(index) << XFR_CMDINX_SHIFT)) });
+
+        let on_drop = OnDrop::new(|| abort(regs, false));
+
+        let status = self
+            .wait_status(INT_BRR | INT_COMMAND_ERRORS | INT_DATA_ERRORS | INT_TNE)
+            .await;
+
+        on_drop.defuse();
+
+        if status & INT_TNE != 0 {
+            self.reset_lines(true);
+            return Err(Error::Tuning);
+        }
+        if status & (INT_COMMAND_ERRORS | INT_DATA_ERRORS) != 0 {
+            self.reset_lines(true);
+            if status & INT_CTOE != 0 {
+                return Err(Error::CommandTimeout);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Wait for the card to be done programming, back in the transfer state, returning its status
+    async fn wait_ready(&mut self) -> Result<u32> {
+        let rca = self.card()?.rca;
+
+        for _ in 0..READY_RETRIES {
+            let status = self
+                .command_r1(Command::new(13, u32::from(rca) << 16, Response::Short), None)
+                .await?;
+
+            if status & R1_READY_FOR_DATA != 0 && (status >> R1_STATE_SHIFT) & R1_STATE_MASK == R1_STATE_TRAN {
+                return Ok(status);
+            }
+
+            embassy_futures::yield_now().await;
+        }
+
+        Err(Error::Timeout)
+    }
+
+    /// Read `blocks` into `buf`, starting from block `address` of the card
+    async fn read(&mut self, address: u32, buf: *mut u8, blocks: usize) -> Result<()> {
+        let card = self.card()?;
+        if u64::from(address) + blocks as u64 > card.block_count() {
+            return Err(Error::OutOfRange);
+        }
+
+        let arg = card.data_address(address);
+        let index = if blocks > 1 { 18 } else { 17 };
+        let transfer = Transfer {
+            direction: Direction::Read,
+            buffer: buf,
+            block_size: BLOCK_SIZE,
+            blocks,
+            auto_stop: true,
+        };
+
+        self.command_r1(Command::new(index, arg, Response::Short), Some(transfer))
+            .await
+            .map(|_| ())
+    }
+
+    /// Write `blocks` from `buf`, starting at block `address` of the card
+    async fn write(&mut self, address: u32, buf: *const u8, blocks: usize) -> Result<()> {
+        if self.is_write_protected() {
+            return Err(Error::WriteProtected);
+        }
+
+        let card = self.card()?;
+        if u64::from(address) + blocks as u64 > card.block_count() {
+            return Err(Error::OutOfRange);
+        }
+
+        let arg = card.data_address(address);
+        let index = if blocks > 1 { 25 } else { 24 };
+        let transfer = Transfer {
+            direction: Direction::Write,
+            buffer: buf.cast_mut(),
+            block_size: BLOCK_SIZE,
+            blocks,
+            auto_stop: true,
+        };
+
+        self.command_r1(Command::new(index, arg, Response::Short), Some(transfer))
+            .await?;
+
+        // the card programs the data after the transfer
+        self.wait_ready().await.map(|_| ())
+    }
+
+    /// Read the blocks from block `address` of the card into `blocks`
+    pub async fn read_blocks(&mut self, address: u32, blocks: &mut [DataBlock]) -> Result<()> {
+        let mut address = address;
+        for chunk in blocks.chunks_mut(MAX_BLOCKS) {
+            self.read(address, chunk.as_mut_ptr().cast(), chunk.len()).await?;
+            address += chunk.len() as u32;
+        }
+        Ok(())
+    }
+
+    /// Write `blocks` to the card, from block `address`
+    pub async fn write_blocks(&mut self, address: u32, blocks: &[DataBlock]) -> Result<()> {
+        let mut address = address;
+        for chunk in blocks.chunks(MAX_BLOCKS) {
+            self.write(address, chunk.as_ptr().cast(), chunk.len()).await?;
+            address += chunk.len() as u32;
+        }
+        Ok(())
+    }
+
+    /// Read block `address` of the card
+    pub async fn read_block(&mut self, address: u32, block: &mut DataBlock) -> Result<()> {
+        self.read_blocks(address, core::slice::from_mut(block)).await
+    }
+
+    /// Write block `address` of the card
+    pub async fn write_block(&mut self, address: u32, block: &DataBlock) -> Result<()> {
+        self.write_blocks(address, core::slice::from_ref(block)).await
+    }
+}
+
+impl Drop for Usdhc<'_> {
+    fn drop(&mut self) {
+        abort(self.info.regs, false);
+        self.info.regs.sys_ctrl().modify(|_, w| w.rsta().set_bit());
+    }
+}
+
+/// Stop the command and the transfer in flight: reset the CMD and DATA lines, which stops the
+/// ADMA engine, then end an open-ended multiple block transfer of the card with CMD12 when `stop`
+fn abort(regs: &crate::pac::usdhc0::RegisterBlock, stop: bool) {
+    // SAFETY: unsafe due to .bits usage
+    regs.int_signal_en().write(|w| unsafe { w.bits(0) });
+
+    regs.sys_ctrl().modify(|_, w| w.rstc().set_bit().rstd().set_bit());
+    while regs.sys_ctrl().read().rstc().bit_is_set() || regs.sys_ctrl().read().rstd().bit_is_set() {}
+
+    // SAFETY: unsafe due to .bits usage
+    regs.mix_ctrl()
+        .modify(|r, w| unsafe { w.bits(r.bits() & !MIX_TRANSFER) });
+
+    if stop {
+        // the next command waits for the card to release DAT0, its status reads the response
+        // SAFETY: unsafe due to .bits usage
+        regs.cmd_arg().write(|w| unsafe { w.bits(0) });
+        regs.cmd_xfr_typ()
+            .write(|w| unsafe { w.bits(Response::ShortBusy.bits() | XFR_CMDTYP_ABORT | (12 << XFR_CMDINX_SHIFT)) });
+    }
+}
+
+/// Reset the controller and set up little-endian ADMA2 transfers
+fn init(regs: &crate::pac::usdhc0::RegisterBlock) {
+    regs.sys_ctrl().modify(|_, w| w.rsta().set_bit());
+    while regs.sys_ctrl().read().rsta().bit_is_set() {}
+
+    // SAFETY: unsafe due to .bits usage, EMODE 2 is little endian and DMASEL 2 is ADMA2
+    regs.prot_ctrl()
+        .modify(|_, w| unsafe { w.emode().bits(2).dmasel().bits(2).dtw().bits(BusWidth::One.dtw()) });
+
+    // longest bursts, the watermarks follow the block size of each transfer
+    // SAFETY: unsafe due to .bits usage
+    regs.wtmk_lvl().write(|w| unsafe {
+        w.rd_wml()
+            .bits(128)
+            .rd_brst_len()
+            .bits(16)
+            .wr_wml()
+            .bits(128)
+            .wr_brst_len()
+            .bits(16)
+    });
+
+    // longest data timeout
+    // SAFETY: unsafe due to .bits usage
+    regs.sys_ctrl().modify(|_, w| unsafe { w.dtocv().bits(0xE) });
+
+    // every flag latches, the task picks the ones it waits for; the card interrupt follows
+    // DAT1 and only latches while an SDIO task waits for it
+    // SAFETY: unsafe due to .bits usage
+    regs.int_status_en().write(|w| unsafe { w.bits(!INT_CINT) });
+    regs.int_signal_en().write(|w| unsafe { w.bits(0) });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Function clock of the default clock setup, the ~500 MHz main PLL divided by 3
+    const FCLK_HZ: u32 = 500_000_000 / FCLK_DIV as u32;
+
+    #[test]
+    fn identification_clock() {
+        let dividers = clock_dividers(FCLK_HZ, IDENTIFICATION_HZ);
+        assert!(matches!(dividers, Some((sdclkfs, dvs, hz))
+            if hz <= IDENTIFICATION_HZ && hz > 300_000 && divided_clock(FCLK_HZ, sdclkfs, dvs) == hz));
+    }
+
+    #[test]
+    fn undivided_clock() {
+        assert_eq!(clock_dividers(100_000_000, 100_000_000), Some((0, 0, 100_000_000)));
+        assert_eq!(clock_dividers(100_000_000, 50_000_000), Some((0, 1, 50_000_000)));
+        assert_eq!(clock_dividers(100_000_000, 0), None);
+    }
+
+    #[test]
+    fn long_response_realigns_bits() {
+        assert_eq!(long_response([0, 0, 0, 0x0080_0000]), 1 << 127);
+        assert_eq!(long_response([1, 0, 0, 0]), 1 << 8);
+    }
+}
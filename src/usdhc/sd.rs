@@ -0,0 +1,478 @@
+//! SD card protocol
+
+use super::{
+    BLOCK_SIZE, BusWidth, Command, Direction, Error, Protocol, Response, Result, Transfer, Usdhc, long_response,
+};
+
+/// CMD8 argument: 2.7 V to 3.6 V, and a check pattern the card echoes
+const CMD8_ARG: u32 = 0x1AA;
+
+/// OCR: power-up done
+const OCR_BUSY: u32 = 1 << 31;
+/// OCR: card capacity status, set for block-addressed cards
+const OCR_CCS: u32 = 1 << 30;
+/// OCR: host capacity support, the host handles block-addressed cards
+const OCR_HCS: u32 = 1 << 30;
+/// OCR: 3.2 V to 3.4 V
+const OCR_VOLTAGE_WINDOW: u32 = 0x0030_0000;
+/// OCR: the host asks for 1.8 V signaling, or the card accepts it
+const OCR_S18: u32 = 1 << 24;
+
+/// ACMD41 attempts before giving up on the card, about a second at 400 kHz
+const ACMD41_RETRIES: usize = 2000;
+
+/// R1: the card expects an application command
+const R1_APP_CMD: u32 = 1 << 5;
+
+/// SCR: the card supports the 4-bit bus
+const SCR_BUS_WIDTH_4: u64 = 1 << 50;
+
+/// CMD6 argument: switch the functions, instead of checking them
+const CMD6_SWITCH: u32 = 1 << 31;
+/// CMD6 argument: leave every group alone, function group 1 is or'd in
+const CMD6_NO_CHANGE: u32 = 0x00FF_FFF0;
+/// CMD6 function group 1: keep the current function
+const FUNCTION_CURRENT: u8 = 0xF;
+/// CMD6 function group 1: high speed, or SDR25 at 1.8 V
+const FUNCTION_HIGH_SPEED: u8 = 1;
+
+/// Tuning command of SDR50
+const CMD19_SEND_TUNING_BLOCK: u8 = 19;
+
+/// Clock of default speed cards
+const DEFAULT_SPEED_HZ: u32 = 25_000_000;
+
+/// Clock of high speed cards
+const HIGH_SPEED_HZ: u32 = 50_000_000;
+
+/// Time the card and the regulator get to settle on 1.8 V, and with the clock running after it
+const VOLTAGE_SWITCH_US: u64 = 5_000;
+const VOLTAGE_SWITCH_CLOCK_US: u64 = 1_000;
+
+/// Highest core clock, from the main PLL, for busy waits without a timer
+#[cfg(not(feature = "time"))]
+const CORE_MAX_HZ: u64 = 500_000_000;
+
+/// UHS-I bus speed mode at 1.8 V signaling
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum UhsMode {
+    /// single data rate up to 50 MHz
+    Sdr25,
+    /// single data rate up to 100 MHz, with tuning
+    Sdr50,
+    /// dual data rate up to 50 MHz
+    Ddr50,
+}
+
+impl UhsMode {
+    /// From the fastest, the modes worth their switch above the clock of default speed
+    const PREFERENCE: [Self; 3] = [Self::Sdr50, Self::Ddr50, Self::Sdr25];
+
+    /// Function of group 1 of CMD6
+    fn function(self) -> u8 {
+        match self {
+            Self::Sdr25 => 1,
+            Self::Sdr50 => 2,
+            Self::Ddr50 => 4,
+        }
+    }
+
+    fn max_hz(self) -> u32 {
+        match self {
+            Self::Sdr25 | Self::Ddr50 => 50_000_000,
+            Self::Sdr50 => 100_000_000,
+        }
+    }
+
+    /// Clock above which the mode beats the slower ones
+    fn min_hz(self) -> u32 {
+        match self {
+            Self::Sdr25 | Self::Ddr50 => DEFAULT_SPEED_HZ,
+            Self::Sdr50 => HIGH_SPEED_HZ,
+        }
+    }
+}
+
+/// Fastest UHS-I mode of the functions `supported` by both the card and the host, for a clock
+/// up to `frequency`
+fn uhs_mode(supported: u16, frequency: u32) -> Option<UhsMode> {
+    UhsMode::PREFERENCE
+        .into_iter()
+        .find(|mode| supported & (1 << mode.function()) != 0 && frequency > mode.min_hz())
+}
+
+/// Wait at least `us` microseconds
+async fn delay_us(us: u64) {
+    #[cfg(feature = "time")]
+    embassy_time::Timer::after_micros(us).await;
+
+    // the loop assumes the fastest core, waiting longer on a slower one
+    #[cfg(not(feature = "time"))]
+    crate::clocks::delay_loop_clocks(us, CORE_MAX_HZ);
+}
+
+/// Kind and capacity class of a card
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CardType {
+    /// SD standard capacity, up to 2 GB, addressed in bytes
+    Sdsc,
+    /// SD high and extended capacity, addressed in blocks
+    Sdhc,
+    /// MMC up to 2 GB, addressed in bytes
+    Mmc,
+    /// MMC above 2 GB, addressed in sectors of 512 bytes
+    MmcHc,
+}
+
+/// SD card or eMMC device found by [`Usdhc::init_sd_card`] or [`Usdhc::init_mmc_card`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Card {
+    /// capacity class
+    pub card_type: CardType,
+    /// relative card address, given by the card during identification
+    pub rca: u16,
+    /// operating conditions register
+    pub ocr: u32,
+    /// card identification register
+    pub cid: u128,
+    /// card specific data register
+    pub csd: u128,
+    /// SD configuration register, 0 for MMC
+    pub scr: u64,
+    /// sector count of the extended CSD, 0 for SD cards
+    pub sectors: u32,
+}
+
+/// Bits `high` down to `low` of `value`
+fn field(value: u128, high: u32, low: u32) -> u64 {
+    ((value >> low) & ((1 << (high - low + 1)) - 1)) as u64
+}
+
+impl Card {
+    /// Number of 512-byte blocks of the card
+    pub fn block_count(&self) -> u64 {
+        match (self.card_type, field(self.csd, 127, 126)) {
+            (CardType::MmcHc, _) => u64::from(self.sectors),
+            // CSD version 1.0 and every MMC CSD: C_SIZE, C_SIZE_MULT and READ_BL_LEN
+            (CardType::Mmc, _) | (_, 0) => {
+                let c_size = field(self.csd, 73, 62);
+                let c_size_mult = field(self.csd, 49, 47);
+                let read_bl_len = field(self.csd, 83, 80);
+                ((c_size + 1) << (c_size_mult + 2 + read_bl_len)) / BLOCK_SIZE as u64
+            }
+            // CSD version 2.0: C_SIZE in units of 512 KiB
+            _ => (field(self.csd, 69, 48) + 1) * 1024,
+        }
+    }
+
+    /// Argument of the data commands for block `block`
+    pub(super) fn data_address(&self, block: u32) -> u32 {
+        match self.card_type {
+            CardType::Sdsc | CardType::Mmc => block * BLOCK_SIZE as u32,
+            CardType::Sdhc | CardType::MmcHc => block,
+        }
+    }
+}
+
+/// Buffer the ADMA engine reaches for small register reads
+#[repr(align(4))]
+pub(super) struct Aligned<const N: usize>(pub(super) [u8; N]);
+
+impl Usdhc<'_> {
+    /// Identify the SD card and bring it to the data transfer state
+    ///
+    /// The card ends on the widest bus of the driver, at the highest clock up to
+    /// [`super::Config::frequency`] it supports. With
+    /// [`super::Config::low_voltage_signaling`], UHS-I cards switch to 1.8 V and their fastest
+    /// bus speed mode.
+    pub async fn init_sd_card(&mut self) -> Result<()> {
+        self.start_identification()?;
+
+        // every card starts at 3.3 V
+        self.info.regs.vend_spec().modify(|_, w| w.vselect().clear_bit());
+
+        self.command(Command::new(0, 0, Response::None), None).await?;
+
+        // version 1 cards don't know CMD8
+        let hcs = match self.command(Command::new(8, CMD8_ARG, Response::Short), None).await {
+            Ok([r7, ..]) if r7 & 0xFFF == CMD8_ARG => OCR_HCS,
+            Ok(_) => return Err(Error::UnsupportedCard),
+            Err(Error::CommandTimeout) => 0,
+            Err(e) => return Err(e),
+        };
+
+        // only version 2 cards know about 1.8 V
+        let s18r = if hcs != 0 && self.config.low_voltage_signaling {
+            OCR_S18
+        } else {
+            0
+        };
+
+        let mut ocr = 0;
+        for _ in 0..ACMD41_RETRIES {
+            self.app_command(0).await?;
+            let [r3, ..] = self
+                .command(Command::new(41, hcs | s18r | OCR_VOLTAGE_WINDOW, Response::Ocr), None)
+                .await?;
+            if r3 & OCR_BUSY != 0 {
+                ocr = r3;
+                break;
+            }
+        }
+
+        if ocr & OCR_BUSY == 0 || ocr & OCR_VOLTAGE_WINDOW == 0 {
+            return Err(Error::UnsupportedCard);
+        }
+
+        let card_type = if ocr & OCR_CCS != 0 {
+            CardType::Sdhc
+        } else {
+            CardType::Sdsc
+        };
+
+        let uhs = s18r != 0 && ocr & OCR_S18 != 0;
+        if uhs {
+            self.switch_voltage().await?;
+        }
+
+        let cid = long_response(self.command(Command::new(2, 0, Response::Long), None).await?);
+        let [r6, ..] = self.command(Command::new(3, 0, Response::Short), None).await?;
+        let rca = (r6 >> 16) as u16;
+        let csd = long_response(
+            self.command(Command::new(9, u32::from(rca) << 16, Response::Long), None)
+                .await?,
+        );
+
+        self.command_r1(Command::new(7, u32::from(rca) << 16, Response::ShortBusy), None)
+            .await?;
+
+        if card_type == CardType::Sdsc {
+            self.command_r1(Command::new(16, BLOCK_SIZE as u32, Response::Short), None)
+                .await?;
+        }
+
+        let scr = self.read_scr(rca).await?;
+        let card = Card {
+            card_type,
+            rca,
+            ocr,
+            cid,
+            csd,
+            scr,
+            sectors: 0,
+        };
+        self.card = Some(card);
+
+        let four_bit = self.bus_width != BusWidth::One && card.scr & SCR_BUS_WIDTH_4 != 0;
+        if four_bit {
+            self.app_command(rca).await?;
+            self.command_r1(Command::new(6, 2, Response::Short), None).await?;
+            self.set_bus_width(BusWidth::Four);
+        }
+
+        // UHS-I modes run on the 4-bit bus only
+        if uhs && four_bit {
+            self.switch_uhs_mode().await?;
+        } else {
+            let mut hz = self.config.frequency.min(DEFAULT_SPEED_HZ);
+            if self.config.frequency > DEFAULT_SPEED_HZ
+                && self.switch_function(FUNCTION_HIGH_SPEED).await? == FUNCTION_HIGH_SPEED
+            {
+                hz = self.config.frequency.min(HIGH_SPEED_HZ);
+            }
+            self.set_clock(hz)?;
+        }
+        self.protocol = Some(Protocol::Sd);
+
+        Ok(())
+    }
+
+    /// Announce an application command to the card of `rca`
+    async fn app_command(&mut self, rca: u16) -> Result<()> {
+        let status = self
+            .command_r1(Command::new(55, u32::from(rca) << 16, Response::Short), None)
+            .await?;
+
+        if status & R1_APP_CMD == 0 {
+            return Err(Error::UnsupportedCard);
+        }
+
+        Ok(())
+    }
+
+    /// Read `buf.len()` bytes of a register after `cmd`, as a single block
+    pub(super) async fn read_register(&mut self, cmd: Command, buf: &mut [u8]) -> Result<()> {
+        let transfer = Transfer {
+            direction: Direction::Read,
+            buffer: buf.as_mut_ptr(),
+            block_size: buf.len(),
+            blocks: 1,
+            auto_stop: false,
+        };
+
+        self.command_r1(cmd, Some(transfer)).await.map(|_| ())
+    }
+
+    /// Read the SD configuration register with ACMD51
+    async fn read_scr(&mut self, rca: u16) -> Result<u64> {
+        let mut scr = Aligned([0; 8]);
+
+        self.app_command(rca).await?;
+        self.read_register(Command::new(51, 0, Response::Short), &mut scr.0)
+            .await?;
+
+        Ok(u64::from_be_bytes(scr.0))
+    }
+
+    /// Read the 64-byte status of CMD6 for `function` of group 1, switching to it with `switch`
+    async fn function_status(&mut self, function: u8, switch: bool) -> Result<Aligned<64>> {
+        let mut status = Aligned([0; 64]);
+        let mode = if switch { CMD6_SWITCH } else { 0 };
+
+        self.read_register(
+            Command::new(6, mode | CMD6_NO_CHANGE | u32::from(function), Response::Short),
+            &mut status.0,
+        )
+        .await?;
+
+        Ok(status)
+    }
+
+    /// Switch function group 1 of the card to `function` with CMD6, returning the function now
+    /// selected, which stays the current one when the card doesn't support `function`
+    async fn switch_function(&mut self, function: u8) -> Result<u8> {
+        let status = self.function_status(function, true).await?;
+
+        // bits 379:376 of the status hold the function group 1 now selected
+        Ok(status.0.get(16).map_or(0, |&byte| byte & 0xF))
+    }
+
+    /// Switch the card and the host to 1.8 V signaling with CMD11
+    async fn switch_voltage(&mut self) -> Result<()> {
+        self.command_r1(Command::new(11, 0, Response::Short), None).await?;
+
+        let regs = self.info.regs;
+
+        // the card holds DAT[3:0] low once the clock stops, until it is done switching
+        regs.vend_spec().modify(|_, w| w.frc_sdclk_on().clear_bit());
+        if regs.pres_state().read().dlsl().bits() & 0xF != 0 {
+            return Err(Error::VoltageSwitch);
+        }
+
+        regs.vend_spec().modify(|_, w| w.vselect().set_bit());
+        delay_us(VOLTAGE_SWITCH_US).await;
+
+        regs.vend_spec().modify(|_, w| w.frc_sdclk_on().set_bit());
+        delay_us(VOLTAGE_SWITCH_CLOCK_US).await;
+        regs.vend_spec().modify(|_, w| w.frc_sdclk_on().clear_bit());
+
+        if regs.pres_state().read().dlsl().bits() & 0xF != 0xF {
+            return Err(Error::VoltageSwitch);
+        }
+
+        Ok(())
+    }
+
+    /// Switch the card to the fastest UHS-I mode both ends support, then tune the sampling point
+    /// when the mode needs it
+    async fn switch_uhs_mode(&mut self) -> Result<()> {
+        let frequency = self.config.frequency;
+        let status = self.function_status(FUNCTION_CURRENT, false).await?;
+
+        // bits 415:400 of the status hold the functions of group 1 the card supports
+        let card = status.0.get(12..14).map_or(0, |bytes| {
+            bytes.iter().fold(0, |bits, &byte| (bits << 8) | u16::from(byte))
+        });
+
+        let caps = self.info.regs.host_ctrl_cap().read();
+        let mut host = 1 << UhsMode::Sdr25.function();
+        if caps.sdr50_support().bit_is_set() {
+            host |= 1 << UhsMode::Sdr50.function();
+        }
+        if caps.ddr50_support().bit_is_set() {
+            host |= 1 << UhsMode::Ddr50.function();
+        }
+
+        let Some(mode) = uhs_mode(card & host, frequency) else {
+            // SDR12, the default speed at 1.8 V
+            self.set_clock(frequency.min(DEFAULT_SPEED_HZ))?;
+            return Ok(());
+        };
+
+        if self.switch_function(mode.function()).await? != mode.function() {
+            return Err(Error::UnsupportedCard);
+        }
+
+        // the prescaler divides by 2 more on dual data rate, set it first
+        self.info
+            .regs
+            .mix_ctrl()
+            .modify(|_, w| w.ddr_en().bit(mode == UhsMode::Ddr50));
+        self.set_clock(frequency.min(mode.max_hz()))?;
+
+        if mode == UhsMode::Sdr50 {
+            self.execute_tuning(CMD19_SEND_TUNING_BLOCK).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(csd: u128) -> Card {
+        Card {
+            card_type: CardType::Sdhc,
+            rca: 0,
+            ocr: 0,
+            cid: 0,
+            csd,
+            scr: 0,
+            sectors: 0,
+        }
+    }
+
+    #[test]
+    fn csd_v2_capacity() {
+        // 0x3B37 units of 512 KiB, a "8 GB" card
+        let csd = (1 << 126) | (0x3B37 << 48);
+        assert_eq!(card(csd).block_count(), 0x3B38 * 1024);
+    }
+
+    #[test]
+    fn csd_v1_capacity() {
+        // 4096 * 2^(7 + 2) blocks of 2^10 bytes, a 2 GB card
+        let csd = (4095 << 62) | (7 << 47) | (10 << 80);
+        assert_eq!(card(csd).block_count(), 4096 * 512 * 2);
+    }
+
+    #[test]
+    fn mmc_capacity() {
+        let mut mmc = card(0);
+        mmc.card_type = CardType::MmcHc;
+        mmc.sectors = 0x0074_A000;
+        assert_eq!(mmc.block_count(), 0x0074_A000);
+    }
+
+    #[test]
+    fn uhs_mode_preference() {
+        let sdr25 = 1 << 1;
+        let sdr50 = 1 << 2;
+        let ddr50 = 1 << 4;
+        assert_eq!(uhs_mode(sdr25 | sdr50 | ddr50, 100_000_000), Some(UhsMode::Sdr50));
+        assert_eq!(uhs_mode(sdr25 | sdr50 | ddr50, 50_000_000), Some(UhsMode::Ddr50));
+        assert_eq!(uhs_mode(sdr25 | sdr50, 50_000_000), Some(UhsMode::Sdr25));
+        assert_eq!(uhs_mode(sdr25 | sdr50 | ddr50, 25_000_000), None);
+    }
+
+    #[test]
+    fn byte_addressed_cards() {
+        let mut sdsc = card(0);
+        sdsc.card_type = CardType::Sdsc;
+        assert_eq!(sdsc.data_address(3), 3 * 512);
+        assert_eq!(card(0).data_address(3), 3);
+    }
+}
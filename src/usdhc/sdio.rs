@@ -0,0 +1,448 @@
+//! SDIO card protocol
+
+use super::{BusWidth, Command, Direction, Error, INT_CINT, Protocol, Response, Result, Transfer, Usdhc};
+
+/// R4: the card finished powering up
+const R4_READY: u32 = 1 << 31;
+/// R4: number of I/O functions
+const R4_FUNCTIONS_SHIFT: u32 = 28;
+const R4_FUNCTIONS_MASK: u32 = 0x7;
+/// R4: the card has a memory part too
+const R4_MEMORY_PRESENT: u32 = 1 << 27;
+/// R4: supported voltages
+const R4_OCR_MASK: u32 = 0x00FF_FFFF;
+
+/// OCR: 3.2 V to 3.4 V
+const OCR_VOLTAGE_WINDOW: u32 = 0x0030_0000;
+
+/// CMD5 attempts before giving up on the card
+const CMD5_RETRIES: usize = 2000;
+
+/// CMD52 and CMD53: write to the card
+const IO_WRITE: u32 = 1 << 31;
+/// CMD52 and CMD53: function number
+const IO_FUNCTION_SHIFT: u32 = 28;
+/// CMD52: read the register back after writing it
+const IO_RAW: u32 = 1 << 27;
+/// CMD53: count in blocks instead of bytes
+const IO_BLOCK_MODE: u32 = 1 << 27;
+/// CMD53: the address increments after each byte
+const IO_INCREMENT: u32 = 1 << 26;
+/// CMD52 and CMD53: register address
+const IO_ADDRESS_SHIFT: u32 = 9;
+const IO_ADDRESS_MASK: u32 = 0x1_FFFF;
+/// CMD53: largest count, 0 stands for 512 bytes or blocks
+const IO_MAX_COUNT: usize = 511;
+
+/// R5 flags: COM_CRC_ERROR, ILLEGAL_COMMAND, ERROR, FUNCTION_NUMBER and OUT_OF_RANGE
+const R5_ERRORS: u32 = 0xCB00;
+
+/// CCCR registers of function 0
+const CCCR_IO_ENABLE: u32 = 0x02;
+const CCCR_IO_READY: u32 = 0x03;
+const CCCR_INT_ENABLE: u32 = 0x04;
+const CCCR_IO_ABORT: u32 = 0x06;
+const CCCR_BUS_INTERFACE: u32 = 0x07;
+const CCCR_CAPABILITY: u32 = 0x08;
+const CCCR_HIGH_SPEED: u32 = 0x13;
+
+/// IO_ABORT: reset the I/O part of the card
+const IO_ABORT_RES: u8 = 1 << 3;
+/// INT_ENABLE: master enable of the card interrupt
+const INT_ENABLE_MASTER: u8 = 1 << 0;
+/// BUS_INTERFACE: 4-bit bus
+const BUS_WIDTH_4: u8 = 0b10;
+/// CAPABILITY: low-speed card, LSC
+const CAPABILITY_LOW_SPEED: u8 = 1 << 6;
+/// CAPABILITY: 4-bit bus of a low-speed card, 4BLS
+const CAPABILITY_LOW_SPEED_4BIT: u8 = 1 << 7;
+/// HIGH_SPEED: the card supports high speed, SHS
+const HIGH_SPEED_SUPPORT: u8 = 1 << 0;
+/// HIGH_SPEED: run in high speed, EHS
+const HIGH_SPEED_ENABLE: u8 = 1 << 1;
+
+/// FBR of function `n` starts at `n * 0x100`
+const FBR_SIZE: u32 = 0x100;
+/// FBR: standard interface code
+const FBR_INTERFACE: u32 = 0x00;
+/// FBR and CCCR: block size, little endian
+const FBR_BLOCK_SIZE: u32 = 0x10;
+
+/// Clock of full-speed cards
+const FULL_SPEED_HZ: u32 = 25_000_000;
+/// Clock of high-speed cards
+const HIGH_SPEED_HZ: u32 = 50_000_000;
+/// Clock of low-speed cards
+const LOW_SPEED_HZ: u32 = 400_000;
+
+/// Highest I/O function number
+const MAX_FUNCTION: u8 = 7;
+
+/// SDIO card found by [`Usdhc::init_sdio_card`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SdioCard {
+    /// relative card address, given by the card during identification
+    pub rca: u16,
+    /// supported voltages
+    pub ocr: u32,
+    /// I/O functions besides function 0, numbered from 1
+    pub functions: u8,
+    /// the card is a combo card, with an SD memory too
+    pub memory_present: bool,
+    /// block size of each function, for block transfers
+    block_sizes: [u16; MAX_FUNCTION as usize + 1],
+}
+
+/// CMD52 argument
+fn io_direct_arg(write: bool, function: u8, address: u32, value: u8) -> u32 {
+    let mut arg = (u32::from(function) << IO_FUNCTION_SHIFT)
+        | ((address & IO_ADDRESS_MASK) << IO_ADDRESS_SHIFT)
+        | u32::from(value);
+    if write {
+        arg |= IO_WRITE | IO_RAW;
+    }
+    arg
+}
+
+/// CMD53 argument, `count` bytes or blocks
+fn io_extended_arg(write: bool, function: u8, address: u32, increment: bool, block_mode: bool, count: usize) -> u32 {
+    let mut arg = (u32::from(function) << IO_FUNCTION_SHIFT)
+        | ((address & IO_ADDRESS_MASK) << IO_ADDRESS_SHIFT)
+        // 512 wraps to 0, which stands for it
+        | (count as u32 & 0x1FF);
+    if write {
+        arg |= IO_WRITE;
+    }
+    if increment {
+        arg |= IO_INCREMENT;
+    }
+    if block_mode {
+        arg |= IO_BLOCK_MODE;
+    }
+    arg
+}
+
+/// Check the R5 flags of a response, returning its data byte
+fn check_r5(response: u32) -> Result<u8> {
+    if response & R5_ERRORS != 0 {
+        Err(Error::Card(response))
+    } else {
+        Ok(response as u8)
+    }
+}
+
+impl Usdhc<'_> {
+    /// Identify the SDIO card and select it
+    ///
+    /// The card ends on the widest bus of the driver, at the highest clock up to
+    /// [`super::Config::frequency`] it supports. Its functions stay disabled until
+    /// [`Usdhc::sdio_enable_function`].
+    pub async fn init_sdio_card(&mut self) -> Result<SdioCard> {
+        self.start_identification()?;
+
+        // a card that was already running only listens to a reset of its I/O part, which it
+        // doesn't answer
+        let _ = self
+            .command(
+                Command::new(52, io_direct_arg(true, 0, CCCR_IO_ABORT, IO_ABORT_RES), Response::Short),
+                None,
+            )
+            .await;
+        self.command(Command::new(0, 0, Response::None), None).await?;
+
+        let [r4, ..] = self.command(Command::new(5, 0, Response::Ocr), None).await?;
+        if r4 & R4_OCR_MASK & OCR_VOLTAGE_WINDOW == 0 {
+            return Err(Error::UnsupportedCard);
+        }
+
+        let mut ready = 0;
+        for _ in 0..CMD5_RETRIES {
+            let [r4, ..] = self
+                .command(Command::new(5, OCR_VOLTAGE_WINDOW, Response::Ocr), None)
+                .await?;
+            if r4 & R4_READY != 0 {
+                ready = r4;
+                break;
+            }
+        }
+
+        if ready & R4_READY == 0 {
+            return Err(Error::UnsupportedCard);
+        }
+
+        let [r6, ..] = self.command(Command::new(3, 0, Response::Short), None).await?;
+        let rca = (r6 >> 16) as u16;
+
+        self.command(Command::new(7, u32::from(rca) << 16, Response::ShortBusy), None)
+            .await?;
+
+        let card = SdioCard {
+            rca,
+            ocr: ready & R4_OCR_MASK,
+            functions: ((ready >> R4_FUNCTIONS_SHIFT) & R4_FUNCTIONS_MASK) as u8,
+            memory_present: ready & R4_MEMORY_PRESENT != 0,
+            block_sizes: [0; MAX_FUNCTION as usize + 1],
+        };
+        self.sdio = Some(card);
+
+        let capability = self.sdio_read_byte(0, CCCR_CAPABILITY).await?;
+        let low_speed = capability & CAPABILITY_LOW_SPEED != 0;
+
+        // full-speed cards all have the 4-bit bus, low-speed ones tell
+        if self.bus_width != BusWidth::One && (!low_speed || capability & CAPABILITY_LOW_SPEED_4BIT != 0) {
+            let interface = self.sdio_read_byte(0, CCCR_BUS_INTERFACE).await?;
+            self.sdio_write_byte(0, CCCR_BUS_INTERFACE, (interface & !0b11) | BUS_WIDTH_4)
+                .await?;
+            self.set_bus_width(BusWidth::Four);
+        }
+
+        let mut hz = if low_speed {
+            LOW_SPEED_HZ
+        } else {
+            self.config.frequency.min(FULL_SPEED_HZ)
+        };
+        if !low_speed && self.config.frequency > FULL_SPEED_HZ {
+            let high_speed = self.sdio_read_byte(0, CCCR_HIGH_SPEED).await?;
+            if high_speed & HIGH_SPEED_SUPPORT != 0 {
+                self.sdio_write_byte(0, CCCR_HIGH_SPEED, high_speed | HIGH_SPEED_ENABLE)
+                    .await?;
+                hz = self.config.frequency.min(HIGH_SPEED_HZ);
+            }
+        }
+        self.set_clock(hz)?;
+        self.protocol = Some(Protocol::Sdio);
+
+        Ok(card)
+    }
+
+    fn sdio_card(&self) -> Result<&SdioCard> {
+        self.sdio.as_ref().ok_or(Error::NoCard)
+    }
+
+    /// Read the register at `address` of `function` with CMD52
+    pub async fn sdio_read_byte(&mut self, function: u8, address: u32) -> Result<u8> {
+        if function > MAX_FUNCTION {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let [r5, ..] = self
+            .command(
+                Command::new(52, io_direct_arg(false, function, address, 0), Response::Short),
+                None,
+            )
+            .await?;
+        check_r5(r5)
+    }
+
+    /// Write `value` to the register at `address` of `function` with CMD52, returning the value
+    /// read back
+    pub async fn sdio_write_byte(&mut self, function: u8, address: u32, value: u8) -> Result<u8> {
+        if function > MAX_FUNCTION {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let [r5, ..] = self
+            .command(
+                Command::new(52, io_direct_arg(true, function, address, value), Response::Short),
+                None,
+            )
+            .await?;
+        check_r5(r5)
+    }
+
+    /// Enable `function` and wait for it to be ready
+    pub async fn sdio_enable_function(&mut self, function: u8) -> Result<()> {
+        if function == 0 || function > self.sdio_card()?.functions {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let enabled = self.sdio_read_byte(0, CCCR_IO_ENABLE).await?;
+        self.sdio_write_byte(0, CCCR_IO_ENABLE, enabled | (1 << function))
+            .await?;
+
+        // the card tells once the function is done initializing, after at most a second or so
+        loop {
+            if self.sdio_read_byte(0, CCCR_IO_READY).await? & (1 << function) != 0 {
+                return Ok(());
+            }
+            embassy_futures::yield_now().await;
+        }
+    }
+
+    /// Standard interface code of `function`, such as 0x1 for UART or 0x7 for WLAN, 0xF when
+    /// the function defines its own interface
+    pub async fn sdio_function_interface(&mut self, function: u8) -> Result<u8> {
+        if function == 0 || function > self.sdio_card()?.functions {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let interface = self
+            .sdio_read_byte(0, u32::from(function) * FBR_SIZE + FBR_INTERFACE)
+            .await?;
+        Ok(interface & 0xF)
+    }
+
+    /// Set the block size of `function` for block transfers, 1 to 2048 bytes
+    pub async fn sdio_set_block_size(&mut self, function: u8, size: u16) -> Result<()> {
+        if function > self.sdio_card()?.functions || size == 0 || size > 2048 {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let [low, high] = size.to_le_bytes();
+        let base = u32::from(function) * FBR_SIZE + FBR_BLOCK_SIZE;
+        self.sdio_write_byte(0, base, low).await?;
+        self.sdio_write_byte(0, base + 1, high).await?;
+
+        if let Some(card) = self.sdio.as_mut()
+            && let Some(block_size) = card.block_sizes.get_mut(usize::from(function))
+        {
+            *block_size = size;
+        }
+
+        Ok(())
+    }
+
+    /// Move `len` bytes at `buffer` with CMD53: whole blocks when the function has a block size
+    /// and `len` is a multiple of it, a byte transfer of up to 512 bytes otherwise
+    async fn io_extended(
+        &mut self,
+        direction: Direction,
+        function: u8,
+        address: u32,
+        increment: bool,
+        buffer: *mut u8,
+        len: usize,
+    ) -> Result<()> {
+        let card = self.sdio_card()?;
+        if function > card.functions || len == 0 {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let block_size = card
+            .block_sizes
+            .get(usize::from(function))
+            .map_or(0, |&size| usize::from(size));
+
+        let (block_mode, block_size, count) = if block_size != 0 && len % block_size == 0 {
+            (true, block_size, len / block_size)
+        } else {
+            (false, len, 1)
+        };
+
+        if count > IO_MAX_COUNT + 1 || (!block_mode && len > 512) {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let write = direction == Direction::Write;
+        let arg = io_extended_arg(
+            write,
+            function,
+            address,
+            increment,
+            block_mode,
+            if block_mode { count } else { len },
+        );
+        let transfer = Transfer {
+            direction,
+            buffer,
+            block_size,
+            blocks: count,
+            auto_stop: false,
+        };
+
+        let [r5, ..] = self
+            .command(Command::new(53, arg, Response::Short), Some(transfer))
+            .await?;
+        check_r5(r5).map(|_| ())
+    }
+
+    /// Read `buf` from `address` of `function` with CMD53, from consecutive registers with
+    /// `increment`, from a FIFO register otherwise
+    ///
+    /// The buffer must be word-aligned, it is filled by the ADMA engine.
+    pub async fn sdio_read(&mut self, function: u8, address: u32, increment: bool, buf: &mut [u8]) -> Result<()> {
+        self.io_extended(
+            Direction::Read,
+            function,
+            address,
+            increment,
+            buf.as_mut_ptr(),
+            buf.len(),
+        )
+        .await
+    }
+
+    /// Write `buf` to `address` of `function` with CMD53, to consecutive registers with
+    /// `increment`, to a FIFO register otherwise
+    ///
+    /// The buffer must be word-aligned, it is read by the ADMA engine.
+    pub async fn sdio_write(&mut self, function: u8, address: u32, increment: bool, buf: &[u8]) -> Result<()> {
+        self.io_extended(
+            Direction::Write,
+            function,
+            address,
+            increment,
+            buf.as_ptr().cast_mut(),
+            buf.len(),
+        )
+        .await
+    }
+
+    /// Let `function` interrupt the host
+    pub async fn sdio_enable_interrupt(&mut self, function: u8) -> Result<()> {
+        if function == 0 || function > self.sdio_card()?.functions {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        let enabled = self.sdio_read_byte(0, CCCR_INT_ENABLE).await?;
+        self.sdio_write_byte(0, CCCR_INT_ENABLE, enabled | INT_ENABLE_MASTER | (1 << function))
+            .await
+            .map(|_| ())
+    }
+
+    /// Wait for the card to interrupt the host
+    ///
+    /// The interrupt holds until the function is serviced, which the caller does before waiting
+    /// again.
+    pub async fn sdio_wait_for_interrupt(&mut self) -> Result<()> {
+        self.sdio_card()?;
+        let regs = self.info.regs;
+
+        // SAFETY: unsafe due to .bits usage
+        regs.int_status_en()
+            .modify(|r, w| unsafe { w.bits(r.bits() | INT_CINT) });
+        self.wait_status(INT_CINT).await;
+        regs.int_status_en()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !INT_CINT) });
+        regs.int_status().write(|w| unsafe { w.bits(INT_CINT) });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_direct_arguments() {
+        // read BUS_INTERFACE of function 0
+        assert_eq!(io_direct_arg(false, 0, 0x07, 0), 0x07 << 9);
+        // write 0x02 to IO_ENABLE, with read after write
+        assert_eq!(io_direct_arg(true, 0, 0x02, 0x02), 0x8800_0402);
+    }
+
+    #[test]
+    fn io_extended_arguments() {
+        // 512 bytes from function 1 wrap the count to 0
+        assert_eq!(io_extended_arg(false, 1, 0, true, false, 512), 0x1400_0000);
+        // 4 blocks to function 2 at 0x8000, fixed address
+        assert_eq!(io_extended_arg(true, 2, 0x8000, false, true, 4), 0xA900_0004);
+    }
+
+    #[test]
+    fn r5_errors() {
+        assert_eq!(check_r5(0x10AB), Ok(0xAB));
+        assert_eq!(check_r5(0x8000), Err(Error::Card(0x8000)));
+    }
+}
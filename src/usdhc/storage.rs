@@ -0,0 +1,128 @@
+//! Block device traits of the filesystem crates
+
+#[cfg(feature = "embedded-sdmmc")]
+use core::cell::RefCell;
+
+#[cfg(feature = "block-device-driver")]
+use super::BLOCK_SIZE;
+#[cfg(any(feature = "embedded-sdmmc", feature = "block-device-driver"))]
+use super::{Error, MAX_BLOCKS, Usdhc};
+
+/// SD card as an [`embedded_sdmmc::BlockDevice`], for the blocking FAT filesystem of
+/// `embedded-sdmmc`
+///
+/// The trait is blocking: every access runs the async driver to completion on the spot.
+#[cfg(feature = "embedded-sdmmc")]
+pub struct SdBlockDevice<'d> {
+    usdhc: RefCell<Usdhc<'d>>,
+}
+
+#[cfg(feature = "embedded-sdmmc")]
+impl<'d> SdBlockDevice<'d> {
+    /// Wrap the driver of an initialized card
+    pub fn new(usdhc: Usdhc<'d>) -> Self {
+        Self {
+            usdhc: RefCell::new(usdhc),
+        }
+    }
+
+    /// Give the driver back
+    pub fn into_inner(self) -> Usdhc<'d> {
+        self.usdhc.into_inner()
+    }
+}
+
+#[cfg(feature = "embedded-sdmmc")]
+impl embedded_sdmmc::BlockDevice for SdBlockDevice<'_> {
+    type Error = Error;
+
+    fn read(
+        &self,
+        blocks: &mut [embedded_sdmmc::Block],
+        start_block_idx: embedded_sdmmc::BlockIdx,
+    ) -> Result<(), Self::Error> {
+        let mut usdhc = self.usdhc.borrow_mut();
+        let mut address = start_block_idx.0;
+
+        // the ADMA engine only reaches word-aligned buffers, others go through a bounce block
+        if blocks.as_ptr() as usize % 4 == 0 {
+            for chunk in blocks.chunks_mut(MAX_BLOCKS) {
+                embassy_futures::block_on(usdhc.read(address, chunk.as_mut_ptr().cast(), chunk.len()))?;
+                address += chunk.len() as u32;
+            }
+        } else {
+            let mut bounce = super::DataBlock::new();
+            for block in blocks {
+                embassy_futures::block_on(usdhc.read_block(address, &mut bounce))?;
+                block.contents = bounce.0;
+                address += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        blocks: &[embedded_sdmmc::Block],
+        start_block_idx: embedded_sdmmc::BlockIdx,
+    ) -> Result<(), Self::Error> {
+        let mut usdhc = self.usdhc.borrow_mut();
+        let mut address = start_block_idx.0;
+
+        if blocks.as_ptr() as usize % 4 == 0 {
+            for chunk in blocks.chunks(MAX_BLOCKS) {
+                embassy_futures::block_on(usdhc.write(address, chunk.as_ptr().cast(), chunk.len()))?;
+                address += chunk.len() as u32;
+            }
+        } else {
+            for block in blocks {
+                embassy_futures::block_on(usdhc.write_block(address, &super::DataBlock(block.contents)))?;
+                address += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn num_blocks(&self) -> Result<embedded_sdmmc::BlockCount, Self::Error> {
+        let blocks = self.usdhc.borrow().card()?.block_count();
+        Ok(embedded_sdmmc::BlockCount(u32::try_from(blocks).unwrap_or(u32::MAX)))
+    }
+}
+
+#[cfg(feature = "block-device-driver")]
+impl block_device_driver::BlockDevice<BLOCK_SIZE> for Usdhc<'_> {
+    type Error = Error;
+    type Align = aligned::A4;
+
+    async fn read(
+        &mut self,
+        block_address: u32,
+        data: &mut [aligned::Aligned<Self::Align, [u8; BLOCK_SIZE]>],
+    ) -> Result<(), Self::Error> {
+        let mut address = block_address;
+        for chunk in data.chunks_mut(MAX_BLOCKS) {
+            Usdhc::read(self, address, chunk.as_mut_ptr().cast(), chunk.len()).await?;
+            address += chunk.len() as u32;
+        }
+        Ok(())
+    }
+
+    async fn write(
+        &mut self,
+        block_address: u32,
+        data: &[aligned::Aligned<Self::Align, [u8; BLOCK_SIZE]>],
+    ) -> Result<(), Self::Error> {
+        let mut address = block_address;
+        for chunk in data.chunks(MAX_BLOCKS) {
+            Usdhc::write(self, address, chunk.as_ptr().cast(), chunk.len()).await?;
+            address += chunk.len() as u32;
+        }
+        Ok(())
+    }
+
+    async fn size(&mut self) -> Result<u64, Self::Error> {
+        Ok(self.card()?.block_count() * BLOCK_SIZE as u64)
+    }
+}
@@ -0,0 +1,205 @@
+//! Micro-Tick Timer (UTICK)
+//!
+//! The UTICK is a 31-bit down-counter clocked from the 1 MHz low-power oscillator, which keeps
+//! running in deep sleep. It gives microsecond delays which don't busy-wait and can wake the chip
+//! from deep sleep, without the millisecond granularity of the RTC.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Poll;
+
+use embassy_hal_internal::{Peri, PeripheralType};
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::clocks::{SleepDomain, StartEnable, SysconPeripheral, WakeupGuard, enable_and_reset, enable_wakeup};
+use crate::interrupt;
+use crate::interrupt::typelevel::{Binding, Interrupt};
+
+/// Longest delay of the 31-bit counter, in microseconds
+pub const MAX_DELAY_US: u32 = 1 << 31;
+
+static WAKER: AtomicWaker = AtomicWaker::new();
+static EXPIRED: AtomicBool = AtomicBool::new(true);
+
+/// UTICK error
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Error {
+    /// Delay is zero or longer than [`MAX_DELAY_US`]
+    InvalidDelay,
+}
+
+/// UTICK interrupt handler
+pub struct InterruptHandler<T: Instance> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandler<T> {
+    unsafe fn on_interrupt() {
+        let regs = T::info().regs;
+
+        if regs.stat().read().intr().bit_is_set() {
+            // The flag is cleared by writing 1
+            regs.stat().write(|w| w.intr().set_bit());
+            EXPIRED.store(true, Ordering::Release);
+            WAKER.wake();
+        }
+    }
+}
+
+/// UTICK driver
+pub struct Utick<'d> {
+    info: Info,
+    _wakeup: WakeupGuard,
+    _lifetime: PhantomData<&'d ()>,
+}
+
+impl<'d> Utick<'d> {
+    /// Create UTICK driver.
+    ///
+    /// Keeps the low-power oscillator running in deep sleep, and lets the UTICK interrupt wake the
+    /// chip from it.
+    pub fn new<T: Instance>(_utick: Peri<'d, T>, _irq: impl Binding<T::Interrupt, InterruptHandler<T>> + 'd) -> Self {
+        T::init();
+
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        Self {
+            info: T::info(),
+            _wakeup: enable_wakeup(&[SleepDomain::Lposc], T::START_ENABLE),
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Start a one-shot delay of `delay_us`, completed by [`Self::wait`]. A running delay is
+    /// restarted.
+    ///
+    /// Returns [`Error::InvalidDelay`] if the delay is zero or longer than [`MAX_DELAY_US`].
+    pub fn start(&mut self, delay_us: u32) -> Result<(), Error> {
+        if delay_us == 0 || delay_us > MAX_DELAY_US {
+            return Err(Error::InvalidDelay);
+        }
+
+        let regs = self.info.regs;
+
+        // Clear a stale interrupt flag, then start counting: the delay lasts DELAYVAL + 1 ticks
+        regs.stat().write(|w| w.intr().set_bit());
+        EXPIRED.store(false, Ordering::Release);
+        // SAFETY: delay_us checked against the 31-bit counter above
+        regs.ctrl()
+            .write(|w| unsafe { w.delayval().bits(delay_us - 1).repeat().clear_bit() });
+
+        Ok(())
+    }
+
+    /// Stop the running delay.
+    pub fn stop(&mut self) {
+        // SAFETY: a zero delay stops the counter
+        self.info.regs.ctrl().write(|w| unsafe { w.delayval().bits(0) });
+        EXPIRED.store(true, Ordering::Release);
+    }
+
+    /// Whether the delay started by [`Self::start`] has expired, or was stopped.
+    ///
+    /// The active flag of the timer is synchronized to the low-power oscillator, so it may still
+    /// read as stopped right after a start. This follows the interrupt instead.
+    pub fn is_expired(&self) -> bool {
+        EXPIRED.load(Ordering::Acquire) || self.info.regs.stat().read().intr().bit_is_set()
+    }
+
+    /// Wait asynchronously for the delay started by [`Self::start`] to complete.
+    ///
+    /// Completes immediately if no delay is running.
+    pub async fn wait(&mut self) {
+        poll_fn(|cx| {
+            WAKER.register(cx.waker());
+
+            if self.is_expired() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+    }
+
+    /// Wait asynchronously for `delay_us`. The chip may enter deep sleep meanwhile.
+    ///
+    /// Returns [`Error::InvalidDelay`] if the delay is zero or longer than [`MAX_DELAY_US`].
+    pub async fn delay_us(&mut self, delay_us: u32) -> Result<(), Error> {
+        self.start(delay_us)?;
+        self.wait().await;
+        Ok(())
+    }
+
+    /// Busy-wait for `delay_us`.
+    ///
+    /// Returns [`Error::InvalidDelay`] if the delay is zero or longer than [`MAX_DELAY_US`].
+    pub fn blocking_delay_us(&mut self, delay_us: u32) -> Result<(), Error> {
+        self.start(delay_us)?;
+        while !self.is_expired() {}
+        Ok(())
+    }
+}
+
+impl Drop for Utick<'_> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+struct Info {
+    regs: crate::pac::Utick0,
+}
+
+trait SealedInstance {
+    /// Returns a new Info, containing the register block.
+    fn info() -> Info;
+
+    /// Initializes power and clocks to peripheral.
+    fn init();
+
+    /// Start enable bit, to wake the chip from deep sleep
+    const START_ENABLE: StartEnable;
+}
+
+/// UTICK instance trait.
+#[allow(private_bounds)]
+pub trait Instance: SealedInstance + PeripheralType + SysconPeripheral + 'static + Send {
+    /// Interrupt for this UTICK instance.
+    type Interrupt: interrupt::typelevel::Interrupt;
+}
+
+impl Instance for crate::peripherals::UTICK0 {
+    type Interrupt = crate::interrupt::typelevel::UTICK0;
+}
+
+impl SealedInstance for crate::peripherals::UTICK0 {
+    const START_ENABLE: StartEnable = StartEnable::Starten0(8);
+
+    fn info() -> Info {
+        // SAFETY: safe from single executor
+        Info {
+            regs: unsafe { crate::pac::Utick0::steal() },
+        }
+    }
+
+    fn init() {
+        // SAFETY: unsafe needed to take pointers to Sysctl0 and Clkctl0, only to power and clock
+        // the UTICK from the low-power oscillator
+        let sysctl0 = unsafe { crate::pac::Sysctl0::steal() };
+        let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
+
+        // Enable low power oscillator, a no-op if it's already enabled
+        sysctl0.pdruncfg0_clr().write(|w| w.lposc_pd().clr_pdruncfg0());
+        // Wait for low-power oscillator to be ready (typically 64 us)
+        while clkctl0.lposcctl0().read().clkrdy().bit_is_clear() {}
+
+        clkctl0.utickfclksel().write(|w| w.sel().lposc());
+
+        enable_and_reset::<crate::peripherals::UTICK0>();
+    }
+}